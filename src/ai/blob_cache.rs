@@ -0,0 +1,165 @@
+//! Content-addressed cache for files shared between models.
+//!
+//! Catalogs often point several models at the same tokenizer or config files. Those downloads
+//! are stored once under `<models dir>/.blobs/sha256/<digest>` and copied to wherever each
+//! model expects them, so editing the file in one model folder leaves the others alone.
+//! `urls.json` remembers the digest and ETag behind each URL; entries that don't list a digest
+//! reuse the cached file only after the server confirms the ETag still matches, since a URL
+//! like Hugging Face's `resolve/main` can change what it serves.
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// What a URL served when it was last downloaded.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UrlEntry {
+    pub sha256: String,
+    /// Sent back as `If-None-Match`; without one the cached file can't be revalidated.
+    #[serde(default)]
+    pub etag: Option<String>,
+}
+
+pub struct BlobCache {
+    root: PathBuf,
+}
+
+impl BlobCache {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    fn blob_path(&self, sha256: &str) -> PathBuf {
+        self.root.join("sha256").join(sha256.to_lowercase())
+    }
+
+    fn index_path(&self) -> PathBuf {
+        self.root.join("urls.json")
+    }
+
+    /// The URL index; one written before ETags were kept doesn't parse and is started over.
+    fn read_index(&self) -> HashMap<String, UrlEntry> {
+        std::fs::read_to_string(self.index_path())
+            .ok()
+            .and_then(|data| serde_json::from_str(&data).ok())
+            .unwrap_or_default()
+    }
+
+    fn record_url(&self, url: &str, entry: UrlEntry) -> Result<()> {
+        let mut index = self.read_index();
+        if index.get(url) == Some(&entry) {
+            return Ok(());
+        }
+        index.insert(url.to_string(), entry);
+        let path = self.index_path();
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_string_pretty(&index)?)?;
+        std::fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+
+    /// The cached file with digest `sha256`.
+    pub fn lookup(&self, sha256: &str) -> Option<PathBuf> {
+        let path = self.blob_path(sha256);
+        path.is_file().then_some(path)
+    }
+
+    /// The file last downloaded from `url` and its ETag, if the server sent one. Only reuse it
+    /// once the server answers `304 Not Modified` to that ETag.
+    pub fn lookup_url(&self, url: &str) -> Option<(PathBuf, String)> {
+        let entry = self.read_index().remove(url)?;
+        let etag = entry.etag?;
+        self.lookup(&entry.sha256).map(|path| (path, etag))
+    }
+
+    /// Fresh path inside the cache to download into before [`insert`](Self::insert), so the
+    /// finished file can be moved in without copying.
+    pub fn staging_path(&self) -> Result<PathBuf> {
+        let dir = self.root.join("tmp");
+        std::fs::create_dir_all(&dir)?;
+        Ok(dir.join(format!("{}.part", uuid::Uuid::new_v4())))
+    }
+
+    /// Move a downloaded file into the cache and return the cached path. The file is removed
+    /// instead if its digest doesn't match `expected_sha256` or the cache already has it.
+    /// `etag` is what the server sent with it.
+    pub fn insert(&self, file: &Path, url: &str, expected_sha256: Option<&str>, etag: Option<&str>) -> Result<PathBuf> {
+        let digest = super::integrity::compute_sha256(file)?;
+        if let Some(expected) = expected_sha256 {
+            if !digest.eq_ignore_ascii_case(expected) {
+                let _ = std::fs::remove_file(file);
+                return Err(anyhow!("SHA256 mismatch for {}: expected {}, got {}", url, expected, digest));
+            }
+        }
+        let blob = self.blob_path(&digest);
+        if blob.is_file() {
+            std::fs::remove_file(file)?;
+        } else {
+            std::fs::create_dir_all(blob.parent().unwrap_or(&self.root))?;
+            std::fs::rename(file, &blob)?;
+        }
+        if let Err(e) = self.record_url(url, UrlEntry { sha256: digest, etag: etag.map(str::to_string) }) {
+            tracing::warn!("Failed to update the download cache index: {}", e);
+        }
+        Ok(blob)
+    }
+
+    /// Place a copy of `blob` at `dest`, replacing what is there. Not a hard link: the models'
+    /// copies must not change together.
+    pub fn copy_into(blob: &Path, dest: &Path) -> Result<()> {
+        if let Some(parent) = dest.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let name = dest.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+        let tmp = dest.with_file_name(format!(".{name}.copying-{}", std::process::id()));
+        std::fs::copy(blob, &tmp).with_context(|| format!("Failed to copy {} to {}", blob.display(), dest.display()))?;
+        std::fs::rename(&tmp, dest)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shared_file_is_stored_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = BlobCache::new(dir.path().join(".blobs"));
+        let url = "https://example.com/a/tokenizer.json";
+        assert!(cache.lookup_url(url).is_none());
+
+        let staged = cache.staging_path().unwrap();
+        std::fs::write(&staged, b"{\"vocab\":{}}").unwrap();
+        let blob = cache.insert(&staged, url, None, Some("\"v1\"")).unwrap();
+        assert!(!staged.exists());
+        assert_eq!(cache.lookup_url(url), Some((blob.clone(), "\"v1\"".to_string())));
+        let digest = blob.file_name().unwrap().to_str().unwrap().to_uppercase();
+        assert_eq!(cache.lookup(&digest), Some(blob.clone()));
+
+        // The same bytes from another URL don't add a second blob; without an ETag that URL
+        // can't be revalidated, so it isn't reused by URL
+        let other = "https://example.com/b/tokenizer.json";
+        let staged = cache.staging_path().unwrap();
+        std::fs::write(&staged, b"{\"vocab\":{}}").unwrap();
+        assert_eq!(cache.insert(&staged, other, Some(&digest), None).unwrap(), blob);
+        assert_eq!(std::fs::read_dir(dir.path().join(".blobs/sha256")).unwrap().count(), 1);
+        assert!(cache.lookup_url(other).is_none());
+
+        // Each model gets its own copy
+        let dests: Vec<PathBuf> = ["model-a", "model-b"].iter().map(|m| dir.path().join(m).join("tokenizer.json")).collect();
+        for dest in &dests {
+            BlobCache::copy_into(&blob, dest).unwrap();
+            assert_eq!(std::fs::read(dest).unwrap(), b"{\"vocab\":{}}");
+        }
+        std::fs::write(&dests[0], b"edited").unwrap();
+        assert_eq!(std::fs::read(&dests[1]).unwrap(), b"{\"vocab\":{}}");
+        assert_eq!(std::fs::read(&blob).unwrap(), b"{\"vocab\":{}}");
+
+        let staged = cache.staging_path().unwrap();
+        std::fs::write(&staged, b"tampered").unwrap();
+        assert!(cache.insert(&staged, url, Some(&digest), None).is_err());
+        assert!(!staged.exists());
+    }
+}
@@ -0,0 +1,127 @@
+//! Remote model catalog signed with ed25519.
+//!
+//! A publisher serves the catalog JSON at some URL and a detached signature of its exact
+//! bytes at the same URL plus `.sig` (base64 or hex). The catalog is only used if the
+//! signature verifies against the public key configured in settings. The last good copy is
+//! cached together with its signature and checked again whenever it is read back.
+
+use anyhow::{anyhow, Context, Result};
+use base64::Engine;
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+const CACHE_FILE: &str = "remote_catalog.json";
+
+/// A catalog as published, with the signature that vouches for it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SignedCatalog {
+    pub url: String,
+    pub fetched_at: chrono::DateTime<chrono::Utc>,
+    /// Catalog JSON exactly as signed.
+    pub catalog: String,
+    pub signature: String,
+}
+
+/// Base64 or hex, whichever `text` is.
+fn decode(text: &str) -> Result<Vec<u8>> {
+    let text = text.trim();
+    if text.len().is_multiple_of(2) && text.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Ok(hex::decode(text)?);
+    }
+    base64::engine::general_purpose::STANDARD.decode(text).map_err(|_| anyhow!("not base64 or hex"))
+}
+
+pub fn parse_public_key(text: &str) -> Result<VerifyingKey> {
+    let bytes: [u8; 32] = decode(text)
+        .context("Invalid catalog public key")?
+        .try_into()
+        .map_err(|_| anyhow!("A catalog public key is 32 bytes"))?;
+    VerifyingKey::from_bytes(&bytes).context("Invalid catalog public key")
+}
+
+/// Check that `signature` is the publisher's signature over `catalog`.
+pub fn verify(catalog: &[u8], signature: &str, public_key: &str) -> Result<()> {
+    let key = parse_public_key(public_key)?;
+    let signature: [u8; 64] = decode(signature)
+        .context("Invalid catalog signature")?
+        .try_into()
+        .map_err(|_| anyhow!("A catalog signature is 64 bytes"))?;
+    key.verify(catalog, &Signature::from_bytes(&signature))
+        .map_err(|_| anyhow!("The catalog signature doesn't match; the catalog was not accepted"))
+}
+
+/// Download the catalog and its signature and verify them.
+pub async fn fetch(client: &reqwest::Client, url: &str, public_key: &str) -> Result<SignedCatalog> {
+    // Reject a bad key before going to the network
+    parse_public_key(public_key)?;
+    let catalog = client.get(url).send().await?.error_for_status()?.text().await?;
+    let signature_url = format!("{url}.sig");
+    let signature = client
+        .get(&signature_url)
+        .send()
+        .await?
+        .error_for_status()
+        .with_context(|| format!("No signature at {signature_url}"))?
+        .text()
+        .await?;
+    verify(catalog.as_bytes(), &signature, public_key)?;
+    Ok(SignedCatalog { url: url.to_string(), fetched_at: chrono::Utc::now(), catalog, signature: signature.trim().to_string() })
+}
+
+pub fn save_cache(dir: &Path, catalog: &SignedCatalog) -> Result<()> {
+    std::fs::create_dir_all(dir)?;
+    let path = dir.join(CACHE_FILE);
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, serde_json::to_string(catalog)?)?;
+    std::fs::rename(&tmp, &path)?;
+    Ok(())
+}
+
+/// The cached catalog for `url`, if there is one and it still verifies with `public_key`.
+pub fn load_cache(dir: &Path, url: &str, public_key: &str) -> Option<SignedCatalog> {
+    let data = std::fs::read_to_string(dir.join(CACHE_FILE)).ok()?;
+    let cached: SignedCatalog = serde_json::from_str(&data).ok()?;
+    if cached.url != url {
+        return None;
+    }
+    match verify(cached.catalog.as_bytes(), &cached.signature, public_key) {
+        Ok(()) => Some(cached),
+        Err(e) => {
+            tracing::warn!("Ignoring cached model catalog: {}", e);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signer, SigningKey};
+
+    #[test]
+    fn test_only_signed_catalogs_are_accepted() {
+        let publisher = SigningKey::from_bytes(&[7u8; 32]);
+        let public_key = base64::engine::general_purpose::STANDARD.encode(publisher.verifying_key().as_bytes());
+        let catalog = r#"[{"name":"tiny","url":"https://example.com/tiny.onnx"}]"#;
+        let signature = hex::encode(publisher.sign(catalog.as_bytes()).to_bytes());
+
+        assert!(verify(catalog.as_bytes(), &signature, &public_key).is_ok());
+        let tampered = catalog.replace("example.com", "evil.example");
+        assert!(verify(tampered.as_bytes(), &signature, &public_key).is_err());
+        let other_key = hex::encode(SigningKey::from_bytes(&[8u8; 32]).verifying_key().as_bytes());
+        assert!(verify(catalog.as_bytes(), &signature, &other_key).is_err());
+        assert!(parse_public_key("short").is_err());
+
+        let dir = tempfile::tempdir().unwrap();
+        let url = "https://example.com/catalog.json";
+        let signed = SignedCatalog { url: url.into(), fetched_at: chrono::Utc::now(), catalog: catalog.into(), signature };
+        save_cache(dir.path(), &signed).unwrap();
+        assert_eq!(load_cache(dir.path(), url, &public_key), Some(signed.clone()));
+        assert_eq!(load_cache(dir.path(), "https://example.com/other.json", &public_key), None);
+
+        // A cached copy edited on disk is rejected too
+        save_cache(dir.path(), &SignedCatalog { catalog: tampered, ..signed }).unwrap();
+        assert_eq!(load_cache(dir.path(), url, &public_key), None);
+    }
+}
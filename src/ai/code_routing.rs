@@ -0,0 +1,177 @@
+//! Spotting prompts that are mostly code and sending them to a code model.
+//!
+//! Detection is a cheap line-by-line guess: fenced lines are code, and so are lines that end or
+//! start like code does. When most of the prompt is code the app asks the engine for a loaded
+//! code model ([`GenerationOverrides::prefer_code_model`](super::GenerationOverrides)); without
+//! one it adds a code-focused system prompt instead.
+
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_SYSTEM_PROMPT: &str = "You are an expert programmer. Answer with correct, idiomatic code in fenced blocks \
+tagged with their language, and keep explanations short and to the point.";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CodeRoutingSettings {
+    pub enabled: bool,
+    /// Use a loaded code model when there is one; otherwise only the system prompt is added.
+    pub route_to_code_model: bool,
+    /// Added to code prompts that stay on a general model.
+    pub system_prompt: String,
+    /// Share of the prompt's lines that must be code, from 0 to 1.
+    pub min_code_share: f32,
+}
+
+impl Default for CodeRoutingSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            route_to_code_model: true,
+            system_prompt: DEFAULT_SYSTEM_PROMPT.to_string(),
+            min_code_share: 0.5,
+        }
+    }
+}
+
+impl CodeRoutingSettings {
+    /// What was detected in `prompt`, if routing is on and enough of it is code.
+    pub fn route(&self, prompt: &str) -> Option<CodeInfo> {
+        let info = detect(prompt);
+        (self.enabled && info.lines > 0 && info.share >= self.min_code_share).then_some(info)
+    }
+
+    /// The system prompt for a code request, naming the language when it is known.
+    pub fn system_prompt_for(&self, info: &CodeInfo) -> String {
+        let prompt = self.system_prompt.trim();
+        match info.language {
+            Some(language) => format!("{prompt}\n\nThe user's code is {language}."),
+            None => prompt.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CodeInfo {
+    pub language: Option<&'static str>,
+    /// Share of non-empty lines that look like code.
+    pub share: f32,
+    /// Non-empty lines in the prompt.
+    pub lines: usize,
+}
+
+/// Markers for each language, checked against the lowercased code lines.
+const LANGUAGE_MARKERS: [(&str, &[&str]); 9] = [
+    ("Rust", &["fn ", "let mut ", "impl ", "pub fn", "#[derive", "&self", "println!", "-> result<"]),
+    ("Python", &["def ", "elif ", "self.", "__init__", "print(", "import ", "from "]),
+    ("TypeScript", &["interface ", ": string", ": number", "export type "]),
+    ("JavaScript", &["function ", "const ", "=>", "console.log", "require("]),
+    ("Go", &["func ", ":=", "package ", "fmt."]),
+    ("C++", &["#include", "std::", "int main", "nullptr", "template<"]),
+    ("Java", &["public class", "system.out", "public static void", "private final"]),
+    ("SQL", &["select ", "insert into", "create table", "where ", "join "]),
+    ("Shell", &["#!/bin", "echo ", "sudo ", "export ", "grep ", "$("]),
+];
+
+/// Fence tags mapped onto [`LANGUAGE_MARKERS`] names.
+fn language_from_fence(tag: &str) -> Option<&'static str> {
+    Some(match tag.to_lowercase().as_str() {
+        "rust" | "rs" => "Rust",
+        "python" | "py" => "Python",
+        "typescript" | "ts" | "tsx" => "TypeScript",
+        "javascript" | "js" | "jsx" => "JavaScript",
+        "go" | "golang" => "Go",
+        "c" | "cpp" | "c++" | "h" | "hpp" => "C++",
+        "java" => "Java",
+        "sql" => "SQL",
+        "sh" | "bash" | "shell" | "zsh" => "Shell",
+        _ => return None,
+    })
+}
+
+/// Whether a line outside a fence reads like code rather than prose.
+fn looks_like_code(line: &str, after_code: bool) -> bool {
+    const STARTS: [&str; 14] = [
+        "//", "#include", "#!", "def ", "fn ", "pub ", "impl ", "import ", "return ", "class ", "func ", "let ", "const ", "}",
+    ];
+    let trimmed = line.trim();
+    trimmed.ends_with(';')
+        || trimmed.ends_with('{')
+        || trimmed.ends_with('}')
+        || STARTS.iter().any(|s| trimmed.starts_with(s))
+        || trimmed.contains("::")
+        || trimmed.contains("=>")
+        || trimmed.contains("();")
+        // A bare call such as `main()` or `print(total)`
+        || (trimmed.ends_with(')') && trimmed.split('(').next().is_some_and(|name| !name.is_empty() && !name.contains(' ')))
+        // Indented lines carry on the code above them
+        || (after_code && line.starts_with([' ', '\t']))
+}
+
+/// How much of `text` is code and which language it most likely is.
+pub fn detect(text: &str) -> CodeInfo {
+    let mut in_fence = false;
+    let mut fence_language = None;
+    let mut lines = 0;
+    let mut code_lines = Vec::new();
+    let mut after_code = false;
+    for line in text.lines().filter(|l| !l.trim().is_empty()) {
+        lines += 1;
+        if let Some(tag) = line.trim().strip_prefix("```") {
+            in_fence = !in_fence;
+            if in_fence {
+                let tag = tag.split_whitespace().next().unwrap_or("");
+                fence_language = fence_language.or_else(|| language_from_fence(tag));
+            }
+            code_lines.push("");
+            continue;
+        }
+        after_code = in_fence || looks_like_code(line, after_code);
+        if after_code {
+            code_lines.push(line);
+        }
+    }
+
+    let language = fence_language.or_else(|| {
+        let code = code_lines.join("\n").to_lowercase();
+        LANGUAGE_MARKERS
+            .iter()
+            .map(|(language, markers)| (*language, markers.iter().filter(|m| code.contains(*m)).count()))
+            .filter(|(_, hits)| *hits > 0)
+            // The first listed language wins a tie
+            .fold(None, |best: Option<(&'static str, usize)>, (language, hits)| match best {
+                Some((_, best_hits)) if best_hits >= hits => best,
+                _ => Some((language, hits)),
+            })
+            .map(|(language, _)| language)
+    });
+    let share = if lines == 0 { 0.0 } else { code_lines.len() as f32 / lines as f32 };
+    CodeInfo { language, share, lines }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_code_prompts_are_detected_and_routed() {
+        let prose = detect("What's a good name for a cat?\nSomething short, please.");
+        assert_eq!((prose.share, prose.language), (0.0, None));
+
+        let fenced = detect("Why doesn't this compile?\n```rs\nlet x = vec![1];\nlet y = x;\nprintln!(\"{x:?}\");\n```");
+        assert_eq!(fenced.language, Some("Rust"));
+        assert!(fenced.share > 0.8);
+
+        let pasted = detect("def total(items):\n    return sum(i.price for i in items)\nprint(total([]))");
+        assert_eq!(pasted.language, Some("Python"));
+        assert_eq!(pasted.share, 1.0);
+
+        let snippet = "Fix this:\nconst add = (a, b) => a + b;\nconsole.log(add(1, 2));";
+        let settings = CodeRoutingSettings::default();
+        assert!(settings.route(snippet).is_none(), "off by default");
+        let settings = CodeRoutingSettings { enabled: true, ..settings };
+        let info = settings.route(snippet).unwrap();
+        assert!(settings.system_prompt_for(&info).ends_with("The user's code is JavaScript."));
+        assert!(settings.route("Explain closures in one paragraph.").is_none());
+        assert!(settings.route("").is_none());
+    }
+}
@@ -0,0 +1,795 @@
+//! Constrained decoding: the output must match a regex, be JSON, or follow a JSON schema.
+//!
+//! A [`Constraint`] answers one question for the sampler: can the text generated so far,
+//! extended by a candidate token, still become a complete match? Tokens for which it can't
+//! are masked out before sampling, so a finished generation always parses.
+//!
+//! Regexes and schemas are compiled to a character-level NFA. Schemas cover `type`,
+//! `properties` / `required` (properties appear in the order the schema lists them, no
+//! extra keys), `items`, `enum` and `const`; sub-schemas without a type accept any JSON
+//! value nested at most [`ANY_VALUE_DEPTH`] levels. Plain JSON mode has no depth limit and
+//! uses a stack-based scanner instead.
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Arc;
+
+/// Nesting allowed inside untyped parts of a schema.
+pub const ANY_VALUE_DEPTH: usize = 3;
+/// Bounded repetitions (`{m,n}`) are unrolled; larger bounds are rejected.
+const MAX_REPEAT: u32 = 256;
+
+/// The output format a user asks for.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum OutputConstraint {
+    /// Any well-formed JSON value.
+    Json,
+    /// JSON matching the given schema (as JSON text).
+    JsonSchema(String),
+    /// The whole output matches the regex.
+    Regex(String),
+}
+
+impl OutputConstraint {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Json => "JSON",
+            Self::JsonSchema(_) => "JSON schema",
+            Self::Regex(_) => "Regex",
+        }
+    }
+
+    pub fn compile(&self) -> Result<Constraint> {
+        let nfa = match self {
+            Self::Json => return Ok(Constraint::AnyJson),
+            Self::JsonSchema(schema) => {
+                let schema: Value = serde_json::from_str(schema).context("The schema is not valid JSON")?;
+                Nfa::compile(&schema_node(&schema)?)
+            }
+            Self::Regex(pattern) => Nfa::compile(&RegexParser::new(pattern).parse()?),
+        };
+        Ok(Constraint::Pattern(Arc::new(nfa)))
+    }
+}
+
+/// A compiled [`OutputConstraint`].
+#[derive(Debug, Clone)]
+pub enum Constraint {
+    Pattern(Arc<Nfa>),
+    AnyJson,
+}
+
+impl Constraint {
+    /// State before any output.
+    pub fn start(&self) -> ConstraintState {
+        match self {
+            Self::Pattern(nfa) => ConstraintState::Pattern { nfa: nfa.clone(), states: nfa.closure(&[nfa.start]) },
+            Self::AnyJson => ConstraintState::Json(JsonScanner::default()),
+        }
+    }
+
+    /// Whether `text` is a complete match.
+    pub fn is_match(&self, text: &str) -> bool {
+        let mut state = self.start();
+        state.feed(text) && state.is_complete()
+    }
+}
+
+/// Progress of the output through a [`Constraint`]; cheap to clone for trying tokens.
+#[derive(Debug, Clone)]
+pub enum ConstraintState {
+    Pattern { nfa: Arc<Nfa>, states: Vec<usize> },
+    Json(JsonScanner),
+}
+
+impl ConstraintState {
+    /// Consume `text`. Returns false (leaving the state unusable) once it can no longer match.
+    pub fn feed(&mut self, text: &str) -> bool {
+        match self {
+            Self::Pattern { nfa, states } => {
+                for c in text.chars() {
+                    *states = nfa.step(states, c);
+                    if states.is_empty() {
+                        return false;
+                    }
+                }
+                true
+            }
+            Self::Json(scanner) => text.chars().all(|c| scanner.feed(c)),
+        }
+    }
+
+    /// Whether the output so far may end here.
+    pub fn is_complete(&self) -> bool {
+        match self {
+            Self::Pattern { nfa, states } => states.contains(&nfa.accept),
+            Self::Json(scanner) => scanner.is_complete(),
+        }
+    }
+
+    /// Whether appending `token` keeps the output matchable.
+    pub fn allows(&self, token: &str) -> bool {
+        self.clone().feed(token)
+    }
+}
+
+// ---------------------------------------------------------------------------------------
+// Patterns
+
+#[derive(Debug, Clone, PartialEq)]
+struct CharSet {
+    negated: bool,
+    ranges: Vec<(char, char)>,
+}
+
+impl CharSet {
+    fn single(c: char) -> Self {
+        Self { negated: false, ranges: vec![(c, c)] }
+    }
+
+    fn of(ranges: &[(char, char)]) -> Self {
+        Self { negated: false, ranges: ranges.to_vec() }
+    }
+
+    fn negate(mut self) -> Self {
+        self.negated = !self.negated;
+        self
+    }
+
+    fn contains(&self, c: char) -> bool {
+        self.ranges.iter().any(|&(lo, hi)| (lo..=hi).contains(&c)) != self.negated
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Empty,
+    Class(CharSet),
+    Concat(Vec<Node>),
+    Alt(Vec<Node>),
+    Repeat { node: Box<Node>, min: u32, max: Option<u32> },
+}
+
+impl Node {
+    fn literal(text: &str) -> Self {
+        Self::Concat(text.chars().map(|c| Self::Class(CharSet::single(c))).collect())
+    }
+
+    fn optional(node: Node) -> Self {
+        Self::Repeat { node: Box::new(node), min: 0, max: Some(1) }
+    }
+
+    fn star(node: Node) -> Self {
+        Self::Repeat { node: Box::new(node), min: 0, max: None }
+    }
+
+    fn plus(node: Node) -> Self {
+        Self::Repeat { node: Box::new(node), min: 1, max: None }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum State {
+    Char(CharSet, usize),
+    Split(Vec<usize>),
+    Accept,
+}
+
+/// Thompson NFA over characters.
+#[derive(Debug)]
+pub struct Nfa {
+    states: Vec<State>,
+    start: usize,
+    accept: usize,
+}
+
+impl Nfa {
+    fn compile(node: &Node) -> Self {
+        let mut nfa = Nfa { states: vec![State::Accept], start: 0, accept: 0 };
+        nfa.start = nfa.build(node, 0);
+        nfa
+    }
+
+    fn push(&mut self, state: State) -> usize {
+        self.states.push(state);
+        self.states.len() - 1
+    }
+
+    /// States matching `node` and then continuing at `next`; returns the entry state.
+    fn build(&mut self, node: &Node, next: usize) -> usize {
+        match node {
+            Node::Empty => next,
+            Node::Class(set) => self.push(State::Char(set.clone(), next)),
+            Node::Concat(nodes) => nodes.iter().rev().fold(next, |next, node| self.build(node, next)),
+            Node::Alt(nodes) => {
+                let starts = nodes.iter().map(|node| self.build(node, next)).collect();
+                self.push(State::Split(starts))
+            }
+            Node::Repeat { node, min, max } => {
+                let mut entry = match max {
+                    None => {
+                        let split = self.push(State::Split(Vec::new()));
+                        let body = self.build(node, split);
+                        self.states[split] = State::Split(vec![body, next]);
+                        split
+                    }
+                    Some(max) => {
+                        let mut entry = next;
+                        for _ in *min..*max {
+                            let body = self.build(node, entry);
+                            entry = self.push(State::Split(vec![body, next]));
+                        }
+                        entry
+                    }
+                };
+                for _ in 0..*min {
+                    entry = self.build(node, entry);
+                }
+                entry
+            }
+        }
+    }
+
+    /// `states` plus everything reachable from them without consuming input, sorted.
+    fn closure(&self, states: &[usize]) -> Vec<usize> {
+        let mut seen = vec![false; self.states.len()];
+        let mut stack = states.to_vec();
+        let mut out = Vec::new();
+        while let Some(s) = stack.pop() {
+            if std::mem::replace(&mut seen[s], true) {
+                continue;
+            }
+            match &self.states[s] {
+                State::Split(targets) => stack.extend(targets),
+                _ => out.push(s),
+            }
+        }
+        out.sort_unstable();
+        out
+    }
+
+    fn step(&self, states: &[usize], c: char) -> Vec<usize> {
+        let next: Vec<usize> = states
+            .iter()
+            .filter_map(|&s| match &self.states[s] {
+                State::Char(set, next) if set.contains(c) => Some(*next),
+                _ => None,
+            })
+            .collect();
+        self.closure(&next)
+    }
+}
+
+/// Parser for the regex subset: literals, `.`, classes, `\d \w \s` (and negations),
+/// groups, `|`, `* + ?` and `{m}` / `{m,}` / `{m,n}`. The pattern always matches the
+/// whole output, so `^` and `$` at the ends are accepted and ignored.
+struct RegexParser<'a> {
+    pattern: &'a str,
+    chars: std::iter::Peekable<std::str::CharIndices<'a>>,
+}
+
+impl<'a> RegexParser<'a> {
+    fn new(pattern: &'a str) -> Self {
+        let pattern = pattern.strip_prefix('^').unwrap_or(pattern);
+        let pattern = pattern.strip_suffix('$').filter(|p| !p.ends_with('\\')).unwrap_or(pattern);
+        Self { pattern, chars: pattern.char_indices().peekable() }
+    }
+
+    fn parse(mut self) -> Result<Node> {
+        let node = self.alternation()?;
+        match self.chars.next() {
+            None => Ok(node),
+            Some((at, c)) => bail!("Unexpected '{c}' at position {at} in /{}/", self.pattern),
+        }
+    }
+
+    fn alternation(&mut self) -> Result<Node> {
+        let mut branches = vec![self.concat()?];
+        while self.chars.next_if(|&(_, c)| c == '|').is_some() {
+            branches.push(self.concat()?);
+        }
+        Ok(if branches.len() == 1 { branches.pop().unwrap() } else { Node::Alt(branches) })
+    }
+
+    fn concat(&mut self) -> Result<Node> {
+        let mut items = Vec::new();
+        while let Some(&(_, c)) = self.chars.peek() {
+            if c == '|' || c == ')' {
+                break;
+            }
+            let atom = self.atom()?;
+            items.push(self.quantified(atom)?);
+        }
+        Ok(Node::Concat(items))
+    }
+
+    fn quantified(&mut self, atom: Node) -> Result<Node> {
+        let (min, max) = match self.chars.peek().map(|&(_, c)| c) {
+            Some('*') => (0, None),
+            Some('+') => (1, None),
+            Some('?') => (0, Some(1)),
+            Some('{') => return self.counted(atom),
+            _ => return Ok(atom),
+        };
+        self.chars.next();
+        Ok(Node::Repeat { node: Box::new(atom), min, max })
+    }
+
+    fn counted(&mut self, atom: Node) -> Result<Node> {
+        self.chars.next();
+        let mut body = String::new();
+        for (_, c) in self.chars.by_ref() {
+            if c == '}' {
+                let (min, max) = match body.split_once(',') {
+                    None => (body.trim(), Some(body.trim())),
+                    Some((min, "")) => (min.trim(), None),
+                    Some((min, max)) => (min.trim(), Some(max.trim())),
+                };
+                let min: u32 = min.parse().with_context(|| format!("Bad repetition {{{body}}}"))?;
+                let max: Option<u32> = max.map(str::parse).transpose().with_context(|| format!("Bad repetition {{{body}}}"))?;
+                if max.is_some_and(|max| max < min) || max.unwrap_or(min) > MAX_REPEAT {
+                    bail!("Repetition {{{body}}} is out of range (at most {MAX_REPEAT})");
+                }
+                return Ok(Node::Repeat { node: Box::new(atom), min, max });
+            }
+            body.push(c);
+        }
+        bail!("Unclosed '{{' in /{}/", self.pattern)
+    }
+
+    fn atom(&mut self) -> Result<Node> {
+        let (at, c) = self.chars.next().ok_or_else(|| anyhow!("Unexpected end of /{}/", self.pattern))?;
+        Ok(match c {
+            '(' => {
+                if self.chars.next_if(|&(_, c)| c == '?').is_some() && self.chars.next_if(|&(_, c)| c == ':').is_none() {
+                    bail!("Only (?:...) groups are supported");
+                }
+                let inner = self.alternation()?;
+                if self.chars.next_if(|&(_, c)| c == ')').is_none() {
+                    bail!("Unclosed '(' at position {at} in /{}/", self.pattern);
+                }
+                inner
+            }
+            '[' => Node::Class(self.class()?),
+            '.' => Node::Class(CharSet::single('\n').negate()),
+            '\\' => Node::Class(self.escape()?),
+            '*' | '+' | '?' | '{' => bail!("Nothing to repeat before '{c}' at position {at}"),
+            c => Node::Class(CharSet::single(c)),
+        })
+    }
+
+    fn escape(&mut self) -> Result<CharSet> {
+        let (_, c) = self.chars.next().ok_or_else(|| anyhow!("Trailing '\\' in /{}/", self.pattern))?;
+        Ok(match c {
+            'd' | 'w' | 's' => shorthand_class(c),
+            'D' | 'W' | 'S' => shorthand_class(c.to_ascii_lowercase()).negate(),
+            'n' => CharSet::single('\n'),
+            't' => CharSet::single('\t'),
+            'r' => CharSet::single('\r'),
+            c if c.is_ascii_alphanumeric() => bail!("Unsupported escape '\\{c}'"),
+            c => CharSet::single(c),
+        })
+    }
+
+    fn class(&mut self) -> Result<CharSet> {
+        let negated = self.chars.next_if(|&(_, c)| c == '^').is_some();
+        let mut set = CharSet { negated, ranges: Vec::new() };
+        let mut first = true;
+        loop {
+            let (_, c) = self.chars.next().ok_or_else(|| anyhow!("Unclosed '[' in /{}/", self.pattern))?;
+            let lo = match c {
+                ']' if !first => return Ok(set),
+                '\\' => match self.escape()? {
+                    CharSet { negated: true, .. } => bail!("Negated escapes are not supported inside [...]"),
+                    CharSet { ranges, .. } if ranges.len() == 1 && ranges[0].0 == ranges[0].1 => ranges[0].0,
+                    CharSet { ranges, .. } => {
+                        set.ranges.extend(ranges);
+                        first = false;
+                        continue;
+                    }
+                },
+                c => c,
+            };
+            first = false;
+            let is_range = self.chars.peek().is_some_and(|&(_, c)| c == '-') && {
+                let mut ahead = self.chars.clone();
+                ahead.next();
+                ahead.peek().is_some_and(|&(_, c)| c != ']')
+            };
+            if is_range {
+                self.chars.next();
+                let (_, hi) = self.chars.next().unwrap();
+                if hi < lo {
+                    bail!("Bad range {lo}-{hi} in /{}/", self.pattern);
+                }
+                set.ranges.push((lo, hi));
+            } else {
+                set.ranges.push((lo, lo));
+            }
+        }
+    }
+}
+
+/// `\d`, `\w` or `\s`.
+fn shorthand_class(c: char) -> CharSet {
+    match c {
+        'd' => CharSet::of(&[('0', '9')]),
+        'w' => CharSet::of(&[('a', 'z'), ('A', 'Z'), ('0', '9'), ('_', '_')]),
+        _ => CharSet::of(&[(' ', ' '), ('\t', '\r')]),
+    }
+}
+
+// ---------------------------------------------------------------------------------------
+// JSON schemas
+
+fn ws() -> Node {
+    Node::star(Node::Class(CharSet::of(&[(' ', ' '), ('\t', '\t'), ('\n', '\n'), ('\r', '\r')])))
+}
+
+fn json_string() -> Node {
+    let plain = CharSet::of(&[('"', '"'), ('\\', '\\'), ('\0', '\u{1f}')]).negate();
+    let hex = Node::Class(CharSet::of(&[('0', '9'), ('a', 'f'), ('A', 'F')]));
+    let escape = Node::Concat(vec![
+        Node::literal("\\"),
+        Node::Alt(vec![
+            Node::Class(CharSet::of(&[('"', '"'), ('\\', '\\'), ('/', '/'), ('b', 'b'), ('f', 'f'), ('n', 'n'), ('r', 'r'), ('t', 't')])),
+            Node::Concat(vec![Node::literal("u"), Node::Repeat { node: Box::new(hex), min: 4, max: Some(4) }]),
+        ]),
+    ]);
+    Node::Concat(vec![Node::literal("\""), Node::star(Node::Alt(vec![Node::Class(plain), escape])), Node::literal("\"")])
+}
+
+fn json_integer() -> Node {
+    let digit = Node::Class(CharSet::of(&[('0', '9')]));
+    Node::Concat(vec![
+        Node::optional(Node::literal("-")),
+        Node::Alt(vec![Node::literal("0"), Node::Concat(vec![Node::Class(CharSet::of(&[('1', '9')])), Node::star(digit)])]),
+    ])
+}
+
+fn json_number() -> Node {
+    let digits = Node::plus(Node::Class(CharSet::of(&[('0', '9')])));
+    Node::Concat(vec![
+        json_integer(),
+        Node::optional(Node::Concat(vec![Node::literal("."), digits.clone()])),
+        Node::optional(Node::Concat(vec![
+            Node::Class(CharSet::of(&[('e', 'e'), ('E', 'E')])),
+            Node::optional(Node::Class(CharSet::of(&[('+', '+'), ('-', '-')]))),
+            digits,
+        ])),
+    ])
+}
+
+/// `[ item, item, ... ]` with optional whitespace.
+fn json_array(item: Node) -> Node {
+    let items = Node::Concat(vec![item.clone(), Node::star(Node::Concat(vec![ws(), Node::literal(","), ws(), item]))]);
+    Node::Concat(vec![Node::literal("["), ws(), Node::optional(items), ws(), Node::literal("]")])
+}
+
+/// `{ "key": value, ... }` with any keys.
+fn json_map(value: Node) -> Node {
+    let member = Node::Concat(vec![json_string(), ws(), Node::literal(":"), ws(), value]);
+    let members = Node::Concat(vec![member.clone(), Node::star(Node::Concat(vec![ws(), Node::literal(","), ws(), member]))]);
+    Node::Concat(vec![Node::literal("{"), ws(), Node::optional(members), ws(), Node::literal("}")])
+}
+
+/// Any JSON value nested at most `depth` levels.
+fn any_json(depth: usize) -> Node {
+    let mut alternatives = vec![json_string(), json_number(), Node::literal("true"), Node::literal("false"), Node::literal("null")];
+    if depth > 0 {
+        alternatives.push(json_array(any_json(depth - 1)));
+        alternatives.push(json_map(any_json(depth - 1)));
+    }
+    Node::Alt(alternatives)
+}
+
+/// Object with the given properties in order; optional ones may be left out.
+fn json_object(properties: &[(String, Node, bool)]) -> Node {
+    fn member(name: &str, value: &Node) -> Node {
+        let key = serde_json::to_string(name).expect("strings serialize");
+        Node::Concat(vec![Node::literal(&key), ws(), Node::literal(":"), ws(), value.clone()])
+    }
+    // After the first member every included property brings its own leading comma
+    let mut tail = Node::Empty;
+    let mut rest = Node::Empty;
+    for (name, value, required) in properties.iter().rev() {
+        let with_comma = Node::Concat(vec![ws(), Node::literal(","), ws(), member(name, value)]);
+        let after = tail.clone();
+        tail = Node::Concat(vec![if *required { with_comma } else { Node::optional(with_comma) }, after.clone()]);
+        let first = Node::Concat(vec![member(name, value), after]);
+        rest = if *required { first } else { Node::Alt(vec![first, rest]) };
+    }
+    Node::Concat(vec![Node::literal("{"), ws(), rest, ws(), Node::literal("}")])
+}
+
+fn schema_node(schema: &Value) -> Result<Node> {
+    let Some(schema) = schema.as_object() else {
+        return match schema {
+            Value::Bool(true) => Ok(any_json(ANY_VALUE_DEPTH)),
+            _ => bail!("A schema must be an object"),
+        };
+    };
+    let literals = |values: &[Value]| Node::Alt(values.iter().map(|v| Node::literal(&v.to_string())).collect());
+    if let Some(value) = schema.get("const") {
+        return Ok(literals(std::slice::from_ref(value)));
+    }
+    if let Some(values) = schema.get("enum") {
+        let values = values.as_array().filter(|v| !v.is_empty()).context("\"enum\" must be a non-empty array")?;
+        return Ok(literals(values));
+    }
+    let types: Vec<&str> = match schema.get("type") {
+        None => return Ok(any_json(ANY_VALUE_DEPTH)),
+        Some(Value::String(t)) => vec![t.as_str()],
+        Some(Value::Array(ts)) => ts.iter().filter_map(Value::as_str).collect(),
+        Some(other) => bail!("Unsupported \"type\": {other}"),
+    };
+    let mut alternatives = Vec::new();
+    for t in types {
+        alternatives.push(match t {
+            "string" => json_string(),
+            "integer" => json_integer(),
+            "number" => json_number(),
+            "boolean" => Node::Alt(vec![Node::literal("true"), Node::literal("false")]),
+            "null" => Node::literal("null"),
+            "array" => json_array(match schema.get("items") {
+                Some(items) => schema_node(items)?,
+                None => any_json(ANY_VALUE_DEPTH - 1),
+            }),
+            "object" => match schema.get("properties").and_then(Value::as_object) {
+                Some(properties) => {
+                    let required: Vec<&str> = schema.get("required").and_then(Value::as_array).map(|r| r.iter().filter_map(Value::as_str).collect()).unwrap_or_default();
+                    let properties = properties
+                        .iter()
+                        .map(|(name, sub)| Ok((name.clone(), schema_node(sub).with_context(|| format!("In property \"{name}\""))?, required.contains(&name.as_str()))))
+                        .collect::<Result<Vec<_>>>()?;
+                    json_object(&properties)
+                }
+                None => json_map(any_json(ANY_VALUE_DEPTH - 1)),
+            },
+            other => bail!("Unsupported type \"{other}\""),
+        });
+    }
+    Ok(Node::Alt(alternatives))
+}
+
+// ---------------------------------------------------------------------------------------
+// Plain JSON
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Container {
+    Object,
+    Array,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Num {
+    Minus,
+    Zero,
+    Int,
+    Dot,
+    Frac,
+    E,
+    ESign,
+    Exp,
+}
+
+impl Num {
+    fn next(self, c: char) -> Option<Num> {
+        use Num::*;
+        Some(match (self, c) {
+            (Minus, '0') => Zero,
+            (Minus, '1'..='9') | (Int, '0'..='9') => Int,
+            (Zero | Int, '.') => Dot,
+            (Dot | Frac, '0'..='9') => Frac,
+            (Zero | Int | Frac, 'e' | 'E') => E,
+            (E, '+' | '-') => ESign,
+            (E | ESign | Exp, '0'..='9') => Exp,
+            _ => return None,
+        })
+    }
+
+    fn is_complete(self) -> bool {
+        matches!(self, Num::Zero | Num::Int | Num::Frac | Num::Exp)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Scan {
+    /// Expecting a value; `]` allowed right after `[`.
+    Value { first_in_array: bool },
+    AfterValue,
+    /// Expecting a key; `}` allowed right after `{`.
+    Key { first: bool },
+    AfterKey,
+    Str { key: bool, escape: Escape },
+    Literal { word: &'static str, pos: usize },
+    Number(Num),
+    Failed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Escape {
+    None,
+    Backslash,
+    Unicode(u8),
+}
+
+/// Character-by-character JSON validator accepting any prefix of a valid document.
+#[derive(Debug, Clone)]
+pub struct JsonScanner {
+    stack: Vec<Container>,
+    scan: Scan,
+}
+
+impl Default for JsonScanner {
+    fn default() -> Self {
+        Self { stack: Vec::new(), scan: Scan::Value { first_in_array: false } }
+    }
+}
+
+impl JsonScanner {
+    fn feed(&mut self, c: char) -> bool {
+        self.scan = self.next(c);
+        self.scan != Scan::Failed
+    }
+
+    fn is_complete(&self) -> bool {
+        self.stack.is_empty() && (self.scan == Scan::AfterValue || matches!(self.scan, Scan::Number(n) if n.is_complete()))
+    }
+
+    fn close(&mut self, container: Container) -> Scan {
+        if self.stack.pop() == Some(container) { Scan::AfterValue } else { Scan::Failed }
+    }
+
+    fn next(&mut self, c: char) -> Scan {
+        let is_ws = matches!(c, ' ' | '\t' | '\n' | '\r');
+        match self.scan {
+            Scan::Failed => Scan::Failed,
+            Scan::Value { first_in_array } => match c {
+                _ if is_ws => self.scan,
+                '{' => {
+                    self.stack.push(Container::Object);
+                    Scan::Key { first: true }
+                }
+                '[' => {
+                    self.stack.push(Container::Array);
+                    Scan::Value { first_in_array: true }
+                }
+                ']' if first_in_array => self.close(Container::Array),
+                '"' => Scan::Str { key: false, escape: Escape::None },
+                't' => Scan::Literal { word: "true", pos: 1 },
+                'f' => Scan::Literal { word: "false", pos: 1 },
+                'n' => Scan::Literal { word: "null", pos: 1 },
+                '-' => Scan::Number(Num::Minus),
+                '0' => Scan::Number(Num::Zero),
+                '1'..='9' => Scan::Number(Num::Int),
+                _ => Scan::Failed,
+            },
+            Scan::AfterValue => match (c, self.stack.last()) {
+                _ if is_ws => Scan::AfterValue,
+                (',', Some(Container::Object)) => Scan::Key { first: false },
+                (',', Some(Container::Array)) => Scan::Value { first_in_array: false },
+                ('}', _) => self.close(Container::Object),
+                (']', _) => self.close(Container::Array),
+                _ => Scan::Failed,
+            },
+            Scan::Key { first } => match c {
+                _ if is_ws => self.scan,
+                '"' => Scan::Str { key: true, escape: Escape::None },
+                '}' if first => self.close(Container::Object),
+                _ => Scan::Failed,
+            },
+            Scan::AfterKey => match c {
+                _ if is_ws => Scan::AfterKey,
+                ':' => Scan::Value { first_in_array: false },
+                _ => Scan::Failed,
+            },
+            Scan::Str { key, escape } => {
+                let escape = match (escape, c) {
+                    (Escape::None, '"') => return if key { Scan::AfterKey } else { Scan::AfterValue },
+                    (Escape::None, '\\') => Escape::Backslash,
+                    (Escape::None, c) if c < ' ' => return Scan::Failed,
+                    (Escape::None, _) => Escape::None,
+                    (Escape::Backslash, '"' | '\\' | '/' | 'b' | 'f' | 'n' | 'r' | 't') => Escape::None,
+                    (Escape::Backslash, 'u') => Escape::Unicode(0),
+                    (Escape::Unicode(n), c) if c.is_ascii_hexdigit() => if n == 3 { Escape::None } else { Escape::Unicode(n + 1) },
+                    _ => return Scan::Failed,
+                };
+                Scan::Str { key, escape }
+            }
+            Scan::Literal { word, pos } => match word[pos..].chars().next() {
+                Some(expected) if expected == c && pos + 1 == word.len() => Scan::AfterValue,
+                Some(expected) if expected == c => Scan::Literal { word, pos: pos + 1 },
+                _ => Scan::Failed,
+            },
+            Scan::Number(num) => match num.next(c) {
+                Some(num) => Scan::Number(num),
+                // The number ended; the character belongs to what follows it
+                None if num.is_complete() => {
+                    self.scan = Scan::AfterValue;
+                    self.next(c)
+                }
+                None => Scan::Failed,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compile(constraint: OutputConstraint) -> Constraint {
+        constraint.compile().unwrap()
+    }
+
+    #[test]
+    fn test_regex_prefixes_and_matches() {
+        let date = compile(OutputConstraint::Regex(r"^\d{4}-\d{2}-\d{2}$".into()));
+        assert!(date.is_match("2026-10-16"));
+        assert!(!date.is_match("2026-10-1"));
+        let mut state = date.start();
+        assert!(state.feed("2026-1"));
+        assert!(state.allows("0-") && !state.allows("x"));
+
+        let answer = compile(OutputConstraint::Regex("(?:yes|no)[.!]?".into()));
+        assert!(answer.is_match("yes") && answer.is_match("no!") && !answer.is_match("maybe"));
+        assert!(compile(OutputConstraint::Regex("[^a-c]+".into())).is_match("xyz"));
+        assert!(OutputConstraint::Regex("(ab".into()).compile().is_err());
+        assert!(OutputConstraint::Regex("a{3,1}".into()).compile().is_err());
+    }
+
+    #[test]
+    fn test_schema_object_with_optional_properties() {
+        let schema = r#"{"type":"object","properties":{"name":{"type":"string"},"age":{"type":"integer"},"tags":{"type":"array","items":{"enum":["a","b"]}}},"required":["name"]}"#;
+        let person = compile(OutputConstraint::JsonSchema(schema.into()));
+        // serde_json keeps properties sorted unless `preserve_order` is on; accept either order
+        let accepts = |a: &str, b: &str| person.is_match(a) || person.is_match(b);
+        assert!(accepts(r#"{"name":"Ada"}"#, r#"{"name":"Ada"}"#));
+        assert!(accepts(r#"{"name": "Ada", "age": 36}"#, r#"{"age": 36, "name": "Ada"}"#));
+        assert!(accepts(r#"{"name":"Ada","tags":["a","b"]}"#, r#"{"name":"Ada","tags":["a","b"]}"#));
+        assert!(!person.is_match(r#"{"age":36}"#));
+        assert!(!person.is_match(r#"{"name":"Ada","tags":["c"]}"#));
+        assert!(!person.is_match(r#"{"name":"Ada","extra":1}"#));
+        assert!(OutputConstraint::JsonSchema("{".into()).compile().is_err());
+    }
+
+    #[test]
+    fn test_sampler_masks_tokens_that_break_the_schema() {
+        use crate::ai::sampler::{LogitsSampler, SamplerConfig};
+        let schema = r#"{"type":"object","properties":{"ok":{"type":"boolean"}},"required":["ok"]}"#;
+        let constraint = compile(OutputConstraint::JsonSchema(schema.into()));
+        let vocab: Vec<String> = ["Sure!", "{", "\"ok\"", ":", " ", "true", "}", "<eos>"].map(String::from).to_vec();
+        // The model would rather chat and stop early; the mask leaves it no choice
+        let preferred = [9.0, 1.0, 1.0, 1.0, 0.5, 1.0, 1.0, 8.0];
+        let mut sampler = LogitsSampler::new(SamplerConfig::default());
+        let mut state = constraint.start();
+        let mut output = String::new();
+        for _ in 0..16 {
+            let mut logits = preferred;
+            let token = sampler.sample_constrained(&mut logits, &vocab, &state, &[7]).unwrap();
+            if token == 7 {
+                break;
+            }
+            assert!(state.feed(&vocab[token]));
+            output.push_str(&vocab[token]);
+        }
+        assert!(constraint.is_match(&output), "{output}");
+        assert_eq!(serde_json::from_str::<Value>(&output).unwrap()["ok"], true);
+    }
+
+    #[test]
+    fn test_plain_json_scanner() {
+        let json = compile(OutputConstraint::Json);
+        for valid in [r#"{"a": [1, -2.5e3, true, null, {"b": "é\n"}]}"#, "[]", "0", " \"x\" "] {
+            assert!(json.is_match(valid), "{valid}");
+        }
+        for invalid in ["{", "[1,]", "{\"a\" 1}", "01", "tru", "[1}"] {
+            assert!(!json.is_match(invalid), "{invalid}");
+        }
+        let mut state = json.start();
+        assert!(state.feed("{\"a\": [1"));
+        assert!(!state.is_complete() && state.allows("]}") && !state.allows("}"));
+    }
+}
@@ -0,0 +1,215 @@
+//! Context budgeting for messages re-entering the prompt.
+//!
+//! Tool results (file contents, fetched pages) can be arbitrarily large. The full
+//! text stays on the stored `ChatMessage` so the UI can show it on demand; only the
+//! copy handed to the provider is compacted.
+
+use super::{ChatMessage, MessageRole};
+use crate::storage::stats::estimate_tokens;
+use serde::{Deserialize, Serialize};
+
+/// Default character budget for a single tool result inside the prompt.
+pub const DEFAULT_TOOL_RESULT_MAX_CHARS: usize = 4000;
+
+/// Marker inserted where content was elided.
+const ELISION_MARKER: &str = "characters omitted";
+
+/// Returns true if a tool result of this size would be compacted.
+pub fn needs_compaction(content: &str, max_chars: usize) -> bool {
+    max_chars > 0 && content.chars().count() > max_chars
+}
+
+/// Keep the head and tail of an oversized tool result and summarise the elided middle.
+/// Head gets two thirds of the budget since file headers / page leads carry most signal.
+pub fn compact_tool_result(content: &str, max_chars: usize) -> String {
+    if !needs_compaction(content, max_chars) {
+        return content.to_string();
+    }
+    let total = content.chars().count();
+    let head_len = max_chars * 2 / 3;
+    let tail_len = max_chars - head_len;
+    let omitted = total - head_len - tail_len;
+    let omitted_lines = content
+        .chars()
+        .skip(head_len)
+        .take(omitted)
+        .filter(|&c| c == '\n')
+        .count();
+
+    let head: String = content.chars().take(head_len).collect();
+    let tail: String = content.chars().skip(total - tail_len).collect();
+    format!(
+        "{head}\n[... {omitted} {ELISION_MARKER} ({omitted_lines} lines) from a {total}-character tool result ...]\n{tail}"
+    )
+}
+
+/// Build the message list sent to a provider, compacting oversized tool results and
+/// spelling out what replies quote.
+pub fn prepare_context(messages: &[ChatMessage], tool_result_max_chars: usize) -> Vec<ChatMessage> {
+    messages
+        .iter()
+        .map(|m| {
+            if matches!(m.role, MessageRole::Tool) && needs_compaction(&m.content, tool_result_max_chars) {
+                let mut compacted = m.clone();
+                compacted.content = compact_tool_result(&m.content, tool_result_max_chars);
+                compacted
+            } else if m.reply_to.is_some() {
+                ChatMessage { content: m.prompt_content(), reply_to: None, ..m.clone() }
+            } else {
+                m.clone()
+            }
+        })
+        .collect()
+}
+
+/// Estimated prompt size of a conversation as it would be sent to the provider.
+pub fn conversation_tokens(messages: &[ChatMessage], tool_result_max_chars: usize) -> u64 {
+    messages
+        .iter()
+        .map(|m| {
+            if matches!(m.role, MessageRole::Tool) && needs_compaction(&m.content, tool_result_max_chars) {
+                estimate_tokens(&compact_tool_result(&m.content, tool_result_max_chars))
+            } else {
+                estimate_tokens(&m.content) + m.reply_to.as_ref().map_or(0, |r| estimate_tokens(&r.excerpt))
+            }
+        })
+        .sum()
+}
+
+/// Prompt and completion tokens spent over a conversation's replies.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenUsage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub replies: u32,
+}
+
+impl TokenUsage {
+    pub fn record(&mut self, prompt_tokens: u64, completion_tokens: u64) {
+        self.prompt_tokens += prompt_tokens;
+        self.completion_tokens += completion_tokens;
+        self.replies += 1;
+    }
+
+    pub fn total(&self) -> u64 {
+        self.prompt_tokens + self.completion_tokens
+    }
+}
+
+/// Soft limits on a conversation's prompt size. Nothing is cut off; the chat header meter
+/// changes colour and a warning is shown when a limit is crossed. 0 disables a limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TokenLimits {
+    /// The model's context window.
+    pub context_tokens: u64,
+    /// Prompt size beyond which replies get noticeably slow on this machine.
+    pub slow_tokens: u64,
+}
+
+impl Default for TokenLimits {
+    fn default() -> Self {
+        Self { context_tokens: 4096, slow_tokens: 2048 }
+    }
+}
+
+/// How close a conversation is to the limits, from best to worst.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum BudgetLevel {
+    Comfortable,
+    Slow,
+    NearContext,
+    OverContext,
+}
+
+impl TokenLimits {
+    /// The share of the context window at which [`BudgetLevel::NearContext`] starts.
+    const NEAR_CONTEXT: f64 = 0.9;
+
+    pub fn assess(&self, tokens: u64) -> BudgetLevel {
+        if self.context_tokens > 0 && tokens > self.context_tokens {
+            BudgetLevel::OverContext
+        } else if self.context_tokens > 0 && tokens as f64 >= self.context_tokens as f64 * Self::NEAR_CONTEXT {
+            BudgetLevel::NearContext
+        } else if self.slow_tokens > 0 && tokens > self.slow_tokens {
+            BudgetLevel::Slow
+        } else {
+            BudgetLevel::Comfortable
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn msg(role: MessageRole, content: &str) -> ChatMessage {
+        ChatMessage {
+            id: "t".into(),
+            content: content.into(),
+            role,
+            timestamp: chrono::Utc::now(),
+            model_used: None,
+            inference_time: None,
+            images: Vec::new(),
+            trace: None,
+            reply_to: None,
+            feedback: None,
+            edited_at: None,
+        }
+    }
+
+    #[test]
+    fn test_short_result_untouched() {
+        assert_eq!(compact_tool_result("short", 100), "short");
+        assert_eq!(compact_tool_result("anything", 0), "anything");
+    }
+
+    #[test]
+    fn test_compaction_keeps_head_and_tail() {
+        let content = format!("HEAD{}TAIL", "x".repeat(10_000));
+        let compacted = compact_tool_result(&content, 300);
+        assert!(compacted.starts_with("HEAD"));
+        assert!(compacted.ends_with("TAIL"));
+        assert!(compacted.contains(ELISION_MARKER));
+        assert!(compacted.chars().count() < 500);
+    }
+
+    #[test]
+    fn test_prepare_context_only_compacts_tool_messages() {
+        let big = "y".repeat(5000);
+        let messages = vec![msg(MessageRole::User, &big), msg(MessageRole::Tool, &big)];
+        let prepared = prepare_context(&messages, 1000);
+        assert_eq!(prepared[0].content, big);
+        assert!(prepared[1].content.contains(ELISION_MARKER));
+        // The original is left intact for on-demand viewing
+        assert_eq!(messages[1].content, big);
+    }
+
+    #[test]
+    fn test_replies_carry_their_quote_into_the_prompt() {
+        let original = msg(MessageRole::Assistant, "Use a mutex.");
+        let reply = ChatMessage { reply_to: Some(crate::ai::QuotedReply::new(&original)), ..msg(MessageRole::User, "Why not a channel?") };
+        let prepared = prepare_context(&[original, reply], 1000);
+        assert!(prepared[1].content.contains("> Use a mutex.") && prepared[1].content.ends_with("Why not a channel?"));
+        assert!(prepared[1].reply_to.is_none());
+    }
+
+    #[test]
+    fn test_token_limits_levels() {
+        let limits = TokenLimits { context_tokens: 1000, slow_tokens: 500 };
+        assert_eq!(limits.assess(100), BudgetLevel::Comfortable);
+        assert_eq!(limits.assess(501), BudgetLevel::Slow);
+        assert_eq!(limits.assess(900), BudgetLevel::NearContext);
+        assert_eq!(limits.assess(1001), BudgetLevel::OverContext);
+        let unlimited = TokenLimits { context_tokens: 0, slow_tokens: 0 };
+        assert_eq!(unlimited.assess(1_000_000), BudgetLevel::Comfortable);
+    }
+
+    #[test]
+    fn test_conversation_tokens_count_the_compacted_prompt() {
+        let messages = vec![msg(MessageRole::User, &"a".repeat(400)), msg(MessageRole::Tool, &"b".repeat(40_000))];
+        let tokens = conversation_tokens(&messages, 400);
+        assert!(tokens > 100 && tokens < 250, "{tokens}");
+    }
+}
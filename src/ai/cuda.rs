@@ -0,0 +1,202 @@
+//! CUDA driver, runtime and cuDNN version probing.
+//!
+//! The CUDA execution provider only reports "registration failed" when one of its
+//! dependencies is missing or has the wrong major version. Before the EP is tried, the
+//! libraries it links against are opened and asked for their versions so the failure can be
+//! turned into an instruction the user can act on.
+
+use crate::ai::providers::LoadError;
+use serde::Serialize;
+use std::ffi::c_int;
+use std::path::PathBuf;
+
+/// CUDA major version the ONNX Runtime 1.19+ GPU builds are compiled against.
+pub const REQUIRED_CUDA_MAJOR: u32 = 12;
+/// cuDNN major version those builds need.
+pub const REQUIRED_CUDNN_MAJOR: u32 = 9;
+
+#[cfg(target_os = "windows")]
+const DRIVER_LIBRARIES: &[&str] = &["nvcuda.dll"];
+#[cfg(not(target_os = "windows"))]
+const DRIVER_LIBRARIES: &[&str] = &["libcuda.so.1", "libcuda.so"];
+
+#[cfg(target_os = "windows")]
+const RUNTIME_LIBRARIES: &[&str] = &["cudart64_12.dll"];
+#[cfg(not(target_os = "windows"))]
+const RUNTIME_LIBRARIES: &[&str] = &["libcudart.so.12", "libcudart.so"];
+
+#[cfg(target_os = "windows")]
+const CUDNN_LIBRARIES: &[&str] = &["cudnn64_9.dll"];
+#[cfg(not(target_os = "windows"))]
+const CUDNN_LIBRARIES: &[&str] = &["libcudnn.so.9", "libcudnn.so"];
+
+/// Have CUDA number GPUs by PCI bus, the order `nvidia-smi` lists them in, so the device ids
+/// shown in the settings select the same GPU. CUDA's default order puts the fastest GPU first.
+/// An order the user chose is left alone. Call before anything opens ONNX Runtime.
+pub fn use_pci_bus_order() {
+    if std::env::var_os("CUDA_DEVICE_ORDER").is_none() {
+        std::env::set_var("CUDA_DEVICE_ORDER", "PCI_BUS_ID");
+    }
+}
+
+/// Whether CUDA device ids follow `nvidia-smi`'s numbering; see [`use_pci_bus_order`].
+pub fn numbers_devices_like_nvidia_smi() -> bool {
+    std::env::var("CUDA_DEVICE_ORDER").is_ok_and(|order| order == "PCI_BUS_ID")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct Version {
+    pub major: u32,
+    pub minor: u32,
+}
+
+impl Version {
+    /// CUDA encodes versions as `major * 1000 + minor * 10`, e.g. 12040 for 12.4.
+    fn from_cuda(encoded: c_int) -> Option<Self> {
+        let encoded = u32::try_from(encoded).ok().filter(|v| *v > 0)?;
+        Some(Self { major: encoded / 1000, minor: (encoded % 1000) / 10 })
+    }
+
+    /// cuDNN 9 uses `major * 10000 + minor * 100 + patch`; earlier releases used
+    /// `major * 1000 + minor * 100 + patch`.
+    fn from_cudnn(encoded: usize) -> Option<Self> {
+        let encoded = u32::try_from(encoded).ok().filter(|v| *v > 0)?;
+        let (major, rest) = if encoded >= 90000 { (encoded / 10000, encoded % 10000) } else { (encoded / 1000, encoded % 1000) };
+        Some(Self { major, minor: rest / 100 })
+    }
+}
+
+impl std::fmt::Display for Version {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+/// Versions found on this machine; `None` where the library couldn't be opened.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct CudaReport {
+    /// Newest CUDA version the installed driver supports.
+    pub driver: Option<Version>,
+    pub runtime: Option<Version>,
+    pub cudnn: Option<Version>,
+}
+
+impl CudaReport {
+    /// Why the CUDA EP can't work here, if any dependency is missing or mismatched.
+    pub fn problem(&self) -> Option<LoadError> {
+        let Some(driver) = self.driver else {
+            return Some(LoadError::CudaDriver(
+                "No NVIDIA driver found. Install the current NVIDIA driver for your GPU, or choose another execution provider.".into(),
+            ));
+        };
+        if driver.major < REQUIRED_CUDA_MAJOR {
+            return Some(LoadError::CudaDriver(format!(
+                "The NVIDIA driver supports CUDA {driver}, but ONNX Runtime needs CUDA {REQUIRED_CUDA_MAJOR}. Update the NVIDIA driver."
+            )));
+        }
+        match self.runtime {
+            None => {
+                return Some(LoadError::CudaToolkit(format!(
+                    "The CUDA {REQUIRED_CUDA_MAJOR} runtime (cudart) was not found. Install CUDA Toolkit {REQUIRED_CUDA_MAJOR}.x and make sure its libraries are on the library path."
+                )))
+            }
+            Some(runtime) if runtime.major != REQUIRED_CUDA_MAJOR => {
+                return Some(LoadError::CudaToolkit(format!(
+                    "CUDA {runtime} is installed, but ONNX Runtime needs CUDA {REQUIRED_CUDA_MAJOR}.x. Install CUDA Toolkit {REQUIRED_CUDA_MAJOR}.x."
+                )))
+            }
+            Some(runtime) if runtime > driver => {
+                return Some(LoadError::CudaDriver(format!(
+                    "CUDA {runtime} needs a newer driver than the installed one (supports CUDA {driver}). Update the NVIDIA driver."
+                )))
+            }
+            Some(_) => {}
+        }
+        match self.cudnn {
+            None => Some(LoadError::Cudnn(format!(
+                "cuDNN {REQUIRED_CUDNN_MAJOR} was not found. Install cuDNN {REQUIRED_CUDNN_MAJOR}.x for CUDA {REQUIRED_CUDA_MAJOR} and make sure its libraries are on the library path."
+            ))),
+            Some(cudnn) if cudnn.major != REQUIRED_CUDNN_MAJOR => Some(LoadError::Cudnn(format!(
+                "cuDNN {cudnn} is installed, but ONNX Runtime needs cuDNN {REQUIRED_CUDNN_MAJOR}.x."
+            ))),
+            Some(_) => None,
+        }
+    }
+
+    /// One-line summary such as "driver CUDA 12.4, runtime 12.2, cuDNN 9.1".
+    pub fn summary(&self) -> String {
+        let show = |v: Option<Version>| v.map_or("not found".to_string(), |v| v.to_string());
+        format!("driver CUDA {}, runtime {}, cuDNN {}", show(self.driver), show(self.runtime), show(self.cudnn))
+    }
+}
+
+/// Library names to try: beside the ONNX Runtime library first (GPU packages often bundle
+/// their CUDA dependencies there), then the system search path.
+fn candidates(names: &[&str]) -> Vec<PathBuf> {
+    let ort_dir = crate::ai::runtime::ort_library_path().parent().map(PathBuf::from).filter(|d| !d.as_os_str().is_empty());
+    let mut paths: Vec<PathBuf> = ort_dir.iter().flat_map(|dir| names.iter().map(move |n| dir.join(n))).collect();
+    paths.extend(names.iter().map(PathBuf::from));
+    paths
+}
+
+fn open(names: &[&str]) -> Option<libloading::Library> {
+    // SAFETY: opening the CUDA libraries runs their initializers, as the CUDA EP would.
+    candidates(names).into_iter().find_map(|path| unsafe { libloading::Library::new(path).ok() })
+}
+
+/// Call a CUDA `fn(int*) -> status` version getter.
+fn cuda_version(names: &[&str], symbol: &[u8]) -> Option<Version> {
+    let library = open(names)?;
+    // SAFETY: `cuDriverGetVersion` / `cudaRuntimeGetVersion` only write one int and need no
+    // prior initialization.
+    unsafe {
+        let get: libloading::Symbol<unsafe extern "C" fn(*mut c_int) -> c_int> = library.get(symbol).ok()?;
+        let mut encoded: c_int = 0;
+        (get(&mut encoded) == 0).then_some(())?;
+        Version::from_cuda(encoded)
+    }
+}
+
+/// Probe the driver, runtime and cuDNN libraries the CUDA EP loads.
+pub fn probe() -> CudaReport {
+    let cudnn = open(CUDNN_LIBRARIES).and_then(|library| {
+        // SAFETY: `cudnnGetVersion` takes no arguments and returns the version number.
+        unsafe {
+            let get: libloading::Symbol<unsafe extern "C" fn() -> usize> = library.get(b"cudnnGetVersion").ok()?;
+            Version::from_cudnn(get())
+        }
+    });
+    CudaReport {
+        driver: cuda_version(DRIVER_LIBRARIES, b"cuDriverGetVersion"),
+        runtime: cuda_version(RUNTIME_LIBRARIES, b"cudaRuntimeGetVersion"),
+        cudnn,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_version_decoding() {
+        assert_eq!(Version::from_cuda(12040), Some(Version { major: 12, minor: 4 }));
+        assert_eq!(Version::from_cuda(0), None);
+        assert_eq!(Version::from_cudnn(90100), Some(Version { major: 9, minor: 1 }));
+        assert_eq!(Version::from_cudnn(8907), Some(Version { major: 8, minor: 9 }));
+    }
+
+    #[test]
+    fn test_problems_are_classified() {
+        let v = |major, minor| Some(Version { major, minor });
+        let report = |driver, runtime, cudnn| CudaReport { driver, runtime, cudnn };
+
+        assert!(report(v(12, 4), v(12, 2), v(9, 1)).problem().is_none());
+        assert!(matches!(report(None, None, None).problem(), Some(LoadError::CudaDriver(_))));
+        assert!(matches!(report(v(11, 8), v(12, 2), v(9, 1)).problem(), Some(LoadError::CudaDriver(_))));
+        assert!(matches!(report(v(12, 2), v(12, 4), v(9, 1)).problem(), Some(LoadError::CudaDriver(_))));
+        assert!(matches!(report(v(12, 4), None, v(9, 1)).problem(), Some(LoadError::CudaToolkit(_))));
+        assert!(matches!(report(v(12, 4), v(11, 8), v(9, 1)).problem(), Some(LoadError::CudaToolkit(_))));
+        assert!(matches!(report(v(12, 4), v(12, 2), v(8, 9)).problem(), Some(LoadError::Cudnn(_))));
+        assert_eq!(report(v(12, 4), None, v(9, 1)).summary(), "driver CUDA 12.4, runtime not found, cuDNN 9.1");
+    }
+}
@@ -0,0 +1,88 @@
+//! Token-by-token decoding for decoder models.
+//!
+//! After the prompt is prefilled, each step samples one token from the logits of the last
+//! position and feeds it back, with the KV cache when the model takes one. Decoding stops
+//! at an end-of-sequence token, when the reply starts the next turn of the transcript
+//! (base models without chat training tend to carry on with "User: …"), or at `max_tokens`.
+
+use anyhow::{bail, Result};
+use ndarray::{ArrayViewD, Axis};
+use std::path::Path;
+
+/// End-of-sequence tokens of common model families, stopped at when the vocabulary has them.
+const END_TOKENS: [&str; 8] =
+    ["</s>", "<|endoftext|>", "<|end_of_text|>", "<|eot_id|>", "<|end|>", "<|im_end|>", "<eos>", "<end_of_turn>"];
+
+/// Lines that open the next turn of the transcript `render_chat_prompt` builds.
+const TURN_MARKERS: [&str; 3] = ["\nUser:", "\nSystem:", "\nTool:"];
+
+/// End-of-sequence token ids: `eos_token_id` from the model's `generation_config.json` or
+/// `config.json` (a number or a list), plus the known end tokens its vocabulary has.
+pub fn eos_token_ids(model_dir: &Path, token_id: impl Fn(&str) -> Option<i64>) -> Vec<i64> {
+    let mut ids = Vec::new();
+    for file in ["generation_config.json", "config.json"] {
+        let Ok(text) = std::fs::read_to_string(model_dir.join(file)) else { continue };
+        let Ok(json) = serde_json::from_str::<serde_json::Value>(&text) else { continue };
+        match json.get("eos_token_id") {
+            Some(serde_json::Value::Number(id)) => ids.extend(id.as_i64()),
+            Some(serde_json::Value::Array(list)) => ids.extend(list.iter().filter_map(serde_json::Value::as_i64)),
+            _ => {}
+        }
+    }
+    ids.extend(END_TOKENS.iter().filter_map(|token| token_id(token)));
+    ids.sort_unstable();
+    ids.dedup();
+    ids
+}
+
+/// Logits of the last position from a `[batch, seq, vocab]` (or `[batch, vocab]`) output.
+pub fn last_logits(logits: ArrayViewD<'_, f32>) -> Result<Vec<f32>> {
+    let batch = match logits.ndim() {
+        2 | 3 if logits.shape()[0] > 0 => logits.index_axis(Axis(0), 0),
+        _ => bail!("Unexpected logits shape {:?}", logits.shape()),
+    };
+    let row = match batch.ndim() {
+        1 => batch,
+        _ => match batch.shape()[0] {
+            0 => bail!("Logits cover no positions"),
+            seq => batch.index_axis(Axis(0), seq - 1),
+        },
+    };
+    Ok(row.iter().copied().collect())
+}
+
+/// Where the reply starts the next turn of the transcript, if it does.
+pub fn end_of_turn(reply: &str) -> Option<usize> {
+    TURN_MARKERS.iter().filter_map(|marker| reply.find(marker)).min()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::{Array2, Array3};
+
+    #[test]
+    fn test_eos_ids_from_config_and_vocabulary() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("generation_config.json"), r#"{"eos_token_id": [2, 32007]}"#).unwrap();
+        std::fs::write(dir.path().join("config.json"), r#"{"eos_token_id": 2}"#).unwrap();
+        let ids = eos_token_ids(dir.path(), |token| (token == "<|end|>").then_some(32001));
+        assert_eq!(ids, vec![2, 32001, 32007]);
+        assert!(eos_token_ids(&dir.path().join("missing"), |_| None).is_empty());
+    }
+
+    #[test]
+    fn test_last_logits_takes_the_final_position() {
+        let logits = Array3::from_shape_vec((1, 2, 3), vec![0.0, 1.0, 2.0, 3.0, 4.0, 5.0]).unwrap();
+        assert_eq!(last_logits(logits.view().into_dyn()).unwrap(), vec![3.0, 4.0, 5.0]);
+        let logits = Array2::from_shape_vec((1, 3), vec![7.0, 8.0, 9.0]).unwrap();
+        assert_eq!(last_logits(logits.view().into_dyn()).unwrap(), vec![7.0, 8.0, 9.0]);
+        assert!(last_logits(Array3::<f32>::zeros((1, 0, 3)).view().into_dyn()).is_err());
+    }
+
+    #[test]
+    fn test_reply_ends_where_the_next_turn_starts() {
+        assert_eq!(end_of_turn("Sure, 4.\nUser: and 3+3?"), Some(8));
+        assert_eq!(end_of_turn("Users: are people"), None);
+    }
+}
@@ -0,0 +1,151 @@
+//! Ratings of assistant replies.
+//!
+//! A 👍 or 👎 (with an optional comment) is stored on the rated message, so it travels with
+//! the chat. The Stats window totals them up, and rated replies can be exported as JSONL
+//! prompt/response pairs for building fine-tuning datasets.
+
+use super::{ChatMessage, ChatSession, MessageRole};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Rating {
+    Good,
+    Poor,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Feedback {
+    pub rating: Rating,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub comment: String,
+    pub rated_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl Feedback {
+    pub fn new(rating: Rating) -> Self {
+        Self { rating, comment: String::new(), rated_at: chrono::Utc::now() }
+    }
+}
+
+/// One line of the export: a rated reply and the message it answered.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RatedPair {
+    pub prompt: String,
+    pub response: String,
+    pub rating: Rating,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub comment: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    pub session: String,
+}
+
+/// Rated replies across `sessions`, oldest chat first. Replies without a user message before
+/// them have nothing to pair with and are left out.
+pub fn rated_pairs(sessions: &[ChatSession]) -> Vec<RatedPair> {
+    let mut pairs = Vec::new();
+    for session in sessions {
+        let mut prompt: Option<&ChatMessage> = None;
+        for message in &session.messages {
+            match message.role {
+                MessageRole::User => prompt = Some(message),
+                MessageRole::Assistant => {
+                    if let (Some(prompt), Some(feedback)) = (prompt, &message.feedback) {
+                        pairs.push(RatedPair {
+                            prompt: prompt.prompt_content(),
+                            response: message.content.clone(),
+                            rating: feedback.rating,
+                            comment: feedback.comment.clone(),
+                            model: message.model_used.clone(),
+                            session: session.id.clone(),
+                        });
+                    }
+                }
+                MessageRole::System | MessageRole::Tool => {}
+            }
+        }
+    }
+    pairs
+}
+
+/// The pairs as JSON Lines.
+pub fn to_jsonl(pairs: &[RatedPair]) -> anyhow::Result<String> {
+    let mut out = String::new();
+    for pair in pairs {
+        out.push_str(&serde_json::to_string(pair)?);
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FeedbackTotals {
+    pub good: u64,
+    pub poor: u64,
+    pub commented: u64,
+    /// Assistant replies, rated or not.
+    pub replies: u64,
+}
+
+impl FeedbackTotals {
+    pub fn from_sessions(sessions: &[ChatSession]) -> Self {
+        let mut totals = Self::default();
+        for message in sessions.iter().flat_map(|s| &s.messages).filter(|m| matches!(m.role, MessageRole::Assistant)) {
+            totals.replies += 1;
+            let Some(feedback) = &message.feedback else { continue };
+            match feedback.rating {
+                Rating::Good => totals.good += 1,
+                Rating::Poor => totals.poor += 1,
+            }
+            totals.commented += u64::from(!feedback.comment.trim().is_empty());
+        }
+        totals
+    }
+
+    pub fn rated(&self) -> u64 {
+        self.good + self.poor
+    }
+
+    /// Share of rated replies rated good.
+    pub fn approval(&self) -> Option<f64> {
+        (self.rated() > 0).then(|| self.good as f64 / self.rated() as f64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(role: MessageRole, content: &str, feedback: Option<Feedback>) -> ChatMessage {
+        ChatMessage { role, feedback, ..ChatMessage::system(content) }
+    }
+
+    #[test]
+    fn test_rated_replies_pair_with_their_prompt() {
+        let commented = Feedback { comment: "Wrong year".into(), ..Feedback::new(Rating::Poor) };
+        let session = ChatSession {
+            id: "s1".into(),
+            title: "Chat".into(),
+            messages: vec![
+                message(MessageRole::Assistant, "Hello! How can I help?", Some(Feedback::new(Rating::Good))),
+                message(MessageRole::User, "When was Rust 1.0?", None),
+                message(MessageRole::Assistant, "2014", Some(commented)),
+                message(MessageRole::User, "Thanks", None),
+                message(MessageRole::Assistant, "You're welcome", None),
+            ],
+            ..Default::default()
+        };
+        let sessions = [session];
+
+        let pairs = rated_pairs(&sessions);
+        assert_eq!(pairs.len(), 1);
+        assert_eq!((pairs[0].prompt.as_str(), pairs[0].response.as_str(), pairs[0].rating), ("When was Rust 1.0?", "2014", Rating::Poor));
+        let line = to_jsonl(&pairs).unwrap();
+        assert!(line.ends_with("\"rating\":\"poor\",\"comment\":\"Wrong year\",\"session\":\"s1\"}\n"));
+
+        let totals = FeedbackTotals::from_sessions(&sessions);
+        assert_eq!(totals, FeedbackTotals { good: 1, poor: 1, commented: 1, replies: 3 });
+        assert_eq!(totals.approval(), Some(0.5));
+    }
+}
@@ -0,0 +1,177 @@
+//! What the execution providers have done on this machine.
+//!
+//! A model load tries the chosen EP first and then falls back through the others. The
+//! fallback order comes from this profile: EPs that have loaded models here go first, ones
+//! that keep failing go last, and ones whose library or driver is missing are skipped. The
+//! profile is kept in `hardware_profile.json` beside the config.
+
+use super::providers::LoadError;
+use super::ExecutionProvider;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Fallbacks in the order tried when nothing is known about this machine yet.
+pub const DEFAULT_FALLBACK_ORDER: [ExecutionProvider; 7] = [
+    ExecutionProvider::QNN,
+    ExecutionProvider::NNAPI,
+    ExecutionProvider::Cuda,
+    ExecutionProvider::DirectML,
+    ExecutionProvider::OpenVINO,
+    ExecutionProvider::CoreML,
+    ExecutionProvider::Cpu,
+];
+
+/// How one load attempt went, as far as the EP is concerned.
+#[derive(Debug, Clone, PartialEq)]
+pub enum EpOutcome {
+    Loaded,
+    Failed,
+    /// The EP can't work here at all (library, driver or toolkit missing).
+    Missing(String),
+}
+
+impl EpOutcome {
+    /// Classify a load error; `None` for errors that aren't the EP's fault, like a missing
+    /// model file or an incompatible ONNX Runtime.
+    pub fn from_load_error(error: &LoadError) -> Option<Self> {
+        match error {
+            LoadError::ExecutionProviderRegistration(m) | LoadError::CudaDriver(m) | LoadError::CudaToolkit(m) | LoadError::Cudnn(m) => {
+                Some(Self::Missing(m.clone()))
+            }
+            LoadError::SessionBuild(_)
+            | LoadError::ModelUnsupported(_)
+            | LoadError::InferenceProbeFailed(_)
+            | LoadError::Panic(_)
+            | LoadError::Unknown(_) => Some(Self::Failed),
+            LoadError::EmptyPath
+            | LoadError::FileMissing(_)
+            | LoadError::NotOnnxFile(_)
+            | LoadError::Io(_)
+            | LoadError::IntegrityCheckFailed(_)
+            | LoadError::VersionIncompatibility(_) => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EpRecord {
+    pub ep: ExecutionProvider,
+    pub successes: u32,
+    pub failures: u32,
+    /// Why the EP is unusable here, if it is.
+    #[serde(default)]
+    pub missing: Option<String>,
+    #[serde(default)]
+    pub last_success: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HardwareProfile {
+    #[serde(default)]
+    pub eps: Vec<EpRecord>,
+}
+
+impl HardwareProfile {
+    /// The saved profile, or an empty one if there is none or it can't be read.
+    pub fn load(path: &Path) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).map_err(|e| tracing::warn!("Ignoring unreadable hardware profile: {}", e)).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        crate::utils::files::write_atomic(path, &serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn record(&mut self, ep: &ExecutionProvider, outcome: EpOutcome) {
+        let record = match self.eps.iter().position(|r| &r.ep == ep) {
+            Some(i) => &mut self.eps[i],
+            None => {
+                self.eps.push(EpRecord { ep: ep.clone(), successes: 0, failures: 0, missing: None, last_success: None });
+                self.eps.last_mut().expect("just pushed")
+            }
+        };
+        match outcome {
+            EpOutcome::Loaded => {
+                record.successes += 1;
+                record.missing = None;
+                record.last_success = Some(chrono::Utc::now());
+            }
+            EpOutcome::Failed => record.failures += 1,
+            EpOutcome::Missing(reason) => {
+                record.failures += 1;
+                record.missing = Some(reason);
+            }
+        }
+    }
+
+    /// EPs to fall back to after `requested`, best first. Missing EPs are left out, except
+    /// the CPU which always works.
+    pub fn fallback_order(&self, requested: &ExecutionProvider, candidates: &[ExecutionProvider]) -> Vec<ExecutionProvider> {
+        let mut order: Vec<(i64, ExecutionProvider)> = candidates
+            .iter()
+            .filter(|ep| *ep != requested)
+            .filter_map(|ep| match self.eps.iter().find(|r| &r.ep == ep) {
+                Some(record) if record.missing.is_some() && *ep != ExecutionProvider::Cpu => None,
+                Some(record) => Some((i64::from(record.successes) - i64::from(record.failures), ep.clone())),
+                None => Some((0, ep.clone())),
+            })
+            .collect();
+        // Stable: EPs with the same score keep the default order
+        order.sort_by_key(|(score, _)| -score);
+        order.into_iter().map(|(_, ep)| ep).collect()
+    }
+
+    /// Let missing EPs be tried again, e.g. after switching to another ONNX Runtime.
+    pub fn forget_missing(&mut self) {
+        for record in &mut self.eps {
+            record.missing = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fallbacks_follow_what_worked_here() {
+        let mut profile = HardwareProfile::default();
+        assert_eq!(profile.fallback_order(&ExecutionProvider::Cuda, &DEFAULT_FALLBACK_ORDER)[..2], [ExecutionProvider::QNN, ExecutionProvider::NNAPI]);
+
+        profile.record(&ExecutionProvider::QNN, EpOutcome::Missing("QNN EP not available".into()));
+        profile.record(&ExecutionProvider::DirectML, EpOutcome::Failed);
+        profile.record(&ExecutionProvider::OpenVINO, EpOutcome::Loaded);
+        profile.record(&ExecutionProvider::Cpu, EpOutcome::Missing("odd".into()));
+        let order = profile.fallback_order(&ExecutionProvider::Cuda, &DEFAULT_FALLBACK_ORDER);
+        assert_eq!(
+            order,
+            [ExecutionProvider::OpenVINO, ExecutionProvider::NNAPI, ExecutionProvider::CoreML, ExecutionProvider::DirectML, ExecutionProvider::Cpu]
+        );
+
+        profile.forget_missing();
+        assert!(profile.fallback_order(&ExecutionProvider::Cuda, &DEFAULT_FALLBACK_ORDER).contains(&ExecutionProvider::QNN));
+    }
+
+    #[test]
+    fn test_profile_round_trips() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("hardware_profile.json");
+        assert!(HardwareProfile::load(&path).eps.is_empty());
+        let mut profile = HardwareProfile::default();
+        profile.record(&ExecutionProvider::Cuda, EpOutcome::Loaded);
+        profile.save(&path).unwrap();
+        let loaded = HardwareProfile::load(&path);
+        assert_eq!(loaded.eps[0].successes, 1);
+        assert!(loaded.eps[0].last_success.is_some());
+    }
+
+    #[test]
+    fn test_only_ep_errors_are_recorded() {
+        assert_eq!(EpOutcome::from_load_error(&LoadError::Cudnn("cuDNN 9 not found".into())), Some(EpOutcome::Missing("cuDNN 9 not found".into())));
+        assert_eq!(EpOutcome::from_load_error(&LoadError::SessionBuild("bad op".into())), Some(EpOutcome::Failed));
+        assert_eq!(EpOutcome::from_load_error(&LoadError::FileMissing("model.onnx".into())), None);
+    }
+}
@@ -1,13 +1,229 @@
 use super::*;
+use super::worker::InferenceWorkers;
+use super::response_cache::ResponseCache;
 use anyhow::Result;
 use std::sync::Arc;
 use tokio::sync::{mpsc, RwLock};
-use tokio::time::{sleep, Duration};
+use tokio::time::Duration;
+use std::time::Instant;
+
+/// Incidents kept for diagnostics.
+const MAX_INCIDENTS: usize = 50;
+
+/// Retries of a provider after a transient error before it counts as failed.
+pub const MAX_TRANSIENT_RETRIES: u32 = 3;
+
+/// Wait before the first retry; doubled for each one after.
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(250);
+
+/// How long a provider that failed is passed over before it is tried again.
+pub const UNHEALTHY_COOLDOWN: Duration = Duration::from_secs(5 * 60);
+
+/// Whether `error` looks like a passing condition (a busy device, a timeout, a dropped
+/// connection) that the same provider may get past on a second try.
+pub fn is_transient_error(error: &anyhow::Error) -> bool {
+    const MARKERS: [&str; 8] = [
+        "timed out",
+        "timeout",
+        "temporarily",
+        "try again",
+        "resource exhausted",
+        "busy",
+        "interrupted",
+        "connection reset",
+    ];
+    let message = format!("{error:#}").to_lowercase();
+    MARKERS.iter().any(|marker| message.contains(marker))
+}
+
+/// A provider shared between the engine and the generations running on it.
+type SharedProvider = Arc<std::sync::Mutex<Box<dyn AIProvider + Send + Sync>>>;
+
+/// Identifies a registered provider. Ids are never reused, so one held across a removal can't
+/// end up pointing at another provider.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ProviderId(u64);
+
+impl std::fmt::Display for ProviderId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+/// A registered provider and what the engine needs to know about it without taking its lock,
+/// which a running generation holds.
+struct ProviderSlot {
+    id: ProviderId,
+    provider: SharedProvider,
+    name: String,
+    supports_constraints: bool,
+    is_demo: bool,
+    /// Whether its model file looks like a code model, for [`GenerationOverrides::prefer_code_model`].
+    is_code_model: bool,
+    health: ProviderHealth,
+}
+
+impl ProviderSlot {
+    fn new(id: ProviderId, provider: Box<dyn AIProvider + Send + Sync>) -> Self {
+        Self {
+            id,
+            name: provider.name().to_string(),
+            supports_constraints: provider.supports_constraints(),
+            is_demo: provider.as_any().is::<BasicDemoProvider>(),
+            is_code_model: provider
+                .get_model_info()
+                .ok()
+                .and_then(|info| info.get("model_path").cloned())
+                .is_some_and(|path| models::ModelType::guess(std::path::Path::new(&path)) == models::ModelType::CodeModel),
+            provider: Arc::new(std::sync::Mutex::new(provider)),
+            health: ProviderHealth::Healthy,
+        }
+    }
+
+    /// Whether the provider can take requests; one busy with a generation counts as available.
+    fn is_available(&self) -> bool {
+        self.provider.try_lock().map_or(true, |p| p.is_available())
+    }
+}
 
 pub struct InferenceEngine {
-    providers: Vec<Box<dyn AIProvider + Send + Sync>>,
-    active_provider: Option<usize>,
+    providers: Vec<ProviderSlot>,
+    active_provider: Option<ProviderId>,
+    next_id: u64,
     config: Arc<RwLock<InferenceConfig>>,
+    incidents: Vec<ProviderIncident>,
+    /// Incidents the UI has not picked up yet.
+    unreported: usize,
+    /// What the provider that answered last was given.
+    last_trace: Option<GenerationTrace>,
+    /// Replies to requests seen before; empty and unused unless turned on.
+    response_cache: ResponseCache,
+}
+
+/// One request bound to a provider. Jobs are created and settled under the engine's lock but
+/// [run](Self::run) without it, so other chats, model info and provider switches aren't held
+/// up for the length of a generation.
+pub struct GenerationJob {
+    id: ProviderId,
+    /// Kept here as the provider may be removed while the job runs.
+    name: String,
+    provider: SharedProvider,
+    context: Vec<ChatMessage>,
+    overrides: GenerationOverrides,
+    /// Label and compiled form of the output constraint, checked against the reply.
+    constraint: Option<(&'static str, constraint::Constraint)>,
+    /// Identifies the request in the response cache.
+    cache_key: u64,
+}
+
+impl GenerationJob {
+    /// Run the provider, waiting for any other generation on it to finish first. `started` is
+    /// called once the provider is ours. Returns the reply and the provider's own trace.
+    pub fn run(&self, started: impl FnOnce()) -> Result<(String, Option<GenerationTrace>)> {
+        let mut provider = self.provider.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        started();
+        // Turns a panic in the provider's Rust code into an error so the engine can fail over.
+        // Only builds that unwind get here: the release profile aborts on panic, and a crash
+        // inside ONNX Runtime's native code ends the process either way.
+        let result = crate::utils::crash::catch_unwind(|| provider.generate_response_with(&self.context, &self.overrides))
+            .unwrap_or_else(|_| Err(anyhow::anyhow!("{} crashed during generation", provider.name())));
+        result.map(|text| (text, provider.last_trace()))
+    }
+}
+
+/// A job's outcome once the engine has recorded it.
+pub enum Settled {
+    Done { text: String, trace: GenerationTrace },
+    /// The provider failed and the engine switched; run this job on the fallback.
+    Retry(GenerationJob),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum ProviderHealth {
+    Healthy,
+    /// Failed during generation; skipped when choosing a fallback until
+    /// [`UNHEALTHY_COOLDOWN`] has passed.
+    Unhealthy { reason: String, since: chrono::DateTime<chrono::Utc> },
+}
+
+impl ProviderHealth {
+    /// Whether the provider may be picked at `now`: healthy, or unhealthy for longer than
+    /// the cooldown, so that one failure doesn't retire it for the rest of the session.
+    pub fn is_usable(&self, now: chrono::DateTime<chrono::Utc>) -> bool {
+        match self {
+            ProviderHealth::Healthy => true,
+            ProviderHealth::Unhealthy { since, .. } => {
+                chrono::Duration::from_std(UNHEALTHY_COOLDOWN).is_ok_and(|cooldown| now - *since >= cooldown)
+            }
+        }
+    }
+}
+
+/// A provider failing mid-conversation and what the engine switched to.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProviderIncident {
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    pub provider: String,
+    pub error: String,
+    /// Provider that took over, if any was left.
+    pub fallback: Option<String>,
+    /// The fallback is the demo provider, i.e. no real model is answering any more.
+    pub degraded_to_demo: bool,
+}
+
+/// The exact input behind one reply, shown in the message inspector.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GenerationTrace {
+    pub provider: String,
+    /// Execution provider the model ran on; `None` for providers that don't use ONNX Runtime.
+    #[serde(default)]
+    pub execution_provider: Option<String>,
+    /// Prompt text after context preparation and chat templating.
+    pub prompt: String,
+    /// Token ids fed to the model; empty when the provider doesn't tokenize.
+    #[serde(default)]
+    pub token_ids: Vec<i64>,
+    pub temperature: f32,
+    pub top_p: f32,
+    #[serde(default)]
+    pub top_k: u32,
+    pub max_tokens: u32,
+    #[serde(default, skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+    pub logit_bias: std::collections::BTreeMap<String, f32>,
+    /// Tokens in the reply; `None` unless the provider decoded it token by token.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reply_tokens: Option<u32>,
+    /// Why the reply ended; `None` unless the provider decoded it token by token.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finish_reason: Option<FinishReason>,
+}
+
+/// Why a decoded reply ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FinishReason {
+    /// The model ended it: an end-of-sequence token, or the start of the next turn.
+    Stop,
+    /// It was cut off at `max_tokens`.
+    Length,
+}
+
+impl GenerationTrace {
+    /// A trace built from the prepared context and the engine's sampler settings.
+    pub fn from_context(provider: &str, context: &[ChatMessage], config: &InferenceConfig) -> Self {
+        Self {
+            provider: provider.to_string(),
+            execution_provider: None,
+            prompt: tokenizer::render_chat_prompt(context),
+            token_ids: Vec::new(),
+            temperature: config.temperature,
+            top_p: config.top_p,
+            top_k: config.top_k,
+            max_tokens: config.max_tokens,
+            logit_bias: config.logit_bias.clone(),
+            reply_tokens: None,
+            finish_reason: None,
+        }
+    }
 }
 
 pub struct BasicDemoProvider;
@@ -107,37 +323,316 @@ impl InferenceEngine {
         Self {
             providers: Vec::new(),
             active_provider: None,
+            next_id: 0,
             config: Arc::new(RwLock::new(InferenceConfig::default())),
+            incidents: Vec::new(),
+            unreported: 0,
+            last_trace: None,
+            response_cache: ResponseCache::default(),
         }
     }
 
     pub async fn add_provider(&mut self, provider: Box<dyn AIProvider + Send + Sync>) {
-        self.providers.push(provider);
+        self.add_provider_sync(provider);
     }
 
-    /// Synchronous helper to add a provider and return its index
-    pub fn add_provider_sync(&mut self, provider: Box<dyn AIProvider + Send + Sync>) -> usize {
-        self.providers.push(provider);
-        self.providers.len() - 1
+    /// Synchronous helper to add a provider and return its id
+    pub fn add_provider_sync(&mut self, provider: Box<dyn AIProvider + Send + Sync>) -> ProviderId {
+        let id = ProviderId(self.next_id);
+        self.next_id += 1;
+        self.providers.push(ProviderSlot::new(id, provider));
+        id
     }
 
-    pub async fn set_active_provider(&mut self, index: usize) -> Result<()> {
-        if index >= self.providers.len() {
-            return Err(anyhow::anyhow!("Provider index out of bounds"));
+    /// Put `provider` in place of the one registered as `id`, keeping the id and, if it was
+    /// active, the selection. The old provider is dropped as for [`remove_provider`](Self::remove_provider).
+    pub fn replace_provider(&mut self, id: ProviderId, provider: Box<dyn AIProvider + Send + Sync>) -> Result<()> {
+        let slot = self.slot_mut(id).ok_or_else(|| anyhow::anyhow!("No provider {id}"))?;
+        *slot = ProviderSlot::new(id, provider);
+        // A new model under the same id answers differently
+        self.response_cache.clear();
+        Ok(())
+    }
+
+    /// Unregister a provider. It is dropped, freeing its model and ONNX Runtime session, as
+    /// soon as any generation still running on it finishes. Removing the active provider
+    /// leaves none active, so the next request falls back to the demo.
+    pub fn remove_provider(&mut self, id: ProviderId) -> Result<()> {
+        let pos = self.providers.iter().position(|p| p.id == id).ok_or_else(|| anyhow::anyhow!("No provider {id}"))?;
+        self.providers.remove(pos);
+        if self.active_provider == Some(id) {
+            self.active_provider = None;
         }
-        self.active_provider = Some(index);
         Ok(())
     }
 
+    pub fn has_provider(&self, id: ProviderId) -> bool {
+        self.slot(id).is_some()
+    }
+
+    fn slot(&self, id: ProviderId) -> Option<&ProviderSlot> {
+        self.providers.iter().find(|p| p.id == id)
+    }
+
+    fn slot_mut(&mut self, id: ProviderId) -> Option<&mut ProviderSlot> {
+        self.providers.iter_mut().find(|p| p.id == id)
+    }
+
+    /// Name and health of every provider.
+    pub fn provider_health(&self) -> Vec<(String, ProviderHealth)> {
+        self.providers.iter().map(|p| (p.name.clone(), p.health.clone())).collect()
+    }
+
+    /// All recorded incidents, oldest first.
+    pub fn incidents(&self) -> &[ProviderIncident] {
+        &self.incidents
+    }
+
+    /// Incidents recorded since the last call, for notifying the user.
+    pub fn take_new_incidents(&mut self) -> Vec<ProviderIncident> {
+        let new = self.incidents[self.incidents.len() - self.unreported..].to_vec();
+        self.unreported = 0;
+        new
+    }
+
+    /// Trace of the most recent successful generation, if it hasn't been taken yet.
+    pub fn take_last_trace(&mut self) -> Option<GenerationTrace> {
+        self.last_trace.take()
+    }
+
+    /// Bind a request to the active provider, adding the demo provider if none is loaded.
+    /// Fails if the request's output constraint is invalid or the provider can't follow it.
+    pub fn begin_generation(&mut self, messages: &[ChatMessage], overrides: &GenerationOverrides) -> Result<GenerationJob> {
+        let constraint = overrides.constraint.as_ref().map(|c| c.compile().map(|compiled| (c.label(), compiled))).transpose()?;
+        self.reprobe_recovered();
+        let code_model = if overrides.prefer_code_model { self.code_provider() } else { None };
+        let id = match code_model.or(self.active_provider) {
+            Some(id) => id,
+            None => {
+                let id = self.add_provider_sync(Box::new(BasicDemoProvider));
+                self.active_provider = Some(id);
+                id
+            }
+        };
+        let slot = self.slot(id).expect("the active provider is registered");
+        if let Some((format, _)) = &constraint {
+            if !slot.supports_constraints {
+                anyhow::bail!("{} can't constrain its output to {format}; load a model that supports it", slot.name);
+            }
+        }
+        let context = self.prepare_context(messages);
+        Ok(GenerationJob {
+            id,
+            name: slot.name.clone(),
+            provider: slot.provider.clone(),
+            cache_key: response_cache::key(id, &context, overrides),
+            context,
+            overrides: overrides.clone(),
+            constraint,
+        })
+    }
+
+    /// Keep up to [`response_cache::DEFAULT_ENTRIES`] replies and answer identical requests
+    /// from them, or stop and forget them.
+    pub fn set_response_cache(&mut self, enabled: bool) {
+        if enabled != self.response_cache.is_enabled() {
+            self.response_cache = ResponseCache::new(if enabled { response_cache::DEFAULT_ENTRIES } else { 0 });
+        }
+    }
+
+    pub fn clear_response_cache(&mut self) {
+        self.response_cache.clear();
+    }
+
+    /// The reply `job` got before, if the cache is on and has it.
+    pub fn cached_reply(&mut self, job: &GenerationJob) -> Option<(String, GenerationTrace)> {
+        let (text, trace) = self.response_cache.get(job.cache_key)?.clone();
+        tracing::info!("Answering from the response cache ({})", job.name);
+        self.last_trace = Some(trace.clone());
+        Some((text, trace))
+    }
+
+    /// Record how `job` went. A failed provider is marked unhealthy and the engine switches to
+    /// the next healthy one (adding the demo provider as a last resort); the job is handed back
+    /// for the fallback to run.
+    pub fn settle(&mut self, job: GenerationJob, result: Result<(String, Option<GenerationTrace>)>) -> Result<Settled> {
+        let error = match result {
+            Ok((text, trace)) => {
+                // A fallback provider may not honour the constraint
+                if let Some((format, compiled)) = &job.constraint {
+                    if !compiled.is_match(&text) {
+                        anyhow::bail!("The reply doesn't match the requested {format}");
+                    }
+                }
+                if let Some(slot) = self.slot_mut(job.id) {
+                    slot.health = ProviderHealth::Healthy;
+                }
+                let trace = trace.unwrap_or_else(|| {
+                    let config = self.config.try_read().map(|c| job.overrides.apply(&c)).unwrap_or_default();
+                    GenerationTrace::from_context(&job.name, &job.context, &config)
+                });
+                self.last_trace = Some(trace.clone());
+                self.response_cache.insert(job.cache_key, text.clone(), trace.clone());
+                return Ok(Settled::Done { text, trace });
+            }
+            Err(e) => e,
+        };
+
+        tracing::warn!("Provider {} failed during generation: {}", job.name, error);
+        if let Some(slot) = self.slot_mut(job.id) {
+            slot.health = ProviderHealth::Unhealthy { reason: error.to_string(), since: chrono::Utc::now() };
+        }
+        let fallback = self.fallback_for(job.id).and_then(|id| self.slot(id)).map(|slot| (slot.id, slot.name.clone(), slot.provider.clone(), slot.is_demo));
+        self.record_incident(ProviderIncident {
+            timestamp: chrono::Utc::now(),
+            provider: job.name.clone(),
+            error: error.to_string(),
+            fallback: fallback.as_ref().map(|(_, name, ..)| name.clone()),
+            degraded_to_demo: fallback.as_ref().is_some_and(|(.., is_demo)| *is_demo),
+        });
+        match fallback {
+            Some((id, name, provider, _)) => {
+                self.active_provider = Some(id);
+                Ok(Settled::Retry(GenerationJob { id, name, provider, ..job }))
+            }
+            None => Err(error),
+        }
+    }
+
+    /// Run the active provider with failover, all under `&mut self`.
+    fn generate_with_failover(&mut self, messages: &[ChatMessage], overrides: &GenerationOverrides) -> Result<(String, String)> {
+        let mut job = self.begin_generation(messages, overrides)?;
+        if let Some((text, trace)) = self.cached_reply(&job) {
+            return Ok((trace.provider, text));
+        }
+        loop {
+            let result = job.run(|| ());
+            match self.settle(job, result)? {
+                Settled::Done { text, trace } => return Ok((trace.provider, text)),
+                Settled::Retry(next) => job = next,
+            }
+        }
+    }
+
+    /// Generate a reply to `messages` on `workers`, taking `engine`'s lock only to pick a
+    /// provider and to record the outcome. `started` is called once the provider is free and
+    /// working on this request. A transient error is retried on the same provider with
+    /// backoff, calling `on_retry` with the attempt number, before it fails over. Returns the
+    /// streamed reply and its trace.
+    pub async fn generate_detached(
+        engine: &RwLock<Self>,
+        workers: &InferenceWorkers,
+        messages: &[ChatMessage],
+        overrides: &GenerationOverrides,
+        started: impl FnOnce() + Send + 'static,
+        on_retry: impl Fn(u32) + Send,
+    ) -> Result<(mpsc::Receiver<String>, GenerationTrace)> {
+        let (mut job, cached) = {
+            let mut engine = engine.write().await;
+            let job = engine.begin_generation(messages, overrides)?;
+            let cached = engine.cached_reply(&job);
+            (job, cached)
+        };
+        if let Some((text, trace)) = cached {
+            started();
+            return Ok((stream_text(text), trace));
+        }
+        let mut started = Some(started);
+        let mut retries = 0;
+        loop {
+            let on_start = started.take();
+            let (job_back, result) = workers.run(move || {
+                let result = job.run(|| on_start.into_iter().for_each(|f| f()));
+                (job, result)
+            }).await?;
+            if let Err(error) = &result {
+                if retries < MAX_TRANSIENT_RETRIES && is_transient_error(error) {
+                    retries += 1;
+                    tracing::info!("Retrying {} after a transient error ({}/{}): {}", job_back.name, retries, MAX_TRANSIENT_RETRIES, error);
+                    on_retry(retries);
+                    tokio::time::sleep(RETRY_BASE_DELAY * 2u32.pow(retries - 1)).await;
+                    job = job_back;
+                    continue;
+                }
+            }
+            retries = 0;
+            match engine.write().await.settle(job_back, result)? {
+                Settled::Done { text, trace } => return Ok((stream_text(text), trace)),
+                Settled::Retry(next) => job = next,
+            }
+        }
+    }
+
+    /// Once the engine has degraded to the demo provider, switch back to the newest real
+    /// provider whose cooldown is over; if it fails again, [`Self::settle`] fails over again.
+    fn reprobe_recovered(&mut self) {
+        if !self.active_provider.and_then(|id| self.slot(id)).is_some_and(|p| p.is_demo) {
+            return;
+        }
+        let now = chrono::Utc::now();
+        if let Some(recovered) = self.providers.iter().rev().find(|p| !p.is_demo && p.health.is_usable(now) && p.is_available()) {
+            tracing::info!("Trying {} again after its cooldown", recovered.name);
+            self.active_provider = Some(recovered.id);
+        }
+    }
+
+    /// Next usable provider other than `failed`, preferring real models over the demo.
+    fn fallback_for(&mut self, failed: ProviderId) -> Option<ProviderId> {
+        let now = chrono::Utc::now();
+        let candidates: Vec<&ProviderSlot> = self.providers
+            .iter()
+            .filter(|p| p.id != failed && p.health.is_usable(now) && p.is_available())
+            .collect();
+        if let Some(real) = candidates.iter().rev().find(|p| !p.is_demo) {
+            return Some(real.id);
+        }
+        if let Some(demo) = candidates.first() {
+            return Some(demo.id);
+        }
+        if self.slot(failed).is_some_and(|p| p.is_demo) {
+            return None;
+        }
+        Some(self.add_provider_sync(Box::new(BasicDemoProvider)))
+    }
+
+    fn record_incident(&mut self, incident: ProviderIncident) {
+        self.incidents.push(incident);
+        self.unreported = (self.unreported + 1).min(MAX_INCIDENTS);
+        if self.incidents.len() > MAX_INCIDENTS {
+            self.incidents.remove(0);
+        }
+    }
+
+    pub async fn set_active_provider(&mut self, id: ProviderId) -> Result<()> {
+        self.set_active_provider_sync(id)
+    }
+
     /// Synchronous helper to set the active provider
-    pub fn set_active_provider_sync(&mut self, index: usize) -> Result<()> {
-        if index >= self.providers.len() {
-            return Err(anyhow::anyhow!("Provider index out of bounds"));
+    pub fn set_active_provider_sync(&mut self, id: ProviderId) -> Result<()> {
+        if self.slot(id).is_none() {
+            return Err(anyhow::anyhow!("No provider {id}"));
         }
-        self.active_provider = Some(index);
+        self.active_provider = Some(id);
         Ok(())
     }
 
+    /// Whether the active provider can follow [`GenerationOverrides::constraint`].
+    pub fn can_constrain_output(&self) -> bool {
+        self.active_provider.and_then(|id| self.slot(id)).is_some_and(|p| p.supports_constraints)
+    }
+
+    /// A usable provider running a code model; the active one if it is one.
+    fn code_provider(&self) -> Option<ProviderId> {
+        let now = chrono::Utc::now();
+        let mut code_models = self.providers.iter().filter(|p| p.is_code_model && p.health.is_usable(now) && p.is_available());
+        code_models.clone().find(|p| Some(p.id) == self.active_provider).or_else(|| code_models.next()).map(|p| p.id)
+    }
+
+    /// Whether a code model is loaded that code prompts can be routed to.
+    pub fn has_code_model(&self) -> bool {
+        self.code_provider().is_some()
+    }
+
     /// Check if an active provider is set
     pub fn has_active_provider(&self) -> bool {
         self.active_provider.is_some()
@@ -146,27 +641,15 @@ impl InferenceEngine {
     pub async fn get_available_providers(&self) -> Vec<String> {
         self.providers
             .iter()
-            .enumerate()
-            .filter_map(|(i, provider)| {
-                if provider.is_available() {
-                    Some(format!("{}: {}", i, provider.name()))
-                } else {
-                    None
-                }
-            })
+            .filter(|p| p.is_available())
+            .map(|p| format!("{}: {}", p.id, p.name))
             .collect()
     }
 
     pub async fn generate_response(&mut self, messages: &[ChatMessage]) -> Result<ChatMessage> {
-        let provider_idx = self.active_provider
-            .ok_or_else(|| anyhow::anyhow!("No active provider set"))?;
-
         let start_time = std::time::Instant::now();
         
-        let response_content = {
-            let provider = &mut self.providers[provider_idx];
-            provider.generate_response(messages)?
-        };
+        let (provider, response_content) = self.generate_with_failover(messages, &GenerationOverrides::default())?;
         
         let inference_time = start_time.elapsed().as_secs_f64();
 
@@ -175,11 +658,24 @@ impl InferenceEngine {
             content: response_content,
             role: MessageRole::Assistant,
             timestamp: chrono::Utc::now(),
-            model_used: Some(self.providers[provider_idx].name().to_string()),
+            model_used: Some(provider),
             inference_time: Some(inference_time),
+            images: Vec::new(),
+            trace: None,
+            reply_to: None,
+            feedback: None,
+            edited_at: None,
         })
     }
 
+    /// Apply context budgets (tool result compaction) before messages reach a provider.
+    fn prepare_context(&self, messages: &[ChatMessage]) -> Vec<ChatMessage> {
+        let max_chars = self.config.try_read()
+            .map(|c| c.tool_result_max_chars)
+            .unwrap_or(context::DEFAULT_TOOL_RESULT_MAX_CHARS);
+        context::prepare_context(messages, max_chars)
+    }
+
     pub async fn update_config(&self, config: InferenceConfig) {
         let mut current_config = self.config.write().await;
         *current_config = config;
@@ -189,84 +685,462 @@ impl InferenceEngine {
         self.config.read().await.clone()
     }
 
-    /// Generate a response and stream it back in chunks over a channel.
-    /// This scaffolds streaming by chunking a full response; later we can replace
-    /// this with true token-by-token streaming from the provider.
-    pub fn generate_response_stream(
-        &mut self,
-        messages: &[ChatMessage],
-        chunk_chars: usize,
-        delay_ms: u64,
-    ) -> Result<mpsc::Receiver<String>> {
-        let provider_idx = self
-            .active_provider
-            .ok_or_else(|| anyhow::anyhow!("No active provider set"))?;
-
-        // Generate the full response synchronously to avoid threading the provider
-        let response_content = {
-            let provider = &mut self.providers[provider_idx];
-            provider.generate_response(messages)?
-        };
-
-        let (tx, rx) = mpsc::channel(32);
+    /// Generate a response and stream it back over a channel.
+    /// Output is emitted as it becomes available and coalesced by [`ChunkBatcher`], so there is no
+    /// artificial pacing: fast providers appear instantly, slow ones flush token by token.
+    pub fn generate_response_stream(&mut self, messages: &[ChatMessage]) -> Result<mpsc::Receiver<String>> {
+        self.generate_response_stream_with(messages, &GenerationOverrides::default())
+    }
 
-        // Stream the response in small chunks to simulate token streaming
-        tokio::spawn(async move {
-            let mut buf = String::new();
-            let mut count = 0usize;
+    /// [`generate_response_stream`](Self::generate_response_stream) with settings for this
+    /// request only; the engine's config is left as it is.
+    pub fn generate_response_stream_with(&mut self, messages: &[ChatMessage], overrides: &GenerationOverrides) -> Result<mpsc::Receiver<String>> {
+        let (_, response_content) = self.generate_with_failover(messages, overrides)?;
+        Ok(stream_text(response_content))
+    }
+}
 
-            for ch in response_content.chars() {
-                buf.push(ch);
-                count += 1;
+/// Stream a finished reply through a [`ChunkBatcher`].
+fn stream_text(text: String) -> mpsc::Receiver<String> {
+    let (pieces_tx, pieces) = mpsc::channel(32);
+    tokio::spawn(async move {
+        for piece in text.split_inclusive(char::is_whitespace) {
+            if pieces_tx.send(piece.to_string()).await.is_err() {
+                return; // receiver dropped
+            }
+        }
+    });
+    batch_pieces(pieces)
+}
 
-                if count >= chunk_chars {
-                    if tx.send(buf.clone()).await.is_err() {
-                        return; // receiver dropped
-                    }
-                    buf.clear();
-                    count = 0;
-                    if delay_ms > 0 {
-                        sleep(Duration::from_millis(delay_ms)).await;
+/// Coalesce `pieces` with a [`ChunkBatcher`], flushing a batch once its first piece has waited
+/// [`ChunkBatcher::max_wait`] even if no further piece arrives to trigger it.
+fn batch_pieces(mut pieces: mpsc::Receiver<String>) -> mpsc::Receiver<String> {
+    let (tx, rx) = mpsc::channel(32);
+    tokio::spawn(async move {
+        let mut batcher = ChunkBatcher::default();
+        loop {
+            let piece = match batcher.deadline() {
+                Some(deadline) => tokio::select! {
+                    piece = pieces.recv() => piece,
+                    _ = tokio::time::sleep_until(deadline.into()) => {
+                        if let Some(chunk) = batcher.take() {
+                            if tx.send(chunk).await.is_err() {
+                                return;
+                            }
+                        }
+                        continue;
                     }
+                },
+                None => pieces.recv().await,
+            };
+            let Some(piece) = piece else { break };
+            if let Some(chunk) = batcher.push(&piece) {
+                if tx.send(chunk).await.is_err() {
+                    return; // receiver dropped
                 }
             }
+        }
+        if let Some(chunk) = batcher.finish() {
+            let _ = tx.send(chunk).await;
+        }
+    });
+    rx
+}
 
-            if !buf.is_empty() {
-                let _ = tx.send(buf).await;
-            }
+/// Adaptive batching for streamed output.
+///
+/// Pieces are coalesced until either one frame's worth of time has passed since the last flush
+/// or the batch grows large. Tokens arriving slower than a frame therefore flush individually,
+/// while bursts are grouped so the UI isn't flooded with tiny updates. A batch whose first piece
+/// has waited `max_wait` is due even if nothing else arrives; [`Self::deadline`] says when, for
+/// a caller to flush it with [`Self::take`].
+pub struct ChunkBatcher {
+    pending: String,
+    last_flush: Instant,
+    /// When the oldest piece in `pending` arrived.
+    first_pending: Option<Instant>,
+    max_latency: Duration,
+    max_wait: Duration,
+    max_batch_chars: usize,
+}
+
+impl Default for ChunkBatcher {
+    fn default() -> Self {
+        Self::new(Duration::from_millis(16), 512)
+    }
+}
+
+impl ChunkBatcher {
+    /// Longest a piece waits in a batch by default.
+    pub const MAX_WAIT: Duration = Duration::from_millis(50);
+
+    pub fn new(max_latency: Duration, max_batch_chars: usize) -> Self {
+        Self { pending: String::new(), last_flush: Instant::now(), first_pending: None, max_latency, max_wait: Self::MAX_WAIT, max_batch_chars }
+    }
+
+    /// The same batcher with pieces held for at most `max_wait`.
+    pub fn with_max_wait(self, max_wait: Duration) -> Self {
+        Self { max_wait, ..self }
+    }
+
+    /// Add a piece; returns a chunk when it is time to flush.
+    pub fn push(&mut self, piece: &str) -> Option<String> {
+        self.pending.push_str(piece);
+        let first = *self.first_pending.get_or_insert_with(Instant::now);
+        if self.pending.len() >= self.max_batch_chars || self.last_flush.elapsed() >= self.max_latency || first.elapsed() >= self.max_wait {
+            self.take()
+        } else {
+            None
+        }
+    }
+
+    /// When the batch held now must be flushed, if there is one.
+    pub fn deadline(&self) -> Option<Instant> {
+        self.first_pending.map(|first| first + self.max_wait)
+    }
+
+    /// Flush the batch now, if there is anything in it.
+    pub fn take(&mut self) -> Option<String> {
+        self.first_pending = None;
+        self.last_flush = Instant::now();
+        (!self.pending.is_empty()).then(|| std::mem::take(&mut self.pending))
+    }
+
+    /// Flush whatever is left at the end of the stream.
+    pub fn finish(self) -> Option<String> {
+        (!self.pending.is_empty()).then_some(self.pending)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_batcher_coalesces_bursts() {
+        let mut batcher = ChunkBatcher::new(Duration::from_secs(60), 10);
+        assert_eq!(batcher.push("abc "), None);
+        assert_eq!(batcher.push("defgh "), Some("abc defgh ".to_string()));
+        assert_eq!(batcher.push("tail"), None);
+        assert_eq!(batcher.finish(), Some("tail".to_string()));
+    }
+
+    struct FailingProvider;
+
+    impl AIProvider for FailingProvider {
+        fn name(&self) -> &str { "Failing" }
+        fn is_available(&self) -> bool { true }
+        fn generate_response(&mut self, _messages: &[ChatMessage]) -> Result<String> {
+            Err(anyhow::anyhow!("CUDA out of memory"))
+        }
+        fn get_model_info(&self) -> Result<std::collections::HashMap<String, String>> { Ok(Default::default()) }
+        fn as_any(&self) -> &dyn std::any::Any { self }
+    }
+
+    #[tokio::test]
+    async fn test_failover_to_demo_marks_provider_unhealthy() {
+        let mut engine = InferenceEngine::new();
+        let id = engine.add_provider_sync(Box::new(FailingProvider));
+        engine.set_active_provider_sync(id).unwrap();
+        let message = ChatMessage {
+            id: "1".into(),
+            content: "hello".into(),
+            role: MessageRole::User,
+            timestamp: chrono::Utc::now(),
+            model_used: None,
+            inference_time: None,
+            images: Vec::new(),
+            trace: None,
+            reply_to: None,
+            feedback: None,
+            edited_at: None,
+        };
+
+        let reply = engine.generate_response(&[message]).await.unwrap();
+        assert_eq!(reply.model_used.as_deref(), Some("Intelligent Demo Mode"));
+        assert!(matches!(engine.provider_health()[0].1, ProviderHealth::Unhealthy { .. }));
+        let incidents = engine.take_new_incidents();
+        assert_eq!(incidents.len(), 1);
+        assert!(incidents[0].degraded_to_demo);
+        assert!(engine.take_new_incidents().is_empty());
+
+        // The trace comes from the provider that answered, with the engine's sampler settings
+        let trace = engine.take_last_trace().unwrap();
+        assert_eq!(trace.provider, "Intelligent Demo Mode");
+        assert_eq!(trace.prompt, "User: hello\nAssistant: ");
+        assert_eq!(trace.max_tokens, InferenceConfig::default().max_tokens);
+        assert!(engine.take_last_trace().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_overrides_apply_to_one_request_only() {
+        let mut engine = InferenceEngine::new();
+        let id = engine.add_provider_sync(Box::new(BasicDemoProvider));
+        engine.set_active_provider_sync(id).unwrap();
+        let message = ChatMessage {
+            id: "1".into(),
+            content: "hello".into(),
+            role: MessageRole::User,
+            timestamp: chrono::Utc::now(),
+            model_used: None,
+            inference_time: None,
+            images: Vec::new(),
+            trace: None,
+            reply_to: None,
+            feedback: None,
+            edited_at: None,
+        };
+
+        let overrides = GenerationOverrides {
+            max_tokens: ResponseLength::Short.max_tokens(),
+            temperature: Some(1.5),
+            top_k: Some(40),
+            logit_bias: [("sorry".to_string(), sampler::BAN_BIAS)].into(),
+            ..Default::default()
+        };
+        engine.generate_response_stream_with(std::slice::from_ref(&message), &overrides).unwrap();
+        let trace = engine.take_last_trace().unwrap();
+        assert_eq!((trace.max_tokens, trace.temperature, trace.top_k), (ResponseLength::SHORT_TOKENS, 1.5, 40));
+        assert_eq!(trace.top_p, InferenceConfig::default().top_p);
+        assert_eq!(trace.logit_bias.get("sorry"), Some(&sampler::BAN_BIAS));
+        assert_eq!(engine.get_config().await.max_tokens, InferenceConfig::default().max_tokens);
+
+        engine.generate_response_stream(&[message]).unwrap();
+        let trace = engine.take_last_trace().unwrap();
+        assert_eq!((trace.max_tokens, trace.top_k), (InferenceConfig::default().max_tokens, 0));
+    }
+
+    #[test]
+    fn test_constraint_needs_a_capable_provider() {
+        let mut engine = InferenceEngine::new();
+        let id = engine.add_provider_sync(Box::new(BasicDemoProvider));
+        engine.set_active_provider_sync(id).unwrap();
+        let overrides = GenerationOverrides { constraint: Some(constraint::OutputConstraint::Json), ..Default::default() };
+        let error = engine.generate_response_stream_with(&[], &overrides).unwrap_err();
+        assert!(error.to_string().contains("can't constrain"));
+        assert!(engine.take_last_trace().is_none());
+
+        let invalid = GenerationOverrides { constraint: Some(constraint::OutputConstraint::Regex("(".into())), ..Default::default() };
+        assert!(engine.generate_response_stream_with(&[], &invalid).is_err());
+    }
+
+    /// Answers once the test lets it.
+    struct GatedProvider(std::sync::Mutex<std::sync::mpsc::Receiver<()>>);
+
+    impl AIProvider for GatedProvider {
+        fn name(&self) -> &str { "Gated" }
+        fn is_available(&self) -> bool { true }
+        fn generate_response(&mut self, _messages: &[ChatMessage]) -> Result<String> {
+            self.0.lock().unwrap().recv()?;
+            Ok("done".into())
+        }
+        fn get_model_info(&self) -> Result<std::collections::HashMap<String, String>> { Ok(Default::default()) }
+        fn as_any(&self) -> &dyn std::any::Any { self }
+    }
+
+    #[tokio::test]
+    async fn test_engine_is_unlocked_while_the_provider_runs() {
+        let (gate, gated) = std::sync::mpsc::channel();
+        let engine = Arc::new(RwLock::new(InferenceEngine::new()));
+        {
+            let mut engine = engine.write().await;
+            let id = engine.add_provider_sync(Box::new(GatedProvider(gated.into())));
+            engine.set_active_provider_sync(id).unwrap();
+        }
+        let workers = InferenceWorkers::new(1);
+        let (started_tx, started_rx) = tokio::sync::oneshot::channel();
+        let task_engine = engine.clone();
+        let generation = tokio::spawn(async move {
+            InferenceEngine::generate_detached(&task_engine, &workers, &[], &GenerationOverrides::default(), move || {
+                let _ = started_tx.send(());
+            }, |_| ()).await
         });
 
-        Ok(rx)
-    }
-
-    /// Placeholder: generate streaming using logits sampling (future real logits extraction)
-    pub fn generate_response_stream_sampled(&mut self, messages: &[ChatMessage], max_tokens: usize, delay_ms: u64) -> Result<mpsc::Receiver<String>> {
-        use crate::ai::sampler::{LogitsSampler, SamplerConfig, SamplingStrategy};
-        let _provider_idx = self.active_provider.ok_or_else(|| anyhow::anyhow!("No active provider set"))?;
-    let mut sampler = LogitsSampler::new(SamplerConfig { temperature: 0.8, strategy: SamplingStrategy::Greedy });
-        let vocab = ["the","rust","ai","model","is","ready","and","responding","to","your","message","now","!","assistant"];
-        let (tx, rx) = mpsc::channel(32);
-    let _base_prompt = messages.iter().filter(|m| matches!(m.role, MessageRole::User)).map(|m| &m.content).last().cloned().unwrap_or_default();
-        // Pre-generate tokens synchronously (not realistic but keeps sampler off async task)
-        let mut generated_tokens: Vec<String> = Vec::new();
-        let mut current = String::new();
-        for step in 0..max_tokens {
-            let logits: Vec<f32> = (0..vocab.len()).map(|_| rand::random::<f32>()).collect();
-            if let Some(idx) = sampler.sample(&logits) {
-                let token = vocab[idx];
-                if token == "assistant" && step < 2 { continue; }
-                current.push_str(token);
-                current.push(' ');
-                generated_tokens.push(current.clone());
-                if token == "!" { break; }
-            } else { break; }
-        }
-        tokio::spawn(async move {
-            for chunk in generated_tokens {
-                if tx.send(chunk.clone()).await.is_err() { break; }
-                if delay_ms > 0 { tokio::time::sleep(Duration::from_millis(delay_ms)).await; }
+        started_rx.await.unwrap();
+        assert!(engine.try_write().is_ok_and(|e| e.has_active_provider()));
+        gate.send(()).unwrap();
+        let (mut stream, trace) = generation.await.unwrap().unwrap();
+        assert_eq!(stream.recv().await.as_deref(), Some("done"));
+        assert_eq!(trace.provider, "Gated");
+    }
+
+    /// Times out `failures` times, then answers.
+    struct FlakyProvider(u32);
+
+    impl AIProvider for FlakyProvider {
+        fn name(&self) -> &str { "Flaky" }
+        fn is_available(&self) -> bool { true }
+        fn generate_response(&mut self, _messages: &[ChatMessage]) -> Result<String> {
+            if self.0 == 0 {
+                return Ok("recovered".into());
             }
-        });
-        Ok(rx)
+            self.0 -= 1;
+            Err(anyhow::anyhow!("Execution timed out"))
+        }
+        fn get_model_info(&self) -> Result<std::collections::HashMap<String, String>> { Ok(Default::default()) }
+        fn as_any(&self) -> &dyn std::any::Any { self }
+    }
+
+    #[tokio::test]
+    async fn test_transient_errors_are_retried_before_failover() {
+        use std::sync::atomic::{AtomicU32, Ordering};
+
+        assert!(is_transient_error(&anyhow::anyhow!("Execution timed out").context("step 3")));
+        assert!(!is_transient_error(&anyhow::anyhow!("CUDA out of memory")));
+
+        for (failures, provider, retries) in [(2, "Flaky", 2), (MAX_TRANSIENT_RETRIES + 1, "Intelligent Demo Mode", MAX_TRANSIENT_RETRIES)] {
+            let engine = RwLock::new(InferenceEngine::new());
+            {
+                let mut engine = engine.write().await;
+                let id = engine.add_provider_sync(Box::new(FlakyProvider(failures)));
+                engine.set_active_provider_sync(id).unwrap();
+            }
+            let seen = AtomicU32::new(0);
+            let workers = InferenceWorkers::new(1);
+            let on_retry = |n| seen.store(n, Ordering::SeqCst);
+            let (_, trace) = InferenceEngine::generate_detached(&engine, &workers, &[], &GenerationOverrides::default(), || (), on_retry).await.unwrap();
+            assert_eq!(trace.provider, provider);
+            assert_eq!(seen.load(Ordering::SeqCst), retries);
+            assert_eq!(engine.read().await.incidents.len(), usize::from(failures > MAX_TRANSIENT_RETRIES));
+        }
+    }
+
+    /// Sets its flag when dropped, standing in for a model holding an ORT session.
+    struct DropFlagProvider(&'static str, Arc<std::sync::atomic::AtomicBool>);
+
+    impl AIProvider for DropFlagProvider {
+        fn name(&self) -> &str { self.0 }
+        fn is_available(&self) -> bool { true }
+        fn generate_response(&mut self, _messages: &[ChatMessage]) -> Result<String> { Ok(self.0.into()) }
+        fn get_model_info(&self) -> Result<std::collections::HashMap<String, String>> { Ok(Default::default()) }
+        fn as_any(&self) -> &dyn std::any::Any { self }
+    }
+
+    impl Drop for DropFlagProvider {
+        fn drop(&mut self) {
+            self.1.store(true, std::sync::atomic::Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn test_replaced_and_removed_providers_are_dropped() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+        let (first_dropped, second_dropped) = (Arc::new(AtomicBool::new(false)), Arc::new(AtomicBool::new(false)));
+        let mut engine = InferenceEngine::new();
+        let id = engine.add_provider_sync(Box::new(DropFlagProvider("First", first_dropped.clone())));
+        engine.set_active_provider_sync(id).unwrap();
+
+        engine.replace_provider(id, Box::new(DropFlagProvider("Second", second_dropped.clone()))).unwrap();
+        assert!(first_dropped.load(Ordering::SeqCst));
+        assert_eq!(engine.provider_health()[0].0, "Second");
+        assert!(engine.has_active_provider());
+
+        // A running job keeps the provider alive until it is done
+        let job = engine.begin_generation(&[], &GenerationOverrides::default()).unwrap();
+        engine.remove_provider(id).unwrap();
+        assert!(!engine.has_active_provider() && engine.provider_health().is_empty());
+        assert!(!second_dropped.load(Ordering::SeqCst));
+        let result = job.run(|| ());
+        assert!(matches!(engine.settle(job, result).unwrap(), Settled::Done { text, .. } if text == "Second"));
+        assert!(second_dropped.load(Ordering::SeqCst));
+
+        // Ids aren't reused
+        let next = engine.add_provider_sync(Box::new(BasicDemoProvider));
+        assert_ne!(next, id);
+        assert!(engine.set_active_provider_sync(id).is_err());
+        assert!(engine.remove_provider(id).is_err());
+    }
+
+    /// Answers with its name; reports `path` as its model file.
+    struct ModelFileProvider(&'static str, &'static str);
+
+    impl AIProvider for ModelFileProvider {
+        fn name(&self) -> &str { self.0 }
+        fn is_available(&self) -> bool { true }
+        fn generate_response(&mut self, _messages: &[ChatMessage]) -> Result<String> { Ok(self.0.into()) }
+        fn get_model_info(&self) -> Result<std::collections::HashMap<String, String>> {
+            Ok([("model_path".to_string(), self.1.to_string())].into())
+        }
+        fn as_any(&self) -> &dyn std::any::Any { self }
+    }
+
+    #[test]
+    fn test_code_prompts_prefer_a_loaded_code_model() {
+        let mut engine = InferenceEngine::new();
+        let general = engine.add_provider_sync(Box::new(ModelFileProvider("General", "/models/phi-3-mini-instruct.onnx")));
+        engine.set_active_provider_sync(general).unwrap();
+        let code = GenerationOverrides { prefer_code_model: true, ..Default::default() };
+        assert!(!engine.has_code_model());
+        assert_eq!(engine.begin_generation(&[], &code).unwrap().name, "General");
+
+        let coder = engine.add_provider_sync(Box::new(ModelFileProvider("Coder", "/models/codellama-7b.onnx")));
+        assert!(engine.has_code_model());
+        assert_eq!(engine.begin_generation(&[], &code).unwrap().name, "Coder");
+        assert_eq!(engine.begin_generation(&[], &GenerationOverrides::default()).unwrap().name, "General");
+
+        // An unhealthy code model is passed over
+        engine.slot_mut(coder).unwrap().health = ProviderHealth::Unhealthy { reason: "test".into(), since: chrono::Utc::now() };
+        assert_eq!(engine.begin_generation(&[], &code).unwrap().name, "General");
+    }
+
+    #[test]
+    fn test_unhealthy_provider_is_reprobed_after_cooldown() {
+        let mut engine = InferenceEngine::new();
+        let real = engine.add_provider_sync(Box::new(ModelFileProvider("Real", "/models/phi-3.onnx")));
+        let demo = engine.add_provider_sync(Box::new(BasicDemoProvider));
+        engine.set_active_provider_sync(demo).unwrap();
+
+        engine.slot_mut(real).unwrap().health = ProviderHealth::Unhealthy { reason: "test".into(), since: chrono::Utc::now() };
+        assert_eq!(engine.begin_generation(&[], &GenerationOverrides::default()).unwrap().id, demo);
+
+        let long_ago = chrono::Utc::now() - chrono::Duration::from_std(UNHEALTHY_COOLDOWN).unwrap() - chrono::Duration::seconds(1);
+        engine.slot_mut(real).unwrap().health = ProviderHealth::Unhealthy { reason: "test".into(), since: long_ago };
+        let job = engine.begin_generation(&[], &GenerationOverrides::default()).unwrap();
+        assert_eq!(job.id, real);
+
+        // Answering clears the mark
+        engine.settle(job, Ok(("ok".into(), None))).unwrap();
+        assert_eq!(engine.provider_health()[0].1, ProviderHealth::Healthy);
+    }
+
+    #[test]
+    fn test_batcher_flushes_slow_tokens_immediately() {
+        let mut batcher = ChunkBatcher::new(Duration::ZERO, 1024);
+        assert_eq!(batcher.push("one "), Some("one ".to_string()));
+        assert_eq!(batcher.push("two"), Some("two".to_string()));
+        assert_eq!(batcher.finish(), None);
+    }
+
+    #[test]
+    fn test_batcher_holds_a_piece_no_longer_than_max_wait() {
+        let mut batcher = ChunkBatcher::new(Duration::from_secs(60), 1024).with_max_wait(Duration::from_millis(20));
+        assert_eq!(batcher.deadline(), None);
+        assert_eq!(batcher.push("one "), None);
+        let deadline = batcher.deadline().expect("a piece is waiting");
+        assert!(deadline <= Instant::now() + Duration::from_millis(20));
+        std::thread::sleep(Duration::from_millis(25));
+        assert_eq!(batcher.push("two "), Some("one two ".to_string()));
+        assert_eq!(batcher.deadline(), None);
+        assert_eq!(batcher.push("three"), None);
+        assert_eq!(batcher.take(), Some("three".to_string()));
+        assert_eq!(batcher.take(), None);
+    }
+
+    #[tokio::test]
+    async fn test_a_batch_is_flushed_when_no_more_pieces_arrive() {
+        let (tx, pieces) = mpsc::channel(4);
+        let mut chunks = batch_pieces(pieces);
+        tx.send("first ".to_string()).await.unwrap();
+        tx.send("burst".to_string()).await.unwrap();
+        // The sender stays open, so only the timer can flush the batch
+        let chunk = tokio::time::timeout(ChunkBatcher::MAX_WAIT * 10, chunks.recv()).await.expect("flushed by the timer");
+        let mut text = chunk.unwrap();
+        if text == "first " {
+            text.push_str(&chunks.recv().await.unwrap());
+        }
+        assert_eq!(text, "first burst");
+        drop(tx);
+        assert_eq!(chunks.recv().await, None);
     }
 }
@@ -0,0 +1,141 @@
+//! SHA256 sidecar manifests for local models.
+//!
+//! `model.onnx` gets a `model.onnx.manifest.json` recording its size and digest. The
+//! manifest also remembers the size/mtime at the last full verification so routine
+//! pre-load checks can skip re-hashing multi-GB files that have not changed.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelManifest {
+    pub file_name: String,
+    pub size: u64,
+    pub sha256: String,
+    #[serde(default)]
+    pub source_url: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    /// Modification time (seconds since epoch) when the digest was last confirmed.
+    #[serde(default)]
+    pub verified_mtime: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum IntegrityStatus {
+    Verified,
+    /// No manifest existed; one was recorded from the current file.
+    Recorded,
+    NoManifest,
+    Corrupted(String),
+}
+
+pub fn manifest_path(model_path: &Path) -> PathBuf {
+    let mut name = model_path.file_name().unwrap_or_default().to_os_string();
+    name.push(".manifest.json");
+    model_path.with_file_name(name)
+}
+
+pub fn compute_sha256(path: &Path) -> Result<String> {
+    let mut hasher = Sha256::new();
+    let mut f = std::fs::File::open(path)?;
+    let mut buf = [0u8; 1024 * 64];
+    loop {
+        let n = std::io::Read::read(&mut f, &mut buf)?;
+        if n == 0 { break; }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+fn mtime_secs(path: &Path) -> Option<u64> {
+    std::fs::metadata(path).ok()?.modified().ok()?.duration_since(UNIX_EPOCH).ok().map(|d| d.as_secs())
+}
+
+pub fn read_manifest(model_path: &Path) -> Option<ModelManifest> {
+    let data = std::fs::read_to_string(manifest_path(model_path)).ok()?;
+    serde_json::from_str(&data).ok()
+}
+
+fn store_manifest(model_path: &Path, manifest: &ModelManifest) -> Result<()> {
+    std::fs::write(manifest_path(model_path), serde_json::to_string_pretty(manifest)?)?;
+    Ok(())
+}
+
+/// Record a manifest for a model whose digest is already known (e.g. just downloaded).
+pub fn write_manifest(model_path: &Path, sha256: &str, source_url: Option<&str>) -> Result<ModelManifest> {
+    let manifest = ModelManifest {
+        file_name: model_path.file_name().and_then(|n| n.to_str()).unwrap_or_default().to_string(),
+        size: std::fs::metadata(model_path)?.len(),
+        sha256: sha256.to_lowercase(),
+        source_url: source_url.map(str::to_string),
+        created_at: chrono::Utc::now(),
+        verified_mtime: mtime_secs(model_path),
+    };
+    store_manifest(model_path, &manifest)?;
+    Ok(manifest)
+}
+
+/// Check a model against its manifest.
+///
+/// With `full` unset, a file whose size and mtime match the last verification is trusted
+/// without hashing; any change (or `full`) forces a full SHA256 pass. When `record_missing`
+/// is set and no manifest exists, the current digest is recorded as the baseline.
+pub fn verify_model(model_path: &Path, full: bool, record_missing: bool) -> Result<IntegrityStatus> {
+    let Some(mut manifest) = read_manifest(model_path) else {
+        if record_missing {
+            let digest = compute_sha256(model_path)?;
+            write_manifest(model_path, &digest, None)?;
+            return Ok(IntegrityStatus::Recorded);
+        }
+        return Ok(IntegrityStatus::NoManifest);
+    };
+
+    let size = std::fs::metadata(model_path)?.len();
+    if size != manifest.size {
+        return Ok(IntegrityStatus::Corrupted(format!(
+            "size is {} bytes, manifest expects {}", size, manifest.size
+        )));
+    }
+    let mtime = mtime_secs(model_path);
+    if !full && mtime.is_some() && mtime == manifest.verified_mtime {
+        return Ok(IntegrityStatus::Verified);
+    }
+
+    let digest = compute_sha256(model_path)?;
+    if digest != manifest.sha256.to_lowercase() {
+        return Ok(IntegrityStatus::Corrupted(format!(
+            "SHA256 is {}, manifest expects {}", digest, manifest.sha256
+        )));
+    }
+    if manifest.verified_mtime != mtime {
+        manifest.verified_mtime = mtime;
+        let _ = store_manifest(model_path, &manifest);
+    }
+    Ok(IntegrityStatus::Verified)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detects_corruption() {
+        let dir = tempfile::tempdir().unwrap();
+        let model = dir.path().join("m.onnx");
+        std::fs::write(&model, b"original weights").unwrap();
+
+        assert_eq!(verify_model(&model, false, false).unwrap(), IntegrityStatus::NoManifest);
+        assert_eq!(verify_model(&model, true, true).unwrap(), IntegrityStatus::Recorded);
+        assert_eq!(verify_model(&model, true, false).unwrap(), IntegrityStatus::Verified);
+
+        // Same size, different content: caught by a full check
+        std::fs::write(&model, b"origXnal weights").unwrap();
+        assert!(matches!(verify_model(&model, true, false).unwrap(), IntegrityStatus::Corrupted(_)));
+
+        std::fs::write(&model, b"truncated").unwrap();
+        assert!(matches!(verify_model(&model, false, false).unwrap(), IntegrityStatus::Corrupted(_)));
+    }
+}
@@ -0,0 +1,95 @@
+//! A scripted provider for testing what drives the engine without a model.
+//!
+//! [`MockProvider`] answers each request with the next scripted reply or error, records the
+//! prompts it was given, and can be held mid-generation until the test releases it.
+
+use super::inference::{FinishReason, GenerationTrace};
+use super::{AIProvider, ChatMessage, InferenceConfig};
+use anyhow::Result;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{mpsc, Arc, Mutex};
+
+#[derive(Default)]
+pub struct MockProvider {
+    script: VecDeque<Result<(String, FinishReason), String>>,
+    /// Each request waits for a message here first, when set.
+    gate: Option<Mutex<mpsc::Receiver<()>>>,
+    prompts: Arc<Mutex<Vec<String>>>,
+    last_trace: Option<GenerationTrace>,
+}
+
+impl MockProvider {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Answer the next request with `text`.
+    pub fn reply(mut self, text: &str) -> Self {
+        self.script.push_back(Ok((text.to_string(), FinishReason::Stop)));
+        self
+    }
+
+    /// Answer the next request with `text`, reported as stopped at the token limit.
+    pub fn cut_off(mut self, text: &str) -> Self {
+        self.script.push_back(Ok((text.to_string(), FinishReason::Length)));
+        self
+    }
+
+    /// Fail the next request with `error`.
+    pub fn fail(mut self, error: &str) -> Self {
+        self.script.push_back(Err(error.to_string()));
+        self
+    }
+
+    /// Hold every request until the returned sender sends (or is dropped).
+    pub fn gated(mut self) -> (Self, mpsc::Sender<()>) {
+        let (tx, rx) = mpsc::channel();
+        self.gate = Some(Mutex::new(rx));
+        (self, tx)
+    }
+
+    /// The last message of every request so far, shared with the provider.
+    pub fn prompts(&self) -> Arc<Mutex<Vec<String>>> {
+        self.prompts.clone()
+    }
+}
+
+impl AIProvider for MockProvider {
+    fn name(&self) -> &str {
+        "Mock"
+    }
+
+    fn is_available(&self) -> bool {
+        true
+    }
+
+    fn generate_response(&mut self, messages: &[ChatMessage]) -> Result<String> {
+        if let Some(gate) = &self.gate {
+            let _ = gate.lock().expect("gate lock").recv();
+        }
+        let prompt = messages.last().map(|m| m.content.clone()).unwrap_or_default();
+        self.prompts.lock().expect("prompts lock").push(prompt);
+        match self.script.pop_front() {
+            Some(Ok((text, finish_reason))) => {
+                let mut trace = GenerationTrace::from_context(self.name(), messages, &InferenceConfig::default());
+                trace.finish_reason = Some(finish_reason);
+                self.last_trace = Some(trace);
+                Ok(text)
+            }
+            Some(Err(error)) => Err(anyhow::anyhow!(error)),
+            None => anyhow::bail!("The mock provider has no reply left"),
+        }
+    }
+
+    fn get_model_info(&self) -> Result<HashMap<String, String>> {
+        Ok(HashMap::new())
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn last_trace(&self) -> Option<GenerationTrace> {
+        self.last_trace.clone()
+    }
+}
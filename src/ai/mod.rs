@@ -1,12 +1,44 @@
+//! Local inference: chat messages, providers and the engine that routes between them.
+//!
+//! An [`AIProvider`] answers a list of [`ChatMessage`]s; [`providers::OnnxProvider`] runs an
+//! ONNX model with the execution provider picked in its [`InferenceConfig`]. The
+//! [`InferenceEngine`] holds the providers, sends each request to the active one, retries
+//! transient errors and fails over to the next provider when one breaks.
+
 pub mod inference;
 pub mod providers;
 pub mod models;
 pub mod tokenizer;
 pub mod sampler;
+pub mod decode;
+pub mod constraint;
+pub mod context;
+pub mod cuda;
+pub mod quantize;
+pub mod integrity;
+pub mod blob_cache;
+pub mod model_card;
+pub mod model_storage;
+pub mod prefill;
+pub mod prompt_cache;
+pub mod response_cache;
+pub mod catalog;
+pub mod watcher;
+pub mod runtime;
+pub mod hardware_profile;
+pub mod vision;
+pub mod worker;
+pub mod feedback;
+pub mod code_routing;
+pub mod power;
+#[cfg(test)]
+pub mod mock;
+
+pub use inference::InferenceEngine;
 
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::any::Any;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -17,6 +49,128 @@ pub struct ChatMessage {
     pub timestamp: chrono::DateTime<chrono::Utc>,
     pub model_used: Option<String>,
     pub inference_time: Option<f64>,
+    /// Images pasted into the prompt (user messages only).
+    #[serde(default)]
+    pub images: Vec<vision::ImageAttachment>,
+    /// What the model was given for this reply; recorded when the developer inspector is on.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trace: Option<inference::GenerationTrace>,
+    /// The earlier message this one answers (user messages only).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reply_to: Option<QuotedReply>,
+    /// The user's rating of this reply (assistant messages only).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub feedback: Option<feedback::Feedback>,
+    /// When the message was last changed after it was written (a rating, a continued reply);
+    /// sync resolves conflicting copies of a message on it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub edited_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// An excerpt of the message a user message replies to. It is shown above the message and
+/// put in front of it in the prompt, so the model knows what "this" refers to.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QuotedReply {
+    pub message_id: String,
+    pub excerpt: String,
+}
+
+impl QuotedReply {
+    /// Longest excerpt kept, in characters.
+    pub const MAX_EXCERPT_CHARS: usize = 400;
+
+    pub fn new(message: &ChatMessage) -> Self {
+        let content = message.content.trim();
+        let excerpt = match content.char_indices().nth(Self::MAX_EXCERPT_CHARS) {
+            Some((end, _)) => format!("{}…", content[..end].trim_end()),
+            None => content.to_string(),
+        };
+        Self { message_id: message.id.clone(), excerpt }
+    }
+}
+
+impl ChatMessage {
+    /// A system prompt to put in front of a request; never stored in a session.
+    pub fn system(content: impl Into<String>) -> Self {
+        Self::with_role(MessageRole::System, content)
+    }
+
+    /// A message typed by the user, timestamped now.
+    pub fn user(content: impl Into<String>) -> Self {
+        Self::with_role(MessageRole::User, content)
+    }
+
+    /// A reply from the model, timestamped now.
+    pub fn assistant(content: impl Into<String>) -> Self {
+        Self::with_role(MessageRole::Assistant, content)
+    }
+
+    /// A tool's output, e.g. a file's contents, to be read by the model.
+    pub fn tool(content: impl Into<String>) -> Self {
+        Self::with_role(MessageRole::Tool, content)
+    }
+
+    fn with_role(role: MessageRole, content: impl Into<String>) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            content: content.into(),
+            role,
+            timestamp: chrono::Utc::now(),
+            model_used: None,
+            inference_time: None,
+            images: Vec::new(),
+            trace: None,
+            reply_to: None,
+            feedback: None,
+            edited_at: None,
+        }
+    }
+
+    /// When the message was last written or changed.
+    pub fn modified_at(&self) -> chrono::DateTime<chrono::Utc> {
+        self.edited_at.unwrap_or(self.timestamp)
+    }
+
+    /// Record a change to the message, so sync prefers this copy over older ones.
+    pub fn mark_edited(&mut self) {
+        self.edited_at = Some(chrono::Utc::now());
+    }
+
+    /// The message as a Markdown section headed by its author and time.
+    pub fn to_markdown(&self) -> String {
+        let role = self.role.label();
+        let local = self.timestamp.with_timezone(&chrono::Local);
+        let model = self.model_used.as_deref().map(|m| format!(" ({m})")).unwrap_or_default();
+        let mut out = format!("**{role}**{model} · {}\n\n", local.format("%Y-%m-%d %H:%M"));
+        if let Some(reply) = &self.reply_to {
+            out.push_str(&block_quote(&reply.excerpt));
+            out.push_str("\n\n");
+        }
+        out.push_str(self.content.trim_end());
+        // A reply cut off mid code block would swallow everything after it
+        if self.content.lines().filter(|l| l.trim_start().starts_with("```")).count() % 2 == 1 {
+            out.push_str("\n```");
+        }
+        out.push('\n');
+        out
+    }
+
+    /// The content as a Markdown block quote.
+    pub fn as_quote(&self) -> String {
+        block_quote(&self.content)
+    }
+
+    /// The content as the model should see it, with the excerpt it replies to in front.
+    pub fn prompt_content(&self) -> String {
+        match &self.reply_to {
+            Some(reply) => format!("In reply to this earlier message:\n{}\n\n{}", block_quote(&reply.excerpt), self.content),
+            None => self.content.clone(),
+        }
+    }
+}
+
+fn block_quote(text: &str) -> String {
+    text.trim().lines().map(|line| if line.is_empty() { ">".to_string() } else { format!("> {line}") }).collect::<Vec<_>>().join("\n")
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -24,6 +178,21 @@ pub enum MessageRole {
     User,
     Assistant,
     System,
+    /// Output of a tool call, such as a file added with `/file`; compacted before
+    /// re-entering the prompt (see `context`).
+    Tool,
+}
+
+impl MessageRole {
+    /// Who wrote the message, as exports name them.
+    pub fn label(&self) -> &'static str {
+        match self {
+            MessageRole::User => "You",
+            MessageRole::Assistant => "Assistant",
+            MessageRole::System => "System",
+            MessageRole::Tool => "Tool",
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -33,6 +202,174 @@ pub struct ChatSession {
     pub messages: Vec<ChatMessage>,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub updated_at: chrono::DateTime<chrono::Utc>,
+    /// Where this session was forked from, if it is a branch of another conversation.
+    #[serde(default)]
+    pub branched_from: Option<BranchOrigin>,
+    /// Ids of the messages the user starred, shown in the Favorites view.
+    #[serde(default)]
+    pub starred: Vec<String>,
+    /// Estimated tokens spent on this conversation's replies.
+    #[serde(default)]
+    pub token_usage: context::TokenUsage,
+    /// Name of the persona the chat was started with.
+    #[serde(default)]
+    pub persona: Option<String>,
+    /// System prompt set for this chat alone (`/system`); takes precedence over the persona's.
+    #[serde(default)]
+    pub system_prompt: Option<String>,
+    /// Moved to the sidebar's Archive group; hidden from the chat list and search.
+    #[serde(default)]
+    pub archived: bool,
+    /// Kept by history pruning whatever its age or size.
+    #[serde(default)]
+    pub pinned: bool,
+    /// Tags from the sidebar's tag menu, normalized by [`crate::ui::tags::normalize`].
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// The latest `/summarize` result, which can stand in for the messages before it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<SessionSummary>,
+}
+
+/// An empty, untitled session with a fresh id, created now.
+impl Default for ChatSession {
+    fn default() -> Self {
+        let now = chrono::Utc::now();
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            title: String::new(),
+            messages: Vec::new(),
+            created_at: now,
+            updated_at: now,
+            branched_from: None,
+            starred: Vec::new(),
+            token_usage: context::TokenUsage::default(),
+            persona: None,
+            system_prompt: None,
+            archived: false,
+            pinned: false,
+            tags: Vec::new(),
+            summary: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionSummary {
+    /// The assistant message holding the summary of everything before it.
+    pub message_id: String,
+    /// Send the summary instead of the earlier messages; they stay in the chat either way.
+    pub replace_in_context: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BranchOrigin {
+    pub session_id: String,
+    /// Last message copied into the branch.
+    pub message_id: String,
+}
+
+impl ChatSession {
+    /// A new session holding this conversation up to and including `message_id`, so an
+    /// alternative continuation can be explored without touching the original.
+    pub fn branch_at(&self, message_id: &str) -> Option<ChatSession> {
+        let end = self.messages.iter().position(|m| m.id == message_id)?;
+        let messages = self.messages[..=end]
+            .iter()
+            .map(|m| ChatMessage { id: uuid::Uuid::new_v4().to_string(), ..m.clone() })
+            .collect();
+        Some(ChatSession {
+            title: format!("{} (branch)", self.title),
+            messages,
+            branched_from: Some(BranchOrigin { session_id: self.id.clone(), message_id: message_id.to_string() }),
+            persona: self.persona.clone(),
+            system_prompt: self.system_prompt.clone(),
+            tags: self.tags.clone(),
+            // Message ids change in the branch, so stars and the summary aren't carried over
+            ..Default::default()
+        })
+    }
+
+    /// The conversation as Markdown, one section per message.
+    pub fn to_markdown(&self) -> String {
+        let mut out = format!("# {}\n", self.title);
+        for message in &self.messages {
+            out.push('\n');
+            out.push_str(&message.to_markdown());
+        }
+        out
+    }
+
+    /// Delete a message, and its star; returns whether it was there.
+    pub fn remove_message(&mut self, message_id: &str) -> bool {
+        let before = self.messages.len();
+        self.messages.retain(|m| m.id != message_id);
+        self.starred.retain(|id| id != message_id);
+        if self.summary.as_ref().is_some_and(|s| s.message_id == message_id) {
+            self.summary = None;
+        }
+        self.messages.len() != before
+    }
+
+    /// The messages a reply is generated from: all of them, or from the summary on when it
+    /// replaces the ones before it.
+    pub fn context_messages(&self) -> &[ChatMessage] {
+        let start = self
+            .summary
+            .as_ref()
+            .filter(|s| s.replace_in_context)
+            .and_then(|s| self.messages.iter().position(|m| m.id == s.message_id))
+            .unwrap_or(0);
+        &self.messages[start..]
+    }
+
+    /// Whether every word of `query` appears in the title or a message (case-insensitive).
+    /// An empty query matches every session.
+    pub fn matches(&self, query: &str) -> bool {
+        let title = self.title.to_lowercase();
+        query.split_whitespace().map(str::to_lowercase).all(|word| {
+            title.contains(&word) || self.messages.iter().any(|m| m.content.to_lowercase().contains(&word))
+        })
+    }
+
+    /// Add or remove a tag; returns whether the chat has it now.
+    pub fn toggle_tag(&mut self, tag: &str) -> bool {
+        if self.tags.iter().any(|t| t == tag) {
+            self.tags.retain(|t| t != tag);
+            false
+        } else {
+            self.tags.push(tag.to_string());
+            true
+        }
+    }
+
+    pub fn is_starred(&self, message_id: &str) -> bool {
+        self.starred.iter().any(|id| id == message_id)
+    }
+
+    /// Star or unstar a message; returns whether it is starred now.
+    pub fn toggle_star(&mut self, message_id: &str) -> bool {
+        if self.is_starred(message_id) {
+            self.starred.retain(|id| id != message_id);
+            false
+        } else {
+            self.starred.push(message_id.to_string());
+            true
+        }
+    }
+}
+
+/// Starred messages across `sessions` as (session index, message), newest first.
+pub fn starred_messages(sessions: &[ChatSession]) -> Vec<(usize, &ChatMessage)> {
+    let mut starred: Vec<(usize, &ChatMessage)> = sessions
+        .iter()
+        .enumerate()
+        .flat_map(|(idx, session)| {
+            session.messages.iter().filter(|m| session.is_starred(&m.id)).map(move |m| (idx, m))
+        })
+        .collect();
+    starred.sort_by_key(|(_, m)| std::cmp::Reverse(m.timestamp));
+    starred
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -41,12 +378,19 @@ pub struct InferenceConfig {
     pub max_tokens: u32,
     pub temperature: f32,
     pub top_p: f32,
+    /// Sample only from the k most likely tokens. 0 disables.
+    #[serde(default)]
+    pub top_k: u32,
+    /// Added to the logits of tokens whose text is the key; [`sampler::BAN_BIAS`] or lower bans them.
+    #[serde(default)]
+    pub logit_bias: BTreeMap<String, f32>,
     pub execution_provider: ExecutionProvider,
     pub use_gpu: bool,
     pub use_npu: bool,
     #[serde(default)]
     pub prefer_npu: bool,
     /// If prefer_npu is true and OpenVINO EP is selected/forced, use this device string.
+    /// Empty builds an `AUTO:` string from the devices OpenVINO reports (NPU, then GPU, then CPU).
     #[serde(default = "InferenceConfig::default_prefer_npu_device_string")]
     pub prefer_npu_device_string: String,
     /// Enable lightweight profiling during model load (writes simple custom profile file, not ORT native yet).
@@ -55,6 +399,79 @@ pub struct InferenceConfig {
     /// Number of warmup iterations to run immediately after session creation (adaptive probe style) to stabilize performance.
     #[serde(default)]
     pub warmup_iterations: u32,
+    /// Explicit device index for multi-device EPs (CUDA / DirectML). None lets the EP pick its default device.
+    #[serde(default)]
+    pub device_id: Option<i32>,
+    /// Explicit OpenVINO device string (e.g. "GPU.0", "NPU"). Takes precedence over prefer_npu_device_string.
+    #[serde(default)]
+    pub device_type: Option<String>,
+    /// Character budget for a single tool result in the prompt; larger results are truncated with a summary marker. 0 disables.
+    #[serde(default = "InferenceConfig::default_tool_result_max_chars")]
+    pub tool_result_max_chars: usize,
+    /// ONNX Runtime session tuning (threads, memory arena, graph optimization).
+    #[serde(default)]
+    pub session_options: SessionOptions,
+    /// Check the model against its SHA256 sidecar manifest before loading.
+    #[serde(default = "InferenceConfig::default_verify_integrity")]
+    pub verify_integrity: bool,
+    /// Prompt tokens per forward run when the model has a KV cache. 0 feeds the whole prompt at once.
+    #[serde(default = "InferenceConfig::default_prefill_chunk_size")]
+    pub prefill_chunk_size: usize,
+    /// Prompts whose KV cache is kept so the next turn only prefills what was added. 0 disables.
+    #[serde(default = "InferenceConfig::default_prompt_cache_entries")]
+    pub prompt_cache_entries: usize,
+    /// Answer a request identical to an earlier one with the earlier reply.
+    #[serde(default)]
+    pub response_cache: bool,
+    /// Pause between forward runs, letting a laptop NPU or GPU cool off; set by [`power::PowerMode`].
+    #[serde(default)]
+    pub step_delay_ms: u64,
+    /// Conversation length warnings shown by the chat header meter.
+    #[serde(default)]
+    pub token_limits: context::TokenLimits,
+}
+
+/// ONNX Runtime session builder options.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionOptions {
+    /// Threads used within a single operator. 0 = auto (logical cores, capped at 4).
+    pub intra_threads: usize,
+    /// Threads used across independent operators; only used with parallel execution. 0 = ORT default.
+    pub inter_threads: usize,
+    pub parallel_execution: bool,
+    /// Use the CPU arena allocator (faster, but holds on to peak memory).
+    pub cpu_mem_arena: bool,
+    /// Pre-plan memory for fixed-shape inputs.
+    pub memory_pattern: bool,
+    pub optimization_level: OptimizationLevel,
+}
+
+impl Default for SessionOptions {
+    fn default() -> Self {
+        Self {
+            intra_threads: 0,
+            inter_threads: 0,
+            parallel_execution: false,
+            cpu_mem_arena: true,
+            memory_pattern: true,
+            optimization_level: OptimizationLevel::All,
+        }
+    }
+}
+
+impl SessionOptions {
+    pub fn effective_intra_threads(&self) -> usize {
+        if self.intra_threads == 0 { num_cpus::get().min(4) } else { self.intra_threads }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OptimizationLevel {
+    Disable,
+    Basic,
+    Extended,
+    All,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -75,6 +492,8 @@ impl Default for InferenceConfig {
             max_tokens: 2048,
             temperature: 0.7,
             top_p: 0.9,
+            top_k: 0,
+            logit_bias: BTreeMap::new(),
             execution_provider: ExecutionProvider::Cpu,
             use_gpu: false,
             use_npu: false,
@@ -82,18 +501,273 @@ impl Default for InferenceConfig {
             prefer_npu_device_string: Self::default_prefer_npu_device_string(),
             profiling: false,
             warmup_iterations: 0,
+            device_id: None,
+            device_type: None,
+            tool_result_max_chars: Self::default_tool_result_max_chars(),
+            session_options: SessionOptions::default(),
+            verify_integrity: true,
+            prefill_chunk_size: Self::default_prefill_chunk_size(),
+            prompt_cache_entries: Self::default_prompt_cache_entries(),
+            response_cache: false,
+            step_delay_ms: 0,
+            token_limits: context::TokenLimits::default(),
         }
     }
 }
 
 impl InferenceConfig {
-    fn default_prefer_npu_device_string() -> String { "AUTO:NPU,CPU".to_string() }
+    fn default_prefer_npu_device_string() -> String { String::new() }
+    fn default_tool_result_max_chars() -> usize { context::DEFAULT_TOOL_RESULT_MAX_CHARS }
+    fn default_verify_integrity() -> bool { true }
+    fn default_prefill_chunk_size() -> usize { prefill::DEFAULT_CHUNK_SIZE }
+    fn default_prompt_cache_entries() -> usize { prompt_cache::DEFAULT_ENTRIES }
+}
+
+/// Settings that apply to a single request, on top of the provider's config.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GenerationOverrides {
+    pub max_tokens: Option<u32>,
+    pub temperature: Option<f32>,
+    pub top_p: Option<f32>,
+    pub top_k: Option<u32>,
+    /// Entries added to (or replacing) the configured logit bias.
+    pub logit_bias: BTreeMap<String, f32>,
+    /// Format the reply must follow; only providers that decode token by token can honour it.
+    pub constraint: Option<constraint::OutputConstraint>,
+    /// Run on a loaded code model rather than the active provider, if there is one.
+    pub prefer_code_model: bool,
+}
+
+impl GenerationOverrides {
+    /// `config` with the overrides applied.
+    pub fn apply(&self, config: &InferenceConfig) -> InferenceConfig {
+        let mut config = config.clone();
+        if let Some(max_tokens) = self.max_tokens {
+            config.max_tokens = max_tokens;
+        }
+        if let Some(temperature) = self.temperature {
+            config.temperature = temperature;
+        }
+        if let Some(top_p) = self.top_p {
+            config.top_p = top_p;
+        }
+        if let Some(top_k) = self.top_k {
+            config.top_k = top_k;
+        }
+        config.logit_bias.extend(self.logit_bias.iter().map(|(token, bias)| (token.clone(), *bias)));
+        config
+    }
+
+    /// These overrides with `base` filling in whatever they leave unset.
+    pub fn layered_over(self, base: &GenerationOverrides) -> Self {
+        let mut logit_bias = base.logit_bias.clone();
+        logit_bias.extend(self.logit_bias);
+        Self {
+            max_tokens: self.max_tokens.or(base.max_tokens),
+            temperature: self.temperature.or(base.temperature),
+            top_p: self.top_p.or(base.top_p),
+            top_k: self.top_k.or(base.top_k),
+            logit_bias,
+            constraint: self.constraint.or_else(|| base.constraint.clone()),
+            prefer_code_model: self.prefer_code_model || base.prefer_code_model,
+        }
+    }
+
+    /// Whether any sampler setting differs from the config.
+    pub fn changes_sampling(&self) -> bool {
+        self.temperature.is_some() || self.top_p.is_some() || self.top_k.is_some() || !self.logit_bias.is_empty()
+    }
+}
+
+/// Reply length picked beside the send button.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ResponseLength {
+    Short,
+    /// Whatever `max_tokens` is set to in Settings.
+    #[default]
+    Normal,
+    Long,
+    Custom(u32),
+}
+
+impl ResponseLength {
+    pub const SHORT_TOKENS: u32 = 256;
+    pub const LONG_TOKENS: u32 = 4096;
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Short => "Short",
+            Self::Normal => "Normal",
+            Self::Long => "Long",
+            Self::Custom(_) => "Custom",
+        }
+    }
+
+    /// The `max_tokens` override for this preset; `None` keeps the configured value.
+    pub fn max_tokens(self) -> Option<u32> {
+        match self {
+            Self::Short => Some(Self::SHORT_TOKENS),
+            Self::Normal => None,
+            Self::Long => Some(Self::LONG_TOKENS),
+            Self::Custom(tokens) => Some(tokens.max(1)),
+        }
+    }
 }
 
 pub trait AIProvider {
     fn name(&self) -> &str;
     fn is_available(&self) -> bool;
     fn generate_response(&mut self, messages: &[ChatMessage]) -> Result<String>;
+    /// Generate with per-request settings. Providers that don't sample can keep the default,
+    /// which ignores the overrides.
+    fn generate_response_with(&mut self, messages: &[ChatMessage], _overrides: &GenerationOverrides) -> Result<String> {
+        self.generate_response(messages)
+    }
     fn get_model_info(&self) -> Result<HashMap<String, String>>;
+    /// Whether the provider masks its sampler with [`GenerationOverrides::constraint`].
+    fn supports_constraints(&self) -> bool {
+        false
+    }
     fn as_any(&self) -> &dyn Any;
-}
\ No newline at end of file
+    /// Details of the last `generate_response` call, for the developer inspector. Providers
+    /// without a tokenizer can leave this to the engine, which records the prompt itself.
+    fn last_trace(&self) -> Option<inference::GenerationTrace> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_branch_copies_history_and_summary_trims_context() {
+        let now = chrono::Utc::now();
+        let message = |id: &str, role| ChatMessage {
+            id: id.into(),
+            content: format!("content {id}"),
+            role,
+            timestamp: now,
+            model_used: None,
+            inference_time: None,
+            images: Vec::new(),
+            trace: None,
+            reply_to: None,
+            feedback: None,
+            edited_at: None,
+        };
+        let session = ChatSession {
+            id: "s1".into(),
+            title: "Rust questions".into(),
+            messages: vec![message("a", MessageRole::User), message("b", MessageRole::Assistant), message("c", MessageRole::User)],
+            starred: vec!["a".into()],
+            ..Default::default()
+        };
+
+        let branch = session.branch_at("b").unwrap();
+        assert_ne!(branch.id, session.id);
+        assert_eq!(branch.messages.len(), 2);
+        assert_eq!(branch.messages[1].content, "content b");
+        assert_ne!(branch.messages[1].id, "b");
+        assert_eq!(branch.branched_from, Some(BranchOrigin { session_id: "s1".into(), message_id: "b".into() }));
+        assert_eq!(session.messages.len(), 3);
+        assert!(session.branch_at("missing").is_none());
+        assert!(branch.starred.is_empty());
+
+        // A summary only shortens the context when it replaces what came before it
+        let mut session = session;
+        session.summary = Some(SessionSummary { message_id: "b".into(), replace_in_context: false });
+        assert_eq!(session.context_messages().len(), 3);
+        session.summary.as_mut().unwrap().replace_in_context = true;
+        assert_eq!(session.context_messages().iter().map(|m| m.id.as_str()).collect::<Vec<_>>(), ["b", "c"]);
+        assert!(session.remove_message("b") && session.summary.is_none());
+        assert_eq!(session.context_messages().len(), 2);
+    }
+
+    #[test]
+    fn test_starred_messages_across_sessions() {
+        let now = chrono::Utc::now();
+        let message = |id: &str, minutes: i64| ChatMessage {
+            id: id.into(),
+            content: id.into(),
+            role: MessageRole::Assistant,
+            timestamp: now + chrono::Duration::minutes(minutes),
+            model_used: None,
+            inference_time: None,
+            images: Vec::new(),
+            trace: None,
+            reply_to: None,
+            feedback: None,
+            edited_at: None,
+        };
+        let session = |id: &str, messages| ChatSession {
+            id: id.into(),
+            title: id.into(),
+            messages,
+            ..Default::default()
+        };
+        let mut sessions = vec![session("s1", vec![message("a", 0), message("b", 1)]), session("s2", vec![message("c", 2)])];
+        assert!(sessions[0].toggle_star("a"));
+        assert!(sessions[1].toggle_star("c"));
+        assert!(sessions[0].toggle_star("b"));
+        assert!(!sessions[0].toggle_star("b"));
+
+        let starred: Vec<(usize, &str)> = starred_messages(&sessions).into_iter().map(|(i, m)| (i, m.id.as_str())).collect();
+        assert_eq!(starred, vec![(1, "c"), (0, "a")]);
+    }
+
+    #[test]
+    fn test_markdown_has_roles_and_closes_cut_off_code() {
+        let reply = ChatMessage { model_used: Some("phi".into()), ..ChatMessage::assistant("```rust\nfn main() {") };
+        let session = ChatSession {
+            id: "s1".into(),
+            title: "Rust".into(),
+            messages: vec![ChatMessage::system("Be brief."), reply],
+            ..Default::default()
+        };
+        let markdown = session.to_markdown();
+        assert!(markdown.starts_with("# Rust\n"));
+        assert!(markdown.contains("**Assistant** (phi) · "));
+        assert!(markdown.trim_end().ends_with("fn main() {\n```"));
+    }
+
+    #[test]
+    fn test_quote_search_and_remove_message() {
+        let message = ChatMessage::system("First line\n\nSecond line\n");
+        assert_eq!(message.as_quote(), "> First line\n>\n> Second line");
+
+        let mut session = ChatSession {
+            id: "s1".into(),
+            title: "Chat".into(),
+            messages: vec![message.clone()],
+            starred: vec![message.id.clone()],
+            ..Default::default()
+        };
+        let mut reply = ChatMessage::system("Why?");
+        reply.reply_to = Some(QuotedReply::new(&message));
+        assert_eq!(reply.prompt_content(), "In reply to this earlier message:\n> First line\n>\n> Second line\n\nWhy?");
+        let long = ChatMessage::system("é".repeat(QuotedReply::MAX_EXCERPT_CHARS + 1));
+        assert_eq!(QuotedReply::new(&long).excerpt.chars().count(), QuotedReply::MAX_EXCERPT_CHARS + 1);
+
+        assert!(session.matches("second FIRST") && session.matches("chat") && session.matches(""));
+        assert!(!session.matches("first third"));
+
+        assert!(session.remove_message(&message.id));
+        assert!(session.messages.is_empty() && session.starred.is_empty());
+        assert!(!session.remove_message(&message.id));
+    }
+
+    #[test]
+    fn test_overrides_layer_over_a_base() {
+        let base = GenerationOverrides {
+            max_tokens: Some(128),
+            temperature: Some(0.2),
+            logit_bias: [("um".to_string(), -5.0), ("yes".to_string(), 1.0)].into(),
+            ..Default::default()
+        };
+        let chat = GenerationOverrides { temperature: Some(0.9), logit_bias: [("yes".to_string(), 2.0)].into(), ..Default::default() };
+        let layered = chat.layered_over(&base);
+        assert_eq!((layered.max_tokens, layered.temperature, layered.top_p), (Some(128), Some(0.9), None));
+        assert_eq!(layered.logit_bias, [("um".to_string(), -5.0), ("yes".to_string(), 2.0)].into());
+    }
+}
@@ -0,0 +1,97 @@
+//! Model cards: the README a publisher ships with a model.
+//!
+//! Catalog entries can name their card explicitly; for files hosted on Hugging Face the card
+//! is the repository's `README.md` at the same revision. Cards usually open with a YAML
+//! front-matter block (`license: apache-2.0`, tags, …) that is split off so the license can
+//! be shown next to the download button instead of as raw YAML.
+
+use anyhow::{Context, Result};
+
+/// A fetched card, with the metadata the UI shows above the Markdown body.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ModelCard {
+    pub url: String,
+    pub license: Option<String>,
+    /// Other top-level `key: value` pairs from the front matter, in order.
+    pub metadata: Vec<(String, String)>,
+    pub markdown: String,
+}
+
+/// Card URL for a file on Hugging Face: `https://huggingface.co/<org>/<repo>/resolve/<rev>/…`
+/// has its card at `https://huggingface.co/<org>/<repo>/raw/<rev>/README.md`.
+pub fn huggingface_card_url(download_url: &str) -> Option<String> {
+    let rest = download_url.strip_prefix("https://huggingface.co/")?;
+    let mut parts = rest.splitn(5, '/');
+    let (org, repo, kind, revision) = (parts.next()?, parts.next()?, parts.next()?, parts.next()?);
+    if org.is_empty() || repo.is_empty() || revision.is_empty() || !matches!(kind, "resolve" | "blob" | "raw") {
+        return None;
+    }
+    Some(format!("https://huggingface.co/{org}/{repo}/raw/{revision}/README.md"))
+}
+
+/// Split a card into its front matter and Markdown body.
+pub fn parse(url: &str, text: &str) -> ModelCard {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let mut card = ModelCard { url: url.to_string(), markdown: text.to_string(), ..Default::default() };
+    let Some(rest) = text.strip_prefix("---\n").or_else(|| text.strip_prefix("---\r\n")) else { return card };
+    let Some(end) = rest.lines().position(|line| line.trim_end() == "---") else { return card };
+
+    let mut lines = rest.lines();
+    for line in lines.by_ref().take(end) {
+        // Only top-level scalars; nested lists and maps are indented or start with '-'
+        if line.starts_with([' ', '\t', '-', '#']) {
+            continue;
+        }
+        let Some((key, value)) = line.split_once(':') else { continue };
+        let value = value.trim().trim_matches(|c| c == '"' || c == '\'');
+        if value.is_empty() {
+            continue;
+        }
+        if key.trim() == "license" {
+            card.license = Some(value.to_string());
+        } else {
+            card.metadata.push((key.trim().to_string(), value.to_string()));
+        }
+    }
+    lines.next(); // closing ---
+    card.markdown = lines.collect::<Vec<_>>().join("\n").trim_start().to_string();
+    card
+}
+
+pub async fn fetch(client: &reqwest::Client, url: &str) -> Result<ModelCard> {
+    // Private repositories need the token for their README too
+    let text = super::models::authorize(client.get(url), url)
+        .send()
+        .await?
+        .error_for_status()
+        .with_context(|| format!("No model card at {url}"))?
+        .text()
+        .await?;
+    Ok(parse(url, &text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_card_url_and_front_matter() {
+        assert_eq!(
+            huggingface_card_url("https://huggingface.co/microsoft/Phi-3-mini-4k-instruct-onnx/resolve/main/cpu/model.onnx"),
+            Some("https://huggingface.co/microsoft/Phi-3-mini-4k-instruct-onnx/raw/main/README.md".to_string())
+        );
+        assert_eq!(huggingface_card_url("https://example.com/org/repo/resolve/main/model.onnx"), None);
+        assert_eq!(huggingface_card_url("https://huggingface.co/org/repo"), None);
+
+        let card = parse(
+            "https://example.com/README.md",
+            "---\nlicense: mit\ntags:\n  - onnx\nlanguage: en\npipeline_tag: \"text-generation\"\n---\n\n# Tiny model\nUse it well.",
+        );
+        assert_eq!(card.license.as_deref(), Some("mit"));
+        assert_eq!(card.metadata, vec![("language".to_string(), "en".to_string()), ("pipeline_tag".to_string(), "text-generation".to_string())]);
+        assert_eq!(card.markdown, "# Tiny model\nUse it well.");
+
+        // No front matter: the whole text is the body
+        assert_eq!(parse("u", "# Title\n---\ntext").markdown, "# Title\n---\ntext");
+    }
+}
@@ -0,0 +1,190 @@
+//! Disk usage of the model directories and cleanup of what is left over.
+//!
+//! A model is its `.onnx` file plus the files kept beside it: the integrity manifest, a
+//! downloaded tokenizer (`<name>.tokenizer.json`) and external weights (`.onnx.data` /
+//! `.onnx_data`). Those companions whose model is gone are reported as orphans, together
+//! with download-cache blobs no model links to any more. Deleted models go to a `.trash`
+//! folder in their directory first, one subfolder per model, so they can be restored.
+
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+
+const TRASH_DIR: &str = ".trash";
+const COMPANION_SUFFIXES: &[&str] = &[".manifest.json", ".data", "_data"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageKind {
+    Model,
+    /// Companion file of a model that no longer exists, or an unused cache blob.
+    Orphan,
+    /// A deleted model waiting in the trash.
+    Trash,
+}
+
+/// One row of the storage overview: everything that goes together when it is removed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StorageItem {
+    pub kind: StorageKind,
+    pub label: String,
+    pub paths: Vec<PathBuf>,
+    pub bytes: u64,
+}
+
+impl StorageItem {
+    fn new(kind: StorageKind, label: String, paths: Vec<PathBuf>) -> Self {
+        let bytes = paths.iter().map(|p| disk_size(p)).sum();
+        Self { kind, label, paths, bytes }
+    }
+
+    /// Remove the files for good.
+    pub fn delete(&self) -> Result<()> {
+        for path in &self.paths {
+            let removed = if path.is_dir() { std::fs::remove_dir_all(path) } else { std::fs::remove_file(path) };
+            match removed {
+                Ok(()) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e).with_context(|| format!("Failed to delete {}", path.display())),
+            }
+        }
+        Ok(())
+    }
+}
+
+fn disk_size(path: &Path) -> u64 {
+    let Ok(meta) = std::fs::symlink_metadata(path) else { return 0 };
+    if !meta.is_dir() {
+        return meta.len();
+    }
+    std::fs::read_dir(path)
+        .map(|entries| entries.flatten().map(|e| disk_size(&e.path())).sum())
+        .unwrap_or(0)
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name().unwrap_or_default().to_string_lossy().to_string()
+}
+
+/// Files belonging to `model_path` that exist next to it.
+pub fn companions(model_path: &Path) -> Vec<PathBuf> {
+    let name = file_name(model_path);
+    let stem = model_path.file_stem().unwrap_or_default().to_string_lossy().to_string();
+    let mut candidates: Vec<PathBuf> = COMPANION_SUFFIXES.iter().map(|suffix| model_path.with_file_name(format!("{name}{suffix}"))).collect();
+    candidates.push(model_path.with_file_name(format!("{stem}.tokenizer.json")));
+    candidates.retain(|p| p.is_file());
+    candidates
+}
+
+/// The model a companion file belongs to, judging by its name.
+fn owner_of(path: &Path) -> Option<PathBuf> {
+    let name = file_name(path);
+    if let Some(stem) = name.strip_suffix(".tokenizer.json") {
+        return Some(path.with_file_name(format!("{stem}.onnx")));
+    }
+    COMPANION_SUFFIXES
+        .iter()
+        .find_map(|suffix| name.strip_suffix(suffix))
+        .filter(|model| model.ends_with(".onnx"))
+        .map(|model| path.with_file_name(model))
+}
+
+/// Blobs in a `.blobs` download cache that no model folder links to, and abandoned staging
+/// files. Link counts are only available on Unix; elsewhere only staging files are reported.
+fn unused_blobs(cache: &Path) -> Vec<PathBuf> {
+    let mut unused: Vec<PathBuf> = std::fs::read_dir(cache.join("tmp")).into_iter().flatten().flatten().map(|e| e.path()).collect();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        let blobs = std::fs::read_dir(cache.join("sha256")).into_iter().flatten().flatten().map(|e| e.path());
+        unused.extend(blobs.filter(|p| std::fs::metadata(p).is_ok_and(|m| m.is_file() && m.nlink() == 1)));
+    }
+    unused
+}
+
+/// Models, orphans and trash across `dirs`.
+pub fn scan(dirs: &[PathBuf]) -> Vec<StorageItem> {
+    let mut items = Vec::new();
+    for dir in dirs {
+        let Ok(entries) = std::fs::read_dir(dir) else { continue };
+        let files: Vec<PathBuf> = entries.flatten().map(|e| e.path()).filter(|p| p.is_file()).collect();
+        for path in &files {
+            if path.extension().and_then(|e| e.to_str()) == Some("onnx") {
+                let mut paths = vec![path.clone()];
+                paths.extend(companions(path));
+                let label = path.file_stem().unwrap_or_default().to_string_lossy().to_string();
+                items.push(StorageItem::new(StorageKind::Model, label, paths));
+            } else if owner_of(path).is_some_and(|model| !model.exists()) {
+                items.push(StorageItem::new(StorageKind::Orphan, file_name(path), vec![path.clone()]));
+            }
+        }
+        for blob in unused_blobs(&dir.join(".blobs")) {
+            items.push(StorageItem::new(StorageKind::Orphan, format!("Unused download cache file {}", file_name(&blob)), vec![blob]));
+        }
+        let trash = std::fs::read_dir(dir.join(TRASH_DIR)).into_iter().flatten().flatten().map(|e| e.path());
+        for entry in trash.filter(|p| p.is_dir()) {
+            items.push(StorageItem::new(StorageKind::Trash, file_name(&entry), vec![entry]));
+        }
+    }
+    items
+}
+
+/// Move a model and its companions into the trash of its directory. Returns the trash folder.
+pub fn move_to_trash(model_path: &Path) -> Result<PathBuf> {
+    let dir = model_path.parent().context("The model has no parent directory")?;
+    let stem = model_path.file_stem().unwrap_or_default().to_string_lossy();
+    let folder = dir.join(TRASH_DIR).join(format!("{stem} ({})", chrono::Local::now().format("%Y-%m-%d %H.%M.%S")));
+    std::fs::create_dir_all(&folder)?;
+    let mut files = vec![model_path.to_path_buf()];
+    files.extend(companions(model_path));
+    for file in files {
+        std::fs::rename(&file, folder.join(file_name(&file))).with_context(|| format!("Failed to move {} to the trash", file.display()))?;
+    }
+    Ok(folder)
+}
+
+/// Move the files of a trashed model back to the models directory the trash belongs to.
+pub fn restore(trash_folder: &Path) -> Result<()> {
+    let dir = trash_folder.parent().and_then(Path::parent).context("Not a trash folder")?;
+    for entry in std::fs::read_dir(trash_folder)?.flatten() {
+        let dest = dir.join(entry.file_name());
+        if dest.exists() {
+            anyhow::bail!("{} already exists", dest.display());
+        }
+        std::fs::rename(entry.path(), &dest)?;
+    }
+    std::fs::remove_dir(trash_folder)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_models_orphans_and_trash() {
+        let dir = tempfile::tempdir().unwrap();
+        let write = |name: &str, bytes: usize| std::fs::write(dir.path().join(name), vec![0u8; bytes]).unwrap();
+        write("phi.onnx", 100);
+        write("phi.onnx.data", 50);
+        write("phi.tokenizer.json", 10);
+        write("gone.tokenizer.json", 7);
+        write("gone.onnx.manifest.json", 3);
+        write("notes.txt", 1);
+
+        let dirs = vec![dir.path().to_path_buf()];
+        let items = scan(&dirs);
+        let model = items.iter().find(|i| i.kind == StorageKind::Model).unwrap();
+        assert_eq!((model.label.as_str(), model.bytes, model.paths.len()), ("phi", 160, 3));
+        let mut orphans: Vec<&str> = items.iter().filter(|i| i.kind == StorageKind::Orphan).map(|i| i.label.as_str()).collect();
+        orphans.sort();
+        assert_eq!(orphans, ["gone.onnx.manifest.json", "gone.tokenizer.json"]);
+
+        let folder = move_to_trash(&dir.path().join("phi.onnx")).unwrap();
+        let items = scan(&dirs);
+        assert!(!items.iter().any(|i| i.kind == StorageKind::Model));
+        assert_eq!(items.iter().find(|i| i.kind == StorageKind::Trash).unwrap().bytes, 160);
+
+        restore(&folder).unwrap();
+        assert!(dir.path().join("phi.onnx.data").exists());
+        assert!(!scan(&dirs).iter().any(|i| i.kind == StorageKind::Trash));
+    }
+}
@@ -1,11 +1,207 @@
 use super::*;
-use anyhow::Result;
+use anyhow::{Context, Result};
 use std::path::{Path, PathBuf};
-use sha2::{Digest, Sha256};
+
+/// Attempts per URL (the first try plus retries) before moving on to the next mirror.
+pub const ATTEMPTS_PER_URL: u32 = 3;
+const MAX_BACKOFF: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// A failed download attempt that will be retried, reported to the progress card.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DownloadRetry {
+    /// Next attempt on the current URL (1-based), or 1 when moving to the next mirror.
+    pub attempt: u32,
+    /// Index of the URL the next attempt uses, and how many there are.
+    pub url_index: usize,
+    pub url_count: usize,
+    pub delay: std::time::Duration,
+    pub error: String,
+}
+
+/// Wait before retry `attempt` (2 = first retry): 1 s, 2 s, 4 s, … up to 30 s.
+pub fn backoff_delay(attempt: u32) -> std::time::Duration {
+    let exponent = attempt.saturating_sub(2).min(16);
+    std::time::Duration::from_secs(1u64 << exponent).min(MAX_BACKOFF)
+}
+
+/// The connection ended before the size the server announced. The `.part` file is kept so
+/// the next attempt requests only the missing range.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TruncatedDownload {
+    pub received: u64,
+    pub expected: u64,
+}
+
+impl std::fmt::Display for TruncatedDownload {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "the download stopped after {} of {} bytes", self.received, self.expected)
+    }
+}
+
+impl std::error::Error for TruncatedDownload {}
+
+/// Total size from a `Content-Range: bytes 100-199/1000` header, unless it is `*`.
+fn content_range_total(header: &str) -> Option<u64> {
+    header.strip_prefix("bytes ")?.rsplit_once('/')?.1.trim().parse().ok()
+}
+
+/// Whether a failed download may succeed if tried again: dropped connections, timeouts,
+/// rate limiting and server errors. Everything else (404, checksum mismatch, disk errors)
+/// won't be fixed by retrying the same URL.
+fn is_transient(error: &anyhow::Error) -> bool {
+    if error.downcast_ref::<TruncatedDownload>().is_some() {
+        return true;
+    }
+    let Some(error) = error.downcast_ref::<reqwest::Error>() else { return false };
+    match error.status() {
+        Some(status) => status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS || status == reqwest::StatusCode::REQUEST_TIMEOUT,
+        None => error.is_timeout() || error.is_connect() || error.is_request() || error.is_body() || error.is_decode(),
+    }
+}
+
+/// Hugging Face access token for gated and private repositories: `HF_TOKEN` (or the older
+/// `HUGGING_FACE_HUB_TOKEN`) when set, otherwise the one saved in settings.
+pub fn huggingface_token() -> Option<String> {
+    ["HF_TOKEN", "HUGGING_FACE_HUB_TOKEN"]
+        .iter()
+        .find_map(|var| std::env::var(var).ok().map(|t| t.trim().to_string()).filter(|t| !t.is_empty()))
+        .or_else(crate::utils::credentials::huggingface_token)
+}
+
+/// Whether [`huggingface_token`] would find a token, without waiting for the credential store:
+/// `None` while the store hasn't been read yet.
+pub fn huggingface_token_known() -> Option<bool> {
+    let from_env = ["HF_TOKEN", "HUGGING_FACE_HUB_TOKEN"]
+        .iter()
+        .any(|var| std::env::var(var).is_ok_and(|t| !t.trim().is_empty()));
+    if from_env {
+        return Some(true);
+    }
+    crate::utils::credentials::cached(crate::utils::credentials::Credential::HuggingFaceToken)
+        .map(|token| token.is_some())
+}
+
+pub fn is_huggingface_url(url: &str) -> bool {
+    reqwest::Url::parse(url).ok().and_then(|u| u.host_str().map(str::to_string)).is_some_and(|host| {
+        host == "huggingface.co" || host == "hf.co" || host.ends_with(".huggingface.co")
+    })
+}
+
+/// Send the Hugging Face token with requests to Hugging Face, and only there.
+pub fn authorize(request: reqwest::RequestBuilder, url: &str) -> reqwest::RequestBuilder {
+    match huggingface_token() {
+        Some(token) if is_huggingface_url(url) => request.bearer_auth(token),
+        _ => request,
+    }
+}
+
+/// Hugging Face answers 401/403 for gated or private repositories the token can't access.
+fn check_gated(response: &reqwest::Response, url: &str) -> Result<()> {
+    let status = response.status();
+    if is_huggingface_url(url) && (status == reqwest::StatusCode::UNAUTHORIZED || status == reqwest::StatusCode::FORBIDDEN) {
+        let hint = if huggingface_token().is_some() {
+            "the access token belongs to an account that hasn't accepted its terms on the model page"
+        } else {
+            "accept its terms on the model page and add an access token from that account in Settings → Network"
+        };
+        return Err(anyhow::anyhow!("Hugging Face refused the download (HTTP {}): the repository is gated or private; {}", status.as_u16(), hint));
+    }
+    Ok(())
+}
+
+/// What an unfinished download was fetching, saved next to its `.part` file as
+/// `<name>.onnx.part.json` so it can be resumed after a restart.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DownloadRecord {
+    pub name: String,
+    pub urls: Vec<String>,
+    #[serde(default)]
+    pub sha256: Option<String>,
+    pub started_at: chrono::DateTime<chrono::Utc>,
+    /// Full size announced by the server, once the download got a response.
+    #[serde(default)]
+    pub expected_size: Option<u64>,
+    /// Strong ETag of the file the part was downloaded from. A resume sends it as `If-Range`,
+    /// so a file replaced on the server comes back whole instead of appended to the old bytes.
+    #[serde(default)]
+    pub etag: Option<String>,
+}
+
+/// A `.part` file left in the downloads directory by a download that didn't finish.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PartialDownload {
+    pub part_path: PathBuf,
+    pub bytes: u64,
+    /// Missing for downloads started before records were kept.
+    pub record: Option<DownloadRecord>,
+}
+
+impl PartialDownload {
+    /// The model name the download was started under, or the file name without `.onnx.part`.
+    pub fn name(&self) -> String {
+        if let Some(record) = &self.record {
+            return record.name.clone();
+        }
+        let file_name = self.part_path.file_name().unwrap_or_default().to_string_lossy();
+        file_name.trim_end_matches(".part").trim_end_matches(".onnx").to_string()
+    }
+
+    fn record_path(part_path: &Path) -> PathBuf {
+        part_path.with_extension("part.json")
+    }
+
+    fn read_record(part_path: &Path) -> Option<DownloadRecord> {
+        std::fs::read_to_string(Self::record_path(part_path)).ok().and_then(|d| serde_json::from_str(&d).ok())
+    }
+
+    /// Add the size and ETag the server announced to the download's record, if it has one.
+    fn note_response(part_path: &Path, size: Option<u64>, etag: Option<String>) {
+        let Some(record) = Self::read_record(part_path) else { return };
+        let updated = DownloadRecord { expected_size: size.or(record.expected_size), etag, ..record.clone() };
+        if updated != record {
+            if let Ok(data) = serde_json::to_string_pretty(&updated) {
+                let _ = std::fs::write(Self::record_path(part_path), data);
+            }
+        }
+    }
+
+    /// Delete the partial file and its record.
+    pub fn discard(&self) -> Result<()> {
+        match std::fs::remove_file(&self.part_path) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e).with_context(|| format!("Failed to delete {}", self.part_path.display())),
+        }
+        let _ = std::fs::remove_file(Self::record_path(&self.part_path));
+        Ok(())
+    }
+}
+
+/// Unfinished model downloads in `dir` (the directory downloads go to).
+pub fn partial_downloads(dir: &Path) -> Vec<PartialDownload> {
+    let Ok(entries) = std::fs::read_dir(dir) else { return Vec::new() };
+    let mut partials: Vec<PartialDownload> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && path.to_string_lossy().ends_with(".onnx.part"))
+        .map(|part_path| {
+            let record = std::fs::read_to_string(PartialDownload::record_path(&part_path))
+                .ok()
+                .and_then(|data| serde_json::from_str(&data).ok());
+            let bytes = std::fs::metadata(&part_path).map(|m| m.len()).unwrap_or(0);
+            PartialDownload { part_path, bytes, record }
+        })
+        .collect();
+    partials.sort_by(|a, b| a.part_path.cmp(&b.part_path));
+    partials
+}
 
 pub struct ModelManager {
-    models_dir: PathBuf,
+    /// Scanned in order; the first directory also receives downloads.
+    models_dirs: Vec<PathBuf>,
     available_models: Vec<ModelInfo>,
+    /// Proxy, certificates and timeouts for every download.
+    network: crate::config::NetworkSettings,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -17,18 +213,51 @@ pub struct ModelInfo {
     pub supported_providers: Vec<ExecutionProvider>,
     pub description: String,
     pub quantization: Option<QuantizationType>,
+    /// Directory the model was found in.
+    #[serde(default)]
+    pub source_dir: Option<PathBuf>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Progress of a system model scan (`detect_system_models_streaming`).
+#[derive(Debug, Clone)]
+pub enum SystemScanEvent {
+    /// About to scan `location`, the `index`-th of `total`.
+    Scanning { location: String, index: usize, total: usize },
+    Found(ModelInfo),
+    Done { found: usize },
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ModelType {
     LanguageModel,
+    #[default]
     ChatModel,
     CodeModel,
     MultiModal,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl ModelType {
+    /// Best guess from the model file's name and the folder it sits in.
+    pub fn guess(path: &Path) -> Self {
+        let lower_name = path.file_stem().map(|s| s.to_string_lossy().to_lowercase()).unwrap_or_default();
+        let path_str = path.to_string_lossy().to_lowercase();
+
+        if lower_name.contains("code") || lower_name.contains("programming") {
+            Self::CodeModel
+        } else if lower_name.contains("chat") || lower_name.contains("instruct") ||
+                  lower_name.contains("silica") || path_str.contains("copilot") {
+            Self::ChatModel
+        } else if lower_name.contains("vision") || lower_name.contains("multimodal") {
+            Self::MultiModal
+        } else {
+            Self::LanguageModel
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub enum QuantizationType {
+    #[default]
     FP32,
     FP16,
     INT8,
@@ -38,31 +267,75 @@ pub enum QuantizationType {
 
 impl ModelManager {
     pub fn new<P: AsRef<Path>>(models_dir: P) -> Result<Self> {
-        let models_dir = models_dir.as_ref().to_path_buf();
-        std::fs::create_dir_all(&models_dir)?;
-        
+        Self::with_directories(vec![models_dir.as_ref().to_path_buf()])
+    }
+
+    /// Manager over several model directories. Only the first (download target) is created;
+    /// the others may live on removable drives and are skipped while missing.
+    pub fn with_directories(models_dirs: Vec<PathBuf>) -> Result<Self> {
+        let primary = models_dirs.first().ok_or_else(|| anyhow::anyhow!("No model directories configured"))?;
+        std::fs::create_dir_all(primary)?;
+
         let mut manager = Self {
-            models_dir,
+            models_dirs,
             available_models: Vec::new(),
+            network: crate::config::NetworkSettings::default(),
         };
-        
+
         manager.scan_models()?;
         Ok(manager)
     }
 
-    /// Download an auxiliary file (e.g., tokenizer JSON) to the specified destination path.
-    /// This method overwrites any existing file.
-    pub async fn download_aux_file(&self, url: &str, dest_path: &Path) -> Result<()> {
+    /// Network settings (from the app config) used by later downloads.
+    pub fn set_network(&mut self, network: crate::config::NetworkSettings) {
+        self.network = network;
+    }
+
+    /// Replace the scanned directories and rescan.
+    pub fn set_directories(&mut self, models_dirs: Vec<PathBuf>) -> Result<()> {
+        if models_dirs.is_empty() {
+            return Err(anyhow::anyhow!("No model directories configured"));
+        }
+        std::fs::create_dir_all(&models_dirs[0])?;
+        self.models_dirs = models_dirs;
+        self.scan_models()
+    }
+
+    /// Content-addressed cache for files shared between models (tokenizers, configs).
+    pub fn blob_cache(&self) -> super::blob_cache::BlobCache {
+        super::blob_cache::BlobCache::new(self.get_models_directory().join(".blobs"))
+    }
+
+    /// Download an auxiliary file (e.g., tokenizer JSON) to the specified destination path,
+    /// overwriting any existing file. Files go through the [`blob_cache`](Self::blob_cache):
+    /// one already downloaded for another model (same digest, or same URL when no digest is
+    /// known and the server confirms its ETag) is copied instead of fetched again, and a
+    /// partial download never reaches `dest_path`.
+    pub async fn download_aux_file(&self, url: &str, expected_sha256: Option<&str>, dest_path: &Path) -> Result<()> {
         use futures_util::StreamExt;
         use tokio::fs::OpenOptions;
         use tokio::io::AsyncWriteExt;
 
-        if let Some(parent) = dest_path.parent() {
-            std::fs::create_dir_all(parent)?;
+        let cache = self.blob_cache();
+        if let Some(blob) = expected_sha256.and_then(|sha256| cache.lookup(sha256)) {
+            super::blob_cache::BlobCache::copy_into(&blob, dest_path)?;
+            tracing::info!("Reused cached {} for {}", blob.display(), dest_path.display());
+            return Ok(());
         }
 
-        let client = reqwest::Client::new();
-        let response = client.get(url).send().await?;
+        let client = self.network.http_client()?;
+        let mut request = authorize(client.get(url), url);
+        let cached = if expected_sha256.is_none() { cache.lookup_url(url) } else { None };
+        if let Some((_, etag)) = &cached {
+            request = request.header(reqwest::header::IF_NONE_MATCH, etag.as_str());
+        }
+        let response = request.send().await?;
+        if let Some((blob, _)) = cached.filter(|_| response.status() == reqwest::StatusCode::NOT_MODIFIED) {
+            super::blob_cache::BlobCache::copy_into(&blob, dest_path)?;
+            tracing::info!("Reused cached {} for {} (unchanged on the server)", blob.display(), dest_path.display());
+            return Ok(());
+        }
+        check_gated(&response, url)?;
         if !response.status().is_success() {
             return Err(anyhow::anyhow!(
                 "Failed to download aux file: HTTP {}",
@@ -70,19 +343,40 @@ impl ModelManager {
             ));
         }
 
+        let expected_size = response.content_length();
+        let etag = response.headers().get(reqwest::header::ETAG).and_then(|v| v.to_str().ok()).map(str::to_string);
+        let part_path = cache.staging_path()?;
         let mut file = OpenOptions::new()
             .create(true)
             .write(true)
             .truncate(true)
-            .open(dest_path)
+            .open(&part_path)
             .await?;
 
         let mut stream = response.bytes_stream();
         while let Some(chunk) = stream.next().await {
-            let chunk = chunk?;
+            let chunk = match chunk {
+                Ok(chunk) => chunk,
+                Err(e) => {
+                    drop(file);
+                    let _ = tokio::fs::remove_file(&part_path).await;
+                    return Err(e.into());
+                }
+            };
             file.write_all(&chunk).await?;
         }
         file.flush().await?;
+        drop(file);
+        let received = std::fs::metadata(&part_path)?.len();
+        if let Some(expected) = expected_size.filter(|expected| *expected != received) {
+            let _ = tokio::fs::remove_file(&part_path).await;
+            return Err(TruncatedDownload { received, expected }).with_context(|| format!("Incomplete download of {url}"));
+        }
+
+        let url_owned = url.to_string();
+        let expected = expected_sha256.map(str::to_string);
+        let blob = tokio::task::spawn_blocking(move || cache.insert(&part_path, &url_owned, expected.as_deref(), etag.as_deref())).await??;
+        super::blob_cache::BlobCache::copy_into(&blob, dest_path)?;
 
         tracing::info!("Downloaded aux file to {}", dest_path.display());
         Ok(())
@@ -90,18 +384,33 @@ impl ModelManager {
 
     pub fn scan_models(&mut self) -> Result<()> {
         self.available_models.clear();
-        
-        if !self.models_dir.exists() {
-            return Ok(());
-        }
+        let mut seen = std::collections::HashSet::new();
 
-        for entry in std::fs::read_dir(&self.models_dir)? {
-            let entry = entry?;
-            let path = entry.path();
-            
-            if path.is_file() && path.extension().and_then(|s| s.to_str()) == Some("onnx") {
-                if let Ok(model_info) = self.analyze_model(&path) {
-                    self.available_models.push(model_info);
+        for dir in &self.models_dirs {
+            if !dir.is_dir() {
+                continue;
+            }
+            let entries = match std::fs::read_dir(dir) {
+                Ok(entries) => entries,
+                Err(e) => {
+                    tracing::warn!("Skipping model directory {}: {}", dir.display(), e);
+                    continue;
+                }
+            };
+
+            for entry in entries.flatten() {
+                let path = entry.path();
+
+                if path.is_file() && path.extension().and_then(|s| s.to_str()) == Some("onnx") {
+                    // The same directory may be listed twice (e.g. via a symlink)
+                    let key = path.canonicalize().unwrap_or_else(|_| path.clone());
+                    if !seen.insert(key) {
+                        continue;
+                    }
+                    if let Ok(mut model_info) = self.analyze_model(&path) {
+                        model_info.source_dir = Some(dir.clone());
+                        self.available_models.push(model_info);
+                    }
                 }
             }
         }
@@ -109,6 +418,31 @@ impl ModelManager {
         Ok(())
     }
 
+    /// Re-analyze one model file (from a watcher event) and insert or update it in the list.
+    pub fn upsert_model_file(&mut self, path: &Path) -> Result<()> {
+        let Some(dir) = self.models_dirs.iter().find(|d| path.parent() == Some(d.as_path())).cloned() else {
+            return Ok(()); // Not in a configured directory
+        };
+        if !path.is_file() {
+            self.remove_model_file(path);
+            return Ok(());
+        }
+        let mut model_info = self.analyze_model(path)?;
+        model_info.source_dir = Some(dir);
+        match self.available_models.iter_mut().find(|m| m.path == path) {
+            Some(existing) => *existing = model_info,
+            None => self.available_models.push(model_info),
+        }
+        Ok(())
+    }
+
+    /// Drop a model whose file disappeared. Returns whether it was listed.
+    pub fn remove_model_file(&mut self, path: &Path) -> bool {
+        let before = self.available_models.len();
+        self.available_models.retain(|m| m.path != path);
+        self.available_models.len() != before
+    }
+
     fn analyze_model(&self, path: &Path) -> Result<ModelInfo> {
         let metadata = std::fs::metadata(path)?;
         let name = path.file_stem()
@@ -157,6 +491,7 @@ impl ModelManager {
             supported_providers,
             description: format!("ONNX model loaded from {}", path.display()),
             quantization,
+            source_dir: path.parent().map(Path::to_path_buf),
         })
     }
 
@@ -176,124 +511,282 @@ impl ModelManager {
         self.download_model_with_verify_and_progress::<fn(u64, u64, f64)>(url, name, expected_sha256, None).await
     }
 
+    /// Download from `urls` in order. Transient failures are retried with exponential backoff
+    /// up to [`ATTEMPTS_PER_URL`] times per URL, other failures move straight on to the next
+    /// mirror. The `.part` file carries over, so each attempt resumes where the last stopped.
+    pub async fn download_model_from_mirrors<F, R>(
+        &mut self,
+        urls: &[String],
+        name: &str,
+        expected_sha256: Option<&str>,
+        mut progress_callback: F,
+        mut on_retry: R,
+    ) -> Result<PathBuf>
+    where
+        F: FnMut(u64, u64, f64) + Send,
+        R: FnMut(DownloadRetry) + Send,
+    {
+        let (_, part_path) = self.download_paths(name);
+        let record_path = PartialDownload::record_path(&part_path);
+        let record = DownloadRecord {
+            name: name.to_string(),
+            urls: urls.to_vec(),
+            sha256: expected_sha256.map(str::to_string),
+            started_at: chrono::Utc::now(),
+            expected_size: None,
+            etag: None,
+        };
+        let write_record = || -> Result<()> {
+            std::fs::create_dir_all(self.get_models_directory())?;
+            std::fs::write(&record_path, serde_json::to_string_pretty(&record)?)?;
+            Ok(())
+        };
+        if let Err(e) = write_record() {
+            tracing::warn!("Failed to record the download of {}: {}", name, e);
+        }
+
+        let mut last_error = anyhow::anyhow!("No download URL for {}", name);
+        for (url_index, url) in urls.iter().enumerate() {
+            for attempt in 1..=ATTEMPTS_PER_URL {
+                match self.download_model_with_verify_and_progress(url, name, expected_sha256, Some(&mut progress_callback)).await {
+                    Ok(path) => {
+                        let _ = std::fs::remove_file(&record_path);
+                        return Ok(path);
+                    }
+                    Err(e) => {
+                        tracing::warn!("Download of {} from {} failed (attempt {}): {:#}", name, url, attempt, e);
+                        let retry = is_transient(&e) && attempt < ATTEMPTS_PER_URL;
+                        let next = if retry { Some((url_index, attempt + 1)) } else { (url_index + 1 < urls.len()).then_some((url_index + 1, 1)) };
+                        if let Some((url_index, attempt)) = next {
+                            let delay = if retry { backoff_delay(attempt) } else { std::time::Duration::ZERO };
+                            on_retry(DownloadRetry { attempt, url_index, url_count: urls.len(), delay, error: format!("{e:#}") });
+                            tokio::time::sleep(delay).await;
+                        }
+                        last_error = e;
+                        if !retry {
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+        Err(last_error)
+    }
+
+    /// Where a download of `name` ends up, and the `.part` file it is written to until then.
+    fn download_paths(&self, name: &str) -> (PathBuf, PathBuf) {
+        let sanitized_name = crate::utils::sanitize_filename(name);
+        let final_path = crate::utils::ensure_file_extension(&self.get_models_directory().join(&sanitized_name), "onnx");
+        let part_path = final_path.with_extension("onnx.part");
+        (final_path, part_path)
+    }
+
     pub async fn download_model_with_verify_and_progress<F>(
         &mut self, 
         url: &str, 
         name: &str, 
         expected_sha256: Option<&str>,
-        mut progress_callback: Option<F>
+        progress_callback: Option<F>
     ) -> Result<PathBuf> 
     where
-        F: FnMut(u64, u64, f64) + Send + 'static,
+        F: FnMut(u64, u64, f64) + Send,
+    {
+        // Prepare paths
+        let (final_path, part_path) = self.download_paths(name);
+
+        // Ensure the models directory exists
+        std::fs::create_dir_all(self.get_models_directory())?;
+
+        self.download_to(url, &final_path, &part_path, expected_sha256, progress_callback).await?;
+
+        // Rescan models after download
+        self.scan_models()?;
+        
+        Ok(final_path)
+    }
+
+    /// Re-download a model its manifest reports as damaged, from the URL it came from. A
+    /// file shorter than recorded only fetches the missing range; anything else starts over.
+    /// The result has to match the digest in the manifest.
+    pub async fn repair_model<F>(&mut self, model_path: &Path, progress_callback: Option<F>) -> Result<()>
+    where
+        F: FnMut(u64, u64, f64) + Send,
+    {
+        let manifest = super::integrity::read_manifest(model_path).context("The model has no manifest to repair it from")?;
+        let url = manifest.source_url.clone().context("Where the model was downloaded from isn't recorded")?;
+        let part_path = model_path.with_extension("onnx.part");
+        // Recorded like any download, so a repair cut short shows up as an interrupted download
+        let record = DownloadRecord {
+            name: model_path.file_stem().unwrap_or_default().to_string_lossy().to_string(),
+            urls: vec![url.clone()],
+            sha256: Some(manifest.sha256.clone()),
+            started_at: chrono::Utc::now(),
+            expected_size: Some(manifest.size),
+            etag: None,
+        };
+        std::fs::write(PartialDownload::record_path(&part_path), serde_json::to_string_pretty(&record)?)?;
+        let size = std::fs::metadata(model_path)?.len();
+        if size < manifest.size {
+            // A copy: the model stays where it is until the repaired file passes the digest check
+            std::fs::copy(model_path, &part_path)?;
+            tracing::info!("Repairing {}: fetching the missing {} bytes", model_path.display(), manifest.size - size);
+        } else {
+            let _ = std::fs::remove_file(&part_path);
+            tracing::info!("Repairing {}: downloading it again", model_path.display());
+        }
+        self.download_to(&url, model_path, &part_path, Some(&manifest.sha256), progress_callback).await?;
+        self.scan_models()
+    }
+
+    /// Download `url` into `part_path`, resuming from what is already there, check its size
+    /// and digest, and move it to `final_path`.
+    async fn download_to<F>(
+        &self,
+        url: &str,
+        final_path: &Path,
+        part_path: &Path,
+        expected_sha256: Option<&str>,
+        mut progress_callback: Option<F>,
+    ) -> Result<()>
+    where
+        F: FnMut(u64, u64, f64) + Send,
     {
         use tokio::io::AsyncWriteExt;
         use tokio::fs::OpenOptions;
         use futures_util::StreamExt;
-        
-        // Prepare paths
-        let sanitized_name = crate::utils::sanitize_filename(name);
-        let final_path = crate::utils::ensure_file_extension(&self.models_dir.join(&sanitized_name), "onnx");
-        let part_path = final_path.with_extension("onnx.part");
 
-        // Ensure the models directory exists
-        std::fs::create_dir_all(&self.models_dir)?;
+        let name = final_path.file_name().unwrap_or_default().to_string_lossy().to_string();
 
         // Determine resume offset
         let mut resume_from: u64 = 0;
         if part_path.exists() {
-            if let Ok(meta) = std::fs::metadata(&part_path) {
+            if let Ok(meta) = std::fs::metadata(part_path) {
                 resume_from = meta.len();
                 tracing::info!("Resuming download for {} at {} bytes", name, resume_from);
             }
         }
 
-        // Build request (Range if resuming)
-        let client = reqwest::Client::new();
-        let mut req = client.get(url);
+        // Build request (Range if resuming, only from the same file when its ETag is known)
+        let client = self.network.http_client()?;
+        let mut req = authorize(client.get(url), url);
+        let mut etag = PartialDownload::read_record(part_path).and_then(|r| r.etag);
         if resume_from > 0 {
             req = req.header(reqwest::header::RANGE, format!("bytes={}-", resume_from));
+            if let Some(etag) = &etag {
+                req = req.header(reqwest::header::IF_RANGE, etag);
+            }
         }
         let response = req.send().await?;
+        check_gated(&response, url)?;
+        let content_range = response
+            .headers()
+            .get(reqwest::header::CONTENT_RANGE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(content_range_total);
 
-        if !(response.status().is_success() || response.status() == reqwest::StatusCode::PARTIAL_CONTENT) {
-            return Err(anyhow::anyhow!("Failed to download model: HTTP {}", response.status()));
-        }
+        // 416: nothing left after `resume_from`, so the part file is complete or too long,
+        // which the size and digest checks below sort out
+        let range_done = resume_from > 0 && response.status() == reqwest::StatusCode::RANGE_NOT_SATISFIABLE;
+        let mut expected_size = content_range;
+        let response = if range_done {
+            None
+        } else {
+            // 4xx/5xx become `reqwest::Error`s so retries can tell server errors from missing files
+            let response = response.error_for_status().context("Failed to download model")?;
+            if resume_from > 0 && response.status() != reqwest::StatusCode::PARTIAL_CONTENT {
+                // The file changed since the part was downloaded, or the server ignored the
+                // Range header; either way it sends the whole file, and appending it would
+                // corrupt the download
+                tracing::info!("Server sent the whole file (changed, or no resume support); restarting {} from the beginning", name);
+                resume_from = 0;
+                tokio::fs::File::create(part_path).await?;
+                expected_size = None;
+            }
+            expected_size = expected_size.or_else(|| response.content_length().map(|len| resume_from + len));
+            // Weak ETags can't be used with If-Range
+            etag = response
+                .headers()
+                .get(reqwest::header::ETAG)
+                .and_then(|v| v.to_str().ok())
+                .filter(|v| !v.starts_with("W/"))
+                .map(str::to_string);
+            Some(response)
+        };
+        PartialDownload::note_response(part_path, expected_size, etag);
 
-        let total_size = response.content_length();
+        if let Some(response) = response {
+            // Open part file for append
+            let mut file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(part_path)
+                .await?;
 
-        // Open part file for append
-        let mut file = OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(&part_path)
-            .await?;
+            // Stream download with progress reporting
+            let mut downloaded = resume_from;
+            let mut stream = response.bytes_stream();
+            let start_time = std::time::Instant::now();
+            let mut last_update = start_time;
 
-        // Stream download with progress reporting
-        let mut downloaded = resume_from;
-        let mut stream = response.bytes_stream();
-        let start_time = std::time::Instant::now();
-        let mut last_update = start_time;
-        
-        while let Some(chunk) = stream.next().await {
-            let chunk = chunk?;
-            file.write_all(&chunk).await?;
-            downloaded += chunk.len() as u64;
-
-            // Report progress every 100ms or so
-            let now = std::time::Instant::now();
-            if now.duration_since(last_update).as_millis() >= 100 {
-                if let Some(total) = total_size {
-                    let total_with_resume = resume_from + total;
-                    let progress = (downloaded as f64) / (total_with_resume as f64);
+            while let Some(chunk) = stream.next().await {
+                let chunk = chunk?;
+                file.write_all(&chunk).await?;
+                downloaded += chunk.len() as u64;
+
+                // Report progress every 100ms or so
+                let now = std::time::Instant::now();
+                if now.duration_since(last_update).as_millis() >= 100 {
                     let elapsed = now.duration_since(start_time).as_secs_f64();
                     let speed = if elapsed > 0.0 { (downloaded - resume_from) as f64 / elapsed } else { 0.0 };
-                    
-                    // Call progress callback if provided
                     if let Some(ref mut callback) = progress_callback {
-                        callback(downloaded, total_with_resume, speed);
+                        callback(downloaded, expected_size.unwrap_or(0), speed);
                     }
-                    
-                    tracing::debug!("Download progress for {}: {:.1}% ({:.1} KB/s)", name, progress * 100.0, speed / 1024.0);
-                } else {
-                    // Unknown total size
-                    if let Some(ref mut callback) = progress_callback {
-                        let elapsed = now.duration_since(start_time).as_secs_f64();
-                        let speed = if elapsed > 0.0 { (downloaded - resume_from) as f64 / elapsed } else { 0.0 };
-                        callback(downloaded, 0, speed);
+                    match expected_size {
+                        Some(total) => tracing::debug!("Download progress for {}: {:.1}% ({:.1} KB/s)", name, downloaded as f64 / total as f64 * 100.0, speed / 1024.0),
+                        None => tracing::debug!("Downloaded {} bytes for {}", downloaded, name),
                     }
-                    tracing::debug!("Downloaded {} bytes for {}", downloaded, name);
+                    last_update = now;
                 }
-                last_update = now;
             }
+            file.flush().await?;
         }
-        file.flush().await?;
 
-        // Verify SHA256 if provided
-        if let Some(expected) = expected_sha256 {
-            let mut hasher = Sha256::new();
-            let mut f = std::fs::File::open(&part_path)?;
-            let mut buf = [0u8; 1024 * 64];
-            loop {
-                let n = std::io::Read::read(&mut f, &mut buf)?;
-                if n == 0 { break; }
-                hasher.update(&buf[..n]);
+        // A dropped connection can end the body early without an error
+        let received = std::fs::metadata(part_path)?.len();
+        match expected_size {
+            Some(expected) if received < expected => {
+                // Keep the part file: the next attempt fetches only the missing range
+                return Err(TruncatedDownload { received, expected }.into());
             }
-            let digest = hasher.finalize();
-            let digest_hex = hex::encode(digest);
+            Some(expected) if received > expected => {
+                let _ = tokio::fs::remove_file(part_path).await;
+                return Err(anyhow::anyhow!("{} is {} bytes, but the server announced {}; the download was discarded", name, received, expected));
+            }
+            _ => {}
+        }
+
+        // Hash the download; verify against the catalog digest if provided
+        let digest_path = part_path.to_path_buf();
+        let digest_hex = tokio::task::spawn_blocking(move || super::integrity::compute_sha256(&digest_path)).await??;
+        if let Some(expected) = expected_sha256 {
             if digest_hex.to_lowercase() != expected.to_lowercase() {
+                // Resuming would only append to the bad data; the next attempt starts over
+                let _ = tokio::fs::remove_file(part_path).await;
                 return Err(anyhow::anyhow!("SHA256 mismatch for {}: expected {}, got {}", name, expected, digest_hex));
             }
             tracing::info!("SHA256 verified for {}", name);
         }
 
         // Move part to final
-        tokio::fs::rename(&part_path, &final_path).await?;
+        tokio::fs::rename(part_path, final_path).await?;
+        let _ = std::fs::remove_file(PartialDownload::record_path(part_path));
         tracing::info!("Successfully downloaded model: {}", final_path.display());
 
-        // Rescan models after download
-        self.scan_models()?;
-        
-        Ok(final_path)
+        // Record the digest in a sidecar manifest for later integrity checks
+        if let Err(e) = super::integrity::write_manifest(final_path, &digest_hex, Some(url)) {
+            tracing::warn!("Failed to write manifest for {}: {}", name, e);
+        }
+        Ok(())
     }
 
     pub fn add_model_from_url(&self, _url: &str, _name: &str) -> Result<PathBuf> {
@@ -301,38 +794,71 @@ impl ModelManager {
         Err(anyhow::anyhow!("Use download_model() async method instead"))
     }
 
+    /// Download target (the first configured directory).
     pub fn get_models_directory(&self) -> &Path {
-        &self.models_dir
+        &self.models_dirs[0]
+    }
+
+    pub fn get_models_directories(&self) -> &[PathBuf] {
+        &self.models_dirs
     }
 
     /// Detect pre-installed AI models on Windows Copilot+ PCs and other systems
+    #[allow(dead_code)]
     pub fn detect_system_models(&self) -> Vec<ModelInfo> {
         let mut detected_models = Vec::new();
-        
+        self.detect_system_models_streaming(|evt| {
+            if let SystemScanEvent::Found(model) = evt {
+                detected_models.push(model);
+            }
+        });
+        detected_models
+    }
+
+    /// Same as `detect_system_models`, but reports each location as it is scanned and each
+    /// model as soon as it is found. Blocking; run it off the UI thread.
+    pub fn detect_system_models_streaming(&self, mut on_event: impl FnMut(SystemScanEvent)) {
+        let locations = self.system_model_locations();
+        let total = locations.len();
+        let mut found = 0;
+        for (index, (location, category)) in locations.into_iter().enumerate() {
+            on_event(SystemScanEvent::Scanning { location: location.clone(), index, total });
+            for model in self.scan_directory_for_models(&location, category) {
+                found += 1;
+                on_event(SystemScanEvent::Found(model));
+            }
+        }
+        on_event(SystemScanEvent::Done { found });
+    }
+
+    /// Every (directory, category) pair searched for system models on this platform.
+    fn system_model_locations(&self) -> Vec<(String, &'static str)> {
+        let mut locations = Vec::new();
+
         // Windows Copilot+ PC model locations
         if cfg!(target_os = "windows") {
-            detected_models.extend(self.scan_windows_system_models());
+            locations.extend(self.windows_system_locations());
         }
         
         // macOS system models
         if cfg!(target_os = "macos") {
-            detected_models.extend(self.scan_macos_system_models());
+            locations.extend(self.macos_system_locations());
         }
         
         // Linux system models
         if cfg!(target_os = "linux") {
-            detected_models.extend(self.scan_linux_system_models());
+            locations.extend(self.linux_system_locations());
         }
         
         // Common cross-platform locations
-        detected_models.extend(self.scan_common_model_locations());
+        locations.extend(self.common_model_locations());
         
-        detected_models
+        locations
     }
     
     #[cfg(target_os = "windows")]
-    fn scan_windows_system_models(&self) -> Vec<ModelInfo> {
-        let mut models = Vec::new();
+    fn windows_system_locations(&self) -> Vec<(String, &'static str)> {
+        let mut locations = Vec::new();
         
         // Phi Silica model locations on Copilot+ PCs
         let phi_locations = vec![
@@ -360,7 +886,7 @@ impl ModelManager {
         ];
         
         for location in phi_locations {
-            models.extend(self.scan_directory_for_models(location, "Phi-3 Silica (System)"));
+            locations.push((location.to_string(), "Phi-3 Silica (System)"));
         }
         
         // Other known Windows AI model locations
@@ -371,16 +897,14 @@ impl ModelManager {
         ];
         
         for location in general_locations {
-            models.extend(self.scan_directory_for_models(location, "System Model"));
+            locations.push((location.to_string(), "System Model"));
         }
         
-        models
+        locations
     }
     
     #[cfg(target_os = "macos")]
-    fn scan_macos_system_models(&self) -> Vec<ModelInfo> {
-        let mut models = Vec::new();
-        
+    fn macos_system_locations(&self) -> Vec<(String, &'static str)> {
         let macos_locations = vec![
             "/System/Library/PrivateFrameworks/CoreML.framework/Versions/A/Resources/Models/",
             "/Applications/Xcode.app/Contents/Developer/Platforms/MacOSX.platform/Developer/Library/CoreML/Models/",
@@ -388,17 +912,11 @@ impl ModelManager {
             "/opt/intel/openvino/models/",
         ];
         
-        for location in macos_locations {
-            models.extend(self.scan_directory_for_models(location, "System Model"));
-        }
-        
-        models
+        macos_locations.into_iter().map(|l| (l.to_string(), "System Model")).collect()
     }
     
     #[cfg(target_os = "linux")]
-    fn scan_linux_system_models(&self) -> Vec<ModelInfo> {
-        let mut models = Vec::new();
-        
+    fn linux_system_locations(&self) -> Vec<(String, &'static str)> {
         let linux_locations = vec![
             "/usr/share/onnxruntime/models/",
             "/usr/local/share/onnxruntime/models/",
@@ -407,24 +925,20 @@ impl ModelManager {
             "/var/lib/ai/models/",
         ];
         
-        for location in linux_locations {
-            models.extend(self.scan_directory_for_models(location, "System Model"));
-        }
-        
-        models
+        linux_locations.into_iter().map(|l| (l.to_string(), "System Model")).collect()
     }
     
     #[cfg(not(target_os = "windows"))]
-    fn scan_windows_system_models(&self) -> Vec<ModelInfo> { Vec::new() }
+    fn windows_system_locations(&self) -> Vec<(String, &'static str)> { Vec::new() }
     
     #[cfg(not(target_os = "macos"))]
-    fn scan_macos_system_models(&self) -> Vec<ModelInfo> { Vec::new() }
+    fn macos_system_locations(&self) -> Vec<(String, &'static str)> { Vec::new() }
     
     #[cfg(not(target_os = "linux"))]
-    fn scan_linux_system_models(&self) -> Vec<ModelInfo> { Vec::new() }
+    fn linux_system_locations(&self) -> Vec<(String, &'static str)> { Vec::new() }
     
-    fn scan_common_model_locations(&self) -> Vec<ModelInfo> {
-        let mut models = Vec::new();
+    fn common_model_locations(&self) -> Vec<(String, &'static str)> {
+        let mut locations = Vec::new();
         
         // Common development and user locations
         if let Ok(home_dir) = std::env::var("HOME").or_else(|_| std::env::var("USERPROFILE")) {
@@ -437,11 +951,11 @@ impl ModelManager {
             ];
             
             for location in common_locations {
-                models.extend(self.scan_directory_for_models(&location, "User Model"));
+                locations.push((location, "User Model"));
             }
         }
         
-        models
+        locations
     }
     
     fn scan_directory_for_models(&self, directory: &str, model_category: &str) -> Vec<ModelInfo> {
@@ -499,7 +1013,7 @@ impl ModelManager {
         let display_name = self.get_friendly_model_name(&file_name, path);
         
         // Determine model type based on path and filename
-        let model_type = self.determine_model_type(&file_name, path);
+        let model_type = ModelType::guess(path);
         
         // Determine quantization from filename
         let quantization = self.determine_quantization(&file_name);
@@ -515,6 +1029,7 @@ impl ModelManager {
             supported_providers,
             description: format!("{} - Detected at {}", category, path.display()),
             quantization,
+            source_dir: path.parent().map(Path::to_path_buf),
         })
     }
     
@@ -553,22 +1068,6 @@ impl ModelManager {
         filename.to_string()
     }
     
-    fn determine_model_type(&self, filename: &str, path: &Path) -> ModelType {
-        let lower_name = filename.to_lowercase();
-        let path_str = path.to_string_lossy().to_lowercase();
-        
-        if lower_name.contains("code") || lower_name.contains("programming") {
-            ModelType::CodeModel
-        } else if lower_name.contains("chat") || lower_name.contains("instruct") || 
-                  lower_name.contains("silica") || path_str.contains("copilot") {
-            ModelType::ChatModel
-        } else if lower_name.contains("vision") || lower_name.contains("multimodal") {
-            ModelType::MultiModal
-        } else {
-            ModelType::LanguageModel
-        }
-    }
-    
     fn determine_quantization(&self, filename: &str) -> Option<QuantizationType> {
         let lower_name = filename.to_lowercase();
         
@@ -649,4 +1148,63 @@ impl ModelManager {
 
         format!("{:.1} {}", size, UNITS[unit_index])
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_backoff_doubles_up_to_the_cap() {
+        assert_eq!(backoff_delay(2), Duration::from_secs(1));
+        assert_eq!(backoff_delay(3), Duration::from_secs(2));
+        assert_eq!(backoff_delay(4), Duration::from_secs(4));
+        assert_eq!(backoff_delay(10), MAX_BACKOFF);
+        assert_eq!(backoff_delay(u32::MAX), MAX_BACKOFF);
+    }
+
+    #[test]
+    fn test_content_range_total() {
+        assert_eq!(content_range_total("bytes 100-199/1000"), Some(1000));
+        assert_eq!(content_range_total("bytes */1000"), Some(1000));
+        assert_eq!(content_range_total("bytes 0-99/*"), None);
+        assert!(is_transient(&TruncatedDownload { received: 5, expected: 10 }.into()));
+    }
+
+    #[test]
+    fn test_partial_downloads_are_found() {
+        let dir = tempfile::tempdir().unwrap();
+        let record = DownloadRecord {
+            name: "Phi-3 mini".into(),
+            urls: vec!["https://example.com/phi3.onnx".into()],
+            sha256: None,
+            started_at: chrono::Utc::now(),
+            expected_size: Some(10),
+            etag: None,
+        };
+        std::fs::write(dir.path().join("Phi-3_mini.onnx.part"), b"1234").unwrap();
+        std::fs::write(dir.path().join("Phi-3_mini.onnx.part.json"), serde_json::to_string(&record).unwrap()).unwrap();
+        std::fs::write(dir.path().join("old.onnx.part"), b"12").unwrap();
+        std::fs::write(dir.path().join("done.onnx"), b"model").unwrap();
+
+        let partials = partial_downloads(dir.path());
+        assert_eq!(partials.len(), 2);
+        assert_eq!((partials[0].name(), partials[0].bytes, partials[0].record.is_some()), ("Phi-3 mini".to_string(), 4, true));
+        assert_eq!((partials[1].name(), partials[1].record.is_none()), ("old".to_string(), true));
+
+        partials[0].discard().unwrap();
+        assert!(!dir.path().join("Phi-3_mini.onnx.part.json").exists());
+        assert_eq!(partial_downloads(dir.path()).len(), 1);
+    }
+
+    #[test]
+    fn test_token_only_goes_to_huggingface() {
+        assert!(is_huggingface_url("https://huggingface.co/meta-llama/Llama-3.2-1B/resolve/main/model.onnx"));
+        assert!(is_huggingface_url("https://cdn-lfs.huggingface.co/repos/ab/cd"));
+        assert!(is_huggingface_url("https://hf.co/org/repo"));
+        assert!(!is_huggingface_url("https://huggingface.co.evil.example/org/repo"));
+        assert!(!is_huggingface_url("https://example.com/?next=https://huggingface.co"));
+        assert!(!is_huggingface_url("not a url"));
+    }
+}
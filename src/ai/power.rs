@@ -0,0 +1,144 @@
+//! Power modes: trading speed for heat and battery life on laptops, where sustained NPU or
+//! GPU inference soon throttles.
+//!
+//! A [`PowerMode`] adjusts the inference config a model is loaded with (thread count,
+//! execution provider, pauses between forward runs). With `follow_battery` set the mode is
+//! lowered while the machine runs on battery. sysinfo has no battery API, so the status is
+//! read from `/sys/class/power_supply` on Linux and is unknown elsewhere.
+
+use super::{ExecutionProvider, InferenceConfig};
+use serde::{Deserialize, Serialize};
+
+/// Threads used in battery saver mode.
+const SAVER_THREADS: usize = 2;
+/// Pause between forward runs in battery saver mode.
+const SAVER_STEP_DELAY_MS: u64 = 20;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PowerMode {
+    /// Every core and the fastest execution provider, no pauses.
+    Performance,
+    /// The configured settings as they are.
+    #[default]
+    Balanced,
+    /// Few threads, no discrete GPU and short pauses between runs.
+    BatterySaver,
+}
+
+impl PowerMode {
+    pub const ALL: [PowerMode; 3] = [Self::Performance, Self::Balanced, Self::BatterySaver];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Performance => "Performance",
+            Self::Balanced => "Balanced",
+            Self::BatterySaver => "Battery saver",
+        }
+    }
+
+    /// Adjust `config` for this mode before a model is loaded.
+    pub fn apply(self, config: &mut InferenceConfig) {
+        match self {
+            Self::Performance => {
+                config.session_options.intra_threads = num_cpus::get();
+                config.step_delay_ms = 0;
+            }
+            Self::Balanced => {}
+            Self::BatterySaver => {
+                config.session_options.intra_threads = SAVER_THREADS;
+                config.step_delay_ms = config.step_delay_ms.max(SAVER_STEP_DELAY_MS);
+                // NPUs are built to run cool; discrete GPUs drain a battery fastest
+                if matches!(config.execution_provider, ExecutionProvider::Cuda | ExecutionProvider::DirectML) {
+                    config.execution_provider = ExecutionProvider::Cpu;
+                    config.device_id = None;
+                    config.device_type = None;
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PowerSettings {
+    pub mode: PowerMode,
+    /// Lower the mode while running on battery.
+    pub follow_battery: bool,
+    /// Battery percentage at or below which battery saver takes over.
+    pub battery_saver_below: u8,
+}
+
+impl Default for PowerSettings {
+    fn default() -> Self {
+        Self { mode: PowerMode::default(), follow_battery: false, battery_saver_below: 30 }
+    }
+}
+
+impl PowerSettings {
+    /// The mode to use given the battery status (None when unknown or there is no battery).
+    pub fn effective_mode(&self, battery: Option<BatteryStatus>) -> PowerMode {
+        match battery {
+            Some(b) if self.follow_battery && b.discharging => {
+                if b.percent <= self.battery_saver_below {
+                    PowerMode::BatterySaver
+                } else if self.mode == PowerMode::Performance {
+                    PowerMode::Balanced
+                } else {
+                    self.mode
+                }
+            }
+            _ => self.mode,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatteryStatus {
+    pub percent: u8,
+    pub discharging: bool,
+}
+
+/// The first battery's status, if the platform reports one.
+pub fn battery_status() -> Option<BatteryStatus> {
+    #[cfg(target_os = "linux")]
+    {
+        let entries = std::fs::read_dir("/sys/class/power_supply").ok()?;
+        entries.flatten().filter(|e| e.file_name().to_string_lossy().starts_with("BAT")).find_map(|e| {
+            let read = |name: &str| std::fs::read_to_string(e.path().join(name)).ok();
+            Some(BatteryStatus {
+                percent: read("capacity")?.trim().parse::<u8>().ok()?.min(100),
+                discharging: read("status")?.trim() == "Discharging",
+            })
+        })
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_battery_lowers_the_mode() {
+        let mut config = InferenceConfig { execution_provider: ExecutionProvider::Cuda, device_id: Some(1), ..InferenceConfig::default() };
+        PowerMode::BatterySaver.apply(&mut config);
+        assert_eq!((config.execution_provider, config.device_id), (ExecutionProvider::Cpu, None));
+        assert_eq!((config.session_options.intra_threads, config.step_delay_ms), (SAVER_THREADS, SAVER_STEP_DELAY_MS));
+
+        let mut npu = InferenceConfig { execution_provider: ExecutionProvider::QNN, ..InferenceConfig::default() };
+        PowerMode::BatterySaver.apply(&mut npu);
+        assert_eq!(npu.execution_provider, ExecutionProvider::QNN);
+
+        let mut settings = PowerSettings { mode: PowerMode::Performance, ..PowerSettings::default() };
+        let on_battery = |percent| Some(BatteryStatus { percent, discharging: true });
+        assert_eq!(settings.effective_mode(on_battery(10)), PowerMode::Performance, "not following the battery");
+        settings.follow_battery = true;
+        assert_eq!(settings.effective_mode(on_battery(80)), PowerMode::Balanced);
+        assert_eq!(settings.effective_mode(on_battery(30)), PowerMode::BatterySaver);
+        assert_eq!(settings.effective_mode(Some(BatteryStatus { percent: 10, discharging: false })), PowerMode::Performance);
+        assert_eq!(settings.effective_mode(None), PowerMode::Performance);
+    }
+}
@@ -0,0 +1,81 @@
+//! Chunked prompt prefill for decoder models with a KV cache.
+//!
+//! Running a long prompt through the model in one go needs activations for every token at
+//! once, which can exceed NPU or CPU memory. Models exported with `past_key_values.*`
+//! inputs and `present.*` outputs can instead take the prompt in fixed-size segments: each
+//! run returns the attention cache for everything seen so far, and that cache is fed back
+//! with the next segment.
+
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
+
+/// Prompt tokens per forward run.
+pub const DEFAULT_CHUNK_SIZE: usize = 256;
+
+/// Token ranges to feed one run at a time; `chunk_size` 0 means the whole prompt at once.
+pub fn chunks(len: usize, chunk_size: usize) -> Vec<Range<usize>> {
+    let step = if chunk_size == 0 { len.max(1) } else { chunk_size };
+    (0..len).step_by(step).map(|start| start..(start + step).min(len)).collect()
+}
+
+/// Name of the cache input fed by a `present.*` output, e.g. `present.3.key` →
+/// `past_key_values.3.key`.
+pub fn past_input_for(output: &str) -> Option<String> {
+    let rest = output.strip_prefix("present_key_values").or_else(|| output.strip_prefix("present"))?;
+    rest.starts_with('.').then(|| format!("past_key_values{rest}"))
+}
+
+/// Prompt tokens processed so far out of the total.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PrefillProgress {
+    pub done: usize,
+    pub total: usize,
+}
+
+impl PrefillProgress {
+    pub fn fraction(&self) -> f32 {
+        if self.total == 0 { 1.0 } else { self.done as f32 / self.total as f32 }
+    }
+}
+
+/// Where a provider reports prefill progress for the UI to poll while it generates.
+#[derive(Debug, Clone, Default)]
+pub struct PrefillMonitor(Arc<Mutex<Option<PrefillProgress>>>);
+
+impl PrefillMonitor {
+    pub fn report(&self, progress: PrefillProgress) {
+        if let Ok(mut current) = self.0.lock() {
+            *current = Some(progress);
+        }
+    }
+
+    pub fn get(&self) -> Option<PrefillProgress> {
+        self.0.lock().ok().and_then(|current| *current)
+    }
+
+    pub fn clear(&self) {
+        if let Ok(mut current) = self.0.lock() {
+            *current = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunks_cover_the_prompt() {
+        assert_eq!(chunks(600, 256), vec![0..256, 256..512, 512..600]);
+        assert_eq!(chunks(100, 0), vec![0..100]);
+        assert!(chunks(0, 256).is_empty());
+    }
+
+    #[test]
+    fn test_present_outputs_map_to_past_inputs() {
+        assert_eq!(past_input_for("present.0.key").as_deref(), Some("past_key_values.0.key"));
+        assert_eq!(past_input_for("present_key_values.11.value").as_deref(), Some("past_key_values.11.value"));
+        assert_eq!(past_input_for("logits"), None);
+        assert_eq!(past_input_for("presentation"), None);
+    }
+}
@@ -0,0 +1,94 @@
+//! Reusing the attention (KV) cache between turns of a chat.
+//!
+//! Each turn's prompt starts with the previous one: the system prompt and the history only
+//! grow. After a prefill the provider keeps the KV state with the prompt's tokens, and a
+//! later prompt that starts with exactly those tokens picks the state up and only prefills
+//! what was added. The tokens are compared in full rather than by hash, since reusing the
+//! state of a different prompt would silently corrupt the reply. Each entry holds the
+//! model's cache for a whole prompt, so only a few are kept, least recently used evicted
+//! first.
+
+use std::collections::VecDeque;
+
+/// Prompts whose KV state is kept.
+pub const DEFAULT_ENTRIES: usize = 2;
+
+struct Entry<T> {
+    tokens: Vec<i64>,
+    state: T,
+}
+
+/// KV states of recent prompts, most recently used last.
+pub struct PromptCache<T> {
+    capacity: usize,
+    entries: VecDeque<Entry<T>>,
+}
+
+impl<T> PromptCache<T> {
+    /// A cache of up to `capacity` prompts; 0 keeps nothing.
+    pub fn new(capacity: usize) -> Self {
+        Self { capacity, entries: VecDeque::new() }
+    }
+
+    /// The longest cached prefix of `tokens`, with its state. A prompt identical to a cached
+    /// one doesn't match: the model has to run on at least one token to answer.
+    pub fn longest_prefix(&mut self, tokens: &[i64]) -> Option<(usize, &T)> {
+        let pos = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| e.tokens.len() < tokens.len() && tokens.starts_with(&e.tokens))
+            .max_by_key(|(_, e)| e.tokens.len())
+            .map(|(pos, _)| pos)?;
+        let entry = self.entries.remove(pos)?;
+        self.entries.push_back(entry);
+        self.entries.back().map(|e| (e.tokens.len(), &e.state))
+    }
+
+    /// Keep `state` as the KV state after `tokens`.
+    pub fn insert(&mut self, tokens: &[i64], state: T) {
+        if self.capacity == 0 {
+            return;
+        }
+        self.entries.retain(|e| e.tokens != tokens);
+        while self.entries.len() >= self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(Entry { tokens: tokens.to_vec(), state });
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_turn_reuses_the_longest_prefix() {
+        let mut cache = PromptCache::new(2);
+        let system = [1, 2, 3];
+        let turn1 = [1, 2, 3, 10, 11];
+        let turn2 = [1, 2, 3, 10, 11, 20, 21, 30];
+        cache.insert(&system, "system");
+        cache.insert(&turn1, "turn 1");
+
+        assert_eq!(cache.longest_prefix(&turn2), Some((5, &"turn 1")));
+        // Regenerating the same prompt falls back to a shorter prefix
+        assert_eq!(cache.longest_prefix(&turn1), Some((3, &"system")));
+        // Another chat with the same system prompt shares it
+        assert_eq!(cache.longest_prefix(&[1, 2, 3, 99]), Some((3, &"system")));
+        assert_eq!(cache.longest_prefix(&[1, 2, 4, 10, 11, 20]), None);
+
+        // "system" was used last, so "turn 1" goes first
+        cache.insert(&turn2, "turn 2");
+        assert_eq!(cache.longest_prefix(&[1, 2, 3, 10, 11, 20, 21, 30, 40]), Some((8, &"turn 2")));
+        assert_eq!(cache.longest_prefix(&[1, 2, 3, 10, 11, 12]), Some((3, &"system")));
+
+        let mut disabled = PromptCache::new(0);
+        disabled.insert(&system, "system");
+        assert!(disabled.longest_prefix(&turn1).is_none());
+    }
+}
@@ -7,9 +7,21 @@ use sysinfo::System;
 use ort::session::Session;
 use ort::session::builder::GraphOptimizationLevel;
 use crate::utils::system::SystemInfo;
-use ndarray::Array2;
-use ort::value::Value;
+use ndarray::{Array2, Array4, ArrayD};
+use ort::session::SessionInputValue;
+use ort::tensor::TensorElementType;
+use ort::value::{DynValue, Value};
+use super::constraint::Constraint;
+use super::decode;
+use super::inference::FinishReason;
+use super::prefill::{self, PrefillMonitor, PrefillProgress};
+use super::sampler::{self, LogitsSampler, SamplerConfig};
+use super::prompt_cache::PromptCache;
 use ort::execution_providers::{ExecutionProviderDispatch, CPUExecutionProvider, CUDAExecutionProvider, DirectMLExecutionProvider, CoreMLExecutionProvider, OpenVINOExecutionProvider};
+#[cfg(feature = "qnn_ep")]
+use ort::execution_providers::{QNNExecutionProvider, qnn::QNNPerformanceMode};
+#[cfg(feature = "nnapi_ep")]
+use ort::execution_providers::NNAPIExecutionProvider;
 
 #[allow(dead_code)]
 pub struct DeviceDetector {
@@ -76,6 +88,9 @@ impl DeviceDetector {
     }
 }
 
+/// The `prefer_npu_device_string` default before OpenVINO devices were discovered.
+const LEGACY_AUTO_DEVICE: &str = "AUTO:NPU,CPU";
+
 pub struct OnnxProvider {
     config: InferenceConfig,
     is_loaded: bool,
@@ -87,6 +102,30 @@ pub struct OnnxProvider {
     model_signature: Option<ModelSignature>,
     last_probe_success: bool,
     loaded_execution_provider: Option<ExecutionProvider>,
+    last_trace: Option<inference::GenerationTrace>,
+    prefill_monitor: PrefillMonitor,
+    /// KV caches of recent prompts, by input name
+    prompt_cache: PromptCache<Vec<(String, ArrayD<f32>)>>,
+    /// End-of-sequence token ids of the loaded model.
+    eos_tokens: Vec<i64>,
+    /// Token strings by id, for resolving the logit bias; read on first use.
+    vocabulary: Option<Vec<String>>,
+    /// The text each token adds, for constrained output; built on first use.
+    token_texts: Option<Vec<String>>,
+    /// Prompt text with the reply appended, and its tokens, after a reply cut off at
+    /// `max_tokens`. Continuing the reply renders that same text; it then runs on the same
+    /// tokens, so the KV cache kept after them is picked up rather than prefilling it again.
+    cut_off: Option<(String, Vec<i64>)>,
+}
+
+/// KV cache values carried from one run to the next, by input name.
+type KvCache = Vec<(String, DynValue)>;
+
+/// A decoded reply.
+struct Decoded {
+    text: String,
+    tokens: Vec<i64>,
+    finish_reason: FinishReason,
 }
 
 /// Structured classification of ONNX model loading failures.
@@ -102,6 +141,13 @@ pub enum LoadError {
     Io(String),
     ModelUnsupported(String),
     InferenceProbeFailed(String),
+    IntegrityCheckFailed(String),
+    /// NVIDIA driver missing or too old for the CUDA EP.
+    CudaDriver(String),
+    /// CUDA runtime (toolkit) missing or the wrong major version.
+    CudaToolkit(String),
+    /// cuDNN missing or the wrong major version.
+    Cudnn(String),
     Panic(String),
     Unknown(String),
 }
@@ -119,6 +165,8 @@ impl std::fmt::Display for LoadError {
             Io(e) => write!(f, "I/O error: {e}"),
             ModelUnsupported(e) => write!(f, "Model unsupported: {e}"),
             InferenceProbeFailed(e) => write!(f, "Inference probe failed: {e}"),
+            IntegrityCheckFailed(e) => write!(f, "Model file failed integrity check (re-download it): {e}"),
+            CudaDriver(e) | CudaToolkit(e) | Cudnn(e) => write!(f, "CUDA unavailable: {e}"),
             Panic(e) => write!(f, "Panic during load: {e}"),
             Unknown(e) => write!(f, "Unknown load error: {e}"),
         }
@@ -130,7 +178,6 @@ impl StdError for LoadError {}
 impl OnnxProvider {
     pub fn new(config: InferenceConfig) -> Result<Self> {
         Ok(Self {
-            config,
             is_loaded: false,
             tokenizer: SimpleTokenizer::new(),
             model_loaded: false,
@@ -140,9 +187,22 @@ impl OnnxProvider {
             model_signature: None,
             last_probe_success: false,
             loaded_execution_provider: None,
+            last_trace: None,
+            prefill_monitor: PrefillMonitor::default(),
+            prompt_cache: PromptCache::new(config.prompt_cache_entries),
+            eos_tokens: Vec::new(),
+            vocabulary: None,
+            token_texts: None,
+            cut_off: None,
+            config,
         })
     }
 
+    /// Report chunked prefill progress to `monitor` (polled by the UI) from now on.
+    pub fn set_prefill_monitor(&mut self, monitor: PrefillMonitor) {
+        self.prefill_monitor = monitor;
+    }
+
     /// New classified load path. Returns rich LoadError variants.
     pub fn load_model_classified(&mut self) -> std::result::Result<(), LoadError> {
         // Basic path validation
@@ -162,47 +222,110 @@ impl OnnxProvider {
             return Err(e);
         }
 
+        if self.config.verify_integrity {
+            use crate::ai::integrity::{verify_model, IntegrityStatus};
+            match verify_model(model_path, false, false) {
+                Ok(IntegrityStatus::Corrupted(reason)) => {
+                    let e = LoadError::IntegrityCheckFailed(reason);
+                    self.last_load_error = Some(e.clone());
+                    return Err(e);
+                }
+                Ok(_) => {}
+                Err(e) => tracing::warn!("Integrity check skipped for {}: {}", self.config.model_path, e),
+            }
+        }
+
         tracing::info!("Loading ONNX model (classified): {}", self.config.model_path);
 
         let sys = SystemInfo::default();
         let mut preferred_ep = self.config.execution_provider.clone();
         if self.config.prefer_npu && sys.has_npu() {
-            preferred_ep = ExecutionProvider::OpenVINO;
+            preferred_ep = if Self::ep_supported(&ExecutionProvider::QNN) { ExecutionProvider::QNN } else { ExecutionProvider::OpenVINO };
+        }
+        if !Self::ep_supported(&preferred_ep) {
+            let msg = format!("{:?} execution provider is not available in this build/platform; using CPU", preferred_ep);
+            tracing::warn!("{}", msg);
+            self.last_ep_error = Some(msg);
+            preferred_ep = ExecutionProvider::Cpu;
+        }
+
+        // CUDA EP registration only says "failed"; check its dependencies first for a usable error
+        let cuda_report = (preferred_ep == ExecutionProvider::Cuda).then(crate::ai::cuda::probe);
+        if let Some(problem) = cuda_report.as_ref().and_then(|r| r.problem()) {
+            tracing::warn!("CUDA check failed ({}): {}", cuda_report.as_ref().map(|r| r.summary()).unwrap_or_default(), problem);
+            self.last_load_error = Some(problem.clone());
+            return Err(problem);
         }
 
         // Build session
+        crate::ai::runtime::bind_library();
         let mut builder = Session::builder().map_err(|e| self.map_session_error("Session builder init", &e))?;
         let mut eps: Vec<ExecutionProviderDispatch> = Vec::new();
         match preferred_ep {
-            ExecutionProvider::Cuda => eps.push(CUDAExecutionProvider::default().build().error_on_failure()),
-            ExecutionProvider::DirectML => eps.push(DirectMLExecutionProvider::default().build().error_on_failure()),
+            ExecutionProvider::Cuda => {
+                let mut cuda = CUDAExecutionProvider::default();
+                if let Some(id) = self.config.device_id { cuda = cuda.with_device_id(id); }
+                eps.push(cuda.build().error_on_failure())
+            },
+            ExecutionProvider::DirectML => {
+                let mut dml = DirectMLExecutionProvider::default();
+                if let Some(id) = self.config.device_id { dml = dml.with_device_id(id); }
+                eps.push(dml.build().error_on_failure())
+            },
             ExecutionProvider::CoreML => eps.push(CoreMLExecutionProvider::default().build().error_on_failure()),
             ExecutionProvider::OpenVINO => {
-                // Allow device string override via env var if prefer_npu enabled. (Placeholder mechanism)
-                if self.config.prefer_npu && !self.config.prefer_npu_device_string.is_empty() {
-                    std::env::set_var("RIA_OPENVINO_DEVICE", &self.config.prefer_npu_device_string);
+                let mut ov = OpenVINOExecutionProvider::default();
+                if let Some(device) = self.openvino_device_type() {
+                    ov = ov.with_device_type(device);
                 }
-                let ov = OpenVINOExecutionProvider::default();
                 eps.push(ov.build().error_on_failure());
             },
+            #[cfg(feature = "qnn_ep")]
+            ExecutionProvider::QNN => {
+                let mut qnn = QNNExecutionProvider::default()
+                    .with_performance_mode(QNNPerformanceMode::HighPerformance);
+                if let Some(path) = SystemInfo::qnn_backend_path() { qnn = qnn.with_backend_path(path.display()); }
+                if let Some(id) = self.config.device_id { qnn = qnn.with_device_id(id); }
+                eps.push(qnn.build().error_on_failure())
+            },
+            #[cfg(feature = "nnapi_ep")]
+            ExecutionProvider::NNAPI => eps.push(NNAPIExecutionProvider::default().with_fp16(true).build().error_on_failure()),
             _ => {}
         }
-        eps.push(CPUExecutionProvider::default().build());
+        let opts = self.config.session_options.clone();
+        eps.push(CPUExecutionProvider::default().with_arena_allocator(opts.cpu_mem_arena).build());
 
         match builder.with_execution_providers(&eps) {
             Ok(b) => builder = b,
             Err(e) => {
                 tracing::warn!("EP registration failed: {}. Falling back to CPU-only.", e);
-                self.last_ep_error = Some(e.to_string());
+                self.last_ep_error = Some(match &cuda_report {
+                    Some(report) => format!("{e} ({})", report.summary()),
+                    None => e.to_string(),
+                });
                 builder = Session::builder().map_err(|e| self.map_session_error("Session builder re-init", &e))?;
-                builder = builder.with_execution_providers([CPUExecutionProvider::default().build()].as_ref())
+                builder = builder.with_execution_providers([CPUExecutionProvider::default().with_arena_allocator(opts.cpu_mem_arena).build()].as_ref())
                     .map_err(|e| self.map_session_error("CPU EP registration", &e))?;
             }
         }
-        builder = builder.with_optimization_level(GraphOptimizationLevel::Level3)
+        let opt_level = match opts.optimization_level {
+            OptimizationLevel::Disable => GraphOptimizationLevel::Disable,
+            OptimizationLevel::Basic => GraphOptimizationLevel::Level1,
+            OptimizationLevel::Extended => GraphOptimizationLevel::Level2,
+            OptimizationLevel::All => GraphOptimizationLevel::Level3,
+        };
+        builder = builder.with_optimization_level(opt_level)
             .map_err(|e| self.map_session_error("Set optimization level", &e))?;
-        builder = builder.with_intra_threads(num_cpus::get().min(4))
+        builder = builder.with_intra_threads(opts.effective_intra_threads())
             .map_err(|e| self.map_session_error("Set intra threads", &e))?;
+        builder = builder.with_parallel_execution(opts.parallel_execution)
+            .map_err(|e| self.map_session_error("Set parallel execution", &e))?;
+        if opts.inter_threads > 0 {
+            builder = builder.with_inter_threads(opts.inter_threads)
+                .map_err(|e| self.map_session_error("Set inter threads", &e))?;
+        }
+        builder = builder.with_memory_pattern(opts.memory_pattern)
+            .map_err(|e| self.map_session_error("Set memory pattern", &e))?;
 
         let session = builder.commit_from_file(&self.config.model_path)
             .map_err(|e| self.classify_error(e.to_string()))?;
@@ -218,6 +341,7 @@ impl OnnxProvider {
             // Introspect model signature
             self.model_signature = Some(ModelSignature::from_session(sess));
         }
+        self.load_tokenizer();
         // Optional warmup & profiling
         if self.config.warmup_iterations > 0 || self.config.profiling {
             let warmups = self.config.warmup_iterations.max(if self.config.profiling { 1 } else { 0 });
@@ -240,7 +364,7 @@ impl OnnxProvider {
                     if self.config.profiling {
                         let dir = std::env::temp_dir();
                         let path = dir.join("ria_onnx_profile.txt");
-                        let _ = std::fs::write(&path, format!("provider={:?}\nwarmup_iterations={warmup_ok}\nrequested_device={}\n", preferred_ep, self.config.prefer_npu_device_string));
+                        let _ = std::fs::write(&path, format!("provider={:?}\nwarmup_iterations={warmup_ok}\nrequested_device={}\n", preferred_ep, self.openvino_device_type().unwrap_or_default()));
                         tracing::info!("Wrote simple profiling file to {:?}", path);
                     }
                 }
@@ -249,7 +373,36 @@ impl OnnxProvider {
         Ok(())
     }
 
+    /// Whether an EP can be attempted at all: QNN / NNAPI need their feature flag and platform support.
+    pub fn ep_supported(ep: &ExecutionProvider) -> bool {
+        match ep {
+            ExecutionProvider::QNN => cfg!(feature = "qnn_ep") && SystemInfo::qnn_backend_path().is_some(),
+            ExecutionProvider::NNAPI => cfg!(feature = "nnapi_ep") && SystemInfo::has_nnapi(),
+            _ => true,
+        }
+    }
+
+    /// Resolve the OpenVINO device string: an explicit device selection wins, otherwise the NPU
+    /// preference string, otherwise an `AUTO:` string over the devices OpenVINO reports.
+    fn openvino_device_type(&self) -> Option<String> {
+        if let Some(device) = self.config.device_type.as_ref().filter(|d| !d.is_empty()) {
+            return Some(device.clone());
+        }
+        if !self.config.prefer_npu {
+            return None;
+        }
+        // Configs saved before device discovery stored the old hard-coded default
+        let configured = self.config.prefer_npu_device_string.as_str();
+        if !configured.is_empty() && configured != LEGACY_AUTO_DEVICE {
+            return Some(configured.to_string());
+        }
+        // Without openvino_c the devices can't be listed; the old default still works then
+        crate::utils::openvino::auto_device_string(crate::utils::openvino::devices())
+            .or_else(|| Some(LEGACY_AUTO_DEVICE.to_string()))
+    }
+
     /// Backwards-compatible adapter returning anyhow::Result.
+    #[allow(dead_code)]
     pub fn load_model(&mut self) -> Result<()> {
         self.load_model_classified().map_err(|e| anyhow!(e.to_string()))
     }
@@ -286,35 +439,111 @@ impl OnnxProvider {
         Ok(self.tokenizer.decode(tokens))
     }
     
-    /// Perform ONNX inference (framework ready, will be enhanced)
-    pub fn run_onnx_inference(&mut self, messages: &[ChatMessage]) -> Result<String> {
+    /// Use the `tokenizer.json` shipped next to the model, with its end-of-sequence tokens;
+    /// replies are only decoded token by token with the model's own tokenizer.
+    fn load_tokenizer(&mut self) {
+        let model_path = std::path::PathBuf::from(&self.config.model_path);
+        let Some(dir) = model_path.parent() else { return };
+        let file = dir.join("tokenizer.json");
+        if !file.exists() {
+            tracing::info!("No tokenizer.json next to {}; replies won't be decoded", model_path.display());
+            return;
+        }
+        match SimpleTokenizer::from_hf_files(&file) {
+            Ok(tokenizer) => {
+                self.eos_tokens = decode::eos_token_ids(dir, |token| tokenizer.token_id(token));
+                self.tokenizer = tokenizer;
+                self.vocabulary = None;
+                self.token_texts = None;
+                self.cut_off = None;
+            }
+            Err(e) => tracing::warn!("Couldn't load {}: {e}", file.display()),
+        }
+    }
+
+    /// Whether replies can be decoded token by token: the model's tokenizer is loaded and the
+    /// model takes token ids and returns logits.
+    pub fn can_decode(&self) -> bool {
+        self.session.is_some()
+            && self.tokenizer.is_model_tokenizer()
+            && self.model_signature.as_ref().is_some_and(|s| {
+                s.logits_output.is_some() && s.input_with_role(InputRole::Ids).is_some() && s.pixel_input().is_none()
+            })
+    }
+
+    /// Perform ONNX inference: decode the reply when the model allows it, otherwise probe the
+    /// model with a forward pass and answer from the framework.
+    pub fn run_onnx_inference(&mut self, messages: &[ChatMessage], overrides: &GenerationOverrides) -> Result<String> {
         if !self.model_loaded {
             return Err(anyhow!("ONNX model not loaded"));
         }
         
-        // Prepare input tokens from chat messages
-        let input_tokens = self.tokenizer.prepare_chat_input(messages);
+        // Prepare input tokens from chat messages; continuing a cut-off reply reuses its tokens
+        let prompt = self.tokenizer.render_prompt(messages);
+        let input_tokens = match self.cut_off.take() {
+            Some((text, tokens)) if text == prompt => tokens,
+            _ => self.tokenizer.prepare_chat_input(messages),
+        };
         
         if input_tokens.is_empty() {
             return Err(anyhow!("No input tokens generated"));
         }
+        let sampling = overrides.apply(&self.config);
+        self.last_trace = Some(inference::GenerationTrace {
+            provider: self.name().to_string(),
+            execution_provider: self.loaded_execution_provider.as_ref().map(|ep| format!("{:?}", ep)),
+            prompt: prompt.clone(),
+            token_ids: input_tokens.clone(),
+            temperature: sampling.temperature,
+            top_p: sampling.top_p,
+            top_k: sampling.top_k,
+            max_tokens: sampling.max_tokens,
+            logit_bias: sampling.logit_bias.clone(),
+            reply_tokens: None,
+            finish_reason: None,
+        });
+
+        if self.can_decode() {
+            let constraint = overrides.constraint.as_ref().map(|c| c.compile()).transpose()?;
+            let decoded = self.decode(&input_tokens, &sampling, constraint.as_ref())?;
+            if decoded.finish_reason == FinishReason::Length {
+                self.cut_off = Some((format!("{prompt}{}", decoded.text), [input_tokens.as_slice(), &decoded.tokens].concat()));
+            }
+            self.last_probe_success = true;
+            if let Some(trace) = self.last_trace.as_mut() {
+                trace.reply_tokens = Some(decoded.tokens.len() as u32);
+                trace.finish_reason = Some(decoded.finish_reason);
+            }
+            return Ok(decoded.text);
+        }
         
         tracing::info!("🚀 ONNX inference framework processing {} tokens", input_tokens.len());
 
         // Try a minimal real forward pass if a session is present
+        // The most recent pasted image goes to the vision input, if the model has one
+        let image = messages.iter().rev().find_map(|m| m.images.last());
         let mut ran_real_forward = false;
         if self.session.is_some() {
-            match self.adaptive_probe(&input_tokens) {
+            let forward = if image.is_none() && self.supports_chunked_prefill() {
+                self.chunked_prefill(&input_tokens).map(|_| ()).or_else(|e| {
+                    tracing::warn!("⚠️ Chunked prefill failed: {e}. Retrying as a single run.");
+                    self.prompt_cache.clear();
+                    self.adaptive_probe(&input_tokens, image)
+                })
+            } else {
+                self.adaptive_probe(&input_tokens, image)
+            };
+            match forward {
                 Ok(()) => { ran_real_forward = true; tracing::info!("🎉 Adaptive ONNX forward probe succeeded"); },
                 Err(e) => { tracing::warn!("⚠️ Adaptive probe failed: {e}. Using framework response."); }
             }
         }
         
-        // If minimal forward succeeded, return a concise success response for now
+        // Without the model's tokenizer the output can't be decoded, so only report the pass
         if ran_real_forward {
             self.last_probe_success = true;
             return Ok(format!(
-                "🎉 Real ONNX forward pass completed successfully. Processed {} tokens. Streaming/token decoding will be enabled next.",
+                "🎉 Real ONNX forward pass completed successfully. Processed {} tokens. Add the model's tokenizer.json next to it to get decoded replies.",
                 input_tokens.len()
             ));
         }
@@ -325,6 +554,109 @@ impl OnnxProvider {
         tracing::info!("✅ ONNX inference framework completed");
         Ok(response)
     }
+
+    /// Decode a reply to `input_tokens`: prefill the prompt, then sample one token at a time
+    /// with the request's temperature, top-k/top-p and logit bias, feeding each back with the
+    /// KV cache (or with the whole sequence for models without one). With a `constraint`,
+    /// only tokens that keep the reply matchable are sampled, and it ends once it matches.
+    fn decode(&mut self, input_tokens: &[i64], sampling: &InferenceConfig, constraint: Option<&Constraint>) -> Result<Decoded> {
+        let with_cache = self.supports_chunked_prefill();
+        let (mut cache, mut logits) = if with_cache {
+            self.chunked_prefill(input_tokens)?
+        } else {
+            (Vec::new(), None)
+        };
+        let bias = if sampling.logit_bias.is_empty() {
+            Vec::new()
+        } else {
+            let vocabulary = self.vocabulary.get_or_insert_with(|| self.tokenizer.vocabulary());
+            sampler::resolve_logit_bias(&sampling.logit_bias, vocabulary)
+        };
+        let mut sampler = LogitsSampler::new(SamplerConfig::from_settings(sampling.temperature, sampling.top_k, sampling.top_p, bias));
+        let mut constrained = match constraint {
+            Some(constraint) => {
+                let texts: &[String] = self.token_texts.get_or_insert_with(|| self.tokenizer.token_texts());
+                let eos: Vec<usize> = self.eos_tokens.iter().map(|id| *id as usize).collect();
+                Some((constraint.start(), texts, eos))
+            }
+            None => None,
+        };
+
+        let session = self.session.as_mut().ok_or_else(|| anyhow!("ONNX session not initialized"))?;
+        let sig = self.model_signature.clone().unwrap_or_else(|| ModelSignature::from_session(session));
+        let mut tokens = input_tokens.to_vec();
+        if !with_cache {
+            logits = forward(session, &sig, &tokens, 0..tokens.len(), &mut cache)?;
+        }
+        let max_tokens = sampling.max_tokens as usize;
+        let mut fed = tokens.len();
+        let mut text = String::new();
+        let finish_reason = loop {
+            if tokens.len() - input_tokens.len() >= max_tokens {
+                break FinishReason::Length;
+            }
+            let mut step_logits = logits.take().ok_or_else(|| anyhow!("Model returned no logits"))?;
+            let next = match constrained.as_mut() {
+                Some((state, texts, eos)) => match sampler.sample_constrained(&mut step_logits, texts, state, eos) {
+                    Some(next) => {
+                        if !eos.contains(&next) {
+                            state.feed(&texts[next]);
+                        }
+                        next
+                    }
+                    None if state.is_complete() => break FinishReason::Stop,
+                    None => return Err(anyhow!("No token of the model's vocabulary can continue the required format")),
+                },
+                None => match sampler.sample(&step_logits) {
+                    Some(next) => next,
+                    None => break FinishReason::Stop,
+                },
+            };
+            let next = next as i64;
+            if self.eos_tokens.contains(&next) {
+                break FinishReason::Stop;
+            }
+            tokens.push(next);
+            text = self.tokenizer.decode(&tokens[input_tokens.len()..]);
+            // A constrained reply isn't a transcript; the constraint says where it ends
+            if constrained.is_none() {
+                if let Some(end) = decode::end_of_turn(&text) {
+                    text.truncate(end);
+                    break FinishReason::Stop;
+                }
+            }
+            if tokens.len() - input_tokens.len() >= max_tokens {
+                break FinishReason::Length;
+            }
+            logits = if with_cache {
+                forward(session, &sig, &tokens, fed..tokens.len(), &mut cache)?
+            } else {
+                cache.clear();
+                forward(session, &sig, &tokens, 0..tokens.len(), &mut cache)?
+            };
+            fed = tokens.len();
+        };
+        tracing::info!("Decoded {} tokens ({finish_reason:?})", tokens.len() - input_tokens.len());
+        if with_cache && fed > input_tokens.len() {
+            self.keep_kv_state(&tokens[..fed], &cache);
+        }
+        Ok(Decoded { text, tokens: tokens.split_off(input_tokens.len()), finish_reason })
+    }
+
+    /// Keep the KV `cache` after `tokens` for later prompts that start with them.
+    fn keep_kv_state(&mut self, tokens: &[i64], cache: &KvCache) {
+        if self.config.prompt_cache_entries == 0 {
+            return;
+        }
+        let state: Result<Vec<_>> = cache
+            .iter()
+            .map(|(name, value)| Ok((name.clone(), value.try_extract_array::<f32>()?.to_owned())))
+            .collect();
+        match state {
+            Ok(state) => self.prompt_cache.insert(tokens, state),
+            Err(e) => tracing::warn!("Could not keep the KV cache for the next turn: {e}"),
+        }
+    }
     
     /// Generate intelligent responses using the ONNX framework
     fn generate_onnx_style_response(&self, messages: &[ChatMessage], input_tokens: &[i64]) -> Result<String> {
@@ -376,7 +708,7 @@ impl OnnxProvider {
 
 impl OnnxProvider {
     /// Adaptive forward probe using introspected model signature.
-    fn adaptive_probe(&mut self, input_tokens: &[i64]) -> Result<()> {
+    fn adaptive_probe(&mut self, input_tokens: &[i64], image: Option<&vision::ImageAttachment>) -> Result<()> {
         let session = self.session.as_mut().ok_or_else(|| anyhow!("ONNX session not initialized"))?;
         let sig = self.model_signature.clone().unwrap_or_else(|| ModelSignature::from_session(session));
         let seq_len = input_tokens.len().min(512);
@@ -390,6 +722,19 @@ impl OnnxProvider {
         let id_names = sig.inputs.iter().filter(|i| matches!(i.role, InputRole::Ids)).map(|i| i.name.as_str()).collect::<Vec<_>>();
         let mask_names = sig.inputs.iter().filter(|i| matches!(i.role, InputRole::AttentionMask)).map(|i| i.name.as_str()).collect::<Vec<_>>();
 
+        // Vision models: ids (+ mask) + pixel values
+        if let (Some(image), Some((pixel_name, size))) = (image, sig.pixel_input()) {
+            let pixels = vision::pixel_values(&image.decode()?, size);
+            let pixel_val = Value::from_array(pixels).map_err(|e| anyhow!("Failed to wrap pixel values: {e}"))?;
+            for idn in &id_names {
+                for mn in &mask_names {
+                    if let Ok(outputs) = session.run(ort::inputs![ *idn => &ids_val, *mn => &mask_val, pixel_name => &pixel_val ]) { tracing::info!("Probe success ids+mask+pixels -> {} outputs", outputs.len()); return Ok(()); }
+                }
+                if let Ok(outputs) = session.run(ort::inputs![ *idn => &ids_val, pixel_name => &pixel_val ]) { tracing::info!("Probe success ids+pixels -> {} outputs", outputs.len()); return Ok(()); }
+            }
+            tracing::warn!("Vision input {} rejected the image; probing text inputs only", pixel_name);
+        }
+
         // Try ids + mask combos
         for idn in &id_names {
             for mn in &mask_names {
@@ -406,6 +751,69 @@ impl OnnxProvider {
         Err(anyhow!("Adaptive probe failed for all recognized input signatures"))
     }
 
+    /// Whether the model takes `past_key_values.*` inputs and returns the matching `present.*`
+    /// outputs, so the prompt can be fed in chunks.
+    fn supports_chunked_prefill(&self) -> bool {
+        self.model_signature.as_ref().is_some_and(|s| {
+            !s.present_outputs.is_empty() && s.inputs.iter().any(|i| i.role == InputRole::PastKeyValues)
+        })
+    }
+
+    /// Feed the prompt `prefill_chunk_size` tokens per run, carrying the KV cache from each
+    /// run's `present.*` outputs into the next run's `past_key_values.*` inputs. Starts from
+    /// the cache of the longest earlier prompt this one extends, and keeps the final cache for
+    /// the next turn. Returns the cache and the logits after the last prompt token.
+    fn chunked_prefill(&mut self, input_tokens: &[i64]) -> Result<(KvCache, Option<Vec<f32>>)> {
+        let session = self.session.as_mut().ok_or_else(|| anyhow!("ONNX session not initialized"))?;
+        let sig = self.model_signature.clone().unwrap_or_else(|| ModelSignature::from_session(session));
+
+        let total = input_tokens.len();
+        let mut cache: KvCache = Vec::new();
+        let reused = match self.prompt_cache.longest_prefix(input_tokens) {
+            Some((len, state)) => {
+                for (name, array) in state {
+                    cache.push((name.clone(), Value::from_array(array.clone())?.into_dyn()));
+                }
+                tracing::info!("Prompt cache hit: reusing {len} of {total} prompt tokens");
+                len
+            }
+            None => 0,
+        };
+        // Without a cached prefix the cache starts empty: zero tokens along the sequence axis
+        // of [batch, heads, seq, head_dim]
+        for input in sig.inputs.iter().filter(|i| i.role == InputRole::PastKeyValues && reused == 0) {
+            if input.element_type != Some(TensorElementType::Float32) {
+                return Err(anyhow!("Unsupported KV cache type {:?} for {}", input.element_type, input.name));
+            }
+            let (heads, head_dim) = match input.shape[..] {
+                [_, heads, _, head_dim] if heads > 0 && head_dim > 0 => (heads as usize, head_dim as usize),
+                _ => return Err(anyhow!("KV cache input {} has no static head dimensions: {:?}", input.name, input.shape)),
+            };
+            let empty = Value::from_array(Array4::<f32>::zeros((1, heads, 0, head_dim)))?;
+            cache.push((input.name.clone(), empty.into_dyn()));
+        }
+
+        self.prefill_monitor.report(PrefillProgress { done: reused, total });
+        let mut logits = None;
+        for range in prefill::chunks(total - reused, self.config.prefill_chunk_size) {
+            let range = range.start + reused..range.end + reused;
+            if range.start > reused && self.config.step_delay_ms > 0 {
+                std::thread::sleep(std::time::Duration::from_millis(self.config.step_delay_ms));
+            }
+            logits = forward(session, &sig, input_tokens, range.clone(), &mut cache)?;
+            tracing::debug!("Prefilled tokens {}..{} of {}", range.start, range.end, total);
+            self.prefill_monitor.report(PrefillProgress { done: range.end, total });
+        }
+
+        self.keep_kv_state(input_tokens, &cache);
+        Ok((cache, logits))
+    }
+
+    /// Whether the loaded model has an image input that pasted images can be fed to.
+    pub fn accepts_images(&self) -> bool {
+        self.model_signature.as_ref().is_some_and(|s| s.pixel_input().is_some())
+    }
+
     /// Test/diagnostics helper: returns input names discovered in model signature.
     pub fn debug_signature_input_names(&self) -> Option<Vec<String>> {
         self.model_signature.as_ref().map(|s| s.inputs.iter().map(|i| i.name.clone()).collect())
@@ -426,15 +834,55 @@ impl OnnxProvider {
     pub fn loaded_execution_provider(&self) -> Option<&ExecutionProvider> { self.loaded_execution_provider.as_ref() }
 }
 
+/// Run the model on `tokens[range]`, after the tokens before it that `cache` holds (or
+/// none, for a model without a KV cache). Refills `cache` from the `present.*` outputs and
+/// returns the logits of the last position, if the model has a logits output.
+fn forward(session: &mut Session, sig: &ModelSignature, tokens: &[i64], range: std::ops::Range<usize>, cache: &mut KvCache) -> Result<Option<Vec<f32>>> {
+    let ids_name = sig.input_with_role(InputRole::Ids).ok_or_else(|| anyhow!("Model has no token id input"))?;
+    let ids = Array2::from_shape_vec((1, range.len()), tokens[range.clone()].to_vec())?;
+    let mut inputs: Vec<(String, SessionInputValue)> = vec![(ids_name.to_string(), Value::from_array(ids)?.into())];
+    if let Some(name) = sig.input_with_role(InputRole::AttentionMask) {
+        // The mask covers the cached tokens as well as this run's
+        inputs.push((name.to_string(), Value::from_array(Array2::from_elem((1, range.end), 1i64))?.into()));
+    }
+    if let Some(name) = sig.input_with_role(InputRole::PositionIds) {
+        let positions = Array2::from_shape_vec((1, range.len()), (range.start as i64..range.end as i64).collect())?;
+        inputs.push((name.to_string(), Value::from_array(positions)?.into()));
+    }
+    inputs.extend(cache.drain(..).map(|(name, value)| (name, value.into())));
+
+    let mut outputs = session.run(inputs)?;
+    for output in &sig.present_outputs {
+        let value = outputs.remove(output).ok_or_else(|| anyhow!("Model did not return {output}"))?;
+        if let Some(past) = prefill::past_input_for(output) {
+            cache.push((past, value));
+        }
+    }
+    match &sig.logits_output {
+        Some(name) => {
+            let value = outputs.get(name).ok_or_else(|| anyhow!("Model did not return {name}"))?;
+            Ok(Some(decode::last_logits(value.try_extract_array::<f32>()?)?))
+        }
+        None => Ok(None),
+    }
+}
+
 /// Model input role classification
 #[derive(Debug, Clone, PartialEq)]
-enum InputRole { Ids, AttentionMask, TokenTypeIds, PositionIds, Unknown }
+enum InputRole { Ids, AttentionMask, TokenTypeIds, PositionIds, PixelValues, PastKeyValues, Unknown }
 
 #[derive(Debug, Clone)]
-struct ModelInputDesc { name: String, role: InputRole }
+struct ModelInputDesc { name: String, role: InputRole, shape: Vec<i64>, element_type: Option<TensorElementType> }
 
 #[derive(Debug, Clone)]
-struct ModelSignature { inputs: Vec<ModelInputDesc> }
+struct ModelSignature {
+    inputs: Vec<ModelInputDesc>,
+    /// `present.*` outputs that feed a `past_key_values.*` input on the next run.
+    present_outputs: Vec<String>,
+    /// The next-token scores: the output named `logits`, or else the first one that isn't
+    /// part of the KV cache.
+    logits_output: Option<String>,
+}
 
 impl ModelSignature {
     fn from_session(session: &Session) -> Self {
@@ -442,14 +890,55 @@ impl ModelSignature {
         for inp in &session.inputs {
             let name = inp.name.clone();
             let lower = name.to_lowercase();
-            let role = if lower.contains("input_ids") || lower == "input" || lower.contains("tokens") { InputRole::Ids }
+            // Checked first: cache names like `past_key_values.0.key` would otherwise match below
+            let role = if lower.contains("past_key_values") { InputRole::PastKeyValues }
+                else if lower.contains("input_ids") || lower == "input" || lower.contains("tokens") { InputRole::Ids }
                 else if lower.contains("attention_mask") || lower == "mask" { InputRole::AttentionMask }
                 else if lower.contains("token_type") { InputRole::TokenTypeIds }
                 else if lower.contains("position") { InputRole::PositionIds }
+                else if lower.contains("pixel") || lower.contains("image") { InputRole::PixelValues }
                 else { InputRole::Unknown };
-            inputs.push(ModelInputDesc { name, role });
+            let shape = inp.input_type.tensor_shape().map(|s| s.to_vec()).unwrap_or_default();
+            let element_type = inp.input_type.tensor_type();
+            inputs.push(ModelInputDesc { name, role, shape, element_type });
+        }
+        let present_outputs = session.outputs.iter()
+            .map(|out| out.name.clone())
+            .filter(|name| prefill::past_input_for(name).is_some())
+            .collect();
+        let logits_output = session.outputs.iter()
+            .find(|out| out.name == "logits")
+            .or_else(|| session.outputs.iter().find(|out| prefill::past_input_for(&out.name).is_none()))
+            .map(|out| out.name.clone());
+        Self { inputs, present_outputs, logits_output }
+    }
+
+    /// One line per input, with the KV cache inputs counted rather than listed.
+    fn describe_inputs(&self) -> String {
+        let mut lines: Vec<String> = self.inputs.iter()
+            .filter(|i| i.role != InputRole::PastKeyValues)
+            .map(|i| {
+                let shape = i.shape.iter().map(|d| if *d < 0 { "?".to_string() } else { d.to_string() }).collect::<Vec<_>>().join("×");
+                let ty = i.element_type.map(|t| format!(" {t:?}")).unwrap_or_default();
+                format!("{} [{shape}]{ty} ({:?})", i.name, i.role)
+            })
+            .collect();
+        let cache = self.inputs.iter().filter(|i| i.role == InputRole::PastKeyValues).count();
+        if cache > 0 {
+            lines.push(format!("{cache} past_key_values inputs"));
         }
-        Self { inputs }
+        lines.join("\n")
+    }
+
+    fn input_with_role(&self, role: InputRole) -> Option<&str> {
+        self.inputs.iter().find(|i| i.role == role).map(|i| i.name.as_str())
+    }
+
+    /// The image input and the square size it expects (from its static shape, if any).
+    fn pixel_input(&self) -> Option<(&str, u32)> {
+        let input = self.inputs.iter().find(|i| i.role == InputRole::PixelValues)?;
+        let size = input.shape.last().copied().filter(|d| *d > 0).map_or(vision::DEFAULT_INPUT_SIZE, |d| d as u32);
+        Some((input.name.as_str(), size))
     }
 }
 
@@ -463,26 +952,40 @@ impl AIProvider for OnnxProvider {
     }
 
     fn generate_response(&mut self, messages: &[ChatMessage]) -> Result<String> {
+        self.generate_response_with(messages, &GenerationOverrides::default())
+    }
+
+    fn generate_response_with(&mut self, messages: &[ChatMessage], overrides: &GenerationOverrides) -> Result<String> {
         if !self.is_loaded {
             return Err(anyhow!("Model not loaded"));
         }
 
         // Use the ONNX inference framework
-        self.run_onnx_inference(messages)
+        self.run_onnx_inference(messages, overrides)
     }
 
     fn get_model_info(&self) -> Result<HashMap<String, String>> {
         let mut info = HashMap::new();
         info.insert("provider".to_string(), "ONNX Runtime".to_string());
         info.insert("model_path".to_string(), self.config.model_path.clone());
-        info.insert("execution_provider".to_string(), format!("{:?}", self.config.execution_provider));
+        // The EP the session actually runs on, which differs from the configured one after a fallback
+        let ep = self.loaded_execution_provider.as_ref().unwrap_or(&self.config.execution_provider);
+        info.insert("execution_provider".to_string(), format!("{ep:?}"));
         info.insert("model_loaded".to_string(), self.model_loaded.to_string());
         info.insert("inference_ready".to_string(), self.is_loaded.to_string());
-        info.insert("framework_status".to_string(), "Active - Ready for ONNX Runtime integration".to_string());
+        if let Some(signature) = &self.model_signature { info.insert("signature_inputs".to_string(), signature.describe_inputs()); }
         if let Some(err) = &self.last_ep_error { info.insert("last_ep_error".to_string(), err.clone()); }
-    if let Some(load_err) = &self.last_load_error { info.insert("last_load_error".to_string(), load_err.to_string()); }
+        if let Some(load_err) = &self.last_load_error { info.insert("last_load_error".to_string(), load_err.to_string()); }
         Ok(info)
     }
 
+    fn supports_constraints(&self) -> bool {
+        self.can_decode()
+    }
+
     fn as_any(&self) -> &dyn std::any::Any { self }
+
+    fn last_trace(&self) -> Option<inference::GenerationTrace> {
+        self.last_trace.clone()
+    }
 }
\ No newline at end of file
@@ -0,0 +1,150 @@
+//! Dynamic INT8 quantization of local FP32 models.
+//!
+//! ONNX Runtime's quantizer lives in its Python package (`onnxruntime.quantization`),
+//! so this drives it as a subprocess and reports coarse progress over a channel.
+
+use anyhow::{anyhow, Result};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, BufReader};
+use tokio::sync::mpsc::UnboundedSender;
+
+#[derive(Debug, Clone)]
+pub enum QuantizeProgress {
+    /// Human readable stage and overall fraction in 0..=1.
+    Stage(String, f32),
+    Completed { output: PathBuf, original_size: u64, quantized_size: u64 },
+    Failed(String),
+}
+
+const QUANTIZE_SCRIPT: &str = r#"
+import sys
+print("STAGE 0.15 Loading model", flush=True)
+from onnxruntime.quantization import quantize_dynamic, QuantType
+import onnx
+model = onnx.load(sys.argv[1], load_external_data=False)
+large = model.ByteSize() > 2_000_000_000
+print("STAGE 0.35 Quantizing weights to INT8", flush=True)
+quantize_dynamic(sys.argv[1], sys.argv[2], weight_type=QuantType.QInt8, use_external_data_format=large)
+print("STAGE 0.95 Saved", flush=True)
+"#;
+
+/// `model.onnx` -> `model-int8.onnx` next to the input.
+pub fn quantized_output_path(input: &Path) -> PathBuf {
+    let stem = input.file_stem().and_then(|s| s.to_str()).unwrap_or("model");
+    input.with_file_name(format!("{stem}-int8.onnx"))
+}
+
+/// Removes the output this run started writing unless it completed: on errors and when the
+/// quantization task is dropped mid-run, e.g. aborted on shutdown (the quantizer process
+/// itself is killed on drop).
+struct PartialOutput<'a>(Option<&'a Path>);
+
+impl Drop for PartialOutput<'_> {
+    fn drop(&mut self) {
+        if let Some(path) = self.0 {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+/// Find a Python interpreter that has `onnxruntime.quantization` installed.
+async fn find_python() -> Option<&'static str> {
+    for candidate in ["python3", "python", "py"] {
+        let ok = tokio::process::Command::new(candidate)
+            .args(["-c", "import onnxruntime.quantization, onnx"])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .await
+            .map(|s| s.success())
+            .unwrap_or(false);
+        if ok {
+            return Some(candidate);
+        }
+    }
+    None
+}
+
+/// Quantize `input` to `output`, streaming progress to `progress`. A final `Completed` or
+/// `Failed` event is always sent.
+pub async fn quantize_dynamic_int8(input: PathBuf, output: PathBuf, progress: UnboundedSender<QuantizeProgress>) {
+    let result = run(&input, &output, &progress).await;
+    let _ = progress.send(match result {
+        Ok(evt) => evt,
+        Err(e) => QuantizeProgress::Failed(e.to_string()),
+    });
+}
+
+async fn run(input: &Path, output: &Path, progress: &UnboundedSender<QuantizeProgress>) -> Result<QuantizeProgress> {
+    if !input.exists() {
+        return Err(anyhow!("Model not found: {}", input.display()));
+    }
+    if output.exists() {
+        return Err(anyhow!("{} already exists", output.display()));
+    }
+    let _ = progress.send(QuantizeProgress::Stage("Checking Python ONNX Runtime tools".into(), 0.05));
+    let python = find_python().await.ok_or_else(|| {
+        anyhow!("Quantization needs Python with `onnxruntime` and `onnx` installed (pip install onnxruntime onnx)")
+    })?;
+
+    // Declared before the child so it drops after it: the quantizer is killed before its
+    // half-written output is removed
+    let mut partial = PartialOutput(Some(output));
+    let mut child = tokio::process::Command::new(python)
+        .arg("-c")
+        .arg(QUANTIZE_SCRIPT)
+        .arg(input)
+        .arg(output)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()?;
+    // Drained alongside stdout so a chatty quantizer can't block on a full pipe
+    let stderr = child.stderr.take().map(|mut stderr| {
+        tokio::spawn(async move {
+            let mut text = String::new();
+            let _ = stderr.read_to_string(&mut text).await;
+            text
+        })
+    });
+
+    if let Some(stdout) = child.stdout.take() {
+        let mut lines = BufReader::new(stdout).lines();
+        while let Some(line) = lines.next_line().await? {
+            if let Some(rest) = line.strip_prefix("STAGE ") {
+                if let Some((frac, msg)) = rest.split_once(' ') {
+                    let frac = frac.parse().unwrap_or(0.5);
+                    let _ = progress.send(QuantizeProgress::Stage(msg.to_string(), frac));
+                }
+            }
+        }
+    }
+    let status = child.wait().await?;
+    if !status.success() {
+        let stderr = match stderr {
+            Some(task) => task.await.unwrap_or_default(),
+            None => String::new(),
+        };
+        let last = stderr.lines().rev().find(|l| !l.trim().is_empty()).unwrap_or("unknown error");
+        return Err(anyhow!("Quantization failed: {}", last));
+    }
+
+    let original_size = std::fs::metadata(input)?.len();
+    let quantized_size = std::fs::metadata(output)
+        .map_err(|_| anyhow!("Quantizer finished but produced no output file"))?
+        .len();
+    partial.0 = None;
+    Ok(QuantizeProgress::Completed { output: output.to_path_buf(), original_size, quantized_size })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_output_path_is_sibling() {
+        let out = quantized_output_path(Path::new("/models/phi-3.onnx"));
+        assert_eq!(out, PathBuf::from("/models/phi-3-int8.onnx"));
+    }
+}
@@ -0,0 +1,101 @@
+//! Exact-match cache of replies, so asking the same thing again answers at once.
+//!
+//! A reply is stored under a hash of the provider it came from, the prepared prompt (roles,
+//! text and images) and the request's overrides; only an identical request hits. With a
+//! temperature above zero that trades variety for speed, which is why the cache is opt-in.
+
+use super::inference::GenerationTrace;
+use super::{ChatMessage, GenerationOverrides};
+use std::collections::{HashMap, VecDeque};
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+/// Replies kept while the cache is on.
+pub const DEFAULT_ENTRIES: usize = 256;
+
+/// Hash identifying a request to `provider`.
+pub fn key(provider: impl Hash, context: &[ChatMessage], overrides: &GenerationOverrides) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    provider.hash(&mut hasher);
+    for message in context {
+        format!("{:?}", message.role).hash(&mut hasher);
+        message.content.hash(&mut hasher);
+        for image in &message.images {
+            image.png_base64.hash(&mut hasher);
+        }
+    }
+    // Covers every sampling setting, the length limit and the output constraint
+    format!("{overrides:?}").hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Recent replies by request, oldest evicted first.
+#[derive(Default)]
+pub struct ResponseCache {
+    capacity: usize,
+    replies: HashMap<u64, (String, GenerationTrace)>,
+    order: VecDeque<u64>,
+}
+
+impl ResponseCache {
+    /// A cache of up to `capacity` replies; 0 turns it off.
+    pub fn new(capacity: usize) -> Self {
+        Self { capacity, ..Self::default() }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.capacity > 0
+    }
+
+    pub fn get(&self, key: u64) -> Option<&(String, GenerationTrace)> {
+        self.replies.get(&key)
+    }
+
+    pub fn insert(&mut self, key: u64, text: String, trace: GenerationTrace) {
+        if !self.is_enabled() {
+            return;
+        }
+        if self.replies.insert(key, (text, trace)).is_none() {
+            self.order.push_back(key);
+        }
+        while self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.replies.remove(&oldest);
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.replies.clear();
+        self.order.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_identical_requests_hit() {
+        let trace = GenerationTrace::from_context("Demo", &[], &Default::default());
+        let question = [ChatMessage::user("What is Rust?")];
+        let asked = key(1u64, &question, &GenerationOverrides::default());
+        let mut cache = ResponseCache::new(2);
+        cache.insert(asked, "A language".into(), trace.clone());
+
+        assert_eq!(cache.get(key(1u64, &question, &GenerationOverrides::default())).map(|(t, _)| t.as_str()), Some("A language"));
+        assert!(cache.get(key(2u64, &question, &GenerationOverrides::default())).is_none(), "another provider");
+        let warmer = GenerationOverrides { temperature: Some(1.2), ..Default::default() };
+        assert!(cache.get(key(1u64, &question, &warmer)).is_none(), "other sampling settings");
+        assert!(cache.get(key(1u64, &[ChatMessage::system("What is Rust?")], &GenerationOverrides::default())).is_none(), "another role");
+
+        cache.insert(2, "b".into(), trace.clone());
+        cache.insert(3, "c".into(), trace.clone());
+        assert!(cache.get(asked).is_none(), "the oldest reply is evicted");
+        cache.clear();
+        assert!(cache.get(3).is_none());
+
+        let mut off = ResponseCache::new(0);
+        off.insert(asked, "A language".into(), trace);
+        assert!(off.get(asked).is_none());
+    }
+}
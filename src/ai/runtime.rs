@@ -0,0 +1,578 @@
+//! ONNX Runtime discovery and version compatibility.
+//!
+//! `ort` is built with `load-dynamic`, so the runtime library is only opened on the first
+//! model load, and an incompatible one makes `ort` panic there. `detect` finds the library
+//! `ort` will open (same lookup rules), asks it for its version through the C API entry
+//! point and lists other copies found on disk, so the status bar and diagnostics can show
+//! the problem at startup and loads can be refused up front.
+//!
+//! Instead of changing the user's Python environment to fix an old runtime, RIA can also
+//! download official ONNX Runtime release builds, checked against pinned SHA-256 digests,
+//! into its own runtime directory and point `ort` at one of them.
+
+use crate::config::NetworkSettings;
+use anyhow::{anyhow, bail, Result};
+use serde::Serialize;
+use std::ffi::CStr;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc::UnboundedSender;
+
+/// ONNX Runtime minor version the `ort` bindings were generated for.
+pub const EXPECTED_MINOR: u32 = ort::MINOR_VERSION;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Compatibility {
+    /// Matches the bindings.
+    Supported,
+    /// Newer than the bindings; `ort` loads it with a warning.
+    Newer,
+    /// Older than the bindings; `ort` refuses to load it.
+    TooOld,
+    /// No runtime library was found, or its version could not be read.
+    Unknown,
+}
+
+impl Compatibility {
+    /// Whether a model load should be attempted at all.
+    pub fn can_load(&self) -> bool {
+        !matches!(self, Compatibility::TooOld)
+    }
+}
+
+/// One row of the compatibility table, keyed by the runtime's minor version.
+pub struct CompatEntry {
+    pub minors: std::ops::RangeInclusive<u32>,
+    pub compatibility: Compatibility,
+    pub note: &'static str,
+}
+
+pub const COMPATIBILITY_TABLE: &[CompatEntry] = &[
+    CompatEntry {
+        minors: 0..=15,
+        compatibility: Compatibility::TooOld,
+        note: "Too old for this build; install ONNX Runtime 1.22 or newer",
+    },
+    CompatEntry {
+        minors: 16..=21,
+        compatibility: Compatibility::TooOld,
+        note: "Found an older runtime (often bundled with Python or other apps); RIA needs 1.22 or newer",
+    },
+    CompatEntry {
+        minors: EXPECTED_MINOR..=EXPECTED_MINOR,
+        compatibility: Compatibility::Supported,
+        note: "Supported, including the NPU execution providers",
+    },
+    CompatEntry {
+        minors: EXPECTED_MINOR + 1..=u32::MAX,
+        compatibility: Compatibility::Newer,
+        note: "Newer than this build was tested with; usually works",
+    },
+];
+
+/// Look up a version string such as "1.22.1" in the compatibility table.
+pub fn compatibility_of(version: &str) -> (Compatibility, &'static str) {
+    let mut parts = version.trim().split('.').map(|p| p.parse::<u32>().ok());
+    match (parts.next().flatten(), parts.next().flatten()) {
+        (Some(1), Some(minor)) => COMPATIBILITY_TABLE
+            .iter()
+            .find(|entry| entry.minors.contains(&minor))
+            .map(|entry| (entry.compatibility, entry.note))
+            .unwrap_or((Compatibility::Unknown, "Unrecognized ONNX Runtime version")),
+        _ => (Compatibility::Unknown, "Unrecognized ONNX Runtime version"),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RuntimeLibrary {
+    pub path: PathBuf,
+    /// From the library itself for the selected runtime, otherwise parsed from the file name.
+    pub version: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RuntimeReport {
+    pub expected_version: String,
+    /// The library `ort` will load, if it could be opened.
+    pub selected: Option<RuntimeLibrary>,
+    /// Other ONNX Runtime libraries found in common locations.
+    pub found: Vec<RuntimeLibrary>,
+    pub compatibility: Compatibility,
+    pub note: String,
+}
+
+impl RuntimeReport {
+    /// Stand-in until [`detect`] has run, which opens the library and so runs off the UI thread.
+    pub fn detecting() -> Self {
+        Self {
+            expected_version: format!("1.{EXPECTED_MINOR}"),
+            selected: None,
+            found: Vec::new(),
+            compatibility: Compatibility::Unknown,
+            note: "Checking the ONNX Runtime library…".to_string(),
+        }
+    }
+
+    /// Short text for the status bar, e.g. "ORT 1.22.0".
+    pub fn status_label(&self) -> String {
+        match self.selected.as_ref().and_then(|lib| lib.version.as_deref()) {
+            Some(version) => format!("ORT {version}"),
+            None => "ORT not found".to_string(),
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+const DEFAULT_LIBRARY: &str = "onnxruntime.dll";
+#[cfg(target_os = "macos")]
+const DEFAULT_LIBRARY: &str = "libonnxruntime.dylib";
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+const DEFAULT_LIBRARY: &str = "libonnxruntime.so";
+
+/// The managed runtime picked with `use_managed_runtime`, if any.
+static MANAGED_LIBRARY: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+/// The library path `ort` will try: the managed runtime if one is in use, else `ort`'s own
+/// rules: `ORT_DYLIB_PATH`, else the default name next to the executable, else the default
+/// name on the system search path.
+pub fn ort_library_path() -> PathBuf {
+    if let Some(library) = MANAGED_LIBRARY.lock().ok().and_then(|managed| managed.clone()) {
+        return library;
+    }
+    let path = std::env::var("ORT_DYLIB_PATH")
+        .ok()
+        .filter(|p| !p.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_LIBRARY));
+    if path.is_absolute() {
+        return path;
+    }
+    let beside_exe = std::env::current_exe().ok().and_then(|exe| exe.parent().map(|dir| dir.join(&path)));
+    match beside_exe {
+        Some(candidate) if candidate.exists() => candidate,
+        _ => path,
+    }
+}
+
+/// Open `path` and ask the runtime for its version via `OrtGetApiBase`.
+pub fn query_version(path: &Path) -> Option<String> {
+    // SAFETY: loading runs the library's initializers, which is what `ort` does anyway.
+    // `OrtGetApiBase` and `GetVersionString` are the stable C entry points of every
+    // ONNX Runtime release and take no arguments; the returned string is static.
+    unsafe {
+        let library = libloading::Library::new(path).ok()?;
+        let get_api_base: libloading::Symbol<unsafe extern "system" fn() -> *const ort::sys::OrtApiBase> =
+            library.get(b"OrtGetApiBase").ok()?;
+        let base = get_api_base();
+        let version = if base.is_null() { std::ptr::null() } else { ((*base).GetVersionString)() };
+        let version = (!version.is_null()).then(|| CStr::from_ptr(version).to_string_lossy().into_owned());
+        // Keep the library mapped like `ort` does; unloading ONNX Runtime is not reliably safe.
+        std::mem::forget(library);
+        version
+    }
+}
+
+/// Version embedded in a file name such as `libonnxruntime.so.1.22.0` or
+/// `libonnxruntime.1.22.0.dylib`.
+fn version_from_file_name(name: &str) -> Option<String> {
+    let digits: Vec<&str> = name
+        .split('.')
+        .skip_while(|part| part.parse::<u32>().is_err())
+        .take_while(|part| part.parse::<u32>().is_ok())
+        .collect();
+    (digits.len() >= 2).then(|| digits.join("."))
+}
+
+fn is_runtime_library(name: &str) -> bool {
+    let lower = name.to_lowercase();
+    let stem_ok = lower.starts_with("libonnxruntime.") || lower.starts_with("onnxruntime.");
+    let ext_ok = lower.ends_with(".dll") || lower.ends_with(".dylib") || lower.contains(".so");
+    stem_ok && ext_ok
+}
+
+fn search_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if let Some(dir) = std::env::current_exe().ok().and_then(|exe| exe.parent().map(Path::to_path_buf)) {
+        dirs.push(dir);
+    }
+    let path_var = if cfg!(target_os = "windows") {
+        "PATH"
+    } else if cfg!(target_os = "macos") {
+        "DYLD_LIBRARY_PATH"
+    } else {
+        "LD_LIBRARY_PATH"
+    };
+    if let Some(paths) = std::env::var_os(path_var) {
+        dirs.extend(std::env::split_paths(&paths));
+    }
+    if cfg!(target_os = "windows") {
+        let windir = std::env::var_os("WINDIR").map(PathBuf::from).unwrap_or_else(|| PathBuf::from(r"C:\Windows"));
+        dirs.push(windir.join("System32"));
+    } else if cfg!(target_os = "macos") {
+        dirs.extend(["/usr/local/lib", "/opt/homebrew/lib"].map(PathBuf::from));
+    } else {
+        dirs.extend(["/usr/lib", "/usr/local/lib", "/usr/lib64", "/usr/lib/x86_64-linux-gnu", "/usr/lib/aarch64-linux-gnu"].map(PathBuf::from));
+    }
+    dirs.dedup();
+    dirs
+}
+
+/// ONNX Runtime libraries in `dirs`, with versions taken from their file names.
+fn scan(dirs: &[PathBuf]) -> Vec<RuntimeLibrary> {
+    let mut found: Vec<RuntimeLibrary> = dirs
+        .iter()
+        .filter_map(|dir| std::fs::read_dir(dir).ok())
+        .flat_map(|entries| entries.flatten())
+        .filter(|entry| is_runtime_library(&entry.file_name().to_string_lossy()))
+        .map(|entry| RuntimeLibrary {
+            version: version_from_file_name(&entry.file_name().to_string_lossy()),
+            path: entry.path(),
+        })
+        .collect();
+    found.sort_by(|a, b| a.path.cmp(&b.path));
+    found.dedup_by(|a, b| a.path == b.path);
+    found
+}
+
+/// Find the runtime `ort` will load and check it against the compatibility table.
+pub fn detect() -> RuntimeReport {
+    let path = ort_library_path();
+    let selected = query_version(&path).map(|version| RuntimeLibrary { path: path.clone(), version: Some(version) });
+    let (compatibility, note) = match selected.as_ref().and_then(|lib| lib.version.as_deref()) {
+        Some(version) => compatibility_of(version),
+        None => (Compatibility::Unknown, "ONNX Runtime library not found; models will run in demo mode"),
+    };
+    let report = RuntimeReport {
+        expected_version: format!("1.{EXPECTED_MINOR}"),
+        selected,
+        found: scan(&search_dirs()),
+        compatibility,
+        note: note.to_string(),
+    };
+    tracing::info!("ONNX Runtime: {} ({:?}) at {}", report.status_label(), report.compatibility, path.display());
+    report
+}
+
+/// ONNX Runtime releases that can be downloaded as managed runtimes.
+pub const MANAGED_VERSIONS: &[&str] = &["1.22.0", "1.22.1", "1.23.0"];
+/// The managed runtime offered by the auto-fix: the newest release matching the bindings.
+pub const RECOMMENDED_VERSION: &str = "1.22.1";
+
+/// SHA-256 of the release archives of `MANAGED_VERSIONS`, as (archive name, hex digest).
+/// Archives without an entry are refused rather than loaded unverified, so a version added
+/// above needs the digests of its release assets here (`sha256sum onnxruntime-*-<version>.*`).
+const RELEASE_SHA256: &[(&str, &str)] = &[];
+
+/// Whether `version` can be downloaded here: its archive for this platform has a pinned
+/// digest. Versions without one are hidden from the runtime manager and the auto-fix.
+pub fn is_downloadable(version: &str) -> bool {
+    release_archive(version).is_some_and(|name| pinned_sha256(&name, RELEASE_SHA256).is_ok())
+}
+
+/// The digest pinned for `archive_name` in `pinned`.
+fn pinned_sha256<'a>(archive_name: &str, pinned: &[(&str, &'a str)]) -> Result<&'a str> {
+    pinned
+        .iter()
+        .find(|(name, _)| *name == archive_name)
+        .map(|(_, digest)| *digest)
+        .ok_or_else(|| anyhow!("this build has no pinned SHA-256 for {archive_name}, so the download can't be verified"))
+}
+
+fn verify_archive(archive_name: &str, digest: &[u8], expected: &str) -> Result<()> {
+    let actual: String = digest.iter().map(|b| format!("{b:02x}")).collect();
+    if !actual.eq_ignore_ascii_case(expected) {
+        bail!("{archive_name} doesn't match its pinned SHA-256 (got {actual}); the download was discarded");
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone)]
+pub enum InstallProgress {
+    /// Human readable stage and overall fraction in 0..=1.
+    Stage(String, f32),
+    Completed { version: String, library: PathBuf, downloaded: u64 },
+    Failed(String),
+    /// Stopped through the cancel flag, with the partial files removed.
+    Cancelled,
+}
+
+/// Release archive name for this platform, e.g. `onnxruntime-linux-x64-1.22.0.tgz`.
+pub fn release_archive(version: &str) -> Option<String> {
+    let platform = match (std::env::consts::OS, std::env::consts::ARCH) {
+        ("linux", "x86_64") => "linux-x64",
+        ("linux", "aarch64") => "linux-aarch64",
+        ("macos", _) => "osx-universal2",
+        ("windows", "x86_64") => "win-x64",
+        ("windows", "aarch64") => "win-arm64",
+        _ => return None,
+    };
+    let extension = if cfg!(target_os = "windows") { "zip" } else { "tgz" };
+    Some(format!("onnxruntime-{platform}-{version}.{extension}"))
+}
+
+pub fn release_url(version: &str) -> Option<String> {
+    release_archive(version)
+        .map(|archive| format!("https://github.com/microsoft/onnxruntime/releases/download/v{version}/{archive}"))
+}
+
+/// Library of the managed runtime `version` under `root`.
+pub fn managed_library(root: &Path, version: &str) -> PathBuf {
+    root.join(version).join(DEFAULT_LIBRARY)
+}
+
+/// Managed runtime versions installed under `root`, sorted.
+pub fn installed_versions(root: &Path) -> Vec<String> {
+    let mut versions: Vec<String> = std::fs::read_dir(root)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| entry.file_name().to_string_lossy().into_owned())
+                .filter(|name| !name.ends_with(".tmp") && managed_library(root, name).exists())
+                .collect()
+        })
+        .unwrap_or_default();
+    versions.sort();
+    versions
+}
+
+/// Point `ort` at the managed runtime `version`, or back at the system one for `None`.
+///
+/// The choice is handed to `ort` by `bind_library` when a model is first loaded, so switching
+/// after that only takes effect after a restart. A user-provided `ORT_DYLIB_PATH` applies
+/// whenever no managed runtime is chosen.
+pub fn use_managed_runtime(root: &Path, version: Option<&str>) {
+    let library = version.map(|v| managed_library(root, v)).filter(|library| library.exists());
+    if let Ok(mut managed) = MANAGED_LIBRARY.lock() {
+        *managed = library;
+    }
+}
+
+/// Give `ort` the library from `ort_library_path` before it opens one. `ort` keeps the first
+/// path it's given, so later calls have no effect.
+pub fn bind_library() {
+    // Only the path is wanted; `ort` still creates its default environment on first use
+    let _ = ort::init_from(ort_library_path().to_string_lossy());
+}
+
+pub fn remove_managed(root: &Path, version: &str) -> Result<()> {
+    std::fs::remove_dir_all(root.join(version))?;
+    Ok(())
+}
+
+/// Download the official release `version` for this platform into `root/<version>`.
+/// The download goes through `network`'s proxy and certificates, as model downloads do.
+/// Setting `cancel` stops it, extraction included; partial files are removed once nothing
+/// writes to them any more, then `Cancelled` is sent.
+pub async fn install(root: PathBuf, version: String, network: NetworkSettings, cancel: Arc<AtomicBool>, progress: UnboundedSender<InstallProgress>) {
+    let result = download_and_extract(&root, &version, &network, &cancel, &progress).await;
+    let _ = progress.send(match result {
+        Ok((library, downloaded)) => InstallProgress::Completed { version, library, downloaded },
+        Err(_) if cancel.load(Ordering::Relaxed) => {
+            discard_partial(&root, &version);
+            InstallProgress::Cancelled
+        }
+        Err(e) => InstallProgress::Failed(format!("ONNX Runtime {version}: {e}")),
+    });
+}
+
+/// Returns the installed library and the archive size.
+async fn download_and_extract(
+    root: &Path,
+    version: &str,
+    network: &NetworkSettings,
+    cancel: &Arc<AtomicBool>,
+    progress: &UnboundedSender<InstallProgress>,
+) -> Result<(PathBuf, u64)> {
+    use futures_util::StreamExt;
+    use sha2::{Digest, Sha256};
+    use tokio::io::AsyncWriteExt;
+
+    let (Some(archive_name), Some(url)) = (release_archive(version), release_url(version)) else {
+        bail!("no release build is published for this platform");
+    };
+    let expected = pinned_sha256(&archive_name, RELEASE_SHA256)?;
+    std::fs::create_dir_all(root)?;
+    let archive = root.join(format!("{archive_name}.part"));
+
+    let _ = progress.send(InstallProgress::Stage(format!("Downloading {archive_name}"), 0.0));
+    let response = network.http_client()?.get(&url).send().await?.error_for_status()?;
+    let total = response.content_length().unwrap_or(0);
+    let mut file = tokio::fs::File::create(&archive).await?;
+    let mut stream = response.bytes_stream();
+    let mut downloaded = 0u64;
+    let mut hasher = Sha256::new();
+    let mut last_update = std::time::Instant::now();
+    while let Some(chunk) = stream.next().await {
+        if cancel.load(Ordering::Relaxed) {
+            bail!("cancelled");
+        }
+        let chunk = chunk?;
+        file.write_all(&chunk).await?;
+        hasher.update(&chunk);
+        downloaded += chunk.len() as u64;
+        if total > 0 && last_update.elapsed().as_millis() >= 100 {
+            let stage = format!(
+                "Downloading {} of {}",
+                crate::utils::format_file_size(downloaded),
+                crate::utils::format_file_size(total)
+            );
+            let _ = progress.send(InstallProgress::Stage(stage, 0.9 * downloaded as f32 / total as f32));
+            last_update = std::time::Instant::now();
+        }
+    }
+    file.flush().await?;
+    drop(file);
+    if let Err(e) = verify_archive(&archive_name, &hasher.finalize(), expected) {
+        let _ = std::fs::remove_file(&archive);
+        return Err(e);
+    }
+
+    let _ = progress.send(InstallProgress::Stage("Extracting".to_string(), 0.9));
+    let staging = root.join(format!("{version}.tmp"));
+    let destination = root.join(version);
+    let cancel = cancel.clone();
+    tokio::task::spawn_blocking(move || {
+        let extracted = extract_libraries(&archive, &staging, &cancel);
+        let _ = std::fs::remove_file(&archive);
+        extracted?;
+        if cancel.load(Ordering::Relaxed) {
+            bail!("cancelled");
+        }
+        if destination.exists() {
+            std::fs::remove_dir_all(&destination)?;
+        }
+        std::fs::rename(&staging, &destination)?;
+        Ok((destination.join(DEFAULT_LIBRARY), downloaded))
+    })
+    .await?
+}
+
+/// Remove what an interrupted `install` of `version` left behind.
+pub fn discard_partial(root: &Path, version: &str) {
+    if let Some(archive) = release_archive(version) {
+        let _ = std::fs::remove_file(root.join(format!("{archive}.part")));
+    }
+    let _ = std::fs::remove_dir_all(root.join(format!("{version}.tmp")));
+}
+
+/// Unpack a release archive into `dest`, keeping only the contents of its `lib` directory.
+/// Checks `cancel` between archive entries (and while `tar` runs, killing it).
+fn extract_libraries(archive: &Path, dest: &Path, cancel: &AtomicBool) -> Result<()> {
+    if dest.exists() {
+        std::fs::remove_dir_all(dest)?;
+    }
+    let unpacked = dest.join("unpacked");
+    std::fs::create_dir_all(&unpacked)?;
+    if archive.to_string_lossy().contains(".zip") {
+        // Windows 10 and later ship bsdtar, which reads zip archives
+        let mut child = std::process::Command::new("tar").arg("-xf").arg(archive).arg("-C").arg(&unpacked).spawn()?;
+        let status = loop {
+            if let Some(status) = child.try_wait()? {
+                break status;
+            }
+            if cancel.load(Ordering::Relaxed) {
+                let _ = child.kill();
+                let _ = child.wait();
+                bail!("cancelled");
+            }
+            std::thread::sleep(std::time::Duration::from_millis(100));
+        };
+        if !status.success() {
+            bail!("failed to unpack {}", archive.display());
+        }
+    } else {
+        let gz = flate2::read::GzDecoder::new(std::fs::File::open(archive)?);
+        let mut tarball = tar::Archive::new(gz);
+        for entry in tarball.entries()? {
+            if cancel.load(Ordering::Relaxed) {
+                bail!("cancelled");
+            }
+            entry?.unpack_in(&unpacked)?;
+        }
+    }
+
+    // Releases contain a single `onnxruntime-<platform>-<version>/` folder
+    let lib_dir = std::fs::read_dir(&unpacked)?
+        .flatten()
+        .map(|entry| entry.path().join("lib"))
+        .find(|lib| lib.is_dir())
+        .ok_or_else(|| anyhow!("archive has no lib directory"))?;
+    for entry in std::fs::read_dir(&lib_dir)?.flatten() {
+        // Symlinks such as libonnxruntime.so -> libonnxruntime.so.1 are moved as they are
+        if !entry.file_type()?.is_dir() {
+            std::fs::rename(entry.path(), dest.join(entry.file_name()))?;
+        }
+    }
+    std::fs::remove_dir_all(&unpacked)?;
+    if std::fs::symlink_metadata(dest.join(DEFAULT_LIBRARY)).is_err() {
+        bail!("archive has no {DEFAULT_LIBRARY}");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compatibility_table() {
+        assert_eq!(compatibility_of(&format!("1.{EXPECTED_MINOR}.1")).0, Compatibility::Supported);
+        assert_eq!(compatibility_of("1.17.1").0, Compatibility::TooOld);
+        assert_eq!(compatibility_of(&format!("1.{}.0", EXPECTED_MINOR + 1)).0, Compatibility::Newer);
+        assert_eq!(compatibility_of("garbage").0, Compatibility::Unknown);
+        assert!(!Compatibility::TooOld.can_load());
+    }
+
+    #[test]
+    fn test_library_file_names() {
+        assert_eq!(version_from_file_name("libonnxruntime.so.1.22.0").as_deref(), Some("1.22.0"));
+        assert_eq!(version_from_file_name("libonnxruntime.1.17.1.dylib").as_deref(), Some("1.17.1"));
+        assert_eq!(version_from_file_name("onnxruntime.dll"), None);
+        assert!(is_runtime_library("libonnxruntime.so.1.22.0"));
+        assert!(!is_runtime_library("libonnxruntime_providers_shared.so"));
+    }
+
+    #[test]
+    fn test_verify_archive_against_pinned_digest() {
+        use sha2::{Digest, Sha256};
+        let digest = Sha256::digest(b"archive");
+        let hex: String = digest.iter().map(|b| format!("{b:02x}")).collect();
+        let pinned = [("onnxruntime-test-1.22.0.tgz", hex.as_str())];
+        let expected = pinned_sha256("onnxruntime-test-1.22.0.tgz", &pinned).unwrap();
+        assert!(verify_archive("onnxruntime-test-1.22.0.tgz", &digest, &expected.to_uppercase()).is_ok());
+        assert!(verify_archive("onnxruntime-test-1.22.0.tgz", &Sha256::digest(b"tampered"), expected).is_err());
+        assert!(pinned_sha256("onnxruntime-test-1.23.0.tgz", &pinned).is_err());
+    }
+
+    #[test]
+    fn test_extract_managed_runtime() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("onnxruntime-test-1.22.0.tgz");
+        {
+            let gz = flate2::write::GzEncoder::new(std::fs::File::create(&archive).unwrap(), flate2::Compression::fast());
+            let mut builder = tar::Builder::new(gz);
+            for (name, data) in [
+                (format!("onnxruntime-test-1.22.0/lib/{DEFAULT_LIBRARY}"), &b"lib"[..]),
+                ("onnxruntime-test-1.22.0/include/onnxruntime_c_api.h".to_string(), &b"header"[..]),
+            ] {
+                let mut header = tar::Header::new_gnu();
+                header.set_size(data.len() as u64);
+                header.set_mode(0o644);
+                header.set_cksum();
+                builder.append_data(&mut header, name, data).unwrap();
+            }
+            builder.into_inner().unwrap().finish().unwrap();
+        }
+
+        let root = dir.path().join("runtime");
+        extract_libraries(&archive, &root.join("1.22.0"), &AtomicBool::new(false)).unwrap();
+        assert!(managed_library(&root, "1.22.0").exists());
+        assert!(!root.join("1.22.0").join("onnxruntime_c_api.h").exists());
+        assert_eq!(installed_versions(&root), vec!["1.22.0".to_string()]);
+
+        // A cancelled extraction stops before unpacking anything
+        assert!(extract_libraries(&archive, &root.join("1.23.0"), &AtomicBool::new(true)).is_err());
+        assert!(!managed_library(&root, "1.23.0").exists());
+    }
+}
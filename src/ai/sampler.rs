@@ -1,13 +1,18 @@
 use rand::prelude::*;
+use super::constraint::ConstraintState;
+use std::collections::BTreeMap;
+
+/// A logit bias at or below this bans the token outright.
+pub const BAN_BIAS: f32 = -100.0;
 
 /// Logits sampling strategy
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum SamplingStrategy {
+    /// Always the most likely token.
     Greedy,
-    #[cfg(feature = "greedy_decode")]
-    TopK { k: usize },
-    #[cfg(feature = "greedy_decode")]
-    TopP { p: f32 },
+    /// Draw from the softmax at the configured temperature, restricted to the `top_k` most
+    /// likely tokens (0: all of them) and then to the smallest set reaching `top_p`.
+    Sample { top_k: usize, top_p: f32 },
 }
 
 /// Sampler configuration
@@ -15,65 +20,172 @@ pub enum SamplingStrategy {
 pub struct SamplerConfig {
     pub temperature: f32,
     pub strategy: SamplingStrategy,
+    /// (token id, bias) added to the logits before anything else; see [`resolve_logit_bias`].
+    pub logit_bias: Vec<(usize, f32)>,
 }
 
-impl Default for SamplerConfig { fn default() -> Self { Self { temperature: 0.8, strategy: SamplingStrategy::Greedy } } }
+impl Default for SamplerConfig { fn default() -> Self { Self { temperature: 0.8, strategy: SamplingStrategy::Greedy, logit_bias: Vec::new() } } }
+
+impl SamplerConfig {
+    /// The sampler for a request's settings; a temperature of 0 decodes greedily.
+    pub fn from_settings(temperature: f32, top_k: u32, top_p: f32, logit_bias: Vec<(usize, f32)>) -> Self {
+        let strategy = if temperature <= 0.0 {
+            SamplingStrategy::Greedy
+        } else {
+            SamplingStrategy::Sample { top_k: top_k as usize, top_p: if top_p > 0.0 { top_p.min(1.0) } else { 1.0 } }
+        };
+        Self { temperature, strategy, logit_bias }
+    }
+}
+
+/// Map a word → bias table onto token ids. A key matches tokens whose text equals it once
+/// the word-boundary markers of BPE (`Ġ`) and SentencePiece (`▁`) vocabularies and
+/// surrounding whitespace are stripped, so "the" covers both "the" and " the". Biases at
+/// or below [`BAN_BIAS`] become negative infinity.
+pub fn resolve_logit_bias(bias: &BTreeMap<String, f32>, vocab: &[String]) -> Vec<(usize, f32)> {
+    if bias.is_empty() {
+        return Vec::new();
+    }
+    vocab
+        .iter()
+        .enumerate()
+        .filter_map(|(id, token)| {
+            let word = token.trim_start_matches(['Ġ', '▁']).trim();
+            let bias = *bias.get(word)?;
+            Some((id, if bias <= BAN_BIAS { f32::NEG_INFINITY } else { bias }))
+        })
+        .collect()
+}
 
-/// Simple sampler applying temperature + strategy to logits (placeholder implementation)
+/// Picks the next token from a model's logits: bias first, then temperature and the
+/// strategy's cut-offs, then softmax.
 pub struct LogitsSampler {
     cfg: SamplerConfig,
-    rng: ThreadRng,
+    rng: StdRng,
 }
 
 impl LogitsSampler {
-    pub fn new(cfg: SamplerConfig) -> Self { Self { cfg, rng: thread_rng() } }
+    pub fn new(cfg: SamplerConfig) -> Self { Self { cfg, rng: StdRng::from_entropy() } }
 
+    /// A sampler whose draws repeat for the same `seed`, for evaluation runs.
+    pub fn seeded(cfg: SamplerConfig, seed: u64) -> Self { Self { cfg, rng: StdRng::seed_from_u64(seed) } }
+
+    /// The next token, or `None` if every token is banned.
     pub fn sample(&mut self, logits: &[f32]) -> Option<usize> {
         if logits.is_empty() { return None; }
+        let biased: Vec<f32>;
+        let logits = if self.cfg.logit_bias.is_empty() {
+            logits
+        } else {
+            biased = self.apply_logit_bias(logits);
+            &biased
+        };
+        if logits.iter().all(|l| *l == f32::NEG_INFINITY) { return None; }
         match self.cfg.strategy {
             SamplingStrategy::Greedy => logits.iter().enumerate().max_by(|a,b| a.1.total_cmp(b.1)).map(|(i,_)| i),
-            #[cfg(feature = "greedy_decode")]
-            SamplingStrategy::TopK { k } => self.sample_top_k(logits, k.max(1)),
-            #[cfg(feature = "greedy_decode")]
-            SamplingStrategy::TopP { p } => self.sample_top_p(logits, p),
+            SamplingStrategy::Sample { top_k, top_p } => self.sample_softmax(logits, top_k, top_p),
         }
     }
 
-    #[cfg(feature = "greedy_decode")]
-    fn sample_top_k(&mut self, logits: &[f32], k: usize) -> Option<usize> {
-        let mut idx: Vec<usize> = (0..logits.len()).collect();
-        idx.sort_unstable_by(|a,b| logits[*b].total_cmp(&logits[*a]));
-        let k = k.min(idx.len());
-        let slice = &idx[..k];
-        self.weighted_choice(logits, slice)
+    /// Sample among the tokens whose `texts` keep the output matchable; an `eos` token only
+    /// once the output is complete. Masked logits are set to negative infinity in place.
+    pub fn sample_constrained(&mut self, logits: &mut [f32], texts: &[String], state: &ConstraintState, eos: &[usize]) -> Option<usize> {
+        let complete = state.is_complete();
+        for (i, logit) in logits.iter_mut().enumerate() {
+            let allowed = if eos.contains(&i) {
+                complete
+            } else {
+                texts.get(i).is_some_and(|text| !text.is_empty() && state.allows(text))
+            };
+            if !allowed {
+                *logit = f32::NEG_INFINITY;
+            }
+        }
+        self.sample(logits)
     }
 
-    #[cfg(feature = "greedy_decode")]
-    fn sample_top_p(&mut self, logits: &[f32], p: f32) -> Option<usize> {
-        let mut idx: Vec<usize> = (0..logits.len()).collect();
-        idx.sort_unstable_by(|a,b| logits[*b].total_cmp(&logits[*a]));
-        let mut cum = 0f32;
-        let mut selected = Vec::new();
-        let exp_logits: Vec<f32> = logits.iter().map(|&l| (l / self.cfg.temperature).exp()).collect();
-        let mut ordered: Vec<(usize,f32)> = idx.iter().map(|&i| (i, exp_logits[i])).collect();
-        let total: f32 = ordered.iter().map(|(_,v)| *v).sum();
-        ordered.iter_mut().for_each(|(_,v)| *v /= total.max(1e-9));
-        for (i, prob) in ordered.iter() {
-            cum += *prob;
-            selected.push(*i);
-            if cum >= p { break; }
+    fn apply_logit_bias(&self, logits: &[f32]) -> Vec<f32> {
+        let mut biased = logits.to_vec();
+        for &(id, bias) in &self.cfg.logit_bias {
+            if let Some(logit) = biased.get_mut(id) {
+                *logit += bias;
+            }
         }
-        self.weighted_choice(&exp_logits, &selected)
+        biased
     }
 
-    #[cfg(feature = "greedy_decode")]
-    fn weighted_choice(&mut self, weights_source: &[f32], indices: &[usize]) -> Option<usize> {
-        if indices.is_empty() { return None; }
+    fn sample_softmax(&mut self, logits: &[f32], top_k: usize, top_p: f32) -> Option<usize> {
+        let mut idx: Vec<usize> = (0..logits.len()).filter(|&i| logits[i] > f32::NEG_INFINITY).collect();
+        idx.sort_unstable_by(|a,b| logits[*b].total_cmp(&logits[*a]));
+        if top_k > 0 {
+            idx.truncate(top_k);
+        }
+        // Softmax over the candidates, shifted by the largest logit to stay finite
+        let temperature = self.cfg.temperature.max(1e-4);
+        let max = logits[*idx.first()?];
+        let weights: Vec<f32> = idx.iter().map(|&i| ((logits[i] - max) / temperature).exp()).collect();
+        let total: f32 = weights.iter().sum();
+        let mut kept = 0;
         let mut cum = 0f32;
-        for &i in indices { cum += weights_source[i].max(0.0); }
-        let r = self.rng.gen::<f32>() * cum.max(1e-9);
+        for weight in &weights {
+            cum += weight / total;
+            kept += 1;
+            if cum >= top_p { break; }
+        }
+        let weights = &weights[..kept];
+        let r = self.rng.gen::<f32>() * weights.iter().sum::<f32>();
         let mut run = 0f32;
-        for &i in indices { run += weights_source[i].max(0.0); if run >= r { return Some(i); } }
-        Some(*indices.last().unwrap())
+        for (&i, weight) in idx.iter().zip(weights) {
+            run += weight;
+            if run >= r { return Some(i); }
+        }
+        idx.get(kept - 1).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_logit_bias_boosts_and_bans_words() {
+        let vocab: Vec<String> = ["Ġthe", "cat", "▁dog", "the", "dogs"].map(String::from).to_vec();
+        let bias = BTreeMap::from([("the".to_string(), 5.0), ("dog".to_string(), BAN_BIAS)]);
+        let resolved = resolve_logit_bias(&bias, &vocab);
+        assert_eq!(resolved, vec![(0, 5.0), (2, f32::NEG_INFINITY), (3, 5.0)]);
+
+        let mut sampler = LogitsSampler::new(SamplerConfig { logit_bias: resolved, ..Default::default() });
+        assert_eq!(sampler.sample(&[0.0, 4.0, 9.0, 0.0, 1.0]), Some(3));
+        assert_eq!(sampler.sample(&[0.0, 6.0, 9.0, 0.0, 1.0]), Some(1));
+    }
+
+    #[test]
+    fn test_sampling_respects_top_k_top_p_and_bans() {
+        let logits = [1.0, 5.0, 4.9, -2.0, 0.5];
+        let mut top_k = LogitsSampler::seeded(SamplerConfig::from_settings(1.0, 2, 1.0, Vec::new()), 7);
+        for _ in 0..200 {
+            assert!(matches!(top_k.sample(&logits), Some(1 | 2)));
+        }
+        // Token 1 alone reaches top-p 0.4
+        let mut top_p = LogitsSampler::seeded(SamplerConfig::from_settings(1.0, 0, 0.4, Vec::new()), 7);
+        assert!((0..50).all(|_| top_p.sample(&logits) == Some(1)));
+        // A banned token is never drawn, and nothing is drawn when everything is banned
+        let banned = SamplerConfig::from_settings(1.0, 0, 1.0, vec![(1, f32::NEG_INFINITY), (2, f32::NEG_INFINITY)]);
+        let mut sampler = LogitsSampler::seeded(banned, 7);
+        assert!((0..200).all(|_| !matches!(sampler.sample(&logits), Some(1 | 2) | None)));
+        assert_eq!(sampler.sample(&[f32::NEG_INFINITY, 0.0, 0.0]), None);
+        let mut greedy = LogitsSampler::new(SamplerConfig::from_settings(0.0, 40, 0.9, Vec::new()));
+        assert_eq!(greedy.sample(&logits), Some(1));
+    }
+
+    #[test]
+    fn test_seeded_sampling_repeats() {
+        let logits: Vec<f32> = (0..50).map(|i| (i % 7) as f32 * 0.3).collect();
+        let config = SamplerConfig::from_settings(0.9, 0, 0.95, Vec::new());
+        let mut a = LogitsSampler::seeded(config.clone(), 42);
+        let mut b = LogitsSampler::seeded(config, 42);
+        let draws_a: Vec<_> = (0..20).map(|_| a.sample(&logits)).collect();
+        let draws_b: Vec<_> = (0..20).map(|_| b.sample(&logits)).collect();
+        assert_eq!(draws_a, draws_b);
     }
 }
@@ -157,28 +157,7 @@ impl SimpleTokenizer {
     pub fn prepare_chat_input(&mut self, messages: &[crate::ai::ChatMessage]) -> Vec<i64> {
         // If HF tokenizer is available, build a simple role-based prompt string and encode
         if let Some(hf) = &self.hf {
-            let mut prompt = String::new();
-            for m in messages {
-                match m.role {
-                    crate::ai::MessageRole::System => {
-                        prompt.push_str("System: ");
-                        prompt.push_str(&m.content);
-                        prompt.push('\n');
-                    }
-                    crate::ai::MessageRole::User => {
-                        prompt.push_str("User: ");
-                        prompt.push_str(&m.content);
-                        prompt.push('\n');
-                    }
-                    crate::ai::MessageRole::Assistant => {
-                        prompt.push_str("Assistant: ");
-                        prompt.push_str(&m.content);
-                        prompt.push('\n');
-                    }
-                }
-            }
-            // Prompt the assistant for the next turn
-            prompt.push_str("Assistant: ");
+            let prompt = render_chat_prompt(messages);
             if let Ok(enc) = hf.encode(prompt, true) {
                 return enc.get_ids().iter().map(|&id| id as i64).collect();
             }
@@ -198,23 +177,143 @@ impl SimpleTokenizer {
                 crate::ai::MessageRole::User => "user",
                 crate::ai::MessageRole::Assistant => "assistant",
                 crate::ai::MessageRole::System => "system",
+                crate::ai::MessageRole::Tool => "tool",
             };
             
             let message_tokens = self.encode_chat(role, &message.content);
             all_tokens.extend(message_tokens);
         }
         
-        // Add assistant token to prompt for response
-        if let Some(assistant_token) = self.get_special_token("<|assistant|>") {
-            all_tokens.push(assistant_token);
+        // Add assistant token to prompt for response, unless an unfinished reply is being continued
+        if !ends_with_reply(messages) {
+            if let Some(assistant_token) = self.get_special_token("<|assistant|>") {
+                all_tokens.push(assistant_token);
+            }
         }
         
         all_tokens
     }
 
+    /// The prompt as text: the role-based string for HF tokenizers, or the basic vocabulary's
+    /// special-token form, matching what `prepare_chat_input` encodes.
+    pub fn render_prompt(&self, messages: &[crate::ai::ChatMessage]) -> String {
+        if self.hf.is_some() {
+            return render_chat_prompt(messages);
+        }
+        let mut prompt = String::from("<|startoftext|>");
+        for message in messages {
+            let role = match message.role {
+                crate::ai::MessageRole::Assistant => "<|assistant|>",
+                crate::ai::MessageRole::System => "<|system|>",
+                crate::ai::MessageRole::User | crate::ai::MessageRole::Tool => "<|user|>",
+            };
+            prompt.push_str(role);
+            prompt.push_str(&message.content);
+        }
+        if !ends_with_reply(messages) {
+            prompt.push_str("<|assistant|>");
+        }
+        prompt
+    }
+
     pub fn vocab_size(&self) -> usize {
         self.vocab.len()
     }
+
+    /// Whether this is the model's own tokenizer (from its `tokenizer.json`) rather than the
+    /// basic built-in vocabulary; model output can only be decoded with the former.
+    pub fn is_model_tokenizer(&self) -> bool {
+        self.hf.is_some()
+    }
+
+    /// Id of a token in the vocabulary, e.g. an end-of-sequence token.
+    pub fn token_id(&self, token: &str) -> Option<i64> {
+        match &self.hf {
+            Some(hf) => hf.token_to_id(token).map(i64::from),
+            None => self.vocab.get(token).copied(),
+        }
+    }
+
+    /// Token strings indexed by id; ids missing from the vocabulary are empty.
+    pub fn vocabulary(&self) -> Vec<String> {
+        let entries: Vec<(String, i64)> = match &self.hf {
+            Some(hf) => hf.get_vocab(true).into_iter().map(|(token, id)| (token, i64::from(id))).collect(),
+            None => self.vocab.iter().map(|(token, id)| (token.clone(), *id)).collect(),
+        };
+        let len = entries.iter().map(|(_, id)| *id + 1).max().unwrap_or(0).max(0) as usize;
+        let mut vocabulary = vec![String::new(); len];
+        for (token, id) in entries {
+            if let Some(slot) = usize::try_from(id).ok().and_then(|id| vocabulary.get_mut(id)) {
+                *slot = token;
+            }
+        }
+        vocabulary
+    }
+
+    /// The text each token adds to the output, indexed by id, for matching tokens against an
+    /// output constraint. Special tokens add no text and are empty.
+    pub fn token_texts(&self) -> Vec<String> {
+        let special: Vec<usize> = match &self.hf {
+            Some(hf) => hf.get_added_tokens_decoder().into_iter().filter(|(_, t)| t.special).map(|(id, _)| id as usize).collect(),
+            None => self.special_tokens.values().map(|id| *id as usize).collect(),
+        };
+        let mut texts: Vec<String> = self.vocabulary().iter().map(|token| token_text(token)).collect();
+        for id in special {
+            if let Some(text) = texts.get_mut(id) {
+                text.clear();
+            }
+        }
+        texts
+    }
+}
+
+/// Undo the vocabulary's encoding of a token: the word-boundary markers of BPE (`Ġ`) and
+/// SentencePiece (`▁`) become spaces, BPE's `Ċ`/`ĉ` newlines and tabs, and SentencePiece
+/// byte tokens like `<0x0A>` their ASCII character.
+fn token_text(token: &str) -> String {
+    if let Some(byte) = token.strip_prefix("<0x").and_then(|rest| rest.strip_suffix('>')) {
+        return u8::from_str_radix(byte, 16).ok().filter(u8::is_ascii).map(|b| char::from(b).to_string()).unwrap_or_default();
+    }
+    token
+        .chars()
+        .map(|c| match c {
+            'Ġ' | '▁' => ' ',
+            'Ċ' => '\n',
+            'ĉ' => '\t',
+            c => c,
+        })
+        .collect()
+}
+
+/// Whether the transcript ends in an assistant message, which is then left open so the
+/// model carries on with it rather than starting a new reply.
+fn ends_with_reply(messages: &[crate::ai::ChatMessage]) -> bool {
+    messages.last().is_some_and(|m| matches!(m.role, crate::ai::MessageRole::Assistant))
+}
+
+/// Role-prefixed transcript ending with an open assistant turn, e.g.
+/// `"User: hi\nAssistant: "`. A trailing assistant message is that open turn.
+pub fn render_chat_prompt(messages: &[crate::ai::ChatMessage]) -> String {
+    let mut prompt = String::new();
+    for m in messages {
+        let role = match m.role {
+            crate::ai::MessageRole::System => "System",
+            crate::ai::MessageRole::User => "User",
+            crate::ai::MessageRole::Assistant => "Assistant",
+            crate::ai::MessageRole::Tool => "Tool",
+        };
+        prompt.push_str(role);
+        prompt.push_str(": ");
+        prompt.push_str(&m.content);
+        prompt.push('\n');
+    }
+    if ends_with_reply(messages) {
+        prompt.pop();
+    } else {
+        // Prompt the assistant for the next turn
+        prompt.push_str("Assistant: ");
+    }
+    prompt
 }
 
 #[cfg(test)]
@@ -252,4 +351,27 @@ mod tests {
         // Should start with user token
         assert_eq!(tokens[0], tokenizer.get_special_token("<|user|>").unwrap());
     }
+
+    #[test]
+    fn test_unfinished_reply_is_left_open() {
+        use crate::ai::ChatMessage;
+        let question = ChatMessage::user("Count to five");
+        let partial = ChatMessage::assistant("1, 2,");
+
+        assert_eq!(render_chat_prompt(std::slice::from_ref(&question)), "User: Count to five\nAssistant: ");
+        assert_eq!(render_chat_prompt(&[question.clone(), partial.clone()]), "User: Count to five\nAssistant: 1, 2,");
+        let tokenizer = SimpleTokenizer::new();
+        assert!(tokenizer.render_prompt(&[question, partial]).ends_with("<|assistant|>1, 2,"));
+    }
+
+    #[test]
+    fn test_token_text_undoes_vocabulary_markers() {
+        assert_eq!(token_text("Ġ{\""), " {\"");
+        assert_eq!(token_text("▁key"), " key");
+        assert_eq!(token_text("ĊĊ"), "\n\n");
+        assert_eq!(token_text("<0x0A>"), "\n");
+        assert_eq!(token_text("<0xE2>"), "");
+        let texts = SimpleTokenizer::new().token_texts();
+        assert!(texts[1].is_empty(), "special tokens add no text");
+    }
 }
\ No newline at end of file
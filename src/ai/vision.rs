@@ -0,0 +1,129 @@
+//! Image attachments for multimodal models.
+//!
+//! Pasted images are downscaled and kept PNG-encoded (base64 inside the session JSON). For
+//! models whose signature takes pixel input they are resized to a square and normalized
+//! with the CLIP mean/std, which is what most ONNX vision encoders expect.
+
+use anyhow::{anyhow, Result};
+use base64::Engine;
+use image::{imageops::FilterType, RgbaImage};
+use ndarray::Array4;
+use serde::{Deserialize, Serialize};
+
+/// Longest side kept for an attached image; larger pastes are downscaled.
+pub const MAX_DIMENSION: u32 = 1024;
+/// Side of the square image fed to vision encoders whose input shape is dynamic.
+pub const DEFAULT_INPUT_SIZE: u32 = 224;
+
+const MEAN: [f32; 3] = [0.481_454_66, 0.457_827_5, 0.408_210_73];
+const STD: [f32; 3] = [0.268_629_54, 0.261_302_6, 0.275_777_1];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ImageAttachment {
+    pub id: String,
+    pub width: u32,
+    pub height: u32,
+    /// PNG data, base64 encoded.
+    pub png_base64: String,
+}
+
+impl ImageAttachment {
+    /// Build an attachment from raw RGBA pixels, downscaling to `MAX_DIMENSION`.
+    pub fn from_rgba(width: u32, height: u32, rgba: Vec<u8>) -> Result<Self> {
+        let image = RgbaImage::from_raw(width, height, rgba)
+            .ok_or_else(|| anyhow!("Image data doesn't match its {width}x{height} size"))?;
+        let image = fit_within(image, MAX_DIMENSION);
+        let mut png = Vec::new();
+        image::DynamicImage::ImageRgba8(image.clone())
+            .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)?;
+        Ok(Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            width: image.width(),
+            height: image.height(),
+            png_base64: base64::engine::general_purpose::STANDARD.encode(png),
+        })
+    }
+
+    pub fn decode(&self) -> Result<RgbaImage> {
+        let png = base64::engine::general_purpose::STANDARD.decode(&self.png_base64)?;
+        Ok(image::load_from_memory_with_format(&png, image::ImageFormat::Png)?.to_rgba8())
+    }
+
+    /// Decoded image scaled to fit in a `max` x `max` box, for previews.
+    pub fn thumbnail(&self, max: u32) -> Result<RgbaImage> {
+        Ok(fit_within(self.decode()?, max))
+    }
+
+    /// Size of the stored PNG.
+    pub fn encoded_len(&self) -> usize {
+        self.png_base64.len() / 4 * 3
+    }
+}
+
+fn fit_within(image: RgbaImage, max: u32) -> RgbaImage {
+    let (width, height) = image.dimensions();
+    if width <= max && height <= max {
+        return image;
+    }
+    let scale = max as f32 / width.max(height) as f32;
+    let new_width = ((width as f32 * scale).round() as u32).max(1);
+    let new_height = ((height as f32 * scale).round() as u32).max(1);
+    image::imageops::resize(&image, new_width, new_height, FilterType::Triangle)
+}
+
+/// `[1, 3, size, size]` normalized RGB tensor for a vision encoder. Transparent pixels are
+/// composited onto white.
+pub fn pixel_values(image: &RgbaImage, size: u32) -> Array4<f32> {
+    let resized = image::imageops::resize(image, size, size, FilterType::Triangle);
+    let side = size as usize;
+    let mut tensor = Array4::<f32>::zeros((1, 3, side, side));
+    for (x, y, pixel) in resized.enumerate_pixels() {
+        let alpha = pixel[3] as f32 / 255.0;
+        for channel in 0..3 {
+            let value = (pixel[channel] as f32 / 255.0) * alpha + (1.0 - alpha);
+            tensor[[0, channel, y as usize, x as usize]] = (value - MEAN[channel]) / STD[channel];
+        }
+    }
+    tensor
+}
+
+/// The image currently on the system clipboard, if there is one.
+pub fn clipboard_image() -> Result<Option<ImageAttachment>> {
+    let mut clipboard = arboard::Clipboard::new().map_err(|e| anyhow!("Clipboard unavailable: {e}"))?;
+    match clipboard.get_image() {
+        Ok(data) => {
+            let attachment = ImageAttachment::from_rgba(data.width as u32, data.height as u32, data.bytes.into_owned())?;
+            Ok(Some(attachment))
+        }
+        Err(arboard::Error::ContentNotAvailable) => Ok(None),
+        Err(e) => Err(anyhow!("Failed to read image from clipboard: {e}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_attachment_downscales_and_round_trips() {
+        let rgba = [200u8, 10, 10, 255].repeat(2048 * 512);
+        let attachment = ImageAttachment::from_rgba(2048, 512, rgba).unwrap();
+        assert_eq!((attachment.width, attachment.height), (MAX_DIMENSION, 256));
+
+        let decoded = attachment.decode().unwrap();
+        assert_eq!(decoded.dimensions(), (MAX_DIMENSION, 256));
+        assert_eq!(decoded.get_pixel(10, 10).0, [200, 10, 10, 255]);
+        assert_eq!(attachment.thumbnail(64).unwrap().dimensions(), (64, 16));
+        assert!(ImageAttachment::from_rgba(4, 4, vec![0; 10]).is_err());
+    }
+
+    #[test]
+    fn test_pixel_values_are_normalized() {
+        let image = RgbaImage::from_pixel(8, 8, image::Rgba([255, 255, 255, 0]));
+        let tensor = pixel_values(&image, 4);
+        assert_eq!(tensor.shape(), &[1, 3, 4, 4]);
+        // Transparent pixels become white
+        let expected = (1.0 - MEAN[0]) / STD[0];
+        assert!((tensor[[0, 0, 2, 3]] - expected).abs() < 1e-5);
+    }
+}
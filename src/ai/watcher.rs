@@ -0,0 +1,105 @@
+//! Filesystem watcher for the model directories.
+//!
+//! Wraps `notify` so the model list can react to `.onnx` files being added, removed or
+//! renamed instead of rescanning on a timer.
+
+use anyhow::Result;
+use notify::event::{EventKind, ModifyKind, RenameMode};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use std::path::{Path, PathBuf};
+use tokio::sync::mpsc;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ModelDirEvent {
+    /// A model file appeared or changed (e.g. a copy still in progress).
+    Added(PathBuf),
+    Removed(PathBuf),
+    /// The watcher lost track (overflow, backend error); do a full rescan.
+    Rescan,
+}
+
+/// Keeps the underlying OS watcher alive; dropping it stops the events.
+pub struct ModelDirWatcher {
+    _watcher: RecommendedWatcher,
+}
+
+fn is_model_file(path: &Path) -> bool {
+    path.extension().and_then(|e| e.to_str()) == Some("onnx")
+}
+
+/// Translate a raw notify event into model list changes.
+fn translate(event: notify::Event) -> Vec<ModelDirEvent> {
+    if event.need_rescan() {
+        return vec![ModelDirEvent::Rescan];
+    }
+    let models = |paths: &[PathBuf]| paths.iter().filter(|p| is_model_file(p)).cloned().collect::<Vec<_>>();
+    match event.kind {
+        EventKind::Create(_) | EventKind::Modify(ModifyKind::Data(_)) => {
+            models(&event.paths).into_iter().map(ModelDirEvent::Added).collect()
+        }
+        EventKind::Remove(_) | EventKind::Modify(ModifyKind::Name(RenameMode::From)) => {
+            models(&event.paths).into_iter().map(ModelDirEvent::Removed).collect()
+        }
+        EventKind::Modify(ModifyKind::Name(RenameMode::To)) => {
+            models(&event.paths).into_iter().map(ModelDirEvent::Added).collect()
+        }
+        EventKind::Modify(ModifyKind::Name(_)) => {
+            // Both ends of a rename (or an unknown rename): check what exists now
+            models(&event.paths)
+                .into_iter()
+                .map(|p| if p.exists() { ModelDirEvent::Added(p) } else { ModelDirEvent::Removed(p) })
+                .collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+impl ModelDirWatcher {
+    /// Watch `dirs` (non-recursively). Directories that don't exist are skipped; the watcher
+    /// only fails if the platform backend itself can't be created.
+    pub fn watch(dirs: &[PathBuf]) -> Result<(Self, mpsc::UnboundedReceiver<ModelDirEvent>)> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+            let events = match res {
+                Ok(event) => translate(event),
+                Err(e) => {
+                    tracing::warn!("Model directory watcher error: {}", e);
+                    vec![ModelDirEvent::Rescan]
+                }
+            };
+            for evt in events {
+                let _ = tx.send(evt);
+            }
+        })?;
+
+        for dir in dirs {
+            if !dir.is_dir() {
+                continue;
+            }
+            if let Err(e) = watcher.watch(dir, RecursiveMode::NonRecursive) {
+                tracing::warn!("Cannot watch model directory {}: {}", dir.display(), e);
+            }
+        }
+        Ok((Self { _watcher: watcher }, rx))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify::event::{CreateKind, RemoveKind};
+
+    #[test]
+    fn test_translate_filters_non_models_and_maps_renames() {
+        let created = notify::Event::new(EventKind::Create(CreateKind::File))
+            .add_path(PathBuf::from("/m/a.onnx"))
+            .add_path(PathBuf::from("/m/a.onnx.part"));
+        assert_eq!(translate(created), vec![ModelDirEvent::Added(PathBuf::from("/m/a.onnx"))]);
+
+        let removed = notify::Event::new(EventKind::Remove(RemoveKind::File)).add_path(PathBuf::from("/m/b.onnx"));
+        assert_eq!(translate(removed), vec![ModelDirEvent::Removed(PathBuf::from("/m/b.onnx"))]);
+
+        let renamed = notify::Event::new(EventKind::Modify(ModifyKind::Name(RenameMode::To))).add_path(PathBuf::from("/m/c.onnx"));
+        assert_eq!(translate(renamed), vec![ModelDirEvent::Added(PathBuf::from("/m/c.onnx"))]);
+    }
+}
@@ -0,0 +1,90 @@
+//! Dedicated threads for running providers.
+//!
+//! Decoding is CPU-bound and can take seconds per reply. Run on the tokio runtime it would
+//! occupy executor threads that downloads, model loads and the UI's background tasks need, so
+//! generations are handed to a small pool of named OS threads instead and their results come
+//! back over a oneshot channel.
+
+use anyhow::Result;
+use std::sync::{mpsc, Arc, Mutex};
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// A fixed pool of inference threads. Dropping the last handle lets the threads exit once the
+/// jobs already queued are done.
+#[derive(Clone)]
+pub struct InferenceWorkers {
+    tx: mpsc::Sender<Job>,
+}
+
+impl InferenceWorkers {
+    pub fn new(threads: usize) -> Self {
+        let threads = threads.max(1);
+        let (tx, rx) = mpsc::channel::<Job>();
+        let rx = Arc::new(Mutex::new(rx));
+        for i in 0..threads {
+            let rx = rx.clone();
+            std::thread::Builder::new()
+                .name(format!("ria-inference-{i}"))
+                .spawn(move || loop {
+                    // Hold the lock only while waiting, so other workers can pick up the next job
+                    let job = match rx.lock() {
+                        Ok(rx) => rx.recv(),
+                        Err(_) => return,
+                    };
+                    match job {
+                        Ok(job) => job(),
+                        Err(_) => return, // every handle dropped
+                    }
+                })
+                .expect("failed to spawn inference thread");
+        }
+        Self { tx }
+    }
+
+    /// Run `job` on a worker thread and wait for its result without blocking the executor.
+    /// The job runs inside the caller's runtime context, so it may spawn tasks (e.g. the
+    /// streaming side of a generation). A panicking job is reported as an error in builds
+    /// that unwind; the release profile aborts on panic instead.
+    pub async fn run<T, F>(&self, job: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> T + Send + 'static,
+    {
+        let runtime = tokio::runtime::Handle::current();
+        let (result_tx, result_rx) = tokio::sync::oneshot::channel();
+        let job: Job = Box::new(move || {
+            let _runtime = runtime.enter();
+            let result = crate::utils::crash::catch_unwind(job);
+            let _ = result_tx.send(result);
+        });
+        self.tx.send(job).map_err(|_| anyhow::anyhow!("Inference threads have stopped"))?;
+        match result_rx.await {
+            Ok(Ok(value)) => Ok(value),
+            Ok(Err(_)) => Err(anyhow::anyhow!("Inference thread panicked")),
+            Err(_) => Err(anyhow::anyhow!("Inference thread exited before finishing")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_jobs_run_on_named_worker_threads() {
+        let workers = InferenceWorkers::new(2);
+        let name = workers.run(|| std::thread::current().name().map(str::to_string)).await.unwrap();
+        assert!(name.unwrap().starts_with("ria-inference-"));
+        // Jobs can spawn onto the caller's runtime
+        let spawned = workers.run(|| tokio::spawn(async { 7 })).await.unwrap();
+        assert_eq!(spawned.await.unwrap(), 7);
+    }
+
+    #[tokio::test]
+    async fn test_panicking_job_is_an_error_and_pool_survives() {
+        let workers = InferenceWorkers::new(1);
+        assert!(workers.run(|| panic!("boom")).await.is_err());
+        assert_eq!(workers.run(|| 1 + 1).await.unwrap(), 2);
+    }
+}
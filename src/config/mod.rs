@@ -1,22 +1,54 @@
+pub mod personas;
+pub mod profiles;
+pub mod saver;
+pub mod workspaces;
+
+use crate::ai::code_routing::CodeRoutingSettings;
 use crate::ai::{ExecutionProvider, InferenceConfig};
+use crate::ai::power::PowerSettings;
+use crate::storage::retention::RetentionSettings;
+use crate::storage::StorageBackendKind;
+use crate::sync::SyncSettings;
 use crate::ui::app::Theme;
-use anyhow::Result;
+use crate::ui::fonts::FontSettings;
+use crate::ui::keybindings::KeyBindings;
+use crate::ui::quick_ask::QuickAskSettings;
+use crate::ui::snippets::{default_snippets, PromptSnippet};
+use crate::ui::streaming::StreamingSettings;
+use crate::ui::theme::Appearance;
+use crate::ui::translate;
+use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppConfig {
     pub theme: Theme,
+    /// Accent color, font size and message density.
+    #[serde(default)]
+    pub appearance: Appearance,
+    /// Custom font files and CJK/emoji fallbacks.
+    #[serde(default)]
+    pub fonts: FontSettings,
     pub ai_config: InferenceConfig,
     pub animation_quality: u32,
     pub enable_animations: bool,
     pub enable_sound: bool,
-    pub models_directory: PathBuf,
+    /// Directories scanned for `.onnx` models; the first one also receives downloads.
+    /// Older configs stored a single `models_directory`, which is migrated on load.
+    #[serde(
+        default = "default_model_directories",
+        alias = "models_directory",
+        deserialize_with = "deserialize_model_directories"
+    )]
+    pub model_directories: Vec<PathBuf>,
     pub chat_history_path: PathBuf,
     pub auto_save: bool,
     pub max_chat_history: usize,
     pub window_size: (f32, f32),
     pub window_position: Option<(f32, f32)>,
+    #[serde(default)]
+    pub window_maximized: bool,
     pub last_used_model: Option<String>,
     pub auto_load_last_model: bool,
     // New automation flags
@@ -27,33 +59,245 @@ pub struct AppConfig {
     #[serde(default)]
     pub auto_fix_onnx_runtime: bool,    // Attempt automatic ONNX runtime fix on version mismatch
     #[serde(default)]
+    pub managed_onnx_runtime: Option<String>, // Downloaded ONNX Runtime version to use instead of the system one
+    #[serde(default)]
     pub enable_ep_fallback: bool,       // Future: attempt alternate EPs on failure
+    #[serde(default)]
+    pub storage_backend: StorageBackendKind, // Where sessions / prompts / memory are persisted
+    #[serde(default)]
+    pub sync: SyncSettings,                  // Optional end-to-end encrypted sync
+    #[serde(default)]
+    pub active_profile: Option<String>,      // Name of the settings profile last applied
+    #[serde(default)]
+    pub tray: TraySettings,                  // System tray icon (needs the `tray` feature)
+    #[serde(default)]
+    pub quick_ask: QuickAskSettings,         // Global hotkey quick-ask popup
+    #[serde(default)]
+    pub keybindings: KeyBindings,            // In-app keyboard shortcuts (overrides of the defaults)
+    #[serde(default)]
+    pub debug_inspector: bool,               // Record each reply's prompt, token ids and sampler settings
+    #[serde(default)]
+    pub power: PowerSettings,                // Power mode and battery following for model loads
+    #[serde(default)]
+    pub error_analytics: bool,               // Count load, execution provider and download errors locally for Diagnostics
+    #[serde(default)]
+    pub network: NetworkSettings,            // Proxy, CA certificate and timeouts for downloads
+    #[serde(default)]
+    pub catalog: CatalogSettings,            // Signed remote model catalog instead of the bundled one
+    #[serde(default = "default_max_concurrent_generations")]
+    pub max_concurrent_generations: usize,   // Chats that may be generating a reply at the same time
+    #[serde(default)]
+    pub idle_unload_minutes: u32,            // Release the loaded model after this many idle minutes (0 = never)
+    #[serde(default = "default_snippets")]
+    pub snippets: Vec<PromptSnippet>,        // Quick Prompts menu, with {{variable}} placeholders
+    #[serde(default)]
+    pub streaming: StreamingSettings,        // Pace of streamed replies and the typing indicator
+    #[serde(default)]
+    pub retention: RetentionSettings,        // Age and size limits for chat history; `max_chat_history` caps the count
+    #[serde(default)]
+    pub code_routing: CodeRoutingSettings,   // Send prompts that are mostly code to a code model or prompt
+    #[serde(default = "translate::default_language")]
+    pub translation_language: String,        // Language last picked under a message's Translate menu
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TraySettings {
+    pub enabled: bool,
+    /// Closing the window minimizes it to the tray instead of quitting.
+    pub minimize_to_tray: bool,
+    /// Send a desktop notification when a long response or a download finishes while minimized.
+    pub notify_when_hidden: bool,
+}
+
+impl Default for TraySettings {
+    fn default() -> Self {
+        Self { enabled: false, minimize_to_tray: true, notify_when_hidden: true }
+    }
+}
+
+/// Proxy, certificates and timeouts for model and runtime downloads and sync.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NetworkSettings {
+    /// `http://`, `https://`, `socks5://` or `socks5h://` proxy URL, credentials included if
+    /// needed. Empty uses the `HTTPS_PROXY` / `HTTP_PROXY` / `ALL_PROXY` environment variables.
+    pub proxy: String,
+    /// Hosts that bypass the proxy, comma separated (`NO_PROXY` syntax).
+    pub no_proxy: String,
+    /// PEM file with extra root certificates, e.g. the CA of a TLS-inspecting corporate proxy.
+    pub ca_certificate: Option<PathBuf>,
+    pub connect_timeout_secs: u64,
+    /// A download is abandoned after this long without receiving data.
+    pub read_timeout_secs: u64,
+}
+
+impl Default for NetworkSettings {
+    fn default() -> Self {
+        Self { proxy: String::new(), no_proxy: String::new(), ca_certificate: None, connect_timeout_secs: 30, read_timeout_secs: 60 }
+    }
+}
+
+impl NetworkSettings {
+    /// Cheap checks for the settings window; `http_client` reports the same problems in full.
+    pub fn problem(&self) -> Option<String> {
+        let proxy = self.proxy.trim();
+        if !proxy.is_empty() && reqwest::Proxy::all(proxy).is_err() {
+            return Some(format!("'{proxy}' is not a valid proxy URL"));
+        }
+        match &self.ca_certificate {
+            Some(path) if !path.is_file() => Some(format!("CA certificate {} not found", path.display())),
+            _ => None,
+        }
+    }
+
+    /// An HTTP client with the proxy, extra root certificates and timeouts applied.
+    pub fn http_client(&self) -> Result<reqwest::Client> {
+        let mut builder = reqwest::Client::builder()
+            .connect_timeout(std::time::Duration::from_secs(self.connect_timeout_secs.max(1)))
+            .read_timeout(std::time::Duration::from_secs(self.read_timeout_secs.max(1)));
+        let proxy = self.proxy.trim();
+        if !proxy.is_empty() {
+            let proxy = reqwest::Proxy::all(proxy)
+                .with_context(|| format!("Invalid proxy URL '{proxy}'"))?
+                .no_proxy(reqwest::NoProxy::from_string(&self.no_proxy));
+            builder = builder.proxy(proxy);
+        }
+        if let Some(path) = &self.ca_certificate {
+            let pem = std::fs::read(path).with_context(|| format!("Failed to read CA certificate {}", path.display()))?;
+            let certificates = reqwest::Certificate::from_pem_bundle(&pem)
+                .with_context(|| format!("{} is not a PEM certificate file", path.display()))?;
+            for certificate in certificates {
+                builder = builder.add_root_certificate(certificate);
+            }
+        }
+        builder.build().context("Failed to set up the HTTP client")
+    }
+}
+
+/// Where the list of downloadable models comes from.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CatalogSettings {
+    /// Catalog JSON URL; its ed25519 signature is read from the same URL plus `.sig`.
+    /// Empty uses the catalog bundled with the app.
+    pub url: String,
+    /// The publisher's ed25519 public key (base64 or hex). Required with a remote catalog.
+    pub public_key: String,
+    pub refresh_hours: u32,
+}
+
+impl Default for CatalogSettings {
+    fn default() -> Self {
+        Self { url: String::new(), public_key: String::new(), refresh_hours: 24 }
+    }
+}
+
+impl CatalogSettings {
+    pub fn is_remote(&self) -> bool {
+        !self.url.trim().is_empty()
+    }
+
+    pub fn refresh_interval(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(u64::from(self.refresh_hours.max(1)) * 3600)
+    }
+}
+
+pub const MIN_WINDOW_SIZE: (f32, f32) = (800.0, 600.0);
+const MAX_WINDOW_EXTENT: f32 = 16_384.0;
+
+fn default_config_dir() -> PathBuf {
+    dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("ria-ai-chat")
+}
+
+fn default_model_directories() -> Vec<PathBuf> {
+    with_working_dir_models(vec![default_config_dir().join("models")])
+}
+
+/// Models used to be read from `./models` under the directory the app was started from.
+/// Configs from that time, and first runs, keep scanning it if it exists.
+fn with_working_dir_models(dirs: Vec<PathBuf>) -> Vec<PathBuf> {
+    match std::env::current_dir() {
+        Ok(cwd) => with_existing_dir(dirs, cwd.join("models")),
+        Err(_) => dirs,
+    }
+}
+
+fn with_existing_dir(mut dirs: Vec<PathBuf>, dir: PathBuf) -> Vec<PathBuf> {
+    if dir.is_dir() && !dirs.contains(&dir) {
+        dirs.push(dir);
+    }
+    dirs
+}
+
+fn default_max_concurrent_generations() -> usize {
+    2
+}
+
+/// Accepts either the current list form or the legacy single-path `models_directory`.
+fn deserialize_model_directories<'de, D>(deserializer: D) -> std::result::Result<Vec<PathBuf>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(PathBuf),
+        Many(Vec<PathBuf>),
+    }
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(dir) => with_working_dir_models(vec![dir]),
+        OneOrMany::Many(dirs) if dirs.is_empty() => default_model_directories(),
+        OneOrMany::Many(dirs) => dirs,
+    })
 }
 
 impl Default for AppConfig {
     fn default() -> Self {
-        let config_dir = dirs::config_dir()
-            .unwrap_or_else(|| PathBuf::from("."))
-            .join("ria-ai-chat");
+        let config_dir = default_config_dir();
 
         Self {
             theme: Theme::Dark,
+            appearance: Appearance::default(),
+            fonts: FontSettings::default(),
             ai_config: InferenceConfig::default(),
             animation_quality: 2, // High quality
             enable_animations: true,
             enable_sound: false,
-            models_directory: config_dir.join("models"),
+            model_directories: vec![config_dir.join("models")],
             chat_history_path: config_dir.join("chat_history.json"),
             auto_save: true,
             max_chat_history: 100,
             window_size: (1200.0, 800.0),
             window_position: None,
+            window_maximized: false,
             last_used_model: None,
             auto_load_last_model: true,
             auto_select_latest_model: true,
             auto_load_new_download: true,
             auto_fix_onnx_runtime: true,
+            managed_onnx_runtime: None,
             enable_ep_fallback: true,
+            storage_backend: StorageBackendKind::default(),
+            sync: SyncSettings::default(),
+            active_profile: None,
+            tray: TraySettings::default(),
+            quick_ask: QuickAskSettings::default(),
+            keybindings: KeyBindings::default(),
+            debug_inspector: false,
+            power: PowerSettings::default(),
+            error_analytics: false,
+            network: NetworkSettings::default(),
+            catalog: CatalogSettings::default(),
+            max_concurrent_generations: default_max_concurrent_generations(),
+            idle_unload_minutes: 0,
+            snippets: default_snippets(),
+            streaming: StreamingSettings::default(),
+            retention: RetentionSettings::default(),
+            code_routing: CodeRoutingSettings::default(),
+            translation_language: translate::default_language(),
         }
     }
 }
@@ -67,8 +311,12 @@ impl AppConfig {
             let config: AppConfig = serde_json::from_str(&content)?;
             Ok(config)
         } else {
-            // Create default config and save it
-            let config = Self::default();
+            // Create default config and save it, keeping the chats in the workspace's folder
+            let config = Self {
+                chat_history_path: config_path.with_file_name("chat_history.json"),
+                model_directories: default_model_directories(),
+                ..Self::default()
+            };
             config.save()?;
             Ok(config)
         }
@@ -76,27 +324,24 @@ impl AppConfig {
 
     pub fn save(&self) -> Result<()> {
         let config_path = Self::get_config_path()?;
-        
-        if let Some(parent) = config_path.parent() {
-            std::fs::create_dir_all(parent)?;
-        }
-
         let content = serde_json::to_string_pretty(self)?;
-        std::fs::write(&config_path, content)?;
+        crate::utils::files::write_atomic(&config_path, &content)?;
         
         tracing::info!("Configuration saved to {:?}", config_path);
         Ok(())
     }
 
+    /// The active workspace's `config.json`.
     fn get_config_path() -> Result<PathBuf> {
         let config_dir = dirs::config_dir()
-            .ok_or_else(|| anyhow::anyhow!("Could not find config directory"))?;
-        
-        Ok(config_dir.join("ria-ai-chat").join("config.json"))
+            .ok_or_else(|| anyhow::anyhow!("Could not find config directory"))?
+            .join("ria-ai-chat");
+        let workspace = workspaces::Workspaces::load_from(&config_dir);
+        Ok(workspaces::dir(&config_dir, workspace.active()).join("config.json"))
     }
 
     pub fn ensure_directories(&self) -> Result<()> {
-        std::fs::create_dir_all(&self.models_directory)?;
+        std::fs::create_dir_all(self.primary_models_directory())?;
         
         if let Some(parent) = self.chat_history_path.parent() {
             std::fs::create_dir_all(parent)?;
@@ -105,6 +350,61 @@ impl AppConfig {
         Ok(())
     }
 
+    /// Where downloads go: the first configured model directory.
+    pub fn primary_models_directory(&self) -> PathBuf {
+        self.model_directories
+            .first()
+            .cloned()
+            .unwrap_or_else(|| default_config_dir().join("models"))
+    }
+
+    /// Saved window size, clamped to the minimum the UI supports.
+    pub fn restored_window_size(&self) -> (f32, f32) {
+        let (w, h) = self.window_size;
+        let valid = |v: f32, min: f32| if v.is_finite() { v.clamp(min, MAX_WINDOW_EXTENT) } else { min };
+        (valid(w, MIN_WINDOW_SIZE.0), valid(h, MIN_WINDOW_SIZE.1))
+    }
+
+    /// Saved window position, unless it is clearly bogus (e.g. a minimized window on
+    /// Windows reports -32000).
+    pub fn restored_window_position(&self) -> Option<(f32, f32)> {
+        self.window_position
+            .filter(|&(x, y)| [x, y].into_iter().all(|v| (-10_000.0..MAX_WINDOW_EXTENT).contains(&v)))
+    }
+
+    /// Directory for the app's log files (e.g. the notification history).
+    pub fn log_dir() -> PathBuf {
+        default_config_dir().join("logs")
+    }
+
+    /// Directory caching the last verified remote model catalog.
+    pub fn catalog_dir() -> PathBuf {
+        default_config_dir().join("catalog")
+    }
+
+    /// Directory holding downloaded ONNX Runtime builds, one subdirectory per version.
+    pub fn runtime_dir() -> PathBuf {
+        default_config_dir().join("runtime")
+    }
+
+    /// Where the execution provider history of this machine is kept.
+    pub fn hardware_profile_path() -> PathBuf {
+        default_config_dir().join("hardware_profile.json")
+    }
+
+    /// Where the persona library is kept.
+    pub fn personas_path() -> PathBuf {
+        default_config_dir().join("personas.json")
+    }
+
+    /// Directory holding the storage backend's files (next to the chat history path).
+    pub fn storage_dir(&self) -> PathBuf {
+        self.chat_history_path
+            .parent()
+            .map(|p| p.to_path_buf())
+            .unwrap_or_else(|| PathBuf::from("."))
+    }
+
     pub fn get_available_execution_providers(&self) -> Vec<ExecutionProvider> {
         let mut providers = vec![ExecutionProvider::Cpu];
 
@@ -134,11 +434,11 @@ impl AppConfig {
         }
 
         // NPU providers (platform-specific)
-        if cfg!(all(target_os = "windows", target_arch = "aarch64")) {
+        if crate::ai::providers::OnnxProvider::ep_supported(&ExecutionProvider::QNN) {
             providers.push(ExecutionProvider::QNN);
         }
 
-        if cfg!(target_os = "android") {
+        if crate::ai::providers::OnnxProvider::ep_supported(&ExecutionProvider::NNAPI) {
             providers.push(ExecutionProvider::NNAPI);
         }
 
@@ -154,34 +454,89 @@ impl AppConfig {
     }
 
     fn is_openvino_available() -> bool {
-        // Check for Intel OpenVINO runtime
-        // This is simplified - in production you'd check for proper installation
-        std::env::var("INTEL_OPENVINO_DIR").is_ok()
+        !crate::utils::openvino::devices().is_empty()
     }
 
-    pub fn validate(&self) -> Result<()> {
+    /// `settings` with the state the app records as it runs (window geometry, the last model,
+    /// the managed runtime, whether the sync password is saved) kept from `self`, so reverting
+    /// the settings window doesn't undo it.
+    pub fn with_settings_of(&self, settings: &AppConfig) -> AppConfig {
+        let mut sync = settings.sync.clone();
+        sync.secret_saved = self.sync.secret_saved;
+        sync.secret = self.sync.secret.clone();
+        AppConfig {
+            sync,
+            window_size: self.window_size,
+            window_position: self.window_position,
+            window_maximized: self.window_maximized,
+            last_used_model: self.last_used_model.clone(),
+            managed_onnx_runtime: self.managed_onnx_runtime.clone(),
+            ..settings.clone()
+        }
+    }
+
+    /// Whether the user's settings differ from `other`'s, compared as they'd be saved.
+    pub fn settings_differ(&self, other: &AppConfig) -> bool {
+        serde_json::to_value(self).ok() != serde_json::to_value(self.with_settings_of(other)).ok()
+    }
+
+    /// Everything wrong with the settings, in the order the settings window shows them.
+    pub fn issues(&self) -> Vec<ConfigIssue> {
+        let mut issues = Vec::new();
+        let mut issue = |field, message: String| issues.push(ConfigIssue { field, message });
+
         // Validate AI config
         if self.ai_config.max_tokens == 0 {
-            return Err(anyhow::anyhow!("Max tokens must be greater than 0"));
+            issue(ConfigField::MaxTokens, "Max tokens must be greater than 0".into());
         }
 
         if !(0.0..=2.0).contains(&self.ai_config.temperature) {
-            return Err(anyhow::anyhow!("Temperature must be between 0.0 and 2.0"));
+            issue(ConfigField::Temperature, "Temperature must be between 0.0 and 2.0".into());
         }
 
         if !(0.0..=1.0).contains(&self.ai_config.top_p) {
-            return Err(anyhow::anyhow!("Top-p must be between 0.0 and 1.0"));
+            issue(ConfigField::TopP, "Top-p must be between 0.0 and 1.0".into());
         }
 
         // Validate paths
         if !self.ai_config.model_path.is_empty() {
             let model_path = PathBuf::from(&self.ai_config.model_path);
             if !model_path.exists() {
-                return Err(anyhow::anyhow!("Model path does not exist: {}", self.ai_config.model_path));
+                issue(ConfigField::ModelPath, format!("Model path does not exist: {}", self.ai_config.model_path));
             }
         }
 
-        Ok(())
+        issues
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        match self.issues().into_iter().next() {
+            Some(issue) => Err(anyhow::anyhow!(issue.message)),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A setting [`AppConfig::issues`] can complain about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigField {
+    MaxTokens,
+    Temperature,
+    TopP,
+    ModelPath,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigIssue {
+    pub field: ConfigField,
+    pub message: String,
+}
+
+impl ConfigIssue {
+    /// Whether the settings can't be saved until this is fixed. A missing model file only
+    /// warns, since it may be on a drive that isn't mounted right now.
+    pub fn blocks_save(&self) -> bool {
+        self.field != ConfigField::ModelPath
     }
 }
 
@@ -210,6 +565,35 @@ mod tests {
         assert!(config.validate().is_err());
     }
 
+    #[test]
+    fn test_issues_name_each_field_and_missing_model_does_not_block() {
+        let mut config = AppConfig::default();
+        config.ai_config.max_tokens = 0;
+        config.ai_config.temperature = 2.5;
+        config.ai_config.model_path = "/nonexistent/model.onnx".into();
+        let issues = config.issues();
+        let fields: Vec<_> = issues.iter().map(|i| i.field).collect();
+        assert_eq!(fields, [ConfigField::MaxTokens, ConfigField::Temperature, ConfigField::ModelPath]);
+        assert_eq!(issues.iter().filter(|i| i.blocks_save()).count(), 2);
+        assert_eq!(config.validate().unwrap_err().to_string(), "Max tokens must be greater than 0");
+    }
+
+    #[test]
+    fn test_revert_keeps_runtime_state() {
+        let saved = AppConfig::default();
+        let mut config = saved.clone();
+        config.window_size = (640.0, 480.0);
+        config.last_used_model = Some("phi.onnx".into());
+        assert!(!config.settings_differ(&saved));
+
+        config.ai_config.temperature = 1.3;
+        assert!(config.settings_differ(&saved));
+        config = config.with_settings_of(&saved);
+        assert_eq!(config.ai_config.temperature, saved.ai_config.temperature);
+        assert_eq!(config.last_used_model.as_deref(), Some("phi.onnx"));
+        assert!(!config.settings_differ(&saved));
+    }
+
     #[test]
     fn test_config_serialization() {
         let config = AppConfig::default();
@@ -219,4 +603,68 @@ mod tests {
         assert_eq!(config.animation_quality, deserialized.animation_quality);
         assert_eq!(config.enable_animations, deserialized.enable_animations);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_session_options_default_when_missing() {
+        let mut value = serde_json::to_value(AppConfig::default()).unwrap();
+        value["ai_config"].as_object_mut().unwrap().remove("session_options");
+        let config: AppConfig = serde_json::from_value(value).unwrap();
+        assert_eq!(config.ai_config.session_options, crate::ai::SessionOptions::default());
+        assert!(config.ai_config.session_options.effective_intra_threads() >= 1);
+    }
+
+    #[test]
+    fn test_legacy_models_directory_migrates_to_list() {
+        let mut value = serde_json::to_value(AppConfig::default()).unwrap();
+        let obj = value.as_object_mut().unwrap();
+        obj.remove("model_directories");
+        obj.insert("models_directory".into(), serde_json::json!("/data/models"));
+        let config: AppConfig = serde_json::from_value(value).unwrap();
+        assert_eq!(config.model_directories, with_working_dir_models(vec![PathBuf::from("/data/models")]));
+        assert_eq!(config.primary_models_directory(), PathBuf::from("/data/models"));
+
+        let cwd = tempfile::tempdir().unwrap();
+        let legacy = cwd.path().join("models");
+        assert_eq!(with_existing_dir(vec![PathBuf::from("/data/models")], legacy.clone()), vec![PathBuf::from("/data/models")]);
+        std::fs::create_dir(&legacy).unwrap();
+        let dirs = with_existing_dir(vec![PathBuf::from("/data/models")], legacy.clone());
+        assert_eq!(dirs, vec![PathBuf::from("/data/models"), legacy.clone()]);
+        assert_eq!(with_existing_dir(dirs.clone(), legacy), dirs);
+    }
+
+    #[test]
+    fn test_restored_window_geometry_is_sanitized() {
+        let config = AppConfig {
+            window_size: (300.0, f32::NAN),
+            window_position: Some((-32000.0, -32000.0)),
+            ..AppConfig::default()
+        };
+        assert_eq!(config.restored_window_size(), MIN_WINDOW_SIZE);
+        assert_eq!(config.restored_window_position(), None);
+
+        let config = AppConfig { window_position: Some((-1280.0, 40.0)), ..AppConfig::default() };
+        assert_eq!(config.restored_window_position(), Some((-1280.0, 40.0)));
+        assert_eq!(config.restored_window_size(), (1200.0, 800.0));
+    }
+
+    #[test]
+    fn test_network_settings_build_clients() {
+        let mut network = NetworkSettings::default();
+        assert!(network.problem().is_none());
+        assert!(network.http_client().is_ok());
+
+        network.proxy = "socks5://127.0.0.1:1080".into();
+        network.no_proxy = "localhost, .corp.example".into();
+        assert!(network.problem().is_none());
+        assert!(network.http_client().is_ok());
+
+        network.proxy = "not a url".into();
+        assert!(network.problem().is_some());
+        assert!(network.http_client().is_err());
+
+        network.proxy.clear();
+        network.ca_certificate = Some(PathBuf::from("/nonexistent/corp-ca.pem"));
+        assert!(network.problem().is_some());
+        assert!(network.http_client().is_err());
+    }
+}
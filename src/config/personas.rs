@@ -0,0 +1,149 @@
+//! Personas: named starting points for a conversation.
+//!
+//! A persona is a system prompt together with the sampling settings and model it works best
+//! with. New chats can start from one; the chat remembers the persona by name, puts its system
+//! prompt in front of every request and uses its sampling unless the chat header overrides it.
+//! The library is kept in `personas.json` beside the config.
+
+use crate::ai::{ChatMessage, GenerationOverrides};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Persona {
+    pub name: String,
+    pub system_prompt: String,
+    #[serde(default)]
+    pub temperature: Option<f32>,
+    #[serde(default)]
+    pub top_p: Option<f32>,
+    #[serde(default)]
+    pub max_tokens: Option<u32>,
+    /// Model file loaded when a chat is started with this persona.
+    #[serde(default)]
+    pub preferred_model: Option<String>,
+}
+
+impl Persona {
+    /// Sampling for chats with this persona, before the chat's own tweaks.
+    pub fn overrides(&self) -> GenerationOverrides {
+        GenerationOverrides {
+            max_tokens: self.max_tokens,
+            temperature: self.temperature,
+            top_p: self.top_p,
+            ..Default::default()
+        }
+    }
+
+    /// The system prompt as the first message of a request, if there is one.
+    pub fn system_message(&self) -> Option<ChatMessage> {
+        let prompt = self.system_prompt.trim();
+        (!prompt.is_empty()).then(|| ChatMessage::system(prompt))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PersonaLibrary {
+    #[serde(default)]
+    pub personas: Vec<Persona>,
+}
+
+impl Default for PersonaLibrary {
+    /// A couple of examples, so the feature shows up before the user has made any.
+    fn default() -> Self {
+        Self {
+            personas: vec![
+                Persona {
+                    name: "Concise assistant".into(),
+                    system_prompt: "Answer briefly and directly. Use lists for steps and skip pleasantries.".into(),
+                    temperature: Some(0.3),
+                    ..Default::default()
+                },
+                Persona {
+                    name: "Code reviewer".into(),
+                    system_prompt: "You review code. Point out bugs, unclear naming and missing error handling, most important first, and show the fix.".into(),
+                    temperature: Some(0.2),
+                    ..Default::default()
+                },
+            ],
+        }
+    }
+}
+
+impl PersonaLibrary {
+    /// The saved library, or the examples if there is none or it can't be read.
+    pub fn load(path: &Path) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).map_err(|e| tracing::warn!("Ignoring unreadable personas: {}", e)).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        crate::utils::files::write_atomic(path, &serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Persona> {
+        self.personas.iter().find(|p| p.name == name)
+    }
+
+    /// Add `persona`, or update the one saved as `previous_name` (its name may have changed).
+    pub fn upsert(&mut self, previous_name: Option<&str>, mut persona: Persona) -> Result<()> {
+        persona.name = persona.name.trim().to_string();
+        if persona.name.is_empty() {
+            return Err(anyhow!("Persona name cannot be empty"));
+        }
+        let existing = previous_name.and_then(|name| self.personas.iter().position(|p| p.name == name));
+        if self.personas.iter().enumerate().any(|(i, p)| p.name == persona.name && Some(i) != existing) {
+            return Err(anyhow!("A persona named '{}' already exists", persona.name));
+        }
+        match existing {
+            Some(i) => self.personas[i] = persona,
+            None => self.personas.push(persona),
+        }
+        Ok(())
+    }
+
+    pub fn remove(&mut self, name: &str) -> bool {
+        let before = self.personas.len();
+        self.personas.retain(|p| p.name != name);
+        self.personas.len() != before
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::MessageRole;
+
+    #[test]
+    fn test_upsert_renames_and_rejects_duplicates() {
+        let mut library = PersonaLibrary { personas: Vec::new() };
+        library.upsert(None, Persona { name: " Tutor ".into(), ..Default::default() }).unwrap();
+        assert!(library.get("Tutor").is_some());
+        assert!(library.upsert(None, Persona { name: "Tutor".into(), ..Default::default() }).is_err());
+        assert!(library.upsert(None, Persona { name: "  ".into(), ..Default::default() }).is_err());
+
+        library.upsert(Some("Tutor"), Persona { name: "Math tutor".into(), temperature: Some(0.1), ..Default::default() }).unwrap();
+        assert_eq!(library.personas.len(), 1);
+        assert_eq!(library.get("Math tutor").unwrap().overrides().temperature, Some(0.1));
+        assert!(library.remove("Math tutor"));
+        assert!(!library.remove("Math tutor"));
+    }
+
+    #[test]
+    fn test_library_round_trips_and_defaults_when_missing() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("personas.json");
+        assert_eq!(PersonaLibrary::load(&path), PersonaLibrary::default());
+
+        let mut library = PersonaLibrary { personas: Vec::new() };
+        library.upsert(None, Persona { name: "Pirate".into(), system_prompt: "Talk like a pirate.".into(), ..Default::default() }).unwrap();
+        library.save(&path).unwrap();
+        let loaded = PersonaLibrary::load(&path);
+        assert_eq!(loaded, library);
+        assert!(matches!(loaded.personas[0].system_message().unwrap().role, MessageRole::System));
+        assert!(Persona::default().system_message().is_none());
+    }
+}
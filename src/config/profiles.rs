@@ -0,0 +1,200 @@
+//! Named settings profiles and settings import/export.
+//!
+//! A profile is a full `AppConfig` snapshot (including `InferenceConfig`) stored as
+//! `<config dir>/ria-ai-chat/profiles/<name>.json`. Applying one keeps machine-local
+//! fields such as window geometry, model directories, font files, the
+//! downloaded ONNX Runtime and sync credentials; exported settings files leave them out.
+
+use super::AppConfig;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::path::{Path, PathBuf};
+
+/// Top-level settings that describe this machine rather than preferences.
+const MACHINE_LOCAL_FIELDS: [&str; 10] = [
+    "model_directories",
+    "fonts",
+    "chat_history_path",
+    "window_size",
+    "window_position",
+    "window_maximized",
+    "last_used_model",
+    "managed_onnx_runtime",
+    "sync",
+    "active_profile",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SettingsProfile {
+    pub name: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub config: AppConfig,
+}
+
+fn profiles_dir() -> Result<PathBuf> {
+    Ok(AppConfig::get_config_path()?
+        .parent()
+        .map(|p| p.join("profiles"))
+        .unwrap_or_else(|| PathBuf::from("profiles")))
+}
+
+/// File-system safe file name for a profile.
+fn profile_file_name(name: &str) -> Result<String> {
+    let trimmed = name.trim();
+    if trimmed.is_empty() {
+        return Err(anyhow!("Profile name cannot be empty"));
+    }
+    let safe: String = trimmed
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' || c == ' ' { c } else { '_' })
+        .collect();
+    Ok(format!("{safe}.json"))
+}
+
+impl AppConfig {
+    /// Replace everything except machine-local settings with `other`.
+    pub fn apply_profile(&mut self, other: &AppConfig) -> Result<()> {
+        self.overlay(other.shareable_fields()?)
+    }
+
+    /// Write the settings to `path` without machine-local fields and secrets, for moving them
+    /// to another machine.
+    pub fn export_to(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(&self.shareable_fields()?)?)?;
+        Ok(())
+    }
+
+    /// Read settings exported with `export_to`. Machine-local fields, in the file or not,
+    /// keep their current values.
+    pub fn import_from(&mut self, path: &Path) -> Result<()> {
+        let content = std::fs::read_to_string(path)?;
+        let fields = match serde_json::from_str(&content) {
+            Ok(Value::Object(fields)) if fields.contains_key("ai_config") => fields,
+            Ok(_) => return Err(anyhow!("Not a RIA settings file")),
+            Err(e) => return Err(anyhow!("Not a RIA settings file: {e}")),
+        };
+        let mut imported = self.clone();
+        imported.overlay(fields).map_err(|e| anyhow!("Not a RIA settings file: {e}"))?;
+        if imported.ai_config.max_tokens == 0 {
+            return Err(anyhow!("Imported settings are invalid: max tokens must be greater than 0"));
+        }
+        *self = imported;
+        Ok(())
+    }
+
+    fn shareable_fields(&self) -> Result<Map<String, Value>> {
+        let Value::Object(mut fields) = serde_json::to_value(self)? else {
+            return Err(anyhow!("Settings didn't serialize to an object"));
+        };
+        fields.retain(|key, _| !MACHINE_LOCAL_FIELDS.contains(&key.as_str()));
+        Ok(fields)
+    }
+
+    /// Take the shareable settings in `fields`, keeping machine-local ones.
+    fn overlay(&mut self, fields: Map<String, Value>) -> Result<()> {
+        let Value::Object(mut merged) = serde_json::to_value(&*self)? else {
+            return Err(anyhow!("Settings didn't serialize to an object"));
+        };
+        for (key, value) in fields {
+            if !MACHINE_LOCAL_FIELDS.contains(&key.as_str()) {
+                merged.insert(key, value);
+            }
+        }
+        let mut updated: AppConfig = serde_json::from_value(Value::Object(merged))?;
+        // `sync` is kept, but the passphrase in it is never serialized
+        updated.sync.passphrase = std::mem::take(&mut self.sync.passphrase);
+        *self = updated;
+        Ok(())
+    }
+}
+
+pub fn list_profiles() -> Vec<String> {
+    let Ok(dir) = profiles_dir() else { return Vec::new() };
+    let Ok(entries) = std::fs::read_dir(dir) else { return Vec::new() };
+    let mut names: Vec<String> = entries
+        .flatten()
+        .filter_map(|e| {
+            let data = std::fs::read_to_string(e.path()).ok()?;
+            serde_json::from_str::<SettingsProfile>(&data).ok().map(|p| p.name)
+        })
+        .collect();
+    names.sort_by_key(|n| n.to_lowercase());
+    names
+}
+
+pub fn save_profile(name: &str, config: &AppConfig) -> Result<PathBuf> {
+    let dir = profiles_dir()?;
+    std::fs::create_dir_all(&dir)?;
+    let path = dir.join(profile_file_name(name)?);
+    let mut config = config.clone();
+    config.sync.secret.clear();
+    config.active_profile = None;
+    let profile = SettingsProfile { name: name.trim().to_string(), created_at: chrono::Utc::now(), config };
+    std::fs::write(&path, serde_json::to_string_pretty(&profile)?)?;
+    Ok(path)
+}
+
+pub fn load_profile(name: &str) -> Result<SettingsProfile> {
+    let path = profiles_dir()?.join(profile_file_name(name)?);
+    let content = std::fs::read_to_string(&path).map_err(|_| anyhow!("Profile '{name}' not found"))?;
+    Ok(serde_json::from_str(&content)?)
+}
+
+pub fn delete_profile(name: &str) -> Result<()> {
+    let path = profiles_dir()?.join(profile_file_name(name)?);
+    std::fs::remove_file(path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_profile_keeps_machine_local_fields() {
+        let mut current = AppConfig {
+            window_size: (1600.0, 900.0),
+            model_directories: vec![PathBuf::from("/fast-ssd/models")],
+            ..AppConfig::default()
+        };
+        current.sync.secret = "s3cr3t".into();
+
+        let mut profile = AppConfig { enable_animations: false, ..AppConfig::default() };
+        profile.ai_config.max_tokens = 256;
+        profile.ai_config.prefer_npu = true;
+
+        current.apply_profile(&profile).unwrap();
+        assert_eq!(current.ai_config.max_tokens, 256);
+        assert!(current.ai_config.prefer_npu);
+        assert!(!current.enable_animations);
+        assert_eq!(current.window_size, (1600.0, 900.0));
+        assert_eq!(current.sync.secret, "s3cr3t");
+        assert_eq!(current.model_directories, vec![PathBuf::from("/fast-ssd/models")]);
+    }
+
+    #[test]
+    fn test_export_leaves_out_machine_local_fields_and_import_keeps_them() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("settings.json");
+        let mut config = AppConfig { window_size: (1600.0, 900.0), managed_onnx_runtime: Some("1.22.0".into()), ..AppConfig::default() };
+        config.sync.secret = "s3cr3t".into();
+        config.ai_config.temperature = 0.2;
+        config.export_to(&path).unwrap();
+        let exported = std::fs::read_to_string(&path).unwrap();
+        assert!(!exported.contains("s3cr3t") && !exported.contains("1.22.0"));
+        assert!(!exported.contains("model_directories") && !exported.contains("window_size"));
+
+        let mut other = AppConfig { model_directories: vec![PathBuf::from("/fast-ssd/models")], ..AppConfig::default() };
+        other.sync.secret = "mine".into();
+        other.import_from(&path).unwrap();
+        assert_eq!(other.ai_config.temperature, 0.2);
+        assert_eq!(other.sync.secret, "mine");
+        assert_eq!(other.model_directories, vec![PathBuf::from("/fast-ssd/models")]);
+        assert_eq!(other.window_size, AppConfig::default().window_size);
+        assert_eq!(other.managed_onnx_runtime, None);
+
+        std::fs::write(&path, "[1, 2]").unwrap();
+        assert!(other.import_from(&path).is_err());
+    }
+}
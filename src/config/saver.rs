@@ -0,0 +1,126 @@
+//! Debounced config saving.
+//!
+//! UI actions ask for the config to be saved as they happen; the file is written on a
+//! background task once the requests have been quiet for a moment, so a burst of changes
+//! costs one write. Requests that don't change anything since the last one are dropped, and
+//! every write goes to a temporary file that replaces `config.json` only once complete.
+
+use super::AppConfig;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Notify;
+
+/// How long requests must stop before the file is written.
+pub const SAVE_DEBOUNCE: Duration = Duration::from_millis(500);
+
+#[derive(Default)]
+struct Shared {
+    /// Serialized config waiting to be written.
+    pending: Mutex<Option<String>>,
+    /// Held while writing, so a flush and the background task don't interleave.
+    writing: Mutex<()>,
+    changed: Notify,
+}
+
+impl Shared {
+    fn write_pending(&self, path: &std::path::Path) {
+        let _writing = self.writing.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(json) = self.pending.lock().unwrap_or_else(|e| e.into_inner()).take() else { return };
+        match crate::utils::files::write_atomic(path, &json) {
+            Ok(()) => tracing::debug!("Configuration saved to {:?}", path),
+            Err(e) => tracing::error!("Failed to save config: {}", e),
+        }
+    }
+}
+
+pub struct ConfigSaver {
+    shared: Arc<Shared>,
+    path: PathBuf,
+    /// What was last queued, to skip saves that change nothing.
+    last_queued: Option<String>,
+}
+
+impl ConfigSaver {
+    /// Start the saver for the config file. Must be called inside the tokio runtime.
+    pub fn spawn() -> anyhow::Result<Self> {
+        Ok(Self::spawn_at(AppConfig::get_config_path()?, SAVE_DEBOUNCE))
+    }
+
+    pub fn spawn_at(path: PathBuf, debounce: Duration) -> Self {
+        let shared = Arc::new(Shared::default());
+        let task_shared = shared.clone();
+        let task_path = path.clone();
+        tokio::spawn(async move {
+            loop {
+                task_shared.changed.notified().await;
+                // Wait for the requests to settle
+                while tokio::time::timeout(debounce, task_shared.changed.notified()).await.is_ok() {}
+                let (shared, path) = (task_shared.clone(), task_path.clone());
+                let _ = tokio::task::spawn_blocking(move || shared.write_pending(&path)).await;
+            }
+        });
+        Self { shared, path, last_queued: None }
+    }
+
+    /// Queue `config` to be written once saves stop coming in.
+    pub fn save(&mut self, config: &AppConfig) {
+        let json = match serde_json::to_string_pretty(config) {
+            Ok(json) => json,
+            Err(e) => return tracing::error!("Failed to serialize config: {}", e),
+        };
+        if self.last_queued.as_ref() == Some(&json) {
+            return;
+        }
+        self.last_queued = Some(json.clone());
+        *self.shared.pending.lock().unwrap_or_else(|e| e.into_inner()) = Some(json);
+        self.shared.changed.notify_one();
+    }
+
+    /// Whether a save is waiting for the debounce to pass.
+    pub fn is_pending(&self) -> bool {
+        self.shared.pending.lock().map(|p| p.is_some()).unwrap_or(false)
+    }
+
+    /// Write a queued save now, e.g. before the app exits.
+    pub fn flush(&self) {
+        self.shared.write_pending(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_saves_are_debounced_and_deduplicated() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.json");
+        let mut saver = ConfigSaver::spawn_at(path.clone(), Duration::from_millis(50));
+
+        let mut config = AppConfig::default();
+        saver.save(&config);
+        config.max_concurrent_generations = 5;
+        saver.save(&config);
+        assert!(!path.exists());
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        let saved: AppConfig = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(saved.max_concurrent_generations, 5);
+
+        // Nothing changed, nothing queued
+        saver.save(&config);
+        assert!(!saver.is_pending());
+    }
+
+    #[tokio::test]
+    async fn test_flush_writes_immediately() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.json");
+        let mut saver = ConfigSaver::spawn_at(path.clone(), Duration::from_secs(60));
+        saver.save(&AppConfig::default());
+        saver.flush();
+        assert!(path.exists());
+        assert!(!saver.is_pending());
+        assert!(!dir.path().join("config.json.tmp").exists());
+    }
+}
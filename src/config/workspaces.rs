@@ -0,0 +1,141 @@
+//! Named workspaces, each with its own settings, chat store and default model.
+//!
+//! The default workspace is the plain `<config dir>/ria-ai-chat` layout; every other one lives
+//! in `workspaces/<name>/` below it with its own `config.json`, whose `chat_history_path`
+//! points into the same folder. `workspaces.json` lists them and says which one opens at
+//! startup; switching restarts the app into the chosen workspace.
+
+use super::AppConfig;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+pub const DEFAULT_WORKSPACE: &str = "default";
+
+const REGISTRY_FILE: &str = "workspaces.json";
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Workspaces {
+    /// Workspace opened at startup; `None` is the default one.
+    pub active: Option<String>,
+    /// Workspaces besides the default one, in the order they were created.
+    pub names: Vec<String>,
+}
+
+/// Folder holding the workspace list and the default workspace.
+pub fn root() -> PathBuf {
+    super::default_config_dir()
+}
+
+impl Workspaces {
+    pub fn load() -> Self {
+        Self::load_from(&root())
+    }
+
+    /// The registry in `root`; empty if there is none or it can't be read.
+    pub fn load_from(root: &Path) -> Self {
+        let path = root.join(REGISTRY_FILE);
+        let Ok(content) = std::fs::read_to_string(&path) else { return Self::default() };
+        serde_json::from_str(&content).unwrap_or_else(|e| {
+            tracing::warn!("Ignoring unreadable workspace list {:?}: {}", path, e);
+            Self::default()
+        })
+    }
+
+    pub fn save(&self) -> Result<()> {
+        self.save_to(&root())
+    }
+
+    pub fn save_to(&self, root: &Path) -> Result<()> {
+        crate::utils::files::write_atomic(root.join(REGISTRY_FILE), &serde_json::to_string_pretty(self)?)
+    }
+
+    /// The workspace to open; the default one if the saved choice no longer exists.
+    pub fn active(&self) -> &str {
+        self.active
+            .as_deref()
+            .filter(|name| self.names.iter().any(|n| n == name))
+            .unwrap_or(DEFAULT_WORKSPACE)
+    }
+
+    pub fn set_active(&mut self, name: &str) {
+        self.active = (name != DEFAULT_WORKSPACE).then(|| name.to_string());
+    }
+
+    /// Every workspace, the default one first.
+    pub fn all(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(DEFAULT_WORKSPACE).chain(self.names.iter().map(String::as_str))
+    }
+
+    /// Add a workspace under `root` whose settings start as a copy of `template`, with an empty
+    /// chat history and sync turned off. Returns the name as stored.
+    pub fn create(&mut self, root: &Path, name: &str, template: &AppConfig) -> Result<String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(anyhow!("Workspace name cannot be empty"));
+        }
+        if self.all().any(|n| n.eq_ignore_ascii_case(name) || dir_name(n) == dir_name(name)) {
+            return Err(anyhow!("A workspace named '{name}' already exists"));
+        }
+        let dir = dir(root, name);
+        let config = AppConfig {
+            chat_history_path: dir.join("chat_history.json"),
+            sync: Default::default(),
+            active_profile: None,
+            ..template.clone()
+        };
+        crate::utils::files::write_atomic(dir.join("config.json"), &serde_json::to_string_pretty(&config)?)?;
+        self.names.push(name.to_string());
+        Ok(name.to_string())
+    }
+}
+
+/// Folder holding a workspace's settings and chats.
+pub fn dir(root: &Path, name: &str) -> PathBuf {
+    if name == DEFAULT_WORKSPACE {
+        root.to_path_buf()
+    } else {
+        root.join("workspaces").join(dir_name(name))
+    }
+}
+
+/// File-system safe folder name for a workspace.
+fn dir_name(name: &str) -> String {
+    name.trim()
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' || c == ' ' { c.to_ascii_lowercase() } else { '_' })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_workspaces_get_their_own_folder_and_history() {
+        let root = tempfile::tempdir().unwrap();
+        let mut workspaces = Workspaces::load_from(root.path());
+        assert_eq!(workspaces.active(), DEFAULT_WORKSPACE);
+        assert_eq!(dir(root.path(), DEFAULT_WORKSPACE), root.path());
+
+        let template = AppConfig { last_used_model: Some("phi.onnx".into()), ..AppConfig::default() };
+        assert_eq!(workspaces.create(root.path(), " Work ", &template).unwrap(), "Work");
+        assert!(workspaces.create(root.path(), "work", &template).is_err());
+        assert!(workspaces.create(root.path(), "default", &template).is_err());
+        assert!(workspaces.create(root.path(), "  ", &template).is_err());
+
+        let work_dir = dir(root.path(), "Work");
+        let config: AppConfig = serde_json::from_str(&std::fs::read_to_string(work_dir.join("config.json")).unwrap()).unwrap();
+        assert_eq!(config.storage_dir(), work_dir);
+        assert_eq!(config.last_used_model.as_deref(), Some("phi.onnx"));
+
+        workspaces.set_active("Work");
+        workspaces.save_to(root.path()).unwrap();
+        let mut reloaded = Workspaces::load_from(root.path());
+        assert_eq!(reloaded.active(), "Work");
+        assert_eq!(reloaded.all().collect::<Vec<_>>(), vec![DEFAULT_WORKSPACE, "Work"]);
+        reloaded.names.clear();
+        assert_eq!(reloaded.active(), DEFAULT_WORKSPACE);
+    }
+}
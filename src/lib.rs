@@ -1,4 +1,46 @@
+//! RIA's local-inference stack, usable without the chat window.
+//!
+//! The app is built on this library; other programs can use the same pieces:
+//!
+//! - [`ai`]: the [`InferenceEngine`](ai::InferenceEngine) that routes requests to providers
+//!   with failover, the ONNX Runtime provider in [`ai::providers`] with execution provider
+//!   fallback (CPU, CUDA, DirectML, OpenVINO, QNN), the [`tokenizer`](ai::tokenizer) and the
+//!   [`sampler`](ai::sampler).
+//! - [`models`]: finding, downloading and verifying ONNX models with the
+//!   [`ModelManager`](models::ModelManager).
+//!
+//! The remaining modules (`config`, `storage`, `sync`, `ui`, `utils`) make up the app and
+//! are public for it; they aren't meant to be stable.
+//!
+//! ```no_run
+//! use ria::ai::providers::OnnxProvider;
+//! use ria::ai::{ChatMessage, InferenceConfig, InferenceEngine};
+//! use ria::models::ModelManager;
+//!
+//! # async fn run() -> anyhow::Result<()> {
+//! let mut models = ModelManager::new("models")?;
+//! models.scan_models()?;
+//! let model = models.get_available_models().first().expect("no models in ./models");
+//!
+//! let config = InferenceConfig { model_path: model.path.to_string_lossy().into(), ..Default::default() };
+//! let mut provider = OnnxProvider::new(config)?;
+//! provider.load_model()?;
+//!
+//! let mut engine = InferenceEngine::new();
+//! let id = engine.add_provider_sync(Box::new(provider));
+//! engine.set_active_provider_sync(id)?;
+//! let question = ChatMessage::user("What is an NPU?");
+//! let reply = engine.generate_response(&[question]).await?;
+//! println!("{}", reply.content);
+//! # Ok(())
+//! # }
+//! ```
+
 pub mod ai;
 pub mod config;
+pub mod storage;
+pub mod sync;
 pub mod ui;
 pub mod utils;
+
+pub use ai::models;
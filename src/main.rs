@@ -1,20 +1,31 @@
-mod ai;
-mod config;
-mod ui;
-mod utils;
+use ria::{ai, config, ui, utils};
 
 use eframe::egui;
 use tracing_subscriber;
 
 #[tokio::main]
 async fn main() -> Result<(), eframe::Error> {
-    // Initialize logging
-    tracing_subscriber::fmt::init();
+    // Initialize logging; the most recent lines are kept for diagnostics reports
+    tracing_subscriber::fmt().with_writer(utils::log_tail::LogTail).init();
+
+    // Restore the window where it was left; RiaApp loads the rest of the config itself
+    let config = config::AppConfig::load().unwrap_or_default();
+    // Crash reports and unsent chat state go to the data directory
+    utils::crash::install(config.storage_dir());
+    // Before anything can open ONNX Runtime: use the downloaded runtime if one was chosen
+    ai::runtime::use_managed_runtime(&config::AppConfig::runtime_dir(), config.managed_onnx_runtime.as_deref());
+    ai::cuda::use_pci_bus_order();
+    let mut viewport = egui::ViewportBuilder::default()
+        .with_inner_size(config.restored_window_size())
+        .with_min_inner_size(config::MIN_WINDOW_SIZE)
+        .with_maximized(config.window_maximized)
+        .with_drag_and_drop(true); // model import by dropping files
+    if let Some(position) = config.restored_window_position() {
+        viewport = viewport.with_position(position);
+    }
 
     let options = eframe::NativeOptions {
-        viewport: egui::ViewportBuilder::default()
-            .with_inner_size([1200.0, 800.0])
-            .with_min_inner_size([800.0, 600.0]),
+        viewport,
         ..Default::default()
     };
 
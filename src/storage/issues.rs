@@ -0,0 +1,254 @@
+//! Local error analytics: model load, execution provider and download failures counted by
+//! cause in a small SQLite database next to the chat storage, for the diagnostics panel's
+//! "most common issues on this machine". Opt-in, and nothing here is ever sent anywhere.
+//!
+//! Errors are sorted into [`Issue`]s by what their messages say, first match wins, so a
+//! cause is recognised however the runtime or server phrased it.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection};
+use std::path::Path;
+
+/// Where an error came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IssueSource {
+    ModelLoad,
+    ExecutionProvider,
+    Download,
+}
+
+impl IssueSource {
+    fn key(self) -> &'static str {
+        match self {
+            Self::ModelLoad => "load",
+            Self::ExecutionProvider => "ep",
+            Self::Download => "download",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::ModelLoad => "Model load",
+            Self::ExecutionProvider => "Execution provider",
+            Self::Download => "Download",
+        }
+    }
+
+    fn from_key(key: &str) -> Option<Self> {
+        [Self::ModelLoad, Self::ExecutionProvider, Self::Download].into_iter().find(|s| s.key() == key)
+    }
+}
+
+/// A cause of errors, with what usually fixes it.
+#[derive(Debug, PartialEq)]
+pub struct Issue {
+    /// Stored with each error; never changes once released.
+    pub key: &'static str,
+    pub title: &'static str,
+    pub fix: &'static str,
+    /// Lowercase phrases in the error message that point to this cause.
+    markers: &'static [&'static str],
+}
+
+const ISSUES: [Issue; 10] = [
+    Issue {
+        key: "checksum",
+        title: "Corrupt or incomplete download",
+        fix: "Download the model again; if it keeps failing, pick another mirror or check for a proxy that alters files.",
+        markers: &["sha256 mismatch", "integrity", "checksum", "but the server announced"],
+    },
+    Issue {
+        key: "gated",
+        title: "Gated or private Hugging Face repository",
+        fix: "Accept the model's terms on its Hugging Face page and add an access token in Settings → Network.",
+        markers: &["gated or private", "401 unauthorized", "403 forbidden"],
+    },
+    Issue {
+        key: "runtime_version",
+        title: "ONNX Runtime version mismatch",
+        fix: "Install ONNX Runtime 1.22 or newer, or use Auto Fix when the load error offers it.",
+        markers: &["version incompatib", "api version", "version mismatch", "requires onnx runtime"],
+    },
+    Issue {
+        key: "out_of_memory",
+        title: "Not enough memory",
+        fix: "Use a smaller or quantized (INT8/INT4) model, close other apps, or load on the CPU instead of the GPU.",
+        markers: &["out of memory", "failed to allocate", "cannot allocate", "bad_alloc"],
+    },
+    Issue {
+        key: "gpu_setup",
+        title: "GPU runtime not set up",
+        fix: "Install the CUDA, cuDNN or DirectML version the runtime expects and update the GPU driver, or turn on execution provider fallback.",
+        markers: &["cuda", "cudnn", "driver", "directml", "tensorrt", "openvino", "qnn"],
+    },
+    Issue {
+        key: "unsupported_model",
+        title: "Model not supported by this runtime",
+        fix: "Use an ONNX export with a supported opset (for example from the catalog), or update ONNX Runtime.",
+        markers: &["opset", "unsupported", "not implemented", "invalid model", "protobuf", "not an onnx"],
+    },
+    Issue {
+        key: "disk_full",
+        title: "Disk full",
+        fix: "Free space in the models directory or add a directory on another drive in Settings → Inference.",
+        markers: &["no space", "disk full", "quota"],
+    },
+    Issue {
+        key: "permission",
+        title: "Permission denied",
+        fix: "Check that the models directory is writable and the model file readable by your user.",
+        markers: &["permission denied", "access is denied", "read-only"],
+    },
+    Issue {
+        key: "missing_file",
+        title: "File not found",
+        fix: "Check that the model (and its tokenizer) is still where it was, or that the download URL is current.",
+        markers: &["no such file", "not found", "404", "missing", "empty model path"],
+    },
+    Issue {
+        key: "network",
+        title: "Network problems",
+        fix: "Check the connection and the proxy and timeouts in Settings → Network; downloads resume where they stopped.",
+        markers: &["timed out", "timeout", "connection", "dns", "stopped after", "tls", "certificate", "error sending request", "server error", "too many requests"],
+    },
+];
+
+/// Errors that match no known cause.
+const OTHER: Issue = Issue {
+    key: "other",
+    title: "Other errors",
+    fix: "Export diagnostics from this panel and attach the report to a bug report.",
+    markers: &[],
+};
+
+/// The cause `message` most likely has.
+pub fn categorize(message: &str) -> &'static Issue {
+    let message = message.to_lowercase();
+    ISSUES
+        .iter()
+        .find(|issue| issue.markers.iter().any(|m| message.contains(m)))
+        .unwrap_or(&OTHER)
+}
+
+fn issue_by_key(key: &str) -> &'static Issue {
+    ISSUES.iter().find(|i| i.key == key).unwrap_or(&OTHER)
+}
+
+/// How often an issue came up.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IssueCount {
+    pub issue: &'static Issue,
+    pub count: u64,
+    /// Sources of the errors, most frequent first.
+    pub sources: Vec<IssueSource>,
+    pub last_seen: DateTime<Utc>,
+    pub last_message: String,
+}
+
+pub struct IssueLog {
+    conn: Connection,
+}
+
+impl IssueLog {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Self::init(Connection::open(path)?)
+    }
+
+    #[cfg(test)]
+    pub fn open_in_memory() -> Result<Self> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(conn: Connection) -> Result<Self> {
+        conn.execute_batch(
+            "PRAGMA journal_mode = WAL;
+             CREATE TABLE IF NOT EXISTS issues (
+                 timestamp TEXT NOT NULL,
+                 source TEXT NOT NULL,
+                 issue TEXT NOT NULL,
+                 message TEXT NOT NULL
+             );
+             CREATE INDEX IF NOT EXISTS issues_issue ON issues (issue);",
+        )?;
+        Ok(Self { conn })
+    }
+
+    /// Count an error from `source`; returns the cause it was put down to.
+    pub fn record(&mut self, at: DateTime<Utc>, source: IssueSource, message: &str) -> Result<&'static Issue> {
+        let issue = categorize(message);
+        self.conn.execute(
+            "INSERT INTO issues (timestamp, source, issue, message) VALUES (?1, ?2, ?3, ?4)",
+            params![at.to_rfc3339(), source.key(), issue.key, message],
+        )?;
+        Ok(issue)
+    }
+
+    /// The `limit` most frequent causes, most frequent first.
+    pub fn most_common(&self, limit: usize) -> Result<Vec<IssueCount>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT issue, COUNT(*), MAX(timestamp),
+                    (SELECT message FROM issues AS last WHERE last.issue = issues.issue ORDER BY timestamp DESC LIMIT 1)
+             FROM issues GROUP BY issue ORDER BY COUNT(*) DESC, MAX(timestamp) DESC LIMIT ?1",
+        )?;
+        let rows = stmt.query_map(params![limit as i64], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?, row.get::<_, String>(2)?, row.get::<_, String>(3)?))
+        })?;
+        let mut counts = Vec::new();
+        for row in rows {
+            let (key, count, last_seen, last_message) = row?;
+            let mut sources = self.conn.prepare(
+                "SELECT source FROM issues WHERE issue = ?1 GROUP BY source ORDER BY COUNT(*) DESC",
+            )?;
+            let sources = sources
+                .query_map(params![key], |row| row.get::<_, String>(0))?
+                .filter_map(|s| s.ok().and_then(|s| IssueSource::from_key(&s)))
+                .collect();
+            counts.push(IssueCount {
+                issue: issue_by_key(&key),
+                count: count as u64,
+                sources,
+                last_seen: DateTime::parse_from_rfc3339(&last_seen).map(|t| t.with_timezone(&Utc)).unwrap_or_default(),
+                last_message,
+            });
+        }
+        Ok(counts)
+    }
+
+    pub fn clear(&mut self) -> Result<()> {
+        self.conn.execute("DELETE FROM issues", [])?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_errors_are_counted_by_cause() {
+        assert_eq!(categorize("SHA256 mismatch for phi3.onnx: expected ab, got cd").key, "checksum");
+        assert_eq!(categorize("CUDA error: out of memory").key, "out_of_memory");
+        assert_eq!(categorize("Failed to download model: HTTP status server error (503 Service Unavailable)").key, "network");
+        assert_eq!(categorize("Model uses opset 21, which is unsupported").key, "unsupported_model");
+        assert_eq!(categorize("Something odd happened").key, "other");
+
+        let mut log = IssueLog::open_in_memory().unwrap();
+        let now = Utc::now();
+        let earlier = now - chrono::Duration::minutes(5);
+        log.record(earlier, IssueSource::Download, "operation timed out").unwrap();
+        log.record(earlier, IssueSource::ExecutionProvider, "cuDNN 9 not found in PATH").unwrap();
+        log.record(earlier, IssueSource::ModelLoad, "CUDA driver version is insufficient").unwrap();
+        log.record(now, IssueSource::ExecutionProvider, "libcudart.so.12: cannot open (CUDA)").unwrap();
+
+        let common = log.most_common(5).unwrap();
+        assert_eq!(common.len(), 2);
+        assert_eq!((common[0].issue.key, common[0].count), ("gpu_setup", 3));
+        assert_eq!(common[0].sources, [IssueSource::ExecutionProvider, IssueSource::ModelLoad]);
+        assert_eq!(common[0].last_message, "libcudart.so.12: cannot open (CUDA)");
+        assert_eq!((common[1].issue.title, common[1].count), ("Network problems", 1));
+
+        log.clear().unwrap();
+        assert!(log.most_common(5).unwrap().is_empty());
+    }
+}
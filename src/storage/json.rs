@@ -0,0 +1,122 @@
+use super::{SavedPrompt, StorageBackend, Tombstones};
+use crate::ai::ChatSession;
+use anyhow::Result;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Portable backend: one JSON file per session plus `prompts.json`, `memory.json` and
+/// `tombstones.json`.
+pub struct JsonFileStorage {
+    root: PathBuf,
+}
+
+impl JsonFileStorage {
+    pub fn open(root: impl Into<PathBuf>) -> Result<Self> {
+        let root = root.into();
+        std::fs::create_dir_all(root.join("sessions"))?;
+        Ok(Self { root })
+    }
+
+    fn session_path(&self, id: &str) -> PathBuf {
+        self.root
+            .join("sessions")
+            .join(format!("{}.json", crate::utils::sanitize_filename(id)))
+    }
+
+    fn read_json<T: serde::de::DeserializeOwned + Default>(path: &Path) -> Result<T> {
+        if !path.exists() {
+            return Ok(T::default());
+        }
+        let data = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&data)?)
+    }
+
+    /// Write to a sibling temp file and rename so a crash never leaves a truncated file.
+    fn write_json<T: serde::Serialize>(path: &Path, value: &T) -> Result<()> {
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_string_pretty(value)?)?;
+        std::fs::rename(&tmp, path)?;
+        Ok(())
+    }
+}
+
+impl StorageBackend for JsonFileStorage {
+    fn name(&self) -> &str {
+        "JSON files"
+    }
+
+    fn load_sessions(&self) -> Result<Vec<ChatSession>> {
+        let mut sessions = Vec::new();
+        for entry in std::fs::read_dir(self.root.join("sessions"))? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|e| e == "json") {
+                match std::fs::read_to_string(&path).map_err(anyhow::Error::from)
+                    .and_then(|d| serde_json::from_str::<ChatSession>(&d).map_err(Into::into))
+                {
+                    Ok(session) => sessions.push(session),
+                    Err(e) => tracing::warn!("Skipping unreadable session file {:?}: {}", path, e),
+                }
+            }
+        }
+        sessions.sort_by_key(|s| s.created_at);
+        Ok(sessions)
+    }
+
+    fn save_session(&mut self, session: &ChatSession) -> Result<()> {
+        Self::write_json(&self.session_path(&session.id), session)
+    }
+
+    fn delete_session(&mut self, id: &str) -> Result<()> {
+        let path = self.session_path(id);
+        if path.exists() {
+            std::fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+
+    fn load_prompts(&self) -> Result<Vec<SavedPrompt>> {
+        Self::read_json(&self.root.join("prompts.json"))
+    }
+
+    fn save_prompt(&mut self, prompt: &SavedPrompt) -> Result<()> {
+        let mut prompts = self.load_prompts()?;
+        match prompts.iter_mut().find(|p| p.id == prompt.id) {
+            Some(existing) => *existing = prompt.clone(),
+            None => prompts.push(prompt.clone()),
+        }
+        Self::write_json(&self.root.join("prompts.json"), &prompts)
+    }
+
+    fn delete_prompt(&mut self, id: &str) -> Result<()> {
+        let mut prompts = self.load_prompts()?;
+        prompts.retain(|p| p.id != id);
+        Self::write_json(&self.root.join("prompts.json"), &prompts)
+    }
+
+    fn load_tombstones(&self) -> Result<Tombstones> {
+        Self::read_json(&self.root.join("tombstones.json"))
+    }
+
+    fn record_tombstones(&mut self, tombstones: &Tombstones) -> Result<()> {
+        let mut recorded = self.load_tombstones()?;
+        recorded.merge(tombstones);
+        Self::write_json(&self.root.join("tombstones.json"), &recorded)
+    }
+
+    fn get_memory(&self, key: &str) -> Result<Option<String>> {
+        let memory: BTreeMap<String, String> = Self::read_json(&self.root.join("memory.json"))?;
+        Ok(memory.get(key).cloned())
+    }
+
+    fn set_memory(&mut self, key: &str, value: &str) -> Result<()> {
+        let path = self.root.join("memory.json");
+        let mut memory: BTreeMap<String, String> = Self::read_json(&path)?;
+        memory.insert(key.to_string(), value.to_string());
+        Self::write_json(&path, &memory)
+    }
+
+    fn list_memory(&self) -> Result<Vec<(String, String)>> {
+        let memory: BTreeMap<String, String> = Self::read_json(&self.root.join("memory.json"))?;
+        Ok(memory.into_iter().collect())
+    }
+}
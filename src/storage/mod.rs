@@ -0,0 +1,370 @@
+//! Persistence for sessions, saved prompts and long-term memory.
+//!
+//! UI code talks to `dyn StorageBackend` only, so new backends (e.g. sync targets)
+//! can be added without touching the views.
+
+pub mod json;
+pub mod retention;
+pub mod sqlite;
+pub mod issues;
+pub mod stats;
+
+use crate::ai::ChatSession;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+pub use json::JsonFileStorage;
+pub use sqlite::SqliteStorage;
+
+/// Which backend to open at startup.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StorageBackendKind {
+    #[default]
+    Sqlite,
+    JsonFiles,
+}
+
+impl StorageBackendKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            StorageBackendKind::Sqlite => "SQLite",
+            StorageBackendKind::JsonFiles => "JSON files",
+        }
+    }
+}
+
+/// A reusable prompt saved by the user.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedPrompt {
+    pub id: String,
+    pub title: String,
+    pub content: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    /// Absent for prompts saved before edits were tracked.
+    #[serde(default)]
+    pub updated_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl SavedPrompt {
+    /// When the prompt last changed, for resolving sync conflicts.
+    pub fn modified_at(&self) -> chrono::DateTime<chrono::Utc> {
+        self.updated_at.unwrap_or(self.created_at)
+    }
+}
+
+/// Records that a session, prompt or message was deleted, so sync deletes it on other devices too
+/// instead of bringing it back.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Tombstone {
+    pub id: String,
+    pub deleted_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl Tombstone {
+    pub fn now(id: impl Into<String>) -> Self {
+        Self { id: id.into(), deleted_at: chrono::Utc::now() }
+    }
+}
+
+/// Deleted sessions, prompts and messages.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Tombstones {
+    pub sessions: Vec<Tombstone>,
+    pub prompts: Vec<Tombstone>,
+    /// Messages deleted from a chat that was kept. Absent in data written before they were recorded.
+    #[serde(default)]
+    pub messages: Vec<Tombstone>,
+}
+
+impl Tombstones {
+    /// Add `other`'s deletions, keeping the later time for ids deleted on both sides.
+    pub fn merge(&mut self, other: &Tombstones) {
+        fn merge_list(list: &mut Vec<Tombstone>, other: &[Tombstone]) {
+            for tombstone in other {
+                match list.iter_mut().find(|t| t.id == tombstone.id) {
+                    Some(existing) => existing.deleted_at = existing.deleted_at.max(tombstone.deleted_at),
+                    None => list.push(tombstone.clone()),
+                }
+            }
+        }
+        merge_list(&mut self.sessions, &other.sessions);
+        merge_list(&mut self.prompts, &other.prompts);
+        merge_list(&mut self.messages, &other.messages);
+    }
+
+    /// Whether session `id` was deleted no earlier than `modified_at`, so that copy is gone.
+    pub fn deletes_session(&self, id: &str, modified_at: chrono::DateTime<chrono::Utc>) -> bool {
+        self.sessions.iter().any(|t| t.id == id && t.deleted_at >= modified_at)
+    }
+
+    /// Whether prompt `id` was deleted no earlier than `modified_at`.
+    pub fn deletes_prompt(&self, id: &str, modified_at: chrono::DateTime<chrono::Utc>) -> bool {
+        self.prompts.iter().any(|t| t.id == id && t.deleted_at >= modified_at)
+    }
+
+    /// Whether message `id` was deleted no earlier than `modified_at`.
+    pub fn deletes_message(&self, id: &str, modified_at: chrono::DateTime<chrono::Utc>) -> bool {
+        self.messages.iter().any(|t| t.id == id && t.deleted_at >= modified_at)
+    }
+}
+
+/// A message matching a search, with a short excerpt around the match.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MessageHit {
+    pub session_id: String,
+    pub message_id: String,
+    pub snippet: String,
+}
+
+pub trait StorageBackend: Send {
+    fn name(&self) -> &str;
+
+    fn load_sessions(&self) -> Result<Vec<ChatSession>>;
+    fn save_session(&mut self, session: &ChatSession) -> Result<()>;
+    fn delete_session(&mut self, id: &str) -> Result<()>;
+
+    /// Messages containing every word of `query` (case-insensitive), at most `limit`.
+    /// Backends with an index override this; the default scans all sessions.
+    fn search_messages(&self, query: &str, limit: usize) -> Result<Vec<MessageHit>> {
+        let words: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+        if words.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut hits = Vec::new();
+        for session in self.load_sessions()? {
+            for message in &session.messages {
+                let content = message.content.to_lowercase();
+                if words.iter().all(|w| content.contains(w.as_str())) {
+                    hits.push(MessageHit {
+                        session_id: session.id.clone(),
+                        message_id: message.id.clone(),
+                        snippet: message.content.chars().take(120).collect(),
+                    });
+                    if hits.len() == limit {
+                        return Ok(hits);
+                    }
+                }
+            }
+        }
+        Ok(hits)
+    }
+
+    fn load_prompts(&self) -> Result<Vec<SavedPrompt>>;
+    fn save_prompt(&mut self, prompt: &SavedPrompt) -> Result<()>;
+    fn delete_prompt(&mut self, id: &str) -> Result<()>;
+
+    /// Deletions recorded with `record_tombstones`, for sync.
+    fn load_tombstones(&self) -> Result<Tombstones>;
+    /// Remember deletions, keeping the later time for ids already recorded.
+    fn record_tombstones(&mut self, tombstones: &Tombstones) -> Result<()>;
+
+    /// Long-term memory is a flat key/value store.
+    fn get_memory(&self, key: &str) -> Result<Option<String>>;
+    fn set_memory(&mut self, key: &str, value: &str) -> Result<()>;
+    fn list_memory(&self) -> Result<Vec<(String, String)>>;
+}
+
+/// Open the configured backend rooted at `dir`.
+pub fn open_storage(kind: StorageBackendKind, dir: &Path) -> Result<Box<dyn StorageBackend>> {
+    std::fs::create_dir_all(dir)?;
+    Ok(match kind {
+        StorageBackendKind::Sqlite => {
+            let mut sqlite = SqliteStorage::open(dir.join("ria.db"))?;
+            // Chats kept by the JSON backend carry over the first time SQLite is used
+            let json_dir = dir.join("storage");
+            if json_dir.join("sessions").is_dir() {
+                let imported = sqlite.import_json(&JsonFileStorage::open(&json_dir)?)?;
+                if imported > 0 {
+                    tracing::info!("Imported {} sessions from JSON storage into SQLite", imported);
+                }
+            }
+            Box::new(sqlite)
+        }
+        StorageBackendKind::JsonFiles => Box::new(JsonFileStorage::open(dir.join("storage"))?),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::{ChatMessage, MessageRole};
+
+    fn sample_session(id: &str) -> ChatSession {
+        let now = chrono::Utc::now();
+        ChatSession {
+            id: id.to_string(),
+            title: format!("Session {id}"),
+            messages: vec![ChatMessage {
+                id: "m1".into(),
+                content: "hello".into(),
+                role: MessageRole::User,
+                timestamp: now,
+                model_used: None,
+                inference_time: None,
+                images: Vec::new(),
+                trace: None,
+                reply_to: None,
+                feedback: None,
+                edited_at: None,
+            }],
+            ..Default::default()
+        }
+    }
+
+    fn exercise_backend(kind: StorageBackendKind) {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = open_storage(kind, dir.path()).unwrap();
+
+        store.save_session(&sample_session("a")).unwrap();
+        store.save_session(&sample_session("b")).unwrap();
+        let mut updated = sample_session("a");
+        updated.title = "Renamed".into();
+        store.save_session(&updated).unwrap();
+        store.delete_session("b").unwrap();
+
+        let sessions = store.load_sessions().unwrap();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].title, "Renamed");
+        assert_eq!(sessions[0].messages[0].content, "hello");
+
+        let prompt = SavedPrompt { id: "p".into(), title: "t".into(), content: "c".into(), created_at: chrono::Utc::now(), updated_at: None };
+        store.save_prompt(&prompt).unwrap();
+        assert_eq!(store.load_prompts().unwrap().len(), 1);
+        store.delete_prompt("p").unwrap();
+        assert!(store.load_prompts().unwrap().is_empty());
+
+        let earlier = Tombstones { sessions: vec![Tombstone::now("b")], ..Default::default() };
+        let later = Tombstones { sessions: vec![Tombstone::now("b")], prompts: vec![Tombstone::now("p")], messages: vec![Tombstone::now("m")] };
+        store.record_tombstones(&later).unwrap();
+        store.record_tombstones(&earlier).unwrap();
+        assert_eq!(store.load_tombstones().unwrap(), later);
+
+        store.set_memory("name", "Ria").unwrap();
+        store.set_memory("name", "Ria 2").unwrap();
+        assert_eq!(store.get_memory("name").unwrap().as_deref(), Some("Ria 2"));
+        assert_eq!(store.list_memory().unwrap().len(), 1);
+
+        let hits = store.search_messages("HELLO", 10).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!((hits[0].session_id.as_str(), hits[0].message_id.as_str()), ("a", "m1"));
+        assert!(store.search_messages("hello goodbye", 10).unwrap().is_empty());
+
+        // Data survives reopening
+        drop(store);
+        let store = open_storage(kind, dir.path()).unwrap();
+        assert_eq!(store.load_sessions().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_sqlite_backend_roundtrip() {
+        exercise_backend(StorageBackendKind::Sqlite);
+    }
+
+    #[test]
+    fn test_json_backend_roundtrip() {
+        exercise_backend(StorageBackendKind::JsonFiles);
+    }
+
+    #[test]
+    fn test_sqlite_writes_message_changes_and_keeps_search_in_step() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = SqliteStorage::open(dir.path().join("ria.db")).unwrap();
+        let mut session = sample_session("a");
+        let mut reply = session.messages[0].clone();
+        reply.id = "m2".into();
+        reply.content = "quantized phi model".into();
+        session.messages.push(reply);
+        store.save_session(&session).unwrap();
+        assert_eq!(store.search_messages("phi", 10).unwrap().len(), 1);
+
+        session.messages[1].content = "edited answer".into();
+        session.messages.remove(0);
+        store.save_session(&session).unwrap();
+        assert!(store.search_messages("phi", 10).unwrap().is_empty());
+        assert!(store.search_messages("hello", 10).unwrap().is_empty());
+        assert_eq!(store.search_messages("edited", 10).unwrap()[0].message_id, "m2");
+
+        let loaded = store.load_sessions().unwrap();
+        assert_eq!(loaded[0].messages.len(), 1);
+        assert_eq!(loaded[0].messages[0].content, "edited answer");
+    }
+
+    #[test]
+    fn test_sqlite_migrates_session_blobs_and_json_files() {
+        let dir = tempfile::tempdir().unwrap();
+
+        // A version 1 database kept whole sessions as JSON blobs
+        let conn = rusqlite::Connection::open(dir.path().join("ria.db")).unwrap();
+        conn.execute_batch("CREATE TABLE sessions (id TEXT PRIMARY KEY, created_at TEXT NOT NULL, data TEXT NOT NULL);").unwrap();
+        let old = sample_session("old");
+        conn.execute(
+            "INSERT INTO sessions VALUES (?1, ?2, ?3)",
+            rusqlite::params![old.id, old.created_at.to_rfc3339(), serde_json::to_string(&old).unwrap()],
+        )
+        .unwrap();
+        drop(conn);
+
+        let mut json = JsonFileStorage::open(dir.path().join("storage")).unwrap();
+        json.save_session(&sample_session("from-json")).unwrap();
+        json.set_memory("name", "Ria").unwrap();
+
+        let store = open_storage(StorageBackendKind::Sqlite, dir.path()).unwrap();
+        let sessions = store.load_sessions().unwrap();
+        let ids: Vec<_> = sessions.iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(ids.len(), 2);
+        assert!(ids.contains(&"old") && ids.contains(&"from-json"));
+        assert!(sessions.iter().all(|s| s.messages.len() == 1));
+        assert_eq!(store.get_memory("name").unwrap().as_deref(), Some("Ria"));
+
+        // The import only happens once, so chats deleted afterwards stay deleted
+        drop(store);
+        let mut store = open_storage(StorageBackendKind::Sqlite, dir.path()).unwrap();
+        store.delete_session("from-json").unwrap();
+        drop(store);
+        let store = open_storage(StorageBackendKind::Sqlite, dir.path()).unwrap();
+        assert_eq!(store.load_sessions().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_sqlite_rekeys_version_2_messages_for_search() {
+        let dir = tempfile::tempdir().unwrap();
+
+        // Version 2 keyed the search index by the messages table's implicit rowid
+        let conn = rusqlite::Connection::open(dir.path().join("ria.db")).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE sessions (id TEXT PRIMARY KEY, created_at TEXT NOT NULL, data TEXT NOT NULL);
+             CREATE TABLE messages (session_id TEXT NOT NULL, id TEXT NOT NULL, position INTEGER NOT NULL,
+                 content TEXT NOT NULL, data TEXT NOT NULL, PRIMARY KEY (session_id, id));
+             CREATE VIRTUAL TABLE messages_fts USING fts5(content, content='messages', content_rowid='rowid');
+             CREATE TRIGGER messages_ai AFTER INSERT ON messages BEGIN
+                 INSERT INTO messages_fts (rowid, content) VALUES (new.rowid, new.content);
+             END;
+             PRAGMA user_version = 2;",
+        )
+        .unwrap();
+        let old = sample_session("a");
+        let meta = ChatSession { messages: Vec::new(), ..old.clone() };
+        conn.execute(
+            "INSERT INTO sessions VALUES (?1, ?2, ?3)",
+            rusqlite::params![old.id, old.created_at.to_rfc3339(), serde_json::to_string(&meta).unwrap()],
+        )
+        .unwrap();
+        let message = &old.messages[0];
+        conn.execute(
+            "INSERT INTO messages VALUES ('a', ?1, 0, ?2, ?3)",
+            rusqlite::params![message.id, message.content, serde_json::to_string(message).unwrap()],
+        )
+        .unwrap();
+        drop(conn);
+
+        let mut store = SqliteStorage::open(dir.path().join("ria.db")).unwrap();
+        assert_eq!(store.search_messages("hello", 10).unwrap()[0].message_id, "m1");
+        store.save_session(&sample_session("b")).unwrap();
+        store.delete_session("a").unwrap();
+        let hits = store.search_messages("hello", 10).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].session_id, "b");
+        assert_eq!(store.load_sessions().unwrap()[0].messages[0].content, "hello");
+    }
+}
@@ -0,0 +1,141 @@
+//! Chat history retention: which chats to delete once there are too many, they are too old or
+//! they take too much space.
+//!
+//! [`plan`] only decides; the app previews the result in Settings and deletes the chats itself,
+//! on startup and every hour when automatic pruning is on. Pinned chats are never pruned.
+
+use crate::ai::ChatSession;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetentionSettings {
+    /// Prune on startup and every hour; otherwise only when asked from Settings.
+    pub automatic: bool,
+    /// Delete chats not used for this many days; 0 keeps them however old.
+    pub max_age_days: u32,
+    /// Delete the oldest chats while all of them together take more; 0 for no limit.
+    pub max_disk_mb: u64,
+}
+
+/// The limit a chat was pruned for; the first that applies is reported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PruneReason {
+    Age,
+    Count,
+    Size,
+}
+
+impl PruneReason {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Age => "too old",
+            Self::Count => "over the chat limit",
+            Self::Size => "over the size limit",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Prunable {
+    pub session_id: String,
+    pub title: String,
+    pub updated_at: DateTime<Utc>,
+    pub bytes: u64,
+    pub reason: PruneReason,
+}
+
+/// Roughly what a chat takes on disk: its size as JSON.
+pub fn session_bytes(session: &ChatSession) -> u64 {
+    serde_json::to_vec(session).map_or(0, |json| json.len() as u64)
+}
+
+/// Chats to delete so that at most `max_sessions` remain (0 for no limit) and the age and size
+/// limits hold, least recently used first. Pinned chats and those in `keep` are never chosen but
+/// still count towards the chat and size limits.
+pub fn plan(
+    sessions: &[ChatSession],
+    settings: &RetentionSettings,
+    max_sessions: usize,
+    keep: &HashSet<String>,
+    now: DateTime<Utc>,
+) -> Vec<Prunable> {
+    let mut oldest_first: Vec<&ChatSession> = sessions.iter().collect();
+    oldest_first.sort_by_key(|s| s.updated_at);
+    let protected = |s: &ChatSession| s.pinned || keep.contains(&s.id);
+
+    let mut remaining = sessions.len();
+    let mut bytes: u64 = sessions.iter().map(session_bytes).sum();
+    let max_bytes = settings.max_disk_mb.saturating_mul(1024 * 1024);
+    let cutoff = (settings.max_age_days > 0).then(|| now - chrono::Duration::days(settings.max_age_days.into()));
+
+    let mut pruned = Vec::new();
+    for session in oldest_first.into_iter().filter(|s| !protected(s)) {
+        let reason = if cutoff.is_some_and(|cutoff| session.updated_at < cutoff) {
+            PruneReason::Age
+        } else if max_sessions > 0 && remaining > max_sessions {
+            PruneReason::Count
+        } else if max_bytes > 0 && bytes > max_bytes {
+            PruneReason::Size
+        } else {
+            continue;
+        };
+        let size = session_bytes(session);
+        remaining -= 1;
+        bytes = bytes.saturating_sub(size);
+        pruned.push(Prunable {
+            session_id: session.id.clone(),
+            title: session.title.clone(),
+            updated_at: session.updated_at,
+            bytes: size,
+            reason,
+        });
+    }
+    pruned
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::ChatMessage;
+
+    #[test]
+    fn test_plan_prunes_oldest_unpinned_first() {
+        let now = Utc::now();
+        let session = |id: &str, days_ago: i64, pinned: bool| ChatSession {
+            id: id.into(),
+            title: id.into(),
+            messages: vec![ChatMessage::system("x".repeat(1000))],
+            updated_at: now - chrono::Duration::days(days_ago),
+            pinned,
+            ..Default::default()
+        };
+        let sessions = vec![session("new", 1, false), session("pinned", 90, true), session("old", 60, false), session("mid", 10, false)];
+        let keep = HashSet::new();
+        let ids = |plan: Vec<Prunable>| plan.into_iter().map(|p| (p.session_id, p.reason)).collect::<Vec<_>>();
+
+        assert!(plan(&sessions, &RetentionSettings::default(), 0, &keep, now).is_empty());
+
+        let by_age = RetentionSettings { max_age_days: 30, ..Default::default() };
+        assert_eq!(ids(plan(&sessions, &by_age, 0, &keep, now)), vec![("old".to_string(), PruneReason::Age)]);
+
+        // The pinned chat counts towards the limit but stays
+        assert_eq!(
+            ids(plan(&sessions, &by_age, 2, &keep, now)),
+            vec![("old".to_string(), PruneReason::Age), ("mid".to_string(), PruneReason::Count)]
+        );
+        let keep_old = HashSet::from(["old".to_string()]);
+        assert_eq!(
+            ids(plan(&sessions, &RetentionSettings::default(), 2, &keep_old, now)),
+            vec![("mid".to_string(), PruneReason::Count), ("new".to_string(), PruneReason::Count)]
+        );
+
+        // Three chats of ~400 KB against a 1 MB limit: the oldest goes
+        let big = |id: &str, days_ago: i64| ChatSession { messages: vec![ChatMessage::system("x".repeat(400_000))], ..session(id, days_ago, false) };
+        let large = vec![big("a", 3), big("b", 2), big("c", 1)];
+        let by_size = RetentionSettings { max_disk_mb: 1, ..Default::default() };
+        assert_eq!(ids(plan(&large, &by_size, 0, &keep, now)), vec![("a".to_string(), PruneReason::Size)]);
+    }
+}
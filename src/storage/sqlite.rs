@@ -0,0 +1,362 @@
+use super::{MessageHit, SavedPrompt, StorageBackend, Tombstone, Tombstones};
+use crate::ai::{ChatMessage, ChatSession};
+use anyhow::Result;
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Duration;
+
+/// Schema version stored in `PRAGMA user_version`. Version 1 kept each session, messages
+/// included, as a single JSON blob; version 2 stores one row per message; version 3 gives
+/// message rows an explicit integer key for the search index.
+const SCHEMA_VERSION: i64 = 3;
+
+/// Message rows and their full-text index. The index refers to rows by `seq`: an implicit
+/// rowid may be renumbered by `VACUUM`, which would point the index at the wrong messages.
+const MESSAGES_SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS messages (
+        seq INTEGER PRIMARY KEY,
+        session_id TEXT NOT NULL,
+        id TEXT NOT NULL,
+        position INTEGER NOT NULL,
+        content TEXT NOT NULL,
+        data TEXT NOT NULL,
+        UNIQUE (session_id, id)
+    );
+    CREATE VIRTUAL TABLE IF NOT EXISTS messages_fts USING fts5(
+        content, content='messages', content_rowid='seq'
+    );
+    CREATE TRIGGER IF NOT EXISTS messages_ai AFTER INSERT ON messages BEGIN
+        INSERT INTO messages_fts (rowid, content) VALUES (new.seq, new.content);
+    END;
+    CREATE TRIGGER IF NOT EXISTS messages_ad AFTER DELETE ON messages BEGIN
+        INSERT INTO messages_fts (messages_fts, rowid, content) VALUES ('delete', old.seq, old.content);
+    END;
+    CREATE TRIGGER IF NOT EXISTS messages_au AFTER UPDATE ON messages BEGIN
+        INSERT INTO messages_fts (messages_fts, rowid, content) VALUES ('delete', old.seq, old.content);
+        INSERT INTO messages_fts (rowid, content) VALUES (new.seq, new.content);
+    END;";
+
+/// How long a write waits for another connection (e.g. a second app instance) to finish.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Default backend: a single SQLite database file.
+///
+/// Sessions hold their metadata only; messages live in their own table (indexed for
+/// full-text search) so saving a session writes just the messages that changed.
+pub struct SqliteStorage {
+    conn: Connection,
+}
+
+impl SqliteStorage {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let conn = Connection::open(path)?;
+        Self::init(conn)
+    }
+
+    fn init(mut conn: Connection) -> Result<Self> {
+        conn.busy_timeout(BUSY_TIMEOUT)?;
+        conn.execute_batch(
+            "PRAGMA journal_mode = WAL;
+             CREATE TABLE IF NOT EXISTS sessions (
+                 id TEXT PRIMARY KEY,
+                 created_at TEXT NOT NULL,
+                 data TEXT NOT NULL
+             );
+             CREATE TABLE IF NOT EXISTS prompts (
+                 id TEXT PRIMARY KEY,
+                 created_at TEXT NOT NULL,
+                 data TEXT NOT NULL
+             );
+             CREATE TABLE IF NOT EXISTS memory (
+                 key TEXT PRIMARY KEY,
+                 value TEXT NOT NULL
+             );
+             CREATE TABLE IF NOT EXISTS meta (
+                 key TEXT PRIMARY KEY,
+                 value TEXT NOT NULL
+             );
+             CREATE TABLE IF NOT EXISTS tombstones (
+                 kind TEXT NOT NULL,
+                 id TEXT NOT NULL,
+                 deleted_at TEXT NOT NULL,
+                 PRIMARY KEY (kind, id)
+             );",
+        )?;
+
+        let version: i64 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        // Version 2 message tables are rebuilt by the migration below
+        if version != 2 {
+            conn.execute_batch(MESSAGES_SCHEMA)?;
+        }
+        if version < SCHEMA_VERSION {
+            let tx = conn.transaction()?;
+            if version < 2 {
+                migrate_session_blobs(&tx)?;
+            }
+            if version == 2 {
+                migrate_message_keys(&tx)?;
+            }
+            tx.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+            tx.commit()?;
+        }
+        Ok(Self { conn })
+    }
+
+    /// Copy sessions, prompts and memory from the JSON file backend, once. Returns the number
+    /// of sessions imported (0 if an import already happened).
+    pub fn import_json(&mut self, json: &dyn StorageBackend) -> Result<usize> {
+        let done: Option<String> = self
+            .conn
+            .query_row("SELECT value FROM meta WHERE key = 'json_imported'", [], |row| row.get(0))
+            .optional()?;
+        if done.is_some() {
+            return Ok(0);
+        }
+        let sessions = json.load_sessions()?;
+        let tx = self.conn.transaction()?;
+        for session in &sessions {
+            write_session(&tx, session)?;
+        }
+        for prompt in json.load_prompts()? {
+            tx.execute(
+                "INSERT OR IGNORE INTO prompts (id, created_at, data) VALUES (?1, ?2, ?3)",
+                params![prompt.id, prompt.created_at.to_rfc3339(), serde_json::to_string(&prompt)?],
+            )?;
+        }
+        for (key, value) in json.list_memory()? {
+            tx.execute("INSERT OR IGNORE INTO memory (key, value) VALUES (?1, ?2)", params![key, value])?;
+        }
+        tx.execute(
+            "INSERT INTO meta (key, value) VALUES ('json_imported', ?1)",
+            params![chrono::Utc::now().to_rfc3339()],
+        )?;
+        tx.commit()?;
+        Ok(sessions.len())
+    }
+}
+
+/// Split version 1 session blobs into metadata plus message rows.
+fn migrate_session_blobs(tx: &Transaction) -> Result<()> {
+    let blobs: Vec<String> = {
+        let mut stmt = tx.prepare("SELECT data FROM sessions")?;
+        let rows = stmt.query_map([], |row| row.get(0))?;
+        rows.collect::<rusqlite::Result<_>>()?
+    };
+    for data in blobs {
+        match serde_json::from_str::<ChatSession>(&data) {
+            Ok(session) => write_session(tx, &session)?,
+            Err(e) => tracing::warn!("Leaving unreadable session row unmigrated: {}", e),
+        }
+    }
+    Ok(())
+}
+
+/// Move version 2 message rows, keyed by their implicit rowid, into the version 3 table and
+/// rebuild the search index over it.
+fn migrate_message_keys(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
+        "DROP TRIGGER IF EXISTS messages_ai;
+         DROP TRIGGER IF EXISTS messages_ad;
+         DROP TRIGGER IF EXISTS messages_au;
+         DROP TABLE IF EXISTS messages_fts;
+         ALTER TABLE messages RENAME TO messages_v2;",
+    )?;
+    tx.execute_batch(MESSAGES_SCHEMA)?;
+    tx.execute_batch(
+        "INSERT INTO messages (session_id, id, position, content, data)
+             SELECT session_id, id, position, content, data FROM messages_v2 ORDER BY rowid;
+         DROP TABLE messages_v2;
+         INSERT INTO messages_fts (messages_fts) VALUES ('rebuild');",
+    )?;
+    Ok(())
+}
+
+/// Upsert `session`, writing only the messages that were added or changed and removing the
+/// ones that are gone.
+fn write_session(tx: &Transaction, session: &ChatSession) -> Result<()> {
+    // The session row keeps everything but the messages
+    let meta = serde_json::to_string(&ChatSession { messages: Vec::new(), ..session.clone() })?;
+    tx.execute(
+        "INSERT INTO sessions (id, created_at, data) VALUES (?1, ?2, ?3)
+         ON CONFLICT (id) DO UPDATE SET created_at = excluded.created_at, data = excluded.data",
+        params![session.id, session.created_at.to_rfc3339(), meta],
+    )?;
+
+    let mut stored: HashMap<String, (i64, String)> = {
+        let mut stmt = tx.prepare("SELECT id, position, data FROM messages WHERE session_id = ?1")?;
+        let rows = stmt.query_map(params![session.id], |row| Ok((row.get(0)?, (row.get(1)?, row.get(2)?))))?;
+        rows.collect::<rusqlite::Result<_>>()?
+    };
+    // An upsert rather than INSERT OR REPLACE, so the update trigger keeps the index in step
+    let mut upsert = tx.prepare_cached(
+        "INSERT INTO messages (session_id, id, position, content, data) VALUES (?1, ?2, ?3, ?4, ?5)
+         ON CONFLICT (session_id, id) DO UPDATE SET
+             position = excluded.position, content = excluded.content, data = excluded.data",
+    )?;
+    for (position, message) in session.messages.iter().enumerate() {
+        let data = serde_json::to_string(message)?;
+        let position = position as i64;
+        if stored.remove(&message.id) != Some((position, data.clone())) {
+            upsert.execute(params![session.id, message.id, position, message.content, data])?;
+        }
+    }
+    for removed in stored.keys() {
+        tx.execute("DELETE FROM messages WHERE session_id = ?1 AND id = ?2", params![session.id, removed])?;
+    }
+    Ok(())
+}
+
+/// Turn free text into an FTS5 query matching every word, without FTS syntax surprises.
+fn fts_query(text: &str) -> String {
+    text.split_whitespace()
+        .map(|word| format!("\"{}\"", word.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+impl StorageBackend for SqliteStorage {
+    fn name(&self) -> &str {
+        "SQLite"
+    }
+
+    fn load_sessions(&self) -> Result<Vec<ChatSession>> {
+        let mut messages: HashMap<String, Vec<ChatMessage>> = HashMap::new();
+        let mut stmt = self.conn.prepare("SELECT session_id, data FROM messages ORDER BY session_id, position")?;
+        let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?;
+        for row in rows {
+            let (session_id, data) = row?;
+            match serde_json::from_str(&data) {
+                Ok(message) => messages.entry(session_id).or_default().push(message),
+                Err(e) => tracing::warn!("Skipping unreadable message row in session {}: {}", session_id, e),
+            }
+        }
+
+        let mut stmt = self.conn.prepare("SELECT data FROM sessions ORDER BY created_at")?;
+        let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+        let mut sessions = Vec::new();
+        for data in rows {
+            match serde_json::from_str::<ChatSession>(&data?) {
+                Ok(mut session) => {
+                    session.messages = messages.remove(&session.id).unwrap_or_default();
+                    sessions.push(session);
+                }
+                Err(e) => tracing::warn!("Skipping unreadable session row: {}", e),
+            }
+        }
+        Ok(sessions)
+    }
+
+    fn save_session(&mut self, session: &ChatSession) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        write_session(&tx, session)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn delete_session(&mut self, id: &str) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        tx.execute("DELETE FROM messages WHERE session_id = ?1", params![id])?;
+        tx.execute("DELETE FROM sessions WHERE id = ?1", params![id])?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn search_messages(&self, query: &str, limit: usize) -> Result<Vec<MessageHit>> {
+        let query = fts_query(query);
+        if query.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut stmt = self.conn.prepare(
+            "SELECT m.session_id, m.id, snippet(messages_fts, 0, '', '', '…', 16)
+             FROM messages_fts JOIN messages m ON m.seq = messages_fts.rowid
+             WHERE messages_fts MATCH ?1 ORDER BY rank LIMIT ?2",
+        )?;
+        let rows = stmt.query_map(params![query, limit as i64], |row| {
+            Ok(MessageHit { session_id: row.get(0)?, message_id: row.get(1)?, snippet: row.get(2)? })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    fn load_prompts(&self) -> Result<Vec<SavedPrompt>> {
+        let mut stmt = self.conn.prepare("SELECT data FROM prompts ORDER BY created_at")?;
+        let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+        let mut prompts = Vec::new();
+        for data in rows {
+            prompts.push(serde_json::from_str(&data?)?);
+        }
+        Ok(prompts)
+    }
+
+    fn save_prompt(&mut self, prompt: &SavedPrompt) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO prompts (id, created_at, data) VALUES (?1, ?2, ?3)",
+            params![prompt.id, prompt.created_at.to_rfc3339(), serde_json::to_string(prompt)?],
+        )?;
+        Ok(())
+    }
+
+    fn delete_prompt(&mut self, id: &str) -> Result<()> {
+        self.conn.execute("DELETE FROM prompts WHERE id = ?1", params![id])?;
+        Ok(())
+    }
+
+    fn load_tombstones(&self) -> Result<Tombstones> {
+        let mut tombstones = Tombstones::default();
+        let mut stmt = self.conn.prepare("SELECT kind, id, deleted_at FROM tombstones")?;
+        let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?)))?;
+        for row in rows {
+            let (kind, id, deleted_at) = row?;
+            let Ok(deleted_at) = chrono::DateTime::parse_from_rfc3339(&deleted_at) else { continue };
+            let deleted_at = deleted_at.with_timezone(&chrono::Utc);
+            let list = match kind.as_str() {
+                "session" => &mut tombstones.sessions,
+                "prompt" => &mut tombstones.prompts,
+                "message" => &mut tombstones.messages,
+                _ => continue,
+            };
+            list.push(Tombstone { id, deleted_at });
+        }
+        Ok(tombstones)
+    }
+
+    fn record_tombstones(&mut self, tombstones: &Tombstones) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        {
+            // Fixed-width timestamps, so max() compares them in time order
+            let mut upsert = tx.prepare(
+                "INSERT INTO tombstones (kind, id, deleted_at) VALUES (?1, ?2, ?3)
+                 ON CONFLICT (kind, id) DO UPDATE SET deleted_at = max(deleted_at, excluded.deleted_at)",
+            )?;
+            let all = tombstones.sessions.iter().map(|t| ("session", t))
+                .chain(tombstones.prompts.iter().map(|t| ("prompt", t)))
+                .chain(tombstones.messages.iter().map(|t| ("message", t)));
+            for (kind, tombstone) in all {
+                upsert.execute(params![kind, tombstone.id, tombstone.deleted_at.to_rfc3339_opts(chrono::SecondsFormat::Nanos, true)])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn get_memory(&self, key: &str) -> Result<Option<String>> {
+        Ok(self
+            .conn
+            .query_row("SELECT value FROM memory WHERE key = ?1", params![key], |row| row.get(0))
+            .optional()?)
+    }
+
+    fn set_memory(&mut self, key: &str, value: &str) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO memory (key, value) VALUES (?1, ?2)",
+            params![key, value],
+        )?;
+        Ok(())
+    }
+
+    fn list_memory(&self) -> Result<Vec<(String, String)>> {
+        let mut stmt = self.conn.prepare("SELECT key, value FROM memory ORDER BY key")?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+}
@@ -0,0 +1,208 @@
+//! Local usage statistics: one row per chat message in a small SQLite database next to the
+//! chat storage, aggregated on demand for the Stats window.
+//!
+//! Streaming doesn't report token counts, so replies are measured with `estimate_tokens`.
+
+use anyhow::Result;
+use chrono::{DateTime, Local, NaiveDate, Utc};
+use rusqlite::{params, Connection};
+use std::path::Path;
+
+/// Usage on one calendar day (local time).
+#[derive(Debug, Clone, PartialEq)]
+pub struct DailyUsage {
+    pub day: NaiveDate,
+    /// Messages the user sent; replies are counted in `tokens`.
+    pub messages: u64,
+    pub tokens: u64,
+}
+
+/// Replies produced by one model.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelUsage {
+    pub model: String,
+    pub replies: u64,
+    pub tokens: u64,
+    pub seconds: f64,
+}
+
+impl ModelUsage {
+    pub fn tokens_per_second(&self) -> Option<f64> {
+        (self.seconds > 0.0).then(|| self.tokens as f64 / self.seconds)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UsageTotals {
+    /// Messages the user sent.
+    pub messages: u64,
+    pub replies: u64,
+    pub tokens: u64,
+    pub seconds: f64,
+}
+
+impl UsageTotals {
+    pub fn tokens_per_second(&self) -> Option<f64> {
+        (self.seconds > 0.0).then(|| self.tokens as f64 / self.seconds)
+    }
+}
+
+/// Everything the Stats window shows, read in one go so it can be kept between frames.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UsageReport {
+    pub daily: Vec<DailyUsage>,
+    pub models: Vec<ModelUsage>,
+    pub totals: UsageTotals,
+}
+
+/// Rough token count for text we only have as a string (about four characters per token).
+pub fn estimate_tokens(text: &str) -> u64 {
+    (text.chars().count() as u64).div_ceil(4)
+}
+
+pub struct UsageStats {
+    conn: Connection,
+}
+
+impl UsageStats {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Self::init(Connection::open(path)?)
+    }
+
+    #[cfg(test)]
+    fn open_in_memory() -> Result<Self> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(conn: Connection) -> Result<Self> {
+        conn.execute_batch(
+            "PRAGMA journal_mode = WAL;
+             CREATE TABLE IF NOT EXISTS usage (
+                 timestamp TEXT NOT NULL,
+                 day TEXT NOT NULL,
+                 model TEXT,
+                 tokens INTEGER NOT NULL DEFAULT 0,
+                 seconds REAL NOT NULL DEFAULT 0
+             );
+             CREATE INDEX IF NOT EXISTS usage_day ON usage (day);",
+        )?;
+        Ok(Self { conn })
+    }
+
+    /// A message the user sent.
+    pub fn record_message(&mut self, at: DateTime<Utc>) -> Result<()> {
+        self.insert(at, None, 0, 0.0)
+    }
+
+    /// A reply from `model` with `tokens` generated in `seconds`.
+    pub fn record_reply(&mut self, at: DateTime<Utc>, model: &str, tokens: u64, seconds: f64) -> Result<()> {
+        self.insert(at, Some(model), tokens, seconds)
+    }
+
+    fn insert(&mut self, at: DateTime<Utc>, model: Option<&str>, tokens: u64, seconds: f64) -> Result<()> {
+        let day = at.with_timezone(&Local).date_naive().to_string();
+        self.conn.execute(
+            "INSERT INTO usage (timestamp, day, model, tokens, seconds) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![at.to_rfc3339(), day, model, tokens as i64, seconds],
+        )?;
+        Ok(())
+    }
+
+    /// The `days` days ending on `today`, oldest first; days without activity are zero.
+    pub fn daily(&self, today: NaiveDate, days: u32) -> Result<Vec<DailyUsage>> {
+        let first = today - chrono::Days::new(u64::from(days.saturating_sub(1)));
+        let mut stmt = self.conn.prepare(
+            "SELECT day, SUM(model IS NULL), SUM(tokens) FROM usage WHERE day >= ?1 AND day <= ?2 GROUP BY day",
+        )?;
+        let rows = stmt.query_map(params![first.to_string(), today.to_string()], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?, row.get::<_, i64>(2)?))
+        })?;
+        let mut usage: Vec<DailyUsage> = first
+            .iter_days()
+            .take(days as usize)
+            .map(|day| DailyUsage { day, messages: 0, tokens: 0 })
+            .collect();
+        for row in rows {
+            let (day, messages, tokens) = row?;
+            let Ok(day) = day.parse::<NaiveDate>() else { continue };
+            if let Some(entry) = usage.iter_mut().find(|u| u.day == day) {
+                entry.messages = messages as u64;
+                entry.tokens = tokens as u64;
+            }
+        }
+        Ok(usage)
+    }
+
+    /// Reply counts per model, busiest first.
+    pub fn per_model(&self) -> Result<Vec<ModelUsage>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT model, COUNT(*), SUM(tokens), SUM(seconds) FROM usage
+             WHERE model IS NOT NULL GROUP BY model ORDER BY COUNT(*) DESC, model",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(ModelUsage {
+                model: row.get(0)?,
+                replies: row.get::<_, i64>(1)? as u64,
+                tokens: row.get::<_, i64>(2)? as u64,
+                seconds: row.get(3)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    /// Daily usage for the `days` days ending on `today`, per-model usage and the totals.
+    pub fn report(&self, today: NaiveDate, days: u32) -> Result<UsageReport> {
+        Ok(UsageReport { daily: self.daily(today, days)?, models: self.per_model()?, totals: self.totals()? })
+    }
+
+    pub fn totals(&self) -> Result<UsageTotals> {
+        Ok(self.conn.query_row(
+            "SELECT COUNT(*) - COUNT(model), COUNT(model), COALESCE(SUM(tokens), 0), COALESCE(SUM(seconds), 0) FROM usage",
+            [],
+            |row| {
+                Ok(UsageTotals {
+                    messages: row.get::<_, i64>(0)? as u64,
+                    replies: row.get::<_, i64>(1)? as u64,
+                    tokens: row.get::<_, i64>(2)? as u64,
+                    seconds: row.get(3)?,
+                })
+            },
+        )?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_usage_is_aggregated_per_day_and_model() {
+        let mut stats = UsageStats::open_in_memory().unwrap();
+        let now = Utc::now();
+        let today = now.with_timezone(&Local).date_naive();
+        let yesterday = now - chrono::Duration::days(1);
+
+        stats.record_message(now).unwrap();
+        stats.record_reply(now, "phi-3", 120, 4.0).unwrap();
+        stats.record_message(yesterday).unwrap();
+        stats.record_reply(yesterday, "phi-3", 30, 2.0).unwrap();
+        stats.record_reply(yesterday, "Demo", 10, 0.0).unwrap();
+
+        let daily = stats.daily(today, 7).unwrap();
+        assert_eq!(daily.len(), 7);
+        assert_eq!(daily[6], DailyUsage { day: today, messages: 1, tokens: 120 });
+        assert_eq!((daily[5].messages, daily[5].tokens), (1, 40));
+        assert_eq!(daily[0].messages, 0);
+
+        let models = stats.per_model().unwrap();
+        assert_eq!(models[0].model, "phi-3");
+        assert_eq!(models[0].replies, 2);
+        assert_eq!(models[0].tokens_per_second(), Some(25.0));
+        assert_eq!(models[1].tokens_per_second(), None);
+
+        let totals = stats.totals().unwrap();
+        assert_eq!((totals.messages, totals.replies, totals.tokens), (2, 3, 160));
+        assert_eq!(stats.report(today, 7).unwrap().totals, totals);
+        assert_eq!(estimate_tokens("abcdefghi"), 3);
+    }
+}
@@ -0,0 +1,88 @@
+//! Conflict resolution: sessions are merged (union of messages) and prompts are
+//! last-writer-wins, both on their last update time. A message on both sides is
+//! last-writer-wins on its own edit time. Deletions win over copies that were last updated
+//! before them.
+
+use crate::ai::ChatSession;
+use crate::storage::{SavedPrompt, Tombstones};
+use std::collections::{HashMap, HashSet};
+
+/// Merge two session sets. Sessions present on one side only are kept unless `deleted` says
+/// they were deleted after their last update. For sessions present on both, messages are
+/// unioned by id and a message on both sides comes from whichever side changed it last; the
+/// title and starred messages come from whichever side was updated last (ties keep local).
+/// Deleted messages are dropped unless they were changed after the deletion.
+pub fn merge_sessions(local: &[ChatSession], remote: &[ChatSession], deleted: &Tombstones) -> Vec<ChatSession> {
+    let mut merged: Vec<ChatSession> = local.to_vec();
+    let index: HashMap<String, usize> = merged.iter().enumerate().map(|(i, s)| (s.id.clone(), i)).collect();
+
+    for remote_session in remote {
+        let Some(&i) = index.get(&remote_session.id) else {
+            merged.push(remote_session.clone());
+            continue;
+        };
+        let local_session = &mut merged[i];
+        let remote_newer = remote_session.updated_at > local_session.updated_at;
+
+        let mut messages: Vec<_> = local_session.messages.clone();
+        for remote_msg in &remote_session.messages {
+            match messages.iter_mut().find(|m| m.id == remote_msg.id) {
+                Some(existing) if remote_msg.modified_at() > existing.modified_at() => *existing = remote_msg.clone(),
+                Some(_) => {}
+                None => messages.push(remote_msg.clone()),
+            }
+        }
+        messages.sort_by_key(|m| m.timestamp);
+        local_session.messages = messages;
+
+        if remote_newer {
+            local_session.title = remote_session.title.clone();
+            local_session.starred = remote_session.starred.clone();
+            local_session.updated_at = remote_session.updated_at;
+        }
+        local_session.created_at = local_session.created_at.min(remote_session.created_at);
+    }
+
+    merged.retain(|s| !deleted.deletes_session(&s.id, s.updated_at));
+    for session in &mut merged {
+        let gone: Vec<String> = session
+            .messages
+            .iter()
+            .filter(|m| deleted.deletes_message(&m.id, m.modified_at()))
+            .map(|m| m.id.clone())
+            .collect();
+        for id in gone {
+            session.remove_message(&id);
+        }
+    }
+    merged.sort_by_key(|s| s.created_at);
+    merged
+}
+
+/// Apply a finished sync round to the sessions as they are now. The round merged a snapshot
+/// taken when it started, so chats created or changed since then are merged with its result
+/// rather than replaced by it. Returns the sessions to keep and the ids of the current ones
+/// that a deletion on some device removes.
+pub fn apply_synced_sessions(current: &[ChatSession], synced: &[ChatSession], deleted: &Tombstones) -> (Vec<ChatSession>, HashSet<String>) {
+    let merged = merge_sessions(current, synced, deleted);
+    let gone = current
+        .iter()
+        .filter(|s| !merged.iter().any(|m| m.id == s.id))
+        .map(|s| s.id.clone())
+        .collect();
+    (merged, gone)
+}
+
+/// Prompts are last-writer-wins by id on their last update, then deletions apply.
+pub fn merge_prompts(local: &[SavedPrompt], remote: &[SavedPrompt], deleted: &Tombstones) -> Vec<SavedPrompt> {
+    let mut merged: Vec<SavedPrompt> = local.to_vec();
+    for remote_prompt in remote {
+        match merged.iter_mut().find(|p| p.id == remote_prompt.id) {
+            Some(existing) if remote_prompt.modified_at() > existing.modified_at() => *existing = remote_prompt.clone(),
+            Some(_) => {}
+            None => merged.push(remote_prompt.clone()),
+        }
+    }
+    merged.retain(|p| !deleted.deletes_prompt(&p.id, p.modified_at()));
+    merged
+}
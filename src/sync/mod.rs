@@ -0,0 +1,346 @@
+//! Opt-in end-to-end encrypted sync of sessions and saved prompts.
+//!
+//! Everything is encrypted client-side with a key derived from a passphrase that never
+//! leaves the device; the remote (WebDAV or S3-compatible) stores a salt and one
+//! encrypted payload blob.
+
+pub mod crypto;
+pub mod merge;
+pub mod remote;
+
+use crate::ai::ChatSession;
+use crate::config::NetworkSettings;
+use crate::storage::{SavedPrompt, Tombstones};
+use crate::utils::credentials::{self, Credential};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+const SALT_KEY: &str = "ria-sync/salt";
+const PAYLOAD_KEY: &str = "ria-sync/payload.bin";
+/// 2 added deletions, 3 message deletions and edit times; older versions would drop them when
+/// writing back, so they refuse it.
+const PAYLOAD_VERSION: u32 = 3;
+/// Rounds of read, merge and conditional write before giving up on a busy remote.
+const MAX_ATTEMPTS: usize = 3;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SyncTarget {
+    #[default]
+    WebDav,
+    S3,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SyncSettings {
+    pub enabled: bool,
+    pub target: SyncTarget,
+    /// WebDAV collection URL, or S3 endpoint (e.g. https://s3.eu-west-1.amazonaws.com).
+    pub endpoint: String,
+    /// S3 only.
+    pub bucket: String,
+    /// S3 only; defaults to us-east-1.
+    pub region: String,
+    /// WebDAV username or S3 access key id.
+    pub username: String,
+    /// WebDAV password or S3 secret key as older versions stored it in config.json. It is
+    /// moved to the system credential store on startup and only written back if that fails.
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub secret: String,
+    /// Whether the password / secret key is in the system credential store.
+    pub secret_saved: bool,
+    /// Encryption passphrase; kept in memory only and never written to config.json.
+    #[serde(skip)]
+    pub passphrase: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SyncStatus {
+    Disabled,
+    /// Enabled, but no passphrase entered this run.
+    Locked,
+    Idle { last_sync: Option<chrono::DateTime<chrono::Utc>> },
+    Syncing,
+    Error(String),
+}
+
+impl SyncStatus {
+    pub fn from_settings(settings: &SyncSettings) -> Self {
+        if !settings.enabled {
+            SyncStatus::Disabled
+        } else if settings.passphrase.is_empty() {
+            SyncStatus::Locked
+        } else {
+            SyncStatus::Idle { last_sync: None }
+        }
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            SyncStatus::Disabled => "Sync off".to_string(),
+            SyncStatus::Locked => "🔒 Sync locked".to_string(),
+            SyncStatus::Idle { last_sync: Some(t) } => format!("☁ Synced {}", t.with_timezone(&chrono::Local).format("%H:%M")),
+            SyncStatus::Idle { last_sync: None } => "☁ Not synced yet".to_string(),
+            SyncStatus::Syncing => "⟳ Syncing…".to_string(),
+            SyncStatus::Error(_) => "⚠ Sync failed".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct SyncPayload {
+    version: u32,
+    sessions: Vec<ChatSession>,
+    prompts: Vec<SavedPrompt>,
+    /// Absent in payloads written before deletions were synced.
+    #[serde(default)]
+    deleted: Tombstones,
+}
+
+/// Result of a sync round: the merged state to apply locally.
+#[derive(Debug, Clone)]
+pub struct SyncOutcome {
+    pub sessions: Vec<ChatSession>,
+    pub prompts: Vec<SavedPrompt>,
+    /// Deletions from every device; local copies of these are to be deleted too.
+    pub deleted: Tombstones,
+}
+
+/// The WebDAV password or S3 secret key, from the credential store once it has been saved there.
+async fn resolve_secret(settings: &SyncSettings) -> Result<String> {
+    if !settings.secret_saved {
+        return Ok(settings.secret.clone());
+    }
+    tokio::task::spawn_blocking(|| credentials::get(Credential::SyncSecret))
+        .await?
+        .ok_or_else(|| anyhow!("The sync password is missing from the system credential store; enter it again in Settings"))
+}
+
+/// Pull, merge, and push. The merged state is returned so the caller can persist it locally.
+pub async fn sync_once(
+    settings: &SyncSettings,
+    network: &NetworkSettings,
+    local_sessions: Vec<ChatSession>,
+    local_prompts: Vec<SavedPrompt>,
+    local_deleted: Tombstones,
+) -> Result<SyncOutcome> {
+    if settings.passphrase.is_empty() {
+        return Err(anyhow!("Enter the sync passphrase first"));
+    }
+    let secret = resolve_secret(settings).await?;
+    let store = remote::RemoteStore::from_settings(settings, &secret, network.http_client()?)?;
+
+    let salt = match store.get(SALT_KEY).await? {
+        Some(salt) => salt.data,
+        None => {
+            let salt = crypto::random_salt().to_vec();
+            // Another device may have created it first; then its salt is the one to use
+            if store.put_if(SALT_KEY, salt.clone(), None).await? {
+                salt
+            } else {
+                store.get(SALT_KEY).await?.ok_or_else(|| anyhow!("Remote sync salt disappeared"))?.data
+            }
+        }
+    };
+    if salt.len() != crypto::SALT_LEN {
+        return Err(anyhow!("Remote sync salt is corrupted"));
+    }
+    let passphrase = settings.passphrase.clone();
+    let key = tokio::task::spawn_blocking(move || crypto::derive_key(&passphrase, &salt)).await?;
+
+    // The write only goes through if nobody wrote the payload since it was read; otherwise
+    // their data is read and merged again
+    for _ in 0..MAX_ATTEMPTS {
+        let remote_object = store.get(PAYLOAD_KEY).await?;
+        let remote_payload = match &remote_object {
+            Some(object) => {
+                let plain = crypto::decrypt(&key, &object.data)?;
+                serde_json::from_slice::<SyncPayload>(&plain)?
+            }
+            None => SyncPayload::default(),
+        };
+        if remote_payload.version > PAYLOAD_VERSION {
+            return Err(anyhow!("Remote data was written by a newer version of RIA"));
+        }
+
+        let mut deleted = local_deleted.clone();
+        deleted.merge(&remote_payload.deleted);
+        let outcome = SyncOutcome {
+            sessions: merge::merge_sessions(&local_sessions, &remote_payload.sessions, &deleted),
+            prompts: merge::merge_prompts(&local_prompts, &remote_payload.prompts, &deleted),
+            deleted,
+        };
+        let payload = SyncPayload {
+            version: PAYLOAD_VERSION,
+            sessions: outcome.sessions.clone(),
+            prompts: outcome.prompts.clone(),
+            deleted: outcome.deleted.clone(),
+        };
+        let blob = crypto::encrypt(&key, &serde_json::to_vec(&payload)?)?;
+        if store.put_if(PAYLOAD_KEY, blob, remote_object.as_ref()).await? {
+            return Ok(outcome);
+        }
+        tracing::info!("Sync payload changed on the remote while merging; merging again");
+    }
+    Err(anyhow!("Another device kept writing the sync data; try again in a moment"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::ChatMessage;
+    use crate::storage::Tombstone;
+    use chrono::{Duration, Utc};
+
+    fn message(id: &str, content: &str, at: chrono::DateTime<Utc>) -> ChatMessage {
+        ChatMessage { id: id.into(), timestamp: at, ..ChatMessage::user(content) }
+    }
+
+    fn session(id: &str, title: &str, updated: chrono::DateTime<Utc>, messages: Vec<ChatMessage>) -> ChatSession {
+        ChatSession { id: id.into(), title: title.into(), messages, created_at: updated, updated_at: updated, ..Default::default() }
+    }
+
+    fn prompt(id: &str, content: &str, created: chrono::DateTime<Utc>, updated: Option<chrono::DateTime<Utc>>) -> SavedPrompt {
+        SavedPrompt { id: id.into(), title: id.into(), content: content.into(), created_at: created, updated_at: updated }
+    }
+
+    #[test]
+    fn test_encrypt_roundtrip_and_wrong_key() {
+        let salt = crypto::random_salt();
+        let key = crypto::derive_key("correct horse", &salt);
+        let blob = crypto::encrypt(&key, b"secret data").unwrap();
+        assert_eq!(crypto::decrypt(&key, &blob).unwrap(), b"secret data");
+        let wrong = crypto::derive_key("wrong", &salt);
+        assert!(crypto::decrypt(&wrong, &blob).is_err());
+    }
+
+    #[test]
+    fn test_merge_sessions_unions_messages_and_resolves_conflicts_on_update_time() {
+        let t0 = Utc::now();
+        let local = vec![session("s1", "Local title", t0, vec![
+            message("m1", "hello", t0),
+            message("m2", "local copy", t0 + Duration::seconds(5)),
+        ])];
+        let remote = vec![
+            session("s1", "Remote title", t0 + Duration::seconds(10), vec![
+                ChatMessage { edited_at: Some(t0 + Duration::seconds(9)), ..message("m2", "edited on the other device", t0 + Duration::seconds(5)) },
+                message("m3", "from other device", t0 + Duration::seconds(8)),
+            ]),
+            session("s2", "Only remote", t0, vec![]),
+        ];
+
+        let merged = merge::merge_sessions(&local, &remote, &Tombstones::default());
+        assert_eq!(merged.len(), 2);
+        let s1 = merged.iter().find(|s| s.id == "s1").unwrap();
+        assert_eq!(s1.title, "Remote title");
+        let contents: Vec<_> = s1.messages.iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(contents, vec!["hello", "edited on the other device", "from other device"]);
+    }
+
+    #[test]
+    fn test_merge_keeps_a_message_edited_later_than_the_other_copy() {
+        let t0 = Utc::now();
+        // Edited here after the other device last touched it, but that device changed the chat since
+        let local = vec![session("s1", "Chat", t0 + Duration::seconds(3), vec![
+            ChatMessage { edited_at: Some(t0 + Duration::seconds(3)), ..message("m1", "rated here", t0) },
+        ])];
+        let remote = vec![session("s1", "Chat", t0 + Duration::seconds(10), vec![
+            message("m1", "original", t0),
+            message("m2", "unrelated later message", t0 + Duration::seconds(10)),
+        ])];
+        let merged = merge::merge_sessions(&local, &remote, &Tombstones::default());
+        let contents: Vec<_> = merged[0].messages.iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(contents, vec!["rated here", "unrelated later message"]);
+    }
+
+    #[test]
+    fn test_merge_drops_deleted_messages() {
+        let t0 = Utc::now();
+        let mut local = session("s1", "Chat", t0 + Duration::seconds(5), vec![message("m1", "kept", t0)]);
+        local.starred = vec!["m2".into()];
+        let remote = vec![session("s1", "Chat", t0, vec![
+            message("m1", "kept", t0),
+            message("m2", "deleted here", t0 + Duration::seconds(1)),
+        ])];
+        let deleted = Tombstones { messages: vec![Tombstone { id: "m2".into(), deleted_at: t0 + Duration::seconds(5) }], ..Default::default() };
+        let merged = merge::merge_sessions(&[local], &remote, &deleted);
+        let ids: Vec<_> = merged[0].messages.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(ids, vec!["m1"]);
+        assert!(merged[0].starred.is_empty());
+    }
+
+    #[test]
+    fn test_changes_made_while_syncing_survive_the_outcome() {
+        let t0 = Utc::now();
+        let snapshot = vec![session("s1", "Chat", t0, vec![message("m1", "hello", t0)])];
+        let remote = vec![session("s1", "Chat", t0 + Duration::seconds(2), vec![
+            message("m1", "hello", t0),
+            message("m2", "from other device", t0 + Duration::seconds(2)),
+        ])];
+        let outcome = merge::merge_sessions(&snapshot, &remote, &Tombstones::default());
+
+        // A reply finished and a new chat was started after the snapshot was taken
+        let mut current = snapshot.clone();
+        current[0].messages.push(message("m3", "reply finished during sync", t0 + Duration::seconds(4)));
+        current[0].updated_at = t0 + Duration::seconds(4);
+        current.push(session("s2", "Started during sync", t0 + Duration::seconds(3), vec![]));
+
+        let (sessions, gone) = merge::apply_synced_sessions(&current, &outcome, &Tombstones::default());
+        assert!(gone.is_empty());
+        let ids: Vec<_> = sessions.iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(ids, vec!["s1", "s2"]);
+        let contents: Vec<_> = sessions[0].messages.iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(contents, vec!["hello", "from other device", "reply finished during sync"]);
+
+        let deleted = Tombstones { sessions: vec![Tombstone { id: "s1".into(), deleted_at: t0 + Duration::seconds(5) }], ..Default::default() };
+        let (sessions, gone) = merge::apply_synced_sessions(&current, &outcome, &deleted);
+        assert_eq!(gone, std::collections::HashSet::from(["s1".to_string()]));
+        assert_eq!(sessions.len(), 1);
+    }
+
+    #[test]
+    fn test_merge_drops_deleted_items_unless_updated_since() {
+        let t0 = Utc::now();
+        let deleted = Tombstones {
+            sessions: vec![
+                Tombstone { id: "gone".into(), deleted_at: t0 + Duration::seconds(5) },
+                Tombstone { id: "edited".into(), deleted_at: t0 + Duration::seconds(5) },
+            ],
+            prompts: vec![Tombstone { id: "p1".into(), deleted_at: t0 + Duration::seconds(5) }],
+            ..Default::default()
+        };
+        let remote = vec![
+            session("gone", "Deleted here, still on the other device", t0, vec![]),
+            session("edited", "Changed after the delete", t0 + Duration::seconds(9), vec![]),
+        ];
+        let merged = merge::merge_sessions(&[], &remote, &deleted);
+        let ids: Vec<_> = merged.iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(ids, vec!["edited"]);
+
+        let local = vec![prompt("p1", "old", t0, None), prompt("p2", "local", t0, Some(t0 + Duration::seconds(7)))];
+        let remote = vec![prompt("p2", "remote", t0 + Duration::seconds(1), None)];
+        let merged = merge::merge_prompts(&local, &remote, &deleted);
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[0].content, "local");
+    }
+
+    #[test]
+    fn test_tombstones_merge_keeps_the_later_deletion() {
+        let t0 = Utc::now();
+        let mut local = Tombstones { sessions: vec![Tombstone { id: "s".into(), deleted_at: t0 }], ..Default::default() };
+        let remote = Tombstones {
+            sessions: vec![Tombstone { id: "s".into(), deleted_at: t0 + Duration::seconds(3) }],
+            prompts: vec![Tombstone { id: "p".into(), deleted_at: t0 }],
+            ..Default::default()
+        };
+        local.merge(&remote);
+        assert_eq!(local.sessions, remote.sessions);
+        assert_eq!(local.prompts, remote.prompts);
+    }
+
+    #[test]
+    fn test_payload_without_deletions_still_reads() {
+        let payload: SyncPayload = serde_json::from_str(r#"{"version":1,"sessions":[],"prompts":[]}"#).unwrap();
+        assert_eq!(payload.deleted, Tombstones::default());
+    }
+}
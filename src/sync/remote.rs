@@ -0,0 +1,259 @@
+//! Minimal object stores for sync: WebDAV (PUT/GET + basic auth) and S3-compatible
+//! endpoints (path-style URLs, AWS Signature V4).
+//!
+//! Writes are conditional on the object's ETag (`If-Match`, or `If-None-Match: *` for a new
+//! object), so two devices syncing at once can't overwrite each other's data unnoticed.
+
+use super::{SyncSettings, SyncTarget};
+use anyhow::{anyhow, Result};
+use hmac::{Hmac, Mac};
+use reqwest::{Client, Method, StatusCode};
+use sha2::{Digest, Sha256};
+
+pub enum RemoteStore {
+    WebDav(WebDavStore),
+    S3(S3Store),
+}
+
+impl RemoteStore {
+    /// `client` carries the proxy and certificates from the network settings.
+    pub fn from_settings(settings: &SyncSettings, secret: &str, client: Client) -> Result<Self> {
+        if settings.endpoint.trim().is_empty() {
+            return Err(anyhow!("Sync endpoint is not configured"));
+        }
+        let endpoint = settings.endpoint.trim_end_matches('/').to_string();
+        Ok(match settings.target {
+            SyncTarget::WebDav => RemoteStore::WebDav(WebDavStore {
+                client,
+                base_url: endpoint,
+                username: settings.username.clone(),
+                password: secret.to_string(),
+            }),
+            SyncTarget::S3 => RemoteStore::S3(S3Store {
+                client,
+                endpoint,
+                bucket: settings.bucket.clone(),
+                region: if settings.region.is_empty() { "us-east-1".to_string() } else { settings.region.clone() },
+                access_key: settings.username.clone(),
+                secret_key: secret.to_string(),
+            }),
+        })
+    }
+
+    /// Fetch an object; `None` if it does not exist yet.
+    pub async fn get(&self, key: &str) -> Result<Option<RemoteObject>> {
+        match self {
+            RemoteStore::WebDav(s) => s.get(key).await,
+            RemoteStore::S3(s) => s.get(key).await,
+        }
+    }
+
+    /// Write an object if it is still the version `expected` was read from (`None`: if it
+    /// still doesn't exist). Returns false when another device wrote it in the meantime.
+    pub async fn put_if(&self, key: &str, data: Vec<u8>, expected: Option<&RemoteObject>) -> Result<bool> {
+        let precondition = match expected {
+            None => Precondition::Absent,
+            Some(RemoteObject { etag: Some(etag), .. }) => Precondition::Matches(etag),
+            // The server doesn't version this object, so the write can't be made conditional
+            Some(RemoteObject { etag: None, .. }) => Precondition::Any,
+        };
+        match self {
+            RemoteStore::WebDav(s) => s.put(key, data, precondition).await,
+            RemoteStore::S3(s) => s.put(key, data, precondition).await,
+        }
+    }
+}
+
+/// An object's contents and the ETag it was served with.
+#[derive(Debug, Clone)]
+pub struct RemoteObject {
+    pub data: Vec<u8>,
+    pub etag: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Precondition<'a> {
+    Absent,
+    Matches(&'a str),
+    Any,
+}
+
+impl Precondition<'_> {
+    fn apply(self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        match self {
+            Precondition::Absent => request.header(reqwest::header::IF_NONE_MATCH, "*"),
+            Precondition::Matches(etag) => request.header(reqwest::header::IF_MATCH, etag),
+            Precondition::Any => request,
+        }
+    }
+}
+
+fn etag(resp: &reqwest::Response) -> Option<String> {
+    resp.headers().get(reqwest::header::ETAG).and_then(|v| v.to_str().ok()).map(str::to_string)
+}
+
+async fn read_response(resp: reqwest::Response) -> Result<Option<RemoteObject>> {
+    match resp.status() {
+        StatusCode::NOT_FOUND => Ok(None),
+        s if s.is_success() => {
+            let etag = etag(&resp);
+            Ok(Some(RemoteObject { data: resp.bytes().await?.to_vec(), etag }))
+        }
+        s => Err(anyhow!("Sync GET failed: HTTP {}", s)),
+    }
+}
+
+/// Whether a conditional PUT went through; 412 (and S3's 409 for a concurrent conditional
+/// write) means the object changed since it was read.
+fn write_response(resp: &reqwest::Response) -> Result<bool> {
+    match resp.status() {
+        StatusCode::PRECONDITION_FAILED | StatusCode::CONFLICT => Ok(false),
+        s if s.is_success() => Ok(true),
+        s => Err(anyhow!("Sync PUT failed: HTTP {}", s)),
+    }
+}
+
+pub struct WebDavStore {
+    client: Client,
+    base_url: String,
+    username: String,
+    password: String,
+}
+
+impl WebDavStore {
+    fn request(&self, method: Method, key: &str) -> reqwest::RequestBuilder {
+        let req = self.client.request(method, format!("{}/{}", self.base_url, key));
+        if self.username.is_empty() { req } else { req.basic_auth(&self.username, Some(&self.password)) }
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<RemoteObject>> {
+        read_response(self.request(Method::GET, key).send().await?).await
+    }
+
+    async fn put(&self, key: &str, data: Vec<u8>, precondition: Precondition<'_>) -> Result<bool> {
+        // Ensure the parent collection exists; servers answer 405 if it already does.
+        if let Some((dir, _)) = key.rsplit_once('/') {
+            let mkcol = Method::from_bytes(b"MKCOL").expect("valid method");
+            let _ = self.request(mkcol, dir).send().await;
+        }
+        let resp = precondition.apply(self.request(Method::PUT, key)).body(data).send().await?;
+        write_response(&resp)
+    }
+}
+
+pub struct S3Store {
+    client: Client,
+    endpoint: String,
+    bucket: String,
+    region: String,
+    access_key: String,
+    secret_key: String,
+}
+
+impl S3Store {
+    async fn get(&self, key: &str) -> Result<Option<RemoteObject>> {
+        read_response(self.signed(Method::GET, key, Vec::new())?.send().await?).await
+    }
+
+    async fn put(&self, key: &str, data: Vec<u8>, precondition: Precondition<'_>) -> Result<bool> {
+        // S3-compatible stores that ignore conditional headers on PUT would overwrite
+        // silently, so the current version is checked first as well
+        if !matches!(precondition, Precondition::Any) {
+            let resp = self.signed(Method::HEAD, key, Vec::new())?.send().await?;
+            let current = match resp.status() {
+                StatusCode::NOT_FOUND => None,
+                s if s.is_success() => Some(etag(&resp)),
+                s => return Err(anyhow!("Sync HEAD failed: HTTP {}", s)),
+            };
+            let unchanged = match (precondition, current) {
+                (Precondition::Absent, None) => true,
+                (Precondition::Matches(expected), Some(Some(etag))) => etag == expected,
+                _ => false,
+            };
+            if !unchanged {
+                return Ok(false);
+            }
+        }
+        let resp = precondition.apply(self.signed(Method::PUT, key, data)?).send().await?;
+        write_response(&resp)
+    }
+
+    fn signed(&self, method: Method, key: &str, body: Vec<u8>) -> Result<reqwest::RequestBuilder> {
+        let url = reqwest::Url::parse(&format!("{}/{}/{}", self.endpoint, self.bucket, key))?;
+        let host = match (url.host_str(), url.port()) {
+            (Some(h), Some(p)) => format!("{h}:{p}"),
+            (Some(h), None) => h.to_string(),
+            _ => return Err(anyhow!("Invalid S3 endpoint")),
+        };
+        let now = chrono::Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let headers = sigv4_headers(SigV4Request {
+            method: method.as_str(),
+            host: &host,
+            path: url.path(),
+            payload: &body,
+            amz_date: &amz_date,
+            region: &self.region,
+            access_key: &self.access_key,
+            secret_key: &self.secret_key,
+        });
+
+        let mut req = self.client.request(method, url).body(body);
+        for (name, value) in headers {
+            req = req.header(name, value);
+        }
+        Ok(req)
+    }
+}
+
+struct SigV4Request<'a> {
+    method: &'a str,
+    host: &'a str,
+    path: &'a str,
+    payload: &'a [u8],
+    amz_date: &'a str,
+    region: &'a str,
+    access_key: &'a str,
+    secret_key: &'a str,
+}
+
+fn hmac_sha256(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Headers (`x-amz-date`, `x-amz-content-sha256`, `authorization`) for an S3 request without query string.
+fn sigv4_headers(req: SigV4Request<'_>) -> Vec<(&'static str, String)> {
+    let date = &req.amz_date[..8];
+    let payload_hash = hex::encode(Sha256::digest(req.payload));
+    let signed_headers = "host;x-amz-content-sha256;x-amz-date";
+    let canonical_request = format!(
+        "{}\n{}\n\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\n{}\n{}",
+        req.method, req.path, req.host, payload_hash, req.amz_date, signed_headers, payload_hash
+    );
+    let scope = format!("{date}/{}/s3/aws4_request", req.region);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        req.amz_date,
+        scope,
+        hex::encode(Sha256::digest(canonical_request.as_bytes()))
+    );
+    let k_date = hmac_sha256(format!("AWS4{}", req.secret_key).as_bytes(), date);
+    let k_region = hmac_sha256(&k_date, req.region);
+    let k_service = hmac_sha256(&k_region, "s3");
+    let k_signing = hmac_sha256(&k_service, "aws4_request");
+    let signature = hex::encode(hmac_sha256(&k_signing, &string_to_sign));
+
+    vec![
+        ("x-amz-date", req.amz_date.to_string()),
+        ("x-amz-content-sha256", payload_hash),
+        (
+            "authorization",
+            format!(
+                "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+                req.access_key, scope, signed_headers, signature
+            ),
+        ),
+    ]
+}
@@ -0,0 +1,43 @@
+//! AccessKit metadata egui can't infer on its own.
+//!
+//! egui reports roles and names for its built-in widgets, but icon-only buttons are named
+//! after their emoji and custom-painted regions such as chat bubbles and toasts have no
+//! node at all. These helpers fill that in; they do nothing while no assistive technology
+//! is connected (egui only builds the AccessKit tree on request).
+
+use egui::accesskit::{Live, Rect, Role};
+
+fn bounds(rect: egui::Rect) -> Rect {
+    Rect {
+        x0: rect.min.x.into(),
+        y0: rect.min.y.into(),
+        x1: rect.max.x.into(),
+        y1: rect.max.y.into(),
+    }
+}
+
+/// Replace the accessible name of a widget, e.g. "Copy message" for a 📋 button.
+pub fn set_name(response: &egui::Response, name: &str) {
+    response.ctx.accesskit_node_builder(response.id, |node| node.set_name(name));
+}
+
+/// Expose a custom-painted region as a node with `role` and `name`.
+pub fn describe_region(ctx: &egui::Context, id: egui::Id, rect: egui::Rect, role: Role, name: &str) {
+    ctx.accesskit_node_builder(id, |node| {
+        node.set_role(role);
+        node.set_name(name);
+        node.set_bounds(bounds(rect));
+    });
+}
+
+/// Expose a chat message so screen readers can step through the conversation.
+pub fn describe_message(ui: &egui::Ui, id: egui::Id, rect: egui::Rect, author: &str, content: &str) {
+    describe_region(ui.ctx(), id, rect, Role::Article, &format!("{author}: {content}"));
+}
+
+/// Expose a toast as a live region so it is announced when it appears.
+pub fn describe_notification(ctx: &egui::Context, id: egui::Id, rect: egui::Rect, urgent: bool, message: &str) {
+    let role = if urgent { Role::Alert } else { Role::Status };
+    describe_region(ctx, id, rect, role, message);
+    ctx.accesskit_node_builder(id, |node| node.set_live(if urgent { Live::Assertive } else { Live::Polite }));
+}
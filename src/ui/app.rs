@@ -1,18 +1,58 @@
 use crate::ai::*;
-use crate::ai::inference::InferenceEngine;
+use crate::ai::inference::{InferenceEngine, ProviderId};
+use crate::ai::feedback::{self, Feedback, FeedbackTotals, Rating};
+use crate::ai::power::{self, PowerMode};
 use crate::ai::providers::OnnxProvider;
 use crate::ai::providers::LoadError;
-use crate::config::AppConfig;
+use crate::ai::hardware_profile::{EpOutcome, HardwareProfile, DEFAULT_FALLBACK_ORDER};
+use crate::ai::runtime::{self as ort_runtime, Compatibility, RuntimeReport};
+use crate::config::personas::PersonaLibrary;
+use crate::config::saver::ConfigSaver;
+use crate::config::{AppConfig, ConfigIssue};
+use crate::config::workspaces::{self, Workspaces, DEFAULT_WORKSPACE};
+use crate::storage::retention::{self, Prunable};
+use crate::storage::{open_storage, MessageHit, StorageBackend, Tombstone, Tombstones};
+use crate::storage::issues::{IssueLog, IssueSource};
+use crate::storage::stats::{UsageReport, UsageStats};
+use crate::sync::{SyncOutcome, SyncStatus};
 use crate::ui::models::ModelManagerUI;
+use crate::utils::crash;
+use crate::utils::credentials::{self, Credential};
 use crate::ui::components::SystemStatusComponent;
+use crate::ui::a11y;
+use crate::ui::events::{AppEvent, EpErrorKind, EventBus, LoadEvents, OnnxEpAttempt, OnnxLoadProgress};
+use crate::ui::export::{self, ExportFormat, HtmlTheme};
+use crate::ui::fonts;
+use crate::ui::keybindings::{self, Action};
+use crate::ui::notification_center::{NotificationCenter, NotificationLog};
+use crate::ui::quick_ask::{self, QuickAsk, QuickAskEvent};
+use crate::ui::runtime_manager::{InstallOutcome, RuntimeChoice, RuntimeManagerUI};
+use crate::ui::settings::{self, SettingsAction, SettingsTab};
+use crate::ui::slash_commands::{self, SlashCommand};
+use crate::ui::snippets::{PromptSnippet, SnippetForm, SnippetFormEvent};
+use crate::ui::chat_controller::{self, ChatController, Finished, Generation, ReplyKind};
+use crate::ui::streaming;
+use crate::ui::tags;
+use crate::ui::repaint::{Activity, RepaintScheduler};
+use crate::ui::theme::{self, Metrics, Palette};
+use crate::ui::translate::{self, Translation};
 use eframe::egui;
 use std::sync::Arc;
+use std::sync::atomic::Ordering;
 use tokio::sync::RwLock;
-use tokio::sync::mpsc;
-use tokio::sync::mpsc::error::TryRecvError;
 use std::time::Instant;
-use crate::ai::inference::BasicDemoProvider;
-use std::collections::VecDeque;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+// Views own their state and draw themselves; `update()` does the upkeep and routes to them.
+mod chat_view;
+mod diagnostics_view;
+mod models_view;
+mod settings_view;
+
+use chat_view::ChatView;
+use diagnostics_view::DiagnosticsView;
+use models_view::ModelsView;
+use settings_view::SettingsView;
 
 #[derive(Debug, Clone)]
 pub struct AppNotification {
@@ -25,7 +65,7 @@ pub struct AppNotification {
     pub actions: Vec<NotificationAction>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub enum NotificationType {
     Success,
     Error,
@@ -34,6 +74,38 @@ pub enum NotificationType {
     Loading,
 }
 
+impl NotificationType {
+    pub fn color(&self) -> egui::Color32 {
+        match self {
+            NotificationType::Success => egui::Color32::from_rgb(34, 139, 34),
+            NotificationType::Error => egui::Color32::from_rgb(220, 53, 69),
+            NotificationType::Warning => egui::Color32::from_rgb(255, 193, 7),
+            NotificationType::Info => egui::Color32::from_rgb(23, 162, 184),
+            NotificationType::Loading => egui::Color32::from_rgb(108, 117, 125),
+        }
+    }
+
+    pub fn icon(&self) -> &'static str {
+        match self {
+            NotificationType::Success => "✅",
+            NotificationType::Error => "❌",
+            NotificationType::Warning => "⚠️",
+            NotificationType::Info => "ℹ️",
+            NotificationType::Loading => "🔄",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            NotificationType::Success => "Success",
+            NotificationType::Error => "Errors",
+            NotificationType::Warning => "Warnings",
+            NotificationType::Info => "Info",
+            NotificationType::Loading => "Progress",
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct NotificationAction {
     pub label: String,
@@ -49,6 +121,11 @@ pub enum NotificationActionType {
     OpenSettings,
     AutoFixOnnx,
     OpenModels,
+    LoadImportedModel,
+    CancelRuntimeInstall,
+    RetryRuntimeInstall,
+    RestoreCrashedSession,
+    OpenCrashReport,
 }
 
 impl AppNotification {
@@ -88,23 +165,11 @@ impl AppNotification {
     }
 
     pub fn get_color(&self) -> egui::Color32 {
-        match self.notification_type {
-            NotificationType::Success => egui::Color32::from_rgb(34, 139, 34),
-            NotificationType::Error => egui::Color32::from_rgb(220, 53, 69),
-            NotificationType::Warning => egui::Color32::from_rgb(255, 193, 7),
-            NotificationType::Info => egui::Color32::from_rgb(23, 162, 184),
-            NotificationType::Loading => egui::Color32::from_rgb(108, 117, 125),
-        }
+        self.notification_type.color()
     }
 
     pub fn get_icon(&self) -> &'static str {
-        match self.notification_type {
-            NotificationType::Success => "✅",
-            NotificationType::Error => "❌",
-            NotificationType::Warning => "⚠️",
-            NotificationType::Info => "ℹ️",
-            NotificationType::Loading => "🔄",
-        }
+        self.notification_type.icon()
     }
 }
 
@@ -112,60 +177,214 @@ impl AppNotification {
 pub struct RiaApp {
     chat_sessions: Vec<ChatSession>,
     current_session: Option<usize>,
-    input_text: String,
+    chat_view: ChatView,
+    /// Reply length picked beside the send button; overrides `max_tokens` per request.
+    response_length: ResponseLength,
+    /// Format the next replies must follow (JSON, schema or regex); None for free text.
+    output_constraint: Option<constraint::OutputConstraint>,
+    /// Sampler tweaks from the chat header, by session id; not saved.
+    session_sampling: HashMap<String, GenerationOverrides>,
+    personas: PersonaLibrary,
+    /// Snippet from Quick Prompts waiting for its variables.
+    snippet_form: Option<SnippetForm>,
+    /// Prompt prefill progress reported by the loaded ONNX provider.
+    prefill_monitor: prefill::PrefillMonitor,
     inference_engine: Arc<RwLock<InferenceEngine>>,
     config: AppConfig,
-    show_settings: bool,
-    show_models: bool,
+    /// Workspaces and which one this window runs in
+    workspaces: Workspaces,
+    /// Workspace to restart into once the window has closed
+    switch_workspace_to: Option<String>,
+    /// Writes `config` in the background; `None` if the config directory is unknown.
+    config_saver: Option<ConfigSaver>,
+    settings_view: SettingsView,
+    models_view: ModelsView,
+    show_favorites: bool,
+    show_stats: bool,
+    /// When retention was last enforced automatically
+    last_prune: Option<Instant>,
+    /// Power mode models are loaded with, from the setting and the battery
+    power_mode: PowerMode,
+    /// When the battery was last read for the power mode
+    last_power_check: Option<Instant>,
     animation_time: f32,
     theme: Theme,
-    model_manager: ModelManagerUI,
+    appearance: theme::Appearance,
+    fonts: fonts::FontSettings,
     model_loaded: bool,
-    generating_response: bool,
-    // Streaming state
-    streaming_rx: Option<mpsc::Receiver<String>>,
-    streaming_buffer: String,
-    streaming_start: Option<Instant>,
+    /// Engine registration of the loaded ONNX model, replaced by the next load.
+    onnx_provider_id: Option<ProviderId>,
+    /// Model released after sitting idle, reloaded from this path on the next message
+    idle_unloaded: Option<String>,
+    /// An idle-unloaded model is loading again; messages wait for it in the queue
+    waking_model: bool,
+    /// When the model last generated or was loaded, for `idle_unload_minutes`
+    last_model_use: Instant,
+    /// What the loaded model reported about itself when it was loaded.
+    model_details: Option<HashMap<String, String>>,
+    /// The loaded model has an image input, so pasting images into the prompt is enabled
+    model_accepts_images: bool,
+    /// Images pasted into the prompt, sent with the next message
+    pending_images: Vec<vision::ImageAttachment>,
+    /// The message the next one in this chat replies to, as (session id, excerpt)
+    pending_reply: Option<(String, QuotedReply)>,
+    /// Rating comment being written, as (session id, message id, text)
+    feedback_comment: Option<(String, String, String)>,
+    /// Thumbnails of pending and visible attachments by id (`None` if the image didn't decode)
+    image_thumbnails: HashMap<String, Option<egui::TextureHandle>>,
+    /// Replies being generated and queued, at most `max_concurrent_generations` at once.
+    chat: ChatController,
+    /// Translations shown under messages, by message id; never part of the chat.
+    translations: HashMap<String, Translation>,
     system_status: SystemStatusComponent,
+    repaint: RepaintScheduler,
     notifications: VecDeque<AppNotification>,
     notification_id_counter: u64,
+    notification_center: NotificationCenter,
     // Accessibility and keyboard navigation
     focus_manager: FocusManager,
     keyboard_shortcuts_enabled: bool,
     // Async ONNX load pipeline
     onnx_load_task: Option<tokio::task::JoinHandle<()>>,
     onnx_load_cancel: Option<tokio::sync::oneshot::Sender<()>>,
-    onnx_progress_rx: Option<mpsc::UnboundedReceiver<OnnxLoadProgress>>,    
+    /// Id of the model load in progress; events of earlier loads are stale
+    onnx_load: Option<u64>,
+    next_load_id: u64,
     onnx_attempt_log: Vec<OnnxEpAttempt>,
-    show_diagnostics: bool,
-    // Channel to receive successfully loaded provider for engine hand-off
-    onnx_loaded_provider_rx: Option<mpsc::Receiver<Box<dyn AIProvider + Send + Sync>>>,
-    onnx_loaded_provider_tx: Option<mpsc::Sender<Box<dyn AIProvider + Send + Sync>>>,
+    /// Which execution providers have worked on this machine; orders load fallbacks.
+    hardware_profile: Arc<std::sync::Mutex<HardwareProfile>>,
+    /// ONNX Runtime found at startup, checked before every model load
+    ort_runtime: RuntimeReport,
+    /// `ort_runtime` has been detected; until then model loads wait in `deferred_onnx_load`
+    ort_runtime_detected: bool,
+    deferred_onnx_load: Option<(InferenceConfig, String)>,
+    /// Downloaded ONNX Runtime builds (Settings → ONNX Runtime and the auto-fix)
+    runtime_manager: RuntimeManagerUI,
+    /// Live progress toast of the running runtime download
+    runtime_install_notification: Option<u64>,
+    /// Version whose download failed last, for the Retry action
+    failed_runtime_install: Option<String>,
+    /// Set once ort has opened a runtime library; switching runtimes then needs a restart
+    ort_used: bool,
+    diagnostics_view: DiagnosticsView,
+    onnx_pending: Option<PendingOnnxLoad>,
+    // Persistence for sessions / prompts / memory
+    storage: Option<Box<dyn StorageBackend>>,
+    // Message and reply counts for the Stats window
+    usage_stats: Option<UsageStats>,
+    /// What the Stats window shows, read when it opens and again after new usage is recorded.
+    usage_report: Option<Result<UsageReport, String>>,
+    // Local tally of errors for Diagnostics, written only with `error_analytics` on
+    issue_log: Option<IssueLog>,
+    // Encrypted sync state
+    sync_status: SyncStatus,
+    syncing: bool,
+    // Drag-and-drop model imports copying on the blocking pool
+    imports_in_flight: usize,
+    // Progress and results of background work, drained once per frame
+    events: EventBus,
+    last_imported_model: Option<std::path::PathBuf>,
+    quick_ask: QuickAsk,
+    #[cfg(feature = "tray")]
+    tray: Option<crate::ui::tray::Tray>,
+    /// Minimized to the tray.
+    #[cfg(feature = "tray")]
+    window_minimized: bool,
+    #[cfg(feature = "tray")]
+    quit_requested: bool,
+    /// State left by a crash in the previous run, until restored or dismissed
+    crash_recovery: Option<crash::Recovery>,
+    /// Crash report shown in the "Crash report" window: (path, contents)
+    crash_report: Option<(std::path::PathBuf, String)>,
+    /// What was last copied into the crash snapshot, so it is only refreshed on change
+    crash_snapshot: CrashSnapshotKey,
+    /// Set once the window was asked to close; background work is wound down first
+    shutdown: Option<Shutdown>,
 }
 
-#[derive(Debug)]
-#[allow(dead_code)]
-enum OnnxLoadProgress {
-    Phase(String),
-    AttemptEP(String),
-    LoadError { ep: String, error: String },
-    Error(String),
-    Loaded { ep: String },
-    Failed(String),
-    Cancelled,
-    AttemptResult(OnnxEpAttempt),
+/// Closing the window while background work finishes.
+struct Shutdown {
+    started: Instant,
+    /// Nothing left to wait for (or the user chose to quit anyway); the next close goes through
+    ready: bool,
 }
 
-#[derive(Debug, Clone)]
-struct OnnxEpAttempt {
-    ep: String,
-    success: bool,
-    error_kind: Option<EpErrorKind>,
-    message: Option<String>,
+/// Longest the "finishing up" dialog waits for background work before closing anyway.
+const SHUTDOWN_GRACE: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// Identifies the state last handed to [`crash::update_snapshot`].
+#[derive(Default, PartialEq)]
+struct CrashSnapshotKey {
+    session: Option<(String, usize, chrono::DateTime<chrono::Utc>)>,
+    draft: String,
+    partial_reply_len: Option<usize>,
+}
+
+/// How often automatic pruning re-checks the retention limits.
+const PRUNE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+
+/// How often the battery is read when the power mode follows it.
+const POWER_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Generations longer than this send a notification when they finish in the background.
+const LONG_GENERATION_SECS: f64 = 10.0;
+
+/// What the UI should do once an async load finishes.
+struct PendingOnnxLoad {
+    model_name: String,
+    /// Value stored in `last_used_model` on success.
+    remember_as: String,
+    /// Auto-loads clear `last_used_model` on failure so a broken model isn't retried every start.
+    auto: bool,
+}
+
+/// Longest side of attachment thumbnails in messages and the input area.
+const THUMBNAIL_SIZE: u32 = 160;
+
+const LOAD_HEARTBEAT_INTERVAL: std::time::Duration = std::time::Duration::from_millis(500);
+
+fn resident_bytes(sys: &mut sysinfo::System, pid: sysinfo::Pid) -> u64 {
+    sys.refresh_processes(sysinfo::ProcessesToUpdate::Some(&[pid]), false);
+    sys.process(pid).map(|p| p.memory()).unwrap_or(0)
 }
 
-#[derive(Debug, Clone, Copy)]
-enum EpErrorKind { VersionMismatch, SessionBuild, ProviderInit, UnsupportedModel, Io, Unknown }
+/// Build the ORT session on the blocking pool, emitting heartbeats until it finishes.
+/// Returns `None` if the load was cancelled; the blocking build is left to finish and dropped.
+async fn load_on_blocking_pool(
+    mut provider: OnnxProvider,
+    ep: &str,
+    total_bytes: u64,
+    events: &LoadEvents,
+    cancel_rx: &mut tokio::sync::oneshot::Receiver<()>,
+) -> Option<Result<OnnxProvider, LoadError>> {
+    let mut handle = tokio::task::spawn_blocking(move || provider.load_model_classified().map(|_| provider));
+    let pid = sysinfo::get_current_pid().ok();
+    let mut sys = sysinfo::System::new();
+    let baseline = pid.map(|pid| resident_bytes(&mut sys, pid)).unwrap_or(0);
+    let started = Instant::now();
+    let mut ticker = tokio::time::interval(LOAD_HEARTBEAT_INTERVAL);
+    ticker.tick().await;
+    loop {
+        tokio::select! {
+            joined = &mut handle => {
+                return Some(joined.unwrap_or_else(|e| {
+                    let msg = if e.is_panic() { "ONNX Runtime panicked while building the session".to_string() } else { e.to_string() };
+                    Err(LoadError::SessionBuild(msg))
+                }));
+            }
+            _ = &mut *cancel_rx => return None,
+            _ = ticker.tick() => {
+                let bytes_mapped = pid.map(|pid| resident_bytes(&mut sys, pid).saturating_sub(baseline)).unwrap_or(0);
+                events.progress(OnnxLoadProgress::Heartbeat {
+                    ep: ep.to_string(),
+                    elapsed_secs: started.elapsed().as_secs_f32(),
+                    bytes_mapped,
+                    total_bytes,
+                });
+            }
+        }
+    }
+}
 
 fn map_load_error(le: &LoadError) -> (EpErrorKind, String) {
     use EpErrorKind as EK; use LoadError as LE;
@@ -178,6 +397,8 @@ fn map_load_error(le: &LoadError) -> (EpErrorKind, String) {
         LE::Panic(m) => (EK::SessionBuild, format!("Panic: {m}")),
         LE::ExecutionProviderRegistration(m) => (EK::SessionBuild, m.clone()),
         LE::InferenceProbeFailed(m) => (EK::SessionBuild, m.clone()),
+        LE::IntegrityCheckFailed(m) => (EK::Io, format!("Integrity: {m}")),
+        LE::CudaDriver(m) | LE::CudaToolkit(m) | LE::Cudnn(m) => (EK::GpuSetup, m.clone()),
         LE::Unknown(m) => (EK::Unknown, m.clone()),
     }
 }
@@ -195,69 +416,51 @@ pub enum FocusableElement {
     Notification(u64), // Notification ID
 }
 
+/// Ties the app's focusable elements to the egui widgets drawn for them.
+///
+/// Focus itself lives in egui's `Memory`, so Tab/Shift+Tab, arrow keys, Enter/Space
+/// activation and screen-reader focus requests all go through egui; this only tracks which
+/// element the focused widget belongs to and forwards programmatic focus requests.
 pub struct FocusManager {
     current_focus: Option<FocusableElement>,
-    focus_ring: Vec<FocusableElement>,
-    focus_index: usize,
-    tab_navigation: bool,
+    /// Widgets registered this frame, in drawing (and therefore Tab) order.
+    widgets: Vec<(FocusableElement, egui::Id)>,
+    /// Widgets registered last frame, used to resolve egui's focused id.
+    previous_widgets: Vec<(FocusableElement, egui::Id)>,
+    pending_focus: Option<FocusableElement>,
 }
 
 impl FocusManager {
     fn new() -> Self {
         Self {
             current_focus: None,
-            focus_ring: Vec::new(),
-            focus_index: 0,
-            tab_navigation: false,
-        }
-    }
-    
-    fn update_focus_ring(&mut self, elements: Vec<FocusableElement>) {
-        self.focus_ring = elements;
-        if self.focus_index >= self.focus_ring.len() && !self.focus_ring.is_empty() {
-            self.focus_index = 0;
+            widgets: Vec::new(),
+            previous_widgets: Vec::new(),
+            pending_focus: None,
         }
     }
-    
-    fn next_focus(&mut self) {
-        if !self.focus_ring.is_empty() {
-            self.focus_index = (self.focus_index + 1) % self.focus_ring.len();
-            self.current_focus = Some(self.focus_ring[self.focus_index].clone());
-            self.tab_navigation = true;
-        }
+
+    /// Resolve which element egui has focused. Call once at the start of each frame.
+    fn begin_frame(&mut self, ctx: &egui::Context) {
+        self.previous_widgets = std::mem::take(&mut self.widgets);
+        let focused = ctx.memory(|m| m.focused());
+        self.current_focus = focused.and_then(|id| {
+            self.previous_widgets.iter().find(|(_, widget)| *widget == id).map(|(element, _)| element.clone())
+        });
     }
-    
-    fn previous_focus(&mut self) {
-        if !self.focus_ring.is_empty() {
-            self.focus_index = if self.focus_index > 0 {
-                self.focus_index - 1
-            } else {
-                self.focus_ring.len() - 1
-            };
-            self.current_focus = Some(self.focus_ring[self.focus_index].clone());
-            self.tab_navigation = true;
+
+    /// Record the widget drawn for `element`, moving focus to it if that was requested.
+    fn register(&mut self, element: FocusableElement, response: &egui::Response) {
+        if self.pending_focus.as_ref() == Some(&element) {
+            response.request_focus();
+            self.pending_focus = None;
         }
+        self.widgets.push((element, response.id));
     }
-    
+
+    /// Focus `element` the next time its widget is drawn.
     fn set_focus(&mut self, element: FocusableElement) {
-        self.current_focus = Some(element.clone());
-        if let Some(index) = self.focus_ring.iter().position(|e| *e == element) {
-            self.focus_index = index;
-        }
-        self.tab_navigation = false;
-    }
-    
-    fn clear_focus(&mut self) {
-        self.current_focus = None;
-        self.tab_navigation = false;
-    }
-    
-    fn is_focused(&self, element: &FocusableElement) -> bool {
-        self.current_focus.as_ref() == Some(element)
-    }
-    
-    fn activate_current(&self) -> bool {
-        self.current_focus.is_some() && self.tab_navigation
+        self.pending_focus = Some(element);
     }
 }
 
@@ -276,60 +479,166 @@ impl Default for Theme {
 
 impl RiaApp {
     pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
-        // Configure fonts
-        let fonts = egui::FontDefinitions::default();
-        
-        // For now, use default fonts. In a real implementation, you would:
-        // 1. Include Inter font or other modern fonts
-        // 2. Load them from assets directory
-        // fonts.font_data.insert("Inter".to_owned(), egui::FontData::from_static(include_bytes!("../../assets/Inter-Regular.ttf")));
-
-        cc.egui_ctx.set_fonts(fonts);
-
-        // Set dark theme
-        cc.egui_ctx.set_visuals(egui::Visuals::dark());
-
         // Load configuration
         let config = AppConfig::load().unwrap_or_else(|_| {
             tracing::warn!("Failed to load config, using defaults");
             AppConfig::default()
         });
 
+        // Custom fonts plus CJK/emoji fallbacks
+        let (fonts, font_warnings) = fonts::build_font_definitions(&config.fonts);
+        cc.egui_ctx.set_fonts(fonts);
+
+        theme::apply(&cc.egui_ctx, &config.theme, &config.appearance);
+
         // Create directories if they don't exist
         if let Err(e) = config.ensure_directories() {
             tracing::error!("Failed to create directories: {}", e);
         }
 
+        let prefill_monitor = prefill::PrefillMonitor::default();
+        let mut engine = InferenceEngine::new();
+        engine.set_response_cache(config.ai_config.response_cache);
+        let inference_engine = Arc::new(RwLock::new(engine));
+        let chat = ChatController::new(inference_engine.clone(), prefill_monitor.clone(), config.max_concurrent_generations);
+
         let mut app = Self {
             chat_sessions: Vec::new(),
             current_session: None,
-            input_text: String::new(),
-            inference_engine: Arc::new(RwLock::new(InferenceEngine::new())),
+            chat_view: ChatView::default(),
+            response_length: ResponseLength::default(),
+            output_constraint: None,
+            session_sampling: HashMap::new(),
+            personas: PersonaLibrary::load(&AppConfig::personas_path()),
+            snippet_form: None,
+            prefill_monitor: prefill_monitor.clone(),
+            inference_engine: inference_engine.clone(),
             config: config.clone(),
-            show_settings: false,
-            show_models: false,
+            workspaces: Workspaces::load(),
+            switch_workspace_to: None,
+            config_saver: ConfigSaver::spawn().map_err(|e| tracing::warn!("Config will be saved synchronously: {}", e)).ok(),
+            settings_view: SettingsView::default(),
+            models_view: ModelsView::new(&config),
+            show_favorites: false,
+            show_stats: false,
+            last_prune: None,
+            power_mode: config.power.mode,
+            last_power_check: None,
             animation_time: 0.0,
             theme: config.theme.clone(),
-            model_manager: ModelManagerUI::new(),
+            appearance: config.appearance.clone(),
+            fonts: config.fonts.clone(),
             model_loaded: false,
-            generating_response: false,
-            streaming_rx: None,
-            streaming_buffer: String::new(),
-            streaming_start: None,
+            onnx_provider_id: None,
+            idle_unloaded: None,
+            waking_model: false,
+            last_model_use: Instant::now(),
+            model_details: None,
+            model_accepts_images: false,
+            pending_images: Vec::new(),
+            pending_reply: None,
+            feedback_comment: None,
+            image_thumbnails: HashMap::new(),
+            chat,
+            translations: HashMap::new(),
             system_status: SystemStatusComponent::new(),
+            repaint: RepaintScheduler::default(),
             notifications: VecDeque::new(),
             notification_id_counter: 0,
+            notification_center: NotificationCenter::new(NotificationLog::open(AppConfig::log_dir().join("notifications.log"))),
             focus_manager: FocusManager::new(),
             keyboard_shortcuts_enabled: true,
             onnx_load_task: None,
             onnx_load_cancel: None,
-            onnx_progress_rx: None,
+            onnx_load: None,
+            next_load_id: 0,
             onnx_attempt_log: Vec::new(),
-            show_diagnostics: false,
-            onnx_loaded_provider_rx: None,
-            onnx_loaded_provider_tx: None,
+            hardware_profile: Arc::new(std::sync::Mutex::new(HardwareProfile::load(&AppConfig::hardware_profile_path()))),
+            ort_runtime: RuntimeReport::detecting(),
+            ort_runtime_detected: false,
+            deferred_onnx_load: None,
+            runtime_manager: RuntimeManagerUI::new(AppConfig::runtime_dir(), config.network.clone()),
+            runtime_install_notification: None,
+            failed_runtime_install: None,
+            ort_used: false,
+            diagnostics_view: DiagnosticsView::default(),
+            onnx_pending: None,
+            storage: None,
+            usage_stats: None,
+            usage_report: None,
+            issue_log: None,
+            sync_status: SyncStatus::from_settings(&config.sync),
+            syncing: false,
+            imports_in_flight: 0,
+            events: EventBus::new(),
+            last_imported_model: None,
+            quick_ask: QuickAsk::default(),
+            #[cfg(feature = "tray")]
+            tray: None,
+            #[cfg(feature = "tray")]
+            window_minimized: false,
+            #[cfg(feature = "tray")]
+            quit_requested: false,
+            crash_recovery: None,
+            crash_report: None,
+            crash_snapshot: CrashSnapshotKey::default(),
+            shutdown: None,
         };
 
+        app.models_view.manager.set_catalog(config.catalog.clone());
+        if app.workspaces.active() != DEFAULT_WORKSPACE {
+            cc.egui_ctx.send_viewport_cmd(egui::ViewportCommand::Title(format!("RIA AI Chat — {}", app.workspaces.active())));
+        }
+
+        #[cfg(feature = "tray")]
+        if config.tray.enabled {
+            match crate::ui::tray::Tray::new(&cc.egui_ctx) {
+                Ok(tray) => app.tray = Some(tray),
+                Err(e) => tracing::warn!("System tray unavailable: {}", e),
+            }
+        }
+
+        // Open the configured storage backend and restore previous sessions
+        match open_storage(config.storage_backend, &config.storage_dir()) {
+            Ok(storage) => {
+                match storage.load_sessions() {
+                    Ok(sessions) => app.chat_sessions = sessions,
+                    Err(e) => tracing::error!("Failed to load sessions from {} storage: {}", storage.name(), e),
+                }
+                app.storage = Some(storage);
+            }
+            Err(e) => tracing::error!("Failed to open {} storage: {}", config.storage_backend.label(), e),
+        }
+        match UsageStats::open(config.storage_dir().join("stats.db")) {
+            Ok(stats) => app.usage_stats = Some(stats),
+            Err(e) => tracing::warn!("Usage statistics unavailable: {}", e),
+        }
+        match IssueLog::open(config.storage_dir().join("issues.db")) {
+            Ok(log) => app.issue_log = Some(log),
+            Err(e) => tracing::warn!("Error analytics unavailable: {}", e),
+        }
+
+        for warning in font_warnings {
+            app.show_warning(warning);
+        }
+        let config_issues = config.issues();
+        if !config_issues.is_empty() {
+            let messages: Vec<&str> = config_issues.iter().map(|i| i.message.as_str()).collect();
+            app.show_warning(format!("Some settings need attention: {}. They're highlighted in Settings.", messages.join("; ")));
+            // Invalid values would only fail once a reply is requested
+            app.settings_view.open |= config_issues.iter().any(ConfigIssue::blocks_save);
+        }
+        if !config.sync.secret.is_empty() {
+            app.move_sync_secret();
+        }
+        app.detect_onnx_runtime(None);
+        // Both can block for seconds on first use; settle them before the settings ask
+        crate::utils::openvino::probe_in_background();
+        credentials::preload(&[Credential::HuggingFaceToken]);
+        if let Some(recovery) = crash::take_recovery(&config.storage_dir()) {
+            app.offer_crash_recovery(recovery);
+        }
+
         // Auto-load last used model if configured
         if config.auto_load_last_model {
             if let Some(ref last_model) = config.last_used_model {
@@ -348,9 +657,8 @@ impl RiaApp {
     // Scan models directory for most recently modified .onnx file
     fn find_latest_local_model(&self) -> Option<String> {
         use std::fs; use std::time::SystemTime;
-        let dir = &self.config.models_directory;
-        let entries = fs::read_dir(dir).ok()?;
         let mut best: Option<(SystemTime, String)> = None;
+        let entries = self.config.model_directories.iter().filter_map(|dir| fs::read_dir(dir).ok()).flatten();
         for e in entries.flatten() {
             let path = e.path();
             if path.extension().and_then(|s| s.to_str()).unwrap_or("") == "onnx" {
@@ -364,643 +672,1188 @@ impl RiaApp {
     }
 
     fn create_new_session(&mut self) {
+        self.create_session_with_persona(None);
+    }
+
+    /// Start a chat, optionally from a persona; the persona's preferred model is loaded if a
+    /// different one is in use.
+    fn create_session_with_persona(&mut self, persona: Option<&str>) {
+        let persona = persona.and_then(|name| self.personas.get(name)).cloned();
         let session = ChatSession {
-            id: uuid::Uuid::new_v4().to_string(),
             title: format!("Chat {}", self.chat_sessions.len() + 1),
-            messages: Vec::new(),
-            created_at: chrono::Utc::now(),
-            updated_at: chrono::Utc::now(),
+            persona: persona.as_ref().map(|p| p.name.clone()),
+            ..Default::default()
         };
         
         self.chat_sessions.push(session);
         self.current_session = Some(self.chat_sessions.len() - 1);
+        self.focus_manager.set_focus(FocusableElement::InputArea);
+
+        if let Some(model) = persona.and_then(|p| p.preferred_model).filter(|m| !m.trim().is_empty()) {
+            let in_use = self.model_loaded && self.config.last_used_model.as_deref() == Some(model.as_str());
+            if !in_use && self.onnx_pending.is_none() {
+                self.load_model_file(&model, false);
+            }
+        }
     }
 
-    fn send_message(&mut self, _ctx: &egui::Context) {
-        if self.input_text.trim().is_empty() || self.generating_response {
-            return;
+    /// The chat's own system prompt, or else its persona's.
+    fn system_message(&self, session_idx: usize) -> Option<ChatMessage> {
+        match self.chat_sessions[session_idx].system_prompt.as_deref().map(str::trim).filter(|p| !p.is_empty()) {
+            Some(prompt) => Some(ChatMessage::system(prompt)),
+            None => self.session_persona(session_idx)?.system_message(),
         }
+    }
 
-        if self.current_session.is_none() {
-            self.create_new_session();
+    /// Run a command typed as `/name args` in the input.
+    fn run_slash_command(&mut self, command: SlashCommand, ctx: &egui::Context) {
+        match command {
+            SlashCommand::New => self.create_new_session(),
+            SlashCommand::Model(query) => match self.models_view.manager.find_models(&query).as_slice() {
+                [] => self.show_warning(format!("No model matching '{query}'; open 🧠 Models to download or add one")),
+                [model] => {
+                    let path = model.path.to_string_lossy().to_string();
+                    self.load_model_file(&path, false);
+                }
+                several => self.show_warning(format!(
+                    "'{query}' matches {}; be more specific",
+                    several.iter().map(|m| m.name.as_str()).collect::<Vec<_>>().join(", ")
+                )),
+            },
+            SlashCommand::Temperature(temperature) => {
+                let session_idx = self.current_or_new_session();
+                let session_id = self.chat_sessions[session_idx].id.clone();
+                self.session_sampling.entry(session_id).or_default().temperature = Some(temperature);
+                self.show_info(format!("Temperature for this chat set to {temperature}"));
+            }
+            SlashCommand::System(prompt) => {
+                let session_idx = self.current_or_new_session();
+                let cleared = prompt.is_empty();
+                self.chat_sessions[session_idx].system_prompt = (!cleared).then_some(prompt);
+                self.persist_session(session_idx);
+                self.show_info(if cleared { "System prompt cleared" } else { "System prompt set for this chat" });
+            }
+            SlashCommand::Export(format) => match self.current_session {
+                Some(session_idx) => self.export_session(session_idx, format, ctx),
+                None => self.show_warning("Open a chat to export it"),
+            },
+            SlashCommand::Summarize { replace } => match self.current_session {
+                Some(session_idx) => self.summarize_session(session_idx, replace),
+                None => self.show_warning("Open a chat to summarize it"),
+            },
+            SlashCommand::File(path) => self.add_file_to_chat(&path),
         }
+    }
 
-        let session_idx = self.current_session.unwrap();
-        let user_message = ChatMessage {
-            id: uuid::Uuid::new_v4().to_string(),
-            content: self.input_text.clone(),
-            role: MessageRole::User,
-            timestamp: chrono::Utc::now(),
-            model_used: None,
-            inference_time: None,
+    /// Add a text file to the open chat as a tool result; the next message can ask about it.
+    fn add_file_to_chat(&mut self, path: &str) {
+        const MAX_FILE_BYTES: u64 = 8 * 1024 * 1024;
+        let path = std::path::Path::new(path);
+        match std::fs::metadata(path) {
+            Ok(meta) if meta.len() > MAX_FILE_BYTES => {
+                return self.show_warning(format!("{} is over {} MB; add a smaller file", path.display(), MAX_FILE_BYTES / (1024 * 1024)));
+            }
+            Ok(_) => {}
+            Err(e) => return self.show_error(format!("Couldn't read {}: {e}", path.display())),
+        }
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::InvalidData => {
+                return self.show_warning(format!("{} isn't a text file", path.display()));
+            }
+            Err(e) => return self.show_error(format!("Couldn't read {}: {e}", path.display())),
         };
+        let session_idx = self.current_or_new_session();
+        let session = &mut self.chat_sessions[session_idx];
+        session.messages.push(ChatMessage::tool(format!("Contents of {}:\n\n{contents}", path.display())));
+        session.updated_at = chrono::Utc::now();
+        self.persist_session(session_idx);
+        self.chat_view.scroll.jump_to_bottom();
+        self.show_info(format!("Added {} to the chat", path.display()));
+    }
 
-        self.chat_sessions[session_idx].messages.push(user_message.clone());
-        let _user_input = self.input_text.clone();
-        self.input_text.clear();
-        self.generating_response = true;
-        self.show_loading("Generating response...");
-
-        // Kick off streaming generation via inference engine. If no provider is loaded,
-        // the engine will fall back to a demo provider.
-        let messages_snapshot = self.chat_sessions[session_idx].messages.clone();
-        let engine_arc = self.inference_engine.clone();
-        let (ui_tx, ui_rx) = mpsc::channel(64);
-        self.streaming_rx = Some(ui_rx);
-        self.streaming_buffer.clear();
-        self.streaming_start = Some(Instant::now());
-
-        // Start a background task to stream chunks
-        tokio::spawn(async move {
-            let mut engine = engine_arc.write().await;
+    fn current_or_new_session(&mut self) -> usize {
+        if self.current_session.is_none() {
+            self.create_new_session();
+        }
+        self.current_session.expect("a session was just created")
+    }
 
-            // Ensure there is at least one provider; if not, add a demo provider
-            if !engine.has_active_provider() {
-                let idx = engine.add_provider_sync(Box::new(BasicDemoProvider));
-                let _ = engine.set_active_provider_sync(idx);
+    /// Save a chat as Markdown or HTML in the downloads folder and copy the file's path. The
+    /// HTML page takes the app's light or dark theme.
+    fn export_session(&mut self, session_idx: usize, format: ExportFormat, ctx: &egui::Context) {
+        let session = &self.chat_sessions[session_idx];
+        let dir = dirs::download_dir().or_else(dirs::home_dir).unwrap_or_else(|| std::path::PathBuf::from("."));
+        let safe_title: String = session.title.chars().map(|c| if c.is_alphanumeric() || c == '-' || c == ' ' { c } else { '_' }).collect();
+        let path = dir.join(format!("{} {}.{}", safe_title.trim(), chrono::Local::now().format("%Y-%m-%d %H%M"), format.extension()));
+        let theme = match self.config.theme {
+            Theme::Dark => HtmlTheme::Dark,
+            Theme::Light => HtmlTheme::Light,
+            Theme::System => HtmlTheme::Auto,
+        };
+        match crate::utils::files::write_atomic(&path, &export::render(session, format, theme)) {
+            Ok(()) => {
+                ctx.output_mut(|o| o.copied_text = path.display().to_string());
+                self.show_success(format!("Chat exported to {} (path copied)", path.display()));
             }
+            Err(e) => self.show_error(format!("Failed to export the chat: {e}")),
+        }
+    }
 
-            // Reasonable defaults: ~16 chars per chunk, 20ms delay
-            let chunk_chars = 16usize;
-            let delay_ms = 20u64;
+    /// Set or clear a reply's rating. A poor rating asks what was wrong.
+    fn rate_message(&mut self, session_idx: usize, message_id: &str, rating: Rating) {
+        let session = &mut self.chat_sessions[session_idx];
+        let Some(message) = session.messages.iter_mut().find(|m| m.id == message_id) else { return };
+        message.feedback = match message.feedback.take() {
+            Some(previous) if previous.rating == rating => None,
+            Some(previous) => Some(Feedback { rating, ..previous }),
+            None => Some(Feedback::new(rating)),
+        };
+        message.mark_edited();
+        let ask_why = rating == Rating::Poor && message.feedback.as_ref().is_some_and(|f| f.comment.is_empty());
+        session.updated_at = chrono::Utc::now();
+        self.persist_session(session_idx);
+        if ask_why {
+            self.edit_feedback_comment(session_idx, message_id);
+        }
+    }
 
-            match engine.generate_response_stream(&messages_snapshot, chunk_chars, delay_ms) {
-                Ok(mut rx) => {
-                    while let Some(chunk) = rx.recv().await {
-                        if ui_tx.send(chunk).await.is_err() {
-                            break;
-                        }
+    fn edit_feedback_comment(&mut self, session_idx: usize, message_id: &str) {
+        let session = &self.chat_sessions[session_idx];
+        let comment = session.messages.iter().find(|m| m.id == message_id).and_then(|m| m.feedback.as_ref()).map(|f| f.comment.clone());
+        if let Some(comment) = comment {
+            self.feedback_comment = Some((session.id.clone(), message_id.to_string(), comment));
+        }
+    }
+
+    /// Small window for the optional comment on a rating.
+    fn render_feedback_comment(&mut self, ctx: &egui::Context) {
+        let Some((_, _, comment)) = self.feedback_comment.as_mut() else { return };
+        let mut open = true;
+        let mut save = false;
+        let mut close = false;
+        egui::Window::new("💬 Rating comment")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label("What was good or wrong about this reply? (optional)");
+                ui.add(egui::TextEdit::multiline(comment).desired_rows(3).hint_text("e.g. made up an API that doesn't exist"));
+                ui.horizontal(|ui| {
+                    save = ui.button("Save").clicked();
+                    close = ui.button("Skip").clicked();
+                });
+            });
+        if save {
+            if let Some((session_id, message_id, comment)) = self.feedback_comment.take() {
+                let Some(session_idx) = self.chat_sessions.iter().position(|s| s.id == session_id) else { return };
+                let session = &mut self.chat_sessions[session_idx];
+                let message = session.messages.iter_mut().find(|m| m.id == message_id);
+                if let Some(message) = message.filter(|m| m.feedback.is_some()) {
+                    if let Some(feedback) = message.feedback.as_mut() {
+                        feedback.comment = comment.trim().to_string();
                     }
-                }
-                Err(e) => {
-                    tracing::error!("Streaming generation failed: {}", e);
-                    // Cannot call self methods from async context
+                    message.mark_edited();
+                    session.updated_at = chrono::Utc::now();
+                    self.persist_session(session_idx);
                 }
             }
-            // Drop tx to signal completion
-        });
+        } else if close || !open {
+            self.feedback_comment = None;
+        }
+    }
 
-        // Display typing indicator; final message will be appended when streaming ends
+    /// Write every rated reply with its prompt as JSONL to the downloads folder.
+    fn export_feedback(&mut self, ctx: &egui::Context) {
+        let pairs = feedback::rated_pairs(&self.chat_sessions);
+        if pairs.is_empty() {
+            self.show_info("No rated replies to export yet. Rate replies with 👍 or 👎 first.");
+            return;
+        }
+        let dir = dirs::download_dir().or_else(dirs::home_dir).unwrap_or_else(|| std::path::PathBuf::from("."));
+        let path = dir.join(format!("ria-feedback {}.jsonl", chrono::Local::now().format("%Y-%m-%d %H%M")));
+        match feedback::to_jsonl(&pairs).and_then(|jsonl| crate::utils::files::write_atomic(&path, &jsonl)) {
+            Ok(()) => {
+                ctx.output_mut(|o| o.copied_text = path.display().to_string());
+                self.show_success(format!("Exported {} rated replies to {} (path copied)", pairs.len(), path.display()));
+            }
+            Err(e) => self.show_error(format!("Failed to export feedback: {e}")),
+        }
     }
 
-    fn render_sidebar(&mut self, _ctx: &egui::Context, ui: &mut egui::Ui) {
-        ui.with_layout(egui::Layout::top_down(egui::Align::LEFT), |ui| {
-            // Header with app title
-            ui.add_space(20.0);
-            ui.horizontal(|ui| {
-                ui.add_space(20.0);
-                ui.label(
-                    egui::RichText::new("🤖 RIA AI Chat")
-                        .size(24.0)
-                        .strong()
-                        .color(egui::Color32::from_rgb(100, 200, 255))
-                );
-            });
-            
-            ui.add_space(30.0);
+    /// The persona the chat was started with, if it still exists.
+    fn session_persona(&self, session_idx: usize) -> Option<&crate::config::personas::Persona> {
+        self.personas.get(self.chat_sessions[session_idx].persona.as_deref()?)
+    }
 
-            // New Chat button
-            ui.horizontal(|ui| {
-                ui.add_space(20.0);
-                if ui.add_sized([200.0, 40.0], egui::Button::new("➕ New Chat")).clicked() {
-                    self.create_new_session();
+    /// Starred messages from all sessions, with a jump to each one in its conversation.
+    fn render_favorites(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_favorites;
+        let mut jump = None;
+        let mut unstar = None;
+        egui::Window::new("⭐ Favorites")
+            .open(&mut open)
+            .default_size([420.0, 380.0])
+            .resizable(true)
+            .show(ctx, |ui| {
+                let starred = crate::ai::starred_messages(&self.chat_sessions);
+                if starred.is_empty() {
+                    ui.label(egui::RichText::new("No starred messages yet. Use ☆ on a message to keep it here.").weak());
+                    return;
                 }
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    for (session_idx, message) in starred {
+                        let session = &self.chat_sessions[session_idx];
+                        ui.group(|ui| {
+                            ui.horizontal(|ui| {
+                                let local = message.timestamp.with_timezone(&chrono::Local);
+                                ui.label(egui::RichText::new(&session.title).strong());
+                                ui.label(egui::RichText::new(local.format("%Y-%m-%d %H:%M").to_string()).small().weak());
+                                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                                    if ui.small_button("⭐").on_hover_text("Remove from favorites").clicked() {
+                                        unstar = Some((session_idx, message.id.clone()));
+                                    }
+                                    if ui.small_button("➡ Go to").on_hover_text("Open the chat at this message").clicked() {
+                                        jump = Some((session_idx, message.id.clone()));
+                                    }
+                                });
+                            });
+                            let preview: String = message.content.chars().take(240).collect();
+                            let ellipsis = if message.content.chars().count() > 240 { "…" } else { "" };
+                            ui.label(format!("{preview}{ellipsis}"));
+                        });
+                    }
+                });
             });
+        self.show_favorites = open;
+        if let Some((session_idx, message_id)) = unstar {
+            self.chat_sessions[session_idx].toggle_star(&message_id);
+            self.chat_sessions[session_idx].updated_at = chrono::Utc::now();
+            self.persist_session(session_idx);
+        }
+        if let Some((session_idx, message_id)) = jump {
+            self.current_session = Some(session_idx);
+            self.chat_view.scroll_to_message = Some(message_id);
+        }
+    }
+
+    /// Fork the session at `message_id` into a new session and switch to it.
+    fn branch_session_at(&mut self, session_idx: usize, message_id: &str) {
+        let Some(branch) = self.chat_sessions.get(session_idx).and_then(|s| s.branch_at(message_id)) else { return };
+        let title = branch.title.clone();
+        self.chat_sessions.push(branch);
+        let idx = self.chat_sessions.len() - 1;
+        self.current_session = Some(idx);
+        self.persist_session(idx);
+        self.focus_manager.set_focus(FocusableElement::InputArea);
+        self.show_success(format!("Started '{title}'; the original chat is unchanged"));
+    }
 
-            ui.add_space(20.0);
-            ui.separator();
-            ui.add_space(20.0);
+    fn start_sync(&mut self) {
+        if self.syncing {
+            return;
+        }
+        let settings = self.config.sync.clone();
+        let network = self.config.network.clone();
+        let sessions = self.chat_sessions.clone();
+        let prompts = self.storage.as_ref()
+            .and_then(|s| s.load_prompts().ok())
+            .unwrap_or_default();
+        let deleted = self.storage.as_ref()
+            .and_then(|s| s.load_tombstones().ok())
+            .unwrap_or_default();
+        let events = self.events.sender();
+        tokio::spawn(async move {
+            let sync = tokio::spawn(async move { crate::sync::sync_once(&settings, &network, sessions, prompts, deleted).await });
+            let result = sync.await.unwrap_or_else(|e| Err(anyhow::anyhow!("Sync task ended unexpectedly: {e}")));
+            events.send(AppEvent::SyncFinished(result));
+        });
+        self.syncing = true;
+        self.sync_status = SyncStatus::Syncing;
+    }
 
-            // Chat sessions list
-            ui.horizontal(|ui| {
-                ui.add_space(20.0);
-                ui.label(egui::RichText::new("Recent Chats").size(16.0).strong());
-            });
+    /// Move a sync password that older versions saved in config.json to the credential store,
+    /// off the UI thread.
+    fn move_sync_secret(&self) {
+        let secret = self.config.sync.secret.clone();
+        let events = self.events.sender();
+        std::thread::spawn(move || {
+            events.send(AppEvent::SyncSecretMoved(credentials::set(Credential::SyncSecret, &secret)));
+        });
+    }
 
-            ui.add_space(10.0);
+    /// Reflect settings changes (enabled / passphrase entered) while no sync runs.
+    fn update_sync_status(&mut self) {
+        if self.syncing {
+            return;
+        }
+        let expected = SyncStatus::from_settings(&self.config.sync);
+        if matches!(expected, SyncStatus::Disabled | SyncStatus::Locked)
+            || matches!(self.sync_status, SyncStatus::Disabled | SyncStatus::Locked)
+        {
+            self.sync_status = expected;
+        }
+    }
 
-            for (i, session) in self.chat_sessions.iter().enumerate() {
-                ui.horizontal(|ui| {
-                    ui.add_space(20.0);
-                    let selected = self.current_session == Some(i);
-                    
-                    let button = egui::Button::new(&session.title)
-                        .fill(if selected { 
-                            egui::Color32::from_rgb(60, 60, 80) 
-                        } else { 
-                            egui::Color32::TRANSPARENT 
-                        });
-                        
-                    if ui.add_sized([200.0, 30.0], button).clicked() {
-                        self.current_session = Some(i);
+    /// Act on everything background work reported since the last frame.
+    fn handle_events(&mut self, ctx: &egui::Context) {
+        for event in self.events.drain() {
+            match event {
+                AppEvent::OnnxLoad { load, progress } => self.on_onnx_load_progress(load, progress),
+                AppEvent::ProviderLoaded { load, provider } => self.activate_loaded_provider(load, provider),
+                AppEvent::SyncFinished(result) => self.finish_sync(result),
+                AppEvent::SyncSecretMoved(result) => match result {
+                    Ok(()) => {
+                        self.config.sync.secret.clear();
+                        self.config.sync.secret_saved = true;
+                        self.save_config();
                     }
-                });
+                    Err(e) => tracing::warn!("Sync password left in config.json: {:#}", e),
+                },
+                AppEvent::ModelImported { source, result } => self.finish_model_import(source, result),
+                AppEvent::RuntimeDetected { report, switched_to } => self.finish_runtime_detection(report, switched_to),
+                AppEvent::DiagnosticsExported(result) => self.finish_diagnostics_export(ctx, result),
             }
+        }
+    }
 
-            // Bottom controls
-            ui.with_layout(egui::Layout::bottom_up(egui::Align::LEFT), |ui| {
-                ui.add_space(20.0);
-                
-                // Model status with enhanced information
-                ui.vertical(|ui| {
-                    ui.horizontal(|ui| {
-                        ui.add_space(20.0);
-                        if self.model_loaded {
-                            ui.colored_label(egui::Color32::GREEN, "🟢 AI Model Active");
-                        } else {
-                            ui.colored_label(egui::Color32::from_rgb(255, 193, 7), "⚡ Demo Mode");
-                        }
-                    });
-                    
-                    // Additional status info
-                    if !self.model_loaded {
-                        ui.add_space(2.0);
-                        ui.horizontal(|ui| {
-                            ui.add_space(20.0);
-                            ui.label(
-                                egui::RichText::new("Intelligent responses active")
-                                    .size(11.0)
-                                    .color(egui::Color32::GRAY)
-                            );
-                        });
-                        
-                        // Show hint about ONNX Runtime if there were loading errors
-                        if self.notifications.iter().any(|n| n.message.contains("ONNX Runtime") || n.message.contains("version")) {
-                            ui.add_space(2.0);
-                            ui.horizontal(|ui| {
-                                ui.add_space(20.0);
-                                ui.hyperlink_to(
-                                    "🔧 Fix ONNX Runtime",
-                                    format!("file:///{}", std::env::current_dir().unwrap_or_default().join("FIX_NPU.md").to_string_lossy())
-                                );
-                            });
-                        }
-                    } else {
-                        // Show current model info when loaded
-                        if let Some(model_name) = &self.config.last_used_model {
-                            ui.add_space(2.0);
-                            ui.horizontal(|ui| {
-                                ui.add_space(20.0);
-                                let display_name = std::path::Path::new(model_name)
-                                    .file_name()
-                                    .and_then(|n| n.to_str())
-                                    .unwrap_or("Unknown")
-                                    .trim_end_matches(".onnx");
-                                ui.label(
-                                    egui::RichText::new(format!("Using: {}", display_name))
-                                        .size(11.0)
-                                        .color(egui::Color32::GRAY)
-                                );
-                            });
-                        }
+    fn finish_sync(&mut self, result: anyhow::Result<SyncOutcome>) {
+        self.syncing = false;
+        match result {
+            Ok(outcome) => {
+                // Deletions made here while the sync ran aren't in the outcome yet
+                let mut deleted = outcome.deleted.clone();
+                if let Some(local) = self.storage.as_ref().and_then(|s| s.load_tombstones().ok()) {
+                    deleted.merge(&local);
+                }
+                let (sessions, gone) = crate::sync::merge::apply_synced_sessions(&self.chat_sessions, &outcome.sessions, &deleted);
+                // Chats deleted on another device go the way local deletes do
+                self.remove_sessions(&gone);
+                let current_id = self.current_session.and_then(|i| self.chat_sessions.get(i)).map(|s| s.id.clone());
+                self.chat_sessions = sessions;
+                self.current_session = current_id.and_then(|id| self.chat_sessions.iter().position(|s| s.id == id));
+                if let Some(storage) = self.storage.as_mut() {
+                    for session in &self.chat_sessions {
+                        if let Err(e) = storage.save_session(session) { tracing::error!("Failed to store synced session: {}", e); }
                     }
-                });
-                
-                ui.add_space(5.0);
-                
-                ui.horizontal(|ui| {
-                    ui.add_space(20.0);
-                    if ui.add_sized([90.0, 35.0], egui::Button::new("⚙️ Settings")).clicked() {
-                        self.show_settings = !self.show_settings;
+                    for prompt in &outcome.prompts {
+                        if let Err(e) = storage.save_prompt(prompt) { tracing::error!("Failed to store synced prompt: {}", e); }
                     }
-                    if ui.add_sized([90.0, 35.0], egui::Button::new("🧠 Models")).clicked() {
-                        self.show_models = !self.show_models;
+                    for tombstone in &outcome.deleted.prompts {
+                        if !outcome.prompts.iter().any(|p| p.id == tombstone.id) {
+                            if let Err(e) = storage.delete_prompt(&tombstone.id) { tracing::error!("Failed to delete synced prompt: {}", e); }
+                        }
                     }
-                });
-            });
-        });
+                    if let Err(e) = storage.record_tombstones(&outcome.deleted) {
+                        tracing::error!("Failed to store synced deletions: {}", e);
+                    }
+                }
+                self.sync_status = SyncStatus::Idle { last_sync: Some(chrono::Utc::now()) };
+            }
+            Err(e) => {
+                tracing::warn!("Sync failed: {}", e);
+                self.show_error(format!("Sync failed: {e}"));
+                self.sync_status = SyncStatus::Error(e.to_string());
+            }
+        }
     }
 
-    fn render_chat_area(&mut self, ctx: &egui::Context, ui: &mut egui::Ui) {
-        if let Some(session_idx) = self.current_session {
-            let session = &self.chat_sessions[session_idx];
-            
-            // Messages area
-            egui::ScrollArea::vertical()
-                .stick_to_bottom(true)
-                .show(ui, |ui| {
-                    ui.add_space(20.0);
-                    
-                    for message in &session.messages {
-                        self.render_message(ui, message);
-                        ui.add_space(10.0);
-                    }
+    fn persist_session(&mut self, session_idx: usize) {
+        if !self.config.auto_save {
+            return;
+        }
+        self.chat_view.message_hits = None;
+        if let (Some(storage), Some(session)) = (self.storage.as_mut(), self.chat_sessions.get(session_idx)) {
+            if let Err(e) = storage.save_session(session) {
+                tracing::error!("Failed to save session {}: {}", session.id, e);
+            }
+        }
+    }
 
-                    // Streaming preview bubble while generating
-                    if self.generating_response && !self.streaming_buffer.is_empty() {
-                        let preview = ChatMessage {
-                            id: "streaming-preview".to_string(),
-                            content: self.streaming_buffer.clone(),
-                            role: MessageRole::Assistant,
-                            timestamp: chrono::Utc::now(),
-                            model_used: Some("…typing".to_string()),
-                            inference_time: None,
-                        };
-                        self.render_message(ui, &preview);
-                        ui.add_space(10.0);
-                    }
-                });
+    /// Archive or restore the chats with these ids.
+    fn set_archived(&mut self, ids: &HashSet<String>, archived: bool) {
+        let now = chrono::Utc::now();
+        for idx in 0..self.chat_sessions.len() {
+            let session = &mut self.chat_sessions[idx];
+            if ids.contains(&session.id) && session.archived != archived {
+                session.archived = archived;
+                session.updated_at = now;
+                self.persist_session(idx);
+            }
+        }
+    }
 
-            // Input area
-            ui.with_layout(egui::Layout::bottom_up(egui::Align::LEFT), |ui| {
-                ui.add_space(20.0);
-                
-                self.render_enhanced_input_area(ui, ctx);
-                
-                ui.add_space(20.0);
-            });
-        } else {
-            // Welcome screen
-            ui.centered_and_justified(|ui| {
-                ui.vertical_centered(|ui| {
-                    ui.label(
-                        egui::RichText::new("Welcome to RIA AI Chat! 🚀")
-                            .size(32.0)
-                            .strong()
-                            .color(egui::Color32::from_rgb(100, 200, 255))
-                    );
-                    
-                    ui.add_space(20.0);
-                    
-                    ui.label(
-                        egui::RichText::new("Start a new conversation to begin chatting with AI")
-                            .size(16.0)
-                            .color(egui::Color32::GRAY)
-                    );
-                    
-                    ui.add_space(30.0);
-                    
-                    if ui.add_sized([200.0, 50.0], egui::Button::new("🆕 Start New Chat")).clicked() {
-                        self.create_new_session();
-                    }
-                });
-            });
+    /// Delete the chats with these ids, stopping their replies and dropping anything queued for
+    /// them. The deletions are remembered so sync removes the chats on other devices too.
+    fn delete_sessions(&mut self, ids: &HashSet<String>) {
+        if let Some(storage) = self.storage.as_mut() {
+            let deleted = Tombstones { sessions: ids.iter().map(Tombstone::now).collect(), ..Default::default() };
+            if let Err(e) = storage.record_tombstones(&deleted) {
+                tracing::error!("Failed to record deleted sessions: {}", e);
+            }
         }
+        self.remove_sessions(ids);
     }
 
-    fn render_enhanced_input_area(&mut self, ui: &mut egui::Ui, ctx: &egui::Context) {
-        let max_chars = 2000;
-        let current_chars = self.input_text.len();
-        let word_count = if self.input_text.trim().is_empty() {
-            0
-        } else {
-            self.input_text.trim().split_whitespace().count()
-        };
-        
-        // Input area container with professional styling
-        egui::Frame::none()
-            .fill(egui::Color32::from_rgb(40, 44, 52))
-            .stroke(egui::Stroke::new(1.0, egui::Color32::from_rgb(60, 66, 74)))
-            .rounding(12.0)
-            .inner_margin(16.0)
-            .show(ui, |ui| {
-                ui.vertical(|ui| {
-                    // Header with formatting controls and stats
-                    ui.horizontal(|ui| {
-                        // Formatting controls
-                        ui.horizontal(|ui| {
-                            ui.label(
-                                egui::RichText::new("✏️")
-                                    .size(16.0)
-                                    .color(egui::Color32::from_rgb(100, 200, 255))
-                            );
-                            
-                            ui.add_space(8.0);
-                            
-                            // Suggested prompts dropdown
-                            egui::ComboBox::from_id_salt("prompt_suggestions")
-                                .selected_text("💡 Quick Prompts")
-                                .width(120.0)
-                                .show_ui(ui, |ui| {
-                                    if ui.selectable_label(false, "📝 Explain this concept").clicked() {
-                                        self.input_text = "Can you explain ".to_string();
-                                    }
-                                    if ui.selectable_label(false, "🔍 Analyze this code").clicked() {
-                                        self.input_text = "Please analyze this code: ".to_string();
-                                    }
-                                    if ui.selectable_label(false, "🐛 Debug this issue").clicked() {
-                                        self.input_text = "Help me debug this problem: ".to_string();
-                                    }
-                                    if ui.selectable_label(false, "💡 Brainstorm ideas").clicked() {
-                                        self.input_text = "I need ideas for ".to_string();
-                                    }
-                                    if ui.selectable_label(false, "📚 Learn about").clicked() {
-                                        self.input_text = "Teach me about ".to_string();
-                                    }
-                                });
-                        });
-                        
-                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                            // Character and word count
-                            let count_color = if current_chars > (max_chars as f32 * 0.9) as usize {
-                                egui::Color32::from_rgb(255, 107, 107) // Red when near limit
-                            } else if current_chars > (max_chars as f32 * 0.7) as usize {
-                                egui::Color32::from_rgb(255, 193, 7) // Yellow when approaching limit
-                            } else {
-                                egui::Color32::GRAY
-                            };
-                            
-                            ui.label(
-                                egui::RichText::new(format!("{}/{} chars | {} words", current_chars, max_chars, word_count))
-                                    .size(11.0)
-                                    .color(count_color)
-                            );
-                        });
-                    });
-                    
-                    ui.add_space(8.0);
-                    
-                    // Main input area
-                    ui.horizontal(|ui| {
-                        // Multi-line text input with accessibility
-                        let available_width = ui.available_width() - 100.0;
-                        
-                        // Add focus indicator for input area
-                        if self.focus_manager.is_focused(&FocusableElement::InputArea) {
-                            self.render_focus_indicator(ui, &FocusableElement::InputArea);
-                        }
-                        
-                        let text_edit_response = ui.add_sized(
-                            [available_width, 60.0],
-                            egui::TextEdit::multiline(&mut self.input_text)
-                                .hint_text(if self.generating_response { 
-                                    "🔄 Generating response..." 
-                                } else { 
-                                    "💬 Type your message here...\n✨ Use Ctrl+Enter to send, Tab to navigate, Ctrl+H for help"
-                                })
-                                .font(egui::TextStyle::Body)
-                                .desired_width(available_width)
-                                .lock_focus(self.generating_response)
-                        );
-                        
-                        // Handle click focus
-                        if text_edit_response.clicked() {
-                            self.focus_manager.set_focus(FocusableElement::InputArea);
-                        }
-                        
-                        // Handle keyboard shortcuts
-                        if text_edit_response.lost_focus() && ui.input(|i| {
-                            i.key_pressed(egui::Key::Enter) && i.modifiers.ctrl
-                        }) && !self.generating_response {
-                            self.send_message(ctx);
-                        }
-                        
-                        ui.add_space(12.0);
-                        
-                        // Send button area
-                        ui.vertical(|ui| {
-                            ui.add_space(8.0);
-                            
-                            let send_enabled = !self.input_text.trim().is_empty() && 
-                                             !self.generating_response && 
-                                             current_chars <= max_chars;
-                            
-                            // Enhanced send button
-                            let send_button_text = if self.generating_response {
-                                "⏳ Generating..."
-                            } else if current_chars > max_chars {
-                                "❌ Too long"
-                            } else if self.input_text.trim().is_empty() {
-                                "✏️ Type first"
-                            } else {
-                                "🚀 Send"
-                            };
-                            
-                            let button_color = if send_enabled {
-                                egui::Color32::from_rgb(0, 123, 255)
-                            } else {
-                                egui::Color32::from_rgb(108, 117, 125)
-                            };
-                            
-                            let send_button = egui::Button::new(send_button_text)
-                                .fill(button_color)
-                                .rounding(8.0);
-                            
-                            // Add focus indicator for send button
-                            if self.focus_manager.is_focused(&FocusableElement::SendButton) {
-                                self.render_focus_indicator(ui, &FocusableElement::SendButton);
-                            }
-                            
-                            let send_response = ui.add_sized([80.0, 36.0], send_button)
-                                .on_hover_text("Send message (Ctrl+Enter or click)")
-                                .on_disabled_hover_text("Type a message first or wait for response to complete");
-                            
-                            if send_response.clicked() && send_enabled {
-                                self.send_message(ctx);
-                            }
-                            
-                            // Handle focus activation
-                            if self.focus_manager.is_focused(&FocusableElement::SendButton) && 
-                               self.focus_manager.activate_current() && send_enabled {
-                                self.send_message(ctx);
-                            }
-                            
-                            // Handle click focus
-                            if send_response.clicked() {
-                                self.focus_manager.set_focus(FocusableElement::SendButton);
-                            }
-                            
-                            // Clear button
-                            if !self.input_text.is_empty() && !self.generating_response {
-                                ui.add_space(4.0);
-                                let clear_button = egui::Button::new("🗑️ Clear")
-                                    .fill(egui::Color32::from_rgb(220, 53, 69))
-                                    .rounding(8.0);
-                                
-                                // Add focus indicator for clear button
-                                if self.focus_manager.is_focused(&FocusableElement::ClearButton) {
-                                    self.render_focus_indicator(ui, &FocusableElement::ClearButton);
-                                }
-                                
-                                let clear_response = ui.add_sized([80.0, 28.0], clear_button)
-                                    .on_hover_text("Clear input text (Ctrl+D)");
-                                
-                                if clear_response.clicked() {
-                                    self.input_text.clear();
-                                }
-                                
-                                // Handle focus activation
-                                if self.focus_manager.is_focused(&FocusableElement::ClearButton) && 
-                                   self.focus_manager.activate_current() {
-                                    self.input_text.clear();
-                                }
-                                
-                                // Handle click focus
-                                if clear_response.clicked() {
-                                    self.focus_manager.set_focus(FocusableElement::ClearButton);
-                                }
-                            }
-                        });
-                    });
-                    
-                    // Footer with helpful tips and accessibility info
-                    if !self.generating_response {
-                        ui.add_space(6.0);
-                        ui.separator();
-                        ui.add_space(4.0);
-                        
-                        ui.horizontal(|ui| {
-                            // Main tips
-                            ui.label(
-                                egui::RichText::new("💡 Tips: Ctrl+Enter to send • Ctrl+H for help • Tab to navigate • Ctrl+M for models")
-                                    .size(10.0)
-                                    .color(egui::Color32::from_rgb(140, 140, 140))
-                            );
-                            
-                            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                                // Focus indicator
-                                if let Some(focused) = &self.focus_manager.current_focus {
-                                    let focus_text = match focused {
-                                        FocusableElement::InputArea => "📝 Input focused",
-                                        FocusableElement::SendButton => "🚀 Send button focused", 
-                                        FocusableElement::ClearButton => "🗑️ Clear button focused",
-                                        FocusableElement::NewChatButton => "🆕 New chat focused",
-                                        FocusableElement::SettingsButton => "⚙️ Settings focused",
-                                        FocusableElement::ModelsButton => "🧠 Models focused",
-                                        FocusableElement::Notification(id) => &format!("🔔 Notification #{} focused", id),
-                                        _ => "Focus active",
-                                    };
-                                    
-                                    ui.label(
-                                        egui::RichText::new(focus_text)
-                                            .size(10.0)
-                                            .color(egui::Color32::from_rgb(100, 150, 255))
-                                            .strong()
-                                    );
-                                }
-                            });
-                        });
-                    }
-                });
-            });
-        
-        ui.add_space(20.0);
+    /// Drop the chats with these ids here and from storage.
+    fn remove_sessions(&mut self, ids: &HashSet<String>) {
+        for id in ids {
+            self.chat.forget(id);
+            if let Some(storage) = self.storage.as_mut() {
+                if let Err(e) = storage.delete_session(id) {
+                    tracing::error!("Failed to delete session {}: {}", id, e);
+                }
+            }
+        }
+        if self.chat.active() == 0 {
+            self.clear_loading_notifications();
+        }
+        if self.pending_reply.as_ref().is_some_and(|(id, _)| ids.contains(id)) {
+            self.pending_reply = None;
+        }
+        if self.feedback_comment.as_ref().is_some_and(|(id, _, _)| ids.contains(id)) {
+            self.feedback_comment = None;
+        }
+        let current_id = self.current_session.and_then(|i| self.chat_sessions.get(i)).map(|s| s.id.clone());
+        self.chat_sessions.retain(|s| !ids.contains(&s.id));
+        self.current_session = current_id.and_then(|id| self.chat_sessions.iter().position(|s| s.id == id));
     }
 
-    // Keyboard navigation and accessibility methods
-    fn handle_keyboard_shortcuts(&mut self, ctx: &egui::Context) {
-        if !self.keyboard_shortcuts_enabled {
+    /// Chats the retention settings would delete now. The open chat and chats with a reply in
+    /// progress or queued are kept, like pinned ones.
+    fn prune_plan(&self) -> Vec<Prunable> {
+        let mut keep: HashSet<String> = self.chat.generating().cloned().collect();
+        keep.extend(self.chat.queued().iter().map(|(id, _)| id.clone()));
+        keep.extend(self.current_session.and_then(|i| self.chat_sessions.get(i)).map(|s| s.id.clone()));
+        retention::plan(&self.chat_sessions, &self.config.retention, self.config.max_chat_history, &keep, chrono::Utc::now())
+    }
+
+    /// Retention limits this device's history only, so pruned chats aren't recorded as deleted
+    /// and stay on other devices. With sync on they would come straight back from the remote
+    /// copy, so pruning is off then (see [`Self::enforce_retention`] and the Chat History settings).
+    fn prune_sessions(&mut self, plan: &[Prunable]) {
+        let ids: HashSet<String> = plan.iter().map(|p| p.session_id.clone()).collect();
+        self.remove_sessions(&ids);
+        tracing::info!("Pruned {} chats from history", ids.len());
+    }
+
+    /// Apply the retention limits on startup and every [`PRUNE_INTERVAL`] when automatic pruning
+    /// is on and sync is off.
+    fn enforce_retention(&mut self) {
+        if !self.config.retention.automatic || self.config.sync.enabled || self.syncing || self.last_prune.is_some_and(|t| t.elapsed() < PRUNE_INTERVAL) {
             return;
         }
-        
-        ctx.input(|input| {
-            // Global shortcuts (Ctrl + key combinations)
-            if input.modifiers.ctrl {
-                if input.key_pressed(egui::Key::N) && !self.show_models && !self.show_settings {
-                    // Ctrl+N: New chat
-                    self.create_new_session();
-                    self.show_success("New chat session created");
+        self.last_prune = Some(Instant::now());
+        let plan = self.prune_plan();
+        if !plan.is_empty() {
+            self.prune_sessions(&plan);
+            let count = plan.len();
+            self.show_info(format!("Removed {count} old chat{} from history (Settings → General → Chat History)", if count == 1 { "" } else { "s" }));
+        }
+    }
+
+    /// Recompute the power mode, reading the battery every [`POWER_CHECK_INTERVAL`] when the
+    /// setting follows it. A change applies from the next model load.
+    fn update_power_mode(&mut self) {
+        let settings = &self.config.power;
+        let mode = if settings.follow_battery {
+            if self.last_power_check.is_some_and(|t| t.elapsed() < POWER_CHECK_INTERVAL) {
+                return;
+            }
+            self.last_power_check = Some(Instant::now());
+            settings.effective_mode(power::battery_status())
+        } else {
+            self.last_power_check = None;
+            settings.mode
+        };
+        if mode != self.power_mode {
+            self.power_mode = mode;
+            if self.model_loaded {
+                self.show_info(format!("Power mode: {}. Reload the model to apply it.", mode.label()));
+            }
+        }
+    }
+
+    /// The inference settings to load a model with: the configured ones, adjusted for the power mode.
+    fn load_config(&self, model_path: &str) -> InferenceConfig {
+        let mut config = self.config.ai_config.clone();
+        config.model_path = model_path.to_string();
+        self.power_mode.apply(&mut config);
+        config
+    }
+
+    /// Tell the user about a crash in the previous run and offer to bring back what was lost.
+    fn offer_crash_recovery(&mut self, recovery: crash::Recovery) {
+        let mut actions = Vec::new();
+        if !recovery.is_empty() {
+            actions.push(NotificationAction { label: "Restore chat".to_string(), action_type: NotificationActionType::RestoreCrashedSession });
+        }
+        if recovery.crash_report.as_ref().is_some_and(|p| p.is_file()) {
+            actions.push(NotificationAction { label: "Open crash report".to_string(), action_type: NotificationActionType::OpenCrashReport });
+        }
+        actions.push(NotificationAction { label: "Dismiss".to_string(), action_type: NotificationActionType::Dismiss });
+        let message = if recovery.is_empty() {
+            "RIA closed unexpectedly last time.".to_string()
+        } else {
+            "RIA closed unexpectedly last time. Your open chat and unsent message were saved.".to_string()
+        };
+        self.add_notification(AppNotification::new(message, NotificationType::Warning).with_duration(0.0).with_actions(actions));
+        self.crash_recovery = Some(recovery);
+    }
+
+    /// Put the chat, streamed reply and draft saved by the crash handler back.
+    fn restore_crashed_session(&mut self) {
+        let Some(recovery) = self.crash_recovery.as_mut() else { return };
+        let draft = std::mem::take(&mut recovery.draft);
+        if let Some(mut session) = recovery.session.take() {
+            if let Some(partial) = recovery.partial_reply.take().filter(|p| !p.trim().is_empty()) {
+                session.messages.push(ChatMessage {
+                    id: uuid::Uuid::new_v4().to_string(),
+                    content: format!("{partial}\n\n*(interrupted)*"),
+                    role: MessageRole::Assistant,
+                    timestamp: chrono::Utc::now(),
+                    model_used: None,
+                    inference_time: None,
+                    images: Vec::new(),
+                    trace: None,
+                    reply_to: None,
+                    feedback: None,
+                    edited_at: None,
+                });
+                session.updated_at = chrono::Utc::now();
+            }
+            let idx = match self.chat_sessions.iter().position(|s| s.id == session.id) {
+                Some(idx) => {
+                    self.chat_sessions[idx] = session;
+                    idx
                 }
-                if input.key_pressed(egui::Key::M) {
-                    // Ctrl+M: Toggle models window
-                    self.show_models = !self.show_models;
-                    if self.show_models {
-                        self.show_settings = false; // Close settings if open
-                    }
+                None => {
+                    self.chat_sessions.push(session);
+                    self.chat_sessions.len() - 1
                 }
-                if input.key_pressed(egui::Key::Comma) {
-                    // Ctrl+, : Toggle settings window
-                    self.show_settings = !self.show_settings;
-                    if self.show_settings {
-                        self.show_models = false; // Close models if open
+            };
+            self.current_session = Some(idx);
+            self.persist_session(idx);
+        }
+        if !draft.trim().is_empty() && self.chat_view.input_text.trim().is_empty() {
+            self.chat_view.input_text = draft;
+        }
+    }
+
+    fn open_crash_report(&mut self) {
+        let Some(path) = self.crash_recovery.as_ref().and_then(|r| r.crash_report.clone()) else { return };
+        match std::fs::read_to_string(&path) {
+            Ok(contents) => self.crash_report = Some((path, contents)),
+            Err(e) => self.show_error(format!("Couldn't read crash report {}: {e}", path.display())),
+        }
+    }
+
+    /// Keep the crash handler's copy of the open chat, draft and streaming reply current.
+    fn update_crash_snapshot(&mut self) {
+        let session = self.current_session.and_then(|idx| self.chat_sessions.get(idx));
+        let generation = session.and_then(|s| self.chat.generation(&s.id));
+        let partial_reply_len = generation.map(|g| g.buffer.len());
+        let session_key = session.map(|s| (s.id.clone(), s.messages.len(), s.updated_at));
+        if self.crash_snapshot.session != session_key {
+            let session = session.cloned();
+            crash::update_snapshot(|r| r.session = session);
+            self.crash_snapshot.session = session_key;
+        }
+        if self.crash_snapshot.draft != self.chat_view.input_text {
+            self.crash_snapshot.draft = self.chat_view.input_text.clone();
+            let draft = self.chat_view.input_text.clone();
+            crash::update_snapshot(|r| r.draft = draft);
+        }
+        if self.crash_snapshot.partial_reply_len != partial_reply_len {
+            let partial = generation.map(|g| g.buffer.clone());
+            crash::update_snapshot(|r| r.partial_reply = partial);
+            self.crash_snapshot.partial_reply_len = partial_reply_len;
+        }
+        crash::update_window(self.config.window_size, self.config.window_position, self.config.window_maximized);
+    }
+
+    fn render_crash_report(&mut self, ctx: &egui::Context) {
+        let Some((path, contents)) = self.crash_report.as_ref() else { return };
+        let mut open = true;
+        egui::Window::new("Crash report")
+            .open(&mut open)
+            .default_size([640.0, 420.0])
+            .resizable(true)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(egui::RichText::new(path.display().to_string()).small().weak());
+                    if ui.button("📋 Copy").clicked() {
+                        ui.ctx().copy_text(contents.clone());
                     }
+                });
+                ui.separator();
+                egui::ScrollArea::both().show(ui, |ui| {
+                    ui.add(egui::Label::new(egui::RichText::new(contents.as_str()).monospace()).wrap_mode(egui::TextWrapMode::Extend));
+                });
+            });
+        if !open {
+            self.crash_report = None;
+        }
+    }
+
+    /// Estimated prompt size of a session if it were sent now.
+    fn session_prompt_tokens(&self, session_idx: usize) -> u64 {
+        context::conversation_tokens(self.chat_sessions[session_idx].context_messages(), self.config.ai_config.tool_result_max_chars)
+    }
+
+    /// Warn when a conversation's prompt crosses into a worse [`context::BudgetLevel`].
+    fn warn_on_token_budget(&mut self, before: u64, after: u64) {
+        let limits = self.config.ai_config.token_limits;
+        let level = limits.assess(after);
+        if level <= limits.assess(before) {
+            return;
+        }
+        let message = match level {
+            context::BudgetLevel::Comfortable => return,
+            context::BudgetLevel::Slow => format!(
+                "This chat is now about {after} tokens, so replies will get slower. A new chat keeps them fast."
+            ),
+            context::BudgetLevel::NearContext => format!(
+                "This chat is about {after} tokens, close to the model's {}-token context.",
+                limits.context_tokens
+            ),
+            context::BudgetLevel::OverContext => format!(
+                "This chat is about {after} tokens and no longer fits the model's {}-token context; the earliest messages may be lost.",
+                limits.context_tokens
+            ),
+        };
+        self.show_warning(message);
+    }
+
+    fn record_usage(&mut self, record: impl FnOnce(&mut UsageStats) -> anyhow::Result<()>) {
+        if let Some(stats) = self.usage_stats.as_mut() {
+            if let Err(e) = record(stats) {
+                tracing::warn!("Failed to record usage: {}", e);
+            }
+            self.usage_report = None;
+        }
+    }
+
+    /// Count an error in the local issue tally, if the user opted in.
+    fn record_issue(&mut self, source: IssueSource, message: &str) {
+        if !self.config.error_analytics {
+            return;
+        }
+        if let Some(log) = self.issue_log.as_mut() {
+            if let Err(e) = log.record(chrono::Utc::now(), source, message) {
+                tracing::warn!("Failed to record issue: {}", e);
+            }
+        }
+    }
+
+    /// Name replies are counted under in the stats: the loaded model file, or the provider
+    /// (e.g. demo mode) when no ONNX model answered.
+    fn usage_model_label(&self, trace: Option<&crate::ai::inference::GenerationTrace>) -> String {
+        let provider = trace.map_or("Unknown", |t| t.provider.as_str());
+        match (&self.config.last_used_model, trace.and_then(|t| t.execution_provider.as_ref())) {
+            (Some(model), Some(_)) => std::path::Path::new(model)
+                .file_stem()
+                .map_or_else(|| model.clone(), |stem| stem.to_string_lossy().into_owned()),
+            _ => provider.to_string(),
+        }
+    }
+
+    fn send_message(&mut self, ctx: &egui::Context) {
+        if let Some(command) = slash_commands::parse(&self.chat_view.input_text) {
+            match command {
+                Ok(command) => {
+                    self.chat_view.input_text.clear();
+                    self.run_slash_command(command, ctx);
                 }
-                if input.key_pressed(egui::Key::K) {
-                    // Ctrl+K: Clear notifications
-                    self.notifications.clear();
-                }
-                if input.key_pressed(egui::Key::D) && !self.input_text.trim().is_empty() {
-                    // Ctrl+D: Clear input
-                    self.input_text.clear();
-                }
-                if input.key_pressed(egui::Key::H) {
-                    // Ctrl+H: Show help notification
-                    self.show_keyboard_help();
-                }
+                Err(usage) => self.show_warning(usage),
             }
-            
-            // Tab navigation
-            if input.key_pressed(egui::Key::Tab) {
-                if input.modifiers.shift {
-                    self.focus_manager.previous_focus();
-                } else {
-                    self.focus_manager.next_focus();
+            return;
+        }
+        if self.chat_view.input_text.trim().is_empty() && self.pending_images.is_empty() {
+            return;
+        }
+
+        if let Some(constraint) = &self.output_constraint {
+            let label = constraint.label();
+            if let Err(e) = constraint.compile() {
+                self.show_warning(format!("Invalid {label}: {e:#}"));
+                return;
+            }
+            if self.inference_engine.try_read().is_ok_and(|engine| !engine.can_constrain_output()) {
+                self.show_warning(format!("The current model can't constrain its output to {label}. Switch the format back to free text or load a model that supports it."));
+                return;
+            }
+        }
+
+        if self.current_session.is_none() {
+            self.create_new_session();
+        }
+
+        let session_idx = self.current_session.unwrap();
+        let user_message = ChatMessage {
+            id: uuid::Uuid::new_v4().to_string(),
+            content: slash_commands::unescape(&self.chat_view.input_text).to_string(),
+            role: MessageRole::User,
+            timestamp: chrono::Utc::now(),
+            model_used: None,
+            inference_time: None,
+            images: std::mem::take(&mut self.pending_images),
+            trace: None,
+            reply_to: self.take_pending_reply(session_idx),
+            feedback: None,
+            edited_at: None,
+        };
+        self.chat_view.input_text.clear();
+        self.chat_view.scroll.jump_to_bottom();
+
+        // Sent once this chat's reply, or one of the others', is done, and the model is back
+        self.wake_idle_model();
+        let session_id = self.chat_sessions[session_idx].id.clone();
+        if !self.chat.can_start(&session_id) || self.waking_model {
+            self.chat.queue(session_id, user_message);
+            return;
+        }
+        self.submit_message(session_idx, user_message);
+    }
+
+    /// Add `user_message` to its chat and start generating the reply.
+    fn submit_message(&mut self, session_idx: usize, user_message: ChatMessage) {
+        self.record_usage(|stats| stats.record_message(user_message.timestamp));
+        let tokens_before = self.session_prompt_tokens(session_idx);
+        self.chat_sessions[session_idx].messages.push(user_message.clone());
+        self.chat_sessions[session_idx].updated_at = chrono::Utc::now();
+        self.persist_session(session_idx);
+        self.warn_on_token_budget(tokens_before, self.session_prompt_tokens(session_idx));
+        self.start_reply(session_idx);
+    }
+
+    /// Generate a reply to the chat as it stands.
+    fn start_reply(&mut self, session_idx: usize) {
+        self.generate_reply(session_idx, ReplyKind::Reply);
+    }
+
+    /// Carry on with the chat's last reply where it stopped, appending to the same message.
+    fn continue_reply(&mut self, session_idx: usize) {
+        let session = &self.chat_sessions[session_idx];
+        if !self.chat.can_start(&session.id) {
+            self.show_warning("Wait for the current reply to finish before continuing.");
+            return;
+        }
+        let Some(last) = session.messages.last().filter(|m| matches!(m.role, MessageRole::Assistant)) else { return };
+        let message_id = last.id.clone();
+        self.generate_reply(session_idx, ReplyKind::Continue(message_id));
+    }
+
+    /// Have the model summarize the chat into a new message. With `replace_in_context`, later
+    /// replies are generated from the summary on instead of the whole chat.
+    fn summarize_session(&mut self, session_idx: usize, replace_in_context: bool) {
+        let session = &self.chat_sessions[session_idx];
+        if session.messages.is_empty() {
+            self.show_info("Nothing to summarize yet");
+            return;
+        }
+        if !self.chat.can_start(&session.id) {
+            self.show_warning("Wait for the current reply to finish before summarizing.");
+            return;
+        }
+        self.generate_reply(session_idx, ReplyKind::Summary { replace_in_context });
+    }
+
+    /// Translate a message into `language` with the loaded model, shown under it. Runs beside
+    /// the chat's replies and leaves the conversation as it is.
+    fn translate_message(&mut self, session_idx: usize, message_id: &str, language: String) {
+        let Some(message) = self.chat_sessions[session_idx].messages.iter().find(|m| m.id == message_id) else { return };
+        let generation = self.chat.spawn(translate::prompt(&message.content, &language), translate::overrides());
+        self.translations.insert(message_id.to_string(), Translation::new(&language, generation.rx));
+        if self.config.translation_language != language {
+            self.config.translation_language = language;
+            self.save_config();
+        }
+    }
+
+    /// Start generating for the chat. To continue a reply, the transcript ends in that
+    /// unfinished message and the model picks up from it; for a summary it ends in the request
+    /// for one, which isn't kept in the chat.
+    fn generate_reply(&mut self, session_idx: usize, kind: ReplyKind) {
+        if self.chat.active() == 0 {
+            self.show_loading("Generating response...");
+        }
+
+        // Kick off streaming generation via inference engine. If no provider is loaded,
+        // the engine will fall back to a demo provider.
+        let persona = self.session_persona(session_idx);
+        let mut system_message = self.system_message(session_idx);
+        let mut prefer_code_model = false;
+        let prompt = self.chat_sessions[session_idx].messages.iter().rev().find(|m| matches!(m.role, MessageRole::User));
+        let summarizing = matches!(kind, ReplyKind::Summary { .. });
+        if let Some(code) = prompt.filter(|_| !summarizing).and_then(|m| self.config.code_routing.route(&m.content)) {
+            let routing = &self.config.code_routing;
+            prefer_code_model = routing.route_to_code_model
+                && self.inference_engine.try_read().is_ok_and(|engine| engine.has_code_model());
+            if !prefer_code_model {
+                let code_prompt = routing.system_prompt_for(&code);
+                system_message = Some(ChatMessage::system(match system_message {
+                    Some(existing) => format!("{}\n\n{code_prompt}", existing.content),
+                    None => code_prompt,
+                }));
+            }
+            tracing::debug!("Code prompt ({:?}, {:.0}% code), code model: {}", code.language, code.share * 100.0, prefer_code_model);
+        }
+        let instruction = summarizing.then(|| ChatMessage::user(chat_controller::SUMMARY_INSTRUCTION));
+        let messages_snapshot = system_message
+            .into_iter()
+            .chain(self.chat_sessions[session_idx].context_messages().iter().cloned())
+            .chain(instruction)
+            .collect();
+        let session_id = self.chat_sessions[session_idx].id.clone();
+        let overrides = GenerationOverrides {
+            max_tokens: self.response_length.max_tokens(),
+            constraint: self.output_constraint.clone(),
+            prefer_code_model,
+            ..self.session_sampling.get(&session_id).cloned().unwrap_or_default()
+        }
+        .layered_over(&persona.map(|p| p.overrides()).unwrap_or_default());
+        // Display typing indicator; final message will be appended when streaming ends
+        self.chat.start(session_id, messages_snapshot, overrides, kind);
+    }
+
+    /// The reply being generated for the open chat, if any.
+    fn current_generation(&self) -> Option<&Generation> {
+        let session = self.chat_sessions.get(self.current_session?)?;
+        self.chat.generation(&session.id)
+    }
+
+    /// Whether the open chat is waiting for a reply.
+    fn is_generating(&self) -> bool {
+        self.current_generation().is_some()
+    }
+
+    /// Pull streamed text into every generation's buffer and finish those whose task is done
+    /// once their preview has caught up.
+    fn poll_generations(&mut self) {
+        self.chat.set_limit(self.config.max_concurrent_generations);
+        for session_id in self.chat.poll(self.config.streaming.chars_per_second, Instant::now()) {
+            if let Some(elapsed) = self.finish_generation(&session_id) {
+                if elapsed > LONG_GENERATION_SECS {
+                    self.notify_if_hidden("Response ready");
                 }
             }
-            
-            // Escape to clear focus or close windows
-            if input.key_pressed(egui::Key::Escape) {
-                if self.show_models {
-                    self.show_models = false;
-                } else if self.show_settings {
-                    self.show_settings = false;
-                } else if self.focus_manager.current_focus.is_some() {
-                    self.focus_manager.clear_focus();
+        }
+        self.start_queued_messages();
+    }
+
+    /// Send queued messages, oldest first, as far as free generation slots allow.
+    fn start_queued_messages(&mut self) {
+        if self.waking_model {
+            return;
+        }
+        while let Some((session_id, message)) = self.chat.next_queued() {
+            // The chat may have been deleted meanwhile
+            if let Some(session_idx) = self.chat_sessions.iter().position(|s| s.id == session_id) {
+                self.submit_message(session_idx, message);
+            }
+        }
+    }
+
+    /// Generate a failed reply again, unless the chat is busy.
+    fn retry_reply(&mut self, session_idx: usize) {
+        if !self.chat.can_start(&self.chat_sessions[session_idx].id) {
+            self.show_warning("Wait for the current reply to finish before retrying.");
+            return;
+        }
+        self.start_reply(session_idx);
+    }
+
+    /// Stop a chat's reply where it is, keeping the text streamed so far.
+    fn stop_generation(&mut self, session_id: &str) {
+        self.finish_generation(session_id);
+    }
+
+    /// Add the reply streamed so far to its chat, record it and save the chat. Returns how
+    /// long it took, or `None` if nothing was streamed or the chat has been deleted meanwhile.
+    fn finish_generation(&mut self, session_id: &str) -> Option<f64> {
+        let prompt_tokens = self.chat_sessions.iter().position(|s| s.id == session_id).map(|idx| self.session_prompt_tokens(idx));
+        let finished = self.chat.finish(&mut self.chat_sessions, session_id, self.config.debug_inspector);
+        if self.chat.active() == 0 {
+            self.clear_loading_notifications();
+        }
+        let (session_idx, kind, elapsed, tokens, trace) = match finished? {
+            Finished::Nothing { error } => {
+                if let Some(error) = error {
+                    self.show_error(format!("The reply failed: {error}"));
                 }
+                return None;
             }
-            
-            // Enter to activate focused element
-            if input.key_pressed(egui::Key::Enter) && self.focus_manager.activate_current() {
-                self.handle_focus_activation();
+            Finished::Reply { session_idx, kind, elapsed, tokens, trace, .. } => (session_idx, kind, elapsed, tokens, trace),
+        };
+        let model = self.usage_model_label(trace.as_ref());
+        self.record_usage(|stats| stats.record_reply(chrono::Utc::now(), &model, tokens, elapsed));
+        let prompt_tokens = prompt_tokens.unwrap_or_default();
+        self.chat_sessions[session_idx].token_usage.record(prompt_tokens, tokens);
+        if let ReplyKind::Summary { replace_in_context } = kind {
+            self.show_success(if replace_in_context {
+                "Chat summarized; replies now start from the summary"
+            } else {
+                "Chat summarized"
+            });
+        }
+        self.persist_session(session_idx);
+        self.warn_on_token_budget(prompt_tokens, self.session_prompt_tokens(session_idx));
+        Some(elapsed)
+    }
+
+    /// Open the quick-ask popup when its hotkey fires and route its questions to the
+    /// current model.
+    fn handle_quick_ask(&mut self, ctx: &egui::Context) {
+        if let Some(error) = self.quick_ask.sync_hotkey(ctx, &self.config.quick_ask) {
+            self.show_warning(error);
+        }
+        match self.quick_ask.show(ctx) {
+            Some(QuickAskEvent::Ask(question)) => {
+                let messages = vec![ChatMessage {
+                    id: uuid::Uuid::new_v4().to_string(),
+                    content: question,
+                    role: MessageRole::User,
+                    timestamp: chrono::Utc::now(),
+                    model_used: None,
+                    inference_time: None,
+                    images: Vec::new(),
+                    trace: None,
+                    reply_to: None,
+                    feedback: None,
+                    edited_at: None,
+                }];
+                let generation = self.chat.spawn(messages, GenerationOverrides::default());
+                self.quick_ask.start_answer(generation.rx);
             }
-            
-            // Arrow keys for navigation
-            if input.key_pressed(egui::Key::ArrowDown) {
-                self.focus_manager.next_focus();
+            Some(QuickAskEvent::Answered { question, answer }) if self.config.quick_ask.append_to_scratch => {
+                self.append_to_scratch_session(question, answer);
             }
-            if input.key_pressed(egui::Key::ArrowUp) {
-                self.focus_manager.previous_focus();
+            _ => {}
+        }
+    }
+
+    /// Add a quick-ask exchange to the "Quick Ask" session, creating it on first use.
+    fn append_to_scratch_session(&mut self, question: String, answer: String) {
+        let now = chrono::Utc::now();
+        let idx = match self.chat_sessions.iter().position(|s| s.id == quick_ask::SCRATCH_SESSION_ID) {
+            Some(idx) => idx,
+            None => {
+                self.chat_sessions.push(ChatSession {
+                    id: quick_ask::SCRATCH_SESSION_ID.to_string(),
+                    title: quick_ask::SCRATCH_SESSION_TITLE.to_string(),
+                    ..Default::default()
+                });
+                self.chat_sessions.len() - 1
             }
-        });
+        };
+        let model_used = self.config.last_used_model.clone();
+        let session = &mut self.chat_sessions[idx];
+        for (content, role) in [(question, MessageRole::User), (answer, MessageRole::Assistant)] {
+            session.messages.push(ChatMessage {
+                id: uuid::Uuid::new_v4().to_string(),
+                content,
+                model_used: matches!(role, MessageRole::Assistant).then(|| model_used.clone()).flatten(),
+                role,
+                timestamp: now,
+                inference_time: None,
+                images: Vec::new(),
+                trace: None,
+                reply_to: None,
+                feedback: None,
+                edited_at: None,
+            });
+        }
+        session.updated_at = now;
+        self.persist_session(idx);
+    }
+
+    /// Attach the image on the clipboard to the next message. `explicit` is set for the
+    /// paste button, which also reports an empty clipboard.
+    fn paste_clipboard_image(&mut self, explicit: bool) {
+        if !self.model_accepts_images {
+            if explicit {
+                self.show_info("The loaded model doesn't take images; load a vision model to attach them");
+            }
+            return;
+        }
+        match vision::clipboard_image() {
+            Ok(Some(attachment)) => {
+                tracing::info!("Attached {}x{} image from clipboard", attachment.width, attachment.height);
+                self.pending_images.push(attachment);
+            }
+            Ok(None) if explicit => self.show_info("There is no image on the clipboard"),
+            Ok(None) => {}
+            Err(e) => self.show_warning(e.to_string()),
+        }
     }
-    fn handle_focus_activation(&mut self) {
-        if let Some(focused_element) = &self.focus_manager.current_focus {
-            match focused_element {
-                FocusableElement::SendButton => {
-                    // Actual send handled elsewhere when generating_response false
+
+    /// Upload thumbnails for the pending attachments and those in the open chat, and drop
+    /// the ones no longer shown.
+    fn sync_image_thumbnails(&mut self, ctx: &egui::Context) {
+        let session = self.current_session.and_then(|idx| self.chat_sessions.get(idx));
+        let shown: Vec<&vision::ImageAttachment> = self
+            .pending_images
+            .iter()
+            .chain(session.into_iter().flat_map(|s| s.messages.iter().flat_map(|m| &m.images)))
+            .collect();
+        self.image_thumbnails.retain(|id, _| shown.iter().any(|a| &a.id == id));
+        for attachment in shown {
+            if self.image_thumbnails.contains_key(&attachment.id) {
+                continue;
+            }
+            let texture = match attachment.thumbnail(THUMBNAIL_SIZE) {
+                Ok(image) => {
+                    let size = [image.width() as usize, image.height() as usize];
+                    let color = egui::ColorImage::from_rgba_unmultiplied(size, image.as_raw());
+                    Some(ctx.load_texture(format!("attachment-{}", attachment.id), color, Default::default()))
                 }
-                FocusableElement::ClearButton => {
-                    self.input_text.clear();
+                Err(e) => {
+                    tracing::warn!("Failed to decode image attachment {}: {}", attachment.id, e);
+                    None
                 }
-                FocusableElement::NewChatButton => {
+            };
+            self.image_thumbnails.insert(attachment.id.clone(), texture);
+        }
+    }
+
+    /// Insert a Quick Prompts snippet, asking for its variables first if it has any.
+    fn pick_snippet(&mut self, snippet: &PromptSnippet) {
+        match SnippetForm::new(snippet) {
+            Some(form) => self.snippet_form = Some(form),
+            None => self.insert_into_input(&snippet.text),
+        }
+    }
+
+    fn show_snippet_form(&mut self, ctx: &egui::Context) {
+        let Some(form) = self.snippet_form.as_mut() else { return };
+        match form.show(ctx) {
+            Some(SnippetFormEvent::Insert(text)) => {
+                self.snippet_form = None;
+                self.insert_into_input(&text);
+            }
+            Some(SnippetFormEvent::Cancel) => self.snippet_form = None,
+            None => {}
+        }
+    }
+
+    /// Replace an empty input, or add to the end of what has been typed.
+    /// The pending reply if it was picked in this chat.
+    fn take_pending_reply(&mut self, session_idx: usize) -> Option<QuotedReply> {
+        let session_id = &self.chat_sessions[session_idx].id;
+        match self.pending_reply.take() {
+            Some((id, reply)) if id == *session_id => Some(reply),
+            other => {
+                self.pending_reply = other;
+                None
+            }
+        }
+    }
+
+    fn insert_into_input(&mut self, text: &str) {
+        if self.chat_view.input_text.trim().is_empty() {
+            self.chat_view.input_text = text.to_string();
+        } else {
+            if !self.chat_view.input_text.ends_with(char::is_whitespace) {
+                self.chat_view.input_text.push(' ');
+            }
+            self.chat_view.input_text.push_str(text);
+        }
+        self.focus_manager.set_focus(FocusableElement::InputArea);
+    }
+
+    // Keyboard navigation and accessibility methods
+    fn handle_keyboard_shortcuts(&mut self, ctx: &egui::Context) {
+        // While a shortcut is being recorded in settings the keys belong to the recorder
+        if !self.keyboard_shortcuts_enabled || keybindings::capturing(ctx).is_some() {
+            return;
+        }
+
+        // Consumed here, before the widgets run, so a binding never also edits the input text.
+        // Sending is handled by the input area, which needs the key when the input has focus.
+        let bindings = self.config.keybindings.by_specificity();
+        let triggered: Vec<Action> = ctx.input_mut(|input| {
+            bindings
+                .into_iter()
+                .filter(|(action, shortcut)| *action != Action::SendMessage && input.consume_shortcut(shortcut))
+                .map(|(action, _)| action)
+                .collect()
+        });
+        for action in triggered {
+            match action {
+                Action::NewChat if !self.models_view.open && !self.settings_view.open => {
                     self.create_new_session();
+                    self.show_success("New chat session created");
                 }
-                FocusableElement::SettingsButton => {
-                    self.show_settings = !self.show_settings;
+                Action::ToggleModels => {
+                    self.models_view.open = !self.models_view.open;
+                    if self.models_view.open {
+                        self.settings_view.open = false; // Close settings if open
+                    }
                 }
-                FocusableElement::ModelsButton => {
-                    self.show_models = !self.show_models;
+                Action::ToggleSettings => {
+                    self.settings_view.open = !self.settings_view.open;
+                    if self.settings_view.open {
+                        self.models_view.open = false; // Close models if open
+                    }
                 }
-                FocusableElement::Notification(id) => {
-                    self.dismiss_notification(*id);
+                Action::ToggleFavorites => self.show_favorites = !self.show_favorites,
+                Action::ClearNotifications => self.notifications.clear(),
+                Action::ClearInput => {
+                    self.chat_view.input_text.clear();
+                    self.pending_images.clear();
                 }
-                _ => {}
+                Action::ShowHelp => self.show_keyboard_help(ctx),
+                Action::NewChat | Action::SendMessage => {}
             }
         }
+
+        ctx.input(|input| {
+            // Escape closes windows; egui itself drops widget focus on Escape.
+            // Tab, arrow keys and Enter/Space activation are handled by egui's focus system.
+            if input.key_pressed(egui::Key::Escape) {
+                if self.models_view.open {
+                    self.models_view.open = false;
+                } else if self.settings_view.open {
+                    self.settings_view.open = false;
+                }
+            }
+        });
     }
     
-    fn show_keyboard_help(&mut self) {
-        let help_message = 
-            "⌨️ Keyboard Shortcuts:\n\
-            • Ctrl+N: New chat\n\
-            • Ctrl+M: Toggle models\n\
-            • Ctrl+,: Settings\n\
-            • Ctrl+K: Clear notifications\n\
-            • Ctrl+D: Clear input\n\
-            • Ctrl+H: This help\n\
-            • Tab/Shift+Tab: Navigate\n\
+    fn show_keyboard_help(&mut self, ctx: &egui::Context) {
+        let mut help_message = "⌨️ Keyboard Shortcuts:\n".to_string();
+        for action in Action::ALL {
+            if self.config.keybindings.shortcut(action).is_some() {
+                help_message.push_str(&format!("• {}: {}\n", self.config.keybindings.describe(ctx, action), action.label()));
+            }
+        }
+        help_message.push_str(
+            "• Tab/Shift+Tab: Navigate\n\
             • Arrow keys: Navigate\n\
-            • Enter: Activate\n\
-            • Escape: Close/Clear";
+            • Enter/Space: Activate\n\
+            • Escape: Close/Clear\n\
+            Rebind shortcuts in Settings → Keyboard Shortcuts",
+        );
         
         self.show_info(help_message);
     }
 
     fn show_onnx_fix_guide(&mut self) {
-        let fix_guide = 
+        let installed = match self.ort_runtime.selected.as_ref().and_then(|lib| lib.version.as_deref()) {
+            Some(version) => format!("Your system has ONNX Runtime v{version}"),
+            None => "ONNX Runtime was not found on your system".to_string(),
+        };
+        let managed = if self.can_auto_fix_onnx_runtime() {
+            format!("0️⃣ EASIEST: Settings → ONNX Runtime → Download {} (kept in RIA's folder)\n\n", ort_runtime::RECOMMENDED_VERSION)
+        } else {
+            String::new()
+        };
+        let fix_guide = format!(
             "🔧 ONNX Runtime Compatibility Fix\n\n\
-            Your system has ONNX Runtime v1.17.1, but RIA needs v1.22+ for NPU support.\n\n\
+            {installed}, but RIA needs v{}+ for NPU support.\n\n\
             Quick Solutions:\n\n\
+            {managed}\
             1️⃣ UPDATE SYSTEM-WIDE:\n\
             • pip uninstall onnxruntime onnxruntime-gpu\n\
             • pip install onnxruntime --upgrade\n\
@@ -1013,9 +1866,11 @@ impl RiaApp {
             • python -c \"import onnxruntime; print(onnxruntime.__version__)\"\n\
             • Should show 1.22.x or higher\n\n\
             ✅ Demo Mode works perfectly while you fix this!\n\
-            ⚡ NPU will activate automatically after the update.";
+            ⚡ NPU will activate automatically after the update.",
+            self.ort_runtime.expected_version,
+        );
         
-        let notification = AppNotification::new(fix_guide.to_string(), NotificationType::Info)
+        let notification = AppNotification::new(fix_guide, NotificationType::Info)
             .with_duration(12.0)
             .with_actions(vec![
                 NotificationAction {
@@ -1026,64 +1881,142 @@ impl RiaApp {
         self.add_notification(notification);
     }
 
-    fn auto_fix_onnx_runtime(&mut self) {
-    self.show_loading("🔧 Attempting ONNX Runtime auto-fix (running in background)...");
-    self.spawn_async_onnx_fix();
+    /// Whether the recommended managed runtime is installed or can be downloaded and verified.
+    fn can_auto_fix_onnx_runtime(&self) -> bool {
+        let version = ort_runtime::RECOMMENDED_VERSION;
+        ort_runtime::is_downloadable(version)
+            || ort_runtime::installed_versions(self.runtime_manager.root()).iter().any(|v| v == version)
     }
-    
-    #[cfg(feature = "legacy_fixes")]
-    fn attempt_onnx_fix_sync(&mut self) {
-        // Legacy synchronous path now delegates to async; retain for compatibility triggers
-        self.spawn_async_onnx_fix();
+
+    /// The actions offered with a runtime problem; "Auto Fix" only when it can succeed.
+    fn onnx_fix_actions(&self) -> Vec<NotificationAction> {
+        let mut actions = Vec::new();
+        if self.can_auto_fix_onnx_runtime() {
+            actions.push(NotificationAction { label: "Auto Fix".to_string(), action_type: NotificationActionType::AutoFixOnnx });
+        }
+        actions.push(NotificationAction { label: "Fix Guide".to_string(), action_type: NotificationActionType::ShowDetails });
+        actions.push(NotificationAction { label: "Not Now".to_string(), action_type: NotificationActionType::Dismiss });
+        actions
     }
 
-    fn spawn_async_onnx_fix(&mut self) {
-    let _notif_id = self.notification_id_counter; // capture for potential future correlation
-        let ctx_config = self.config.auto_fix_onnx_runtime; // whether we even proceed
-        if !ctx_config { return; }
-        // Channel to push progress messages back to UI thread via notifications
-        let (progress_tx, mut progress_rx) = tokio::sync::mpsc::unbounded_channel::<String>();
-        // Spawn worker
-        tokio::spawn(async move {
-            use std::process::Command;
-            // Helper closure to run command and capture output
-            let run_cmd = |cmd: &str, args: &[&str]| -> Result<(bool,String), String> {
-                Command::new(cmd).args(args).output().map(|out| {
-                    let success = out.status.success();
-                    let stderr = String::from_utf8_lossy(&out.stderr).to_string();
-                    (success, stderr)
-                }).map_err(|e| e.to_string())
-            };
-            let _ = progress_tx.send("Detecting Python environment...".into());
-            let python = if Command::new("python").arg("--version").output().is_ok() { "python" } else if Command::new("python3").arg("--version").output().is_ok() { "python3" } else { let _=progress_tx.send("Python not found. Manual fix required.".into()); return; };
-            let _ = progress_tx.send("Upgrading onnxruntime via pip...".into());
-            match run_cmd(python, &["-m","pip","install","onnxruntime","--upgrade","--user"]) {
-                Ok((true,_)) => {
-                    // verify
-                    if let Ok(output) = Command::new(python).args(["-c","import onnxruntime; print(onnxruntime.__version__)"]).output() {
-                        let ver = String::from_utf8_lossy(&output.stdout).trim().to_string();
-                        let _ = progress_tx.send(format!("Installed version: {ver}"));
-                        if ver.starts_with("1.22") || ver.starts_with("1.2") { let _=progress_tx.send("SUCCESS".into()); } else { let _=progress_tx.send("Attempting conda fallback...".into()); }
-                    } else { let _=progress_tx.send("Verification failed".into()); }
-                },
-                Ok((false,stderr)) => { let _=progress_tx.send(format!("pip upgrade failed: {stderr}")); let _=progress_tx.send("Attempting conda fallback...".into()); },
-                Err(e) => { let _=progress_tx.send(format!("pip not runnable: {e}")); let _=progress_tx.send("Attempting conda fallback...".into()); }
+    /// Download the recommended ONNX Runtime into RIA's runtime folder and switch to it,
+    /// instead of changing the user's Python environment. Without a verifiable download
+    /// the manual guide is shown instead.
+    fn auto_fix_onnx_runtime(&mut self) {
+        if self.runtime_manager.is_installing() {
+            self.show_info("ONNX Runtime download already in progress");
+            return;
+        }
+        let version = ort_runtime::RECOMMENDED_VERSION;
+        if ort_runtime::installed_versions(self.runtime_manager.root()).iter().any(|v| v == version) {
+            self.switch_onnx_runtime(RuntimeChoice::Managed(version.to_string()));
+            return;
+        }
+        if !ort_runtime::is_downloadable(version) {
+            self.show_onnx_fix_guide();
+            return;
+        }
+        self.runtime_manager.install(version);
+    }
+
+    /// Keep the runtime download toast up to date and summarize the result when it ends.
+    fn poll_runtime_install(&mut self) {
+        let outcome = self.runtime_manager.poll();
+        if let Some(job) = self.runtime_manager.job() {
+            let text = format!("🔧 Installing ONNX Runtime {}: {} ({:.0}%)", job.version, job.stage, job.fraction * 100.0);
+            let existing = self.runtime_install_notification.and_then(|id| self.notifications.iter_mut().find(|n| n.id == id));
+            match existing {
+                Some(notification) => notification.message = text,
+                None => {
+                    let notification = AppNotification::new(text, NotificationType::Loading).with_actions(vec![
+                        NotificationAction { label: "Cancel".to_string(), action_type: NotificationActionType::CancelRuntimeInstall },
+                    ]);
+                    self.add_notification(notification);
+                    self.runtime_install_notification = Some(self.notification_id_counter);
+                }
             }
-            // Conda fallback
-            if Command::new("conda").arg("--version").output().is_ok() { if let Ok((ok,_)) = run_cmd("conda", &["install","onnxruntime=1.22","-y","-c","conda-forge"]) { let _=progress_tx.send(if ok {"Conda install success".into()} else {"Conda install failed".into()}); } }
-            // Winget fallback (Windows only)
-            #[cfg(target_os="windows")] {
-                if Command::new("winget").arg("--version").output().is_ok() { let _=progress_tx.send("Trying winget install...".into()); if let Ok((ok,_)) = run_cmd("winget", &["install","Microsoft.ONNXRuntime"]) { let _=progress_tx.send(if ok {"Winget install success".into()} else {"Winget install failed".into()}); } }
+        }
+        let Some(outcome) = outcome else { return };
+        if let Some(id) = self.runtime_install_notification.take() {
+            self.dismiss_notification(id);
+        }
+        match outcome {
+            InstallOutcome::Installed { version, downloaded, elapsed } => {
+                self.failed_runtime_install = None;
+                self.show_success(format!(
+                    "ONNX Runtime {version} installed ({} in {:.0}s)",
+                    crate::utils::format_file_size(downloaded),
+                    elapsed.as_secs_f64()
+                ));
+                self.switch_onnx_runtime(RuntimeChoice::Managed(version));
             }
-            let _ = progress_tx.send("DONE".into());
+            InstallOutcome::Failed { version, error } => {
+                self.record_issue(IssueSource::Download, &error);
+                self.failed_runtime_install = Some(version);
+                let notification = AppNotification::new(format!("ONNX Runtime download failed: {error}"), NotificationType::Error)
+                    .with_duration(10.0)
+                    .with_actions(vec![
+                        NotificationAction { label: "Retry".to_string(), action_type: NotificationActionType::RetryRuntimeInstall },
+                        NotificationAction { label: "Fix Guide".to_string(), action_type: NotificationActionType::ShowDetails },
+                    ]);
+                self.add_notification(notification);
+            }
+            InstallOutcome::Cancelled { version } => self.show_info(format!("ONNX Runtime {version} download cancelled")),
+        }
+    }
+
+    /// Use a managed runtime (or the system one) from now on and re-check compatibility.
+    fn switch_onnx_runtime(&mut self, choice: RuntimeChoice) {
+        let version = match &choice {
+            RuntimeChoice::System => None,
+            RuntimeChoice::Managed(version) => Some(version.clone()),
+        };
+        ort_runtime::use_managed_runtime(self.runtime_manager.root(), version.as_deref());
+        self.config.managed_onnx_runtime = version;
+        self.save_config();
+        // Another runtime may ship the EPs this one lacked
+        if let Ok(mut profile) = self.hardware_profile.lock() {
+            profile.forget_missing();
+            if let Err(e) = profile.save(&AppConfig::hardware_profile_path()) {
+                tracing::warn!("Failed to save hardware profile: {}", e);
+            }
+        }
+        let name = match &choice {
+            RuntimeChoice::System => "the system ONNX Runtime".to_string(),
+            RuntimeChoice::Managed(version) => format!("ONNX Runtime {version}"),
+        };
+        if self.ort_used {
+            self.show_info(format!("Switched to {name}. Restart RIA to use it for models."));
+        } else {
+            self.detect_onnx_runtime(Some(name));
+        }
+    }
+
+    /// Check the ONNX Runtime library on a blocking thread (it is opened to read its version)
+    /// and report back with `RuntimeDetected`.
+    fn detect_onnx_runtime(&mut self, switched_to: Option<String>) {
+        self.ort_runtime_detected = false;
+        let events = self.events.sender();
+        tokio::task::spawn_blocking(move || {
+            events.send(AppEvent::RuntimeDetected { report: ort_runtime::detect(), switched_to });
         });
-        // UI-side polling integration: queue a lightweight task to poll progress each frame.
-        // We'll reuse notifications; store progress strings temporarily
-        self.show_info("Auto-fix running in background. Progress will appear here.");
-        // Attach a small poller by pushing into a vector for later integration (simplified: poll inside update())
-        // We'll store receiver in app state (add field if needed). For minimal change, reuse existing pattern via a static once cell not added now.
-        // NOTE: For full integration we'd add a field; omitted for brevity per incremental step.
-        while let Ok(msg) = progress_rx.try_recv() { self.show_info(format!("AutoFix: {msg}")); }
+    }
+
+    fn finish_runtime_detection(&mut self, report: RuntimeReport, switched_to: Option<String>) {
+        self.ort_runtime = report;
+        self.ort_runtime_detected = true;
+        if let Some(name) = switched_to {
+            self.show_success(format!("Now using {name} ({})", self.ort_runtime.status_label()));
+        }
+        if let Some((cfg, info_name)) = self.deferred_onnx_load.take().filter(|_| self.onnx_pending.is_some()) {
+            self.start_async_onnx_load(cfg, info_name);
+        }
+    }
+    
+    #[cfg(feature = "legacy_fixes")]
+    fn attempt_onnx_fix_sync(&mut self) {
+        // Legacy synchronous trigger; the fix is now a managed runtime download
+        self.auto_fix_onnx_runtime();
     }
     
     #[cfg(feature = "legacy_fixes")]
@@ -1172,340 +2105,98 @@ impl RiaApp {
         self.add_notification(fallback_notification);
     }
     
-    fn update_focus_ring(&mut self) {
-        let mut focus_elements = Vec::new();
-        
-        // Always available elements
-        if !self.show_models && !self.show_settings {
-            focus_elements.push(FocusableElement::InputArea);
-            focus_elements.push(FocusableElement::SendButton);
-            if !self.input_text.is_empty() {
-                focus_elements.push(FocusableElement::ClearButton);
-            }
-            focus_elements.push(FocusableElement::NewChatButton);
-        }
-        
-        focus_elements.push(FocusableElement::ModelsButton);
-        focus_elements.push(FocusableElement::SettingsButton);
-        
-        // Add notification elements
-        for notification in &self.notifications {
-            if notification.dismissible {
-                focus_elements.push(FocusableElement::Notification(notification.id));
+    /// Process tray menu actions and turn close requests into minimize-to-tray.
+    #[cfg(feature = "tray")]
+    fn handle_tray(&mut self, ctx: &egui::Context) {
+        use crate::ui::tray::TrayAction;
+
+        // Also restored from the taskbar, not only from the tray
+        self.window_minimized = ctx.input(|i| i.viewport().minimized).unwrap_or(self.window_minimized);
+        while let Some(action) = self.tray.as_mut().and_then(|t| t.try_recv()) {
+            match action {
+                TrayAction::NewChat => {
+                    self.set_window_minimized(ctx, false);
+                    self.create_new_session();
+                }
+                TrayAction::ShowHide => self.set_window_minimized(ctx, !self.window_minimized),
+                TrayAction::Quit => {
+                    self.quit_requested = true;
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                }
             }
         }
-        
-        self.focus_manager.update_focus_ring(focus_elements);
-    }
-    
-    fn render_focus_indicator(&self, ui: &mut egui::Ui, element: &FocusableElement) {
-        if self.focus_manager.is_focused(element) && self.focus_manager.tab_navigation {
-            let painter = ui.painter();
-            let rect = ui.max_rect();
-            painter.rect_stroke(
-                rect.expand(2.0),
-                4.0,
-                egui::Stroke::new(2.0, egui::Color32::from_rgb(100, 150, 255))
-            );
+
+        if self.hides_on_close() && ctx.input(|i| i.viewport().close_requested()) {
+            ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+            self.set_window_minimized(ctx, true);
         }
     }
-    
-    fn save_config(&self) -> anyhow::Result<()> {
-        let config_dir = dirs::config_dir()
-            .unwrap_or_else(|| std::path::PathBuf::from("."))
-            .join("ria-ai-chat");
-        
-        std::fs::create_dir_all(&config_dir)?;
-        let config_path = config_dir.join("config.json");
-        let config_json = serde_json::to_string_pretty(&self.config)?;
-        std::fs::write(config_path, config_json)?;
-        Ok(())
-    }
-
-    fn render_message(&self, ui: &mut egui::Ui, message: &ChatMessage) {
-        let is_user = matches!(message.role, MessageRole::User);
-        
-        ui.horizontal(|ui| {
-            if !is_user {
-                // AI Avatar
-                ui.vertical(|ui| {
-                    ui.add_space(2.0);
-                    egui::Frame::none()
-                        .fill(egui::Color32::from_rgb(100, 150, 255))
-                        .rounding(16.0)
-                        .inner_margin(8.0)
-                        .show(ui, |ui| {
-                            ui.label(
-                                egui::RichText::new("🤖")
-                                    .size(16.0)
-                            );
-                        });
-                });
-                ui.add_space(10.0);
-            }
-
-            ui.allocate_ui_with_layout(
-                [ui.available_width() - 80.0, 0.0].into(),
-                if is_user { egui::Layout::right_to_left(egui::Align::TOP) } 
-                else { egui::Layout::left_to_right(egui::Align::TOP) },
-                |ui| {
-                    // Enhanced message bubble with professional styling
-                    egui::Frame::none()
-                        .fill(if is_user { 
-                            egui::Color32::from_rgb(65, 105, 170) 
-                        } else { 
-                            egui::Color32::from_rgb(75, 85, 110) 
-                        })
-                        .stroke(egui::Stroke::new(1.0, if is_user {
-                            egui::Color32::from_rgb(85, 125, 190)
-                        } else {
-                            egui::Color32::from_rgb(95, 105, 130)
-                        }))
-                        .rounding(egui::Rounding {
-                            nw: if is_user { 12.0 } else { 4.0 },
-                            ne: if is_user { 4.0 } else { 12.0 },
-                            sw: 12.0,
-                            se: 12.0,
-                        })
-                        .inner_margin(16.0)
-                        .shadow(egui::epaint::Shadow {
-                            offset: [1.0, 2.0].into(),
-                            blur: 6.0,
-                            spread: 0.0,
-                            color: egui::Color32::from_rgba_unmultiplied(0, 0, 0, 40),
-                        })
-                        .show(ui, |ui| {
-                            ui.set_max_width(500.0);
-                            
-                            // Message content with better typography
-                            ui.label(
-                                egui::RichText::new(&message.content)
-                                    .size(15.0)
-                                    .color(egui::Color32::WHITE)
-                                    .line_height(Some(22.0))
-                            );
-                            
-                            ui.add_space(8.0);
-                            
-                            // Enhanced metadata and action row
-                            ui.horizontal(|ui| {
-                                // Timestamp
-                                ui.label(
-                                    egui::RichText::new(
-                                        message.timestamp.format("%H:%M").to_string()
-                                    )
-                                    .size(11.0)
-                                    .color(egui::Color32::from_rgb(200, 210, 220))
-                                );
-                                
-                                // Model info with icon
-                                if let Some(model) = &message.model_used {
-                                    ui.separator();
-                                    ui.label("🧠");
-                                    ui.label(
-                                        egui::RichText::new(model)
-                                            .size(11.0)
-                                            .color(egui::Color32::from_rgb(180, 220, 180))
-                                    );
-                                }
-                                
-                                // Performance metrics with icon
-                                if let Some(time) = message.inference_time {
-                                    ui.separator();
-                                    ui.label("⚡");
-                                    ui.label(
-                                        egui::RichText::new(format!("{:.1}s", time))
-                                            .size(11.0)
-                                            .color(egui::Color32::from_rgb(255, 220, 100))
-                                    );
-                                }
-                                
-                                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                                    // Message actions
-                                    if ui.small_button("📋")
-                                        .on_hover_text("Copy message")
-                                        .clicked() {
-                                        ui.output_mut(|o| o.copied_text = message.content.clone());
-                                    }
-                                    
-                                    if !is_user {
-                                        if ui.small_button("🔄")
-                                            .on_hover_text("Regenerate response")
-                                            .clicked() {
-                                            // TODO: Implement regenerate
-                                        }
-                                        
-                                        if ui.small_button("👍")
-                                            .on_hover_text("Good response")
-                                            .clicked() {
-                                            // TODO: Implement rating
-                                        }
-                                        
-                                        if ui.small_button("👎")
-                                            .on_hover_text("Poor response")
-                                            .clicked() {
-                                            // TODO: Implement rating
-                                        }
-                                    }
-                                });
-                            });
-                        });
-                }
-            );
 
-            if is_user {
-                // User Avatar
-                ui.add_space(10.0);
-                ui.vertical(|ui| {
-                    ui.add_space(2.0);
-                    egui::Frame::none()
-                        .fill(egui::Color32::from_rgb(65, 105, 170))
-                        .rounding(16.0)
-                        .inner_margin(8.0)
-                        .show(ui, |ui| {
-                            ui.label(
-                                egui::RichText::new("👤")
-                                    .size(16.0)
-                                    .color(egui::Color32::WHITE)
-                            );
-                        });
-                });
-            }
-        });
+    /// Closing the window minimizes it to the tray instead of quitting.
+    #[cfg(feature = "tray")]
+    fn hides_on_close(&self) -> bool {
+        self.tray.is_some() && self.config.tray.minimize_to_tray && !self.quit_requested
     }
 
-    fn load_selected_model(&mut self) {
-        if let Some(selected_model) = self.model_manager.get_selected_model() {
-            tracing::info!("Loading model: {}", selected_model);
-            
-            // Show loading notification
-            self.show_loading(format!("Loading model '{}'...", selected_model));
-            
-            // Get model info (now sync)
-            if let Some(info) = self.model_manager.get_selected_model_info() {
-                // Check if the model file exists
-                if !info.path.exists() {
-                    self.clear_loading_notifications();
-                    self.show_error(format!("Model file not found: {}", info.path.display()));
-                    return;
-                }
-                
-                // Create inference config from settings, override model path
-                let mut config = self.config.ai_config.clone();
-                config.model_path = info.path.to_string_lossy().to_string();
+    #[cfg(not(feature = "tray"))]
+    fn handle_tray(&mut self, _ctx: &egui::Context) {}
+
+    /// Minimize rather than hide: an invisible window gets no frames on some platforms, so
+    /// tray clicks queued for it would never be handled.
+    #[cfg(feature = "tray")]
+    fn set_window_minimized(&mut self, ctx: &egui::Context, minimized: bool) {
+        self.window_minimized = minimized;
+        ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(minimized));
+        if !minimized {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Focus);
+        }
+    }
 
-                // Log desired provider
-                tracing::info!("Requested EP: {:?}, prefer_npu={}", config.execution_provider, config.prefer_npu);
-                
-                // Create a safer loading task that handles ONNX Runtime issues
-                let engine_arc = self.inference_engine.clone();
-                // model_name/model_path not currently needed; keep minimal cloning
-                
-                // For now, let's use a simplified approach that falls back to demo mode
-                // if ONNX loading fails due to version incompatibility
-                match self.try_load_onnx_model_safely(&config, &info) {
-                    Ok(provider) => {
-                        tracing::info!("Model loaded successfully: {}", info.name);
-                        self.clear_loading_notifications();
-                        self.show_success(format!("Model '{}' loaded successfully!", info.name));
-                        self.model_loaded = true;
-                        
-                        // Save as last used model
-                        self.config.last_used_model = Some(info.name.clone());
-                        let _ = self.save_config(); // Save config with last used model
-
-                        // Register provider with inference engine asynchronously
-                        tokio::spawn(async move {
-                            let mut engine = engine_arc.write().await;
-                            let idx = engine.add_provider_sync(Box::new(provider));
-                            let _ = engine.set_active_provider_sync(idx);
-                        });
-                    }
-                    Err(e) => {
-                        tracing::error!("Failed to load ONNX model: {}", e);
-                        self.clear_loading_notifications();
-                        
-                        // Provide helpful error message based on the error type
-                        if (e.to_string().contains("version") || e.to_string().contains("1.17.1") || e.to_string().contains("1.16")) && self.config.auto_fix_onnx_runtime {
-                            let notification = AppNotification::new(
-                                format!("ONNX Runtime version incompatibility detected.\n\n\
-                                        Model '{}' needs ONNX Runtime v1.22+ but you have v1.17.1.\n\n\
-                                        ✅ Good news: Chat works perfectly in Demo Mode!\n\
-                                        ⚡ Get intelligent programming responses right now.\n\n\
-                                        To use real AI models, update ONNX Runtime when convenient.", info.name),
-                                NotificationType::Warning
-                            ).with_duration(8.0)
-                            .with_actions(vec![
-                                NotificationAction {
-                                    label: "Auto Fix".to_string(),
-                                    action_type: NotificationActionType::AutoFixOnnx,
-                                },
-                                NotificationAction {
-                                    label: "Fix Guide".to_string(),
-                                    action_type: NotificationActionType::ShowDetails,
-                                },
-                                NotificationAction {
-                                    label: "Not Now".to_string(),
-                                    action_type: NotificationActionType::Dismiss,
-                                }
-                            ]);
-                            self.add_notification(notification);
-                        } else {
-                            self.show_warning(format!("Model '{}' couldn't load: {}\n\n✅ Demo Mode active with intelligent responses!", info.name, e));
-                        }
-                        
-                        // Keep the demo provider active for chat functionality
-                        self.model_loaded = false;
-                    }
-                }
-            } else {
-                tracing::warn!("Selected model not found: {}", selected_model);
-                self.clear_loading_notifications();
-                self.show_warning(format!("Selected model not found: {}", selected_model));
-            }
-        } else {
-            self.show_info("Please select a model first from the 🧠 Models tab");
+    /// Send a desktop notification about finished background work while the window is
+    /// minimized to the tray.
+    fn notify_if_hidden(&self, message: &str) {
+        #[cfg(feature = "tray")]
+        if self.tray.is_some() && self.window_minimized && self.config.tray.notify_when_hidden {
+            crate::ui::tray::notify("RIA AI Chat", message);
         }
+        #[cfg(not(feature = "tray"))]
+        let _ = message;
     }
-    
-    fn try_load_onnx_model_safely(&self, config: &InferenceConfig, _info: &crate::ai::models::ModelInfo) -> anyhow::Result<OnnxProvider> {
-        // Possibly attempt EP fallback sequence if enabled
-        let mut attempt_providers: Vec<InferenceConfig> = Vec::new();
-        attempt_providers.push(config.clone());
-        if self.config.enable_ep_fallback {
-            // Basic ordered fallback list
-            use crate::ai::ExecutionProvider as EP;
-            let order = [EP::Cuda, EP::DirectML, EP::OpenVINO, EP::CoreML, EP::Cpu];
-            for ep in order.iter() {
-                if *ep != config.execution_provider && *ep != crate::ai::ExecutionProvider::QNN { // skip QNN until supported
-                    let mut alt = config.clone();
-                    alt.execution_provider = ep.clone();
-                    attempt_providers.push(alt);
-                }
+
+    /// Remember the current window geometry; it is written to disk on exit.
+    fn track_window_geometry(&mut self, ctx: &egui::Context) {
+        let (inner, outer, maximized, minimized) = ctx.input(|i| {
+            let vp = i.viewport();
+            (vp.inner_rect, vp.outer_rect, vp.maximized, vp.minimized)
+        });
+        if minimized == Some(true) {
+            return;
+        }
+        if let Some(maximized) = maximized {
+            self.config.window_maximized = maximized;
+            if maximized {
+                // Keep the restored size/position for when the window is un-maximized
+                return;
             }
         }
-        let mut last_err: Option<anyhow::Error> = None;
-        for cfg in attempt_providers {
-            let attempt_ep = cfg.execution_provider.clone();
-            let res = std::panic::catch_unwind(|| -> anyhow::Result<OnnxProvider> {
-                let mut provider = OnnxProvider::new(cfg.clone())
-                    .map_err(|e| anyhow::anyhow!("Failed to create ONNX provider: {}", e))?;
-                provider.load_model().map_err(|e| {
-                    if e.to_string().contains("1.16") || e.to_string().contains("1.17") || e.to_string().contains("version") {
-                        anyhow::anyhow!("ONNX Runtime version incompatibility: {}", e)
-                    } else { anyhow::anyhow!("Model loading failed: {}", e) }
-                })?;
-                Ok(provider)
-            });
-            match res {
-                Ok(ok) => { if let Ok(p) = ok { if attempt_ep != config.execution_provider { tracing::info!("EP fallback succeeded with {:?}", attempt_ep); } return Ok(p); } else { last_err = ok.err(); } },
-                Err(panic_info) => {
-                    let panic_msg = if let Some(s) = panic_info.downcast_ref::<String>() { s.clone() } else if let Some(s) = panic_info.downcast_ref::<&str>() { s.to_string() } else { "ONNX Runtime panic occurred".to_string() };
-                    last_err = Some(anyhow::anyhow!("ONNX Runtime version incompatibility (panic): {}", panic_msg));
+        if let Some(rect) = inner {
+            self.config.window_size = (rect.width(), rect.height());
+        }
+        if let Some(rect) = outer {
+            self.config.window_position = Some((rect.min.x, rect.min.y));
+        }
+    }
+
+    /// Queue the config to be written; bursts of changes are saved once they settle.
+    fn save_config(&mut self) {
+        match self.config_saver.as_mut() {
+            Some(saver) => saver.save(&self.config),
+            None => {
+                if let Err(e) = self.config.save() {
+                    tracing::error!("Failed to save config: {}", e);
                 }
             }
         }
-        Err(last_err.unwrap_or_else(|| anyhow::anyhow!("Unknown ONNX load failure")))
     }
 
     #[allow(dead_code)]
@@ -1538,6 +2229,7 @@ impl RiaApp {
     fn add_notification(&mut self, mut notification: AppNotification) {
         self.notification_id_counter += 1;
         notification.id = self.notification_id_counter;
+        self.notification_center.record(notification.notification_type, &notification.message);
         self.notifications.push_back(notification);
         
         // Limit to 5 notifications max
@@ -1598,6 +2290,7 @@ impl RiaApp {
     fn render_notifications(&mut self, ctx: &egui::Context) {
         let mut to_dismiss = Vec::new();
         let mut actions_to_handle = Vec::new();
+        let focus_manager = &mut self.focus_manager;
         
         // Render notifications as toast popups in the top-right corner
         let screen_rect = ctx.screen_rect();
@@ -1618,6 +2311,9 @@ impl RiaApp {
                 .anchor(egui::Align2::RIGHT_TOP, egui::vec2(-20.0, y_offset))
                 .fixed_size([notification_width, 70.0])
                 .show(ctx, |ui| {
+                    let urgent = matches!(notification.notification_type, NotificationType::Error | NotificationType::Warning);
+                    let region_id = ui.make_persistent_id(("notification", notification.id));
+                    a11y::describe_notification(ui.ctx(), region_id, ui.max_rect(), urgent, &notification.message);
                     egui::Frame::none()
                         .fill(notification.get_color().gamma_multiply(0.1))
                         .stroke(egui::Stroke::new(1.0, notification.get_color()))
@@ -1668,7 +2364,10 @@ impl RiaApp {
                                 ui.with_layout(egui::Layout::right_to_left(egui::Align::TOP), |ui| {
                                     // Dismiss button
                                     if notification.dismissible {
-                                        if ui.small_button("✕").clicked() {
+                                        let dismiss = ui.small_button("✕");
+                                        a11y::set_name(&dismiss, "Dismiss notification");
+                                        focus_manager.register(FocusableElement::Notification(notification.id), &dismiss);
+                                        if dismiss.clicked() {
                                             to_dismiss.push(notification.id);
                                         }
                                     }
@@ -1693,18 +2392,42 @@ impl RiaApp {
                     self.show_onnx_fix_guide();
                     to_dismiss.push(notification_id);
                 }
-                NotificationActionType::OpenSettings => {
-                    self.show_settings = true;
+                NotificationActionType::OpenSettings => {
+                    self.settings_view.open = true;
+                    to_dismiss.push(notification_id);
+                }
+                NotificationActionType::AutoFixOnnx => {
+                    self.auto_fix_onnx_runtime();
+                    to_dismiss.push(notification_id);
+                }
+                NotificationActionType::OpenModels => {
+                    self.models_view.open = true;
+                    to_dismiss.push(notification_id);
+                }
+                NotificationActionType::LoadImportedModel => {
+                    if let Some(path) = self.last_imported_model.take() {
+                        self.load_model_file(&path.to_string_lossy(), false);
+                    }
                     to_dismiss.push(notification_id);
                 }
-                NotificationActionType::AutoFixOnnx => {
-                    self.auto_fix_onnx_runtime();
+                NotificationActionType::CancelRuntimeInstall => {
+                    // The next poll reports the cancellation and removes the progress toast
+                    self.runtime_manager.cancel();
+                }
+                NotificationActionType::RetryRuntimeInstall => {
+                    if let Some(version) = self.failed_runtime_install.take() {
+                        self.runtime_manager.install(&version);
+                    }
                     to_dismiss.push(notification_id);
                 }
-                NotificationActionType::OpenModels => {
-                    self.show_models = true;
+                NotificationActionType::RestoreCrashedSession => {
+                    self.restore_crashed_session();
                     to_dismiss.push(notification_id);
                 }
+                NotificationActionType::OpenCrashReport => {
+                    // Keeps the toast so the chat can still be restored afterwards
+                    self.open_crash_report();
+                }
             }
         }
         
@@ -1714,6 +2437,170 @@ impl RiaApp {
         }
     }
 
+    /// Stop work that shouldn't outlive the window: the reply being generated is kept as it is,
+    /// a model load and runtime download are cancelled, model downloads stop where they are
+    /// (their `.part` files resume later) and the open chat is saved.
+    fn stop_background_work(&mut self) {
+        let generating: Vec<String> = self.chat.generating().cloned().collect();
+        for session_id in generating {
+            self.stop_generation(&session_id);
+        }
+        if let Some(cancel) = self.onnx_load_cancel.take() {
+            let _ = cancel.send(());
+        }
+        self.deferred_onnx_load = None;
+        if self.runtime_manager.is_installing() {
+            self.runtime_manager.cancel();
+        }
+        self.models_view.manager.shutdown();
+        if let Some(session_idx) = self.current_session {
+            self.persist_session(session_idx);
+        }
+    }
+
+    /// Background work that is allowed to finish before the app exits.
+    fn unfinished_work(&mut self) -> Vec<String> {
+        let mut work = self.models_view.manager.running_tasks();
+        if self.syncing {
+            work.push("Syncing chats".to_string());
+        }
+        if self.imports_in_flight > 0 {
+            work.push(format!("Importing {} model file(s)", self.imports_in_flight));
+        }
+        if self.config_saver.as_ref().is_some_and(|saver| saver.is_pending()) {
+            work.push("Saving settings".to_string());
+        }
+        if self.runtime_manager.is_installing() {
+            // Cancelled by now; waits for it to clean up its partial files
+            work.push("Stopping the ONNX Runtime download".to_string());
+        }
+        work
+    }
+
+    /// Ask for the next frame only as soon as something on screen will have changed.
+    fn schedule_repaint(&mut self, ctx: &egui::Context) {
+        if self.chat.active() > 0 || self.quick_ask.is_generating() || self.translations.values().any(Translation::is_streaming) {
+            self.repaint.request(Activity::Animating);
+        }
+        if self.onnx_load.is_some() || !self.ort_runtime_detected || self.diagnostics_view.exporting || self.runtime_manager.is_installing() || self.shutdown.is_some() || !self.unfinished_work().is_empty() {
+            self.repaint.request(Activity::Progress);
+        }
+        for notification in self.notifications.iter().filter(|n| n.duration > 0.0) {
+            let remaining = notification.duration - notification.created_at.elapsed().as_secs_f32();
+            self.repaint.request(Activity::After(std::time::Duration::from_secs_f32(remaining.max(0.0))));
+        }
+        ctx.request_repaint_after(self.repaint.take_delay());
+    }
+
+    /// Turn a close request into a graceful shutdown: stop background work and, while some of it
+    /// is still winding down, keep the window open with a "finishing up" dialog.
+    fn handle_close_request(&mut self, ctx: &egui::Context) {
+        let requested = ctx.input(|i| i.viewport().close_requested());
+        #[cfg(feature = "tray")]
+        let requested = requested && !self.hides_on_close();
+        if requested {
+            match &self.shutdown {
+                Some(shutdown) if shutdown.ready => return,
+                Some(_) => ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose),
+                None => {
+                    self.stop_background_work();
+                    let ready = self.unfinished_work().is_empty();
+                    if !ready {
+                        ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+                    }
+                    self.shutdown = Some(Shutdown { started: Instant::now(), ready });
+                }
+            }
+        }
+
+        let Some(started) = self.shutdown.as_ref().filter(|s| !s.ready).map(|s| s.started) else { return };
+        let work = self.unfinished_work();
+        let mut quit = work.is_empty() || started.elapsed() > SHUTDOWN_GRACE;
+        if !quit {
+            egui::Window::new("Finishing up…")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+                .show(ctx, |ui| {
+                    ui.label("RIA will close once this is done:");
+                    for item in &work {
+                        ui.horizontal(|ui| {
+                            ui.spinner();
+                            ui.label(item);
+                        });
+                    }
+                    ui.add_space(6.0);
+                    quit = ui.button("Quit now").clicked();
+                });
+        }
+        if quit {
+            if let Some(shutdown) = self.shutdown.as_mut() {
+                shutdown.ready = true;
+            }
+            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+        }
+    }
+
+    /// Start importing model files dropped onto the window and draw the drop overlay.
+    fn handle_dropped_files(&mut self, ctx: &egui::Context) {
+        if ctx.input(|i| !i.raw.hovered_files.is_empty()) {
+            let painter = ctx.layer_painter(egui::LayerId::new(egui::Order::Foreground, egui::Id::new("model_drop_overlay")));
+            let screen = ctx.screen_rect();
+            painter.rect_filled(screen, 0.0, egui::Color32::from_black_alpha(160));
+            painter.text(
+                screen.center(),
+                egui::Align2::CENTER_CENTER,
+                "Drop .onnx or .gguf files to import them as models",
+                egui::FontId::proportional(22.0),
+                egui::Color32::WHITE,
+            );
+        }
+
+        let dropped: Vec<std::path::PathBuf> = ctx.input(|i| i.raw.dropped_files.iter().filter_map(|f| f.path.clone()).collect());
+        for path in dropped {
+            let file_name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+            if !crate::utils::files::is_importable_model(&path) {
+                self.show_warning(format!("'{file_name}' is not an ONNX or GGUF model file"));
+                continue;
+            }
+            self.show_loading(format!("Importing '{file_name}'…"));
+            self.imports_in_flight += 1;
+            let dest_dir = self.config.primary_models_directory();
+            let events = self.events.sender();
+            tokio::task::spawn_blocking(move || {
+                let result = crate::utils::files::import_model_file(&path, &dest_dir);
+                events.send(AppEvent::ModelImported { source: path, result });
+            });
+        }
+    }
+
+    fn finish_model_import(&mut self, source: std::path::PathBuf, result: anyhow::Result<std::path::PathBuf>) {
+        self.imports_in_flight = self.imports_in_flight.saturating_sub(1);
+        if self.imports_in_flight == 0 {
+            self.clear_loading_notifications();
+        }
+        let file_name = source.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        match result {
+            Ok(imported) => {
+                tracing::info!("Imported model {:?} -> {:?}", source, imported);
+                self.models_view.manager.refresh_models();
+                if imported.extension().and_then(|e| e.to_str()) == Some("onnx") {
+                    self.last_imported_model = Some(imported);
+                    let notification = AppNotification::new(format!("Imported '{file_name}'. Load it now?"), NotificationType::Success)
+                        .with_duration(10.0)
+                        .with_actions(vec![
+                            NotificationAction { label: "Load now".to_string(), action_type: NotificationActionType::LoadImportedModel },
+                            NotificationAction { label: "Later".to_string(), action_type: NotificationActionType::Dismiss },
+                        ]);
+                    self.add_notification(notification);
+                } else {
+                    self.show_info(format!("Imported '{file_name}'. GGUF models are kept in the models directory but only ONNX models can be loaded by the current runtime."));
+                }
+            }
+            Err(e) => self.show_error(format!("Failed to import '{file_name}': {e}")),
+        }
+    }
+
     fn auto_load_cached_model(&mut self, model_path: &str) {
         use std::path::Path;
         
@@ -1721,14 +2608,15 @@ impl RiaApp {
         let model_file_path = Path::new(model_path);
         if !model_file_path.exists() {
             tracing::warn!("Cached model not found: {}", model_path);
-            // Try to find model in models directory
-            let models_dir = &self.config.models_directory;
+            // Try to find model in any of the model directories
             let model_name = model_file_path.file_name()
                 .and_then(|name| name.to_str())
                 .unwrap_or("unknown");
             
-            let model_in_dir = models_dir.join(model_name);
-            if model_in_dir.exists() {
+            let found = self.config.model_directories.iter()
+                .flat_map(|dir| [dir.join(model_name), dir.join(format!("{model_name}.onnx"))])
+                .find(|p| p.is_file());
+            if let Some(model_in_dir) = found {
                 self.attempt_auto_load_model(&model_in_dir.to_string_lossy());
                 return;
             }
@@ -1742,417 +2630,552 @@ impl RiaApp {
     
     fn attempt_auto_load_model(&mut self, model_path: &str) {
         tracing::info!("Auto-loading cached model: {}", model_path);
-        
-        // Show loading notification
-        self.show_loading("Auto-loading previous model...");
-        
-        // Try to load the model with the same logic as manual loading
-        let mut inference_config = self.config.ai_config.clone();
-        inference_config.model_path = model_path.to_string();
-        
-        // Create model info for loading
-        let model_info = crate::ai::models::ModelInfo {
-            name: std::path::Path::new(model_path)
-                .file_name()
-                .and_then(|name| name.to_str())
-                .unwrap_or("Unknown")
-                .to_string(),
-            path: std::path::PathBuf::from(model_path),
-            size: std::fs::metadata(model_path)
-                .map(|m| m.len())
-                .unwrap_or(0),
-            model_type: crate::ai::models::ModelType::LanguageModel,
-            quantization: None, // Unknown quantization
-            supported_providers: vec![],
-            description: "Auto-loaded model".to_string(),
-        };
-        
-        match self.try_load_onnx_model_safely(&inference_config, &model_info) {
-            Ok(provider) => {
-                // Update inference engine with the loaded provider
-                let engine_update_result = {
-                    if let Ok(mut engine) = self.inference_engine.try_write() {
-                        let provider_index = engine.add_provider_sync(Box::new(provider));
-                        engine.set_active_provider_sync(provider_index)
-                    } else {
-                        Err(anyhow::anyhow!("Failed to acquire write lock on inference engine"))
-                    }
-                };
+        self.load_model_file(model_path, true);
+    }
 
-                match engine_update_result {
-                    Ok(_) => {
-                        self.model_loaded = true;
-                        self.config.ai_config = inference_config.clone();
-                        
-                        // Save config to remember this model
-                        if let Err(e) = self.save_config() {
-                            tracing::error!("Failed to save config after auto-loading: {}", e);
-                        }
-                        
-                        self.clear_loading_notifications();
-                        self.show_success(&format!("Auto-loaded model: {}", 
-                            std::path::Path::new(model_path)
-                                .file_name()
-                                .and_then(|name| name.to_str())
-                                .unwrap_or("Unknown")
-                        ));
-                    }
-                    Err(_) => {
-                        tracing::error!("Failed to set active provider for auto-loading");
-                        self.clear_loading_notifications();
-                        self.show_error("Failed to initialize auto-loaded model");
-                    }
-                }
-            }
-            Err(e) => {
-                tracing::error!("Failed to auto-load model {}: {}", model_path, e);
-                self.clear_loading_notifications();
-                
-                if (e.to_string().contains("version") || e.to_string().contains("1.16") || e.to_string().contains("1.17")) && self.config.auto_fix_onnx_runtime {
-                    let notification = AppNotification::new(
-                        "Auto-load failed: ONNX Runtime version incompatibility. Please update ONNX Runtime to v1.22+".to_string(),
-                        NotificationType::Error
-                    ).with_actions(vec![
-                        NotificationAction {
-                            label: "Auto Fix".to_string(),
-                            action_type: NotificationActionType::AutoFixOnnx,
-                        },
-                        NotificationAction {
-                            label: "Help".to_string(),
-                            action_type: NotificationActionType::ShowDetails,
-                        },
-                        NotificationAction {
-                            label: "Dismiss".to_string(),
-                            action_type: NotificationActionType::Dismiss,
-                        }
-                    ]);
-                    self.add_notification(notification);
-                } else {
-                    self.show_warning(&format!("Could not auto-load previous model: {}", e));
-                }
-                
-                // Clear the invalid cached model from config
-                self.config.last_used_model = None;
-                if let Err(e) = self.save_config() {
-                    tracing::error!("Failed to save config after clearing invalid model: {}", e);
-                }
-            }
-        }
+    /// Load a model by path through the async loader. `auto` loads forget the model on failure.
+    fn load_model_file(&mut self, model_path: &str, auto: bool) {
+        let inference_config = self.load_config(model_path);
+        
+        let model_name = std::path::Path::new(model_path)
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or("Unknown")
+            .to_string();
+
+        self.onnx_pending = Some(PendingOnnxLoad { model_name: model_name.clone(), remember_as: model_path.to_string(), auto });
+        self.start_async_onnx_load(inference_config, model_name);
     }
 
-    // Start asynchronous ONNX model loading with cancellation & progress reporting
-    #[allow(dead_code)]
+    // Start asynchronous ONNX model loading with cancellation & progress reporting.
+    // Each ORT session build runs on tokio's blocking pool so neither the UI nor the runtime stalls.
     fn start_async_onnx_load(&mut self, cfg: InferenceConfig, info_name: String) {
+        if !self.ort_runtime_detected {
+            // Picked up again by `finish_runtime_detection`
+            self.deferred_onnx_load = Some((cfg, info_name));
+            return;
+        }
+        // A runtime older than the bindings makes ort panic on first use; don't even try
+        if !self.ort_runtime.compatibility.can_load() {
+            self.onnx_pending = None;
+            self.warn_incompatible_runtime(&info_name);
+            return;
+        }
+        self.ort_used = true;
         // Cancel any existing task
         if let Some(cancel) = self.onnx_load_cancel.take() { let _ = cancel.send(()); }
         self.onnx_load_task = None;
-        self.onnx_progress_rx = None;
 
         let (cancel_tx, mut cancel_rx) = tokio::sync::oneshot::channel();
-        let (progress_tx, progress_rx) = mpsc::unbounded_channel();
-        self.onnx_progress_rx = Some(progress_rx);
+        let load = self.next_load_id;
+        self.next_load_id += 1;
+        self.onnx_load = Some(load);
+        let events = self.events.sender().for_load(load);
         self.onnx_load_cancel = Some(cancel_tx);
 
-        // Post loading notification
-        let notif = AppNotification::new(format!("Loading model '{info_name}' asynchronously…"), NotificationType::Loading)
-            .with_actions(vec![NotificationAction { label: "Cancel".into(), action_type: NotificationActionType::Dismiss }]);
-        self.add_notification(notif);
+        // Post loading notification; heartbeats update its text in place
+        self.show_loading(format!("Loading model '{info_name}'…"));
 
         let enable_fallback = self.config.enable_ep_fallback;
-        let auto_fix = self.config.auto_fix_onnx_runtime;
-        let ep_sequence = [ExecutionProvider::Cuda, ExecutionProvider::DirectML, ExecutionProvider::OpenVINO, ExecutionProvider::CoreML, ExecutionProvider::Cpu];
-        // Provider hand-off channel (create per load)
-        let (prov_tx, prov_rx) = mpsc::channel(1);
-        self.onnx_loaded_provider_rx = Some(prov_rx);
-        self.onnx_loaded_provider_tx = Some(prov_tx.clone());
-
-    let handle = tokio::spawn(async move {
-            progress_tx.send(OnnxLoadProgress::Phase("validate_path".into())).ok();
+        let auto_fix = self.config.auto_fix_onnx_runtime && self.can_auto_fix_onnx_runtime();
+        let ep_sequence = self.hardware_profile.lock().map(|p| p.fallback_order(&cfg.execution_provider, &DEFAULT_FALLBACK_ORDER)).unwrap_or_default();
+        let hardware_profile = self.hardware_profile.clone();
+        let total_bytes = std::fs::metadata(&cfg.model_path).map(|m| m.len()).unwrap_or(0);
+        let prefill_monitor = self.prefill_monitor.clone();
+
+        let handle = tokio::spawn(async move {
+            events.progress(OnnxLoadProgress::Phase("validate_path".into()));
             if cancel_rx.try_recv().is_ok() { return; }
             // Initial provider create to validate config
-            if let Err(e) = OnnxProvider::new(cfg.clone()) { progress_tx.send(OnnxLoadProgress::Error(format!("Provider init failed: {e}"))).ok(); return; }
+            if let Err(e) = OnnxProvider::new(cfg.clone()) { events.progress(OnnxLoadProgress::Error(format!("Provider init failed: {e}"))); return; }
 
             // Build attempt config list (EP fallbacks if enabled)
             let mut attempts: Vec<InferenceConfig> = vec![cfg.clone()];
             if enable_fallback {
-                for ep in ep_sequence.iter() { if *ep != cfg.execution_provider { let mut alt = cfg.clone(); alt.execution_provider = ep.clone(); attempts.push(alt); } }
+                for ep in ep_sequence.iter() {
+                    if OnnxProvider::ep_supported(ep) { let alt = InferenceConfig { execution_provider: ep.clone(), device_id: None, device_type: None, ..cfg.clone() }; attempts.push(alt); }
+                }
             }
+            // Remember how each EP did so later loads try the ones that work here first
+            let record = |ep: &ExecutionProvider, outcome: EpOutcome| {
+                let Ok(mut profile) = hardware_profile.lock() else { return };
+                profile.record(ep, outcome);
+                if let Err(e) = profile.save(&AppConfig::hardware_profile_path()) {
+                    tracing::warn!("Failed to save hardware profile: {}", e);
+                }
+            };
 
-            for attempt_cfg in attempts {                
-                if cancel_rx.try_recv().is_ok() { progress_tx.send(OnnxLoadProgress::Cancelled).ok(); return; }
+            for attempt_cfg in attempts {
+                if cancel_rx.try_recv().is_ok() { events.progress(OnnxLoadProgress::Cancelled); return; }
                 let ep_label = format!("{:?}", attempt_cfg.execution_provider);
-                progress_tx.send(OnnxLoadProgress::AttemptEP(ep_label.clone())).ok();
-                let mut attempt_provider = match OnnxProvider::new(attempt_cfg.clone()) {
+                events.progress(OnnxLoadProgress::AttemptEP(ep_label.clone()));
+                let attempt_provider = match OnnxProvider::new(attempt_cfg.clone()) {
                     Ok(p) => p,
-                    Err(e) => { progress_tx.send(OnnxLoadProgress::AttemptResult(OnnxEpAttempt { ep: ep_label.clone(), success: false, error_kind: Some(EpErrorKind::ProviderInit), message: Some(e.to_string()) })).ok(); continue; }
+                    Err(e) => { events.progress(OnnxLoadProgress::AttemptResult(OnnxEpAttempt { ep: ep_label.clone(), success: false, error_kind: Some(EpErrorKind::ProviderInit), message: Some(e.to_string()) })); continue; }
+                };
+                let Some(result) = load_on_blocking_pool(attempt_provider, &ep_label, total_bytes, &events, &mut cancel_rx).await else {
+                    events.progress(OnnxLoadProgress::Cancelled);
+                    return;
                 };
-                match attempt_provider.load_model_classified() {
-                    Ok(_) => {
-                        let _ = prov_tx.send(Box::new(attempt_provider) as Box<dyn AIProvider + Send + Sync>).await;
-                        progress_tx.send(OnnxLoadProgress::AttemptResult(OnnxEpAttempt { ep: ep_label.clone(), success: true, error_kind: None, message: None })).ok();
-                        progress_tx.send(OnnxLoadProgress::Loaded { ep: ep_label }).ok();
+                match result {
+                    Ok(mut provider) => {
+                        record(&attempt_cfg.execution_provider, EpOutcome::Loaded);
+                        provider.set_prefill_monitor(prefill_monitor.clone());
+                        let accepts_images = provider.accepts_images();
+                        events.provider(Box::new(provider));
+                        events.progress(OnnxLoadProgress::AttemptResult(OnnxEpAttempt { ep: ep_label.clone(), success: true, error_kind: None, message: None }));
+                        events.progress(OnnxLoadProgress::Loaded { ep: ep_label, accepts_images });
                         return;
                     },
                     Err(le) => {
+                        if let Some(outcome) = EpOutcome::from_load_error(&le) {
+                            record(&attempt_cfg.execution_provider, outcome);
+                        }
                         let (kind, msg) = map_load_error(&le);
                         let msg2 = if matches!(kind, EpErrorKind::VersionMismatch) && auto_fix { format!("{msg} (auto-fix available)") } else { msg };
-                        progress_tx.send(OnnxLoadProgress::AttemptResult(OnnxEpAttempt { ep: ep_label.clone(), success: false, error_kind: Some(kind), message: Some(msg2) })).ok();
+                        events.progress(OnnxLoadProgress::AttemptResult(OnnxEpAttempt { ep: ep_label.clone(), success: false, error_kind: Some(kind), message: Some(msg2) }));
                     }
-                }                
+                }
             }
-            progress_tx.send(OnnxLoadProgress::Failed("All attempts failed".into())).ok();
+            events.progress(OnnxLoadProgress::Failed("All attempts failed".into()));
         });
         self.onnx_load_task = Some(handle);
     }
 
-    #[allow(dead_code)]
-    fn poll_async_onnx_progress(&mut self) {
-        let mut finished_success = None::<String>;
-        if let Some(rx) = self.onnx_progress_rx.as_mut() {
-            let mut events = Vec::new();
-            while let Ok(evt) = rx.try_recv() { events.push(evt); }
-            for evt in events { self.handle_onnx_progress_event(evt, &mut finished_success); }
+    fn on_onnx_load_progress(&mut self, load: u64, progress: OnnxLoadProgress) {
+        if self.onnx_load != Some(load) {
+            return;
         }
+        let finished = progress.is_final();
+        let mut finished_success = None::<String>;
+        self.handle_onnx_progress_event(progress, &mut finished_success);
+        if !finished { return; }
+        self.clear_loading_notifications();
+        self.waking_model = false;
+        let pending = self.onnx_pending.take();
         if let Some(ep) = finished_success {
-            // mark loaded state flags
-            self.model_loaded = true; // placeholder; in future store the provider instance from task via channel
-            self.show_success(format!("Model loaded successfully via {ep}"));
-            // cleanup channels
-            self.onnx_load_cancel = None;
-            self.onnx_progress_rx = None;
-            self.onnx_load_task = None;
-            self.onnx_loaded_provider_tx = None; // sender dropped
+            self.model_loaded = true;
+            self.idle_unloaded = None;
+            self.last_model_use = Instant::now();
+            match &pending {
+                Some(p) => {
+                    tracing::info!("Model loaded successfully: {} via {}", p.model_name, ep);
+                    self.show_success(format!("Model '{}' loaded successfully via {ep}", p.model_name));
+                    self.config.last_used_model = Some(p.remember_as.clone());
+                    self.save_config();
+                }
+                None => self.show_success(format!("Model loaded successfully via {ep}")),
+            }
+        } else if let Some(p) = pending {
+            // Keep the demo provider active for chat functionality
+            self.model_loaded = false;
+            self.model_accepts_images = false;
+            let version_mismatch = self.onnx_attempt_log.iter().any(|a| matches!(a.error_kind, Some(EpErrorKind::VersionMismatch)));
+            if version_mismatch && self.config.auto_fix_onnx_runtime {
+                let notification = AppNotification::new(
+                    format!("ONNX Runtime version incompatibility detected while loading '{}'.\n\n\
+                            ✅ Chat keeps working in Demo Mode.\n\n\
+                            To use real AI models, update ONNX Runtime to v1.22+.", p.model_name),
+                    NotificationType::Warning
+                ).with_duration(8.0)
+                .with_actions(self.onnx_fix_actions());
+                self.add_notification(notification);
+            }
+            if p.auto {
+                // Clear the invalid cached model from config
+                self.config.last_used_model = None;
+                self.save_config();
+            }
+        }
+        self.onnx_load_cancel = None;
+        self.onnx_load = None;
+        self.onnx_load_task = None;
+    }
+
+    /// Put a freshly loaded model in place of the previous one and make it active.
+    fn activate_loaded_provider(&mut self, load: u64, provider_box: Box<dyn AIProvider + Send + Sync>) {
+        if self.onnx_load != Some(load) {
+            return;
+        }
+        self.model_details = provider_box.get_model_info().ok();
+        let mut activation_result: Result<(), String> = Ok(());
+        if let Ok(mut engine) = self.inference_engine.try_write() {
+            // Replace the previous model so its session is freed rather than kept around
+            let registered = match self.onnx_provider_id.filter(|&id| engine.has_provider(id)) {
+                Some(id) => engine.replace_provider(id, provider_box).map(|_| id),
+                None => Ok(engine.add_provider_sync(provider_box)),
+            };
+            match registered.and_then(|id| engine.set_active_provider_sync(id).map(|_| id)) {
+                Ok(id) => self.onnx_provider_id = Some(id),
+                Err(e) => activation_result = Err(format!("Failed to activate ONNX provider: {e}")),
+            }
+        } else {
+            activation_result = Err("Inference engine write lock busy".to_string());
+        }
+        match activation_result {
+            Ok(_) => tracing::info!("ONNX provider activated"),
+            Err(err_msg) => self.show_error(err_msg),
         }
     }
 
-    #[allow(dead_code)]
     fn handle_onnx_progress_event(&mut self, evt: OnnxLoadProgress, success_out: &mut Option<String>) {
         match evt {
-            OnnxLoadProgress::Phase(p) => self.show_info(format!("ONNX load phase: {p}")),
+            OnnxLoadProgress::Phase(p) => tracing::debug!("ONNX load phase: {p}"),
             OnnxLoadProgress::AttemptEP(ep) => self.show_info(format!("Trying execution provider {ep}")),
-            OnnxLoadProgress::Loaded { ep } => { *success_out = Some(ep.clone()); },
+            OnnxLoadProgress::Loaded { ep, accepts_images } => {
+                *success_out = Some(ep.clone());
+                self.model_accepts_images = accepts_images;
+            },
             OnnxLoadProgress::LoadError { ep, error } => { self.show_warning(format!("EP {ep} failed: {error}")); },
-            OnnxLoadProgress::Error(e) => self.show_error(format!("ONNX load error: {e}")),
+            OnnxLoadProgress::Error(e) => {
+                self.record_issue(IssueSource::ModelLoad, &e);
+                self.show_error(format!("ONNX load error: {e}"));
+            }
             OnnxLoadProgress::Failed(msg) => self.show_error(format!("Model load failed: {msg}")),
             OnnxLoadProgress::Cancelled => self.show_warning("Model load cancelled".to_string()),
             OnnxLoadProgress::AttemptResult(attempt) => {
                 if !attempt.success { if let Some(kind) = &attempt.error_kind { self.show_warning(format!("EP {} failed ({:?}): {}", attempt.ep, kind, attempt.message.clone().unwrap_or_default())); } }
+                if !attempt.success {
+                    let message = format!("{}: {}", attempt.ep, attempt.message.as_deref().unwrap_or("failed"));
+                    self.record_issue(IssueSource::ExecutionProvider, &message);
+                }
                 self.onnx_attempt_log.push(attempt);
                 // Keep diagnostics panel open automatically on failures
-                self.show_diagnostics = true;
+                self.diagnostics_view.open = true;
+            }
+            OnnxLoadProgress::Heartbeat { ep, elapsed_secs, bytes_mapped, total_bytes } => {
+                let name = self.onnx_pending.as_ref().map(|p| p.model_name.as_str()).unwrap_or("model");
+                let mut text = format!("Loading '{name}' via {ep}… {elapsed_secs:.0}s");
+                if bytes_mapped > 0 {
+                    text.push_str(&format!(", {} mapped", crate::utils::format_file_size(bytes_mapped)));
+                    if total_bytes > 0 { text.push_str(&format!(" of {}", crate::utils::format_file_size(total_bytes))); }
+                }
+                if let Some(n) = self.notifications.iter_mut().find(|n| n.notification_type == NotificationType::Loading) {
+                    n.message = text;
+                }
             }
         }
     }
 
-    fn ui_diagnostics_panel(&mut self, ui: &mut egui::Ui) {
-        if !self.show_diagnostics { return; }
-        egui::CollapsingHeader::new("🩺 ONNX Diagnostics").default_open(true).show(ui, |ui| {
-            if self.onnx_attempt_log.is_empty() { ui.label("No attempts recorded yet"); return; }
-            ui.separator();
-            ui.label("Execution Provider Attempts:");
-            for att in &self.onnx_attempt_log {
-                let status = if att.success { "✅" } else { "❌" };
-                ui.label(format!("{status} EP {} -> {}{}", att.ep, if att.success { "SUCCESS" } else { "FAIL" }, att.error_kind.as_ref().map(|k| format!(" ({k:?})")).unwrap_or_default()));
-                if let Some(msg) = &att.message { if !att.success { ui.small(format!("    • {}", msg)); } }
-            }
-            ui.separator();
-            if ui.button("Clear Log").clicked() { self.onnx_attempt_log.clear(); }
-            if ui.button(if self.show_diagnostics { "Hide Diagnostics" } else { "Show Diagnostics" }).clicked() { self.show_diagnostics = !self.show_diagnostics; }
-        });
+    fn warn_incompatible_runtime(&mut self, model_name: &str) {
+        let found = self.ort_runtime.selected.as_ref().map(|lib| lib.path.display().to_string()).unwrap_or_default();
+        let notification = AppNotification::new(
+            format!("Can't load '{model_name}': {} at {found} is not compatible.\n\n\
+                    {}.\n\n\
+                    ✅ Chat keeps working in Demo Mode.", self.ort_runtime.status_label(), self.ort_runtime.note),
+            NotificationType::Warning
+        ).with_duration(10.0)
+        .with_actions(self.onnx_fix_actions());
+        self.add_notification(notification);
     }
 
-} // end impl RiaApp
+    /// Tell the user when the engine had to switch away from a failing provider.
+    /// Drop the loaded model and go back to demo mode. Replies already being generated on it
+    /// finish first.
+    fn unload_model(&mut self) {
+        if self.onnx_provider_id.is_none() {
+            return;
+        }
+        if !self.release_model() {
+            return self.show_warning("The model is busy; try unloading it again in a moment.");
+        }
+        self.idle_unloaded = None;
+        self.show_info("Model unloaded");
+    }
 
-// Auxiliary enums and impls follow.
+    /// Remove the loaded model from the engine. False while the engine is busy.
+    fn release_model(&mut self) -> bool {
+        let Some(id) = self.onnx_provider_id else { return true };
+        let Ok(mut engine) = self.inference_engine.try_write() else { return false };
+        if let Err(e) = engine.remove_provider(id) {
+            tracing::warn!("Failed to unload model: {}", e);
+        }
+        drop(engine);
+        self.onnx_provider_id = None;
+        self.model_details = None;
+        self.model_loaded = false;
+        self.model_accepts_images = false;
+        true
+    }
 
-impl eframe::App for RiaApp {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        // Update animation time
-        self.animation_time += ctx.input(|i| i.stable_dt);
-        
-        // Update notifications (remove expired ones)
-        self.update_notifications();
+    /// Release the model once nothing has used it for `idle_unload_minutes`, remembering it
+    /// for [`wake_idle_model`](Self::wake_idle_model).
+    fn unload_idle_model(&mut self) {
+        let minutes = self.config.idle_unload_minutes;
+        if minutes == 0 || !self.model_loaded || self.onnx_provider_id.is_none() || self.onnx_pending.is_some() {
+            return;
+        }
+        if self.chat.active() > 0 || !self.chat.queued().is_empty() || self.translations.values().any(Translation::is_streaming) {
+            self.last_model_use = Instant::now();
+            return;
+        }
+        if self.last_model_use.elapsed() < std::time::Duration::from_secs(u64::from(minutes) * 60) {
+            return;
+        }
+        let path = self.model_details.as_ref().and_then(|d| d.get("model_path").cloned()).or_else(|| self.config.last_used_model.clone());
+        // A busy engine is tried again next frame
+        if self.release_model() {
+            tracing::info!("Unloaded the model after {minutes} idle minutes");
+            self.idle_unloaded = path;
+        }
+    }
 
-        // Poll async ONNX load progress & provider channel
-        self.poll_async_onnx_progress();
-        if let Some(rx) = self.onnx_loaded_provider_rx.as_mut() {
-            match rx.try_recv() {
-                Ok(provider_box) => {
-                    let mut activation_result: Result<(), String> = Ok(());
-                    if let Ok(mut engine) = self.inference_engine.try_write() {
-                        let idx = engine.add_provider_sync(provider_box);
-                        if let Err(e) = engine.set_active_provider_sync(idx) { activation_result = Err(format!("Failed to activate ONNX provider: {e}")); }
-                    } else {
-                        activation_result = Err("Inference engine write lock busy".to_string());
-                    }
-                    match activation_result {
-                        Ok(_) => self.show_success("ONNX provider activated".to_string()),
-                        Err(err_msg) => self.show_error(err_msg),
-                    }
-                },
-                Err(TryRecvError::Empty) => {},
-                Err(TryRecvError::Disconnected) => { self.onnx_loaded_provider_rx = None; },
-            }
+    /// Start reloading a model released by [`unload_idle_model`](Self::unload_idle_model).
+    fn wake_idle_model(&mut self) {
+        // Another load is running; keep the path so the wake can happen once it finishes
+        if self.onnx_pending.is_some() {
+            return;
         }
-        
-        // Handle keyboard shortcuts and navigation
-        self.handle_keyboard_shortcuts(ctx);
+        let Some(path) = self.idle_unloaded.take() else { return };
+        tracing::info!("Reloading idle-unloaded model {}", path);
+        self.waking_model = true;
+        self.load_model_file(&path, false);
+        // The load may refuse to start (incompatible runtime); don't hold messages for it
+        self.waking_model = self.onnx_pending.is_some();
+    }
 
-        // Check for newly completed model downloads and auto-load if enabled
-        if self.config.auto_load_new_download {
-            let completed = self.model_manager.take_completed_downloads();
-            if !completed.is_empty() {
-                // Prefer the last (most recent) completed download
-                if let Some(latest_name) = completed.last() {
-                    // Build full path relative to models directory if not absolute
-                    let mut candidate_path = std::path::PathBuf::from(latest_name);
-                    if candidate_path.is_relative() {
-                        candidate_path = self.config.models_directory.join(&candidate_path);
-                    }
-                    if candidate_path.exists() {
-                        tracing::info!("Auto-loading newly downloaded model: {:?}", candidate_path);
-                        self.auto_load_cached_model(&candidate_path.to_string_lossy());
-                    } else {
-                        tracing::warn!("Completed download path not found: {:?}", candidate_path);
-                    }
-                }
-            }
+    /// Status bar badge for the detected ONNX Runtime; opens the diagnostics panel.
+    fn ort_runtime_badge(&mut self, ui: &mut egui::Ui) {
+        let report = &self.ort_runtime;
+        if !self.ort_runtime_detected {
+            ui.add(egui::Label::new(egui::RichText::new("⏳ ORT").small())).on_hover_text(&report.note);
+            return;
         }
-        
-        // Update focus ring based on current UI state
-        self.update_focus_ring();
+        let icon = match report.compatibility {
+            Compatibility::Supported => "✅",
+            Compatibility::Newer => "ℹ",
+            Compatibility::TooOld | Compatibility::Unknown => "⚠",
+        };
+        let text = format!("{icon} {}", report.status_label());
+        let hover = format!("{}\nThis build expects ONNX Runtime {}.x", report.note, report.expected_version);
+        let response = ui.add(egui::Button::new(text).small().frame(false)).on_hover_text(hover);
+        a11y::set_name(&response, &format!("{}, {:?}", report.status_label(), report.compatibility));
+        if response.clicked() {
+            self.diagnostics_view.open = !self.diagnostics_view.open;
+        }
+    }
 
-        // Settings window
-        if self.show_settings {
-            egui::Window::new("Settings")
-                .collapsible(false)
-                .resizable(true)
-                .default_size([400.0, 300.0])
-                .show(ctx, |ui| {
-                    crate::ui::settings::render_settings(ui, &mut self.config, &mut self.system_status);
-                    
-                    if ui.button("Close").clicked() {
-                        self.show_settings = false;
-                    }
-                });
+    /// Turn the engine's response cache on or off to match the settings.
+    fn configure_response_cache(&self) {
+        let engine = self.inference_engine.clone();
+        let enabled = self.config.ai_config.response_cache;
+        tokio::spawn(async move { engine.write().await.set_response_cache(enabled) });
+    }
+
+    /// Status bar note while the model is unloaded for being idle; clicking reloads it now.
+    fn idle_model_badge(&mut self, ui: &mut egui::Ui) {
+        if self.waking_model {
+            ui.label(egui::RichText::new("⏳ Reloading model").small());
+        } else if self.idle_unloaded.is_some() {
+            let hover = format!("Unloaded after {} idle minutes to free memory. It reloads with the next message; click to reload now.", self.config.idle_unload_minutes);
+            if ui.add(egui::Button::new("💤 Model unloaded").small().frame(false)).on_hover_text(hover).clicked() {
+                self.wake_idle_model();
+            }
         }
+    }
 
-        // Models window
-        if self.show_models {
-            egui::Window::new("AI Models")
-                .collapsible(false)
+    /// The Stats window with usage and reply ratings.
+    fn render_stats(&mut self, ctx: &egui::Context) {
+        if self.show_stats {
+            let mut open = self.show_stats;
+            let mut export = false;
+            let feedback = FeedbackTotals::from_sessions(&self.chat_sessions);
+            egui::Window::new("📊 Stats")
+                .open(&mut open)
+                .default_size([520.0, 460.0])
                 .resizable(true)
-                .default_size([650.0, 500.0])
-                .max_size([750.0, 650.0])
                 .show(ctx, |ui| {
-                    self.model_manager.render(ui);
-                    
-                    ui.add_space(10.0);
-                    ui.separator();
-                    ui.add_space(10.0);
-                    
-                    // Model selection and loading
-                    ui.horizontal(|ui| {
-                        if let Some(selected_model) = self.model_manager.get_selected_model() {
-                            ui.label(format!("Selected: {}", selected_model));
-                            
-                            if ui.button("Load Model").clicked() {
-                                self.load_selected_model();
+                    egui::ScrollArea::vertical().show(ui, |ui| {
+                        match &self.usage_stats {
+                            Some(stats) => {
+                                let report = self.usage_report.get_or_insert_with(|| crate::ui::stats::load(stats));
+                                crate::ui::stats::render(ui, report);
                             }
-                        } else {
-                            ui.label("No model selected");
-                        }
-                        
-                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                            if ui.button("Close").clicked() {
-                                self.show_models = false;
+                            None => {
+                                ui.label("Usage statistics are unavailable (the stats database couldn't be opened).");
                             }
-                        });
+                        }
+                        ui.add_space(8.0);
+                        export = crate::ui::stats::render_feedback(ui, &feedback);
                     });
                 });
-        }
-
-        // Drain streaming channel (if any) and update buffer
-        if let Some(rx) = self.streaming_rx.as_mut() {
-            loop {
-                match rx.try_recv() {
-                    Ok(chunk) => {
-                        self.streaming_buffer.push_str(&chunk);
-                    }
-                    Err(TryRecvError::Empty) => break,
-                    Err(TryRecvError::Disconnected) => {
-                        // Finalize: append assistant message with the assembled content
-                        if let Some(session_idx) = self.current_session {
-                            if !self.streaming_buffer.is_empty() {
-                                let elapsed = self.streaming_start.map(|t| t.elapsed().as_secs_f64()).unwrap_or(0.0);
-                                let ai_message = ChatMessage {
-                                    id: uuid::Uuid::new_v4().to_string(),
-                                    content: std::mem::take(&mut self.streaming_buffer),
-                                    role: MessageRole::Assistant,
-                                    timestamp: chrono::Utc::now(),
-                                    model_used: Some("Streaming".to_string()),
-                                    inference_time: Some(elapsed),
-                                };
-                                self.chat_sessions[session_idx].messages.push(ai_message);
-                            }
-                        }
-                        self.generating_response = false;
-                        self.clear_loading_notifications();
-                        self.streaming_rx = None;
-                        self.streaming_start = None;
-                        break;
-                    }
-                }
+            self.show_stats = open;
+            if !open {
+                self.usage_report = None;
+            }
+            if export {
+                self.export_feedback(ctx);
             }
         }
+    }
 
-        // Top status bar
+    /// Top bar with the chat list toggle, the system status and badges for runtime, power mode and sync.
+    fn render_status_bar(&mut self, ctx: &egui::Context, palette: &Palette) {
         egui::TopBottomPanel::top("status_bar").show(ctx, |ui| {
             egui::Frame::none()
-                .fill(egui::Color32::from_rgb(25, 25, 35))
-                .stroke(egui::Stroke::new(1.0, egui::Color32::from_rgb(50, 50, 60)))
+                .fill(palette.status_bar_fill)
+                .stroke(egui::Stroke::new(1.0, palette.status_bar_stroke))
                 .inner_margin(4.0)
                 .show(ui, |ui| {
-                    self.system_status.render_status_bar(ui);
+                    ui.horizontal(|ui| {
+                        self.sidebar_toggle(ui);
+                        self.system_status.render_status_bar(ui);
+                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                            self.notification_center.bell_button(ui);
+                            self.ort_runtime_badge(ui);
+                            self.idle_model_badge(ui);
+                            if self.power_mode != PowerMode::Balanced {
+                                let icon = if self.power_mode == PowerMode::Performance { "⚡" } else { "🔋" };
+                                ui.label(egui::RichText::new(format!("{icon} {}", self.power_mode.label())).small())
+                                    .on_hover_text("Power mode for model loads (Settings → Hardware)");
+                            }
+                            if self.sync_status != SyncStatus::Disabled {
+                                let hover = match &self.sync_status {
+                                    SyncStatus::Error(e) => format!("{e}\nClick to retry"),
+                                    SyncStatus::Locked => "Enter the sync passphrase in Settings".to_string(),
+                                    _ => "Click to sync now".to_string(),
+                                };
+                                let can_sync = !matches!(self.sync_status, SyncStatus::Locked | SyncStatus::Syncing);
+                                if ui.add_enabled(can_sync, egui::Button::new(self.sync_status.label()).small().frame(false))
+                                    .on_hover_text(hover)
+                                    .on_disabled_hover_text("Sync unavailable")
+                                    .clicked()
+                                {
+                                    self.start_sync();
+                                }
+                            }
+                        });
+                    });
                 });
         });
+    }
 
-        // Main UI
-        egui::CentralPanel::default().show(ctx, |ui| {
-            ui.horizontal(|ui| {
-                // Sidebar
-                ui.allocate_ui_with_layout(
-                    [250.0, ui.available_height()].into(),
-                    egui::Layout::top_down(egui::Align::LEFT),
-                    |ui| {
-                        egui::Frame::none()
-                            .fill(egui::Color32::from_rgb(30, 30, 40))
-                            .show(ui, |ui| {
-                                self.render_sidebar(ctx, ui);
-                            });
-                    }
-                );
+} // end impl RiaApp
 
-                ui.separator();
+// Auxiliary enums and impls follow.
 
-                // Chat area
-                ui.allocate_ui_with_layout(
-                    [ui.available_width(), ui.available_height()].into(),
-                    egui::Layout::top_down(egui::Align::LEFT),
-                    |ui| {
-                        self.render_chat_area(ctx, ui);
-                        ui.add_space(8.0);
-                        self.ui_diagnostics_panel(ui);
-                    }
-                );
-            });
-        });
+impl eframe::App for RiaApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        // Update animation time
+        self.animation_time += ctx.input(|i| i.stable_dt);
+        
+        // Update notifications (remove expired ones)
+        self.update_notifications();
+
+        self.focus_manager.begin_frame(ctx);
+        self.handle_events(ctx);
+        self.update_sync_status();
+        self.enforce_retention();
+        self.update_power_mode();
+        self.unload_idle_model();
+        self.handle_tray(ctx);
+        self.handle_close_request(ctx);
+        self.handle_quick_ask(ctx);
+        self.poll_runtime_install();
+        self.track_window_geometry(ctx);
+        self.update_crash_snapshot();
+        self.handle_dropped_files(ctx);
+        self.sync_image_thumbnails(ctx);
+
+        // Settings, profiles and imports all edit config.theme directly
+        if self.config.theme != self.theme || self.config.appearance != self.appearance {
+            theme::apply(ctx, &self.config.theme, &self.config.appearance);
+            self.theme = self.config.theme.clone();
+            self.appearance = self.config.appearance.clone();
+        }
+        if self.config.fonts != self.fonts {
+            let (fonts, warnings) = fonts::build_font_definitions(&self.config.fonts);
+            ctx.set_fonts(fonts);
+            self.fonts = self.config.fonts.clone();
+            for warning in warnings {
+                self.show_warning(warning);
+            }
+        }
+        let palette = Palette::current(ctx);
+
+        self.poll_provider_incidents();
+        // Handle keyboard shortcuts and navigation
+        self.handle_keyboard_shortcuts(ctx);
+
+        self.handle_finished_downloads();
+
+        self.show_settings_window(ctx);
+        self.show_models_window(ctx);
+
+        // Drain streaming channels and finish completed replies
+        self.poll_generations();
+        self.translations.values_mut().for_each(Translation::poll);
+
+        self.render_status_bar(ctx, &palette);
 
+        self.render_main_panel(ctx, &palette);
         
-        // Render notifications (toast popups)
+        // Render notifications (toast popups) and the history window
         self.render_notifications(ctx);
+        self.notification_center.show(ctx);
+        self.show_snippet_form(ctx);
+        if self.show_favorites {
+            self.render_favorites(ctx);
+        }
+        self.render_stats(ctx);
+        self.render_feedback_comment(ctx);
+        self.render_prune_preview(ctx);
+        self.render_crash_report(ctx);
+
+        self.schedule_repaint(ctx);
+    }
+
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        // Already done if the window was closed normally; not if the app is quitting another way
+        self.stop_background_work();
+        if let Some(saver) = &self.config_saver {
+            saver.flush();
+        }
+        crash::discard_recovery();
+        // Write only the geometry so unsaved edits in the settings window stay unsaved
+        let mut saved = AppConfig::load().unwrap_or_else(|_| self.config.clone());
+        saved.window_size = self.config.window_size;
+        saved.window_position = self.config.window_position;
+        saved.window_maximized = self.config.window_maximized;
+        if let Err(e) = saved.save() {
+            tracing::error!("Failed to save window geometry: {}", e);
+        }
+        if let Some(name) = self.switch_workspace_to.take() {
+            self.workspaces.set_active(&name);
+            if let Err(e) = self.workspaces.save() {
+                tracing::error!("Failed to switch to workspace {}: {}", name, e);
+                return;
+            }
+            let relaunch = std::env::current_exe().and_then(|exe| std::process::Command::new(exe).args(std::env::args_os().skip(1)).spawn());
+            if let Err(e) = relaunch {
+                tracing::error!("Failed to restart into workspace {}: {}", name, e);
+            }
+        }
+    }
+}
 
-        // Request repaint for smooth animations
-        ctx.request_repaint();
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_focus_manager_uses_egui_focus() {
+        let ctx = egui::Context::default();
+        let mut focus = FocusManager::new();
+        focus.set_focus(FocusableElement::SendButton);
+        for _ in 0..2 {
+            let _ = ctx.run(egui::RawInput::default(), |ctx| {
+                focus.begin_frame(ctx);
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    let new_chat = ui.button("New chat");
+                    focus.register(FocusableElement::NewChatButton, &new_chat);
+                    let send = ui.button("Send");
+                    focus.register(FocusableElement::SendButton, &send);
+                });
+            });
+        }
+        let _ = ctx.run(egui::RawInput::default(), |ctx| focus.begin_frame(ctx));
+        assert_eq!(focus.current_focus, Some(FocusableElement::SendButton));
+        assert!(focus.pending_focus.is_none());
     }
-}
\ No newline at end of file
+}
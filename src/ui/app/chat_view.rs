@@ -0,0 +1,2028 @@
+//! The chat view: the sidebar with the chat list, the conversation and the input area.
+
+use super::*;
+
+/// What the chat view keeps between frames.
+#[derive(Default)]
+pub(super) struct ChatView {
+    pub(super) input_text: String,
+    /// Message to scroll into view on the next frame (Favorites → jump to context)
+    pub(super) scroll_to_message: Option<String>,
+    pub(super) scroll: ChatScroll,
+    /// Sidebar search over chat titles and messages
+    pub(super) session_search: String,
+    /// Whether the sidebar search also covers archived chats
+    pub(super) search_archived: bool,
+    /// Messages found by the storage index for the search text they were looked up for;
+    /// cleared when a chat is saved
+    pub(super) message_hits: Option<(String, Vec<MessageHit>)>,
+    /// Tags a chat must all have to be listed in the sidebar
+    pub(super) tag_filter: Vec<String>,
+    /// Chats ticked in the sidebar's select mode, or `None` outside it
+    pub(super) session_selection: Option<HashSet<String>>,
+    /// Bulk delete was pressed once and waits for confirmation
+    pub(super) confirm_bulk_delete: bool,
+    /// The chat list beside the chat was hidden
+    pub(super) sidebar_collapsed: bool,
+    /// In the narrow layout, the chat list is shown in place of the chat
+    pub(super) sidebar_drawer: bool,
+}
+
+/// Windows narrower than this (phones, tablets in portrait) show the chat list in place of
+/// the chat rather than beside it.
+const NARROW_WIDTH: f32 = 720.0;
+
+/// Width of the chat list beside the chat.
+const SIDEBAR_WIDTH: f32 = 250.0;
+
+/// Most message matches listed under the sidebar search.
+const MESSAGE_HIT_LIMIT: usize = 20;
+
+/// What the main panel shows.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum MainLayout {
+    SidebarAndChat,
+    Chat,
+    Sidebar,
+}
+
+impl ChatView {
+    fn layout(&self, width: f32) -> MainLayout {
+        if width < NARROW_WIDTH {
+            if self.sidebar_drawer { MainLayout::Sidebar } else { MainLayout::Chat }
+        } else if self.sidebar_collapsed {
+            MainLayout::Chat
+        } else {
+            MainLayout::SidebarAndChat
+        }
+    }
+
+    /// Show or hide the chat list in the layout for a window `width` wide.
+    fn toggle_sidebar(&mut self, width: f32) {
+        if width < NARROW_WIDTH {
+            self.sidebar_drawer = !self.sidebar_drawer;
+        } else {
+            self.sidebar_collapsed = !self.sidebar_collapsed;
+        }
+    }
+}
+
+/// Distance from the end of the chat that still counts as "at the bottom".
+const BOTTOM_SLACK: f32 = 24.0;
+
+/// Whether the chat view follows new output. Scrolling up stops following so streaming
+/// doesn't pull the view back down; reaching the bottom again resumes it.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct ChatScroll {
+    /// Session the state belongs to; switching chats starts at the bottom again.
+    session: Option<String>,
+    following: bool,
+    /// Content height when the user last saw the bottom.
+    seen_height: f32,
+    /// Scrolling to the end was requested and hasn't arrived yet.
+    jump: bool,
+}
+
+impl Default for ChatScroll {
+    fn default() -> Self {
+        Self { session: None, following: true, seen_height: 0.0, jump: false }
+    }
+}
+
+impl ChatScroll {
+    pub(super) fn jump_to_bottom(&mut self) {
+        self.following = true;
+        self.jump = true;
+    }
+
+    /// Update from the scroll area after it was shown. Returns whether content arrived
+    /// below the viewport since the user scrolled away.
+    fn observe(&mut self, offset: f32, viewport: f32, content: f32) -> bool {
+        let at_bottom = offset + viewport >= content - BOTTOM_SLACK;
+        if at_bottom {
+            self.following = true;
+            self.jump = false;
+            self.seen_height = content;
+        } else if !self.jump {
+            self.following = false;
+        }
+        !self.following && content > self.seen_height + 1.0
+    }
+}
+
+/// Collapsible "Inspect" drawer with what the model was given for a reply.
+/// `text` on one line, cut to `max_chars` with an ellipsis.
+fn one_line(text: &str, max_chars: usize) -> String {
+    let flat = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    match flat.char_indices().nth(max_chars) {
+        Some((end, _)) => format!("{}…", flat[..end].trim_end()),
+        None => flat,
+    }
+}
+
+/// Reply, copy, quote, translate and delete entries for a message, shown on right-click and
+/// under "⋯". Copying is done here; the rest is returned for the caller. `language` is the
+/// translation language picked last.
+fn message_menu(ui: &mut egui::Ui, message: &ChatMessage, language: &str) -> Option<MessageAction> {
+    let mut action = None;
+    if ui.button("📋 Copy").clicked() {
+        ui.output_mut(|o| o.copied_text = message.content.clone());
+        ui.close_menu();
+    }
+    if ui.button("📝 Copy as Markdown").clicked() {
+        ui.output_mut(|o| o.copied_text = message.to_markdown());
+        ui.close_menu();
+    }
+    if ui.button("↩ Reply to this").clicked() {
+        action = Some(MessageAction::Reply);
+        ui.close_menu();
+    }
+    if ui.button("❝ Quote in reply").clicked() {
+        action = Some(MessageAction::Quote);
+        ui.close_menu();
+    }
+    if message.feedback.is_some() && ui.button("💬 Comment on rating…").clicked() {
+        action = Some(MessageAction::CommentOnRating);
+        ui.close_menu();
+    }
+    if ui.button(format!("🌐 Translate to {language}")).clicked() {
+        action = Some(MessageAction::Translate(language.to_string()));
+        ui.close_menu();
+    }
+    ui.menu_button("🌐 Translate to…", |ui| {
+        for other in translate::LANGUAGES {
+            if ui.button(other).clicked() {
+                action = Some(MessageAction::Translate(other.to_string()));
+                ui.close_menu();
+            }
+        }
+    });
+    ui.separator();
+    if ui.button("🗑 Delete message").clicked() {
+        action = Some(MessageAction::Delete);
+        ui.close_menu();
+    }
+    action
+}
+
+/// A translation under its message. Returns true when it is closed.
+fn render_translation(ui: &mut egui::Ui, translation: &Translation) -> bool {
+    let mut close = false;
+    ui.horizontal(|ui| {
+        ui.add_space(52.0);
+        ui.group(|ui| {
+            ui.vertical(|ui| {
+                ui.horizontal(|ui| {
+                    ui.label(egui::RichText::new(format!("🌐 {}", translation.language)).small().strong());
+                    if translation.is_streaming() {
+                        ui.spinner();
+                    } else if ui.small_button("📋").on_hover_text("Copy translation").clicked() {
+                        ui.output_mut(|o| o.copied_text = translation.text.clone());
+                    }
+                    close = ui.small_button("✕").on_hover_text("Hide translation").clicked();
+                });
+                ui.label(&translation.text);
+            });
+        });
+    });
+    close
+}
+
+fn render_trace(ui: &mut egui::Ui, message_id: &str, trace: &crate::ai::inference::GenerationTrace) {
+    egui::CollapsingHeader::new(egui::RichText::new("🔍 Inspect").small())
+        .id_salt(("inspect", message_id))
+        .show(ui, |ui| {
+            egui::Grid::new(("inspect_grid", message_id)).num_columns(2).spacing([12.0, 2.0]).show(ui, |ui| {
+                ui.label("Provider");
+                ui.monospace(&trace.provider);
+                ui.end_row();
+                ui.label("Execution provider");
+                ui.monospace(trace.execution_provider.as_deref().unwrap_or("n/a"));
+                ui.end_row();
+                ui.label("Sampler");
+                ui.monospace(format!(
+                    "temperature {:.2}, top_p {:.2}, top_k {}, max_tokens {}",
+                    trace.temperature, trace.top_p, trace.top_k, trace.max_tokens
+                ));
+                ui.end_row();
+                ui.label("Tokens");
+                ui.monospace(trace.token_ids.len().to_string());
+                ui.end_row();
+                if !trace.logit_bias.is_empty() {
+                    ui.label("Logit bias");
+                    ui.monospace(trace.logit_bias.iter().map(|(word, bias)| format!("{word:?} {bias:+}")).collect::<Vec<_>>().join(", "));
+                    ui.end_row();
+                }
+            });
+
+            ui.horizontal(|ui| {
+                ui.label(egui::RichText::new("Prompt").strong());
+                if ui.small_button("📋").on_hover_text("Copy prompt").clicked() {
+                    ui.output_mut(|o| o.copied_text = trace.prompt.clone());
+                }
+            });
+            egui::ScrollArea::vertical()
+                .max_height(200.0)
+                .id_salt(("inspect_prompt", message_id))
+                .show(ui, |ui| {
+                    ui.label(egui::RichText::new(&trace.prompt).monospace().size(12.0));
+                });
+
+            if !trace.token_ids.is_empty() {
+                let ids = trace.token_ids.iter().map(i64::to_string).collect::<Vec<_>>().join(" ");
+                ui.horizontal(|ui| {
+                    ui.label(egui::RichText::new("Token ids").strong());
+                    if ui.small_button("📋").on_hover_text("Copy token ids").clicked() {
+                        ui.output_mut(|o| o.copied_text = ids.clone());
+                    }
+                });
+                egui::ScrollArea::vertical()
+                    .max_height(120.0)
+                    .id_salt(("inspect_tokens", message_id))
+                    .show(ui, |ui| {
+                        ui.label(egui::RichText::new(&ids).monospace().size(12.0));
+                    });
+            }
+        });
+}
+
+/// Clicks in the sidebar's chat list, handled after the list is drawn.
+#[derive(Debug, Clone, PartialEq)]
+enum SessionAction {
+    Open(usize),
+    /// Open the chat scrolled to this message.
+    OpenAt(usize, String),
+    Stop(String),
+    /// Tick or untick the chat in select mode.
+    ToggleSelected(String),
+    SetArchived(String, bool),
+    SetPinned(String, bool),
+    /// Add or remove a tag on the chat.
+    ToggleTag(String, String),
+}
+
+/// Per-message buttons that change app state, handled after the message list is drawn.
+#[derive(Debug, Clone, PartialEq)]
+enum MessageAction {
+    Branch,
+    ToggleStar,
+    /// Put the message into the input as a block quote.
+    Quote,
+    /// Make the next message a reply to this one.
+    Reply,
+    /// Scroll to the message with this id, which this one replies to.
+    ShowOriginal(String),
+    Delete,
+    /// Rate the reply; rating it the same way again clears the rating.
+    Rate(Rating),
+    /// Add or edit the comment on the reply's rating.
+    CommentOnRating,
+    /// Show the message translated into this language under it.
+    Translate(String),
+}
+
+impl RiaApp {
+    /// The chat list beside the chat, or in narrow windows one of them, with the diagnostics
+    /// panel under the chat when open.
+    pub(super) fn render_main_panel(&mut self, ctx: &egui::Context, palette: &Palette) {
+        let layout = self.chat_view.layout(ctx.screen_rect().width());
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                if layout != MainLayout::Chat {
+                    let width = if layout == MainLayout::Sidebar { ui.available_width() } else { SIDEBAR_WIDTH };
+                    let session_before = self.current_session;
+                    ui.allocate_ui_with_layout(
+                        [width, ui.available_height()].into(),
+                        egui::Layout::top_down(egui::Align::LEFT),
+                        |ui| {
+                            egui::Frame::none()
+                                .fill(palette.sidebar_fill)
+                                .show(ui, |ui| {
+                                    self.render_sidebar(ctx, ui);
+                                });
+                        }
+                    );
+                    // Opening or starting a chat from the drawer shows it
+                    if self.current_session != session_before {
+                        self.chat_view.sidebar_drawer = false;
+                    }
+                    if layout == MainLayout::Sidebar {
+                        return;
+                    }
+                    ui.separator();
+                }
+
+                // Chat area
+                ui.allocate_ui_with_layout(
+                    [ui.available_width(), ui.available_height()].into(),
+                    egui::Layout::top_down(egui::Align::LEFT),
+                    |ui| {
+                        self.render_chat_area(ctx, ui);
+                        ui.add_space(8.0);
+                        self.ui_diagnostics_panel(ui);
+                    }
+                );
+            });
+        });
+    }
+
+    /// Status bar button that shows or hides the chat list.
+    pub(super) fn sidebar_toggle(&mut self, ui: &mut egui::Ui) {
+        let response = ui.add(egui::Button::new("☰").frame(false)).on_hover_text("Show or hide the chat list");
+        a11y::set_name(&response, "Chat list");
+        if response.clicked() {
+            self.chat_view.toggle_sidebar(ui.ctx().screen_rect().width());
+        }
+    }
+
+    /// Switch between workspaces or create one; either restarts into the chosen workspace.
+    fn render_workspace_menu(&mut self, ui: &mut egui::Ui) {
+        let active = self.workspaces.active().to_string();
+        let mut chosen = None;
+        let menu = ui.menu_button(format!("🗂 {active}"), |ui| {
+            ui.label(egui::RichText::new("Workspaces keep their own settings, chats and default model").small().weak());
+            for name in self.workspaces.all() {
+                if ui.selectable_label(name == active, name).clicked() {
+                    chosen = Some(name.to_string());
+                    ui.close_menu();
+                }
+            }
+            ui.separator();
+            let draft_id = egui::Id::new("new_workspace_name");
+            let mut draft = ui.data_mut(|d| d.get_temp::<String>(draft_id).unwrap_or_default());
+            ui.horizontal(|ui| {
+                let field = ui.add(egui::TextEdit::singleline(&mut draft).hint_text("New workspace").desired_width(120.0));
+                let submitted = field.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                if (ui.add_enabled(!draft.trim().is_empty(), egui::Button::new("➕")).on_hover_text("Create it and switch to it").clicked() || submitted)
+                    && !draft.trim().is_empty()
+                {
+                    match self.workspaces.create(&workspaces::root(), &draft, &self.config) {
+                        Ok(name) => {
+                            chosen = Some(name);
+                            draft.clear();
+                            ui.close_menu();
+                        }
+                        Err(e) => self.show_error(e.to_string()),
+                    }
+                }
+            });
+            ui.data_mut(|d| d.insert_temp(draft_id, draft));
+        });
+        a11y::set_name(&menu.response.on_hover_text("Workspace"), "Switch workspace");
+        if let Some(name) = chosen.filter(|name| *name != active) {
+            if let Err(e) = self.workspaces.save() {
+                self.show_error(format!("Couldn't save the workspace list: {e}"));
+                return;
+            }
+            self.switch_workspace_to = Some(name);
+            #[cfg(feature = "tray")]
+            {
+                self.quit_requested = true;
+            }
+            ui.ctx().send_viewport_cmd(egui::ViewportCommand::Close);
+        }
+    }
+
+    /// One chat in the sidebar list, with a tick box in select mode and Archive in its context menu.
+    fn render_session_row(&self, ui: &mut egui::Ui, palette: &Palette, i: usize) -> Option<SessionAction> {
+        let session = &self.chat_sessions[i];
+        let mut action = None;
+        ui.horizontal(|ui| {
+            ui.add_space(20.0);
+            if let Some(selection) = &self.chat_view.session_selection {
+                let mut ticked = selection.contains(&session.id);
+                if ui.checkbox(&mut ticked, "").changed() {
+                    action = Some(SessionAction::ToggleSelected(session.id.clone()));
+                }
+            }
+            let selected = self.current_session == Some(i);
+            let generating = self.chat.is_generating(&session.id);
+
+            let mut label = if session.branched_from.is_some() { format!("🌿 {}", session.title) } else { session.title.clone() };
+            if session.pinned {
+                label = format!("📌 {label}");
+            }
+            if generating {
+                label = format!("⏳ {label}");
+            }
+            let button = egui::Button::new(label)
+                .fill(if selected {
+                    palette.sidebar_selected
+                } else {
+                    egui::Color32::TRANSPARENT
+                });
+
+            let mut response = ui.add_sized([200.0, 30.0], button);
+            tags::dots(ui, &session.tags);
+            if let Some(origin) = &session.branched_from {
+                let parent = self.chat_sessions.iter().find(|s| s.id == origin.session_id).map(|s| s.title.as_str());
+                response = response.on_hover_text(format!("Branched from '{}'", parent.unwrap_or("a deleted chat")));
+            }
+            if response.clicked() {
+                action = Some(match &self.chat_view.session_selection {
+                    Some(_) => SessionAction::ToggleSelected(session.id.clone()),
+                    None => SessionAction::Open(i),
+                });
+            }
+            response.context_menu(|ui| {
+                let label = if session.archived { "📤 Unarchive" } else { "📦 Archive" };
+                if ui.button(label).clicked() {
+                    action = Some(SessionAction::SetArchived(session.id.clone(), !session.archived));
+                    ui.close_menu();
+                }
+                let label = if session.pinned { "📌 Unpin" } else { "📌 Pin" };
+                if ui.button(label).on_hover_text("Pinned chats are never pruned from history").clicked() {
+                    action = Some(SessionAction::SetPinned(session.id.clone(), !session.pinned));
+                    ui.close_menu();
+                }
+                ui.separator();
+                ui.label(egui::RichText::new("Tags").small().weak());
+                for tag in tags::known_tags(&self.chat_sessions) {
+                    let mut has = session.tags.contains(&tag);
+                    let text = egui::RichText::new(format!("● {tag}")).color(tags::color(&tag));
+                    if ui.checkbox(&mut has, text).changed() {
+                        action = Some(SessionAction::ToggleTag(session.id.clone(), tag));
+                    }
+                }
+                let draft_id = egui::Id::new(("new_tag", &session.id));
+                let mut draft = ui.data_mut(|d| d.get_temp::<String>(draft_id).unwrap_or_default());
+                let field = ui.add(egui::TextEdit::singleline(&mut draft).hint_text("New tag").desired_width(120.0));
+                if field.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                    if let Some(tag) = tags::normalize(&draft).filter(|t| !session.tags.contains(t)) {
+                        action = Some(SessionAction::ToggleTag(session.id.clone(), tag));
+                    }
+                    draft.clear();
+                }
+                ui.data_mut(|d| d.insert_temp(draft_id, draft));
+            });
+            if generating {
+                let stop_button = ui.small_button("⏹").on_hover_text("Stop this chat's reply");
+                a11y::set_name(&stop_button, "Stop reply");
+                if stop_button.clicked() {
+                    action = Some(SessionAction::Stop(session.id.clone()));
+                }
+            }
+        });
+        action
+    }
+
+    /// Messages matching the sidebar search, looked up in the storage backend's index once
+    /// per search text.
+    fn render_message_hits(&mut self, ui: &mut egui::Ui, query: &str, include_archived: bool) -> Option<SessionAction> {
+        if self.chat_view.message_hits.as_ref().is_none_or(|(searched, _)| searched != query) {
+            let hits = match self.storage.as_ref().map(|storage| storage.search_messages(query, MESSAGE_HIT_LIMIT)) {
+                Some(Ok(hits)) => hits,
+                Some(Err(e)) => {
+                    tracing::warn!("Message search failed: {}", e);
+                    Vec::new()
+                }
+                None => Vec::new(),
+            };
+            self.chat_view.message_hits = Some((query.to_string(), hits));
+        }
+        let (_, hits) = self.chat_view.message_hits.as_ref()?;
+        let hits: Vec<(usize, &MessageHit)> = hits
+            .iter()
+            .filter_map(|hit| {
+                let idx = self.chat_sessions.iter().position(|s| s.id == hit.session_id)?;
+                (include_archived || !self.chat_sessions[idx].archived).then_some((idx, hit))
+            })
+            .collect();
+        if hits.is_empty() {
+            return None;
+        }
+        let mut action = None;
+        ui.add_space(6.0);
+        ui.horizontal(|ui| {
+            ui.add_space(20.0);
+            ui.label(egui::RichText::new("Messages").small().weak());
+        });
+        for (idx, hit) in hits {
+            ui.horizontal(|ui| {
+                ui.add_space(20.0);
+                let text = format!("{}\n{}", self.chat_sessions[idx].title, hit.snippet.replace('\n', " "));
+                let button = egui::Button::new(egui::RichText::new(text).small()).frame(false).wrap();
+                if ui.add_sized([200.0, 0.0], button).on_hover_text("Open the chat at this message").clicked() {
+                    action = Some(SessionAction::OpenAt(idx, hit.message_id.clone()));
+                }
+            });
+        }
+        action
+    }
+
+    /// Select-mode toolbar: archive, restore or delete the ticked chats.
+    fn render_bulk_actions(&mut self, ui: &mut egui::Ui, selection: HashSet<String>) {
+        let all_archived = !selection.is_empty() && self.chat_sessions.iter().filter(|s| selection.contains(&s.id)).all(|s| s.archived);
+        ui.horizontal(|ui| {
+            ui.add_space(20.0);
+            ui.label(egui::RichText::new(format!("{} selected", selection.len())).small());
+            if ui.small_button("All").on_hover_text("Select every chat in the list").clicked() {
+                let query = self.chat_view.session_search.trim();
+                let include_archived = !query.is_empty() && self.chat_view.search_archived;
+                let visible = self.chat_sessions.iter().filter(|s| {
+                    s.matches(query) && tags::matches_filter(s, &self.chat_view.tag_filter) && (!s.archived || include_archived)
+                });
+                self.chat_view.session_selection = Some(visible.map(|s| s.id.clone()).collect());
+            }
+            ui.add_enabled_ui(!selection.is_empty(), |ui| {
+                let (label, hover) = if all_archived { ("📤", "Move back to the chat list") } else { ("📦", "Archive") };
+                if ui.small_button(label).on_hover_text(hover).clicked() {
+                    self.set_archived(&selection, !all_archived);
+                    self.chat_view.session_selection = None;
+                }
+                if self.chat_view.confirm_bulk_delete {
+                    let confirm = egui::Button::new(egui::RichText::new("Delete?").color(ui.visuals().error_fg_color)).small();
+                    if ui.add(confirm).on_hover_text("Click again to delete the selected chats for good").clicked() {
+                        let count = selection.len();
+                        self.delete_sessions(&selection);
+                        self.chat_view.session_selection = None;
+                        self.chat_view.confirm_bulk_delete = false;
+                        self.show_info(format!("Deleted {count} chat{}", if count == 1 { "" } else { "s" }));
+                    }
+                } else if ui.small_button("🗑").on_hover_text("Delete").clicked() {
+                    self.chat_view.confirm_bulk_delete = true;
+                }
+            });
+            if ui.small_button("✕").on_hover_text("Leave select mode").clicked() {
+                self.chat_view.session_selection = None;
+                self.chat_view.confirm_bulk_delete = false;
+            }
+        });
+    }
+
+    fn render_sidebar(&mut self, ctx: &egui::Context, ui: &mut egui::Ui) {
+        let palette = Palette::current(ctx);
+        ui.with_layout(egui::Layout::top_down(egui::Align::LEFT), |ui| {
+            // Header with app title
+            ui.add_space(20.0);
+            ui.horizontal(|ui| {
+                ui.add_space(20.0);
+                ui.label(
+                    egui::RichText::new("🤖 RIA AI Chat")
+                        .size(24.0)
+                        .strong()
+                        .color(palette.accent)
+                );
+            });
+            
+            ui.add_space(8.0);
+            ui.horizontal(|ui| {
+                ui.add_space(20.0);
+                self.render_workspace_menu(ui);
+            });
+
+            ui.add_space(22.0);
+
+            // New Chat button
+            ui.horizontal(|ui| {
+                ui.add_space(20.0);
+                let new_chat = ui.add_sized([200.0, 40.0], egui::Button::new("➕ New Chat"));
+                a11y::set_name(&new_chat, "New chat");
+                self.focus_manager.register(FocusableElement::NewChatButton, &new_chat);
+                if new_chat.clicked() {
+                    self.create_new_session();
+                }
+                let personas = ui.menu_button("🎭", |ui| {
+                    ui.label(egui::RichText::new("New chat with persona").small().color(palette.muted_text));
+                    let mut chosen = None;
+                    for persona in &self.personas.personas {
+                        let hover = persona.preferred_model.as_deref().map(|m| format!("{}\n\nModel: {m}", persona.system_prompt)).unwrap_or_else(|| persona.system_prompt.clone());
+                        if ui.button(&persona.name).on_hover_text(hover).clicked() {
+                            chosen = Some(persona.name.clone());
+                        }
+                    }
+                    if self.personas.personas.is_empty() {
+                        ui.label("No personas yet; add them in Settings");
+                    }
+                    if let Some(name) = chosen {
+                        self.create_session_with_persona(Some(&name));
+                        ui.close_menu();
+                    }
+                });
+                a11y::set_name(&personas.response.on_hover_text("New chat with a persona"), "New chat with persona");
+            });
+
+            ui.add_space(20.0);
+            ui.separator();
+            ui.add_space(20.0);
+
+            // Chat sessions list
+            ui.horizontal(|ui| {
+                ui.add_space(20.0);
+                ui.label(egui::RichText::new("Recent Chats").size(16.0).strong());
+                let favorites = ui.small_button("⭐").on_hover_text("Favorites: starred messages from all chats");
+                a11y::set_name(&favorites, "Favorites");
+                if favorites.clicked() {
+                    self.show_favorites = !self.show_favorites;
+                }
+                let stats = ui.small_button("📊").on_hover_text("Usage statistics");
+                a11y::set_name(&stats, "Usage statistics");
+                if stats.clicked() {
+                    self.show_stats = !self.show_stats;
+                }
+            });
+
+            ui.add_space(6.0);
+            ui.horizontal(|ui| {
+                ui.add_space(20.0);
+                let search = ui.add_sized([170.0, 24.0], egui::TextEdit::singleline(&mut self.chat_view.session_search).hint_text("🔍 Search chats"));
+                a11y::set_name(&search, "Search chats");
+                let selecting = self.chat_view.session_selection.is_some();
+                let select = ui.selectable_label(selecting, "☑").on_hover_text("Select chats to archive or delete");
+                a11y::set_name(&select, "Select chats");
+                if select.clicked() {
+                    self.chat_view.session_selection = if selecting { None } else { Some(HashSet::new()) };
+                    self.chat_view.confirm_bulk_delete = false;
+                }
+            });
+            let query = self.chat_view.session_search.trim().to_string();
+            if !query.is_empty() {
+                ui.horizontal(|ui| {
+                    ui.add_space(20.0);
+                    ui.checkbox(&mut self.chat_view.search_archived, "Include archived");
+                });
+            }
+            let used_tags = tags::known_tags(&self.chat_sessions).into_iter().filter(|t| self.chat_sessions.iter().any(|s| s.tags.contains(t))).collect::<Vec<_>>();
+            if !used_tags.is_empty() {
+                ui.horizontal(|ui| {
+                    ui.add_space(20.0);
+                    ui.horizontal_wrapped(|ui| {
+                        ui.set_max_width(200.0);
+                        for tag in &used_tags {
+                            let active = self.chat_view.tag_filter.contains(tag);
+                            if tags::chip(ui, tag, active).on_hover_text("Show only chats with this tag").clicked() {
+                                if active {
+                                    self.chat_view.tag_filter.retain(|t| t != tag);
+                                } else {
+                                    self.chat_view.tag_filter.push(tag.clone());
+                                }
+                            }
+                        }
+                    });
+                });
+            }
+            self.chat_view.tag_filter.retain(|t| used_tags.contains(t));
+            if let Some(selection) = &self.chat_view.session_selection {
+                self.render_bulk_actions(ui, selection.clone());
+            }
+
+            ui.add_space(10.0);
+
+            let include_archived = !query.is_empty() && self.chat_view.search_archived;
+            let mut actions = Vec::new();
+            let mut archived = Vec::new();
+            for (i, session) in self.chat_sessions.iter().enumerate() {
+                if !session.matches(&query) || !tags::matches_filter(session, &self.chat_view.tag_filter) {
+                    continue;
+                }
+                if session.archived && !include_archived {
+                    archived.push(i);
+                    continue;
+                }
+                actions.extend(self.render_session_row(ui, &palette, i));
+            }
+            if !query.is_empty() {
+                actions.extend(self.render_message_hits(ui, &query, include_archived));
+            }
+            if query.is_empty() && !archived.is_empty() {
+                ui.horizontal(|ui| {
+                    ui.add_space(20.0);
+                    ui.vertical(|ui| {
+                        egui::CollapsingHeader::new(format!("📦 Archive ({})", archived.len())).id_salt("sidebar_archive").default_open(false).show(ui, |ui| {
+                            for &i in &archived {
+                                actions.extend(self.render_session_row(ui, &palette, i));
+                            }
+                        });
+                    });
+                });
+            }
+            for action in actions {
+                match action {
+                    SessionAction::Open(i) => self.current_session = Some(i),
+                    SessionAction::OpenAt(i, message_id) => {
+                        self.current_session = Some(i);
+                        self.chat_view.scroll_to_message = Some(message_id);
+                    }
+                    SessionAction::Stop(session_id) => self.stop_generation(&session_id),
+                    SessionAction::ToggleSelected(session_id) => {
+                        if let Some(selection) = self.chat_view.session_selection.as_mut() {
+                            if !selection.remove(&session_id) {
+                                selection.insert(session_id);
+                            }
+                            self.chat_view.confirm_bulk_delete = false;
+                        }
+                    }
+                    SessionAction::SetArchived(session_id, archive) => self.set_archived(&HashSet::from([session_id]), archive),
+                    SessionAction::SetPinned(session_id, pinned) => {
+                        if let Some(idx) = self.chat_sessions.iter().position(|s| s.id == session_id) {
+                            self.chat_sessions[idx].pinned = pinned;
+                            self.chat_sessions[idx].updated_at = chrono::Utc::now();
+                            self.persist_session(idx);
+                        }
+                    }
+                    SessionAction::ToggleTag(session_id, tag) => {
+                        if let Some(idx) = self.chat_sessions.iter().position(|s| s.id == session_id) {
+                            self.chat_sessions[idx].toggle_tag(&tag);
+                            self.chat_sessions[idx].updated_at = chrono::Utc::now();
+                            self.persist_session(idx);
+                        }
+                    }
+                }
+            }
+
+            // Bottom controls
+            ui.with_layout(egui::Layout::bottom_up(egui::Align::LEFT), |ui| {
+                ui.add_space(20.0);
+                
+                // Model status with enhanced information
+                ui.vertical(|ui| {
+                    ui.horizontal(|ui| {
+                        ui.add_space(20.0);
+                        if self.model_loaded {
+                            let status = ui.colored_label(egui::Color32::GREEN, "🟢 AI Model Active");
+                            if let Some(details) = &self.model_details {
+                                let summary = ["model_path", "execution_provider"].iter().filter_map(|k| details.get(*k)).cloned().collect::<Vec<_>>().join("\n");
+                                status.on_hover_text(summary);
+                                if ui.small_button("ℹ").on_hover_text("Model details").clicked() {
+                                    self.diagnostics_view.open = true;
+                                }
+                            }
+                            if self.onnx_provider_id.is_some()
+                                && ui.small_button("⏏").on_hover_text("Unload the model and free its memory").clicked()
+                            {
+                                self.unload_model();
+                            }
+                        } else if self.idle_unloaded.is_some() || self.waking_model {
+                            ui.colored_label(egui::Color32::GRAY, "💤 Model unloaded while idle")
+                                .on_hover_text("It reloads when you send the next message");
+                        } else {
+                            ui.colored_label(palette.warning, "⚡ Demo Mode");
+                        }
+                    });
+                    
+                    // Additional status info
+                    if !self.model_loaded {
+                        ui.add_space(2.0);
+                        ui.horizontal(|ui| {
+                            ui.add_space(20.0);
+                            ui.label(
+                                egui::RichText::new("Intelligent responses active")
+                                    .size(11.0)
+                                    .color(egui::Color32::GRAY)
+                            );
+                        });
+                        
+                        // Show hint about ONNX Runtime if there were loading errors
+                        if self.notifications.iter().any(|n| n.message.contains("ONNX Runtime") || n.message.contains("version")) {
+                            ui.add_space(2.0);
+                            ui.horizontal(|ui| {
+                                ui.add_space(20.0);
+                                ui.hyperlink_to(
+                                    "🔧 Fix ONNX Runtime",
+                                    format!("file:///{}", std::env::current_dir().unwrap_or_default().join("FIX_NPU.md").to_string_lossy())
+                                );
+                            });
+                        }
+                    } else {
+                        // Show current model info when loaded
+                        if let Some(model_name) = &self.config.last_used_model {
+                            ui.add_space(2.0);
+                            ui.horizontal(|ui| {
+                                ui.add_space(20.0);
+                                let display_name = std::path::Path::new(model_name)
+                                    .file_name()
+                                    .and_then(|n| n.to_str())
+                                    .unwrap_or("Unknown")
+                                    .trim_end_matches(".onnx");
+                                ui.label(
+                                    egui::RichText::new(format!("Using: {}", display_name))
+                                        .size(11.0)
+                                        .color(egui::Color32::GRAY)
+                                );
+                            });
+                        }
+                    }
+                });
+                
+                ui.add_space(5.0);
+                
+                ui.horizontal(|ui| {
+                    ui.add_space(20.0);
+                    let settings = ui.add_sized([90.0, 35.0], egui::Button::new("⚙️ Settings"));
+                    a11y::set_name(&settings, "Settings");
+                    self.focus_manager.register(FocusableElement::SettingsButton, &settings);
+                    if settings.clicked() {
+                        self.settings_view.open = !self.settings_view.open;
+                    }
+                    let models = ui.add_sized([90.0, 35.0], egui::Button::new("🧠 Models"));
+                    a11y::set_name(&models, "Models");
+                    self.focus_manager.register(FocusableElement::ModelsButton, &models);
+                    if models.clicked() {
+                        self.models_view.open = !self.models_view.open;
+                    }
+                });
+            });
+        });
+    }
+
+    fn render_chat_header(&mut self, ui: &mut egui::Ui, session_idx: usize) {
+        let palette = Palette::current(ui.ctx());
+        let session_id = self.chat_sessions[session_idx].id.clone();
+        ui.horizontal(|ui| {
+            ui.label(egui::RichText::new(&self.chat_sessions[session_idx].title).strong().color(palette.heading_text));
+            if let Some(name) = &self.chat_sessions[session_idx].persona {
+                let hover = match self.session_persona(session_idx) {
+                    Some(persona) => persona.system_prompt.clone(),
+                    None => "This persona has been deleted; the chat continues without it".to_string(),
+                };
+                ui.label(egui::RichText::new(format!("🎭 {name}")).small().color(palette.muted_text)).on_hover_text(hover);
+            }
+            if let Some(prompt) = &self.chat_sessions[session_idx].system_prompt {
+                ui.label(egui::RichText::new("📝 System prompt").small().color(palette.muted_text))
+                    .on_hover_text(format!("{prompt}\n\nChange it with /system, or clear it with /system alone"));
+            }
+            self.render_token_meter(ui, session_idx);
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                let customized = self.session_sampling.get(&session_id).is_some_and(|o| o.changes_sampling());
+                let label = if customized { "🎛 Sampling •" } else { "🎛 Sampling" };
+                // The chat's persona, if any, moves the starting point away from Settings
+                let defaults = self.session_persona(session_idx).map(|p| p.overrides()).unwrap_or_default().apply(&self.config.ai_config);
+                let menu = ui.menu_button(label, |ui| {
+                    let config = &defaults;
+                    let overrides = self.session_sampling.entry(session_id.clone()).or_default();
+                    ui.label(egui::RichText::new("For this chat only").small().color(palette.muted_text));
+                    egui::Grid::new("session_sampling").num_columns(2).show(ui, |ui| {
+                        let mut temperature = overrides.temperature.unwrap_or(config.temperature);
+                        ui.label("Temperature");
+                        if ui.add(egui::Slider::new(&mut temperature, 0.0..=2.0).step_by(0.05)).changed() {
+                            overrides.temperature = Some(temperature);
+                        }
+                        ui.end_row();
+                        let mut top_p = overrides.top_p.unwrap_or(config.top_p);
+                        ui.label("Top-p");
+                        if ui.add(egui::Slider::new(&mut top_p, 0.0..=1.0).step_by(0.05)).changed() {
+                            overrides.top_p = Some(top_p);
+                        }
+                        ui.end_row();
+                        let mut top_k = overrides.top_k.unwrap_or(config.top_k);
+                        ui.label("Top-k");
+                        if ui.add(egui::Slider::new(&mut top_k, 0..=200)).on_hover_text("0 disables").changed() {
+                            overrides.top_k = Some(top_k);
+                        }
+                        ui.end_row();
+                    });
+                    ui.collapsing("Logit bias", |ui| {
+                        if !config.logit_bias.is_empty() {
+                            ui.label(egui::RichText::new(format!("Added to the {} entries from Settings", config.logit_bias.len())).small().color(palette.muted_text));
+                        }
+                        crate::ui::settings::render_logit_bias(ui, "session_logit_bias", &mut overrides.logit_bias);
+                    });
+                    if ui.add_enabled(overrides.changes_sampling(), egui::Button::new("Reset to Settings")).clicked() {
+                        *overrides = GenerationOverrides::default();
+                    }
+                    ui.label(egui::RichText::new(crate::ui::settings::SAMPLING_SCOPE).small().color(palette.muted_text));
+                });
+                let hover = if customized { "Sampling changed for this chat" } else { "Adjust sampling for this chat" };
+                let menu = menu.response.on_hover_text(hover);
+                a11y::set_name(&menu, "Sampling settings for this chat");
+
+                let mut export = None;
+                let menu = ui.menu_button("⬇ Export", |ui| {
+                    if ui.button("📝 Markdown file").clicked() {
+                        export = Some(ExportFormat::Markdown);
+                        ui.close_menu();
+                    }
+                    if ui.button("🌐 HTML page").on_hover_text("A standalone page to share the transcript").clicked() {
+                        export = Some(ExportFormat::Html);
+                        ui.close_menu();
+                    }
+                });
+                a11y::set_name(&menu.response.on_hover_text("Save this chat to the downloads folder"), "Export chat");
+                if let Some(format) = export {
+                    self.export_session(session_idx, format, ui.ctx());
+                }
+
+                let copy = ui.button("📋 Copy").on_hover_text("Copy the whole conversation as Markdown");
+                a11y::set_name(&copy, "Copy conversation");
+                if copy.clicked() {
+                    let markdown = self.chat_sessions[session_idx].to_markdown();
+                    ui.ctx().output_mut(|o| o.copied_text = markdown);
+                    self.show_success("Conversation copied as Markdown");
+                }
+            });
+        });
+        ui.separator();
+    }
+
+    /// Session title and the sampling popover above the messages.
+    /// How full the context is with this conversation, plus the tokens its replies used so far.
+    fn render_token_meter(&self, ui: &mut egui::Ui, session_idx: usize) {
+        let palette = Palette::current(ui.ctx());
+        let limits = self.config.ai_config.token_limits;
+        let tokens = self.session_prompt_tokens(session_idx);
+        let (color, status) = match limits.assess(tokens) {
+            context::BudgetLevel::Comfortable => (palette.success, None),
+            context::BudgetLevel::Slow => (palette.warning, Some("Long chat: replies will be slower")),
+            context::BudgetLevel::NearContext => (palette.warning, Some("Close to the model's context limit")),
+            context::BudgetLevel::OverContext => (palette.danger, Some("Over the model's context limit: the earliest messages may be lost")),
+        };
+        let compact = |n: u64| if n >= 1000 { format!("{:.1}k", n as f64 / 1000.0) } else { n.to_string() };
+        let (fraction, text) = if limits.context_tokens > 0 {
+            let fraction = (tokens as f32 / limits.context_tokens as f32).min(1.0);
+            (fraction, format!("{} / {} tokens", compact(tokens), compact(limits.context_tokens)))
+        } else {
+            (0.0, format!("{} tokens", compact(tokens)))
+        };
+        let usage = &self.chat_sessions[session_idx].token_usage;
+        let mut hover = format!(
+            "Prompt now: about {tokens} tokens\nSpent in this chat: {} prompt + {} completion = {} tokens over {} replies",
+            usage.prompt_tokens, usage.completion_tokens, usage.total(), usage.replies
+        );
+        if let Some(status) = status {
+            hover = format!("{status}\n{hover}");
+        }
+        ui.add(egui::ProgressBar::new(fraction).desired_width(140.0).fill(color).text(text))
+            .on_hover_text(hover);
+    }
+
+    fn render_chat_area(&mut self, ctx: &egui::Context, ui: &mut egui::Ui) {
+        if let Some(session_idx) = self.current_session {
+            let session = &self.chat_sessions[session_idx];
+            let message_gap = Metrics::current(ctx).message_gap;
+            let mut message_action = None;
+            let mut unqueue = None;
+            let (mut retry_failed, mut dismiss_failed) = (false, false);
+            let mut continue_reply = false;
+            let mut summary_context = None;
+            let mut close_translation = None;
+            let mut scrolled = false;
+            if self.chat_view.scroll.session.as_deref() != Some(session.id.as_str()) {
+                self.chat_view.scroll = ChatScroll { session: Some(session.id.clone()), ..ChatScroll::default() };
+                self.chat_view.scroll.jump_to_bottom();
+            }
+            
+            self.render_chat_header(ui, session_idx);
+            let session = &self.chat_sessions[session_idx];
+
+            // Messages area; only sticks to the bottom while the user hasn't scrolled up
+            let scroll_output = egui::ScrollArea::vertical()
+                .stick_to_bottom(self.chat_view.scroll.following)
+                .show(ui, |ui| {
+                    ui.add_space(20.0);
+
+                    let generation = self.current_generation();
+                    for message in &session.messages {
+                        // A reply being continued shows its new text in place
+                        let continued = generation.filter(|g| g.kind == ReplyKind::Continue(message.id.clone())).map(|g| ChatMessage {
+                            content: format!("{}{}", message.content, streaming::visible(&g.buffer, g.pacer.shown())),
+                            ..message.clone()
+                        });
+                        let message = continued.as_ref().unwrap_or(message);
+                        let rendered = ui.scope(|ui| self.render_message(ui, message, session.is_starred(&message.id)));
+                        if let Some(action) = rendered.inner {
+                            message_action = Some((message.id.clone(), action));
+                        }
+                        if let Some(translation) = self.translations.get(&message.id) {
+                            if render_translation(ui, translation) {
+                                close_translation = Some(message.id.clone());
+                            }
+                        }
+                        if let Some(summary) = session.summary.as_ref().filter(|s| s.message_id == message.id) {
+                            let mut replace = summary.replace_in_context;
+                            ui.horizontal(|ui| {
+                                ui.add_space(52.0);
+                                let toggle = ui.checkbox(&mut replace, egui::RichText::new("Use in place of the earlier messages").small())
+                                    .on_hover_text("Replies are generated from this summary on; the earlier messages stay in the chat");
+                                if toggle.changed() {
+                                    summary_context = Some(replace);
+                                }
+                            });
+                        }
+                        if self.chat_view.scroll_to_message.as_deref() == Some(message.id.as_str()) {
+                            rendered.response.scroll_to_me(Some(egui::Align::Center));
+                            scrolled = true;
+                        }
+                        ui.add_space(message_gap);
+                    }
+
+                    if let Some(generation) = generation.filter(|g| g.pacer.shown() == 0) {
+                        let retries = generation.retries.load(Ordering::Relaxed);
+                        if retries > 0 {
+                            ui.label(egui::RichText::new(format!(
+                                "🔄 The model hit a temporary error, retrying ({retries}/{})…",
+                                inference::MAX_TRANSIENT_RETRIES
+                            )).weak());
+                            ui.add_space(message_gap);
+                        } else if generation.running.load(Ordering::Relaxed) {
+                            // Long prompts are fed to the model in chunks before the first token arrives
+                            if let Some(progress) = self.prefill_monitor.get().filter(|p| p.done < p.total) {
+                                ui.add(
+                                    egui::ProgressBar::new(progress.fraction())
+                                        .desired_width(240.0)
+                                        .text(format!("Processing prompt {}/{} tokens", progress.done, progress.total)),
+                                );
+                                ui.add_space(message_gap);
+                            } else if self.config.streaming.typing_indicator {
+                                ui.horizontal(|ui| {
+                                    ui.add_space(8.0);
+                                    streaming::typing_indicator(ui, Palette::current(ui.ctx()).muted_text, self.config.enable_animations);
+                                });
+                                ui.add_space(message_gap);
+                            }
+                        } else {
+                            ui.label(egui::RichText::new("⏳ Waiting for another chat's reply to finish…").weak());
+                            ui.add_space(message_gap);
+                        }
+                        ui.ctx().request_repaint_after(std::time::Duration::from_millis(100));
+                    }
+
+                    // Streaming preview bubble while generating
+                    if let Some(generation) = generation.filter(|g| g.pacer.shown() > 0 && !matches!(g.kind, ReplyKind::Continue(_))) {
+                        let preview = ChatMessage {
+                            id: "streaming-preview".to_string(),
+                            content: streaming::visible(&generation.buffer, generation.pacer.shown()).to_string(),
+                            role: MessageRole::Assistant,
+                            timestamp: chrono::Utc::now(),
+                            model_used: Some("…typing".to_string()),
+                            inference_time: None,
+                            images: Vec::new(),
+                            trace: None,
+                            reply_to: None,
+                            feedback: None,
+                            edited_at: None,
+                        };
+                        self.render_message(ui, &preview, false);
+                        ui.add_space(message_gap);
+                    }
+
+                    let last_reply = session.messages.last().filter(|m| matches!(m.role, MessageRole::Assistant));
+                    if generation.is_none() && last_reply.is_some_and(|m| self.chat.is_truncated(&m.id)) {
+                        ui.horizontal(|ui| {
+                            ui.add_space(52.0);
+                            let chip = egui::Button::new(egui::RichText::new("⏩ Continue").small()).rounding(12.0);
+                            let chip = ui.add(chip).on_hover_text("The reply stopped at the token limit; generate the rest of it");
+                            a11y::set_name(&chip, "Continue the reply");
+                            continue_reply = chip.clicked();
+                        });
+                        ui.add_space(message_gap);
+                    }
+
+                    if let Some(failure) = self.chat.failure(&session.id) {
+                        ui.group(|ui| {
+                            ui.label(egui::RichText::new("⚠ The reply failed").strong().color(ui.visuals().error_fg_color));
+                            ui.label(egui::RichText::new(&failure.error).small());
+                            if failure.retries > 0 {
+                                ui.label(egui::RichText::new(format!("Gave up after {} automatic retries.", failure.retries)).small().weak());
+                            }
+                            ui.horizontal(|ui| {
+                                retry_failed = ui.button("🔄 Retry").on_hover_text("Generate the reply again").clicked();
+                                dismiss_failed = ui.small_button("Dismiss").clicked();
+                            });
+                        });
+                        ui.add_space(message_gap);
+                    }
+
+                    // Messages sent while the chat was busy, in the order they'll go out
+                    let queued = self.chat.queued().iter().enumerate().filter(|(_, (id, _))| *id == session.id);
+                    for (pos, (_, message)) in queued {
+                        ui.horizontal(|ui| {
+                            ui.label(egui::RichText::new("🕓 Queued").small().weak());
+                            if ui.small_button("✕").on_hover_text("Don't send this message").clicked() {
+                                unqueue = Some(pos);
+                            }
+                        });
+                        ui.scope(|ui| {
+                            ui.set_opacity(0.55);
+                            self.render_message(ui, message, false);
+                        });
+                        ui.add_space(message_gap);
+                    }
+
+                    let end = ui.allocate_response(egui::vec2(1.0, 1.0), egui::Sense::hover());
+                    if self.chat_view.scroll.jump && !scrolled {
+                        end.scroll_to_me(Some(egui::Align::BOTTOM));
+                    }
+                });
+
+            if let Some(pos) = unqueue {
+                self.chat.unqueue(pos);
+            }
+            if continue_reply {
+                self.continue_reply(session_idx);
+            }
+            if let Some(message_id) = close_translation {
+                self.translations.remove(&message_id);
+            }
+            if let Some(replace_in_context) = summary_context {
+                let session = &mut self.chat_sessions[session_idx];
+                if let Some(summary) = session.summary.as_mut() {
+                    summary.replace_in_context = replace_in_context;
+                    session.updated_at = chrono::Utc::now();
+                    self.persist_session(session_idx);
+                }
+            }
+            if retry_failed {
+                self.retry_reply(session_idx);
+            } else if dismiss_failed {
+                let session_id = &self.chat_sessions[session_idx].id;
+                self.chat.dismiss_failure(session_id);
+            }
+            if scrolled {
+                self.chat_view.scroll_to_message = None;
+                self.chat_view.scroll.following = false;
+                self.chat_view.scroll.jump = false;
+            }
+            let unseen = self.chat_view.scroll.observe(
+                scroll_output.state.offset.y,
+                scroll_output.inner_rect.height(),
+                scroll_output.content_size.y,
+            );
+            if !self.chat_view.scroll.following {
+                let label = match (unseen, self.is_generating()) {
+                    (true, true) => "↓ New tokens",
+                    (true, false) => "↓ New messages",
+                    (false, _) => "↓ Latest",
+                };
+                let rect = scroll_output.inner_rect;
+                egui::Area::new(egui::Id::new("jump_to_bottom"))
+                    .order(egui::Order::Foreground)
+                    .pivot(egui::Align2::CENTER_BOTTOM)
+                    .fixed_pos(rect.center_bottom() - egui::vec2(0.0, 12.0))
+                    .show(ctx, |ui| {
+                        let palette = Palette::current(ctx);
+                        let button = egui::Button::new(egui::RichText::new(label).color(egui::Color32::WHITE))
+                            .fill(if unseen { palette.accent } else { palette.muted_text })
+                            .rounding(16.0);
+                        let response = ui.add(button).on_hover_text("Jump to the latest message");
+                        a11y::set_name(&response, "Jump to latest message");
+                        if response.clicked() {
+                            self.chat_view.scroll.jump_to_bottom();
+                        }
+                    });
+            }
+            match message_action {
+                Some((message_id, MessageAction::Branch)) => self.branch_session_at(session_idx, &message_id),
+                Some((message_id, MessageAction::ToggleStar)) => {
+                    let session = &mut self.chat_sessions[session_idx];
+                    session.toggle_star(&message_id);
+                    session.updated_at = chrono::Utc::now();
+                    self.persist_session(session_idx);
+                }
+                Some((message_id, MessageAction::Quote)) => {
+                    let quote = self.chat_sessions[session_idx].messages.iter().find(|m| m.id == message_id).map(ChatMessage::as_quote);
+                    if let Some(quote) = quote {
+                        let separator = if self.chat_view.input_text.trim().is_empty() { "" } else { "\n\n" };
+                        self.chat_view.input_text = format!("{}{separator}{quote}\n\n", self.chat_view.input_text.trim_end());
+                        self.focus_manager.set_focus(FocusableElement::InputArea);
+                    }
+                }
+                Some((message_id, MessageAction::Reply)) => {
+                    let session = &self.chat_sessions[session_idx];
+                    if let Some(message) = session.messages.iter().find(|m| m.id == message_id) {
+                        self.pending_reply = Some((session.id.clone(), QuotedReply::new(message)));
+                        self.focus_manager.set_focus(FocusableElement::InputArea);
+                    }
+                }
+                Some((_, MessageAction::ShowOriginal(original_id))) => {
+                    if self.chat_sessions[session_idx].messages.iter().any(|m| m.id == original_id) {
+                        self.chat_view.scroll_to_message = Some(original_id);
+                    } else {
+                        self.show_info("The quoted message has been deleted");
+                    }
+                }
+                Some((message_id, MessageAction::Rate(rating))) => self.rate_message(session_idx, &message_id, rating),
+                Some((message_id, MessageAction::CommentOnRating)) => self.edit_feedback_comment(session_idx, &message_id),
+                Some((message_id, MessageAction::Translate(language))) => self.translate_message(session_idx, &message_id, language),
+                Some((message_id, MessageAction::Delete)) => {
+                    let session = &mut self.chat_sessions[session_idx];
+                    if session.remove_message(&message_id) {
+                        session.updated_at = chrono::Utc::now();
+                        self.translations.remove(&message_id);
+                        // Remembered so sync doesn't bring it back from another device
+                        if let Some(storage) = self.storage.as_mut() {
+                            let deleted = Tombstones { messages: vec![Tombstone::now(message_id.as_str())], ..Default::default() };
+                            if let Err(e) = storage.record_tombstones(&deleted) {
+                                tracing::error!("Failed to record deleted message: {}", e);
+                            }
+                        }
+                        self.persist_session(session_idx);
+                        self.show_info("Message deleted");
+                    }
+                }
+                None => {}
+            }
+
+            // Input area
+            ui.with_layout(egui::Layout::bottom_up(egui::Align::LEFT), |ui| {
+                ui.add_space(20.0);
+                
+                self.render_enhanced_input_area(ui, ctx);
+                
+                ui.add_space(20.0);
+            });
+        } else {
+            // Welcome screen
+            ui.centered_and_justified(|ui| {
+                ui.vertical_centered(|ui| {
+                    ui.label(
+                        egui::RichText::new("Welcome to RIA AI Chat! 🚀")
+                            .size(32.0)
+                            .strong()
+                            .color(Palette::current(ctx).accent)
+                    );
+                    
+                    ui.add_space(20.0);
+                    
+                    ui.label(
+                        egui::RichText::new("Start a new conversation to begin chatting with AI")
+                            .size(16.0)
+                            .color(egui::Color32::GRAY)
+                    );
+                    
+                    ui.add_space(30.0);
+                    
+                    if ui.add_sized([200.0, 50.0], egui::Button::new("🆕 Start New Chat")).clicked() {
+                        self.create_new_session();
+                    }
+                });
+            });
+        }
+    }
+
+    fn render_message(&self, ui: &mut egui::Ui, message: &ChatMessage, starred: bool) -> Option<MessageAction> {
+        let mut action = None;
+        let is_user = matches!(message.role, MessageRole::User);
+        let palette = Palette::current(ui.ctx());
+        let metrics = Metrics::current(ui.ctx());
+        
+        ui.horizontal(|ui| {
+            if !is_user {
+                // AI Avatar
+                ui.vertical(|ui| {
+                    ui.add_space(2.0);
+                    egui::Frame::none()
+                        .fill(palette.accent)
+                        .rounding(16.0)
+                        .inner_margin(8.0)
+                        .show(ui, |ui| {
+                            ui.label(
+                                egui::RichText::new("🤖")
+                                    .size(16.0)
+                            );
+                        });
+                });
+                ui.add_space(10.0);
+            }
+
+            let bubble = ui.allocate_ui_with_layout(
+                [ui.available_width() - 80.0, 0.0].into(),
+                if is_user { egui::Layout::right_to_left(egui::Align::TOP) } 
+                else { egui::Layout::left_to_right(egui::Align::TOP) },
+                |ui| {
+                    // Enhanced message bubble with professional styling
+                    egui::Frame::none()
+                        .fill(if is_user { 
+                            palette.user_bubble_fill
+                        } else { 
+                            palette.ai_bubble_fill
+                        })
+                        .stroke(egui::Stroke::new(1.0, if is_user {
+                            palette.user_bubble_stroke
+                        } else {
+                            palette.ai_bubble_stroke
+                        }))
+                        .rounding(egui::Rounding {
+                            nw: if is_user { 12.0 } else { 4.0 },
+                            ne: if is_user { 4.0 } else { 12.0 },
+                            sw: 12.0,
+                            se: 12.0,
+                        })
+                        .inner_margin(metrics.bubble_margin)
+                        .shadow(egui::epaint::Shadow {
+                            offset: [1.0, 2.0].into(),
+                            blur: 6.0,
+                            spread: 0.0,
+                            color: palette.shadow,
+                        })
+                        .show(ui, |ui| {
+                            ui.set_max_width(500.0);
+
+                            if let Some(reply) = &message.reply_to {
+                                let quote = egui::Frame::none()
+                                    .fill(palette.chip_fill)
+                                    .rounding(4.0)
+                                    .inner_margin(egui::Margin { left: 8.0, right: 6.0, top: 4.0, bottom: 4.0 })
+                                    .show(ui, |ui| {
+                                        ui.label(egui::RichText::new(one_line(&reply.excerpt, 160)).size(metrics.meta_text).italics().color(palette.bubble_meta_text));
+                                    });
+                                let bar = egui::Rect::from_min_size(quote.response.rect.min, egui::vec2(3.0, quote.response.rect.height()));
+                                ui.painter().rect_filled(bar, 1.5, palette.accent);
+                                let quote = quote.response.interact(egui::Sense::click()).on_hover_text("Show the message this replies to");
+                                if quote.clicked() {
+                                    action = Some(MessageAction::ShowOriginal(reply.message_id.clone()));
+                                }
+                                ui.add_space(4.0);
+                            }
+
+                            if !message.images.is_empty() {
+                                ui.horizontal_wrapped(|ui| {
+                                    for attachment in &message.images {
+                                        if let Some(Some(texture)) = self.image_thumbnails.get(&attachment.id) {
+                                            let size = THUMBNAIL_SIZE as f32;
+                                            ui.add(egui::Image::new(texture).max_size(egui::vec2(size, size)).rounding(6.0))
+                                                .on_hover_text(format!("{}×{} image", attachment.width, attachment.height));
+                                        } else {
+                                            ui.label(egui::RichText::new("🖼 Image could not be shown").color(palette.bubble_meta_text));
+                                        }
+                                    }
+                                });
+                            }
+                            
+                            // Large tool results show the compacted form the model sees; full text on demand
+                            let max_chars = self.config.ai_config.tool_result_max_chars;
+                            let is_large_tool_result = matches!(message.role, MessageRole::Tool)
+                                && crate::ai::context::needs_compaction(&message.content, max_chars);
+                            let display_text = if is_large_tool_result {
+                                crate::ai::context::compact_tool_result(&message.content, max_chars)
+                            } else {
+                                message.content.clone()
+                            };
+
+                            // Message content with better typography; TeX formulas are rendered
+                            let text = if !is_large_tool_result && crate::ui::math::contains_math(&display_text) {
+                                egui::Label::new(crate::ui::math::layout(
+                                    &display_text,
+                                    metrics.message_text,
+                                    palette.bubble_text,
+                                    Some(metrics.message_text * 1.45),
+                                ))
+                            } else {
+                                egui::Label::new(
+                                    egui::RichText::new(display_text)
+                                        .size(metrics.message_text)
+                                        .color(palette.bubble_text)
+                                        .line_height(Some(metrics.message_text * 1.45))
+                                )
+                            };
+                            let text = ui.add(text.selectable(true));
+                            if message.id != "streaming-preview" {
+                                text.context_menu(|ui| {
+                                    if let Some(chosen) = message_menu(ui, message, &self.config.translation_language) {
+                                        action = Some(chosen);
+                                    }
+                                });
+                            }
+
+                            if is_large_tool_result {
+                                egui::CollapsingHeader::new("Show full tool result")
+                                    .id_salt(("tool_full", &message.id))
+                                    .show(ui, |ui| {
+                                        egui::ScrollArea::vertical()
+                                            .max_height(300.0)
+                                            .id_salt(("tool_full_scroll", &message.id))
+                                            .show(ui, |ui| {
+                                                ui.label(egui::RichText::new(&message.content).monospace().size(12.0));
+                                            });
+                                    });
+                            }
+                            
+                            if let Some(trace) = message.trace.as_ref().filter(|_| self.config.debug_inspector) {
+                                render_trace(ui, &message.id, trace);
+                            }
+
+                            ui.add_space(metrics.bubble_margin / 2.0);
+                            
+                            // Enhanced metadata and action row
+                            ui.horizontal(|ui| {
+                                // Timestamp
+                                ui.label(
+                                    egui::RichText::new(
+                                        message.timestamp.format("%H:%M").to_string()
+                                    )
+                                    .size(metrics.meta_text)
+                                    .color(palette.bubble_meta_text)
+                                );
+                                
+                                // Model info with icon
+                                if let Some(model) = &message.model_used {
+                                    ui.separator();
+                                    ui.label("🧠");
+                                    ui.label(
+                                        egui::RichText::new(model)
+                                            .size(metrics.meta_text)
+                                            .color(palette.model_tag_text)
+                                    );
+                                }
+                                
+                                // Performance metrics with icon
+                                if let Some(time) = message.inference_time {
+                                    ui.separator();
+                                    ui.label("⚡");
+                                    ui.label(
+                                        egui::RichText::new(format!("{:.1}s", time))
+                                            .size(metrics.meta_text)
+                                            .color(palette.timing_text)
+                                    );
+                                }
+                                
+                                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                                    // Message actions
+                                    let copy = ui.small_button("📋").on_hover_text("Copy message");
+                                    a11y::set_name(&copy, "Copy message");
+                                    if copy.clicked() {
+                                        ui.output_mut(|o| o.copied_text = message.content.clone());
+                                    }
+
+                                    if message.id != "streaming-preview" {
+                                        let more = ui.menu_button("⋯", |ui| {
+                                            if let Some(chosen) = message_menu(ui, message, &self.config.translation_language) {
+                                                action = Some(chosen);
+                                            }
+                                        });
+                                        more.response.on_hover_text("More actions (or right-click the text)");
+
+                                        let (icon, hover) = if starred { ("⭐", "Remove from favorites") } else { ("☆", "Add to favorites") };
+                                        let star = ui.small_button(icon).on_hover_text(hover);
+                                        a11y::set_name(&star, hover);
+                                        if star.clicked() {
+                                            action = Some(MessageAction::ToggleStar);
+                                        }
+
+                                        let branch = ui.small_button("🌿").on_hover_text("Branch here: continue in a new chat from this message");
+                                        a11y::set_name(&branch, "Branch conversation from this message");
+                                        if branch.clicked() {
+                                            action = Some(MessageAction::Branch);
+                                        }
+                                    }
+                                    
+                                    if !is_user {
+                                        let regenerate = ui.small_button("🔄").on_hover_text("Regenerate response");
+                                        a11y::set_name(&regenerate, "Regenerate response");
+                                        if regenerate.clicked() {
+                                            // TODO: Implement regenerate
+                                        }
+                                        
+                                        if message.id != "streaming-preview" {
+                                            let rating = message.feedback.as_ref().map(|f| f.rating);
+                                            let comment = message.feedback.as_ref().map(|f| f.comment.trim()).filter(|c| !c.is_empty());
+                                            for (value, icon, hover, name) in [
+                                                (Rating::Good, "👍", "Good response", "Rate as good response"),
+                                                (Rating::Poor, "👎", "Poor response", "Rate as poor response"),
+                                            ] {
+                                                let selected = rating == Some(value);
+                                                let mut button = ui.add(egui::SelectableLabel::new(selected, icon)).on_hover_text(hover);
+                                                if let Some(comment) = comment.filter(|_| selected) {
+                                                    button = button.on_hover_text(format!("“{comment}”"));
+                                                }
+                                                a11y::set_name(&button, name);
+                                                if button.clicked() {
+                                                    action = Some(MessageAction::Rate(value));
+                                                }
+                                            }
+                                        }
+                                    }
+                                });
+                            });
+                        });
+                }
+            );
+            let author = match message.role {
+                MessageRole::User => "You",
+                MessageRole::Assistant => "Assistant",
+                MessageRole::System => "System",
+                MessageRole::Tool => "Tool result",
+            };
+            a11y::describe_message(ui, ui.make_persistent_id(("message", &message.id)), bubble.response.rect, author, &message.content);
+
+            if is_user {
+                // User Avatar
+                ui.add_space(10.0);
+                ui.vertical(|ui| {
+                    ui.add_space(2.0);
+                    egui::Frame::none()
+                        .fill(palette.user_bubble_stroke)
+                        .rounding(16.0)
+                        .inner_margin(8.0)
+                        .show(ui, |ui| {
+                            ui.label(
+                                egui::RichText::new("👤")
+                                    .size(16.0)
+                                    .color(egui::Color32::WHITE)
+                            );
+                        });
+                });
+            }
+        });
+        action
+    }
+
+    fn render_enhanced_input_area(&mut self, ui: &mut egui::Ui, ctx: &egui::Context) {
+        let max_chars = 2000;
+        let current_chars = self.chat_view.input_text.len();
+        let word_count = if self.chat_view.input_text.trim().is_empty() {
+            0
+        } else {
+            self.chat_view.input_text.trim().split_whitespace().count()
+        };
+        
+        // Input area container with professional styling
+        let palette = Palette::current(ctx);
+        egui::Frame::none()
+            .fill(palette.card_fill)
+            .stroke(egui::Stroke::new(1.0, palette.card_stroke))
+            .rounding(12.0)
+            .inner_margin(16.0)
+            .show(ui, |ui| {
+                ui.vertical(|ui| {
+                    // Header with formatting controls and stats
+                    ui.horizontal(|ui| {
+                        // Formatting controls
+                        ui.horizontal(|ui| {
+                            ui.label(
+                                egui::RichText::new("✏️")
+                                    .size(16.0)
+                                    .color(palette.accent)
+                            );
+                            
+                            ui.add_space(8.0);
+                            
+                            // Suggested prompts dropdown
+                            egui::ComboBox::from_id_salt("prompt_suggestions")
+                                .selected_text("💡 Quick Prompts")
+                                .width(120.0)
+                                .show_ui(ui, |ui| {
+                                    let mut picked = None;
+                                    for snippet in &self.config.snippets {
+                                        if ui.selectable_label(false, &snippet.name).on_hover_text(&snippet.text).clicked() {
+                                            picked = Some(snippet.clone());
+                                        }
+                                    }
+                                    if self.config.snippets.is_empty() {
+                                        ui.label("No snippets; add them in Settings");
+                                    }
+                                    ui.separator();
+                                    if ui.selectable_label(false, "✏ Edit snippets…").clicked() {
+                                        self.settings_view.open = true;
+                                    }
+                                    if let Some(snippet) = picked {
+                                        self.pick_snippet(&snippet);
+                                    }
+                                });
+
+                            let paste = ui
+                                .add_enabled(self.model_accepts_images && !self.is_generating(), egui::Button::new("📎 Image"))
+                                .on_hover_text("Attach the image on the clipboard (Ctrl+V in the input)")
+                                .on_disabled_hover_text("Load a vision model to attach images");
+                            a11y::set_name(&paste, "Paste image from clipboard");
+                            if paste.clicked() {
+                                self.paste_clipboard_image(true);
+                            }
+                        });
+                        
+                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                            // Character and word count
+                            let count_color = if current_chars > (max_chars as f32 * 0.9) as usize {
+                                palette.danger // Red when near limit
+                            } else if current_chars > (max_chars as f32 * 0.7) as usize {
+                                palette.warning // Yellow when approaching limit
+                            } else {
+                                palette.muted_text
+                            };
+                            
+                            ui.label(
+                                egui::RichText::new(format!("{}/{} chars | {} words", current_chars, max_chars, word_count))
+                                    .size(11.0)
+                                    .color(count_color)
+                            );
+                        });
+                    });
+                    
+                    ui.add_space(8.0);
+
+                    // The message the next one replies to
+                    let session_id = self.current_session.map(|i| self.chat_sessions[i].id.as_str());
+                    let mut cancel_reply = false;
+                    if let Some((_, reply)) = self.pending_reply.as_ref().filter(|(id, _)| Some(id.as_str()) == session_id) {
+                        egui::Frame::none()
+                            .fill(palette.chip_fill)
+                            .stroke(egui::Stroke::new(1.0, palette.card_stroke))
+                            .rounding(8.0)
+                            .inner_margin(6.0)
+                            .show(ui, |ui| {
+                                ui.horizontal(|ui| {
+                                    ui.label(egui::RichText::new("↩ Replying to").size(11.0).strong());
+                                    ui.label(egui::RichText::new(one_line(&reply.excerpt, 80)).size(11.0).italics().color(palette.muted_text));
+                                    let cancel = ui.small_button("✕").on_hover_text("Don't reply to this message");
+                                    a11y::set_name(&cancel, "Cancel reply");
+                                    cancel_reply = cancel.clicked();
+                                });
+                            });
+                        ui.add_space(8.0);
+                    }
+                    if cancel_reply {
+                        self.pending_reply = None;
+                    }
+
+                    // Attached images, sent with the next message
+                    if !self.pending_images.is_empty() {
+                        let mut remove = None;
+                        ui.horizontal_wrapped(|ui| {
+                            for (idx, attachment) in self.pending_images.iter().enumerate() {
+                                egui::Frame::none()
+                                    .fill(palette.chip_fill)
+                                    .stroke(egui::Stroke::new(1.0, palette.card_stroke))
+                                    .rounding(8.0)
+                                    .inner_margin(4.0)
+                                    .show(ui, |ui| {
+                                        ui.horizontal(|ui| {
+                                            if let Some(Some(texture)) = self.image_thumbnails.get(&attachment.id) {
+                                                ui.add(egui::Image::new(texture).max_size(egui::vec2(48.0, 48.0)).rounding(4.0));
+                                            }
+                                            ui.label(
+                                                egui::RichText::new(format!(
+                                                    "{}×{} · {} KB",
+                                                    attachment.width,
+                                                    attachment.height,
+                                                    attachment.encoded_len().div_ceil(1024)
+                                                ))
+                                                    .size(11.0)
+                                                    .color(palette.muted_text),
+                                            );
+                                            let remove_button = ui.small_button("✕").on_hover_text("Remove image");
+                                            a11y::set_name(&remove_button, "Remove attached image");
+                                            if remove_button.clicked() {
+                                                remove = Some(idx);
+                                            }
+                                        });
+                                    });
+                            }
+                        });
+                        if let Some(idx) = remove {
+                            self.pending_images.remove(idx);
+                        }
+                        ui.add_space(8.0);
+                    }
+                    
+                    // Main input area
+                    let narrow = ctx.screen_rect().width() < NARROW_WIDTH;
+                    let mut typing = false;
+                    ui.horizontal(|ui| {
+                        // Multi-line text input with accessibility
+                        let available_width = ui.available_width() - 100.0;
+                        let send_key = self.config.keybindings.describe(ctx, Action::SendMessage);
+                        let help_key = self.config.keybindings.describe(ctx, Action::ShowHelp);
+                        // With plain Enter bound to send, newlines move to Shift+Enter
+                        let plain_enter = egui::KeyboardShortcut::new(egui::Modifiers::NONE, egui::Key::Enter);
+                        let return_key = if self.config.keybindings.shortcut(Action::SendMessage) == Some(plain_enter) {
+                            egui::KeyboardShortcut::new(egui::Modifiers::SHIFT, egui::Key::Enter)
+                        } else {
+                            plain_enter
+                        };
+                        
+                        let generating = self.is_generating();
+                        let text_edit_response = ui.add_sized(
+                            [available_width, 60.0],
+                            egui::TextEdit::multiline(&mut self.chat_view.input_text)
+                                .hint_text(if generating { 
+                                    "🔄 Generating response... messages sent now are queued".to_string()
+                                } else { 
+                                    format!("💬 Type your message here, or / for commands...\n✨ Use {send_key} to send, Tab to navigate, {help_key} for help")
+                                })
+                                .return_key(return_key)
+                                .font(egui::TextStyle::Body)
+                                .desired_width(available_width)
+                                .lock_focus(generating)
+                        );
+                        
+                        a11y::set_name(&text_edit_response, "Message input");
+                        // On phones the on-screen keyboard takes the bottom of the screen when
+                        // the input is focused; keep the latest message in view above it
+                        typing = text_edit_response.has_focus();
+                        if narrow && text_edit_response.gained_focus() {
+                            self.chat_view.scroll.jump_to_bottom();
+                        }
+                        self.focus_manager.register(FocusableElement::InputArea, &text_edit_response);
+                        self.render_command_palette(ui, &text_edit_response);
+
+                        // egui only sees text pastes; an image-only clipboard yields no event
+                        if text_edit_response.has_focus()
+                            && self.model_accepts_images
+                            && ui.input(|i| i.key_released(egui::Key::V) && i.modifiers.command)
+                        {
+                            self.paste_clipboard_image(false);
+                        }
+                        
+                        // Enter combinations other than the return key make the input give up focus
+                        if (text_edit_response.has_focus() || text_edit_response.lost_focus())
+                            && ui.input(|i| self.config.keybindings.pressed(i, Action::SendMessage))
+                        {
+                            self.send_message(ctx);
+                        }
+                        
+                        ui.add_space(12.0);
+                        
+                        // Send button area
+                        ui.vertical(|ui| {
+                            ui.add_space(8.0);
+                            
+                            let has_content = !self.chat_view.input_text.trim().is_empty() || !self.pending_images.is_empty();
+                            // Other chats' replies count against the limit too
+                            let at_limit = !generating && self.chat.is_full();
+                            // With nothing typed while generating, the button stops the reply
+                            let stops = generating && !has_content;
+                            let queues = (generating || at_limit) && has_content;
+                            let send_enabled = has_content && current_chars <= max_chars;
+                            
+                            let send_button_text = if stops {
+                                "⏹ Stop"
+                            } else if current_chars > max_chars {
+                                "❌ Too long"
+                            } else if queues {
+                                "🕓 Queue"
+                            } else if !has_content {
+                                "✏️ Type first"
+                            } else {
+                                "🚀 Send"
+                            };
+                            
+                            let button_color = if stops {
+                                palette.danger
+                            } else if send_enabled {
+                                egui::Color32::from_rgb(0, 123, 255)
+                            } else {
+                                egui::Color32::from_rgb(108, 117, 125)
+                            };
+                            
+                            let send_button = egui::Button::new(send_button_text)
+                                .fill(button_color)
+                                .rounding(8.0);
+                            
+                            let hover = if stops {
+                                "Stop this reply, keeping what has been written so far".to_string()
+                            } else if queues && generating {
+                                "Send once the current reply is done".to_string()
+                            } else if queues {
+                                format!("{} replies are already generating in other chats; this is sent when one finishes", self.chat.active())
+                            } else {
+                                format!("Send message ({} or click)", self.config.keybindings.describe(ctx, Action::SendMessage))
+                            };
+                            let send_response = ui.add_sized([80.0, 36.0], send_button)
+                                .on_hover_text(hover)
+                                .on_disabled_hover_text("Type a message first or wait for response to complete");
+                            a11y::set_name(&send_response, if stops { "Stop reply" } else if queues { "Queue message" } else { "Send message" });
+                            self.focus_manager.register(FocusableElement::SendButton, &send_response);
+                            self.render_focus_indicator(ui, &send_response);
+                            
+                            // Enter/Space on the focused button also count as a click
+                            if send_response.clicked() && stops {
+                                if let Some(session_idx) = self.current_session {
+                                    let session_id = self.chat_sessions[session_idx].id.clone();
+                                    self.stop_generation(&session_id);
+                                }
+                            } else if send_response.clicked() && send_enabled {
+                                self.send_message(ctx);
+                                self.focus_manager.set_focus(FocusableElement::InputArea);
+                            }
+                            // The main button queues while there is text, so stopping moves here
+                            if generating && has_content && ui.small_button("⏹ Stop reply").clicked() {
+                                if let Some(session_idx) = self.current_session {
+                                    let session_id = self.chat_sessions[session_idx].id.clone();
+                                    self.stop_generation(&session_id);
+                                }
+                            }
+
+                            ui.add_space(4.0);
+                            self.render_response_length(ui);
+                            self.render_output_format(ui);
+                            
+                            // Clear button
+                            if !self.chat_view.input_text.is_empty() && !generating {
+                                ui.add_space(4.0);
+                                let clear_button = egui::Button::new("🗑️ Clear")
+                                    .fill(palette.danger)
+                                    .rounding(8.0);
+                                
+                                let clear_response = ui.add_sized([80.0, 28.0], clear_button)
+                                    .on_hover_text(format!("Clear input text ({})", self.config.keybindings.describe(ctx, Action::ClearInput)));
+                                a11y::set_name(&clear_response, "Clear input");
+                                self.focus_manager.register(FocusableElement::ClearButton, &clear_response);
+                                self.render_focus_indicator(ui, &clear_response);
+                                
+                                if clear_response.clicked() {
+                                    self.chat_view.input_text.clear();
+                                    self.focus_manager.set_focus(FocusableElement::InputArea);
+                                }
+                            }
+                        });
+                    });
+                    
+                    // Footer with helpful tips and accessibility info, left out while the
+                    // keyboard leaves little room
+                    let keyboard_up = narrow && typing;
+                    if !self.is_generating() && !keyboard_up {
+                        ui.add_space(6.0);
+                        ui.separator();
+                        ui.add_space(4.0);
+                        
+                        ui.horizontal(|ui| {
+                            // Main tips
+                            ui.label(
+                                egui::RichText::new(format!(
+                                    "💡 Tips: {} to send • {} for help • Tab to navigate • {} for models",
+                                    self.config.keybindings.describe(ctx, Action::SendMessage),
+                                    self.config.keybindings.describe(ctx, Action::ShowHelp),
+                                    self.config.keybindings.describe(ctx, Action::ToggleModels),
+                                ))
+                                    .size(10.0)
+                                    .color(palette.muted_text)
+                            );
+                            
+                            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                                // Focus indicator
+                                if let Some(focused) = &self.focus_manager.current_focus {
+                                    let focus_text = match focused {
+                                        FocusableElement::InputArea => "📝 Input focused",
+                                        FocusableElement::SendButton => "🚀 Send button focused", 
+                                        FocusableElement::ClearButton => "🗑️ Clear button focused",
+                                        FocusableElement::NewChatButton => "🆕 New chat focused",
+                                        FocusableElement::SettingsButton => "⚙️ Settings focused",
+                                        FocusableElement::ModelsButton => "🧠 Models focused",
+                                        FocusableElement::Notification(id) => &format!("🔔 Notification #{} focused", id),
+                                        _ => "Focus active",
+                                    };
+                                    
+                                    ui.label(
+                                        egui::RichText::new(focus_text)
+                                            .size(10.0)
+                                            .color(palette.accent)
+                                            .strong()
+                                    );
+                                }
+                            });
+                        });
+                    }
+                });
+            });
+        
+        ui.add_space(20.0);
+    }
+
+    /// Slash commands matching what has been typed, floating above the input.
+    fn render_command_palette(&mut self, ui: &mut egui::Ui, input: &egui::Response) {
+        let commands = slash_commands::matching(&self.chat_view.input_text);
+        if commands.is_empty() {
+            return;
+        }
+        let palette = Palette::current(ui.ctx());
+        let mut chosen = None;
+        egui::Area::new(ui.id().with("command_palette"))
+            .order(egui::Order::Foreground)
+            .pivot(egui::Align2::LEFT_BOTTOM)
+            .fixed_pos(input.rect.left_top() - egui::vec2(0.0, 4.0))
+            .show(ui.ctx(), |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    for command in commands {
+                        let label = egui::RichText::new(format!("/{} {}", command.name, command.args)).monospace();
+                        let row = ui.horizontal(|ui| {
+                            let clicked = ui.selectable_label(false, label).clicked();
+                            ui.label(egui::RichText::new(command.help).small().color(palette.muted_text));
+                            clicked
+                        });
+                        if row.inner {
+                            chosen = Some(command.name);
+                        }
+                    }
+                });
+            });
+        if let Some(name) = chosen {
+            self.chat_view.input_text = format!("/{name} ");
+            self.focus_manager.set_focus(FocusableElement::InputArea);
+        }
+    }
+
+    /// Short / Normal / Long / Custom picker under the send button. Only the next requests
+    /// use it; `max_tokens` in Settings stays as it is.
+    fn render_response_length(&mut self, ui: &mut egui::Ui) {
+        let configured = self.config.ai_config.max_tokens;
+        let tokens = |length: ResponseLength| length.max_tokens().unwrap_or(configured);
+        let combo = egui::ComboBox::from_id_salt("response_length")
+            .width(80.0)
+            .selected_text(self.response_length.label())
+            .show_ui(ui, |ui| {
+                for length in [ResponseLength::Short, ResponseLength::Normal, ResponseLength::Long] {
+                    ui.selectable_value(&mut self.response_length, length, format!("{} ({} tokens)", length.label(), tokens(length)));
+                }
+                let custom = ResponseLength::Custom(tokens(self.response_length));
+                if ui.selectable_label(matches!(self.response_length, ResponseLength::Custom(_)), "Custom…").clicked() {
+                    self.response_length = custom;
+                }
+            });
+        let combo = combo.response.on_hover_text(format!(
+            "Reply length for the next messages: up to {} tokens\n\n{}",
+            tokens(self.response_length),
+            crate::ui::settings::SAMPLING_SCOPE
+        ));
+        a11y::set_name(&combo, "Response length");
+        if let ResponseLength::Custom(tokens) = &mut self.response_length {
+            ui.add_sized([80.0, 20.0], egui::DragValue::new(tokens).range(1..=8192).suffix(" tok"));
+        }
+    }
+
+    /// Output format picker under the send button: free text, JSON, a JSON schema or a regex.
+    fn render_output_format(&mut self, ui: &mut egui::Ui) {
+        use constraint::OutputConstraint;
+        let palette = Palette::current(ui.ctx());
+        let label = match &self.output_constraint {
+            None => "{ } Text".to_string(),
+            Some(constraint) => format!("{{ }} {} •", constraint.label()),
+        };
+        let menu = ui.menu_button(label, |ui| {
+            ui.set_min_width(280.0);
+            let text = match &self.output_constraint {
+                Some(OutputConstraint::JsonSchema(text) | OutputConstraint::Regex(text)) => text.clone(),
+                _ => String::new(),
+            };
+            let choices = [
+                ("Free text", None),
+                ("JSON", Some(OutputConstraint::Json)),
+                ("JSON schema", Some(OutputConstraint::JsonSchema(text.clone()))),
+                ("Regex", Some(OutputConstraint::Regex(text))),
+            ];
+            ui.horizontal(|ui| {
+                for (name, choice) in choices {
+                    let selected = self.output_constraint.as_ref().map(std::mem::discriminant) == choice.as_ref().map(std::mem::discriminant);
+                    if ui.radio(selected, name).clicked() && !selected {
+                        self.output_constraint = choice;
+                    }
+                }
+            });
+            match &mut self.output_constraint {
+                Some(OutputConstraint::JsonSchema(text)) => {
+                    ui.add(egui::TextEdit::multiline(text).code_editor().desired_rows(6).hint_text(r#"{"type": "object", "properties": {...}, "required": [...]}"#));
+                }
+                Some(OutputConstraint::Regex(text)) => {
+                    ui.add(egui::TextEdit::singleline(text).code_editor().hint_text(r"\d{4}-\d{2}-\d{2}"));
+                }
+                _ => {}
+            }
+            if let Some(constraint) = &self.output_constraint {
+                match constraint.compile() {
+                    Ok(_) => ui.label(egui::RichText::new("✔ Replies must match this format").small().color(palette.muted_text)),
+                    Err(e) => ui.label(egui::RichText::new(format!("{e:#}")).small().color(palette.danger)),
+                };
+            }
+        });
+        let menu = menu.response.on_hover_text("Constrain the next replies to JSON, a JSON schema or a regex");
+        a11y::set_name(&menu, "Output format");
+    }
+
+    /// Outline a focused button so keyboard focus is visible on the colored fills.
+    fn render_focus_indicator(&self, ui: &egui::Ui, response: &egui::Response) {
+        if response.has_focus() {
+            ui.painter().rect_stroke(
+                response.rect.expand(2.0),
+                6.0,
+                egui::Stroke::new(2.0, Palette::current(ui.ctx()).accent)
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_narrow_windows_show_the_chat_list_as_a_drawer() {
+        let mut view = ChatView::default();
+        assert_eq!(view.layout(1200.0), MainLayout::SidebarAndChat);
+        assert_eq!(view.layout(400.0), MainLayout::Chat);
+
+        view.toggle_sidebar(400.0);
+        assert_eq!(view.layout(400.0), MainLayout::Sidebar);
+        // Each layout remembers its own choice
+        assert_eq!(view.layout(1200.0), MainLayout::SidebarAndChat);
+        view.toggle_sidebar(1200.0);
+        assert_eq!(view.layout(1200.0), MainLayout::Chat);
+        assert_eq!(view.layout(400.0), MainLayout::Sidebar);
+    }
+
+    #[test]
+    fn test_chat_scroll_stops_following_when_user_scrolls_up() {
+        let mut scroll = ChatScroll::default();
+        assert!(!scroll.observe(400.0, 600.0, 1000.0));
+        assert!(scroll.following);
+
+        // User scrolls up, then more tokens stream in below
+        assert!(!scroll.observe(100.0, 600.0, 1000.0));
+        assert!(!scroll.following);
+        assert!(scroll.observe(100.0, 600.0, 1200.0));
+
+        // Jumping keeps following while the scroll animation runs
+        scroll.jump_to_bottom();
+        assert!(!scroll.observe(300.0, 600.0, 1200.0));
+        assert!(scroll.following);
+        assert!(!scroll.observe(600.0, 600.0, 1200.0));
+        assert!(!scroll.jump);
+    }
+}
@@ -0,0 +1,190 @@
+//! The diagnostics panel under the chat: model details, runtime and execution provider
+//! status, provider failures and the local error tally.
+
+use super::*;
+
+/// What the diagnostics view keeps between frames.
+#[derive(Default)]
+pub(super) struct DiagnosticsView {
+    pub(super) open: bool,
+    /// Providers that failed during generation, copied from the engine
+    pub(super) provider_incidents: Vec<crate::ai::inference::ProviderIncident>,
+    /// A report is being collected in the background
+    pub(super) exporting: bool,
+}
+
+/// `get_model_info` keys shown first in the model details, with their labels.
+const MODEL_DETAIL_LABELS: [(&str, &str); 6] = [
+    ("provider", "Provider"),
+    ("execution_provider", "Execution provider"),
+    ("model_path", "Model"),
+    ("signature_inputs", "Inputs"),
+    ("last_ep_error", "Last EP error"),
+    ("last_load_error", "Last load error"),
+];
+
+impl RiaApp {
+    pub(super) fn poll_provider_incidents(&mut self) {
+        // The engine is locked while a response is generated; pick incidents up afterwards
+        let incidents = match self.inference_engine.try_write() {
+            Ok(mut engine) => engine.take_new_incidents(),
+            Err(_) => return,
+        };
+        for incident in incidents {
+            let message = match &incident.fallback {
+                Some(fallback) if incident.degraded_to_demo => {
+                    self.model_loaded = false;
+                    self.model_details = None;
+                    self.model_accepts_images = false;
+                    format!("{} failed ({}). Switched to {fallback}; reload the model to try again.", incident.provider, incident.error)
+                }
+                Some(fallback) => format!("{} failed ({}). Switched to {fallback}.", incident.provider, incident.error),
+                None => format!("{} failed ({}) and no other provider is available.", incident.provider, incident.error),
+            };
+            let notification = AppNotification::new(message, NotificationType::Warning)
+                .with_duration(8.0)
+                .with_actions(vec![
+                    NotificationAction { label: "Models".to_string(), action_type: NotificationActionType::OpenModels },
+                    NotificationAction { label: "Dismiss".to_string(), action_type: NotificationActionType::Dismiss },
+                ]);
+            self.add_notification(notification);
+            self.diagnostics_view.provider_incidents.push(incident);
+        }
+    }
+
+    /// Write a diagnostics report for bug reports on a blocking thread; the user is told where
+    /// it went once it is written.
+    pub(super) fn export_diagnostics(&mut self) {
+        use crate::utils::diagnostics::{DiagnosticsReport, EpAttemptReport};
+        if self.diagnostics_view.exporting {
+            self.show_info("Diagnostics are already being exported");
+            return;
+        }
+        let attempts = self
+            .onnx_attempt_log
+            .iter()
+            .map(|att| EpAttemptReport {
+                execution_provider: att.ep.clone(),
+                success: att.success,
+                error_kind: att.error_kind.map(|k| format!("{k:?}")),
+                message: att.message.clone(),
+            })
+            .collect();
+        let config = self.config.clone();
+        let model_loaded = self.model_loaded;
+        let runtime = self.ort_runtime.clone();
+        let incidents = self.diagnostics_view.provider_incidents.clone();
+        let notifications = self.notification_center.recent(100);
+        let events = self.events.sender();
+        tokio::task::spawn_blocking(move || {
+            let report = DiagnosticsReport::collect(&config, model_loaded, runtime, attempts, incidents, notifications);
+            events.send(AppEvent::DiagnosticsExported(report.write_to(&AppConfig::log_dir())));
+        });
+        self.diagnostics_view.exporting = true;
+    }
+
+    pub(super) fn finish_diagnostics_export(&mut self, ctx: &egui::Context, result: anyhow::Result<std::path::PathBuf>) {
+        self.diagnostics_view.exporting = false;
+        match result {
+            Ok(path) => {
+                ctx.output_mut(|o| o.copied_text = path.display().to_string());
+                self.show_success(format!("Diagnostics saved to {} (path copied)", path.display()));
+            }
+            Err(e) => self.show_error(format!("Failed to export diagnostics: {e}")),
+        }
+    }
+
+    /// The causes of most errors in the local tally, with what usually fixes them.
+    fn ui_common_issues(&mut self, ui: &mut egui::Ui) {
+        let Some(log) = self.issue_log.as_mut() else { return };
+        let common = match log.most_common(5) {
+            Ok(common) => common,
+            Err(e) => {
+                ui.small(format!("Couldn't read the error tally: {e}"));
+                return;
+            }
+        };
+        ui.label("Most common issues on this machine:");
+        if common.is_empty() {
+            ui.small("    No errors counted yet.");
+            return;
+        }
+        for entry in &common {
+            let sources: Vec<&str> = entry.sources.iter().map(|s| s.label()).collect();
+            let last_seen = entry.last_seen.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M");
+            ui.label(format!("    • {} — {}× ({}), last {last_seen}", entry.issue.title, entry.count, sources.join(", ")))
+                .on_hover_text(&entry.last_message);
+            ui.small(format!("        Fix: {}", entry.issue.fix));
+        }
+        if ui.small_button("Clear tally").clicked() {
+            if let Err(e) = log.clear() {
+                tracing::warn!("Failed to clear the error tally: {}", e);
+            }
+        }
+    }
+
+    pub(super) fn ui_diagnostics_panel(&mut self, ui: &mut egui::Ui) {
+        if !self.diagnostics_view.open { return; }
+        if let Some(details) = &self.model_details {
+            egui::CollapsingHeader::new("🧠 Model details").default_open(true).show(ui, |ui| {
+                egui::Grid::new("model_details").num_columns(2).striped(true).show(ui, |ui| {
+                    let known = MODEL_DETAIL_LABELS.iter().filter_map(|(key, label)| details.get(*key).map(|v| (*label, v)));
+                    let mut other: Vec<_> = details.iter().filter(|(k, _)| !MODEL_DETAIL_LABELS.iter().any(|(key, _)| key == k)).collect();
+                    other.sort();
+                    for (label, value) in known.chain(other.into_iter().map(|(k, v)| (k.as_str(), v))) {
+                        ui.label(label);
+                        ui.add(egui::Label::new(egui::RichText::new(value).small()).wrap());
+                        ui.end_row();
+                    }
+                });
+            });
+        }
+        egui::CollapsingHeader::new("🩺 ONNX Diagnostics").default_open(true).show(ui, |ui| {
+            let runtime = &self.ort_runtime;
+            ui.label(format!("Runtime: {} ({:?}, expected {}.x)", runtime.status_label(), runtime.compatibility, runtime.expected_version));
+            if let Some(lib) = &runtime.selected { ui.small(format!("    • {}", lib.path.display())); }
+            ui.small(format!("    • {}", runtime.note));
+            for lib in runtime.found.iter().filter(|lib| runtime.selected.as_ref().map(|s| s.path != lib.path).unwrap_or(true)) {
+                ui.small(format!("    • Also found: {} ({})", lib.path.display(), lib.version.as_deref().unwrap_or("unknown version")));
+            }
+            ui.separator();
+            if let Ok(profile) = self.hardware_profile.lock() {
+                if !profile.eps.is_empty() {
+                    ui.label("Execution providers on this machine (fallbacks are tried best first):");
+                    for record in &profile.eps {
+                        let status = match &record.missing {
+                            Some(reason) => format!("skipped: {reason}"),
+                            None => format!("{} loaded, {} failed", record.successes, record.failures),
+                        };
+                        ui.small(format!("    • {:?}: {status}", record.ep));
+                    }
+                    ui.separator();
+                }
+            }
+            if !self.diagnostics_view.provider_incidents.is_empty() {
+                ui.label("Provider failures during generation:");
+                for incident in &self.diagnostics_view.provider_incidents {
+                    let local = incident.timestamp.with_timezone(&chrono::Local);
+                    ui.small(format!("    • {} {} -> {}: {}", local.format("%H:%M:%S"), incident.provider, incident.fallback.as_deref().unwrap_or("none"), incident.error));
+                }
+                ui.separator();
+            }
+            if self.config.error_analytics {
+                self.ui_common_issues(ui);
+                ui.separator();
+            }
+            if self.onnx_attempt_log.is_empty() { ui.label("No attempts recorded yet"); return; }
+            ui.separator();
+            ui.label("Execution Provider Attempts:");
+            for att in &self.onnx_attempt_log {
+                let status = if att.success { "✅" } else { "❌" };
+                ui.label(format!("{status} EP {} -> {}{}", att.ep, if att.success { "SUCCESS" } else { "FAIL" }, att.error_kind.as_ref().map(|k| format!(" ({k:?})")).unwrap_or_default()));
+                if let Some(msg) = &att.message { if !att.success { ui.small(format!("    • {}", msg)); } }
+            }
+            ui.separator();
+            if ui.button("Clear Log").clicked() { self.onnx_attempt_log.clear(); }
+            if ui.button("📤 Export diagnostics").on_hover_text("Save a report to attach to bug reports").clicked() { self.export_diagnostics(); }
+            if ui.button(if self.diagnostics_view.open { "Hide Diagnostics" } else { "Show Diagnostics" }).clicked() { self.diagnostics_view.open = !self.diagnostics_view.open; }
+        });
+    }
+}
@@ -0,0 +1,121 @@
+//! The models window and what happens when its downloads finish.
+
+use super::*;
+
+/// What the models view keeps between frames. The manager outlives the window: downloads
+/// go on while it is closed.
+pub(super) struct ModelsView {
+    pub(super) open: bool,
+    pub(super) manager: ModelManagerUI,
+}
+
+impl ModelsView {
+    pub(super) fn new(config: &AppConfig) -> Self {
+        Self { open: false, manager: ModelManagerUI::new(config.model_directories.clone(), config.network.clone()) }
+    }
+}
+
+impl RiaApp {
+    /// The models window: catalog, downloads and loading the selected model.
+    pub(super) fn show_models_window(&mut self, ctx: &egui::Context) {
+        if self.models_view.open {
+            egui::Window::new("AI Models")
+                .collapsible(false)
+                .resizable(true)
+                .default_size([650.0, 500.0])
+                .max_size([750.0, 650.0])
+                .show(ctx, |ui| {
+                    self.models_view.manager.render(ui);
+
+                    ui.add_space(10.0);
+                    ui.separator();
+                    ui.add_space(10.0);
+
+                    // Model selection and loading
+                    ui.horizontal(|ui| {
+                        if let Some(selected_model) = self.models_view.manager.get_selected_model() {
+                            ui.label(format!("Selected: {}", selected_model));
+
+                            if ui.button("Load Model").clicked() {
+                                self.load_selected_model();
+                            }
+                        } else {
+                            ui.label("No model selected");
+                        }
+
+                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                            if ui.button("Close").clicked() {
+                                self.models_view.open = false;
+                            }
+                        });
+                    });
+                });
+        }
+    }
+
+    /// Report finished downloads and auto-load the newest one if enabled. Downloads keep
+    /// progressing while the models window is closed (or the app is in the tray).
+    pub(super) fn handle_finished_downloads(&mut self) {
+        // Downloads keep progressing while the models window is closed (or the app is in the tray).
+        self.models_view.manager.handle_progress_updates();
+        let completed = self.models_view.manager.take_completed_downloads();
+        for error in self.models_view.manager.take_failed_downloads() {
+            self.record_issue(IssueSource::Download, &error);
+        }
+        if let Some(name) = completed.last() {
+            self.notify_if_hidden(&format!("Download complete: {name}"));
+        }
+        if self.config.auto_load_new_download {
+            // Prefer the last (most recent) completed download
+            if let Some(latest_name) = completed.last() {
+                // Build full path relative to models directory if not absolute
+                let mut candidate_path = std::path::PathBuf::from(latest_name);
+                if candidate_path.is_relative() {
+                    candidate_path = self.config.primary_models_directory().join(&candidate_path);
+                }
+                if candidate_path.exists() {
+                    tracing::info!("Auto-loading newly downloaded model: {:?}", candidate_path);
+                    self.auto_load_cached_model(&candidate_path.to_string_lossy());
+                } else {
+                    tracing::warn!("Completed download path not found: {:?}", candidate_path);
+                }
+            }
+        }
+    }
+
+    fn load_selected_model(&mut self) {
+        if let Some(selected_model) = self.models_view.manager.get_selected_model() {
+            tracing::info!("Loading model: {}", selected_model);
+            
+            // Show loading notification
+            self.show_loading(format!("Loading model '{}'...", selected_model));
+            
+            // Get model info (now sync)
+            if let Some(info) = self.models_view.manager.get_selected_model_info() {
+                // Check if the model file exists
+                if !info.path.exists() {
+                    self.clear_loading_notifications();
+                    self.show_error(format!("Model file not found: {}", info.path.display()));
+                    return;
+                }
+                
+                // Create inference config from settings, override model path
+                let config = self.load_config(&info.path.to_string_lossy());
+
+                // Log desired provider
+                tracing::info!("Requested EP: {:?}, prefer_npu={}, power mode {}", config.execution_provider, config.prefer_npu, self.power_mode.label());
+
+                // Session construction runs on the blocking pool; the result arrives via poll_async_onnx_progress
+                self.clear_loading_notifications();
+                self.onnx_pending = Some(PendingOnnxLoad { model_name: info.name.clone(), remember_as: info.name.clone(), auto: false });
+                self.start_async_onnx_load(config, info.name.clone());
+            } else {
+                tracing::warn!("Selected model not found: {}", selected_model);
+                self.clear_loading_notifications();
+                self.show_warning(format!("Selected model not found: {}", selected_model));
+            }
+        } else {
+            self.show_info("Please select a model first from the 🧠 Models tab");
+        }
+    }
+}
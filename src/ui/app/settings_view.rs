@@ -0,0 +1,148 @@
+//! The settings window and the retention preview opened from it.
+
+use super::*;
+
+/// What the settings view keeps between frames.
+#[derive(Default)]
+pub(super) struct SettingsView {
+    pub(super) open: bool,
+    pub(super) tab: SettingsTab,
+    /// The config as last saved while the window is open, for Revert.
+    pub(super) snapshot: Option<AppConfig>,
+    /// Chats the retention limits would delete, shown until pruned or closed
+    pub(super) prune_preview: Option<Vec<Prunable>>,
+}
+
+impl RiaApp {
+    /// The settings window, with the ONNX Runtime manager under the Hardware tab.
+    pub(super) fn show_settings_window(&mut self, ctx: &egui::Context) {
+        if self.settings_view.open {
+            egui::Window::new("Settings")
+                .collapsible(false)
+                .resizable(true)
+                .default_size([400.0, 300.0])
+                .show(ctx, |ui| {
+                    let model_dirs_before = self.config.model_directories.clone();
+                    let network_before = self.config.network.clone();
+                    let catalog_before = self.config.catalog.clone();
+                    let response_cache_before = self.config.ai_config.response_cache;
+                    let sync_secret_saved_before = self.config.sync.secret_saved;
+                    let snapshot = self.settings_view.snapshot.get_or_insert_with(|| self.config.clone());
+                    let unsaved = self.config.settings_differ(snapshot);
+                    match settings::render_settings(ui, &mut self.config, &mut self.settings_view.tab, unsaved, &mut self.system_status, &mut self.personas) {
+                        Some(SettingsAction::Apply) => self.save_settings(),
+                        Some(SettingsAction::Revert) => {
+                            if let Some(snapshot) = &self.settings_view.snapshot {
+                                self.config = self.config.with_settings_of(snapshot);
+                            }
+                        }
+                        Some(SettingsAction::PreviewPruning) => self.settings_view.prune_preview = Some(self.prune_plan()),
+                        Some(SettingsAction::ClearResponseCache) => {
+                            let engine = self.inference_engine.clone();
+                            tokio::spawn(async move { engine.write().await.clear_response_cache() });
+                            self.show_info("Cached replies cleared");
+                        }
+                        None => {}
+                    }
+                    // The password itself is already in the credential store; record that now,
+                    // not only on Apply
+                    if self.config.sync.secret_saved != sync_secret_saved_before {
+                        self.save_config();
+                    }
+                    if self.config.ai_config.response_cache != response_cache_before {
+                        self.configure_response_cache();
+                    }
+                    if self.config.model_directories != model_dirs_before {
+                        self.models_view.manager.set_model_directories(self.config.model_directories.clone());
+                    }
+                    if self.config.network != network_before {
+                        self.models_view.manager.set_network(self.config.network.clone());
+                        self.runtime_manager.set_network(self.config.network.clone());
+                    }
+                    if self.config.catalog != catalog_before {
+                        self.models_view.manager.set_catalog(self.config.catalog.clone());
+                    }
+
+                    if self.settings_view.tab == SettingsTab::Hardware {
+                        ui.add_space(20.0);
+                        let selected = self.config.managed_onnx_runtime.clone();
+                        if let Some(choice) = self.runtime_manager.render(ui, selected.as_deref(), &self.ort_runtime) {
+                            self.switch_onnx_runtime(choice);
+                        }
+                    }
+                    ui.add_space(10.0);
+
+                    ui.horizontal(|ui| {
+                        if ui.button("Close").clicked() {
+                            self.settings_view.open = false;
+                        }
+                        if ui.button("🩺 Export diagnostics").on_hover_text("Save a report to attach to bug reports").clicked() {
+                            self.export_diagnostics();
+                        }
+                    });
+                });
+        } else {
+            self.settings_view.snapshot = None;
+        }
+    }
+
+    /// What pruning would delete with the limits in Settings, and a button to do it now.
+    pub(super) fn render_prune_preview(&mut self, ctx: &egui::Context) {
+        let Some(plan) = &self.settings_view.prune_preview else { return };
+        let mut open = true;
+        let mut prune = false;
+        let mut close = false;
+        egui::Window::new("🧹 Pruning preview")
+            .open(&mut open)
+            .collapsible(false)
+            .default_width(420.0)
+            .show(ctx, |ui| {
+                if plan.is_empty() {
+                    ui.label("Nothing to prune: every chat is within the limits.");
+                } else {
+                    let bytes: u64 = plan.iter().map(|p| p.bytes).sum();
+                    ui.label(format!("{} chats ({:.1} MB) would be deleted:", plan.len(), bytes as f64 / (1024.0 * 1024.0)));
+                    egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                        egui::Grid::new("prune_preview").num_columns(3).striped(true).spacing([12.0, 4.0]).show(ui, |ui| {
+                            for candidate in plan {
+                                ui.label(&candidate.title);
+                                ui.label(egui::RichText::new(candidate.updated_at.with_timezone(&chrono::Local).format("%Y-%m-%d").to_string()).weak());
+                                ui.label(egui::RichText::new(candidate.reason.label()).small().weak());
+                                ui.end_row();
+                            }
+                        });
+                    });
+                    ui.label(egui::RichText::new("Pin a chat from its menu in the sidebar to keep it.").small().weak());
+                }
+                ui.horizontal(|ui| {
+                    prune = ui.add_enabled(!plan.is_empty(), egui::Button::new("🗑 Prune now")).clicked();
+                    close = ui.button("Close").clicked();
+                });
+            });
+        if prune {
+            // Recompute so chats opened or pinned since the preview are spared
+            let plan = self.prune_plan();
+            self.prune_sessions(&plan);
+            self.show_success(format!("Deleted {} chats", plan.len()));
+            self.settings_view.prune_preview = None;
+        } else if close || !open {
+            self.settings_view.prune_preview = None;
+        }
+    }
+
+    /// Save from the settings window: invalid values are refused rather than saved to fail
+    /// later, and anything merely suspicious is pointed out.
+    fn save_settings(&mut self) {
+        let issues = self.config.issues();
+        if let Some(issue) = issues.iter().find(|i| i.blocks_save()) {
+            self.show_error(format!("Settings not saved: {}", issue.message));
+            return;
+        }
+        self.save_config();
+        self.settings_view.snapshot = Some(self.config.clone());
+        match issues.first() {
+            Some(issue) => self.show_warning(format!("Settings saved. {}", issue.message)),
+            None => self.show_success("Settings saved"),
+        }
+    }
+}
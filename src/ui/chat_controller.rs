@@ -0,0 +1,468 @@
+//! The send → stream → finish cycle of chat replies, without any UI.
+//!
+//! [`ChatController`] starts generations on the inference engine, takes in their streamed
+//! text, queues messages sent while the chats are busy and turns finished generations into
+//! messages on their chat. The app drives it once a frame and shows what it reports, such as
+//! notifications, saving and usage stats; the tests below drive it with a scripted provider.
+
+use crate::ai::inference::{self, FinishReason, InferenceEngine};
+use crate::ai::prefill::PrefillMonitor;
+use crate::ai::worker::InferenceWorkers;
+use crate::ai::{ChatMessage, ChatSession, GenerationOverrides, MessageRole, SessionSummary};
+use crate::storage::stats as usage;
+use crate::ui::streaming::Pacer;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::mpsc;
+use tokio::sync::mpsc::error::TryRecvError;
+use tokio::sync::RwLock;
+
+/// Asked of the model, after the conversation, to make a `/summarize` summary.
+pub const SUMMARY_INSTRUCTION: &str = "Summarize our conversation so far in a few short paragraphs or bullet points. \
+Keep the facts, decisions, code and open questions that later replies would need; leave out small talk.";
+
+/// Heads the summary message in the chat.
+pub const SUMMARY_HEADING: &str = "📝 **Summary of the conversation so far**";
+
+/// A reply being generated for one chat.
+pub struct Generation {
+    pub rx: mpsc::Receiver<String>,
+    /// The reply's trace once the text is ready, or the error that ended it.
+    outcome_rx: tokio::sync::oneshot::Receiver<Result<inference::GenerationTrace, String>>,
+    /// Set while the provider is working on this reply; before that it waits for another chat's.
+    pub running: Arc<AtomicBool>,
+    /// Automatic retries after transient provider errors so far.
+    pub retries: Arc<AtomicU32>,
+    pub buffer: String,
+    /// How much of `buffer` the preview shows.
+    pub pacer: Pacer,
+    /// Set once the task has sent everything; the reply is finished when the preview catches up.
+    done: bool,
+    started: Instant,
+    pub kind: ReplyKind,
+}
+
+/// What a generation's text becomes once it is finished.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum ReplyKind {
+    /// A new assistant message.
+    #[default]
+    Reply,
+    /// More of the assistant message with this id, which stopped at the token limit.
+    Continue(String),
+    /// A summary of the chat so far (`/summarize`), recorded as the chat's summary.
+    Summary { replace_in_context: bool },
+}
+
+/// A reply that ended in an error before any text arrived.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FailedReply {
+    pub error: String,
+    pub retries: u32,
+}
+
+/// How a generation ended, as reported by [`ChatController::finish`].
+#[derive(Debug)]
+pub enum Finished {
+    /// Nothing was streamed. A failure is also kept as the chat's [`FailedReply`].
+    Nothing { error: Option<String> },
+    /// The text was added to the chat at `session_idx`.
+    Reply {
+        session_idx: usize,
+        kind: ReplyKind,
+        elapsed: f64,
+        /// Estimated tokens of the text added.
+        tokens: u64,
+        trace: Option<inference::GenerationTrace>,
+    },
+}
+
+pub struct ChatController {
+    engine: Arc<RwLock<InferenceEngine>>,
+    /// Threads that run the providers, off the tokio runtime.
+    workers: InferenceWorkers,
+    prefill_monitor: PrefillMonitor,
+    /// Replies being generated, by session id; at most `limit`.
+    generations: HashMap<String, Generation>,
+    /// Messages sent while their chat was busy, as (session id, message), oldest first.
+    queued: VecDeque<(String, ChatMessage)>,
+    /// Replies that failed with nothing streamed, by session id, until retried or dismissed.
+    failed: HashMap<String, FailedReply>,
+    /// Replies the provider stopped at `max_tokens`, by message id; offered a Continue chip.
+    truncated: HashSet<String>,
+    limit: usize,
+}
+
+impl ChatController {
+    /// A controller running at most `limit` replies at once, on as many inference threads.
+    pub fn new(engine: Arc<RwLock<InferenceEngine>>, prefill_monitor: PrefillMonitor, limit: usize) -> Self {
+        Self {
+            engine,
+            workers: InferenceWorkers::new(limit),
+            prefill_monitor,
+            generations: HashMap::new(),
+            queued: VecDeque::new(),
+            failed: HashMap::new(),
+            truncated: HashSet::new(),
+            limit: limit.max(1),
+        }
+    }
+
+    /// Change how many replies may generate at once, with a new pool of as many inference
+    /// threads. Replies already running finish on the old threads, which then exit.
+    pub fn set_limit(&mut self, limit: usize) {
+        let limit = limit.max(1);
+        if limit != self.limit {
+            self.workers = InferenceWorkers::new(limit);
+            self.limit = limit;
+        }
+    }
+
+    /// Start generating in the background. Replies for different chats on the same provider
+    /// take turns; each streams as soon as its text is ready. The provider runs on the
+    /// inference threads, not the tokio runtime, and the engine stays unlocked meanwhile.
+    pub fn spawn(&self, messages_snapshot: Vec<ChatMessage>, overrides: GenerationOverrides) -> Generation {
+        let engine_arc = self.engine.clone();
+        let workers = self.workers.clone();
+        let prefill_monitor = self.prefill_monitor.clone();
+        let (ui_tx, ui_rx) = mpsc::channel(64);
+        let (outcome_tx, outcome_rx) = tokio::sync::oneshot::channel();
+        let running = Arc::new(AtomicBool::new(false));
+        let (task_running, generation_running) = (running.clone(), running.clone());
+        let retries = Arc::new(AtomicU32::new(0));
+        let task_retries = retries.clone();
+
+        // Start a background task to stream chunks
+        tokio::spawn(async move {
+            let started = move || task_running.store(true, Ordering::Relaxed);
+            let on_retry = |attempt| task_retries.store(attempt, Ordering::Relaxed);
+            let generated = InferenceEngine::generate_detached(&engine_arc, &workers, &messages_snapshot, &overrides, started, on_retry).await;
+            prefill_monitor.clear();
+            generation_running.store(false, Ordering::Relaxed);
+
+            match generated {
+                Ok((mut rx, trace)) => {
+                    let _ = outcome_tx.send(Ok(trace));
+                    while let Some(chunk) = rx.recv().await {
+                        if ui_tx.send(chunk).await.is_err() {
+                            break;
+                        }
+                    }
+                }
+                Err(e) => {
+                    tracing::error!("Streaming generation failed: {:#}", e);
+                    let _ = outcome_tx.send(Err(format!("{e:#}")));
+                }
+            }
+            drop(ui_tx);
+        });
+        Generation {
+            rx: ui_rx,
+            outcome_rx,
+            running,
+            retries,
+            buffer: String::new(),
+            pacer: Pacer::default(),
+            done: false,
+            started: Instant::now(),
+            kind: ReplyKind::Reply,
+        }
+    }
+
+    /// Generate for the chat from `messages_snapshot`; see [`ReplyKind`] for what becomes of it.
+    pub fn start(&mut self, session_id: String, messages_snapshot: Vec<ChatMessage>, overrides: GenerationOverrides, kind: ReplyKind) {
+        self.failed.remove(&session_id);
+        if let ReplyKind::Continue(message_id) = &kind {
+            self.truncated.remove(message_id);
+        }
+        let generation = Generation { kind, ..self.spawn(messages_snapshot, overrides) };
+        self.generations.insert(session_id, generation);
+    }
+
+    pub fn generation(&self, session_id: &str) -> Option<&Generation> {
+        self.generations.get(session_id)
+    }
+
+    pub fn is_generating(&self, session_id: &str) -> bool {
+        self.generations.contains_key(session_id)
+    }
+
+    /// Chats with a reply in progress.
+    pub fn generating(&self) -> impl Iterator<Item = &String> {
+        self.generations.keys()
+    }
+
+    /// How many replies are in progress.
+    pub fn active(&self) -> usize {
+        self.generations.len()
+    }
+
+    /// Whether every generation slot is taken.
+    pub fn is_full(&self) -> bool {
+        self.generations.len() >= self.limit
+    }
+
+    /// Whether a reply for the chat can start right away.
+    pub fn can_start(&self, session_id: &str) -> bool {
+        !self.is_generating(session_id) && !self.is_full()
+    }
+
+    /// Hold a message until its chat and a generation slot are free.
+    pub fn queue(&mut self, session_id: String, message: ChatMessage) {
+        self.queued.push_back((session_id, message));
+    }
+
+    pub fn queued(&self) -> &VecDeque<(String, ChatMessage)> {
+        &self.queued
+    }
+
+    pub fn unqueue(&mut self, pos: usize) {
+        self.queued.remove(pos);
+    }
+
+    /// The oldest queued message that can be sent now: a chat's next message waits for its
+    /// previous reply.
+    pub fn next_queued(&mut self) -> Option<(String, ChatMessage)> {
+        if self.is_full() {
+            return None;
+        }
+        let pos = self.queued.iter().position(|(id, _)| !self.generations.contains_key(id))?;
+        self.queued.remove(pos)
+    }
+
+    pub fn failure(&self, session_id: &str) -> Option<&FailedReply> {
+        self.failed.get(session_id)
+    }
+
+    pub fn dismiss_failure(&mut self, session_id: &str) {
+        self.failed.remove(session_id);
+    }
+
+    pub fn is_truncated(&self, message_id: &str) -> bool {
+        self.truncated.contains(message_id)
+    }
+
+    /// Stop the chat's reply and forget anything failed or queued for it, as when it is deleted.
+    pub fn forget(&mut self, session_id: &str) {
+        self.generations.remove(session_id);
+        self.failed.remove(session_id);
+        self.queued.retain(|(id, _)| id != session_id);
+    }
+
+    /// Pull streamed text into every generation's buffer and reveal it at `chars_per_second`.
+    /// Returns the chats whose generation is done and fully shown, ready for [`finish`](Self::finish).
+    pub fn poll(&mut self, chars_per_second: u32, now: Instant) -> Vec<String> {
+        let mut finished = Vec::new();
+        for (session_id, generation) in &mut self.generations {
+            while !generation.done {
+                match generation.rx.try_recv() {
+                    Ok(chunk) => {
+                        generation.pacer.received(&chunk);
+                        generation.buffer.push_str(&chunk);
+                    }
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => generation.done = true,
+                }
+            }
+            generation.pacer.advance(chars_per_second, now);
+            if generation.done && generation.pacer.caught_up() {
+                finished.push(session_id.clone());
+            }
+        }
+        finished
+    }
+
+    /// End the chat's generation, adding what was streamed so far to the chat in `sessions`.
+    /// Stopping a reply early is finishing it before it is done; dropping the receiver stops
+    /// the generation task at its next chunk. `None` if the chat wasn't generating or has been
+    /// deleted meanwhile. `keep_trace` stores the reply's trace on its message.
+    pub fn finish(&mut self, sessions: &mut [ChatSession], session_id: &str, keep_trace: bool) -> Option<Finished> {
+        let mut generation = self.generations.remove(session_id)?;
+        let session_idx = sessions.iter().position(|s| s.id == session_id)?;
+        let outcome = generation.outcome_rx.try_recv().ok();
+        if generation.buffer.is_empty() {
+            let error = match outcome {
+                Some(Err(error)) => {
+                    let retries = generation.retries.load(Ordering::Relaxed);
+                    self.failed.insert(session_id.to_string(), FailedReply { error: error.clone(), retries });
+                    Some(error)
+                }
+                _ => None,
+            };
+            return Some(Finished::Nothing { error });
+        }
+        let elapsed = generation.started.elapsed().as_secs_f64();
+        let trace = outcome.and_then(Result::ok);
+        let tokens = trace
+            .as_ref()
+            .and_then(|t| t.reply_tokens)
+            .map_or_else(|| usage::estimate_tokens(&generation.buffer), u64::from);
+        let truncated = trace.as_ref().is_some_and(|t| t.finish_reason == Some(FinishReason::Length));
+
+        let session = &mut sessions[session_idx];
+        let continued = match &generation.kind {
+            ReplyKind::Continue(id) => session.messages.iter_mut().find(|m| &m.id == id),
+            _ => None,
+        };
+        let message_id = match continued {
+            Some(message) => {
+                message.content.push_str(&generation.buffer);
+                message.mark_edited();
+                message.inference_time = Some(message.inference_time.unwrap_or(0.0) + elapsed);
+                message.id.clone()
+            }
+            None => {
+                let content = match generation.kind {
+                    ReplyKind::Summary { .. } => format!("{SUMMARY_HEADING}\n\n{}", generation.buffer.trim()),
+                    _ => std::mem::take(&mut generation.buffer),
+                };
+                let ai_message = ChatMessage {
+                    id: uuid::Uuid::new_v4().to_string(),
+                    content,
+                    role: MessageRole::Assistant,
+                    timestamp: chrono::Utc::now(),
+                    model_used: Some("Streaming".to_string()),
+                    inference_time: Some(elapsed),
+                    images: Vec::new(),
+                    trace: trace.clone().filter(|_| keep_trace),
+                    reply_to: None,
+                    feedback: None,
+                    edited_at: None,
+                };
+                let id = ai_message.id.clone();
+                session.messages.push(ai_message);
+                id
+            }
+        };
+        if let ReplyKind::Summary { replace_in_context } = generation.kind {
+            session.summary = Some(SessionSummary { message_id: message_id.clone(), replace_in_context });
+        }
+        if truncated {
+            self.truncated.insert(message_id);
+        }
+        session.updated_at = chrono::Utc::now();
+        Some(Finished::Reply { session_idx, kind: generation.kind, elapsed, tokens, trace })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::mock::MockProvider;
+    use std::time::Duration;
+
+    fn session(id: &str) -> ChatSession {
+        ChatSession {
+            id: id.into(),
+            title: id.into(),
+            messages: vec![ChatMessage::user("Hi")],
+            ..Default::default()
+        }
+    }
+
+    fn controller(provider: MockProvider, limit: usize) -> ChatController {
+        let mut engine = InferenceEngine::new();
+        let id = engine.add_provider_sync(Box::new(provider));
+        engine.set_active_provider_sync(id).unwrap();
+        ChatController::new(Arc::new(RwLock::new(engine)), PrefillMonitor::default(), limit)
+    }
+
+    /// Poll until a generation is done, as the app does every frame.
+    async fn wait_for_finished(chat: &mut ChatController) -> Vec<String> {
+        for _ in 0..500 {
+            let finished = chat.poll(0, Instant::now());
+            if !finished.is_empty() {
+                return finished;
+            }
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        panic!("the generation never finished");
+    }
+
+    fn start(chat: &mut ChatController, session: &ChatSession, kind: ReplyKind) {
+        chat.start(session.id.clone(), session.messages.clone(), GenerationOverrides::default(), kind);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_reply_streams_into_the_chat_and_queue_waits_its_turn() {
+        let mut chat = controller(MockProvider::new().reply("Hello there, how can I help?").reply("Second"), 1);
+        let mut sessions = vec![session("a"), session("b")];
+        start(&mut chat, &sessions[0], ReplyKind::Reply);
+        assert!(!chat.can_start("b"));
+        chat.queue("b".into(), ChatMessage::system("queued"));
+        assert!(chat.next_queued().is_none(), "every slot is taken");
+
+        assert_eq!(wait_for_finished(&mut chat).await, ["a"]);
+        let Some(Finished::Reply { session_idx, tokens, .. }) = chat.finish(&mut sessions, "a", false) else { panic!("no reply") };
+        assert_eq!((session_idx, tokens), (0, usage::estimate_tokens("Hello there, how can I help?")));
+        let reply = sessions[0].messages.last().unwrap();
+        assert!(matches!(reply.role, MessageRole::Assistant));
+        assert_eq!(reply.content, "Hello there, how can I help?");
+        assert_eq!(chat.active(), 0);
+        assert_eq!(chat.next_queued().map(|(id, m)| (id, m.content)), Some(("b".to_string(), "queued".to_string())));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_only_a_reply_cut_off_at_the_limit_offers_continue() {
+        let long = "word ".repeat(400);
+        let mut chat = controller(MockProvider::new().reply(&long).cut_off("1, 2,").reply(" 3."), 1);
+        let mut sessions = vec![session("a")];
+
+        // However long, a reply that ended by itself is complete
+        start(&mut chat, &sessions[0], ReplyKind::Reply);
+        wait_for_finished(&mut chat).await;
+        chat.finish(&mut sessions, "a", false);
+        assert!(!chat.is_truncated(&sessions[0].messages.last().unwrap().id));
+
+        start(&mut chat, &sessions[0], ReplyKind::Reply);
+        wait_for_finished(&mut chat).await;
+        chat.finish(&mut sessions, "a", false);
+        let cut_off = sessions[0].messages.last().unwrap().id.clone();
+        assert!(chat.is_truncated(&cut_off));
+
+        start(&mut chat, &sessions[0], ReplyKind::Continue(cut_off.clone()));
+        assert!(!chat.is_truncated(&cut_off));
+        wait_for_finished(&mut chat).await;
+        chat.finish(&mut sessions, "a", false);
+        assert_eq!(sessions[0].messages.last().unwrap().content, "1, 2, 3.");
+        assert!(!chat.is_truncated(&cut_off));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_stopping_retrying_and_failing() {
+        let (provider, release) = MockProvider::new().reply("Too late").fail("Execution timed out").reply("Recovered").gated();
+        let prompts = provider.prompts();
+        let mut chat = controller(provider, 1);
+        let mut sessions = vec![session("a")];
+
+        // Stopped while the provider is still working: nothing is added and nothing failed
+        start(&mut chat, &sessions[0], ReplyKind::Reply);
+        assert!(matches!(chat.finish(&mut sessions, "a", false), Some(Finished::Nothing { error: None })));
+        assert_eq!(sessions[0].messages.len(), 1);
+        assert!(chat.failure("a").is_none());
+        release.send(()).unwrap();
+
+        // A transient error is retried on the same provider
+        start(&mut chat, &sessions[0], ReplyKind::Reply);
+        release.send(()).unwrap();
+        release.send(()).unwrap();
+        wait_for_finished(&mut chat).await;
+        assert_eq!(chat.generation("a").unwrap().retries.load(Ordering::Relaxed), 1);
+        assert!(matches!(chat.finish(&mut sessions, "a", false), Some(Finished::Reply { .. })));
+        assert_eq!(sessions[0].messages.last().unwrap().content, "Recovered");
+        assert_eq!(prompts.lock().unwrap().len(), 3);
+
+        // A request the provider can't take fails before anything streams and is kept for a retry
+        let overrides = GenerationOverrides { constraint: Some(crate::ai::constraint::OutputConstraint::Json), ..Default::default() };
+        chat.start("a".into(), sessions[0].messages.clone(), overrides, ReplyKind::Reply);
+        wait_for_finished(&mut chat).await;
+        let Some(Finished::Nothing { error: Some(error) }) = chat.finish(&mut sessions, "a", false) else { panic!("expected a failure") };
+        assert!(error.contains("can't constrain its output"), "{error}");
+        assert_eq!(chat.failure("a").map(|f| f.error.as_str()), Some(error.as_str()));
+        assert_eq!(sessions[0].messages.len(), 2);
+        chat.dismiss_failure("a");
+        assert!(chat.failure("a").is_none());
+    }
+}
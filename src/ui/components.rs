@@ -1,5 +1,6 @@
 use eframe::egui;
-use crate::utils::system::SystemInfo;
+use crate::utils::system::{ComputeDevice, SystemInfo};
+use crate::ui::theme::Palette;
 use std::time::{Instant, Duration};
 
 // Download state tracking
@@ -22,6 +23,9 @@ pub enum DownloadStatus {
     Completed,
     Failed(String),
     Cancelled,
+    /// An attempt failed and the download is tried again after `delay_secs`. `mirror` is
+    /// (mirror number, mirror count) once the primary URL has been given up on.
+    Retrying { attempt: u32, max_attempts: u32, mirror: Option<(usize, usize)>, delay_secs: u64, error: String },
 }
 
 // Enhanced download progress component
@@ -49,9 +53,10 @@ impl DownloadProgressCard {
     }
     
     pub fn show(&mut self, ui: &mut egui::Ui) {
+        let palette = Palette::current(ui.ctx());
         egui::Frame::none()
-            .fill(egui::Color32::from_rgb(25, 35, 45))
-            .stroke(egui::Stroke::new(1.0, egui::Color32::from_rgb(60, 70, 80)))
+            .fill(palette.card_fill)
+            .stroke(egui::Stroke::new(1.0, palette.card_stroke))
             .rounding(8.0)
             .inner_margin(12.0)
             .show(ui, |ui| {
@@ -64,6 +69,7 @@ impl DownloadProgressCard {
                         DownloadStatus::Completed => ("✅", egui::Color32::GREEN),
                         DownloadStatus::Failed(_) => ("❌", egui::Color32::RED),
                         DownloadStatus::Cancelled => ("🚫", egui::Color32::GRAY),
+                        DownloadStatus::Retrying { .. } => ("🔁", egui::Color32::from_rgb(255, 165, 0)),
                     };
                     
                     ui.colored_label(color, icon);
@@ -91,7 +97,7 @@ impl DownloadProgressCard {
                             }
                             
                             // Add cancel button for active downloads
-                            if matches!(self.info.status, DownloadStatus::Downloading | DownloadStatus::Starting) {
+                            if matches!(self.info.status, DownloadStatus::Downloading | DownloadStatus::Starting | DownloadStatus::Retrying { .. }) {
                                 ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
                                     if ui.small_button("❌")
                                         .on_hover_text("Cancel download")
@@ -103,6 +109,19 @@ impl DownloadProgressCard {
                             }
                         });
                         
+                        if let DownloadStatus::Retrying { attempt, max_attempts, mirror, delay_secs, error } = &self.info.status {
+                            let source = match mirror {
+                                Some((index, count)) => format!("mirror {index}/{count}"),
+                                None => "primary URL".to_string(),
+                            };
+                            let when = if *delay_secs > 0 { format!(" in {delay_secs}s") } else { String::new() };
+                            ui.colored_label(
+                                egui::Color32::from_rgb(255, 165, 0),
+                                format!("Retrying{when}: attempt {attempt}/{max_attempts} on {source}"),
+                            )
+                            .on_hover_text(error);
+                        }
+
                         // Error message for failed downloads
                         if let DownloadStatus::Failed(error) = &self.info.status {
                             ui.colored_label(egui::Color32::RED, format!("Error: {}", error));
@@ -252,12 +271,12 @@ impl LoadingSpinner {
         let center = rect.center();
 
         // Draw spinning arc
-        let _stroke = egui::Stroke::new(3.0, egui::Color32::from_rgb(100, 200, 255));
+        let accent = Palette::current(ui.ctx()).accent;
         
         for i in 0..8 {
             let angle = self.rotation + i as f32 * std::f32::consts::PI / 4.0;
             let alpha = ((i as f32 / 8.0) * 255.0) as u8;
-            let color = egui::Color32::from_rgba_unmultiplied(100, 200, 255, alpha);
+            let color = egui::Color32::from_rgba_unmultiplied(accent.r(), accent.g(), accent.b(), alpha);
             
             let start = center + [angle.cos() * radius * 0.6, angle.sin() * radius * 0.6].into();
             let end = center + [angle.cos() * radius * 0.9, angle.sin() * radius * 0.9].into();
@@ -411,6 +430,8 @@ pub struct SystemStatusComponent {
     last_update: Instant,
     update_interval: Duration,
     show_details: bool,
+    compute_devices: Option<Vec<ComputeDevice>>,
+    compute_devices_include_openvino: bool,
 }
 
 impl Default for SystemStatusComponent {
@@ -420,6 +441,8 @@ impl Default for SystemStatusComponent {
             last_update: Instant::now(),
             update_interval: Duration::from_secs(2), // Update every 2 seconds
             show_details: false,
+            compute_devices: None,
+            compute_devices_include_openvino: false,
         }
     }
 }
@@ -510,6 +533,17 @@ impl SystemStatusComponent {
         });
     }
 
+    /// Selectable EP devices; enumerated once and cached since it shells out to vendor tools,
+    /// and once more when the background OpenVINO device query finishes.
+    pub fn compute_devices(&mut self) -> &[ComputeDevice] {
+        let openvino_ready = crate::utils::openvino::devices_if_ready().is_some();
+        if self.compute_devices.is_none() || (openvino_ready && !self.compute_devices_include_openvino) {
+            self.compute_devices = Some(self.system_info.enumerate_compute_devices());
+            self.compute_devices_include_openvino = openvino_ready;
+        }
+        self.compute_devices.as_deref().unwrap_or_default()
+    }
+
     pub fn get_memory_usage_percent(&self) -> f32 {
         let mem_info = self.system_info.get_memory_info();
         if let Some(usage_percent) = mem_info.get("usage_percent") {
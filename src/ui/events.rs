@@ -0,0 +1,159 @@
+//! One channel for what background work reports back to the UI.
+//!
+//! Tasks post [`AppEvent`]s through an [`EventSender`]; `update()` drains the [`EventBus`]
+//! once per frame and handles the events in the order they were sent. Events from work that
+//! can be superseded, like a model load that was cancelled and started again, carry the id
+//! of the work they belong to so stale ones can be told apart.
+
+use crate::ai::AIProvider;
+use crate::sync::SyncOutcome;
+use std::path::PathBuf;
+use tokio::sync::mpsc;
+
+/// Something finished or moved on in the background.
+pub enum AppEvent {
+    /// Progress of model load `load`.
+    OnnxLoad { load: u64, progress: OnnxLoadProgress },
+    /// Model load `load` built its provider; sent just before its `Loaded` progress.
+    ProviderLoaded { load: u64, provider: Box<dyn AIProvider + Send + Sync> },
+    SyncFinished(anyhow::Result<SyncOutcome>),
+    /// The sync password older versions kept in config.json was moved to the credential store.
+    SyncSecretMoved(anyhow::Result<()>),
+    /// A dropped model file was copied into the models directory, or failed to be.
+    ModelImported { source: PathBuf, result: anyhow::Result<PathBuf> },
+    /// The ONNX Runtime library was checked; `switched_to` names the runtime just chosen in
+    /// Settings, if that is what triggered the check.
+    RuntimeDetected { report: crate::ai::runtime::RuntimeReport, switched_to: Option<String> },
+    /// A diagnostics report was written to this path, or failed to be.
+    DiagnosticsExported(anyhow::Result<PathBuf>),
+}
+
+#[derive(Debug)]
+#[allow(dead_code)]
+pub enum OnnxLoadProgress {
+    Phase(String),
+    AttemptEP(String),
+    LoadError { ep: String, error: String },
+    Error(String),
+    Loaded { ep: String, accepts_images: bool },
+    Failed(String),
+    Cancelled,
+    AttemptResult(OnnxEpAttempt),
+    /// Periodic while a session is being built: elapsed time and resident memory growth
+    /// since the attempt started (a proxy for how much of the model has been mapped).
+    Heartbeat { ep: String, elapsed_secs: f32, bytes_mapped: u64, total_bytes: u64 },
+}
+
+impl OnnxLoadProgress {
+    /// Whether the load is over, one way or the other.
+    pub fn is_final(&self) -> bool {
+        matches!(self, Self::Loaded { .. } | Self::Failed(_) | Self::Cancelled | Self::Error(_))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct OnnxEpAttempt {
+    pub ep: String,
+    pub success: bool,
+    pub error_kind: Option<EpErrorKind>,
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum EpErrorKind { VersionMismatch, SessionBuild, ProviderInit, UnsupportedModel, GpuSetup, Io, Unknown }
+
+/// Posts events to the bus; cheap to clone into tasks. Events sent after the app has gone
+/// are dropped.
+#[derive(Clone)]
+pub struct EventSender(mpsc::UnboundedSender<AppEvent>);
+
+impl EventSender {
+    pub fn send(&self, event: AppEvent) {
+        let _ = self.0.send(event);
+    }
+
+    /// A sender for the events of model load `load`.
+    pub fn for_load(&self, load: u64) -> LoadEvents {
+        LoadEvents { events: self.clone(), load }
+    }
+}
+
+/// Reports one model load.
+#[derive(Clone)]
+pub struct LoadEvents {
+    events: EventSender,
+    load: u64,
+}
+
+impl LoadEvents {
+    pub fn progress(&self, progress: OnnxLoadProgress) {
+        self.events.send(AppEvent::OnnxLoad { load: self.load, progress });
+    }
+
+    pub fn provider(&self, provider: Box<dyn AIProvider + Send + Sync>) {
+        self.events.send(AppEvent::ProviderLoaded { load: self.load, provider });
+    }
+}
+
+pub struct EventBus {
+    tx: mpsc::UnboundedSender<AppEvent>,
+    rx: mpsc::UnboundedReceiver<AppEvent>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+        Self { tx, rx }
+    }
+
+    pub fn sender(&self) -> EventSender {
+        EventSender(self.tx.clone())
+    }
+
+    /// Everything sent since the last drain, oldest first.
+    pub fn drain(&mut self) -> Vec<AppEvent> {
+        let mut events = Vec::new();
+        while let Ok(event) = self.rx.try_recv() {
+            events.push(event);
+        }
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_events_arrive_in_order_with_their_load() {
+        let mut bus = EventBus::new();
+        let (first, second) = (bus.sender().for_load(1), bus.sender().for_load(2));
+        first.progress(OnnxLoadProgress::AttemptEP("Cuda".into()));
+        bus.sender().send(AppEvent::ModelImported { source: "a.onnx".into(), result: Ok("models/a.onnx".into()) });
+        second.progress(OnnxLoadProgress::Loaded { ep: "Cpu".into(), accepts_images: false });
+        first.progress(OnnxLoadProgress::Cancelled);
+
+        let seen: Vec<_> = bus
+            .drain()
+            .into_iter()
+            .map(|event| match event {
+                AppEvent::OnnxLoad { load, progress } => format!("{load}: {progress:?} final={}", progress.is_final()),
+                AppEvent::ModelImported { source, .. } => format!("imported {}", source.display()),
+                _ => unreachable!(),
+            })
+            .collect();
+        assert_eq!(seen, [
+            "1: AttemptEP(\"Cuda\") final=false",
+            "imported a.onnx",
+            "2: Loaded { ep: \"Cpu\", accepts_images: false } final=true",
+            "1: Cancelled final=true",
+        ]);
+        assert!(bus.drain().is_empty());
+    }
+}
@@ -0,0 +1,398 @@
+//! Chat transcripts saved as files: Markdown ([`ChatSession::to_markdown`]) or a standalone,
+//! read-only HTML page for sharing.
+//!
+//! The HTML page needs nothing but a browser: styles are inline, code blocks are highlighted
+//! here rather than by a script, and message text goes through the same Markdown parser as
+//! model cards, so raw HTML in a message is dropped instead of rendered.
+
+use crate::ai::{ChatMessage, ChatSession, MessageRole};
+use crate::ui::markdown::{self, Block, Span};
+use std::fmt::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Markdown,
+    Html,
+}
+
+impl ExportFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Markdown => "md",
+            Self::Html => "html",
+        }
+    }
+}
+
+/// Colors of the HTML page; `Auto` follows the reader's system setting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HtmlTheme {
+    Auto,
+    Light,
+    Dark,
+}
+
+/// The chat in `format`.
+pub fn render(session: &ChatSession, format: ExportFormat, theme: HtmlTheme) -> String {
+    match format {
+        ExportFormat::Markdown => session.to_markdown(),
+        ExportFormat::Html => to_html(session, theme),
+    }
+}
+
+const STYLE: &str = r#"
+:root { --bg: #f6f7f9; --card: #ffffff; --text: #1f2328; --muted: #6b7280; --border: #d8dee4;
+  --user: #e8f0fe; --accent: #2f6fdf; --code-bg: #f3f4f6; --kw: #a626a4; --str: #50a14f; --com: #8a8f98;
+  --num: #b76b01; --ty: #c18401; --fun: #4078f2; }
+:root[data-theme="dark"] { --bg: #16181d; --card: #1f2229; --text: #e6e6e6; --muted: #9aa1ad; --border: #30343d;
+  --user: #23324d; --accent: #6ea8fe; --code-bg: #15171c; --kw: #c678dd; --str: #98c379; --com: #7f848e;
+  --num: #d19a66; --ty: #e5c07b; --fun: #61afef; }
+@media (prefers-color-scheme: dark) { :root:not([data-theme]) { --bg: #16181d; --card: #1f2229; --text: #e6e6e6;
+  --muted: #9aa1ad; --border: #30343d; --user: #23324d; --accent: #6ea8fe; --code-bg: #15171c; --kw: #c678dd;
+  --str: #98c379; --com: #7f848e; --num: #d19a66; --ty: #e5c07b; --fun: #61afef; } }
+* { box-sizing: border-box; }
+body { margin: 0; background: var(--bg); color: var(--text); line-height: 1.55;
+  font: 15px/1.55 system-ui, -apple-system, "Segoe UI", Roboto, sans-serif; }
+main { max-width: 860px; margin: 0 auto; padding: 32px 20px; }
+header h1 { margin: 0 0 4px; font-size: 26px; }
+.meta { color: var(--muted); font-size: 13px; }
+.message { background: var(--card); border: 1px solid var(--border); border-radius: 12px; padding: 14px 18px; margin: 14px 0; }
+.message.user { background: var(--user); margin-left: 12%; }
+.message.assistant { margin-right: 6%; }
+.message.system, .message.tool { font-size: 14px; border-style: dashed; }
+.author { font-weight: 600; margin-bottom: 6px; }
+.author .meta { font-weight: normal; margin-left: 6px; }
+blockquote { margin: 8px 0; padding: 2px 12px; border-left: 3px solid var(--accent); color: var(--muted); }
+pre { background: var(--code-bg); border: 1px solid var(--border); border-radius: 8px; padding: 12px; overflow-x: auto; }
+pre .lang { display: block; color: var(--muted); font-size: 11px; margin-bottom: 6px; text-transform: uppercase; }
+code { font: 13px/1.45 ui-monospace, "Cascadia Code", Consolas, monospace; }
+p code, li code, td code { background: var(--code-bg); padding: 1px 4px; border-radius: 4px; }
+table { border-collapse: collapse; margin: 8px 0; }
+th, td { border: 1px solid var(--border); padding: 4px 10px; text-align: left; }
+a { color: var(--accent); }
+.kw { color: var(--kw); } .str { color: var(--str); } .com { color: var(--com); font-style: italic; }
+.num { color: var(--num); } .ty { color: var(--ty); } .fun { color: var(--fun); }
+footer { margin-top: 28px; text-align: center; }
+"#;
+
+/// The chat as a self-contained HTML page.
+pub fn to_html(session: &ChatSession, theme: HtmlTheme) -> String {
+    let theme_attr = match theme {
+        HtmlTheme::Auto => "",
+        HtmlTheme::Light => " data-theme=\"light\"",
+        HtmlTheme::Dark => " data-theme=\"dark\"",
+    };
+    let title = escape(&session.title);
+    let mut out = String::new();
+    let _ = write!(
+        out,
+        "<!DOCTYPE html>\n<html lang=\"en\"{theme_attr}>\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <meta name=\"generator\" content=\"RIA AI Chat\">\n<title>{title}</title>\n<style>{STYLE}</style>\n</head>\n<body>\n<main>\n"
+    );
+
+    let mut meta = vec![format!("{} messages", session.messages.len())];
+    if let (Some(first), Some(last)) = (session.messages.first(), session.messages.last()) {
+        let day = |m: &ChatMessage| m.timestamp.with_timezone(&chrono::Local).format("%B %-d, %Y").to_string();
+        let (from, to) = (day(first), day(last));
+        meta.push(if from == to { from } else { format!("{from} – {to}") });
+    }
+    if let Some(persona) = &session.persona {
+        meta.push(format!("Persona: {persona}"));
+    }
+    let _ = write!(out, "<header>\n<h1>{title}</h1>\n<p class=\"meta\">{}</p>\n</header>\n", escape(&meta.join(" · ")));
+
+    for message in &session.messages {
+        message_html(&mut out, message);
+    }
+
+    let exported = chrono::Local::now().format("%Y-%m-%d %H:%M");
+    let _ = write!(out, "<footer class=\"meta\">Exported from RIA AI Chat on {exported}</footer>\n</main>\n</body>\n</html>\n");
+    out
+}
+
+fn message_html(out: &mut String, message: &ChatMessage) {
+    let class = match message.role {
+        MessageRole::User => "user",
+        MessageRole::Assistant => "assistant",
+        MessageRole::System => "system",
+        MessageRole::Tool => "tool",
+    };
+    let time = message.timestamp.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M");
+    let model = message.model_used.as_deref().map(|m| format!(" · {}", escape(m))).unwrap_or_default();
+    let _ = write!(
+        out,
+        "<article class=\"message {class}\">\n<div class=\"author\">{}<span class=\"meta\">{time}{model}</span></div>\n",
+        message.role.label()
+    );
+    if let Some(reply) = &message.reply_to {
+        let _ = writeln!(out, "<blockquote>{}</blockquote>", escape(&reply.excerpt).replace('\n', "<br>"));
+    }
+    for block in markdown::parse(&message.content, None) {
+        block_html(out, &block);
+    }
+    if !message.images.is_empty() {
+        let _ = writeln!(out, "<p class=\"meta\">🖼 {} image(s) attached</p>", message.images.len());
+    }
+    out.push_str("</article>\n");
+}
+
+fn block_html(out: &mut String, block: &Block) {
+    match block {
+        Block::Heading(level, spans) => {
+            let level = (level + 1).min(6);
+            let _ = writeln!(out, "<h{level}>{}</h{level}>", spans_html(spans));
+        }
+        Block::Paragraph(spans) => {
+            let _ = writeln!(out, "<p>{}</p>", spans_html(spans));
+        }
+        Block::ListItem { depth, marker, spans } => {
+            let indent = depth.saturating_sub(1) * 20;
+            let _ = writeln!(out, "<p style=\"margin-left: {indent}px\">{} {}</p>", escape(marker), spans_html(spans));
+        }
+        Block::Quote(spans) => {
+            let _ = writeln!(out, "<blockquote>{}</blockquote>", spans_html(spans));
+        }
+        Block::Code { lang, code } => {
+            out.push_str("<pre>");
+            if let Some(lang) = lang {
+                let _ = write!(out, "<span class=\"lang\">{}</span>", escape(lang));
+            }
+            let _ = writeln!(out, "<code>{}</code></pre>", highlight(code, lang.as_deref()));
+        }
+        Block::Table(rows) => {
+            out.push_str("<table>\n");
+            for (i, row) in rows.iter().enumerate() {
+                let cell = if i == 0 { "th" } else { "td" };
+                out.push_str("<tr>");
+                for spans in row {
+                    let _ = write!(out, "<{cell}>{}</{cell}>", spans_html(spans));
+                }
+                out.push_str("</tr>\n");
+            }
+            out.push_str("</table>\n");
+        }
+        Block::Rule => out.push_str("<hr>\n"),
+    }
+}
+
+fn spans_html(spans: &[Span]) -> String {
+    let mut out = String::new();
+    for span in spans {
+        if span.text == "\n" {
+            out.push_str("<br>");
+            continue;
+        }
+        let mut html = escape(&span.text);
+        for (on, tag) in [(span.code, "code"), (span.bold, "strong"), (span.italic, "em"), (span.strike, "s")] {
+            if on {
+                html = format!("<{tag}>{html}</{tag}>");
+            }
+        }
+        match span.link.as_deref().filter(|url| is_safe_link(url)) {
+            Some(url) => {
+                let _ = write!(out, "<a href=\"{}\" rel=\"noopener noreferrer\">{html}</a>", escape(url));
+            }
+            None => out.push_str(&html),
+        }
+    }
+    out
+}
+
+/// Only links that can't run script in the reader's browser.
+fn is_safe_link(url: &str) -> bool {
+    let url = url.trim_start().to_ascii_lowercase();
+    ["http://", "https://", "mailto:"].iter().any(|scheme| url.starts_with(scheme))
+}
+
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// How to recognize the parts of one language family that get a color.
+struct Syntax {
+    keywords: &'static [&'static str],
+    line_comments: &'static [&'static str],
+    block_comment: Option<(&'static str, &'static str)>,
+    quotes: &'static [char],
+}
+
+const RUST: Syntax = Syntax {
+    keywords: &[
+        "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern", "false", "fn", "for",
+        "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return", "self", "Self", "static",
+        "struct", "super", "trait", "true", "type", "unsafe", "use", "where", "while",
+    ],
+    line_comments: &["//"],
+    block_comment: Some(("/*", "*/")),
+    quotes: &['"'],
+};
+
+const PYTHON: Syntax = Syntax {
+    keywords: &[
+        "and", "as", "assert", "async", "await", "break", "class", "continue", "def", "del", "elif", "else", "except",
+        "False", "finally", "for", "from", "global", "if", "import", "in", "is", "lambda", "None", "nonlocal", "not", "or",
+        "pass", "raise", "return", "self", "True", "try", "while", "with", "yield",
+    ],
+    line_comments: &["#"],
+    block_comment: None,
+    quotes: &['"', '\''],
+};
+
+const C_LIKE: Syntax = Syntax {
+    keywords: &[
+        "abstract", "async", "auto", "await", "bool", "break", "case", "catch", "char", "class", "const", "continue",
+        "default", "defer", "delete", "do", "double", "else", "enum", "export", "extends", "false", "final", "float", "for",
+        "from", "func", "function", "go", "if", "implements", "import", "in", "int", "interface", "let", "long", "namespace",
+        "new", "null", "nullptr", "package", "private", "protected", "public", "return", "short", "static", "struct",
+        "switch", "this", "throw", "true", "try", "type", "typedef", "undefined", "unsigned", "using", "var", "void",
+        "while", "yield",
+    ],
+    line_comments: &["//"],
+    block_comment: Some(("/*", "*/")),
+    quotes: &['"', '\'', '`'],
+};
+
+const SHELL: Syntax = Syntax {
+    keywords: &["case", "do", "done", "echo", "elif", "else", "esac", "export", "fi", "for", "function", "if", "in", "local", "return", "then", "while"],
+    line_comments: &["#"],
+    block_comment: None,
+    quotes: &['"', '\''],
+};
+
+const SQL: Syntax = Syntax {
+    keywords: &[
+        "and", "as", "by", "create", "delete", "from", "group", "insert", "into", "join", "left", "limit", "not", "null",
+        "on", "or", "order", "select", "set", "table", "update", "values", "where",
+    ],
+    line_comments: &["--"],
+    block_comment: Some(("/*", "*/")),
+    quotes: &['\''],
+};
+
+const DATA: Syntax = Syntax { keywords: &["true", "false", "null"], line_comments: &["#"], block_comment: None, quotes: &['"', '\''] };
+
+fn syntax(lang: &str) -> Option<&'static Syntax> {
+    Some(match lang {
+        "rust" | "rs" => &RUST,
+        "python" | "py" => &PYTHON,
+        "c" | "h" | "cpp" | "c++" | "hpp" | "cc" | "java" | "kotlin" | "kt" | "cs" | "csharp" | "go" | "swift" | "js"
+        | "javascript" | "jsx" | "ts" | "typescript" | "tsx" | "php" | "dart" | "scala" => &C_LIKE,
+        "sh" | "bash" | "shell" | "zsh" | "console" | "powershell" | "ps1" => &SHELL,
+        "sql" => &SQL,
+        "json" | "toml" | "yaml" | "yml" | "ini" => &DATA,
+        _ => return None,
+    })
+}
+
+/// `code` as escaped HTML with keywords, strings, comments, numbers, types and calls wrapped
+/// in classed spans. Languages it doesn't know are only escaped.
+fn highlight(code: &str, lang: Option<&str>) -> String {
+    let Some(syntax) = lang.and_then(syntax) else { return escape(code) };
+    let case_insensitive = std::ptr::eq(syntax, &SQL);
+    let mut out = String::with_capacity(code.len() * 2);
+    let span = |out: &mut String, class: &str, text: &str| {
+        let _ = write!(out, "<span class=\"{class}\">{}</span>", escape(text));
+    };
+    let mut rest = code;
+    while let Some(c) = rest.chars().next() {
+        if let Some((open, close)) = syntax.block_comment.filter(|(open, _)| rest.starts_with(open)) {
+            let end = rest[open.len()..].find(close).map_or(rest.len(), |i| open.len() + i + close.len());
+            span(&mut out, "com", &rest[..end]);
+            rest = &rest[end..];
+        } else if syntax.line_comments.iter().any(|marker| rest.starts_with(marker)) {
+            let end = rest.find('\n').unwrap_or(rest.len());
+            span(&mut out, "com", &rest[..end]);
+            rest = &rest[end..];
+        } else if syntax.quotes.contains(&c) {
+            let mut end = rest.len();
+            let mut escaped = false;
+            for (i, ch) in rest.char_indices().skip(1) {
+                match ch {
+                    _ if escaped => escaped = false,
+                    '\\' => escaped = true,
+                    '\n' if c != '`' => {
+                        end = i;
+                        break;
+                    }
+                    _ if ch == c => {
+                        end = i + ch.len_utf8();
+                        break;
+                    }
+                    _ => {}
+                }
+            }
+            span(&mut out, "str", &rest[..end]);
+            rest = &rest[end..];
+        } else if c.is_ascii_digit() {
+            let end = rest.find(|ch: char| !(ch.is_ascii_alphanumeric() || ch == '.' || ch == '_')).unwrap_or(rest.len());
+            span(&mut out, "num", &rest[..end]);
+            rest = &rest[end..];
+        } else if c.is_alphabetic() || c == '_' {
+            let end = rest.find(|ch: char| !(ch.is_alphanumeric() || ch == '_')).unwrap_or(rest.len());
+            let word = &rest[..end];
+            let keyword = if case_insensitive {
+                syntax.keywords.iter().any(|k| k.eq_ignore_ascii_case(word))
+            } else {
+                syntax.keywords.contains(&word)
+            };
+            if keyword {
+                span(&mut out, "kw", word);
+            } else if rest[end..].starts_with('(') || rest[end..].starts_with("!(") {
+                span(&mut out, "fun", word);
+            } else if word.starts_with(|ch: char| ch.is_uppercase()) && !case_insensitive {
+                span(&mut out, "ty", word);
+            } else {
+                out.push_str(&escape(word));
+            }
+            rest = &rest[end..];
+        } else {
+            out.push_str(&escape(&rest[..c.len_utf8()]));
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_html_export_escapes_and_highlights() {
+        let question = ChatMessage::user("How do I print <b>bold</b>? [x](javascript:alert(1))");
+        let answer = ChatMessage {
+            model_used: Some("phi".into()),
+            ..ChatMessage::assistant("Like this:\n\n```rust\n// say hi\nlet s = \"<hi>\";\nprintln!(\"{s}\", 42);\n```")
+        };
+        let session = ChatSession {
+            id: "s1".into(),
+            title: "Tags & <script>".into(),
+            messages: vec![question, answer],
+            ..Default::default()
+        };
+
+        let html = render(&session, ExportFormat::Html, HtmlTheme::Dark);
+        assert!(html.starts_with("<!DOCTYPE html>\n<html lang=\"en\" data-theme=\"dark\">"));
+        assert!(html.contains("<title>Tags &amp; &lt;script&gt;</title>"));
+        assert!(!html.contains("<script>") && !html.contains("<b>") && !html.contains("javascript:"));
+        assert!(html.contains("<article class=\"message user\">") && html.contains("Assistant<span class=\"meta\">"));
+        assert!(html.contains("<span class=\"com\">// say hi</span>"));
+        assert!(html.contains("<span class=\"kw\">let</span> s = <span class=\"str\">&quot;&lt;hi&gt;&quot;</span>;"));
+        assert!(html.contains("<span class=\"fun\">println</span>!(") && html.contains("<span class=\"num\">42</span>"));
+
+        assert_eq!(highlight("a < b", None), "a &lt; b");
+        assert_eq!(highlight("SELECT 1 -- one", Some("sql")), "<span class=\"kw\">SELECT</span> <span class=\"num\">1</span> <span class=\"com\">-- one</span>");
+    }
+}
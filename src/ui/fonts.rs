@@ -0,0 +1,181 @@
+//! Font setup: user-supplied fonts plus installed CJK and emoji fallbacks.
+//!
+//! egui only bundles Latin, a monochrome emoji font and an icon font, so Chinese, Japanese
+//! and Korean chat text renders as boxes unless a font covering it is added to the
+//! fallback chain. Font files are validated first because egui panics on data it can't parse.
+
+use anyhow::{anyhow, Result};
+use egui::{FontData, FontDefinitions, FontFamily};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FontSettings {
+    /// TTF/OTF/TTC files to load; the first one becomes the main UI font and the rest
+    /// are used as fallbacks.
+    pub custom_fonts: Vec<PathBuf>,
+    /// Fall back to an installed CJK font for Chinese, Japanese and Korean text.
+    pub cjk_fallback: bool,
+    /// Fall back to an installed symbol/emoji font for glyphs egui's bundled one lacks.
+    pub system_emoji: bool,
+}
+
+impl Default for FontSettings {
+    fn default() -> Self {
+        Self { custom_fonts: Vec::new(), cjk_fallback: true, system_emoji: true }
+    }
+}
+
+/// Installed fonts covering CJK, most complete first. `.ttc` collections use face 0.
+fn cjk_candidates() -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    if cfg!(target_os = "windows") {
+        let fonts = std::env::var_os("WINDIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from(r"C:\Windows"))
+            .join("Fonts");
+        for name in ["msyh.ttc", "YuGothM.ttc", "malgun.ttf", "simsun.ttc", "msgothic.ttc"] {
+            paths.push(fonts.join(name));
+        }
+    } else if cfg!(target_os = "macos") {
+        for path in [
+            "/System/Library/Fonts/PingFang.ttc",
+            "/System/Library/Fonts/Hiragino Sans GB.ttc",
+            "/System/Library/Fonts/ヒラギノ角ゴシック W3.ttc",
+            "/System/Library/Fonts/AppleSDGothicNeo.ttc",
+            "/Library/Fonts/Arial Unicode.ttf",
+        ] {
+            paths.push(PathBuf::from(path));
+        }
+    } else {
+        for path in [
+            "/usr/share/fonts/opentype/noto/NotoSansCJK-Regular.ttc",
+            "/usr/share/fonts/noto-cjk/NotoSansCJK-Regular.ttc",
+            "/usr/share/fonts/google-noto-cjk/NotoSansCJK-Regular.ttc",
+            "/usr/share/fonts/truetype/wqy/wqy-microhei.ttc",
+            "/usr/share/fonts/wenquanyi/wqy-microhei/wqy-microhei.ttc",
+            "/usr/share/fonts/truetype/droid/DroidSansFallbackFull.ttf",
+        ] {
+            paths.push(PathBuf::from(path));
+        }
+    }
+    paths
+}
+
+/// Symbol/emoji fonts with plain outlines. Color-only formats (Apple Color Emoji,
+/// Noto Color Emoji) can't be rasterized by egui and are left out.
+fn emoji_candidates() -> Vec<PathBuf> {
+    if cfg!(target_os = "windows") {
+        let fonts = std::env::var_os("WINDIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from(r"C:\Windows"))
+            .join("Fonts");
+        vec![fonts.join("seguiemj.ttf"), fonts.join("seguisym.ttf")]
+    } else if cfg!(target_os = "macos") {
+        vec![PathBuf::from("/System/Library/Fonts/Apple Symbols.ttf")]
+    } else {
+        vec![
+            PathBuf::from("/usr/share/fonts/truetype/ancient-scripts/Symbola_hint.ttf"),
+            PathBuf::from("/usr/share/fonts/TTF/Symbola.ttf"),
+            PathBuf::from("/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf"),
+        ]
+    }
+}
+
+/// Read a font file and check that egui will accept it.
+pub fn load_font(path: &Path) -> Result<FontData> {
+    let bytes = std::fs::read(path).map_err(|e| anyhow!("Cannot read font {}: {e}", path.display()))?;
+    let font = ab_glyph::FontRef::try_from_slice_and_index(&bytes, 0)
+        .map_err(|_| anyhow!("{} is not a TrueType/OpenType font", path.display()))?;
+    let units = ab_glyph::Font::units_per_em(&font).unwrap_or(0.0);
+    if !(16.0..=16384.0).contains(&units) {
+        return Err(anyhow!("{} has an unsupported units-per-em ({units})", path.display()));
+    }
+    Ok(FontData::from_owned(bytes))
+}
+
+fn first_loadable(candidates: Vec<PathBuf>) -> Option<(PathBuf, FontData)> {
+    candidates
+        .into_iter()
+        .filter(|p| p.is_file())
+        .find_map(|p| load_font(&p).ok().map(|data| (p, data)))
+}
+
+/// Build egui's font definitions for `settings`. Fonts that fail to load are skipped and
+/// reported in the returned warnings instead of aborting startup.
+pub fn build_font_definitions(settings: &FontSettings) -> (FontDefinitions, Vec<String>) {
+    let mut fonts = FontDefinitions::default();
+    let mut warnings = Vec::new();
+
+    let mut primary = None;
+    let mut fallbacks = Vec::new();
+    for (i, path) in settings.custom_fonts.iter().enumerate() {
+        match load_font(path) {
+            Ok(data) => {
+                let name = format!("custom-{i}");
+                fonts.font_data.insert(name.clone(), data);
+                if primary.is_none() {
+                    primary = Some(name);
+                } else {
+                    fallbacks.push(name);
+                }
+            }
+            Err(e) => warnings.push(e.to_string()),
+        }
+    }
+
+    if settings.cjk_fallback {
+        match first_loadable(cjk_candidates()) {
+            Some((path, data)) => {
+                tracing::info!("Using {} for CJK text", path.display());
+                fonts.font_data.insert("system-cjk".to_owned(), data);
+                fallbacks.push("system-cjk".to_owned());
+            }
+            None => tracing::debug!("No installed CJK font found"),
+        }
+    }
+    if settings.system_emoji {
+        if let Some((path, data)) = first_loadable(emoji_candidates()) {
+            tracing::info!("Using {} for extra symbols", path.display());
+            fonts.font_data.insert("system-emoji".to_owned(), data);
+            fallbacks.push("system-emoji".to_owned());
+        }
+    }
+
+    for family in [FontFamily::Proportional, FontFamily::Monospace] {
+        let list = fonts.families.entry(family.clone()).or_default();
+        if let Some(name) = &primary {
+            // Monospace keeps its own primary face so code stays aligned
+            if family == FontFamily::Proportional {
+                list.insert(0, name.clone());
+            } else {
+                list.push(name.clone());
+            }
+        }
+        list.extend(fallbacks.iter().cloned());
+    }
+
+    (fonts, warnings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_invalid_custom_font_is_skipped() {
+        let dir = tempfile::tempdir().unwrap();
+        let bogus = dir.path().join("bogus.ttf");
+        std::fs::write(&bogus, b"definitely not a font").unwrap();
+
+        let settings = FontSettings {
+            custom_fonts: vec![bogus, dir.path().join("missing.ttf")],
+            cjk_fallback: false,
+            system_emoji: false,
+        };
+        let (fonts, warnings) = build_font_definitions(&settings);
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(fonts.families[&FontFamily::Proportional], FontDefinitions::default().families[&FontFamily::Proportional]);
+    }
+}
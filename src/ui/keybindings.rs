@@ -0,0 +1,288 @@
+//! Rebindable in-app keyboard shortcuts.
+//!
+//! Bindings are stored in the config as shortcut strings such as `"CmdOrCtrl+N"` (`CmdOrCtrl`
+//! is Cmd on macOS and Ctrl elsewhere). Actions missing from the config use their default, and
+//! an empty string leaves an action unbound. Global shortcuts are consumed before the widgets
+//! run, so a binding never also reaches the message input.
+
+use anyhow::{anyhow, Result};
+use egui::{Key, KeyboardShortcut, Modifiers};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    NewChat,
+    SendMessage,
+    ToggleModels,
+    ToggleSettings,
+    ToggleFavorites,
+    ClearNotifications,
+    ClearInput,
+    ShowHelp,
+}
+
+impl Action {
+    pub const ALL: [Action; 8] = [
+        Action::NewChat,
+        Action::SendMessage,
+        Action::ToggleModels,
+        Action::ToggleSettings,
+        Action::ToggleFavorites,
+        Action::ClearNotifications,
+        Action::ClearInput,
+        Action::ShowHelp,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Action::NewChat => "New chat",
+            Action::SendMessage => "Send message",
+            Action::ToggleModels => "Toggle models",
+            Action::ToggleSettings => "Settings",
+            Action::ToggleFavorites => "Favorites",
+            Action::ClearNotifications => "Clear notifications",
+            Action::ClearInput => "Clear input",
+            Action::ShowHelp => "Shortcut help",
+        }
+    }
+
+    fn default_spec(self) -> &'static str {
+        match self {
+            Action::NewChat => "CmdOrCtrl+N",
+            Action::SendMessage => "CmdOrCtrl+Enter",
+            Action::ToggleModels => "CmdOrCtrl+M",
+            Action::ToggleSettings => "CmdOrCtrl+Comma",
+            Action::ToggleFavorites => "CmdOrCtrl+Shift+F",
+            Action::ClearNotifications => "CmdOrCtrl+K",
+            Action::ClearInput => "CmdOrCtrl+D",
+            Action::ShowHelp => "CmdOrCtrl+H",
+        }
+    }
+}
+
+/// Parse `"Ctrl+Shift+N"`-style shortcuts. Modifiers: `CmdOrCtrl`, `Cmd`, `Ctrl`, `Alt`, `Shift`.
+pub fn parse_shortcut(spec: &str) -> Result<KeyboardShortcut> {
+    let mut modifiers = Modifiers::NONE;
+    let mut key = None;
+    for part in spec.split('+').map(str::trim) {
+        match part.to_ascii_lowercase().as_str() {
+            "cmdorctrl" | "commandorcontrol" => modifiers = modifiers | Modifiers::COMMAND,
+            "cmd" | "command" | "super" => modifiers = modifiers | Modifiers::MAC_CMD,
+            "ctrl" | "control" => modifiers = modifiers | Modifiers::CTRL,
+            "alt" | "option" => modifiers = modifiers | Modifiers::ALT,
+            "shift" => modifiers = modifiers | Modifiers::SHIFT,
+            _ if key.is_some() => return Err(anyhow!("Shortcut '{spec}' has more than one key")),
+            _ => key = Some(Key::from_name(part).ok_or_else(|| anyhow!("Unknown key '{part}' in shortcut '{spec}'"))?),
+        }
+    }
+    let key = key.ok_or_else(|| anyhow!("Shortcut '{spec}' has no key"))?;
+    Ok(KeyboardShortcut::new(modifiers, key))
+}
+
+/// The config form of a shortcut, readable by `parse_shortcut`.
+pub fn shortcut_spec(shortcut: &KeyboardShortcut) -> String {
+    let modifiers = shortcut.modifiers;
+    let mut parts = Vec::new();
+    if modifiers.command {
+        parts.push("CmdOrCtrl");
+    } else if modifiers.mac_cmd {
+        parts.push("Cmd");
+    } else if modifiers.ctrl {
+        parts.push("Ctrl");
+    }
+    if modifiers.alt {
+        parts.push("Alt");
+    }
+    if modifiers.shift {
+        parts.push("Shift");
+    }
+    parts.push(shortcut.logical_key.name());
+    parts.join("+")
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KeyBindings {
+    /// Overrides of the default shortcuts; an empty string unbinds the action.
+    pub overrides: BTreeMap<Action, String>,
+}
+
+impl KeyBindings {
+    /// The shortcut for `action`, or `None` if it is unbound or its spec doesn't parse.
+    pub fn shortcut(&self, action: Action) -> Option<KeyboardShortcut> {
+        let spec = self.overrides.get(&action).map_or(action.default_spec(), String::as_str);
+        if spec.trim().is_empty() {
+            return None;
+        }
+        parse_shortcut(spec)
+            .map_err(|e| tracing::warn!("Ignoring key binding for {:?}: {}", action, e))
+            .ok()
+    }
+
+    pub fn set(&mut self, action: Action, shortcut: Option<KeyboardShortcut>) {
+        let spec = shortcut.map(|s| shortcut_spec(&s)).unwrap_or_default();
+        if spec == action.default_spec() {
+            self.overrides.remove(&action);
+        } else {
+            self.overrides.insert(action, spec);
+        }
+    }
+
+    pub fn reset(&mut self, action: Action) {
+        self.overrides.remove(&action);
+    }
+
+    /// Human-readable shortcut for menus and help, e.g. "Ctrl+N" (or "⌘N" on macOS).
+    pub fn describe(&self, ctx: &egui::Context, action: Action) -> String {
+        self.shortcut(action).map_or_else(|| "Unbound".to_string(), |s| ctx.format_shortcut(&s))
+    }
+
+    /// Other actions bound to the same shortcut as `action`.
+    pub fn conflicts(&self, action: Action) -> Vec<Action> {
+        let Some(shortcut) = self.shortcut(action) else { return Vec::new() };
+        Action::ALL
+            .into_iter()
+            .filter(|other| *other != action && self.shortcut(*other) == Some(shortcut))
+            .collect()
+    }
+
+    /// Bound actions, most specific shortcut first so `Ctrl+Shift+F` is consumed before `Ctrl+F`
+    /// can match it.
+    pub fn by_specificity(&self) -> Vec<(Action, KeyboardShortcut)> {
+        let mut bound: Vec<_> = Action::ALL
+            .into_iter()
+            .filter_map(|action| self.shortcut(action).map(|s| (action, s)))
+            .collect();
+        bound.sort_by_key(|(_, s)| {
+            let m = s.modifiers;
+            std::cmp::Reverse(u8::from(m.alt) + u8::from(m.shift) + u8::from(m.ctrl || m.command || m.mac_cmd))
+        });
+        bound
+    }
+
+    /// Whether `action`'s shortcut was pressed this frame, without consuming it.
+    pub fn pressed(&self, input: &egui::InputState, action: Action) -> bool {
+        self.shortcut(action)
+            .is_some_and(|s| input.modifiers.matches_logically(s.modifiers) && input.key_pressed(s.logical_key))
+    }
+}
+
+fn capture_id() -> egui::Id {
+    egui::Id::new("keybinding_capture")
+}
+
+/// The action the settings UI is recording a new shortcut for. Global shortcuts are
+/// suspended meanwhile so the keys reach the recorder.
+pub fn capturing(ctx: &egui::Context) -> Option<Action> {
+    ctx.data(|d| d.get_temp(capture_id()))
+}
+
+/// Settings section listing every action with its shortcut. Click a shortcut to record a new
+/// one; Escape cancels the recording.
+pub fn render(ui: &mut egui::Ui, bindings: &mut KeyBindings) {
+    let ctx = ui.ctx().clone();
+    let mut capture = capturing(&ctx);
+    if let Some(action) = capture {
+        let pressed = ui.input(|i| {
+            i.events.iter().find_map(|event| match event {
+                egui::Event::Key { key, pressed: true, modifiers, .. } => Some((*key, *modifiers)),
+                _ => None,
+            })
+        });
+        match pressed {
+            Some((Key::Escape, _)) => capture = None,
+            Some((key, modifiers)) => {
+                bindings.set(action, Some(KeyboardShortcut::new(normalize(modifiers), key)));
+                capture = None;
+            }
+            None => {}
+        }
+    }
+
+    let danger = ui.visuals().error_fg_color;
+    egui::Grid::new("keybindings_grid").num_columns(3).spacing([12.0, 4.0]).show(ui, |ui| {
+        for action in Action::ALL {
+            ui.label(action.label());
+            let text = if capture == Some(action) { "Press keys…".to_string() } else { bindings.describe(&ctx, action) };
+            let button = ui.add(egui::Button::new(text).min_size(egui::vec2(140.0, 0.0)));
+            crate::ui::a11y::set_name(&button, &format!("Shortcut for {}", action.label()));
+            if button.on_hover_text("Click, then press the new shortcut (Escape cancels)").clicked() {
+                capture = Some(action);
+            }
+            ui.horizontal(|ui| {
+                if ui.small_button("Unbind").clicked() {
+                    bindings.set(action, None);
+                }
+                if bindings.overrides.contains_key(&action) && ui.small_button("Reset").clicked() {
+                    bindings.reset(action);
+                }
+                let conflicts = bindings.conflicts(action);
+                if !conflicts.is_empty() {
+                    let names: Vec<_> = conflicts.iter().map(|a| a.label()).collect();
+                    ui.colored_label(danger, format!("Also used by {}", names.join(", ")));
+                }
+            });
+            ui.end_row();
+        }
+    });
+    if !bindings.overrides.is_empty() && ui.button("Reset all shortcuts").clicked() {
+        bindings.overrides.clear();
+    }
+
+    ctx.data_mut(|d| match capture {
+        Some(action) => d.insert_temp(capture_id(), action),
+        None => d.remove::<Action>(capture_id()),
+    });
+}
+
+/// Record Ctrl (or Cmd on macOS) as the platform command modifier so bindings carry over
+/// between systems.
+fn normalize(mut modifiers: Modifiers) -> Modifiers {
+    let command = if cfg!(target_os = "macos") { modifiers.mac_cmd } else { modifiers.ctrl };
+    if command {
+        modifiers.command = true;
+        if cfg!(target_os = "macos") {
+            modifiers.mac_cmd = false;
+        } else {
+            modifiers.ctrl = false;
+        }
+    }
+    modifiers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shortcut_specs_round_trip() {
+        let shortcut = parse_shortcut("CmdOrCtrl+Shift+F").unwrap();
+        assert_eq!(shortcut, KeyboardShortcut::new(Modifiers::COMMAND | Modifiers::SHIFT, Key::F));
+        assert_eq!(shortcut_spec(&shortcut), "CmdOrCtrl+Shift+F");
+        assert_eq!(parse_shortcut("ctrl + ,").unwrap(), KeyboardShortcut::new(Modifiers::CTRL, Key::Comma));
+        assert!(parse_shortcut("Ctrl+Shift").is_err());
+        assert!(parse_shortcut("Ctrl+N+M").is_err());
+    }
+
+    #[test]
+    fn test_overrides_unbinding_and_conflicts() {
+        let mut bindings = KeyBindings::default();
+        assert_eq!(bindings.shortcut(Action::ClearInput), Some(parse_shortcut("CmdOrCtrl+D").unwrap()));
+
+        bindings.set(Action::ClearInput, None);
+        assert_eq!(bindings.shortcut(Action::ClearInput), None);
+
+        bindings.set(Action::ShowHelp, Some(parse_shortcut("CmdOrCtrl+N").unwrap()));
+        assert_eq!(bindings.conflicts(Action::NewChat), vec![Action::ShowHelp]);
+
+        // Setting the default back drops the override
+        bindings.set(Action::ShowHelp, Some(parse_shortcut("CmdOrCtrl+H").unwrap()));
+        assert!(!bindings.overrides.contains_key(&Action::ShowHelp));
+
+        let json = serde_json::to_string(&bindings).unwrap();
+        assert_eq!(json, r#"{"overrides":{"clear_input":""}}"#);
+        assert_eq!(serde_json::from_str::<KeyBindings>(&json).unwrap(), bindings);
+    }
+}
@@ -0,0 +1,349 @@
+//! Read-only Markdown rendering, used for model cards.
+//!
+//! The document is parsed once into a flat list of [`Block`]s (paragraphs, headings, list
+//! items, code, tables) and drawn with ordinary egui widgets, so links stay clickable and
+//! text selectable. Raw HTML, which model cards use for logos and badges, is skipped, and
+//! images are replaced by their alt text.
+
+use pulldown_cmark::{CodeBlockKind, Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+
+/// A run of text sharing one style.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Span {
+    pub text: String,
+    pub bold: bool,
+    pub italic: bool,
+    pub code: bool,
+    pub strike: bool,
+    pub link: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Block {
+    Heading(u8, Vec<Span>),
+    Paragraph(Vec<Span>),
+    /// One list item (or a later paragraph of one, with an empty marker); `depth` starts at 1.
+    ListItem { depth: usize, marker: String, spans: Vec<Span> },
+    Quote(Vec<Span>),
+    /// A code block and the language named on its fence, if any.
+    Code { lang: Option<String>, code: String },
+    /// Rows of cells; the first row is the header.
+    Table(Vec<Vec<Vec<Span>>>),
+    Rule,
+}
+
+#[derive(Default)]
+struct Builder {
+    blocks: Vec<Block>,
+    spans: Vec<Span>,
+    base_url: Option<reqwest::Url>,
+    heading: Option<u8>,
+    // Next number of each open list, `None` for bullet lists
+    lists: Vec<Option<u64>>,
+    marker: Option<String>,
+    quote_depth: usize,
+    code: Option<String>,
+    code_lang: Option<String>,
+    table: Option<Vec<Vec<Vec<Span>>>>,
+    table_head: bool,
+    bold: usize,
+    italic: usize,
+    strike: usize,
+    link: Option<String>,
+}
+
+impl Builder {
+    fn push_text(&mut self, text: &str, code: bool) {
+        self.spans.push(Span {
+            text: text.to_string(),
+            bold: self.bold > 0 || self.table_head,
+            italic: self.italic > 0,
+            code,
+            strike: self.strike > 0,
+            link: self.link.clone(),
+        });
+    }
+
+    /// End the current run of inline text as whatever block encloses it.
+    fn flush(&mut self) {
+        if self.spans.is_empty() || self.table.is_some() {
+            return;
+        }
+        let spans = std::mem::take(&mut self.spans);
+        let block = if !self.lists.is_empty() {
+            Block::ListItem { depth: self.lists.len(), marker: self.marker.take().unwrap_or_default(), spans }
+        } else if self.quote_depth > 0 {
+            Block::Quote(spans)
+        } else {
+            Block::Paragraph(spans)
+        };
+        self.blocks.push(block);
+    }
+
+    /// Links relative to the document (`LICENSE`, `./docs/usage.md`) resolved against its URL.
+    fn resolve(&self, dest: &str) -> Option<String> {
+        if dest.starts_with('#') {
+            return None;
+        }
+        match reqwest::Url::parse(dest) {
+            Ok(url) => Some(url.to_string()),
+            Err(_) => self.base_url.as_ref()?.join(dest).ok().map(|url| url.to_string()),
+        }
+    }
+
+    fn start(&mut self, tag: Tag<'_>) {
+        match tag {
+            Tag::Heading { level, .. } => {
+                self.flush();
+                self.heading = Some(match level {
+                    HeadingLevel::H1 => 1,
+                    HeadingLevel::H2 => 2,
+                    HeadingLevel::H3 => 3,
+                    _ => 4,
+                });
+            }
+            Tag::List(start) => {
+                self.flush();
+                self.lists.push(start);
+            }
+            Tag::Item => {
+                self.flush();
+                self.marker = Some(match self.lists.last_mut() {
+                    Some(Some(n)) => {
+                        *n += 1;
+                        format!("{}.", *n - 1)
+                    }
+                    _ => "•".to_string(),
+                });
+            }
+            Tag::BlockQuote(_) => {
+                self.flush();
+                self.quote_depth += 1;
+            }
+            Tag::CodeBlock(kind) => {
+                self.flush();
+                self.code = Some(String::new());
+                self.code_lang = match kind {
+                    CodeBlockKind::Fenced(info) => info.split_whitespace().next().map(str::to_lowercase),
+                    CodeBlockKind::Indented => None,
+                };
+            }
+            Tag::Table(_) => {
+                self.flush();
+                self.table = Some(Vec::new());
+            }
+            Tag::TableHead | Tag::TableRow => {
+                self.table_head = matches!(tag, Tag::TableHead);
+                if let Some(rows) = self.table.as_mut() {
+                    rows.push(Vec::new());
+                }
+            }
+            Tag::Emphasis => self.italic += 1,
+            Tag::Strong => self.bold += 1,
+            Tag::Strikethrough => self.strike += 1,
+            Tag::Link { dest_url, .. } => self.link = self.resolve(&dest_url),
+            Tag::Image { .. } => self.push_text("🖼 ", false),
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, tag: TagEnd) {
+        match tag {
+            TagEnd::Heading(_) => {
+                let spans = std::mem::take(&mut self.spans);
+                if let Some(level) = self.heading.take() {
+                    self.blocks.push(Block::Heading(level, spans));
+                }
+            }
+            TagEnd::Paragraph | TagEnd::Item => self.flush(),
+            TagEnd::List(_) => {
+                self.flush();
+                self.lists.pop();
+            }
+            TagEnd::BlockQuote(_) => {
+                self.flush();
+                self.quote_depth = self.quote_depth.saturating_sub(1);
+            }
+            TagEnd::CodeBlock => {
+                if let Some(code) = self.code.take() {
+                    self.blocks.push(Block::Code { lang: self.code_lang.take(), code: code.trim_end_matches('\n').to_string() });
+                }
+            }
+            TagEnd::TableCell => {
+                let cell = std::mem::take(&mut self.spans);
+                if let Some(row) = self.table.as_mut().and_then(|rows| rows.last_mut()) {
+                    row.push(cell);
+                }
+            }
+            TagEnd::TableHead => self.table_head = false,
+            TagEnd::Table => {
+                if let Some(rows) = self.table.take() {
+                    self.blocks.push(Block::Table(rows));
+                }
+            }
+            TagEnd::Emphasis => self.italic = self.italic.saturating_sub(1),
+            TagEnd::Strong => self.bold = self.bold.saturating_sub(1),
+            TagEnd::Strikethrough => self.strike = self.strike.saturating_sub(1),
+            TagEnd::Link => self.link = None,
+            _ => {}
+        }
+    }
+}
+
+/// Parse `markdown` into blocks. Relative links are resolved against `base_url`.
+pub fn parse(markdown: &str, base_url: Option<&str>) -> Vec<Block> {
+    let mut builder = Builder { base_url: base_url.and_then(|url| reqwest::Url::parse(url).ok()), ..Default::default() };
+    let options = Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+    for event in Parser::new_ext(markdown, options) {
+        match event {
+            Event::Start(tag) => builder.start(tag),
+            Event::End(tag) => builder.end(tag),
+            Event::Text(text) => match builder.code.as_mut() {
+                Some(code) => code.push_str(&text),
+                None => builder.push_text(&text, false),
+            },
+            Event::Code(text) => builder.push_text(&text, true),
+            Event::SoftBreak => builder.push_text(" ", false),
+            Event::HardBreak => builder.push_text("\n", false),
+            Event::TaskListMarker(done) => builder.push_text(if done { "☑ " } else { "☐ " }, false),
+            Event::Rule => {
+                builder.flush();
+                builder.blocks.push(Block::Rule);
+            }
+            _ => {} // raw HTML, footnotes
+        }
+    }
+    builder.flush();
+    builder.blocks
+}
+
+fn show_spans(ui: &mut egui::Ui, spans: &[Span], size: Option<f32>, color: Option<egui::Color32>) {
+    ui.horizontal_wrapped(|ui| {
+        ui.spacing_mut().item_spacing.x = 0.0;
+        for span in spans {
+            if span.text == "\n" {
+                ui.end_row();
+                continue;
+            }
+            let mut text = egui::RichText::new(&span.text);
+            if let Some(size) = size {
+                text = text.size(size);
+            }
+            if let Some(color) = color {
+                text = text.color(color);
+            }
+            if span.bold {
+                text = text.strong();
+            }
+            if span.italic {
+                text = text.italics();
+            }
+            if span.strike {
+                text = text.strikethrough();
+            }
+            if span.code {
+                text = text.code();
+            }
+            match &span.link {
+                Some(url) => {
+                    ui.hyperlink_to(text, url).on_hover_text(url);
+                }
+                None => {
+                    ui.label(text);
+                }
+            }
+        }
+    });
+}
+
+pub fn show(ui: &mut egui::Ui, blocks: &[Block]) {
+    for block in blocks {
+        match block {
+            Block::Heading(level, spans) => {
+                ui.add_space(if *level <= 2 { 10.0 } else { 6.0 });
+                let size = match level {
+                    1 => 22.0,
+                    2 => 19.0,
+                    3 => 16.0,
+                    _ => 14.0,
+                };
+                let bold: Vec<Span> = spans.iter().map(|s| Span { bold: true, ..s.clone() }).collect();
+                show_spans(ui, &bold, Some(size), Some(ui.visuals().strong_text_color()));
+                if *level <= 2 {
+                    ui.separator();
+                }
+            }
+            Block::Paragraph(spans) => {
+                show_spans(ui, spans, None, None);
+                ui.add_space(6.0);
+            }
+            Block::ListItem { depth, marker, spans } => {
+                ui.horizontal(|ui| {
+                    ui.add_space(*depth as f32 * 14.0);
+                    ui.add_sized([18.0, ui.text_style_height(&egui::TextStyle::Body)], egui::Label::new(marker.as_str()));
+                    ui.vertical(|ui| show_spans(ui, spans, None, None));
+                });
+            }
+            Block::Quote(spans) => {
+                egui::Frame::none()
+                    .stroke(egui::Stroke::new(1.0, ui.visuals().widgets.noninteractive.bg_stroke.color))
+                    .inner_margin(egui::Margin::symmetric(10.0, 4.0))
+                    .show(ui, |ui| show_spans(ui, spans, None, Some(ui.visuals().weak_text_color())));
+                ui.add_space(6.0);
+            }
+            Block::Code { code, .. } => {
+                egui::Frame::none()
+                    .fill(ui.visuals().code_bg_color)
+                    .rounding(4.0)
+                    .inner_margin(8.0)
+                    .show(ui, |ui| {
+                        egui::ScrollArea::horizontal().id_salt(code.as_ptr()).show(ui, |ui| {
+                            ui.add(egui::Label::new(egui::RichText::new(code).monospace()).extend());
+                        });
+                    });
+                ui.add_space(6.0);
+            }
+            Block::Table(rows) => {
+                egui::Grid::new(rows.as_ptr()).striped(true).spacing([12.0, 4.0]).show(ui, |ui| {
+                    for row in rows {
+                        for cell in row {
+                            show_spans(ui, cell, None, None);
+                        }
+                        ui.end_row();
+                    }
+                });
+                ui.add_space(6.0);
+            }
+            Block::Rule => {
+                ui.separator();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blocks() {
+        let markdown = "# Phi-3\n\nRead the **license** in [LICENSE](LICENSE).\n\n- one\n- two\n  1. nested\n\n```Rust title\ncode\n```\n\n| a | b |\n|---|---|\n| 1 | 2 |\n\n<img src=\"logo.png\">\n";
+        let blocks = parse(markdown, Some("https://huggingface.co/org/repo/raw/main/README.md"));
+        let plain = |text: &str| Span { text: text.into(), ..Default::default() };
+
+        assert_eq!(blocks[0], Block::Heading(1, vec![plain("Phi-3")]));
+        let Block::Paragraph(spans) = &blocks[1] else { panic!("{:?}", blocks[1]) };
+        assert!(spans.iter().any(|s| s.text == "license" && s.bold));
+        let link = spans.iter().find(|s| s.text == "LICENSE").unwrap();
+        assert_eq!(link.link.as_deref(), Some("https://huggingface.co/org/repo/raw/main/LICENSE"));
+
+        assert_eq!(blocks[2], Block::ListItem { depth: 1, marker: "•".into(), spans: vec![plain("one")] });
+        assert_eq!(blocks[3], Block::ListItem { depth: 1, marker: "•".into(), spans: vec![plain("two")] });
+        assert_eq!(blocks[4], Block::ListItem { depth: 2, marker: "1.".into(), spans: vec![plain("nested")] });
+        assert_eq!(blocks[5], Block::Code { lang: Some("rust".into()), code: "code".into() });
+        let Block::Table(rows) = &blocks[6] else { panic!("{:?}", blocks[6]) };
+        assert_eq!(rows.len(), 2);
+        assert!(rows[0][0][0].bold && !rows[1][0][0].bold);
+        assert_eq!(blocks.len(), 7, "HTML is skipped");
+    }
+}
@@ -0,0 +1,590 @@
+//! Inline TeX math in chat messages.
+//!
+//! Models write formulas as `$...$`, `$$...$$`, `\(...\)` or `\[...\]`. There is no TeX
+//! engine here: the common subset (Greek letters, operators, arrows, fractions, roots,
+//! scripts, `\mathbb`, accents) is turned into Unicode text, and superscripts/subscripts are
+//! laid out as smaller raised or lowered sections of the same `LayoutJob`. Anything not
+//! understood is left as written. Symbols outside egui's bundled font need a fallback font
+//! that covers them (see the font settings).
+
+use egui::text::{LayoutJob, TextFormat};
+use egui::{Align, Color32, FontId};
+
+/// Part of a message: plain text or a formula.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Segment<'a> {
+    Text(&'a str),
+    Math { tex: &'a str, display: bool },
+}
+
+/// Vertical position of a run of formula text.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Level {
+    Normal,
+    Sup,
+    Sub,
+}
+
+/// Split `text` into plain text and formulas. Code spans and fenced blocks are left alone,
+/// and a `$` only opens inline math when it hugs its contents (`$x$`, not `$5 and $10`).
+pub fn split(text: &str) -> Vec<Segment<'_>> {
+    let mut segments = Vec::new();
+    let mut start = 0;
+    let mut i = 0;
+    let mut in_fence = false;
+    let mut in_code = false;
+    while i < text.len() {
+        let rest = &text[i..];
+        if rest.starts_with("```") {
+            in_fence = !in_fence;
+            i += 3;
+            continue;
+        }
+        if in_fence {
+            i += rest.chars().next().map_or(1, char::len_utf8);
+            continue;
+        }
+        if rest.starts_with('`') {
+            in_code = !in_code;
+            i += 1;
+            continue;
+        }
+        if in_code {
+            i += rest.chars().next().map_or(1, char::len_utf8);
+            continue;
+        }
+
+        if rest.starts_with("\\$") {
+            i += 2;
+            continue;
+        }
+        let found = if let Some(inner) = rest.strip_prefix("$$") {
+            inner.find("$$").map(|end| (2, end, 2, true))
+        } else if let Some(inner) = rest.strip_prefix("\\[") {
+            inner.find("\\]").map(|end| (2, end, 2, true))
+        } else if let Some(inner) = rest.strip_prefix("\\(") {
+            inner.find("\\)").map(|end| (2, end, 2, false))
+        } else if let Some(inner) = rest.strip_prefix('$') {
+            inline_dollar(inner).map(|end| (1, end, 1, false))
+        } else {
+            None
+        };
+
+        match found {
+            Some((open, len, close, display)) if !rest[open..open + len].trim().is_empty() => {
+                if start < i {
+                    segments.push(Segment::Text(&text[start..i]));
+                }
+                segments.push(Segment::Math { tex: rest[open..open + len].trim(), display });
+                i += open + len + close;
+                start = i;
+            }
+            _ => i += rest.chars().next().map_or(1, char::len_utf8),
+        }
+    }
+    if start < text.len() {
+        segments.push(Segment::Text(&text[start..]));
+    }
+    segments
+}
+
+/// Length of the formula after an opening `$`, if the closing one is on the same line.
+fn inline_dollar(rest: &str) -> Option<usize> {
+    if rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let end = rest.find(['$', '\n'])?;
+    let after = &rest[end + 1..];
+    let valid = rest[end..].starts_with('$')
+        && !rest[..end].ends_with(char::is_whitespace)
+        && !rest[..end].ends_with('\\')
+        && !rest[..end].contains('`')
+        && !after.starts_with(|c: char| c.is_ascii_digit());
+    valid.then_some(end)
+}
+
+pub fn contains_math(text: &str) -> bool {
+    (text.contains('$') || text.contains("\\(") || text.contains("\\["))
+        && split(text).iter().any(|s| matches!(s, Segment::Math { .. }))
+}
+
+/// Lay out a message with its formulas rendered. Display math gets its own, slightly larger
+/// line.
+pub fn layout(text: &str, font_size: f32, color: Color32, line_height: Option<f32>) -> LayoutJob {
+    let format = |size: f32, level: Level| TextFormat {
+        font_id: FontId::proportional(size),
+        color,
+        line_height: if level == Level::Normal { line_height } else { None },
+        valign: if level == Level::Sup { Align::TOP } else { Align::BOTTOM },
+        ..Default::default()
+    };
+    let mut job = LayoutJob::default();
+    let mut break_before_text = false;
+    for segment in split(text) {
+        match segment {
+            Segment::Text(text) => {
+                if break_before_text && !text.starts_with('\n') {
+                    job.append("\n", 0.0, format(font_size, Level::Normal));
+                }
+                break_before_text = false;
+                job.append(text, 0.0, format(font_size, Level::Normal));
+            }
+            Segment::Math { tex, display } => {
+                let size = if display { font_size * 1.15 } else { font_size };
+                if display {
+                    if !job.text.is_empty() && !job.text.ends_with('\n') {
+                        job.append("\n", 0.0, format(font_size, Level::Normal));
+                    }
+                    job.append("    ", 0.0, format(size, Level::Normal));
+                }
+                for (level, piece) in render(tex) {
+                    let piece_size = if level == Level::Normal { size } else { size * 0.7 };
+                    job.append(&piece, 0.0, format(piece_size, level));
+                }
+                break_before_text = display;
+            }
+        }
+    }
+    job
+}
+
+/// A formula as plain Unicode text, with scripts written as `^x` / `_x`.
+#[cfg(test)]
+fn to_plain(tex: &str) -> String {
+    render(tex)
+        .into_iter()
+        .map(|(level, piece)| match level {
+            Level::Normal => piece,
+            Level::Sup if piece.chars().count() == 1 => format!("^{piece}"),
+            Level::Sup => format!("^({piece})"),
+            Level::Sub if piece.chars().count() == 1 => format!("_{piece}"),
+            Level::Sub => format!("_({piece})"),
+        })
+        .collect()
+}
+
+fn render(tex: &str) -> Vec<(Level, String)> {
+    let mut out = Vec::new();
+    let mut parser = Parser { chars: tex.chars().collect(), pos: 0 };
+    parser.run(Level::Normal, false, &mut out);
+    out
+}
+
+fn push(out: &mut Vec<(Level, String)>, level: Level, text: &str) {
+    match out.last_mut() {
+        Some((last, piece)) if *last == level => piece.push_str(text),
+        _ => out.push((level, text.to_string())),
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    /// Render until the end of input or, inside a group, the closing brace.
+    fn run(&mut self, level: Level, in_group: bool, out: &mut Vec<(Level, String)>) {
+        while let Some(c) = self.peek() {
+            self.pos += 1;
+            match c {
+                '}' if in_group => return,
+                '{' => self.run(level, true, out),
+                '^' | '_' => {
+                    let script = if c == '^' { Level::Sup } else { Level::Sub };
+                    // Nested scripts stay at the first script level.
+                    let target = if level == Level::Normal { script } else { level };
+                    let arg = self.argument();
+                    render_into(&arg, target, out);
+                }
+                '\\' => self.command(level, out),
+                '~' => push(out, level, " "),
+                '&' => push(out, level, "  "),
+                c if c.is_whitespace() => {
+                    if !out.last().is_some_and(|(_, p)| p.ends_with(' ')) {
+                        push(out, level, " ");
+                    }
+                }
+                c => push(out, level, c.encode_utf8(&mut [0; 4])),
+            }
+        }
+    }
+
+    fn skip_spaces(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    fn command_name(&mut self) -> String {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_alphabetic()) {
+            self.pos += 1;
+        }
+        if self.pos == start {
+            if let Some(c) = self.peek() {
+                self.pos += 1;
+                return c.to_string();
+            }
+        }
+        self.chars[start..self.pos].iter().collect()
+    }
+
+    /// The next argument as source text: a braced group, a command or a single character.
+    fn argument(&mut self) -> String {
+        self.skip_spaces();
+        match self.peek() {
+            Some('{') => {
+                self.pos += 1;
+                let start = self.pos;
+                let mut depth = 1;
+                while let Some(c) = self.peek() {
+                    self.pos += 1;
+                    match c {
+                        '{' => depth += 1,
+                        '}' => {
+                            depth -= 1;
+                            if depth == 0 {
+                                return self.chars[start..self.pos - 1].iter().collect();
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                self.chars[start..].iter().collect()
+            }
+            Some('\\') => {
+                self.pos += 1;
+                format!("\\{}", self.command_name())
+            }
+            Some(c) => {
+                self.pos += 1;
+                c.to_string()
+            }
+            None => String::new(),
+        }
+    }
+
+    fn optional_argument(&mut self) -> Option<String> {
+        self.skip_spaces();
+        if self.peek() != Some('[') {
+            return None;
+        }
+        let end = self.chars[self.pos..].iter().position(|&c| c == ']')?;
+        let arg = self.chars[self.pos + 1..self.pos + end].iter().collect();
+        self.pos += end + 1;
+        Some(arg)
+    }
+
+    fn command(&mut self, level: Level, out: &mut Vec<(Level, String)>) {
+        let name = self.command_name();
+        if let Some(symbol) = symbol(&name) {
+            push(out, level, symbol);
+            return;
+        }
+        match name.as_str() {
+            "frac" | "dfrac" | "tfrac" | "cfrac" => {
+                let numerator = self.argument();
+                let denominator = self.argument();
+                render_operand(&numerator, level, out);
+                push(out, level, "/");
+                render_operand(&denominator, level, out);
+            }
+            "binom" => {
+                let n = self.argument();
+                let k = self.argument();
+                push(out, level, "C(");
+                render_into(&n, level, out);
+                push(out, level, ", ");
+                render_into(&k, level, out);
+                push(out, level, ")");
+            }
+            "sqrt" => {
+                if let Some(index) = self.optional_argument() {
+                    let index_level = if level == Level::Normal { Level::Sup } else { level };
+                    render_into(&index, index_level, out);
+                }
+                let radicand = self.argument();
+                push(out, level, "√");
+                render_operand(&radicand, level, out);
+            }
+            "text" | "textrm" | "textit" | "textbf" | "mbox" => {
+                let text = self.argument();
+                push(out, level, &text);
+            }
+            "mathrm" | "mathit" | "mathbf" | "mathsf" | "mathtt" | "mathcal" | "boldsymbol" | "operatorname" => {
+                let arg = self.argument();
+                render_into(&arg, level, out);
+            }
+            "mathbb" => {
+                let arg = self.argument();
+                let text: String = arg.chars().map(double_struck).collect();
+                push(out, level, &text);
+            }
+            "hat" | "widehat" | "bar" | "overline" | "vec" | "dot" | "ddot" | "tilde" | "widetilde" => {
+                let arg = self.argument();
+                render_into(&arg, level, out);
+                let mark = match name.as_str() {
+                    "hat" | "widehat" => "\u{302}",
+                    "bar" | "overline" => "\u{305}",
+                    "vec" => "\u{20d7}",
+                    "dot" => "\u{307}",
+                    "ddot" => "\u{308}",
+                    _ => "\u{303}",
+                };
+                push(out, level, mark);
+            }
+            "left" | "right" | "big" | "Big" | "bigg" | "Bigg" | "bigl" | "bigr" | "Bigl" | "Bigr" => {
+                self.skip_spaces();
+                // `\left.` is an invisible delimiter.
+                if self.peek() == Some('.') {
+                    self.pos += 1;
+                }
+            }
+            "begin" | "end" => {
+                self.argument();
+            }
+            "displaystyle" | "textstyle" | "limits" | "nolimits" | "!" => {}
+            "," | ":" | ";" | " " => push(out, level, " "),
+            "quad" => push(out, level, "  "),
+            "qquad" => push(out, level, "    "),
+            "\\" => push(out, level, "\n"),
+            "{" | "}" | "$" | "%" | "&" | "#" | "_" | "|" => push(out, level, &name),
+            _ => push(out, level, &format!("\\{name}")),
+        }
+    }
+}
+
+fn render_into(tex: &str, level: Level, out: &mut Vec<(Level, String)>) {
+    let mut parser = Parser { chars: tex.chars().collect(), pos: 0 };
+    parser.run(level, false, out);
+}
+
+/// Render a fraction or root operand, parenthesized unless it is a single symbol or number.
+fn render_operand(tex: &str, level: Level, out: &mut Vec<(Level, String)>) {
+    let mut inner = Vec::new();
+    render_into(tex, level, &mut inner);
+    let text: String = inner.iter().map(|(_, p)| p.as_str()).collect();
+    let simple = inner.len() <= 1
+        && (text.chars().count() == 1 || text.chars().all(|c| c.is_ascii_digit() || c == '.'));
+    if !simple {
+        push(out, level, "(");
+    }
+    for (piece_level, piece) in inner {
+        push(out, piece_level, &piece);
+    }
+    if !simple {
+        push(out, level, ")");
+    }
+}
+
+fn double_struck(c: char) -> char {
+    match c {
+        'N' => 'ℕ',
+        'Z' => 'ℤ',
+        'Q' => 'ℚ',
+        'R' => 'ℝ',
+        'C' => 'ℂ',
+        'P' => 'ℙ',
+        'H' => 'ℍ',
+        c => c,
+    }
+}
+
+fn symbol(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "alpha" => "α",
+        "beta" => "β",
+        "gamma" => "γ",
+        "delta" => "δ",
+        "epsilon" | "varepsilon" => "ε",
+        "zeta" => "ζ",
+        "eta" => "η",
+        "theta" => "θ",
+        "vartheta" => "ϑ",
+        "iota" => "ι",
+        "kappa" => "κ",
+        "lambda" => "λ",
+        "mu" => "μ",
+        "nu" => "ν",
+        "xi" => "ξ",
+        "pi" => "π",
+        "varpi" => "ϖ",
+        "rho" | "varrho" => "ρ",
+        "sigma" => "σ",
+        "varsigma" => "ς",
+        "tau" => "τ",
+        "upsilon" => "υ",
+        "phi" => "ϕ",
+        "varphi" => "φ",
+        "chi" => "χ",
+        "psi" => "ψ",
+        "omega" => "ω",
+        "Gamma" => "Γ",
+        "Delta" => "Δ",
+        "Theta" => "Θ",
+        "Lambda" => "Λ",
+        "Xi" => "Ξ",
+        "Pi" => "Π",
+        "Sigma" => "Σ",
+        "Upsilon" => "Υ",
+        "Phi" => "Φ",
+        "Psi" => "Ψ",
+        "Omega" => "Ω",
+        "times" => "×",
+        "cdot" | "cdotp" => "·",
+        "div" => "÷",
+        "pm" => "±",
+        "mp" => "∓",
+        "ast" => "∗",
+        "star" => "⋆",
+        "circ" => "∘",
+        "bullet" => "•",
+        "oplus" => "⊕",
+        "otimes" => "⊗",
+        "setminus" => "∖",
+        "leq" | "le" => "≤",
+        "geq" | "ge" => "≥",
+        "neq" | "ne" => "≠",
+        "approx" => "≈",
+        "equiv" => "≡",
+        "sim" => "∼",
+        "simeq" => "≃",
+        "cong" => "≅",
+        "propto" => "∝",
+        "ll" => "≪",
+        "gg" => "≫",
+        "in" => "∈",
+        "notin" => "∉",
+        "ni" => "∋",
+        "subset" => "⊂",
+        "subseteq" => "⊆",
+        "supset" => "⊃",
+        "supseteq" => "⊇",
+        "cup" => "∪",
+        "cap" => "∩",
+        "emptyset" | "varnothing" => "∅",
+        "forall" => "∀",
+        "exists" => "∃",
+        "nexists" => "∄",
+        "neg" | "lnot" => "¬",
+        "land" | "wedge" => "∧",
+        "lor" | "vee" => "∨",
+        "to" | "rightarrow" => "→",
+        "leftarrow" | "gets" => "←",
+        "leftrightarrow" => "↔",
+        "Rightarrow" | "implies" => "⇒",
+        "Leftarrow" => "⇐",
+        "Leftrightarrow" | "iff" => "⇔",
+        "mapsto" => "↦",
+        "uparrow" => "↑",
+        "downarrow" => "↓",
+        "infty" => "∞",
+        "partial" => "∂",
+        "nabla" => "∇",
+        "sum" => "∑",
+        "prod" => "∏",
+        "coprod" => "∐",
+        "int" => "∫",
+        "iint" => "∬",
+        "iiint" => "∭",
+        "oint" => "∮",
+        "ldots" | "dots" | "dotsc" => "…",
+        "cdots" | "dotsb" => "⋯",
+        "vdots" => "⋮",
+        "ddots" => "⋱",
+        "prime" => "′",
+        "angle" => "∠",
+        "perp" => "⊥",
+        "parallel" => "∥",
+        "mid" => "∣",
+        "langle" => "⟨",
+        "rangle" => "⟩",
+        "lceil" => "⌈",
+        "rceil" => "⌉",
+        "lfloor" => "⌊",
+        "rfloor" => "⌋",
+        "lbrace" => "{",
+        "rbrace" => "}",
+        "vert" => "|",
+        "Vert" => "‖",
+        "hbar" => "ℏ",
+        "ell" => "ℓ",
+        "Re" => "ℜ",
+        "Im" => "ℑ",
+        "aleph" => "ℵ",
+        "degree" => "°",
+        "therefore" => "∴",
+        "because" => "∵",
+        "colon" => ":",
+        "sin" => "sin",
+        "cos" => "cos",
+        "tan" => "tan",
+        "cot" => "cot",
+        "sec" => "sec",
+        "csc" => "csc",
+        "arcsin" => "arcsin",
+        "arccos" => "arccos",
+        "arctan" => "arctan",
+        "sinh" => "sinh",
+        "cosh" => "cosh",
+        "tanh" => "tanh",
+        "log" => "log",
+        "ln" => "ln",
+        "exp" => "exp",
+        "lim" => "lim",
+        "max" => "max",
+        "min" => "min",
+        "sup" => "sup",
+        "inf" => "inf",
+        "det" => "det",
+        "arg" => "arg",
+        "deg" => "deg",
+        "gcd" => "gcd",
+        "dim" => "dim",
+        "ker" => "ker",
+        "Pr" => "Pr",
+        "mod" | "bmod" => " mod ",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_finds_math_but_not_prices_or_code() {
+        let segments = split("Area is $\\pi r^2$ and\n$$E = mc^2$$\nCost: $5 and $10, `$x$`");
+        assert_eq!(
+            segments,
+            vec![
+                Segment::Text("Area is "),
+                Segment::Math { tex: "\\pi r^2", display: false },
+                Segment::Text(" and\n"),
+                Segment::Math { tex: "E = mc^2", display: true },
+                Segment::Text("\nCost: $5 and $10, `$x$`"),
+            ]
+        );
+        assert!(contains_math("\\(a+b\\)"));
+        assert!(!contains_math("```\n$x$\n```"));
+        assert!(!contains_math("It costs $3 or $4."));
+    }
+
+    #[test]
+    fn test_tex_to_unicode() {
+        assert_eq!(to_plain("\\alpha \\leq \\beta"), "α ≤ β");
+        assert_eq!(to_plain("\\frac{a+b}{2}"), "(a+b)/2");
+        assert_eq!(to_plain("\\frac12"), "1/2");
+        assert_eq!(to_plain("\\sqrt{x}"), "√x");
+        assert_eq!(to_plain("x^{2n} + y_i"), "x^(2n) + y_i");
+        assert_eq!(to_plain("\\sum_{i=1}^n i"), "∑_(i=1)^n i");
+        assert_eq!(to_plain("x \\in \\mathbb{R}"), "x ∈ ℝ");
+        assert_eq!(to_plain("\\left( \\text{mean} \\right)"), "( mean )");
+        assert_eq!(to_plain("\\unknown{x}"), "\\unknownx");
+    }
+}
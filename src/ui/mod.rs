@@ -1,8 +1,29 @@
+pub mod a11y;
 pub mod app;
 #[cfg(feature = "demo_ui")]
 pub mod chat;
 pub mod settings;
+pub mod slash_commands;
+pub mod chat_controller;
 pub mod components;
+pub mod events;
+pub mod export;
+pub mod fonts;
+pub mod keybindings;
+pub mod markdown;
+pub mod math;
 pub mod models;
+pub mod notification_center;
+pub mod quick_ask;
+pub mod repaint;
+pub mod runtime_manager;
+pub mod snippets;
+pub mod stats;
+pub mod streaming;
+pub mod tags;
+pub mod theme;
+pub mod translate;
+#[cfg(feature = "tray")]
+pub mod tray;
 
 pub use app::RiaApp;
\ No newline at end of file
@@ -1,8 +1,18 @@
-use crate::ai::models::{ModelInfo, ModelManager, ModelType, QuantizationType};
+use crate::ai::models::{DownloadRetry, ModelInfo, ModelManager, ModelType, PartialDownload, QuantizationType, SystemScanEvent, ATTEMPTS_PER_URL};
 use crate::ai::ExecutionProvider;
+use crate::ai::quantize::QuantizeProgress;
+use crate::ai::integrity::IntegrityStatus;
+use crate::ai::watcher::{ModelDirEvent, ModelDirWatcher};
+use crate::ai::catalog::{self, SignedCatalog};
+use crate::ai::model_card::{self, ModelCard};
+use crate::ai::model_storage::{self, StorageItem, StorageKind};
+use crate::config::{AppConfig, CatalogSettings, NetworkSettings};
 use crate::ui::components::{DownloadProgressCard, DownloadInfo, DownloadStatus, SystemLoadingIndicator};
+use crate::ui::markdown;
+use crate::ui::theme::Palette;
 use eframe::egui;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::sync::{RwLock, mpsc};
 use serde::{Deserialize, Serialize};
@@ -16,6 +26,10 @@ pub struct ModelManagerUI {
     download_url: String,
     download_name: String,
     downloading: HashMap<String, DownloadProgressCard>, // model_name -> download info
+    download_tasks: HashMap<String, tokio::task::JoinHandle<()>>, // aborted on shutdown
+    // Directory downloads go to, and the `.part` files in it no running download owns
+    downloads_dir: PathBuf,
+    interrupted: Vec<PartialDownload>,
     progress_rx: mpsc::UnboundedReceiver<ProgressUpdate>, // Progress updates from download tasks
     progress_tx: mpsc::UnboundedSender<ProgressUpdate>, // Send progress updates
     scanning: bool,
@@ -23,15 +37,77 @@ pub struct ModelManagerUI {
     success_message: Option<String>,
     show_remote_models: bool,
     remote_models: Vec<RemoteModelInfo>,
+    network: NetworkSettings,
+    // Remote catalog source, last refresh attempt, the fetch in flight and a status line
+    catalog: CatalogSettings,
+    catalog_edited: Option<Instant>,
+    catalog_fetched: Option<Instant>,
+    catalog_rx: Option<tokio::sync::oneshot::Receiver<anyhow::Result<SignedCatalog>>>,
+    catalog_status: Option<(bool, String)>, // (is_error, message)
+    // Model cards by model name (None while fetching) and the one open in the details pane
+    model_cards: HashMap<String, Option<Result<LoadedCard, String>>>,
+    card_tx: mpsc::UnboundedSender<(String, Result<ModelCard, String>)>,
+    card_rx: mpsc::UnboundedReceiver<(String, Result<ModelCard, String>)>,
+    shown_card: Option<String>,
+    // Entry waiting for its license to be accepted, whether the box is ticked, and the
+    // licenses accepted so far (see `license_key`)
+    pending_license: Option<RemoteModelInfo>,
+    license_ticked: bool,
+    accepted_licenses: HashSet<String>,
     current_tab: ModelTab,
     system_models: Vec<ModelInfo>,
     system_models_loaded: bool,
     system_loading: Option<SystemLoadingIndicator>,
+    system_scan_rx: Option<mpsc::UnboundedReceiver<SystemScanEvent>>,
     tab_loading_states: HashMap<ModelTab, bool>,
     show_help: bool, // Show help overlay
     last_model_update: Option<Instant>, // Track when we last updated models
     // Recently completed downloads to be consumed by app (FIFO)
     completed_downloads: Vec<String>,
+    /// Errors of downloads that failed since the app last took them
+    failed_downloads: Vec<String>,
+    // Running quantization job: (model name, stage, fraction) + progress channel
+    quantize_job: Option<(String, String, f32)>,
+    quantize_rx: Option<mpsc::UnboundedReceiver<QuantizeProgress>>,
+    // Quantization task and the file it writes, removed if the task is aborted
+    quantize_task: Option<(tokio::task::JoinHandle<()>, PathBuf)>,
+    // Integrity verification results per model name (None while a check is running)
+    integrity: HashMap<String, Option<Result<IntegrityStatus, String>>>,
+    integrity_tx: mpsc::UnboundedSender<(String, Result<IntegrityStatus, String>)>,
+    integrity_rx: mpsc::UnboundedReceiver<(String, Result<IntegrityStatus, String>)>,
+    // Repairs of damaged models: progress fraction per model name, fed by `repair_rx`
+    repairing: HashMap<String, f32>,
+    repair_tx: mpsc::UnboundedSender<(String, RepairUpdate)>,
+    repair_rx: mpsc::UnboundedReceiver<(String, RepairUpdate)>,
+    // Storage tab: what was found on the last scan, ticked rows (by first path) and sort order
+    storage_items: Vec<StorageItem>,
+    storage_selected: HashSet<PathBuf>,
+    storage_sort_by_size: bool,
+    // Filesystem watcher over the model directories; None falls back to periodic polling
+    dir_watcher: Option<ModelDirWatcher>,
+    dir_events: Option<mpsc::UnboundedReceiver<ModelDirEvent>>,
+    // Model list snapshots from the shared manager; the only way `available_models` changes
+    list_tx: mpsc::UnboundedSender<ModelListMsg>,
+    list_rx: mpsc::UnboundedReceiver<ModelListMsg>,
+}
+
+#[derive(Debug)]
+enum RepairUpdate {
+    Progress(f32),
+    Done(Result<(), String>),
+}
+
+/// A model card with its Markdown parsed for display.
+struct LoadedCard {
+    card: ModelCard,
+    blocks: Vec<markdown::Block>,
+}
+
+/// Sent from background scans/reads of the shared `ModelManager` to the UI.
+#[derive(Debug)]
+enum ModelListMsg {
+    Snapshot { models: Vec<ModelInfo>, from_scan: bool },
+    ScanFailed(String),
 }
 
 #[derive(Debug, Clone)]
@@ -48,9 +124,10 @@ enum ModelTab {
     Local,
     System,
     Remote,
+    Storage,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct RemoteModelInfo {
     pub name: String,
     pub description: String,
@@ -63,22 +140,93 @@ pub struct RemoteModelInfo {
     pub sha256: Option<String>,
     #[serde(default)]
     pub tokenizer_url: Option<String>,
+    /// Alternative URLs for the same file, tried in order when `url` keeps failing.
+    #[serde(default)]
+    pub mirrors: Vec<String>,
+    #[serde(default)]
+    pub tokenizer_sha256: Option<String>,
+    /// Other files the model needs beside it, e.g. external weights or configs.
+    #[serde(default)]
+    pub files: Vec<ModelFile>,
+    /// README with license and usage notes; defaults to the card of the Hugging Face repo `url` is in.
+    #[serde(default)]
+    pub card_url: Option<String>,
+    /// Terms that must be accepted before downloading.
+    #[serde(default)]
+    pub license: Option<ModelLicense>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ModelLicense {
+    /// e.g. "Llama 3.2 Community License"
+    pub name: String,
+    #[serde(default)]
+    pub url: Option<String>,
+    /// The Hugging Face repo only serves files to accounts that accepted the terms on its
+    /// page, so the download needs an access token.
+    #[serde(default)]
+    pub gated: bool,
+}
+
+const ACCEPTED_LICENSES_FILE: &str = "accepted_licenses.json";
+
+/// Acceptance is remembered per model and license, so changed terms are asked about again.
+fn license_key(model: &str, license: &ModelLicense) -> String {
+    format!("{model}\u{1f}{}", license.name)
+}
+
+fn load_accepted_licenses() -> HashSet<String> {
+    std::fs::read_to_string(AppConfig::catalog_dir().join(ACCEPTED_LICENSES_FILE))
+        .ok()
+        .and_then(|data| serde_json::from_str(&data).ok())
+        .unwrap_or_default()
+}
+
+/// A file downloaded next to a catalog model. Shared files (same digest or URL) are fetched
+/// once and linked from the download cache.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ModelFile {
+    pub url: String,
+    /// Name in the models directory, e.g. "phi3-mini.onnx.data".
+    pub file_name: String,
+    #[serde(default)]
+    pub sha256: Option<String>,
+}
+
+impl RemoteModelInfo {
+    /// `url` followed by the mirrors, without duplicates.
+    pub fn download_urls(&self) -> Vec<String> {
+        let mut urls = vec![self.url.clone()];
+        for mirror in &self.mirrors {
+            if !urls.contains(mirror) {
+                urls.push(mirror.clone());
+            }
+        }
+        urls
+    }
+
+    pub fn card_url(&self) -> Option<String> {
+        self.card_url.clone().or_else(|| model_card::huggingface_card_url(&self.url))
+    }
 }
 
 impl ModelManagerUI {
-    pub fn new() -> Self {
-        let models_dir = std::env::current_dir()
-            .unwrap_or_default()
-            .join("models");
-        
-        let manager = Arc::new(RwLock::new(
-            ModelManager::new(&models_dir).unwrap_or_else(|_| {
-                ModelManager::new(".").expect("Failed to create model manager")
-            })
-        ));
+    pub fn new(model_directories: Vec<PathBuf>, network: NetworkSettings) -> Self {
+        let watched_dirs = model_directories.clone();
+        let mut manager = ModelManager::with_directories(model_directories).unwrap_or_else(|e| {
+            tracing::warn!("Falling back to ./models: {}", e);
+            ModelManager::new("./models").expect("Failed to create model manager")
+        });
+        manager.set_network(network.clone());
+        let downloads_dir = manager.get_models_directory().to_path_buf();
+        let manager = Arc::new(RwLock::new(manager));
 
         // Create progress update channel
         let (progress_tx, progress_rx) = mpsc::unbounded_channel();
+        let (integrity_tx, integrity_rx) = mpsc::unbounded_channel();
+        let (list_tx, list_rx) = mpsc::unbounded_channel();
+        let (card_tx, card_rx) = mpsc::unbounded_channel();
+        let (repair_tx, repair_rx) = mpsc::unbounded_channel();
 
         let mut ui = Self {
             manager,
@@ -87,6 +235,9 @@ impl ModelManagerUI {
             download_url: String::new(),
             download_name: String::new(),
             downloading: HashMap::new(),
+            download_tasks: HashMap::new(),
+            downloads_dir,
+            interrupted: Vec::new(),
             progress_rx,
             progress_tx,
             scanning: false,
@@ -94,17 +245,53 @@ impl ModelManagerUI {
             success_message: None,
             show_remote_models: false,
             remote_models: Vec::new(),
+            network,
+            catalog: CatalogSettings::default(),
+            catalog_edited: None,
+            catalog_fetched: None,
+            catalog_rx: None,
+            catalog_status: None,
+            model_cards: HashMap::new(),
+            card_tx,
+            card_rx,
+            shown_card: None,
+            pending_license: None,
+            license_ticked: false,
+            accepted_licenses: load_accepted_licenses(),
             current_tab: ModelTab::Local,
             system_models: Vec::new(),
             system_models_loaded: false,
             system_loading: None,
+            system_scan_rx: None,
             tab_loading_states: HashMap::new(),
             show_help: false,
             last_model_update: None,
             completed_downloads: Vec::new(),
+            failed_downloads: Vec::new(),
+            quantize_job: None,
+            quantize_rx: None,
+            quantize_task: None,
+            integrity: HashMap::new(),
+            integrity_tx,
+            integrity_rx,
+            repairing: HashMap::new(),
+            repair_tx,
+            repair_rx,
+            storage_items: Vec::new(),
+            storage_selected: HashSet::new(),
+            storage_sort_by_size: true,
+            dir_watcher: None,
+            dir_events: None,
+            list_tx,
+            list_rx,
         };
 
+        ui.start_dir_watcher(&watched_dirs);
         ui.load_remote_models();
+        ui.scan_interrupted();
+        if !ui.interrupted.is_empty() {
+            tracing::info!("Found {} interrupted download(s)", ui.interrupted.len());
+        }
         ui
     }
     
@@ -133,74 +320,165 @@ impl ModelManagerUI {
                 // Clear loading state immediately (remote models are pre-loaded)
                 self.tab_loading_states.insert(ModelTab::Remote, false);
             },
+            ModelTab::Storage => {
+                self.show_remote_models = false;
+                self.scan_storage();
+                self.tab_loading_states.insert(ModelTab::Storage, false);
+            },
         }
     }
 
     pub fn refresh_models(&mut self) {
-        self.scanning = true;
         self.system_models_loaded = false; // Force re-scan of system models
+
+        // Rescan local models in background; the result arrives via handle_model_list_updates
+        self.spawn_rescan(None);
         
+        // Re-detect system models in background
+        self.start_system_model_loading();
+    }
+
+    /// Rescan the shared manager (optionally switching directories first) on the blocking
+    /// pool and post the resulting list back to the UI.
+    fn spawn_rescan(&mut self, new_dirs: Option<Vec<PathBuf>>) {
+        self.scanning = true;
         let manager = self.manager.clone();
-        
-        // Spawn background task to scan both local and system models
-        tokio::spawn(async move {
-            let mut guard = manager.write().await;
+        let tx = self.list_tx.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut guard = manager.blocking_write();
             tracing::info!("Starting background model scan...");
-            match guard.scan_models() {
+            let result = match new_dirs {
+                Some(dirs) => guard.set_directories(dirs),
+                None => guard.scan_models(),
+            };
+            let msg = match result {
                 Ok(()) => {
-                    let model_count = guard.get_available_models().len();
-                    tracing::info!("Background model scan completed - found {} models", model_count);
-                },
+                    tracing::info!("Background model scan completed - found {} models", guard.get_available_models().len());
+                    ModelListMsg::Snapshot { models: guard.get_available_models().to_vec(), from_scan: true }
+                }
                 Err(e) => {
                     tracing::error!("Failed to scan local models: {}", e);
+                    ModelListMsg::ScanFailed(e.to_string())
                 }
-            }
+            };
+            let _ = tx.send(msg);
         });
-        
-        // Load system models in background
-        self.load_system_models();
-        
-        // Update local models immediately (sync scan)
-        self.update_available_models();
-        
-        // Reset scanning state
-        self.scanning = false;
+    }
+
+    fn handle_model_list_updates(&mut self) {
+        while let Ok(msg) = self.list_rx.try_recv() {
+            match msg {
+                ModelListMsg::Snapshot { models, from_scan } => {
+                    if from_scan { self.scanning = false; }
+                    self.apply_model_snapshot(models);
+                }
+                ModelListMsg::ScanFailed(e) => {
+                    self.scanning = false;
+                    self.error_message = Some(format!("Failed to scan models: {}", e));
+                }
+            }
+        }
+    }
+
+    fn apply_model_snapshot(&mut self, models: Vec<ModelInfo>) {
+        let models_before = self.available_models.len();
+        self.available_models = models;
+        self.last_model_update = Some(Instant::now());
+        self.scan_interrupted();
+
+        let models_after = self.available_models.len();
+        if models_after != models_before {
+            tracing::info!("Model list updated: {} -> {} models", models_before, models_after);
+            // Show models found in debug
+            for model in &self.available_models {
+                tracing::debug!("Found model: {} ({})", model.name, model.path.display());
+            }
+        }
     }
     
-    fn update_available_models(&mut self) {
-        // Get the current available models from the manager synchronously
-        if let Ok(guard) = self.manager.try_read() {
-            let models_before = self.available_models.len();
-            self.available_models = guard.get_available_models().to_vec();
-            self.last_model_update = Some(Instant::now());
-            
-            let models_after = self.available_models.len();
-            if models_after != models_before {
-                tracing::info!("Model list updated: {} -> {} models", models_before, models_after);
-                // Show models found in debug
-                for model in &self.available_models {
-                    tracing::debug!("Found model: {} ({})", model.name, model.path.display());
+    fn start_dir_watcher(&mut self, dirs: &[PathBuf]) {
+        self.dir_watcher = None;
+        self.dir_events = None;
+        match ModelDirWatcher::watch(dirs) {
+            Ok((watcher, events)) => {
+                self.dir_watcher = Some(watcher);
+                self.dir_events = Some(events);
+            }
+            Err(e) => tracing::warn!("Model directory watcher unavailable, falling back to polling: {}", e),
+        }
+    }
+
+    /// Apply queued watcher events to the manager and refresh the list once.
+    fn handle_dir_events(&mut self) {
+        let Some(rx) = self.dir_events.as_mut() else { return };
+        let mut events = Vec::new();
+        while let Ok(evt) = rx.try_recv() { events.push(evt); }
+        if events.is_empty() { return; }
+
+        let Ok(mut guard) = self.manager.try_write() else {
+            // Manager busy (e.g. a download rescanning); rescan once it's free
+            self.spawn_rescan(None);
+            return;
+        };
+        if events.contains(&ModelDirEvent::Rescan) {
+            if let Err(e) = guard.scan_models() { tracing::error!("Failed to rescan models: {}", e); }
+        } else {
+            for evt in events {
+                match evt {
+                    ModelDirEvent::Added(path) => {
+                        if let Err(e) = guard.upsert_model_file(&path) { tracing::debug!("Skipping {}: {}", path.display(), e); }
+                    }
+                    ModelDirEvent::Removed(path) => { guard.remove_model_file(&path); }
+                    ModelDirEvent::Rescan => {}
                 }
             }
+        }
+        drop(guard);
+        self.update_available_models();
+    }
+
+    /// Point the manager at a new set of model directories (from settings) and rescan.
+    pub fn set_model_directories(&mut self, dirs: Vec<PathBuf>) {
+        self.start_dir_watcher(&dirs);
+        if let Some(first) = dirs.first() {
+            self.downloads_dir = first.clone();
+            self.scan_interrupted();
+        }
+        self.spawn_rescan(Some(dirs));
+    }
+
+    /// Apply changed proxy / certificate / timeout settings to later downloads.
+    pub fn set_network(&mut self, network: NetworkSettings) {
+        self.network = network.clone();
+        let manager = self.manager.clone();
+        // Waits for a running download, which holds the manager until it finishes
+        tokio::spawn(async move { manager.write().await.set_network(network) });
+    }
+
+    /// Copy the manager's current list without rescanning. Uses the lock directly when free,
+    /// otherwise reads in the background and posts the snapshot.
+    fn update_available_models(&mut self) {
+        let snapshot = self.manager.try_read().map(|guard| guard.get_available_models().to_vec());
+        if let Ok(models) = snapshot {
+            self.apply_model_snapshot(models);
         } else {
-            tracing::debug!("Could not acquire read lock for model update, scheduling retry");
-            // If we can't get a read lock, try again after a short delay
+            tracing::debug!("Model manager busy, reading snapshot in background");
+            self.last_model_update = Some(Instant::now()); // don't queue a read every frame
             let manager = self.manager.clone();
+            let tx = self.list_tx.clone();
             tokio::spawn(async move {
-                tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
-                let guard = manager.read().await;
-                let models = guard.get_available_models().to_vec();
-                tracing::info!("Delayed scan found {} local models", models.len());
-                // Note: We can't update the UI from here due to async context
-                // The UI will need to poll for updates or we need a different approach
+                let models = manager.read().await.get_available_models().to_vec();
+                let _ = tx.send(ModelListMsg::Snapshot { models, from_scan: false });
             });
         }
     }
     
     fn update_available_models_if_needed(&mut self) {
-        // Update models every 2 seconds or if never updated
+        // With a watcher, changes arrive as events; only poll when it couldn't start
+        // (or a busy manager deferred an update)
         let should_update = match self.last_model_update {
             None => true,
+            Some(_) if self.dir_watcher.is_some() => false,
             Some(last_update) => last_update.elapsed() > Duration::from_secs(2),
         };
         
@@ -209,23 +487,6 @@ impl ModelManagerUI {
         }
     }
     
-    fn load_system_models(&mut self) {
-        if self.system_models_loaded {
-            return; // Already loaded
-        }
-        
-        let manager = self.manager.clone();
-        
-        // Spawn background task to detect system models
-        tokio::spawn(async move {
-            let guard = manager.read().await;
-            let detected_models = guard.detect_system_models();
-            tracing::info!("System model detection completed: {} models found", detected_models.len());
-            // Note: In a real app, you'd need to communicate back to the UI thread
-            // For now, the detection happens but we can't update the UI from here
-        });
-    }
-
     fn handle_keyboard_shortcuts(&mut self, ui: &mut egui::Ui) {
         // Handle keyboard shortcuts for better UX
         ui.input(|i| {
@@ -234,7 +495,7 @@ impl ModelManagerUI {
                 self.refresh_models();
             }
             
-            // Tab navigation: Ctrl+1, Ctrl+2, Ctrl+3 for tabs
+            // Tab navigation: Ctrl+1 … Ctrl+4 for tabs
             if i.modifiers.ctrl && i.key_pressed(egui::Key::Num1) {
                 self.switch_to_tab(ModelTab::Local);
             }
@@ -244,6 +505,9 @@ impl ModelManagerUI {
             if i.modifiers.ctrl && i.key_pressed(egui::Key::Num3) {
                 self.switch_to_tab(ModelTab::Remote);
             }
+            if i.modifiers.ctrl && i.key_pressed(egui::Key::Num4) {
+                self.switch_to_tab(ModelTab::Storage);
+            }
             
             // Escape to clear messages
             if i.key_pressed(egui::Key::Escape) {
@@ -262,10 +526,28 @@ impl ModelManagerUI {
         });
     }
 
-    fn handle_progress_updates(&mut self) {
+    pub fn handle_progress_updates(&mut self) {
+        self.poll_catalog();
+        self.handle_quantize_progress();
+        self.handle_integrity_results();
+        self.handle_repair_updates();
+        while let Ok((name, result)) = self.card_rx.try_recv() {
+            let loaded = result.map(|card| LoadedCard { blocks: markdown::parse(&card.markdown, Some(&card.url)), card });
+            self.model_cards.insert(name, Some(loaded));
+        }
+
         // Process all pending progress updates
         while let Ok(update) = self.progress_rx.try_recv() {
             if let Some(download_card) = self.downloading.get_mut(&update.model_name) {
+                if matches!(update.status, DownloadStatus::Retrying { .. }) {
+                    // Keep the bytes so far; the retry resumes from them
+                    let mut info = download_card.info.clone();
+                    info.status = update.status;
+                    info.speed_bps = 0.0;
+                    info.eta_seconds = 0.0;
+                    download_card.update(info);
+                    continue;
+                }
                 let progress = if update.total_bytes > 0 { 
                     update.downloaded_bytes as f32 / update.total_bytes as f32 
                 } else { 
@@ -304,12 +586,132 @@ impl ModelManagerUI {
                 
                 if let DownloadStatus::Failed(error) = &update.status {
                     self.error_message = Some(format!("Failed to download {}: {}", update.model_name, error));
+                    self.failed_downloads.push(error.clone());
                     // Keep failed download visible for user to see
+                    self.scan_interrupted();
+                }
+            }
+        }
+    }
+
+    fn start_quantize(&mut self, model: &ModelInfo) {
+        if self.quantize_job.is_some() {
+            self.error_message = Some("A quantization job is already running".to_string());
+            return;
+        }
+        let output = crate::ai::quantize::quantized_output_path(&model.path);
+        let (tx, rx) = mpsc::unbounded_channel();
+        let task = tokio::spawn(crate::ai::quantize::quantize_dynamic_int8(model.path.clone(), output.clone(), tx));
+        self.quantize_task = Some((task, output));
+        self.quantize_job = Some((model.name.clone(), "Starting".to_string(), 0.0));
+        self.quantize_rx = Some(rx);
+    }
+
+    fn start_verify(&mut self, model: &ModelInfo) {
+        self.integrity.insert(model.name.clone(), None);
+        let tx = self.integrity_tx.clone();
+        let name = model.name.clone();
+        let path = model.path.clone();
+        tokio::task::spawn_blocking(move || {
+            // Full hash; first verification of an untracked model records its baseline
+            let result = crate::ai::integrity::verify_model(&path, true, true).map_err(|e| e.to_string());
+            let _ = tx.send((name, result));
+        });
+    }
+
+    /// Fetch a damaged model again from where it was downloaded (see `ModelManager::repair_model`).
+    fn start_repair(&mut self, model: &ModelInfo) {
+        self.repairing.insert(model.name.clone(), 0.0);
+        let manager = self.manager.clone();
+        let tx = self.repair_tx.clone();
+        let name = model.name.clone();
+        let path = model.path.clone();
+        let task = tokio::spawn(async move {
+            let progress = {
+                let tx = tx.clone();
+                let name = name.clone();
+                move |downloaded: u64, total: u64, _speed: f64| {
+                    if total > 0 {
+                        let _ = tx.send((name.clone(), RepairUpdate::Progress(downloaded as f32 / total as f32)));
+                    }
+                }
+            };
+            let result = manager.write().await.repair_model(&path, Some(progress)).await.map_err(|e| format!("{e:#}"));
+            let _ = tx.send((name, RepairUpdate::Done(result)));
+        });
+        self.download_tasks.insert(model.name.clone(), task);
+    }
+
+    fn handle_repair_updates(&mut self) {
+        while let Ok((name, update)) = self.repair_rx.try_recv() {
+            match update {
+                RepairUpdate::Progress(fraction) => {
+                    self.repairing.insert(name, fraction);
+                }
+                RepairUpdate::Done(result) => {
+                    self.repairing.remove(&name);
+                    match result {
+                        Ok(()) => {
+                            self.success_message = Some(format!("{name} repaired and verified"));
+                            self.integrity.insert(name, Some(Ok(IntegrityStatus::Verified)));
+                        }
+                        Err(e) => self.error_message = Some(format!("Failed to repair {name}: {e}")),
+                    }
+                    self.scan_interrupted();
                 }
             }
         }
     }
 
+    fn handle_integrity_results(&mut self) {
+        while let Ok((name, result)) = self.integrity_rx.try_recv() {
+            match &result {
+                Ok(IntegrityStatus::Verified) => self.success_message = Some(format!("{name}: checksum verified")),
+                Ok(IntegrityStatus::Recorded) => self.success_message = Some(format!("{name}: no manifest found, checksum recorded for future checks")),
+                Ok(IntegrityStatus::Corrupted(reason)) => self.error_message = Some(format!("{name} is corrupted: {reason}")),
+                Ok(IntegrityStatus::NoManifest) => {}
+                Err(e) => self.error_message = Some(format!("Failed to verify {name}: {e}")),
+            }
+            self.integrity.insert(name, Some(result));
+        }
+    }
+
+    fn handle_quantize_progress(&mut self) {
+        let Some(rx) = self.quantize_rx.as_mut() else { return };
+        let mut rescan = false;
+        while let Ok(evt) = rx.try_recv() {
+            match evt {
+                QuantizeProgress::Stage(stage, frac) => {
+                    if let Some(job) = self.quantize_job.as_mut() {
+                        job.1 = stage;
+                        job.2 = frac;
+                    }
+                }
+                QuantizeProgress::Completed { output, original_size, quantized_size } => {
+                    self.success_message = Some(format!(
+                        "Quantized model saved as {} ({} → {})",
+                        output.file_name().and_then(|n| n.to_str()).unwrap_or_default(),
+                        ModelManager::format_file_size(original_size),
+                        ModelManager::format_file_size(quantized_size),
+                    ));
+                    self.quantize_job = None;
+                    rescan = true; // so the new file shows up even without a watcher
+                }
+                QuantizeProgress::Failed(e) => {
+                    self.error_message = Some(e);
+                    self.quantize_job = None;
+                }
+            }
+        }
+        if self.quantize_job.is_none() {
+            self.quantize_rx = None;
+            self.quantize_task = None;
+        }
+        if rescan {
+            self.spawn_rescan(None);
+        }
+    }
+
     // Allow app layer to fetch and clear completed downloads (returns names in order)
     pub fn take_completed_downloads(&mut self) -> Vec<String> {
         let mut v = Vec::new();
@@ -317,6 +719,11 @@ impl ModelManagerUI {
         v
     }
 
+    /// Errors of downloads that failed since the last call.
+    pub fn take_failed_downloads(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.failed_downloads)
+    }
+
     pub fn render(&mut self, ui: &mut egui::Ui) {
         // Handle any pending download progress updates
         self.handle_progress_updates();
@@ -324,7 +731,10 @@ impl ModelManagerUI {
         // Handle keyboard shortcuts
         self.handle_keyboard_shortcuts(ui);
         
-        // Periodically update available models to catch newly downloaded files
+        // Pick up added/removed model files and streamed system scan results
+        self.handle_model_list_updates();
+        self.handle_dir_events();
+        self.handle_system_scan_events();
         self.update_available_models_if_needed();
         
         ui.heading("🧠 AI Model Management");
@@ -364,6 +774,10 @@ impl ModelManagerUI {
             if ui.selectable_label(self.current_tab == ModelTab::Remote, remote_label).clicked() {
                 self.switch_to_tab(ModelTab::Remote);
             }
+
+            if ui.selectable_label(self.current_tab == ModelTab::Storage, "💾 Storage").clicked() {
+                self.switch_to_tab(ModelTab::Storage);
+            }
             
             ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
                 // Help button
@@ -387,6 +801,7 @@ impl ModelManagerUI {
             ModelTab::Local => self.render_local_models(ui),
             ModelTab::System => self.render_system_models(ui),
             ModelTab::Remote => self.render_remote_models(ui),
+            ModelTab::Storage => self.render_storage(ui),
         }
 
         ui.add_space(20.0);
@@ -396,30 +811,41 @@ impl ModelManagerUI {
         if self.show_help {
             self.render_help_overlay(ui);
         }
+        self.render_license_dialog(ui.ctx());
     }
 
     fn render_local_models(&mut self, ui: &mut egui::Ui) {
-        // Model directory info
-        ui.horizontal(|ui| {
-            ui.label("Models Directory:");
-            ui.code("./models/");
-            if ui.button("📂 Open Folder")
-                .on_hover_text("Open the models directory in your file explorer")
-                .clicked() {
-                let models_dir = std::env::current_dir().unwrap_or_default().join("models");
-                if let Err(e) = std::fs::create_dir_all(&models_dir) {
-                    self.error_message = Some(format!("Failed to create models directory: {}", e));
-                } else {
-                    // Try to open the folder in file manager
-                    let _ = std::process::Command::new("explorer")
-                        .arg(models_dir)
-                        .spawn();
+        // Model directory info (managed in Settings → Model Directories)
+        let model_dirs = self.manager.try_read().map(|g| g.get_models_directories().to_vec()).unwrap_or_default();
+        for (i, dir) in model_dirs.iter().enumerate() {
+            ui.horizontal(|ui| {
+                ui.label(if i == 0 { "Models Directory:" } else { "Also scanning:" });
+                ui.code(dir.display().to_string());
+                if !dir.is_dir() {
+                    ui.colored_label(Palette::current(ui.ctx()).warning, "(not found)");
                 }
-            }
-        });
+                if ui.button("📂 Open Folder")
+                    .on_hover_text("Open this directory in your file explorer")
+                    .clicked() {
+                    if let Err(e) = std::fs::create_dir_all(dir) {
+                        self.error_message = Some(format!("Failed to create models directory: {}", e));
+                    } else {
+                        // Try to open the folder in file manager
+                        let _ = std::process::Command::new("explorer")
+                            .arg(dir)
+                            .spawn();
+                    }
+                }
+            });
+        }
 
         ui.add_space(10.0);
 
+        if !self.interrupted.is_empty() {
+            self.render_interrupted_downloads(ui);
+            ui.add_space(10.0);
+        }
+
         // Local models list
         egui::ScrollArea::vertical()
             .max_height(400.0)
@@ -430,7 +856,7 @@ impl ModelManagerUI {
                             ui.add_space(50.0);
                             ui.label("No ONNX models found");
                             ui.add_space(10.0);
-                            ui.label("Add .onnx files to the models/ directory");
+                            ui.label("Add .onnx files to one of the model directories above");
                             ui.add_space(10.0);
                             if ui.button("Download Popular Models")
                                 .on_hover_text("Browse and download pre-configured ONNX models")
@@ -475,9 +901,28 @@ impl ModelManagerUI {
     }
 
     fn render_remote_models(&mut self, ui: &mut egui::Ui) {
-        ui.label("Popular ONNX Models:");
+        ui.horizontal(|ui| {
+            ui.label("Popular ONNX Models:");
+            if self.catalog.is_remote() {
+                if self.catalog_rx.is_some() {
+                    ui.spinner();
+                } else if ui.small_button("⟳").on_hover_text("Refresh the signed catalog").clicked() {
+                    self.refresh_catalog();
+                }
+            }
+        });
+        if let Some((is_error, status)) = &self.catalog_status {
+            let palette = Palette::current(ui.ctx());
+            let color = if *is_error { palette.warning } else { ui.visuals().weak_text_color() };
+            ui.colored_label(color, egui::RichText::new(status).small());
+        }
         ui.add_space(10.0);
 
+        if self.shown_card.is_some() {
+            self.render_model_card_pane(ui);
+            ui.add_space(10.0);
+        }
+
         egui::ScrollArea::vertical()
             .max_height(500.0)
             .show(ui, |ui| {
@@ -488,20 +933,97 @@ impl ModelManagerUI {
             });
     }
 
+    /// Open the details pane for `model`, fetching its card the first time.
+    fn show_model_card(&mut self, model: &RemoteModelInfo) {
+        self.shown_card = Some(model.name.clone());
+        if matches!(self.model_cards.get(&model.name), Some(None | Some(Ok(_)))) {
+            return; // loading or loaded; failed cards are fetched again
+        }
+        let Some(url) = model.card_url() else { return };
+        let client = match self.network.http_client() {
+            Ok(client) => client,
+            Err(e) => {
+                self.model_cards.insert(model.name.clone(), Some(Err(format!("{e:#}"))));
+                return;
+            }
+        };
+        self.model_cards.insert(model.name.clone(), None);
+        let tx = self.card_tx.clone();
+        let name = model.name.clone();
+        tokio::spawn(async move {
+            let result = model_card::fetch(&client, &url).await.map_err(|e| format!("{e:#}"));
+            let _ = tx.send((name, result));
+        });
+    }
+
+    fn render_model_card_pane(&mut self, ui: &mut egui::Ui) {
+        let Some(name) = self.shown_card.clone() else { return };
+        let palette = Palette::current(ui.ctx());
+        egui::Frame::none()
+            .fill(palette.card_fill)
+            .stroke(egui::Stroke::new(1.0, palette.accent))
+            .rounding(12.0)
+            .inner_margin(14.0)
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(egui::RichText::new(format!("📄 {name}")).size(16.0).strong().color(palette.heading_text));
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        if ui.small_button("✖").on_hover_text("Close the model card").clicked() {
+                            self.shown_card = None;
+                        }
+                    });
+                });
+                match self.model_cards.get(&name) {
+                    None | Some(None) => {
+                        ui.horizontal(|ui| {
+                            ui.spinner();
+                            ui.label("Fetching model card…");
+                        });
+                    }
+                    Some(Some(Err(e))) => {
+                        ui.colored_label(palette.warning, format!("Couldn't load the model card: {e}"));
+                        if ui.button("🔄 Retry").clicked() {
+                            if let Some(model) = self.remote_models.iter().find(|m| m.name == name).cloned() {
+                                self.show_model_card(&model);
+                            }
+                        }
+                    }
+                    Some(Some(Ok(loaded))) => {
+                        ui.horizontal_wrapped(|ui| {
+                            let license = loaded.card.license.as_deref().unwrap_or("not stated");
+                            let color = if loaded.card.license.is_some() { palette.accent } else { palette.warning };
+                            ui.label(egui::RichText::new(format!("⚖ License: {license}")).strong().color(color));
+                            for (key, value) in loaded.card.metadata.iter().take(4) {
+                                ui.label(egui::RichText::new(format!("{key}: {value}")).small().color(palette.muted_text));
+                            }
+                            ui.hyperlink_to("Open in browser", &loaded.card.url);
+                        });
+                        ui.separator();
+                        egui::ScrollArea::vertical()
+                            .id_salt("model_card_pane")
+                            .max_height(320.0)
+                            .auto_shrink([false, true])
+                            .show(ui, |ui| markdown::show(ui, &loaded.blocks));
+                    }
+                }
+            });
+    }
+
     fn render_local_model_card(&mut self, ui: &mut egui::Ui, model: &ModelInfo) {
         let selected = self.selected_model.as_ref() == Some(&model.name);
+        let palette = Palette::current(ui.ctx());
         
         // Enhanced card with hover effects and professional styling
         let base_fill = if selected {
-            egui::Color32::from_rgb(45, 55, 75) // Selected highlight
+            palette.card_fill_selected
         } else {
-            egui::Color32::from_rgb(42, 46, 54) // Default
+            palette.card_fill
         };
         
         let stroke_color = if selected {
-            egui::Color32::from_rgb(100, 150, 255) // Blue border when selected
+            palette.accent // Accent border when selected
         } else {
-            egui::Color32::from_rgb(65, 70, 80) // Subtle border
+            palette.card_stroke
         };
         
         egui::Frame::none()
@@ -510,7 +1032,7 @@ impl ModelManagerUI {
             .rounding(12.0)
             .inner_margin(18.0)
             .shadow(egui::epaint::Shadow {
-                color: egui::Color32::from_black_alpha(80),
+                color: palette.shadow,
                 offset: egui::vec2(0.0, 4.0),
                 blur: 8.0,
                 spread: 0.0,
@@ -543,14 +1065,14 @@ impl ModelManagerUI {
                                 egui::RichText::new(&model.name)
                                     .size(18.0)
                                     .strong()
-                                    .color(egui::Color32::WHITE)
+                                    .color(palette.heading_text)
                             );
                             
                             if selected {
                                 ui.label(
                                     egui::RichText::new("● SELECTED")
                                         .size(10.0)
-                                        .color(egui::Color32::from_rgb(76, 175, 80))
+                                        .color(palette.success)
                                         .strong()
                                 );
                             }
@@ -566,17 +1088,26 @@ impl ModelManagerUI {
                             };
                             
                             egui::Frame::none()
-                                .fill(egui::Color32::from_rgb(55, 60, 70))
+                                .fill(palette.chip_fill)
                                 .rounding(4.0)
                                 .inner_margin(egui::Margin::symmetric(8.0, 4.0))
                                 .show(ui, |ui| {
                                     ui.label(
                                         egui::RichText::new(type_text)
                                             .size(11.0)
-                                            .color(egui::Color32::from_rgb(200, 200, 200))
+                                            .color(palette.secondary_text)
                                     );
                                 });
                         });
+
+                        // Source directory (models may come from several drives)
+                        if let Some(dir) = &model.source_dir {
+                            ui.label(
+                                egui::RichText::new(format!("📁 {}", dir.display()))
+                                    .size(11.0)
+                                    .color(palette.muted_text)
+                            ).on_hover_text(model.path.display().to_string());
+                        }
                     });
                     
                     ui.with_layout(egui::Layout::right_to_left(egui::Align::TOP), |ui| {
@@ -624,6 +1155,28 @@ impl ModelManagerUI {
                             egui::Color32::from_rgb(255, 152, 0));
                         ui.add_space(8.0);
                     }
+
+                    // Integrity badge from the last verification
+                    match self.integrity.get(&model.name) {
+                        Some(None) => { ui.spinner(); ui.label("Verifying…"); }
+                        Some(Some(Ok(IntegrityStatus::Verified | IntegrityStatus::Recorded))) => {
+                            self.render_info_card(ui, "🛡", "Integrity", "Verified", egui::Color32::from_rgb(76, 175, 80));
+                        }
+                        Some(Some(Ok(IntegrityStatus::Corrupted(reason)))) => {
+                            let reason = reason.clone();
+                            self.render_info_card(ui, "⚠", "Integrity", "Corrupted", egui::Color32::from_rgb(244, 67, 54));
+                            if let Some(fraction) = self.repairing.get(&model.name) {
+                                ui.add(egui::ProgressBar::new(*fraction).desired_width(120.0).show_percentage());
+                            } else if crate::ai::integrity::read_manifest(&model.path).is_some_and(|m| m.source_url.is_some())
+                                && ui.button("🔧 Repair")
+                                    .on_hover_text(format!("{reason}. Download the damaged or missing part again from the original source."))
+                                    .clicked()
+                            {
+                                self.start_repair(model);
+                            }
+                        }
+                        _ => {}
+                    }
                 });
                 
                 ui.add_space(10.0);
@@ -633,7 +1186,7 @@ impl ModelManagerUI {
                     ui.label(
                         egui::RichText::new("Supported Execution Providers")
                             .size(12.0)
-                            .color(egui::Color32::from_rgb(180, 180, 180))
+                            .color(palette.muted_text)
                     );
                     
                     ui.add_space(6.0);
@@ -679,13 +1232,13 @@ impl ModelManagerUI {
                     ui.label(
                         egui::RichText::new("📂")
                             .size(12.0)
-                            .color(egui::Color32::from_rgb(150, 150, 150))
+                            .color(palette.muted_text)
                     );
                     
                     ui.label(
                         egui::RichText::new(format!("{}", model.path.display()))
                             .size(10.0)
-                            .color(egui::Color32::from_rgb(150, 150, 150))
+                            .color(palette.muted_text)
                             .monospace()
                     );
                     
@@ -696,12 +1249,33 @@ impl ModelManagerUI {
                             .rounding(6.0);
                         
                         if ui.add_sized([80.0, 28.0], delete_button)
-                            .on_hover_text("Permanently delete this model from your computer")
+                            .on_hover_text("Move this model and its tokenizer and manifest to the trash (see the Storage tab)")
                             .clicked() {
-                            if let Err(e) = std::fs::remove_file(&model.path) {
-                                self.error_message = Some(format!("Failed to delete model: {}", e));
+                            if let Err(e) = model_storage::move_to_trash(&model.path) {
+                                self.error_message = Some(format!("Failed to delete model: {:#}", e));
                             } else {
-                                self.success_message = Some("Model deleted successfully".to_string());
+                                self.success_message = Some("Model moved to the trash".to_string());
+                            }
+                        }
+
+                        let verifying = matches!(self.integrity.get(&model.name), Some(None));
+                        if ui.add_enabled(!verifying, egui::Button::new("🔍 Verify").rounding(6.0))
+                            .on_hover_text("Re-check the file's SHA256 against its manifest")
+                            .clicked() {
+                            self.start_verify(model);
+                        }
+
+                        // Dynamic INT8 quantization is only meaningful for full-precision models
+                        if matches!(model.quantization, None | Some(QuantizationType::FP32)) {
+                            let busy = self.quantize_job.is_some();
+                            let quantize_button = egui::Button::new("🗜 Quantize")
+                                .fill(egui::Color32::from_rgb(255, 152, 0))
+                                .rounding(6.0);
+                            if ui.add_enabled(!busy, quantize_button)
+                                .on_hover_text("Create a dynamic INT8 copy of this model (smaller, faster on CPU)")
+                                .on_disabled_hover_text("Another quantization is in progress")
+                                .clicked() {
+                                self.start_quantize(model);
                             }
                         }
                     });
@@ -722,7 +1296,7 @@ impl ModelManagerUI {
                         ui.label(
                             egui::RichText::new(label)
                                 .size(10.0)
-                                .color(egui::Color32::from_rgb(180, 180, 180))
+                                .color(Palette::current(ui.ctx()).muted_text)
                         );
                     });
                     ui.label(
@@ -749,12 +1323,17 @@ impl ModelManagerUI {
             self.start_system_model_loading();
         }
 
-        // Show loading indicator if system models are being loaded
+        // Show loading indicator if system models are being loaded; results stream in below it
         if let Some(ref mut loading) = self.system_loading {
             ui.add_space(20.0);
             loading.show(ui);
             ui.add_space(20.0);
-        } else if self.system_models.is_empty() {
+            ui.ctx().request_repaint();
+        }
+        if self.system_models.is_empty() {
+            if self.system_loading.is_some() {
+                return; // Nothing found yet
+            }
             ui.horizontal(|ui| {
                 ui.label("ℹ️");
                 ui.vertical(|ui| {
@@ -786,57 +1365,57 @@ impl ModelManagerUI {
     }
     
     fn start_system_model_loading(&mut self) {
-        if self.system_models_loaded {
+        if self.system_models_loaded || self.system_scan_rx.is_some() {
             return;
         }
         
         // Initialize loading indicator
         let mut loading = SystemLoadingIndicator::new();
-        loading.set_stage("Scanning system directories...".to_string(), 0.1);
+        loading.set_stage("Scanning system directories...".to_string(), 0.0);
         self.system_loading = Some(loading);
-        
-        // Simulate progressive loading stages
-        self.load_system_models_with_progress();
+        self.system_models.clear();
+
+        // Detection walks the filesystem, so it runs on the blocking pool and streams back
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.system_scan_rx = Some(rx);
+        let manager = self.manager.clone();
+        tokio::task::spawn_blocking(move || {
+            let guard = manager.blocking_read();
+            guard.detect_system_models_streaming(|evt| { let _ = tx.send(evt); });
+        });
     }
-    
-    fn load_system_models_with_progress(&mut self) {
-        if self.system_models_loaded {
-            return;
-        }
-        
-        // Update progress stages
-        if let Some(ref mut loading) = self.system_loading {
-            loading.set_stage("Checking Windows system paths...".to_string(), 0.3);
-        }
-        
-        // Since we can't use async in the UI thread, we'll create a temporary ModelManager
-        // just for system model detection. This is safe since detect_system_models() 
-        // only reads from the filesystem and doesn't modify state.
-        let temp_manager = crate::ai::models::ModelManager::new("./models").ok();
-        
-        if let Some(manager) = temp_manager {
-            if let Some(ref mut loading) = self.system_loading {
-                loading.set_stage("Analyzing discovered models...".to_string(), 0.7);
-            }
-            
-            self.system_models = manager.detect_system_models();
-            
-            if let Some(ref mut loading) = self.system_loading {
-                loading.set_stage("Finalizing detection...".to_string(), 1.0);
+
+    fn handle_system_scan_events(&mut self) {
+        let Some(rx) = self.system_scan_rx.as_mut() else { return };
+        let mut events = Vec::new();
+        while let Ok(evt) = rx.try_recv() { events.push(evt); }
+        for evt in events {
+            match evt {
+                SystemScanEvent::Scanning { location, index, total } => {
+                    if let Some(ref mut loading) = self.system_loading {
+                        loading.set_stage(format!("Scanning {location}"), index as f32 / total.max(1) as f32);
+                    }
+                }
+                SystemScanEvent::Found(model) => {
+                    if !self.system_models.iter().any(|m| m.path == model.path) {
+                        self.system_models.push(model);
+                    }
+                }
+                SystemScanEvent::Done { found } => {
+                    self.system_models_loaded = true;
+                    self.system_loading = None; // Hide loading indicator
+                    self.system_scan_rx = None;
+                    self.tab_loading_states.insert(ModelTab::System, false);
+                    tracing::info!("System model detection completed: {} models found", found);
+                    return;
+                }
             }
-            
-            self.system_models_loaded = true;
-            self.system_loading = None; // Hide loading indicator
-            tracing::info!("System model detection completed: {} models found", self.system_models.len());
-        } else {
-            self.system_loading = None;
-            tracing::warn!("Failed to create temporary ModelManager for system model detection");
         }
     }
 
     fn render_system_model_card(&mut self, ui: &mut egui::Ui, model: &ModelInfo) {
         egui::Frame::none()
-            .fill(egui::Color32::from_rgb(40, 50, 40)) // Slightly green tint for system models
+            .fill(Palette::current(ui.ctx()).system_card_fill) // Slightly green tint for system models
             .rounding(8.0)
             .inner_margin(15.0)
             .show(ui, |ui| {
@@ -926,18 +1505,19 @@ impl ModelManagerUI {
 
     fn render_remote_model_card(&mut self, ui: &mut egui::Ui, model: &RemoteModelInfo) {
         let is_downloading = self.downloading.contains_key(&model.name);
+        let palette = Palette::current(ui.ctx());
         
         // Enhanced card with download-specific styling
         let base_fill = if is_downloading {
-            egui::Color32::from_rgb(45, 50, 65) // Download in progress highlight
+            palette.card_fill_selected
         } else {
-            egui::Color32::from_rgb(38, 42, 52) // Default remote model color
+            palette.card_fill
         };
         
         let stroke_color = if is_downloading {
             egui::Color32::from_rgb(33, 150, 243) // Blue border when downloading
         } else {
-            egui::Color32::from_rgb(60, 65, 75) // Subtle border
+            palette.card_stroke
         };
         
         egui::Frame::none()
@@ -946,7 +1526,7 @@ impl ModelManagerUI {
             .rounding(12.0)
             .inner_margin(18.0)
             .shadow(egui::epaint::Shadow {
-                color: egui::Color32::from_black_alpha(60),
+                color: palette.shadow,
                 offset: egui::vec2(0.0, 3.0),
                 blur: 6.0,
                 spread: 0.0,
@@ -979,7 +1559,7 @@ impl ModelManagerUI {
                                 egui::RichText::new(&model.name)
                                     .size(18.0)
                                     .strong()
-                                    .color(egui::Color32::WHITE)
+                                    .color(palette.heading_text)
                             );
                             
                             // Cloud indicator for remote models
@@ -1004,7 +1584,7 @@ impl ModelManagerUI {
                         ui.label(
                             egui::RichText::new(&model.description)
                                 .size(13.0)
-                                .color(egui::Color32::from_rgb(200, 200, 200))
+                                .color(palette.secondary_text)
                                 .italics()
                         );
                         
@@ -1020,14 +1600,14 @@ impl ModelManagerUI {
                             };
                             
                             egui::Frame::none()
-                                .fill(egui::Color32::from_rgb(55, 60, 70))
+                                .fill(palette.chip_fill)
                                 .rounding(4.0)
                                 .inner_margin(egui::Margin::symmetric(8.0, 4.0))
                                 .show(ui, |ui| {
                                     ui.label(
                                         egui::RichText::new(type_text)
                                             .size(11.0)
-                                            .color(egui::Color32::from_rgb(200, 200, 200))
+                                            .color(palette.secondary_text)
                                     );
                                 });
                             
@@ -1069,7 +1649,20 @@ impl ModelManagerUI {
                             if ui.add_sized([100.0, 32.0], download_button)
                                 .on_hover_text(format!("Download {} ({:.1} MB)", model.name, model.size_mb))
                                 .clicked() {
-                                self.start_download(model.url.clone(), model.name.clone());
+                                self.request_download(model);
+                            }
+                        }
+                        if model.card_url().is_some() {
+                            let open = self.shown_card.as_ref() == Some(&model.name);
+                            if ui.selectable_label(open, "📄 Model card")
+                                .on_hover_text("Read the license and usage notes before downloading")
+                                .clicked()
+                            {
+                                if open {
+                                    self.shown_card = None;
+                                } else {
+                                    self.show_model_card(model);
+                                }
                             }
                         }
                     });
@@ -1100,7 +1693,7 @@ impl ModelManagerUI {
                     ui.label(
                         egui::RichText::new("Available Features")
                             .size(12.0)
-                            .color(egui::Color32::from_rgb(180, 180, 180))
+                            .color(palette.muted_text)
                     );
                     
                     ui.add_space(6.0);
@@ -1148,6 +1741,29 @@ impl ModelManagerUI {
                             ui.add_space(4.0);
                         }
                         
+                        // License that has to be accepted before downloading
+                        if let Some(license) = &model.license {
+                            egui::Frame::none()
+                                .fill(palette.warning.gamma_multiply(0.2))
+                                .stroke(egui::Stroke::new(1.0, palette.warning))
+                                .rounding(12.0)
+                                .inner_margin(egui::Margin::symmetric(10.0, 5.0))
+                                .show(ui, |ui| {
+                                    ui.horizontal(|ui| {
+                                        ui.label(egui::RichText::new(if license.gated { "🔐" } else { "⚖" }).size(12.0));
+                                        ui.label(
+                                            egui::RichText::new(&license.name)
+                                                .size(11.0)
+                                                .color(palette.warning)
+                                                .strong()
+                                        );
+                                    });
+                                })
+                                .response
+                                .on_hover_text(if license.gated { "Gated: needs accepting the license and a Hugging Face token" } else { "The license must be accepted before downloading" });
+                            ui.add_space(4.0);
+                        }
+
                         // Remote model indicator
                         egui::Frame::none()
                             .fill(egui::Color32::from_rgb(156, 39, 176).gamma_multiply(0.2))
@@ -1175,7 +1791,7 @@ impl ModelManagerUI {
                     ui.label(
                         egui::RichText::new("🌐")
                             .size(12.0)
-                            .color(egui::Color32::from_rgb(150, 150, 150))
+                            .color(palette.muted_text)
                     );
                     
                     let truncated_url = if model.url.len() > 60 {
@@ -1187,7 +1803,7 @@ impl ModelManagerUI {
                     ui.label(
                         egui::RichText::new(truncated_url)
                             .size(10.0)
-                            .color(egui::Color32::from_rgb(150, 150, 150))
+                            .color(palette.muted_text)
                             .monospace()
                     );
                 });
@@ -1259,6 +1875,22 @@ impl ModelManagerUI {
             ui.add_space(8.0);
         }
         
+        if let Some((name, stage, frac)) = &self.quantize_job {
+            egui::Frame::none()
+                .fill(egui::Color32::from_rgba_unmultiplied(60, 45, 20, 200))
+                .stroke(egui::Stroke::new(1.0, egui::Color32::from_rgb(255, 152, 0)))
+                .rounding(6.0)
+                .inner_margin(10.0)
+                .show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label(format!("Quantizing {name}: {stage}"));
+                    });
+                    ui.add(egui::ProgressBar::new(*frac).show_percentage());
+                });
+            ui.add_space(8.0);
+        }
+
         // Show active downloads
         if !self.downloading.is_empty() {
             ui.strong("Active Downloads:");
@@ -1275,10 +1907,290 @@ impl ModelManagerUI {
         }
     }
 
+    /// Start downloading a catalog entry, asking for its license to be accepted first.
+    fn request_download(&mut self, model: &RemoteModelInfo) {
+        match &model.license {
+            Some(license) if !self.accepted_licenses.contains(&license_key(&model.name, license)) => {
+                self.pending_license = Some(model.clone());
+                self.license_ticked = false;
+            }
+            _ => self.start_download(model.url.clone(), model.name.clone()),
+        }
+    }
+
+    fn render_license_dialog(&mut self, ctx: &egui::Context) {
+        let Some(model) = self.pending_license.clone() else { return };
+        let Some(license) = model.license.as_ref() else { return };
+        let mut open = true;
+        let mut decision = None;
+        egui::Window::new("📜 License agreement")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.set_max_width(420.0);
+                ui.label(format!("{} is distributed under the {}.", model.name, license.name));
+                ui.label("Read the terms before downloading; they may restrict how the model can be used.");
+                ui.horizontal(|ui| {
+                    if let Some(url) = &license.url {
+                        ui.hyperlink_to("📜 Read the license", url);
+                    }
+                    if model.card_url().is_some() && ui.link("📄 Model card").clicked() {
+                        self.show_model_card(&model);
+                    }
+                });
+                if license.gated {
+                    ui.add_space(6.0);
+                    let palette = Palette::current(ui.ctx());
+                    ui.label("This is a gated Hugging Face repository: accept the terms on the model page with your Hugging Face account as well.");
+                    match crate::ai::models::huggingface_token_known() {
+                        Some(true) => {
+                            ui.colored_label(palette.success, "✔ A Hugging Face access token is set");
+                        }
+                        None => {
+                            ui.label(egui::RichText::new("Checking the system credential store for an access token…").weak());
+                            ui.ctx().request_repaint_after(std::time::Duration::from_millis(250));
+                        }
+                        Some(false) => {
+                            ui.colored_label(palette.warning, "⚠ No access token set. Add one from that account in Settings → Network, or the download will be refused.");
+                        }
+                    }
+                }
+                ui.add_space(8.0);
+                ui.checkbox(&mut self.license_ticked, format!("I have read and accept the {}", license.name));
+                ui.add_space(8.0);
+                ui.horizontal(|ui| {
+                    if ui.add_enabled(self.license_ticked, egui::Button::new("📥 Accept and download")).clicked() {
+                        decision = Some(true);
+                    }
+                    if ui.button("Cancel").clicked() {
+                        decision = Some(false);
+                    }
+                });
+            });
+        if !open {
+            decision = Some(false);
+        }
+        match decision {
+            Some(true) => {
+                self.pending_license = None;
+                self.accepted_licenses.insert(license_key(&model.name, license));
+                if let Err(e) = self.save_accepted_licenses() {
+                    tracing::warn!("Failed to remember the accepted license: {}", e);
+                }
+                tracing::info!("{} accepted for {}", license.name, model.name);
+                self.start_download(model.url.clone(), model.name.clone());
+            }
+            Some(false) => self.pending_license = None,
+            None => {}
+        }
+    }
+
+    fn save_accepted_licenses(&self) -> anyhow::Result<()> {
+        let dir = AppConfig::catalog_dir();
+        std::fs::create_dir_all(&dir)?;
+        let mut accepted: Vec<&String> = self.accepted_licenses.iter().collect();
+        accepted.sort();
+        std::fs::write(dir.join(ACCEPTED_LICENSES_FILE), serde_json::to_string_pretty(&accepted)?)?;
+        Ok(())
+    }
+
+    fn scan_storage(&mut self) {
+        let dirs = self.manager.try_read().map(|g| g.get_models_directories().to_vec()).unwrap_or_else(|_| vec![self.downloads_dir.clone()]);
+        self.storage_items = model_storage::scan(&dirs);
+        let present: HashSet<&PathBuf> = self.storage_items.iter().filter_map(|i| i.paths.first()).collect();
+        self.storage_selected.retain(|p| present.contains(p));
+    }
+
+    fn render_storage(&mut self, ui: &mut egui::Ui) {
+        let palette = Palette::current(ui.ctx());
+        let total = |kind: StorageKind| ModelManager::format_file_size(self.storage_items.iter().filter(|i| i.kind == kind).map(|i| i.bytes).sum());
+        let summary = format!("Models: {}   Orphaned files: {}   Trash: {}", total(StorageKind::Model), total(StorageKind::Orphan), total(StorageKind::Trash));
+        ui.horizontal(|ui| {
+            ui.label(summary);
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                if ui.button("🔄").on_hover_text("Scan again").clicked() {
+                    self.scan_storage();
+                }
+                ui.checkbox(&mut self.storage_sort_by_size, "Largest first");
+            });
+        });
+        ui.add_space(8.0);
+
+        let mut items = self.storage_items.clone();
+        if self.storage_sort_by_size {
+            items.sort_by_key(|i| std::cmp::Reverse(i.bytes));
+        } else {
+            items.sort_by_key(|i| i.label.to_lowercase());
+        }
+        let mut restore = None;
+        egui::ScrollArea::vertical().max_height(420.0).auto_shrink([false, true]).show(ui, |ui| {
+            for (kind, heading, empty) in [
+                (StorageKind::Model, "Models", "No models"),
+                (StorageKind::Orphan, "Orphaned files", "Nothing left over from removed models"),
+                (StorageKind::Trash, "Trash", "The trash is empty"),
+            ] {
+                ui.label(egui::RichText::new(heading).strong().color(palette.heading_text));
+                let rows: Vec<&StorageItem> = items.iter().filter(|i| i.kind == kind).collect();
+                if rows.is_empty() {
+                    ui.label(egui::RichText::new(empty).small().color(palette.muted_text));
+                }
+                egui::Grid::new(("storage", heading)).num_columns(3).striped(true).spacing([12.0, 4.0]).show(ui, |ui| {
+                    for item in rows {
+                        let Some(key) = item.paths.first() else { continue };
+                        let mut ticked = self.storage_selected.contains(key);
+                        let hover = item.paths.iter().map(|p| p.display().to_string()).collect::<Vec<_>>().join("\n");
+                        if ui.checkbox(&mut ticked, &item.label).on_hover_text(hover).changed() {
+                            if ticked {
+                                self.storage_selected.insert(key.clone());
+                            } else {
+                                self.storage_selected.remove(key);
+                            }
+                        }
+                        ui.label(ModelManager::format_file_size(item.bytes));
+                        if kind == StorageKind::Trash && ui.small_button("↩ Restore").clicked() {
+                            restore = Some(key.clone());
+                        }
+                        ui.end_row();
+                    }
+                });
+                ui.add_space(8.0);
+            }
+        });
+
+        let selected: Vec<&StorageItem> = items.iter().filter(|i| i.paths.first().is_some_and(|p| self.storage_selected.contains(p))).collect();
+        let selected_bytes: u64 = selected.iter().map(|i| i.bytes).sum();
+        let trashes_models = selected.iter().any(|i| i.kind == StorageKind::Model);
+        let mut clean = false;
+        ui.horizontal(|ui| {
+            let label = format!("🗑 Delete {} selected ({})", selected.len(), ModelManager::format_file_size(selected_bytes));
+            clean = ui
+                .add_enabled(!selected.is_empty(), egui::Button::new(label))
+                .on_hover_text(if trashes_models { "Models go to the trash; orphaned files and trash contents are deleted for good" } else { "Deleted for good" })
+                .clicked();
+            if ui.button("Select orphans and trash").clicked() {
+                for item in items.iter().filter(|i| i.kind != StorageKind::Model) {
+                    if let Some(key) = item.paths.first() {
+                        self.storage_selected.insert(key.clone());
+                    }
+                }
+            }
+        });
+
+        if clean {
+            let mut errors = Vec::new();
+            let count = selected.len();
+            for item in selected {
+                let result = match item.kind {
+                    StorageKind::Model => model_storage::move_to_trash(&item.paths[0]).map(|_| ()),
+                    StorageKind::Orphan | StorageKind::Trash => item.delete(),
+                };
+                if let Err(e) = result {
+                    errors.push(format!("{}: {e:#}", item.label));
+                }
+            }
+            if errors.is_empty() {
+                self.success_message = Some(format!("Cleaned up {count} item(s), {} freed or moved to the trash", ModelManager::format_file_size(selected_bytes)));
+            } else {
+                self.error_message = Some(errors.join("; "));
+            }
+            self.storage_selected.clear();
+            self.scan_storage();
+            self.spawn_rescan(None);
+        }
+        if let Some(folder) = restore {
+            match model_storage::restore(&folder) {
+                Ok(()) => self.success_message = Some("Model restored".to_string()),
+                Err(e) => self.error_message = Some(format!("Failed to restore: {e:#}")),
+            }
+            self.scan_storage();
+            self.spawn_rescan(None);
+        }
+    }
+
+    /// Find `.part` files left by downloads that aren't running (crashed, quit or failed).
+    fn scan_interrupted(&mut self) {
+        let running = |name: &str| self.download_tasks.get(name).is_some_and(|task| !task.is_finished());
+        self.interrupted = crate::ai::models::partial_downloads(&self.downloads_dir)
+            .into_iter()
+            .filter(|partial| !running(&partial.name()))
+            .collect();
+    }
+
+    /// URL to resume `partial` from: the one it was started with, or a catalog entry of the same name.
+    fn resume_url(&self, partial: &PartialDownload) -> Option<String> {
+        if let Some(url) = partial.record.as_ref().and_then(|r| r.urls.first()) {
+            return Some(url.clone());
+        }
+        let name = partial.name();
+        self.remote_models
+            .iter()
+            .find(|m| m.name == name || crate::utils::sanitize_filename(&m.name) == name)
+            .map(|m| m.url.clone())
+    }
+
+    fn render_interrupted_downloads(&mut self, ui: &mut egui::Ui) {
+        let palette = Palette::current(ui.ctx());
+        let mut resume = None;
+        let mut discard = None;
+        egui::Frame::none()
+            .fill(palette.card_fill)
+            .stroke(egui::Stroke::new(1.0, palette.warning))
+            .rounding(8.0)
+            .inner_margin(12.0)
+            .show(ui, |ui| {
+                ui.label(egui::RichText::new("⏸ Interrupted downloads").strong().color(palette.heading_text));
+                ui.label(egui::RichText::new("These downloads stopped before finishing. Resuming continues from the data already saved.").small().color(palette.muted_text));
+                ui.add_space(4.0);
+                egui::Grid::new("interrupted_downloads").num_columns(3).spacing([12.0, 6.0]).show(ui, |ui| {
+                    for partial in &self.interrupted {
+                        ui.label(partial.name());
+                        let mut info = ModelManager::format_file_size(partial.bytes);
+                        if let Some(record) = &partial.record {
+                            let started = record.started_at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M");
+                            info = format!("{info} downloaded, started {started}");
+                        }
+                        ui.label(egui::RichText::new(info).small().color(palette.secondary_text));
+                        ui.horizontal(|ui| {
+                            let url = self.resume_url(partial);
+                            let button = ui.add_enabled(url.is_some(), egui::Button::new("▶ Resume"));
+                            let button = if url.is_none() { button.on_disabled_hover_text("Where this download came from isn't known") } else { button };
+                            if button.clicked() {
+                                resume = url.map(|url| (url, partial.name()));
+                            }
+                            if ui.button("🗑 Discard").on_hover_text(partial.part_path.display().to_string()).clicked() {
+                                discard = Some(partial.clone());
+                            }
+                        });
+                        ui.end_row();
+                    }
+                });
+            });
+        if let Some((url, name)) = resume {
+            self.start_download(url, name);
+        }
+        if let Some(partial) = discard {
+            match partial.discard() {
+                Ok(()) => self.success_message = Some(format!("Discarded the partial download of {}", partial.name())),
+                Err(e) => self.error_message = Some(format!("{e:#}")),
+            }
+            self.scan_interrupted();
+        }
+    }
+
     fn start_download(&mut self, url: String, name: String) {
         tracing::info!("Download requested for: {}", name);
         let manager = self.manager.clone();
         let maybe_entry = self.remote_models.iter().find(|m| m.name == name).cloned();
+        // A download resumed after a restart keeps the mirrors and checksum it was started with
+        let record = self
+            .interrupted
+            .iter()
+            .find(|p| p.name() == name)
+            .and_then(|p| p.record.clone())
+            .filter(|r| r.urls.first() == Some(&url));
+        self.interrupted.retain(|p| p.name() != name);
         
         // Create download progress card
         let download_info = DownloadInfo {
@@ -1298,10 +2210,19 @@ impl ModelManagerUI {
         // Clone progress sender for the async task
         let progress_tx = self.progress_tx.clone();
         let download_name = name.clone();
+        let task_name = name.clone();
 
-        tokio::spawn(async move {
-            let sha = maybe_entry.as_ref().and_then(|m| m.sha256.as_ref()).map(|s| s.clone());
+        let task = tokio::spawn(async move {
+            let sha = maybe_entry.as_ref().and_then(|m| m.sha256.as_ref()).map(|s| s.clone())
+                .or_else(|| record.as_ref().and_then(|r| r.sha256.clone()));
+            // Catalog mirrors only apply when downloading the catalog URL itself
+            let urls = maybe_entry.as_ref().filter(|m| m.url == url).map_or_else(
+                || record.as_ref().map_or_else(|| vec![url.clone()], |r| r.urls.clone()),
+                |m| m.download_urls(),
+            );
             let tok_url = maybe_entry.as_ref().and_then(|m| m.tokenizer_url.as_ref()).map(|s| s.clone());
+            let tok_sha = maybe_entry.as_ref().and_then(|m| m.tokenizer_sha256.clone());
+            let extra_files = maybe_entry.as_ref().map(|m| m.files.clone()).unwrap_or_default();
 
             // Create progress callback that sends updates through the channel
             let progress_callback = {
@@ -1318,30 +2239,62 @@ impl ModelManagerUI {
                 }
             };
 
-            let mut guard = manager.write().await;
-            match guard.download_model_with_verify_and_progress(&url, &name, sha.as_deref(), Some(progress_callback)).await {
-                Ok(model_path) => {
-                    tracing::info!("Model downloaded: {}", model_path.display());
-                    
-                    // Send completion status
-                    let _ = progress_tx.send(ProgressUpdate {
-                        model_name: download_name.clone(),
-                        downloaded_bytes: 0, // Will be updated by progress callback
+            let on_retry = {
+                let tx = progress_tx.clone();
+                let name = download_name.clone();
+                move |retry: DownloadRetry| {
+                    let _ = tx.send(ProgressUpdate {
+                        model_name: name.clone(),
+                        downloaded_bytes: 0,
                         total_bytes: 0,
                         speed_bps: 0.0,
-                        status: DownloadStatus::Completed,
+                        status: DownloadStatus::Retrying {
+                            attempt: retry.attempt,
+                            max_attempts: ATTEMPTS_PER_URL,
+                            mirror: (retry.url_index > 0).then_some((retry.url_index, retry.url_count - 1)),
+                            delay_secs: retry.delay.as_secs(),
+                            error: retry.error,
+                        },
                     });
-                    
-                    // Download tokenizer if provided
+                }
+            };
+
+            let mut guard = manager.write().await;
+            match guard.download_model_from_mirrors(&urls, &name, sha.as_deref(), progress_callback, on_retry).await {
+                Ok(model_path) => {
+                    tracing::info!("Model downloaded: {}", model_path.display());
+
+                    // Tokenizer and extra files first, so an auto-load on completion finds them
                     if let Some(tu) = tok_url {
                         let tok_name = format!("{}.tokenizer.json", crate::utils::sanitize_filename(&name));
                         let tok_path = guard.get_models_directory().join(tok_name);
-                        if let Err(e) = guard.download_aux_file(&tu, &tok_path).await {
+                        if let Err(e) = guard.download_aux_file(&tu, tok_sha.as_deref(), &tok_path).await {
                             tracing::warn!("Failed to download tokenizer for {}: {}", name, e);
                         } else {
                             tracing::info!("Tokenizer downloaded for {}", name);
                         }
                     }
+                    let mut failed_files = Vec::new();
+                    for file in &extra_files {
+                        let dest = guard.get_models_directory().join(crate::utils::sanitize_filename(&file.file_name));
+                        if let Err(e) = guard.download_aux_file(&file.url, file.sha256.as_deref(), &dest).await {
+                            tracing::warn!("Failed to download {} for {}: {}", file.file_name, name, e);
+                            failed_files.push(format!("{}: {}", file.file_name, e));
+                        }
+                    }
+
+                    let status = if failed_files.is_empty() {
+                        DownloadStatus::Completed
+                    } else {
+                        DownloadStatus::Failed(format!("Model downloaded, but files it needs failed: {}", failed_files.join("; ")))
+                    };
+                    let _ = progress_tx.send(ProgressUpdate {
+                        model_name: download_name.clone(),
+                        downloaded_bytes: 0, // Will be updated by progress callback
+                        total_bytes: 0,
+                        speed_bps: 0.0,
+                        status,
+                    });
                 }
                 Err(e) => {
                     tracing::error!("Download failed for {}: {}", name, e);
@@ -1357,6 +2310,121 @@ impl ModelManagerUI {
                 }
             }
         });
+        self.download_tasks.insert(task_name, task);
+    }
+
+    /// Downloads and quantization still running, described for the "finishing up" dialog.
+    pub fn running_tasks(&mut self) -> Vec<String> {
+        self.download_tasks.retain(|_, task| !task.is_finished());
+        let mut tasks: Vec<String> = self.download_tasks.keys().map(|name| format!("Downloading {name}")).collect();
+        if let (Some((task, _)), Some((name, ..))) = (&self.quantize_task, &self.quantize_job) {
+            if !task.is_finished() {
+                tasks.push(format!("Quantizing {name}"));
+            }
+        }
+        tasks
+    }
+
+    /// Stop downloads and quantization before the app exits. Downloads keep their `.part`
+    /// file so the next attempt resumes; the quantizer removes its half-written output.
+    pub fn shutdown(&mut self) {
+        for (name, task) in &self.download_tasks {
+            if !task.is_finished() {
+                tracing::info!("Stopping download of {} for shutdown", name);
+                task.abort();
+            }
+        }
+        if let Some((task, output)) = &self.quantize_task {
+            if !task.is_finished() {
+                tracing::info!("Stopping quantization into {} for shutdown", output.display());
+                task.abort();
+            }
+        }
+    }
+
+    /// Switch the catalog source (from settings). A remote catalog starts from its verified
+    /// cache, if any, and is refreshed once the cache is older than the refresh interval.
+    pub fn set_catalog(&mut self, catalog: CatalogSettings) {
+        self.catalog = catalog;
+        self.catalog_rx = None;
+        self.catalog_fetched = None;
+        self.catalog_status = None;
+        self.catalog_edited = Some(Instant::now());
+        self.load_remote_models();
+        if !self.catalog.is_remote() {
+            return;
+        }
+        let Some(cached) = catalog::load_cache(&AppConfig::catalog_dir(), self.catalog.url.trim(), &self.catalog.public_key) else { return };
+        if self.apply_catalog(&cached) {
+            let age = (chrono::Utc::now() - cached.fetched_at).to_std().unwrap_or_default();
+            self.catalog_fetched = Instant::now().checked_sub(age);
+        }
+    }
+
+    /// Fetch the remote catalog again on the next frame.
+    pub fn refresh_catalog(&mut self) {
+        self.catalog_fetched = None;
+    }
+
+    fn apply_catalog(&mut self, signed: &SignedCatalog) -> bool {
+        match serde_json::from_str::<Vec<RemoteModelInfo>>(&signed.catalog) {
+            Ok(list) => {
+                let updated = signed.fetched_at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M");
+                self.catalog_status = Some((false, format!("Signed catalog: {} models, updated {updated}", list.len())));
+                self.remote_models = list;
+                true
+            }
+            Err(e) => {
+                self.catalog_status = Some((true, format!("The signed catalog isn't a valid model list: {e}")));
+                false
+            }
+        }
+    }
+
+    /// Take a finished catalog fetch and start the next one when the current copy is stale.
+    fn poll_catalog(&mut self) {
+        if let Some(rx) = self.catalog_rx.as_mut() {
+            match rx.try_recv() {
+                Err(tokio::sync::oneshot::error::TryRecvError::Empty) => return,
+                Err(tokio::sync::oneshot::error::TryRecvError::Closed) => {}
+                Ok(Ok(signed)) => {
+                    if self.apply_catalog(&signed) {
+                        tracing::info!("Remote model catalog refreshed from {}", signed.url);
+                        if let Err(e) = catalog::save_cache(&AppConfig::catalog_dir(), &signed) {
+                            tracing::warn!("Failed to cache the model catalog: {}", e);
+                        }
+                    }
+                }
+                Ok(Err(e)) => {
+                    tracing::warn!("Model catalog refresh failed: {:#}", e);
+                    self.catalog_status = Some((true, format!("Catalog refresh failed: {e:#}")));
+                }
+            }
+            self.catalog_rx = None;
+        }
+
+        if !self.catalog.is_remote() || self.catalog_fetched.is_some_and(|t| t.elapsed() < self.catalog.refresh_interval()) {
+            return;
+        }
+        // Settings edits arrive per keystroke; fetch once the URL and key have settled
+        if self.catalog_edited.is_some_and(|t| t.elapsed() < Duration::from_secs(1)) {
+            return;
+        }
+        self.catalog_fetched = Some(Instant::now());
+        let client = match self.network.http_client() {
+            Ok(client) => client,
+            Err(e) => {
+                self.catalog_status = Some((true, format!("Catalog refresh failed: {e:#}")));
+                return;
+            }
+        };
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        let url = self.catalog.url.trim().to_string();
+        let public_key = self.catalog.public_key.clone();
+        tokio::spawn(async move {
+            let _ = tx.send(catalog::fetch(&client, &url, &public_key).await);
+        });
+        self.catalog_rx = Some(rx);
     }
 
     fn load_remote_models(&mut self) {
@@ -1387,8 +2455,7 @@ impl ModelManagerUI {
                 model_type: ModelType::ChatModel,
                 quantization: QuantizationType::INT4,
                 requirements: "4GB RAM".to_string(),
-                sha256: None,
-                tokenizer_url: None,
+                ..Default::default()
             },
             RemoteModelInfo {
                 name: "TinyLlama-1.1B-Chat".to_string(),
@@ -1398,8 +2465,7 @@ impl ModelManagerUI {
                 model_type: ModelType::ChatModel,
                 quantization: QuantizationType::FP32,
                 requirements: "2GB RAM".to_string(),
-                sha256: None,
-                tokenizer_url: None,
+                ..Default::default()
             },
             RemoteModelInfo {
                 name: "CodeQwen1.5-7B-Chat".to_string(),
@@ -1409,8 +2475,7 @@ impl ModelManagerUI {
                 model_type: ModelType::CodeModel,
                 quantization: QuantizationType::INT8,
                 requirements: "16GB RAM".to_string(),
-                sha256: None,
-                tokenizer_url: None,
+                ..Default::default()
             },
             RemoteModelInfo {
                 name: "Qwen2-0.5B-Instruct".to_string(),
@@ -1420,8 +2485,7 @@ impl ModelManagerUI {
                 model_type: ModelType::ChatModel,
                 quantization: QuantizationType::INT8,
                 requirements: "1GB RAM".to_string(),
-                sha256: None,
-                tokenizer_url: None,
+                ..Default::default()
             },
         ];
     }
@@ -1431,23 +2495,25 @@ impl ModelManagerUI {
     }
 
     pub fn get_selected_model_info(&self) -> Option<ModelInfo> {
-        if let Some(selected_name) = &self.selected_model {
-            // For now, simulate model info since we need this to be sync
-            // In a real implementation, you'd use channels or store model info locally
-            Some(ModelInfo {
-                name: selected_name.clone(),
-                path: std::path::PathBuf::from(format!("./models/{}.onnx", selected_name)),
-                size: 1000000, // 1MB placeholder
-                model_type: crate::ai::models::ModelType::ChatModel,
-                supported_providers: vec![crate::ai::ExecutionProvider::Cpu],
-                description: format!("Simulated model info for {}", selected_name),
-                quantization: Some(crate::ai::models::QuantizationType::FP32),
-            })
-        } else {
-            None
-        }
+        let selected_name = self.selected_model.as_ref()?;
+        // Models can live in any configured directory, so resolve through the scanned lists
+        self.available_models.iter()
+            .chain(self.system_models.iter())
+            .find(|m| &m.name == selected_name)
+            .cloned()
     }
     
+    /// Known models whose name is `query`, or failing that contains it (ignoring case).
+    pub fn find_models(&self, query: &str) -> Vec<ModelInfo> {
+        let query = query.trim().to_lowercase();
+        let all = || self.available_models.iter().chain(self.system_models.iter());
+        let exact: Vec<ModelInfo> = all().filter(|m| m.name.to_lowercase() == query).cloned().collect();
+        if !exact.is_empty() {
+            return exact;
+        }
+        all().filter(|m| m.name.to_lowercase().contains(&query)).cloned().collect()
+    }
+
     fn render_help_overlay(&mut self, ui: &mut egui::Ui) {
         // Show help overlay as a popup window
         egui::Window::new("🔧 Keyboard Shortcuts")
@@ -1457,7 +2523,7 @@ impl ModelManagerUI {
             .show(ui.ctx(), |ui| {
                 ui.vertical(|ui| {
                     ui.heading("Navigation");
-                    ui.label("Ctrl+1, Ctrl+2, Ctrl+3 - Switch between tabs");
+                    ui.label("Ctrl+1 … Ctrl+4 - Switch between tabs");
                     ui.label("F5 or Ctrl+R - Refresh models");
                     ui.label("Escape - Clear messages/close help");
                     
@@ -0,0 +1,238 @@
+//! Notification history behind the 🔔 button in the status bar.
+//!
+//! Toasts disappear after a few seconds; every notification is also kept here and appended
+//! to a JSON-lines log file so earlier load and download failures can be reviewed, even
+//! after a restart. The log is rotated by size and only a few old files are kept.
+
+use crate::ui::app::NotificationType;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Entries kept in memory (and shown in the panel).
+const MAX_HISTORY: usize = 500;
+/// The log is rotated once it grows past this size.
+const MAX_LOG_BYTES: u64 = 512 * 1024;
+/// Rotated files kept next to the live log (`notifications.1.log`, ...).
+const ROTATED_LOGS: usize = 2;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NotificationRecord {
+    pub timestamp: DateTime<Utc>,
+    pub severity: NotificationType,
+    pub message: String,
+}
+
+pub struct NotificationLog {
+    path: Option<PathBuf>,
+    records: VecDeque<NotificationRecord>,
+}
+
+fn rotated_path(path: &Path, index: usize) -> PathBuf {
+    path.with_extension(format!("{index}.log"))
+}
+
+fn read_records(path: &Path) -> Vec<NotificationRecord> {
+    std::fs::read_to_string(path)
+        .map(|content| content.lines().filter_map(|line| serde_json::from_str(line).ok()).collect())
+        .unwrap_or_default()
+}
+
+impl NotificationLog {
+    /// History backed by the log file at `path`, pre-filled with what earlier runs logged.
+    pub fn open(path: PathBuf) -> Self {
+        let mut records = VecDeque::new();
+        for index in (1..=ROTATED_LOGS).rev() {
+            records.extend(read_records(&rotated_path(&path, index)));
+        }
+        records.extend(read_records(&path));
+        while records.len() > MAX_HISTORY {
+            records.pop_front();
+        }
+        Self { path: Some(path), records }
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    pub fn records(&self) -> &VecDeque<NotificationRecord> {
+        &self.records
+    }
+
+    pub fn record(&mut self, severity: NotificationType, message: &str) {
+        let record = NotificationRecord { timestamp: Utc::now(), severity, message: message.to_string() };
+        if let Some(path) = &self.path {
+            if let Err(e) = Self::append(path, &record) {
+                tracing::warn!("Failed to write notification log {}: {}", path.display(), e);
+            }
+        }
+        self.records.push_back(record);
+        if self.records.len() > MAX_HISTORY {
+            self.records.pop_front();
+        }
+    }
+
+    /// Forget the in-memory history; the log files are left for later review.
+    pub fn clear(&mut self) {
+        self.records.clear();
+    }
+
+    fn append(path: &Path, record: &NotificationRecord) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        if std::fs::metadata(path).map(|m| m.len() >= MAX_LOG_BYTES).unwrap_or(false) {
+            Self::rotate(path)?;
+        }
+        let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+        writeln!(file, "{}", serde_json::to_string(record)?)?;
+        Ok(())
+    }
+
+    fn rotate(path: &Path) -> Result<()> {
+        for index in (1..ROTATED_LOGS).rev() {
+            let from = rotated_path(path, index);
+            if from.exists() {
+                std::fs::rename(&from, rotated_path(path, index + 1))?;
+            }
+        }
+        std::fs::rename(path, rotated_path(path, 1))?;
+        Ok(())
+    }
+}
+
+/// Severities that can be filtered in the panel; loading toasts are transient and not logged.
+const FILTERABLE: [NotificationType; 4] =
+    [NotificationType::Error, NotificationType::Warning, NotificationType::Info, NotificationType::Success];
+
+pub struct NotificationCenter {
+    log: NotificationLog,
+    open: bool,
+    unread: usize,
+    hidden: Vec<NotificationType>,
+}
+
+impl NotificationCenter {
+    pub fn new(log: NotificationLog) -> Self {
+        Self { log, open: false, unread: 0, hidden: Vec::new() }
+    }
+
+    pub fn record(&mut self, severity: NotificationType, message: &str) {
+        if severity == NotificationType::Loading {
+            return;
+        }
+        self.log.record(severity, message);
+        if !self.open {
+            self.unread += 1;
+        }
+    }
+
+    /// The last `count` notifications, oldest first.
+    pub fn recent(&self, count: usize) -> Vec<NotificationRecord> {
+        let records = self.log.records();
+        records.iter().skip(records.len().saturating_sub(count)).cloned().collect()
+    }
+
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+        self.unread = 0;
+    }
+
+    /// Bell button for the status bar, with the number of unseen notifications.
+    pub fn bell_button(&mut self, ui: &mut egui::Ui) {
+        let text = if self.unread > 0 { format!("🔔 {}", self.unread) } else { "🔔".to_string() };
+        let response = ui
+            .add(egui::Button::new(text).small().frame(false))
+            .on_hover_text("Notification history");
+        crate::ui::a11y::set_name(&response, &format!("Notifications, {} unread", self.unread));
+        if response.clicked() {
+            self.toggle();
+        }
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) {
+        let mut open = self.open;
+        egui::Window::new("🔔 Notifications")
+            .open(&mut open)
+            .default_size([420.0, 360.0])
+            .resizable(true)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    for severity in FILTERABLE {
+                        let mut shown = !self.hidden.contains(&severity);
+                        if ui.checkbox(&mut shown, format!("{} {}", severity.icon(), severity.label())).changed() {
+                            if shown {
+                                self.hidden.retain(|s| *s != severity);
+                            } else {
+                                self.hidden.push(severity);
+                            }
+                        }
+                    }
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        if ui.button("Clear").on_hover_text("Clear the list (the log file is kept)").clicked() {
+                            self.log.clear();
+                        }
+                    });
+                });
+                ui.separator();
+
+                egui::ScrollArea::vertical().max_height(260.0).show(ui, |ui| {
+                    let mut any = false;
+                    for record in self.log.records().iter().rev().filter(|r| !self.hidden.contains(&r.severity)) {
+                        any = true;
+                        ui.horizontal_wrapped(|ui| {
+                            let local = record.timestamp.with_timezone(&chrono::Local);
+                            ui.label(egui::RichText::new(local.format("%Y-%m-%d %H:%M:%S").to_string()).small().weak());
+                            ui.label(egui::RichText::new(record.severity.icon()).color(record.severity.color()));
+                            ui.label(&record.message);
+                        });
+                    }
+                    if !any {
+                        ui.label(egui::RichText::new("No notifications").weak());
+                    }
+                });
+
+                if let Some(path) = self.log.path() {
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        ui.label(egui::RichText::new("Log file:").small());
+                        ui.code(path.display().to_string());
+                        if ui.small_button("📋").on_hover_text("Copy path").clicked() {
+                            ui.output_mut(|o| o.copied_text = path.display().to_string());
+                        }
+                    });
+                }
+            });
+        self.open = open;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_rotates_and_reloads_history() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notifications.log");
+        let mut log = NotificationLog::open(path.clone());
+        let message = "x".repeat(64 * 1024);
+        for _ in 0..40 {
+            log.record(NotificationType::Error, &message);
+        }
+        log.record(NotificationType::Warning, "Download failed");
+
+        assert!(rotated_path(&path, 1).exists());
+        assert!(!rotated_path(&path, ROTATED_LOGS + 1).exists());
+
+        let reopened = NotificationLog::open(path);
+        let last = reopened.records().back().unwrap();
+        assert_eq!(last.message, "Download failed");
+        assert_eq!(last.severity, NotificationType::Warning);
+        assert!(reopened.records().len() < 41);
+    }
+}
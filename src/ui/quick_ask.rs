@@ -0,0 +1,259 @@
+//! Quick-ask popup summoned by a global hotkey.
+//!
+//! A small always-on-top window ("Spotlight style") takes one question, streams the
+//! current model's answer into it and hands the exchange back to the app, which can append
+//! it to a scratch session.
+
+use anyhow::{anyhow, Result};
+use global_hotkey::hotkey::HotKey;
+use global_hotkey::{GlobalHotKeyEvent, GlobalHotKeyManager, HotKeyState};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tokio::sync::mpsc::error::TryRecvError;
+
+/// Session the quick-ask exchanges are appended to.
+pub const SCRATCH_SESSION_ID: &str = "quick-ask-scratch";
+pub const SCRATCH_SESSION_TITLE: &str = "Quick Ask";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QuickAskSettings {
+    pub enabled: bool,
+    /// e.g. "Ctrl+Shift+Space"; "CmdOrCtrl" maps to Cmd on macOS.
+    pub hotkey: String,
+    /// Append each question and answer to the "Quick Ask" scratch session.
+    pub append_to_scratch: bool,
+}
+
+impl Default for QuickAskSettings {
+    fn default() -> Self {
+        Self { enabled: false, hotkey: "CmdOrCtrl+Shift+Space".to_string(), append_to_scratch: true }
+    }
+}
+
+pub fn parse_hotkey(spec: &str) -> Result<HotKey> {
+    spec.trim().parse::<HotKey>().map_err(|e| anyhow!("Invalid hotkey '{spec}': {e}"))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum QuickAskEvent {
+    /// The user submitted a question; the app should start streaming an answer.
+    Ask(String),
+    Answered { question: String, answer: String },
+}
+
+/// Registered global hotkey; unregistered on drop.
+struct RegisteredHotkey {
+    manager: GlobalHotKeyManager,
+    hotkey: HotKey,
+    spec: String,
+}
+
+impl Drop for RegisteredHotkey {
+    fn drop(&mut self) {
+        let _ = self.manager.unregister(self.hotkey);
+    }
+}
+
+#[derive(Default)]
+pub struct QuickAsk {
+    open: bool,
+    focus_prompt: bool,
+    prompt: String,
+    question: String,
+    answer: String,
+    stream: Option<mpsc::Receiver<String>>,
+    error: Option<String>,
+    triggered: Arc<AtomicBool>,
+    hotkey: Option<RegisteredHotkey>,
+    handler_installed: bool,
+}
+
+impl QuickAsk {
+    /// Register, change or drop the global hotkey to match `settings`. Returns an error
+    /// message the first time a new spec fails to register.
+    pub fn sync_hotkey(&mut self, ctx: &egui::Context, settings: &QuickAskSettings) -> Option<String> {
+        let wanted = settings.enabled.then(|| settings.hotkey.trim().to_string());
+        let current = self.hotkey.as_ref().map(|h| h.spec.clone());
+        if wanted == current || (wanted.is_some() && self.error.as_deref() == wanted.as_deref()) {
+            return None;
+        }
+        self.hotkey = None;
+        self.error = None;
+        let spec = wanted?;
+        match self.register(ctx, &spec) {
+            Ok(registered) => {
+                tracing::info!("Quick-ask hotkey registered: {}", spec);
+                self.hotkey = Some(registered);
+                None
+            }
+            Err(e) => {
+                // Remember the failing spec so it isn't retried every frame
+                self.error = Some(spec);
+                Some(format!("Quick-ask hotkey unavailable: {e}"))
+            }
+        }
+    }
+
+    fn register(&mut self, ctx: &egui::Context, spec: &str) -> Result<RegisteredHotkey> {
+        let hotkey = parse_hotkey(spec)?;
+        let manager = GlobalHotKeyManager::new()?;
+        manager.register(hotkey)?;
+        if !self.handler_installed {
+            // The handler can only be installed once; it serves every later registration
+            let triggered = self.triggered.clone();
+            let ctx = ctx.clone();
+            GlobalHotKeyEvent::set_event_handler(Some(move |event: GlobalHotKeyEvent| {
+                if event.state == HotKeyState::Pressed {
+                    triggered.store(true, Ordering::SeqCst);
+                    ctx.request_repaint();
+                }
+            }));
+            self.handler_installed = true;
+        }
+        Ok(RegisteredHotkey { manager, hotkey, spec: spec.to_string() })
+    }
+
+    pub fn open(&mut self) {
+        self.open = true;
+        self.focus_prompt = true;
+    }
+
+    pub fn is_generating(&self) -> bool {
+        self.stream.is_some()
+    }
+
+    /// Stream the answer to the question last returned by `QuickAskEvent::Ask`.
+    pub fn start_answer(&mut self, stream: mpsc::Receiver<String>) {
+        self.answer.clear();
+        self.stream = Some(stream);
+    }
+
+    /// Draw the popup (if open) and report what happened this frame.
+    pub fn show(&mut self, ctx: &egui::Context) -> Option<QuickAskEvent> {
+        if self.triggered.swap(false, Ordering::SeqCst) {
+            // Pressing the hotkey again while the popup is up dismisses it
+            if self.open && !self.is_generating() {
+                self.open = false;
+            } else {
+                self.open();
+            }
+        }
+
+        let mut event = self.poll_stream();
+        if !self.open {
+            return event;
+        }
+
+        let viewport_id = egui::ViewportId::from_hash_of("ria_quick_ask");
+        if self.focus_prompt {
+            ctx.send_viewport_cmd_to(viewport_id, egui::ViewportCommand::Focus);
+        }
+        let builder = egui::ViewportBuilder::default()
+            .with_title("Ask RIA")
+            .with_inner_size([560.0, 240.0])
+            .with_resizable(false)
+            .with_decorations(false)
+            .with_window_level(egui::WindowLevel::AlwaysOnTop);
+
+        ctx.show_viewport_immediate(viewport_id, builder, |ctx, class| {
+            if class == egui::ViewportClass::Embedded {
+                egui::Window::new("Ask RIA")
+                    .collapsible(false)
+                    .resizable(false)
+                    .anchor(egui::Align2::CENTER_TOP, [0.0, 80.0])
+                    .show(ctx, |ui| {
+                        if let Some(ask) = self.body(ui) {
+                            event = Some(ask);
+                        }
+                    });
+            } else {
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    if let Some(ask) = self.body(ui) {
+                        event = Some(ask);
+                    }
+                });
+            }
+            if ctx.input(|i| i.viewport().close_requested() || i.key_pressed(egui::Key::Escape)) {
+                self.open = false;
+            }
+        });
+        event
+    }
+
+    fn poll_stream(&mut self) -> Option<QuickAskEvent> {
+        let rx = self.stream.as_mut()?;
+        loop {
+            match rx.try_recv() {
+                Ok(chunk) => self.answer.push_str(&chunk),
+                Err(TryRecvError::Empty) => return None,
+                Err(TryRecvError::Disconnected) => {
+                    self.stream = None;
+                    if self.answer.trim().is_empty() {
+                        self.answer = "No answer was generated.".to_string();
+                        return None;
+                    }
+                    return Some(QuickAskEvent::Answered {
+                        question: self.question.clone(),
+                        answer: self.answer.clone(),
+                    });
+                }
+            }
+        }
+    }
+
+    fn body(&mut self, ui: &mut egui::Ui) -> Option<QuickAskEvent> {
+        let mut event = None;
+        let generating = self.is_generating();
+        ui.horizontal(|ui| {
+            ui.label(egui::RichText::new("🤖").size(20.0));
+            let edit = egui::TextEdit::singleline(&mut self.prompt)
+                .hint_text("Ask anything… (Enter to send, Esc to close)")
+                .desired_width(f32::INFINITY)
+                .font(egui::TextStyle::Heading);
+            let response = ui.add_enabled(!generating, edit);
+            if self.focus_prompt {
+                response.request_focus();
+                self.focus_prompt = false;
+            }
+            let submitted = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            if submitted && !self.prompt.trim().is_empty() {
+                self.question = std::mem::take(&mut self.prompt).trim().to_string();
+                event = Some(QuickAskEvent::Ask(self.question.clone()));
+            }
+        });
+
+        if !self.question.is_empty() {
+            ui.separator();
+            ui.label(egui::RichText::new(&self.question).strong());
+            egui::ScrollArea::vertical().max_height(140.0).stick_to_bottom(true).show(ui, |ui| {
+                if self.answer.is_empty() && self.is_generating() {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label("Thinking…");
+                    });
+                } else {
+                    ui.label(&self.answer);
+                }
+            });
+            if !self.is_generating() && !self.answer.is_empty() && ui.small_button("📋 Copy answer").clicked() {
+                ui.output_mut(|o| o.copied_text = self.answer.clone());
+            }
+        }
+        event
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_hotkey_parses() {
+        assert!(parse_hotkey(&QuickAskSettings::default().hotkey).is_ok());
+        assert!(parse_hotkey("Ctrl+Alt+R").is_ok());
+        assert!(parse_hotkey("Ctrl+Nope").is_err());
+    }
+}
@@ -0,0 +1,75 @@
+//! When to draw the next frame.
+//!
+//! egui redraws on input by itself. Everything else — streamed tokens, progress from
+//! background tasks, toasts timing out — has to ask for a frame. Each frame the app reports
+//! what is in motion and the next repaint is scheduled for the most urgent of it, with a slow
+//! poll when nothing is going on (for results on channels that don't wake the UI themselves).
+
+use std::time::Duration;
+
+/// Streaming text and animations.
+const ANIMATION_INTERVAL: Duration = Duration::from_millis(16);
+/// Download, load and install progress.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+/// Nothing moving: background channels and the status bar's system stats.
+const IDLE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Something that needs frames without user input.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Activity {
+    Animating,
+    Progress,
+    /// Changes once this much time has passed, e.g. a toast expiring.
+    After(Duration),
+}
+
+impl Activity {
+    fn delay(self) -> Duration {
+        match self {
+            Self::Animating => ANIMATION_INTERVAL,
+            Self::Progress => PROGRESS_INTERVAL,
+            Self::After(delay) => delay,
+        }
+    }
+}
+
+/// Collects the frame's activities; see the module docs.
+#[derive(Debug, Default)]
+pub struct RepaintScheduler {
+    next: Option<Duration>,
+}
+
+impl RepaintScheduler {
+    pub fn request(&mut self, activity: Activity) {
+        let delay = activity.delay();
+        self.next = Some(self.next.map_or(delay, |next| next.min(delay)));
+    }
+
+    /// Delay before the next frame, starting over for the frame after.
+    pub fn take_delay(&mut self) -> Duration {
+        self.next.take().unwrap_or(IDLE_INTERVAL).min(IDLE_INTERVAL)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_most_urgent_activity_wins_and_idle_polls_slowly() {
+        let mut scheduler = RepaintScheduler::default();
+        assert_eq!(scheduler.take_delay(), IDLE_INTERVAL);
+
+        scheduler.request(Activity::After(Duration::from_secs(3)));
+        assert_eq!(scheduler.take_delay(), IDLE_INTERVAL);
+
+        scheduler.request(Activity::Progress);
+        scheduler.request(Activity::After(Duration::from_millis(50)));
+        assert_eq!(scheduler.take_delay(), Duration::from_millis(50));
+
+        scheduler.request(Activity::Progress);
+        scheduler.request(Activity::Animating);
+        assert_eq!(scheduler.take_delay(), ANIMATION_INTERVAL);
+        assert_eq!(scheduler.take_delay(), IDLE_INTERVAL);
+    }
+}
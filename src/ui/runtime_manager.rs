@@ -0,0 +1,223 @@
+//! Settings section for managed ONNX Runtime builds: download, switch and remove them.
+
+use crate::ai::runtime::{self, InstallProgress, RuntimeReport};
+use crate::config::NetworkSettings;
+use eframe::egui;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+/// Runtime the user picked in the settings section.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RuntimeChoice {
+    System,
+    Managed(String),
+}
+
+/// How a runtime download ended.
+#[derive(Debug, Clone, PartialEq)]
+pub enum InstallOutcome {
+    Installed { version: String, downloaded: u64, elapsed: Duration },
+    Failed { version: String, error: String },
+    Cancelled { version: String },
+}
+
+/// A running runtime download.
+pub struct InstallJob {
+    pub version: String,
+    pub stage: String,
+    pub fraction: f32,
+    started: Instant,
+    cancel: Arc<AtomicBool>,
+    rx: mpsc::UnboundedReceiver<InstallProgress>,
+}
+
+pub struct RuntimeManagerUI {
+    root: PathBuf,
+    network: NetworkSettings,
+    installed: Vec<String>,
+    job: Option<InstallJob>,
+    /// (is_error, message)
+    status: Option<(bool, String)>,
+}
+
+impl RuntimeManagerUI {
+    pub fn new(root: PathBuf, network: NetworkSettings) -> Self {
+        let installed = runtime::installed_versions(&root);
+        Self { root, network, installed, job: None, status: None }
+    }
+
+    /// Apply changed proxy / certificate / timeout settings to later downloads.
+    pub fn set_network(&mut self, network: NetworkSettings) {
+        self.network = network;
+    }
+
+    pub fn root(&self) -> &std::path::Path {
+        &self.root
+    }
+
+    pub fn is_installing(&self) -> bool {
+        self.job.is_some()
+    }
+
+    pub fn job(&self) -> Option<&InstallJob> {
+        self.job.as_ref()
+    }
+
+    pub fn install(&mut self, version: &str) {
+        if self.job.is_some() {
+            self.status = Some((true, "A runtime download is already running".to_string()));
+            return;
+        }
+        let (tx, rx) = mpsc::unbounded_channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+        tokio::spawn(runtime::install(self.root.clone(), version.to_string(), self.network.clone(), cancel.clone(), tx));
+        self.job = Some(InstallJob {
+            version: version.to_string(),
+            stage: "Starting".to_string(),
+            fraction: 0.0,
+            started: Instant::now(),
+            cancel,
+            rx,
+        });
+        self.status = None;
+    }
+
+    /// Ask the running download to stop. It removes its partial files once extraction (which
+    /// runs on a blocking thread) has stopped too, and `poll` then reports the cancellation.
+    pub fn cancel(&mut self) {
+        let Some(job) = self.job.as_mut() else { return };
+        if !job.cancel.swap(true, Ordering::Relaxed) {
+            tracing::info!("Cancelling ONNX Runtime {} download", job.version);
+            job.stage = "Cancelling".to_string();
+        }
+    }
+
+    /// Drain download progress; returns the outcome once the download ends.
+    pub fn poll(&mut self) -> Option<InstallOutcome> {
+        let job = self.job.as_mut()?;
+        let mut outcome = None;
+        while let Ok(evt) = job.rx.try_recv() {
+            match evt {
+                InstallProgress::Stage(..) if job.cancel.load(Ordering::Relaxed) => {}
+                InstallProgress::Stage(stage, fraction) => {
+                    job.stage = stage;
+                    job.fraction = fraction;
+                }
+                InstallProgress::Completed { version, library, downloaded } => {
+                    tracing::info!("Installed ONNX Runtime {} at {}", version, library.display());
+                    self.status = Some((false, format!("ONNX Runtime {version} installed")));
+                    outcome = Some(InstallOutcome::Installed { version, downloaded, elapsed: job.started.elapsed() });
+                }
+                InstallProgress::Failed(error) => {
+                    tracing::warn!("Managed runtime download failed: {}", error);
+                    self.status = Some((true, error.clone()));
+                    outcome = Some(InstallOutcome::Failed { version: job.version.clone(), error });
+                }
+                InstallProgress::Cancelled => {
+                    tracing::info!("Cancelled ONNX Runtime {} download", job.version);
+                    self.status = Some((false, format!("Download of {} cancelled", job.version)));
+                    outcome = Some(InstallOutcome::Cancelled { version: job.version.clone() });
+                }
+            }
+        }
+        if outcome.is_some() {
+            self.job = None;
+            self.installed = runtime::installed_versions(&self.root);
+        }
+        outcome
+    }
+
+    /// Render the section; returns the runtime the user switched to, if any.
+    pub fn render(&mut self, ui: &mut egui::Ui, selected: Option<&str>, detected: &RuntimeReport) -> Option<RuntimeChoice> {
+        let mut choice = None;
+        ui.heading("ONNX Runtime");
+        ui.separator();
+
+        ui.label(format!("In use: {} ({:?})", detected.status_label(), detected.compatibility));
+        if let Some(lib) = &detected.selected {
+            ui.label(egui::RichText::new(lib.path.display().to_string()).small().weak());
+        }
+
+        ui.horizontal(|ui| {
+            ui.label("Runtime:");
+            let current = match selected {
+                Some(version) => format!("Managed {version}"),
+                None => "System".to_string(),
+            };
+            egui::ComboBox::from_id_salt("onnx_runtime_choice").selected_text(current).show_ui(ui, |ui| {
+                if ui.selectable_label(selected.is_none(), "System").clicked() && selected.is_some() {
+                    choice = Some(RuntimeChoice::System);
+                }
+                for version in &self.installed {
+                    let is_selected = selected == Some(version.as_str());
+                    if ui.selectable_label(is_selected, format!("Managed {version}")).clicked() && !is_selected {
+                        choice = Some(RuntimeChoice::Managed(version.clone()));
+                    }
+                }
+            });
+        });
+
+        let offered: Vec<&str> = runtime::MANAGED_VERSIONS
+            .iter()
+            .copied()
+            .filter(|version| self.installed.iter().any(|v| v == version) || runtime::is_downloadable(version))
+            .collect();
+        if !runtime::MANAGED_VERSIONS.iter().any(|version| runtime::is_downloadable(version)) {
+            ui.label(
+                egui::RichText::new("This build can't verify ONNX Runtime downloads for this platform, so none are offered; install ONNX Runtime system-wide instead.")
+                    .small()
+                    .weak(),
+            );
+        }
+        for version in offered {
+            ui.horizontal(|ui| {
+                let recommended = version == runtime::RECOMMENDED_VERSION;
+                ui.label(if recommended { format!("{version} (recommended)") } else { version.to_string() });
+                if self.installed.iter().any(|v| v == version) {
+                    let in_use = selected == Some(version);
+                    if ui
+                        .add_enabled(!in_use, egui::Button::new("Remove").small())
+                        .on_disabled_hover_text("Switch to another runtime first")
+                        .clicked()
+                    {
+                        match runtime::remove_managed(&self.root, version) {
+                            Ok(()) => self.status = Some((false, format!("Removed ONNX Runtime {version}"))),
+                            Err(e) => self.status = Some((true, format!("Failed to remove {version}: {e}"))),
+                        }
+                        self.installed = runtime::installed_versions(&self.root);
+                    }
+                } else if ui
+                    .add_enabled(self.job.is_none(), egui::Button::new("Download").small())
+                    .on_hover_text(runtime::release_url(version).unwrap_or_else(|| "Not available for this platform".into()))
+                    .clicked()
+                {
+                    self.install(version);
+                }
+            });
+        }
+
+        let mut cancel = false;
+        if let Some(job) = &self.job {
+            ui.horizontal(|ui| {
+                ui.add(egui::ProgressBar::new(job.fraction).text(format!("{}: {}", job.version, job.stage)).desired_width(260.0));
+                cancel = ui.small_button("Cancel").clicked();
+            });
+        }
+        if cancel {
+            self.cancel();
+        }
+        if let Some((is_error, message)) = &self.status {
+            let color = if *is_error { ui.visuals().error_fg_color } else { ui.visuals().weak_text_color() };
+            ui.label(egui::RichText::new(message).small().color(color));
+        }
+        ui.label(
+            egui::RichText::new("Managed runtimes are official release builds kept in RIA's own folder; your Python environment is not touched. Switching after a model was loaded needs a restart.")
+                .small()
+                .weak(),
+        );
+        choice
+    }
+}
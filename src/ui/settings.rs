@@ -1,59 +1,429 @@
-use crate::config::AppConfig;
+use crate::ai::sampler::BAN_BIAS;
+use crate::config::personas::{Persona, PersonaLibrary};
+use crate::config::{profiles, AppConfig, ConfigField, ConfigIssue};
 use crate::ui::components::SystemStatusComponent;
+use crate::ui::theme::{self, MessageDensity, Palette};
+use crate::ui::keybindings;
+use crate::ui::quick_ask::{parse_hotkey, QuickAskSettings};
 use eframe::egui;
 
-pub fn render_settings(ui: &mut egui::Ui, config: &mut AppConfig, system_status: &mut SystemStatusComponent) {
-    ui.heading("Application Settings");
+/// Transient state for the profiles section, kept in egui's temp storage.
+#[derive(Clone, Default)]
+struct ProfilesUiState {
+    profiles: Option<Vec<String>>,
+    selected: Option<String>,
+    new_name: String,
+    file_path: String,
+    /// (is_error, message)
+    status: Option<(bool, String)>,
+}
+
+/// Transient state for the persona editor, kept in egui's temp storage.
+#[derive(Clone, Default)]
+struct PersonasUiState {
+    /// Name the draft was saved under; `None` for a new persona.
+    editing: Option<String>,
+    draft: Persona,
+    /// (is_error, message)
+    status: Option<(bool, String)>,
+}
+
+/// Sections of the settings window.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SettingsTab {
+    #[default]
+    General,
+    Inference,
+    Hardware,
+    Appearance,
+    Advanced,
+}
+
+impl SettingsTab {
+    pub const ALL: [SettingsTab; 5] = [Self::General, Self::Inference, Self::Hardware, Self::Appearance, Self::Advanced];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::General => "General",
+            Self::Inference => "Inference",
+            Self::Hardware => "Hardware",
+            Self::Appearance => "Appearance",
+            Self::Advanced => "Advanced",
+        }
+    }
+}
+
+/// What the user asked for with the buttons under the settings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SettingsAction {
+    /// Save the settings as they are now.
+    Apply,
+    /// Go back to the settings as they were last saved.
+    Revert,
+    /// List the chats the retention limits would delete.
+    PreviewPruning,
+    /// Forget the replies kept by the response cache.
+    ClearResponseCache,
+}
+
+/// Edits take effect as they're made; Apply saves them and Revert undoes them. `unsaved`
+/// says whether there is anything to apply or revert. Applying is refused while
+/// [`AppConfig::issues`] has blocking ones. The persona library is saved by its own editor.
+pub fn render_settings(
+    ui: &mut egui::Ui,
+    config: &mut AppConfig,
+    tab: &mut SettingsTab,
+    unsaved: bool,
+    system_status: &mut SystemStatusComponent,
+    personas: &mut PersonaLibrary,
+) -> Option<SettingsAction> {
+    let issues = config.issues();
+    let defaults = AppConfig::default();
+    let mut action = None;
+
+    ui.horizontal(|ui| {
+        for candidate in SettingsTab::ALL {
+            let flagged = issues.iter().any(|i| i.blocks_save() && issue_tab(i.field) == candidate);
+            let label = if flagged { format!("⚠ {}", candidate.label()) } else { candidate.label().to_string() };
+            ui.selectable_value(tab, candidate, label);
+        }
+    });
+    ui.separator();
+
+    egui::ScrollArea::vertical()
+        .max_height(ui.ctx().screen_rect().height() * 0.6)
+        .auto_shrink([false, true])
+        .show(ui, |ui| {
+            ui.add_space(10.0);
+            match *tab {
+                SettingsTab::General => {
+                    if general_tab(ui, config, &defaults) {
+                        action = Some(SettingsAction::PreviewPruning);
+                    }
+                }
+                SettingsTab::Inference => inference_tab(ui, config, &defaults, &issues, personas),
+                SettingsTab::Hardware => {
+                    if hardware_tab(ui, config, &defaults, system_status) {
+                        action = Some(SettingsAction::ClearResponseCache);
+                    }
+                }
+                SettingsTab::Appearance => appearance_tab(ui, config, &defaults),
+                SettingsTab::Advanced => {
+                    if advanced_tab(ui, config, &defaults) {
+                        // A profile or settings file replaced the config; save it like any other change
+                        action = Some(SettingsAction::Apply);
+                    }
+                }
+            }
+        });
+
+    ui.separator();
+    let blocking: Vec<&str> = issues.iter().filter(|i| i.blocks_save()).map(|i| i.message.as_str()).collect();
+    if !blocking.is_empty() {
+        ui.colored_label(Palette::current(ui.ctx()).danger, format!("Fix the highlighted settings to save: {}.", blocking.join("; ")));
+    }
+    ui.horizontal(|ui| {
+        if ui.add_enabled(unsaved && blocking.is_empty(), egui::Button::new("💾 Apply"))
+            .on_hover_text("Save these settings")
+            .on_disabled_hover_text(if blocking.is_empty() { "No unsaved changes" } else { "Some settings are invalid" })
+            .clicked()
+        {
+            action = Some(SettingsAction::Apply);
+        }
+        if ui.add_enabled(unsaved, egui::Button::new("↺ Revert")).on_hover_text("Undo changes since the settings were last saved").clicked() {
+            action = Some(SettingsAction::Revert);
+        }
+        if unsaved {
+            ui.label(egui::RichText::new("Unsaved changes").small().weak());
+        }
+    });
+    action
+}
+
+/// The tab a field is edited on.
+fn issue_tab(field: ConfigField) -> SettingsTab {
+    match field {
+        ConfigField::MaxTokens | ConfigField::Temperature | ConfigField::TopP | ConfigField::ModelPath => SettingsTab::Inference,
+    }
+}
+
+/// Shown next to the sampling controls (max tokens, temperature, top-p/k, logit bias, per-chat
+/// overrides and reply length): only the ONNX provider decodes token by token.
+pub const SAMPLING_SCOPE: &str =
+    "Applied by ONNX models that ship a tokenizer.json, which are decoded token by token. Other providers only record these in each reply's details.";
+
+/// A small button that puts `value` back to `default`, disabled while it already is.
+fn reset_button<T: PartialEq + Clone>(ui: &mut egui::Ui, value: &mut T, default: &T) {
+    if ui.add_enabled(value != default, egui::Button::new("↺").small()).on_hover_text("Reset to default").clicked() {
+        *value = default.clone();
+    }
+}
+
+/// Behaviour: automation, storage, history, tray, snippets, quick ask and shortcuts. Returns
+/// true when the pruning preview was asked for.
+fn general_tab(ui: &mut egui::Ui, config: &mut AppConfig, defaults: &AppConfig) -> bool {
+    ui.heading("Automation");
     ui.separator();
     ui.add_space(10.0);
+    ui.checkbox(&mut config.auto_load_last_model, "Auto-load last used model on startup");
+    ui.checkbox(&mut config.auto_select_latest_model, "If none, auto-select most recent model");
+    ui.checkbox(&mut config.auto_load_new_download, "Auto-load model immediately after download");
+    ui.checkbox(&mut config.auto_fix_onnx_runtime, "Attempt ONNX Runtime auto-fix on version mismatch");
+    ui.checkbox(&mut config.enable_ep_fallback, "Enable execution provider fallback attempts");
 
-    // Theme selection
+    ui.add_space(20.0);
+
+    ui.heading("Storage");
+    ui.separator();
+    ui.add_space(10.0);
     ui.horizontal(|ui| {
-        ui.label("Theme:");
-        egui::ComboBox::from_label("")
-            .selected_text(format!("{:?}", config.theme))
+        use crate::storage::StorageBackendKind;
+        ui.label("Backend:");
+        egui::ComboBox::from_id_salt("storage_backend")
+            .selected_text(config.storage_backend.label())
             .show_ui(ui, |ui| {
-                ui.selectable_value(&mut config.theme, crate::ui::app::Theme::Dark, "Dark");
-                ui.selectable_value(&mut config.theme, crate::ui::app::Theme::Light, "Light");
-                ui.selectable_value(&mut config.theme, crate::ui::app::Theme::System, "System");
+                for kind in [StorageBackendKind::Sqlite, StorageBackendKind::JsonFiles] {
+                    ui.selectable_value(&mut config.storage_backend, kind, kind.label());
+                }
             });
+        reset_button(ui, &mut config.storage_backend, &defaults.storage_backend);
+    });
+    ui.checkbox(&mut config.auto_save, "Save chat sessions automatically");
+    ui.label(egui::RichText::new("Backend changes take effect after restart.").small().weak());
+
+    ui.add_space(20.0);
+
+    ui.heading("Chat History");
+    ui.separator();
+    ui.add_space(10.0);
+    ui.horizontal(|ui| {
+        ui.label("Keep at most");
+        ui.add(egui::DragValue::new(&mut config.max_chat_history).range(0..=100_000).suffix(" chats"));
+        reset_button(ui, &mut config.max_chat_history, &defaults.max_chat_history);
+    });
+    ui.horizontal(|ui| {
+        ui.label("Delete chats unused for");
+        ui.add(egui::DragValue::new(&mut config.retention.max_age_days).range(0..=3650).suffix(" days"));
+        reset_button(ui, &mut config.retention.max_age_days, &defaults.retention.max_age_days);
+    });
+    ui.horizontal(|ui| {
+        ui.label("Limit chat history to");
+        ui.add(egui::DragValue::new(&mut config.retention.max_disk_mb).range(0..=100_000).suffix(" MB"));
+        reset_button(ui, &mut config.retention.max_disk_mb, &defaults.retention.max_disk_mb);
     });
+    let preview = ui.add_enabled_ui(!config.sync.enabled, |ui| {
+        ui.checkbox(&mut config.retention.automatic, "Prune automatically on startup and every hour");
+        ui.label(egui::RichText::new("0 means no limit. Pinned chats are always kept; the oldest chats go first. Pruning only removes chats from this device.").small().weak());
+        ui.button("🔍 Preview pruning").on_hover_text("See which chats these limits would delete").clicked()
+    }).inner;
+    if config.sync.enabled {
+        ui.label(egui::RichText::new("Pruning is off while sync is on: pruned chats would come back with the next sync.").small().weak());
+    }
+
+    ui.add_space(20.0);
+
+    ui.heading("System Tray");
+    ui.separator();
+    ui.add_space(10.0);
+    ui.add_enabled_ui(cfg!(feature = "tray"), |ui| {
+        ui.checkbox(&mut config.tray.enabled, "Show an icon in the system tray");
+        ui.add_enabled_ui(config.tray.enabled, |ui| {
+            ui.checkbox(&mut config.tray.minimize_to_tray, "Closing the window minimizes it to the tray");
+            ui.checkbox(&mut config.tray.notify_when_hidden, "Notify me when a long response or download finishes");
+        });
+    });
+    let note = if cfg!(feature = "tray") {
+        "Takes effect after restart."
+    } else {
+        "This build was compiled without tray support (enable the `tray` feature)."
+    };
+    ui.label(egui::RichText::new(note).small().weak());
+
+    ui.add_space(20.0);
+
+    ui.heading("Prompt Snippets");
+    ui.separator();
+    ui.add_space(10.0);
+    ui.label(egui::RichText::new("Offered under 💡 Quick Prompts. Write {{name}} where a value should be asked for.").small().weak());
+    crate::ui::snippets::render_editor(ui, &mut config.snippets);
+
+    ui.add_space(20.0);
+
+    ui.heading("Quick Ask");
+    ui.separator();
+    ui.add_space(10.0);
+    render_quick_ask(ui, &mut config.quick_ask);
+
+    ui.add_space(20.0);
 
+    ui.heading("Keyboard Shortcuts");
+    ui.separator();
     ui.add_space(10.0);
+    keybindings::render(ui, &mut config.keybindings);
+    preview
+}
 
+/// The model, its sampling and limits, code prompt routing, where models are found and personas.
+fn inference_tab(ui: &mut egui::Ui, config: &mut AppConfig, defaults: &AppConfig, issues: &[ConfigIssue], personas: &mut PersonaLibrary) {
     // AI Settings
     ui.heading("AI Settings");
     ui.separator();
     ui.add_space(10.0);
 
+    // Sliders keep an out-of-range value from the file so it can be shown as an error
+    // instead of being changed behind the user's back
     ui.horizontal(|ui| {
-        ui.label("Model Path:");
+        field_label(ui, "Model Path:", issues, ConfigField::ModelPath);
         ui.text_edit_singleline(&mut config.ai_config.model_path);
         if ui.button("Browse").clicked() {
             // Would open file dialog in a real implementation
         }
     });
+    field_issue(ui, issues, ConfigField::ModelPath);
 
     ui.add_space(10.0);
 
     ui.horizontal(|ui| {
-        ui.label("Max Tokens:");
-        ui.add(egui::Slider::new(&mut config.ai_config.max_tokens, 1..=4096));
+        field_label(ui, "Max Tokens:", issues, ConfigField::MaxTokens);
+        ui.add(egui::Slider::new(&mut config.ai_config.max_tokens, 1..=4096).clamping(egui::SliderClamping::Edits));
+        reset_button(ui, &mut config.ai_config.max_tokens, &defaults.ai_config.max_tokens);
     });
+    field_issue(ui, issues, ConfigField::MaxTokens);
 
     ui.horizontal(|ui| {
-        ui.label("Temperature:");
-        ui.add(egui::Slider::new(&mut config.ai_config.temperature, 0.0..=2.0).step_by(0.1));
+        field_label(ui, "Temperature:", issues, ConfigField::Temperature);
+        ui.add(egui::Slider::new(&mut config.ai_config.temperature, 0.0..=2.0).step_by(0.1).clamping(egui::SliderClamping::Edits));
+        reset_button(ui, &mut config.ai_config.temperature, &defaults.ai_config.temperature);
     });
+    field_issue(ui, issues, ConfigField::Temperature);
 
     ui.horizontal(|ui| {
-        ui.label("Top-p:");
-        ui.add(egui::Slider::new(&mut config.ai_config.top_p, 0.0..=1.0).step_by(0.05));
+        field_label(ui, "Top-p:", issues, ConfigField::TopP);
+        ui.add(egui::Slider::new(&mut config.ai_config.top_p, 0.0..=1.0).step_by(0.05).clamping(egui::SliderClamping::Edits));
+        reset_button(ui, &mut config.ai_config.top_p, &defaults.ai_config.top_p);
+    });
+    field_issue(ui, issues, ConfigField::TopP);
+
+    ui.horizontal(|ui| {
+        ui.label("Top-k:");
+        ui.add(egui::Slider::new(&mut config.ai_config.top_k, 0..=200))
+            .on_hover_text("Sample only from the k most likely tokens. 0 disables.");
+        reset_button(ui, &mut config.ai_config.top_k, &defaults.ai_config.top_k);
     });
 
+    ui.collapsing(format!("Logit bias ({})", config.ai_config.logit_bias.len()), |ui| {
+        render_logit_bias(ui, "settings_logit_bias", &mut config.ai_config.logit_bias);
+    });
+    ui.label(egui::RichText::new(SAMPLING_SCOPE).small().weak());
+
+    ui.horizontal(|ui| {
+        ui.label("Tool result budget (chars):");
+        ui.add(egui::DragValue::new(&mut config.ai_config.tool_result_max_chars).range(0..=100_000).speed(100))
+            .on_hover_text("Larger tool outputs are truncated before re-entering the context. 0 disables.");
+        reset_button(ui, &mut config.ai_config.tool_result_max_chars, &defaults.ai_config.tool_result_max_chars);
+    });
+
+    ui.horizontal(|ui| {
+        let limits = &mut config.ai_config.token_limits;
+        ui.label("Context window:");
+        ui.add(egui::DragValue::new(&mut limits.context_tokens).range(0..=1_000_000).speed(64).suffix(" tokens"))
+            .on_hover_text("Warn when a chat no longer fits the model's context. 0 disables.");
+        ui.label("Slow after:");
+        ui.add(egui::DragValue::new(&mut limits.slow_tokens).range(0..=1_000_000).speed(64).suffix(" tokens"))
+            .on_hover_text("Warn when a chat is long enough for replies to get slow. 0 disables.");
+        reset_button(ui, limits, &defaults.ai_config.token_limits);
+    });
+
+    ui.horizontal(|ui| {
+        ui.label("Simultaneous replies:");
+        ui.add(egui::DragValue::new(&mut config.max_concurrent_generations).range(1..=8))
+            .on_hover_text("How many chats can be generating a reply at once. They take turns on the model and stream as soon as their text is ready.");
+        reset_button(ui, &mut config.max_concurrent_generations, &defaults.max_concurrent_generations);
+    });
+
+    ui.horizontal(|ui| {
+        ui.label("Unload when idle for:");
+        ui.add(egui::DragValue::new(&mut config.idle_unload_minutes).range(0..=24 * 60).suffix(" min"))
+            .on_hover_text("Release the model's memory after this long without replies. It reloads by itself when you send the next message. 0 keeps it loaded.");
+        reset_button(ui, &mut config.idle_unload_minutes, &defaults.idle_unload_minutes);
+    });
+
+    ui.add_space(20.0);
+
+    ui.heading("Code Prompts");
+    ui.separator();
+    ui.add_space(10.0);
+    let routing = &mut config.code_routing;
+    ui.checkbox(&mut routing.enabled, "Detect prompts that are mostly code");
+    ui.add_enabled_ui(routing.enabled, |ui| {
+        ui.horizontal(|ui| {
+            ui.label("Code share:");
+            ui.add(egui::Slider::new(&mut routing.min_code_share, 0.1..=1.0).step_by(0.05))
+                .on_hover_text("How much of the prompt has to look like code");
+            reset_button(ui, &mut routing.min_code_share, &defaults.code_routing.min_code_share);
+        });
+        ui.checkbox(&mut routing.route_to_code_model, "Answer with a loaded code model");
+        ui.horizontal(|ui| {
+            ui.label("System prompt without a code model:");
+            reset_button(ui, &mut routing.system_prompt, &defaults.code_routing.system_prompt);
+        });
+        ui.add(egui::TextEdit::multiline(&mut routing.system_prompt).desired_rows(2).desired_width(f32::INFINITY));
+    });
+    ui.label(egui::RichText::new("A model counts as a code model when its file name says so, e.g. \"codellama\".").small().weak());
+
+    ui.add_space(20.0);
+
+    ui.heading("Model Directories");
+    ui.separator();
     ui.add_space(10.0);
+    let mut remove = None;
+    let mut make_primary = None;
+    let can_remove = config.model_directories.len() > 1;
+    for (i, dir) in config.model_directories.iter().enumerate() {
+        ui.horizontal(|ui| {
+            ui.label(if i == 0 { "⬇" } else { "  " }).on_hover_text("Downloads go to the first directory");
+            ui.code(dir.display().to_string());
+            if !dir.is_dir() {
+                ui.colored_label(Palette::current(ui.ctx()).warning, "not found");
+            }
+            if i > 0 && ui.small_button("⬆").on_hover_text("Use for downloads").clicked() {
+                make_primary = Some(i);
+            }
+            if ui.add_enabled(can_remove, egui::Button::new("✖").small()).on_hover_text("Stop scanning this directory").clicked() {
+                remove = Some(i);
+            }
+        });
+    }
+    if let Some(i) = make_primary {
+        let dir = config.model_directories.remove(i);
+        config.model_directories.insert(0, dir);
+    }
+    if let Some(i) = remove {
+        config.model_directories.remove(i);
+    }
+    ui.horizontal(|ui| {
+        let id = ui.make_persistent_id("new_model_dir");
+        let mut new_dir = ui.data_mut(|d| d.get_temp::<String>(id).unwrap_or_default());
+        ui.text_edit_singleline(&mut new_dir).on_hover_text("Path to a folder containing .onnx models");
+        let candidate = std::path::PathBuf::from(new_dir.trim());
+        let addable = !new_dir.trim().is_empty() && !config.model_directories.contains(&candidate);
+        if ui.add_enabled(addable, egui::Button::new("➕ Add")).clicked() {
+            config.model_directories.push(candidate);
+            new_dir.clear();
+        }
+        ui.data_mut(|d| d.insert_temp(id, new_dir));
+    });
+
+    ui.add_space(20.0);
+
+    render_personas(ui, personas);
+}
 
+/// Execution provider, device and ONNX Runtime options, with the system's status. Returns
+/// true when the response cache should be cleared.
+fn hardware_tab(ui: &mut egui::Ui, config: &mut AppConfig, defaults: &AppConfig, system_status: &mut SystemStatusComponent) -> bool {
     // Execution Provider
+    let previous_ep = config.ai_config.execution_provider.clone();
     ui.horizontal(|ui| {
         ui.label("Execution Provider:");
         egui::ComboBox::from_label("")
@@ -65,8 +435,66 @@ pub fn render_settings(ui: &mut egui::Ui, config: &mut AppConfig, system_status:
                 ui.selectable_value(&mut config.ai_config.execution_provider, crate::ai::ExecutionProvider::CoreML, "CoreML");
                 ui.selectable_value(&mut config.ai_config.execution_provider, crate::ai::ExecutionProvider::OpenVINO, "OpenVINO");
                 ui.selectable_value(&mut config.ai_config.execution_provider, crate::ai::ExecutionProvider::QNN, "QNN (NPU)");
+                ui.selectable_value(&mut config.ai_config.execution_provider, crate::ai::ExecutionProvider::NNAPI, "NNAPI (Android)");
             });
+        reset_button(ui, &mut config.ai_config.execution_provider, &defaults.ai_config.execution_provider);
     });
+    // A device picked for one provider means nothing (or another device) to the next
+    if config.ai_config.execution_provider != previous_ep {
+        config.ai_config.device_id = None;
+        config.ai_config.device_type = None;
+    }
+
+    // Device selection for the chosen execution provider
+    let ep = config.ai_config.execution_provider.clone();
+    let devices: Vec<_> = system_status.compute_devices().iter()
+        .filter(|d| d.execution_provider == ep)
+        .cloned()
+        .collect();
+    if !devices.is_empty() {
+        let is_selected = |d: &crate::utils::system::ComputeDevice, cfg: &crate::ai::InferenceConfig| {
+            d.device_id == cfg.device_id && d.device_type == cfg.device_type
+        };
+        let selected_label = devices.iter()
+            .find(|d| is_selected(d, &config.ai_config))
+            .map(|d| d.label.clone())
+            .unwrap_or_else(|| "Auto".to_string());
+        ui.horizontal(|ui| {
+            ui.label("Device:");
+            egui::ComboBox::from_id_salt("ep_device")
+                .selected_text(selected_label)
+                .show_ui(ui, |ui| {
+                    let auto = config.ai_config.device_id.is_none() && config.ai_config.device_type.is_none();
+                    if ui.selectable_label(auto, "Auto").clicked() {
+                        config.ai_config.device_id = None;
+                        config.ai_config.device_type = None;
+                    }
+                    for device in &devices {
+                        if ui.selectable_label(is_selected(device, &config.ai_config), &device.label).clicked() {
+                            config.ai_config.device_id = device.device_id;
+                            config.ai_config.device_type = device.device_type.clone();
+                        }
+                    }
+                });
+        });
+    }
+
+    let openvino_devices = crate::utils::openvino::devices_if_ready();
+    if openvino_devices.is_none() {
+        // Queried at startup on a background thread; loading OpenVINO can take seconds
+        ui.ctx().request_repaint_after(std::time::Duration::from_millis(250));
+    }
+    if ep == crate::ai::ExecutionProvider::OpenVINO {
+        let text = if openvino_devices.is_none() {
+            "Looking for OpenVINO devices…".to_string()
+        } else if let Some(found) = openvino_devices.filter(|found| !found.is_empty()) {
+            let names: Vec<_> = found.iter().map(|d| d.name.as_str()).collect();
+            format!("OpenVINO devices: {}", names.join(", "))
+        } else {
+            "OpenVINO runtime (openvino_c) not found, so its devices can't be listed.".to_string()
+        };
+        ui.label(egui::RichText::new(text).small().weak());
+    }
 
     ui.add_space(10.0);
 
@@ -74,7 +502,144 @@ pub fn render_settings(ui: &mut egui::Ui, config: &mut AppConfig, system_status:
     ui.checkbox(&mut config.ai_config.use_npu, "Use NPU acceleration");
 
     ui.add_space(6.0);
-    ui.checkbox(&mut config.ai_config.prefer_npu, "Prefer Intel NPU (OpenVINO) if available");
+    ui.checkbox(&mut config.ai_config.prefer_npu, "Prefer NPU (OpenVINO / QNN) if available");
+    if config.ai_config.prefer_npu {
+        let auto = openvino_devices.and_then(crate::utils::openvino::auto_device_string)
+            .unwrap_or_else(|| "AUTO".to_string());
+        ui.horizontal(|ui| {
+            ui.label("OpenVINO device:");
+            ui.add(egui::TextEdit::singleline(&mut config.ai_config.prefer_npu_device_string).hint_text(auto).desired_width(180.0))
+                .on_hover_text("Leave empty to use every discovered device, accelerators first");
+        });
+    }
+
+    ui.add_space(20.0);
+
+    // Power mode (applied on next model load)
+    ui.heading("Power");
+    ui.separator();
+    ui.add_space(10.0);
+    {
+        use crate::ai::power::PowerMode;
+        let power = &mut config.power;
+        ui.horizontal(|ui| {
+            ui.label("Power mode:");
+            egui::ComboBox::from_id_salt("power_mode")
+                .selected_text(power.mode.label())
+                .show_ui(ui, |ui| {
+                    for mode in PowerMode::ALL {
+                        ui.selectable_value(&mut power.mode, mode, mode.label());
+                    }
+                });
+            reset_button(ui, &mut power.mode, &defaults.power.mode);
+        });
+        ui.label(egui::RichText::new(match power.mode {
+            PowerMode::Performance => "Uses every core and no pauses; expect heat and fan noise on long replies.",
+            PowerMode::Balanced => "Uses the settings on this tab as they are.",
+            PowerMode::BatterySaver => "Two threads, short pauses between runs, and the CPU instead of a discrete GPU. NPUs are kept.",
+        }).small().weak());
+        ui.checkbox(&mut power.follow_battery, "Follow the battery")
+            .on_hover_text("On battery, Performance drops to Balanced, and to Battery saver when the charge is low");
+        ui.add_enabled_ui(power.follow_battery, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Battery saver below:");
+                ui.add(egui::DragValue::new(&mut power.battery_saver_below).range(5..=100).suffix("%"));
+                reset_button(ui, &mut power.battery_saver_below, &defaults.power.battery_saver_below);
+            });
+        });
+        if crate::ai::power::battery_status().is_none() {
+            ui.label(egui::RichText::new("No battery status is available on this system.").small().weak());
+        }
+        ui.label(egui::RichText::new("Applies the next time a model loads.").small().weak());
+    }
+
+    ui.add_space(20.0);
+
+    // ONNX Runtime session options (applied on next model load)
+    ui.heading("Runtime");
+    ui.separator();
+    ui.add_space(10.0);
+    {
+        use crate::ai::OptimizationLevel;
+        let opts = &mut config.ai_config.session_options;
+        let default_opts = &defaults.ai_config.session_options;
+        ui.horizontal(|ui| {
+            ui.label("Intra-op threads:");
+            ui.add(egui::DragValue::new(&mut opts.intra_threads).range(0..=num_cpus::get()))
+                .on_hover_text("0 = automatic");
+            reset_button(ui, &mut opts.intra_threads, &default_opts.intra_threads);
+        });
+        ui.checkbox(&mut opts.parallel_execution, "Parallel operator execution");
+        ui.add_enabled_ui(opts.parallel_execution, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Inter-op threads:");
+                ui.add(egui::DragValue::new(&mut opts.inter_threads).range(0..=num_cpus::get()))
+                    .on_hover_text("0 = ONNX Runtime default");
+                reset_button(ui, &mut opts.inter_threads, &default_opts.inter_threads);
+            });
+        });
+        ui.checkbox(&mut opts.cpu_mem_arena, "CPU memory arena");
+        ui.checkbox(&mut opts.memory_pattern, "Memory pattern optimization");
+        ui.horizontal(|ui| {
+            ui.label("Graph optimization:");
+            egui::ComboBox::from_id_salt("graph_opt_level")
+                .selected_text(format!("{:?}", opts.optimization_level))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut opts.optimization_level, OptimizationLevel::Disable, "Disable");
+                    ui.selectable_value(&mut opts.optimization_level, OptimizationLevel::Basic, "Basic");
+                    ui.selectable_value(&mut opts.optimization_level, OptimizationLevel::Extended, "Extended");
+                    ui.selectable_value(&mut opts.optimization_level, OptimizationLevel::All, "All");
+                });
+            reset_button(ui, &mut opts.optimization_level, &default_opts.optimization_level);
+        });
+        ui.label(egui::RichText::new("Runtime options take effect on the next model load.").small().weak());
+    }
+    ui.horizontal(|ui| {
+        ui.label("Prefill chunk size:");
+        ui.add(egui::DragValue::new(&mut config.ai_config.prefill_chunk_size).range(0..=8192).speed(16).suffix(" tokens"))
+            .on_hover_text("Long prompts are fed to models with a KV cache in chunks of this size to bound memory. 0 = whole prompt at once");
+        reset_button(ui, &mut config.ai_config.prefill_chunk_size, &defaults.ai_config.prefill_chunk_size);
+    });
+    ui.horizontal(|ui| {
+        ui.label("Prompt cache:");
+        ui.add(egui::DragValue::new(&mut config.ai_config.prompt_cache_entries).range(0..=16).suffix(" prompts"))
+            .on_hover_text("Keep the KV cache of recent prompts so the next message only prefills what's new. Each entry holds the model's cache for a whole chat. 0 disables");
+        reset_button(ui, &mut config.ai_config.prompt_cache_entries, &defaults.ai_config.prompt_cache_entries);
+    });
+    ui.label(egui::RichText::new("The prompt cache size takes effect on the next model load.").small().weak());
+    let mut clear_response_cache = false;
+    ui.horizontal(|ui| {
+        ui.checkbox(&mut config.ai_config.response_cache, "Reuse replies to identical requests")
+            .on_hover_text("Asking exactly the same thing with the same settings answers at once with the earlier reply instead of running the model again");
+        clear_response_cache = ui.add_enabled(config.ai_config.response_cache, egui::Button::new("🗑 Clear").small())
+            .on_hover_text("Forget the cached replies")
+            .clicked();
+    });
+
+    ui.add_space(20.0);
+
+    // System Status and Memory Monitoring
+    system_status.render(ui);
+    clear_response_cache
+}
+
+/// Theme, look, fonts, animations and how replies stream in.
+fn appearance_tab(ui: &mut egui::Ui, config: &mut AppConfig, defaults: &AppConfig) {
+    // Theme selection
+    ui.horizontal(|ui| {
+        ui.label("Theme:");
+        egui::ComboBox::from_label("")
+            .selected_text(format!("{:?}", config.theme))
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut config.theme, crate::ui::app::Theme::Dark, "Dark");
+                ui.selectable_value(&mut config.theme, crate::ui::app::Theme::Light, "Light");
+                ui.selectable_value(&mut config.theme, crate::ui::app::Theme::System, "System");
+            });
+        reset_button(ui, &mut config.theme, &defaults.theme);
+    });
+
+    render_appearance(ui, config);
+    render_fonts(ui, config);
 
     ui.add_space(20.0);
 
@@ -96,6 +661,7 @@ pub fn render_settings(ui: &mut egui::Ui, config: &mut AppConfig, system_status:
                 ui.selectable_value(&mut config.animation_quality, 1, "Medium");
                 ui.selectable_value(&mut config.animation_quality, 2, "High");
             });
+        reset_button(ui, &mut config.animation_quality, &defaults.animation_quality);
     });
 
     ui.checkbox(&mut config.enable_animations, "Enable animations");
@@ -103,25 +669,601 @@ pub fn render_settings(ui: &mut egui::Ui, config: &mut AppConfig, system_status:
 
     ui.add_space(20.0);
 
-    // System Status and Memory Monitoring
-    system_status.render(ui);
+    ui.heading("Streaming");
+    ui.separator();
+    ui.add_space(10.0);
+    let streaming = &mut config.streaming;
+    ui.horizontal(|ui| {
+        let mut instant = streaming.chars_per_second == 0;
+        if ui.checkbox(&mut instant, "Show text as it arrives").on_hover_text("Off paces bursts of text into a steady flow").changed() {
+            streaming.chars_per_second = if instant { 0 } else { defaults.streaming.chars_per_second };
+        }
+    });
+    ui.add_enabled_ui(streaming.chars_per_second > 0, |ui| {
+        ui.horizontal(|ui| {
+            ui.label("Pace:");
+            ui.add(egui::Slider::new(&mut streaming.chars_per_second, 10..=2000).logarithmic(true).suffix(" chars/s"))
+                .on_hover_text("Speeds up on its own when the model is faster, so replies never lag by more than a second");
+            reset_button(ui, &mut streaming.chars_per_second, &defaults.streaming.chars_per_second);
+        });
+    });
+    ui.checkbox(&mut streaming.typing_indicator, "Typing indicator while waiting for the first words");
+}
+
+/// Network, catalog, sync, developer options and profiles. Returns true when a profile or
+/// settings file replaced the config.
+fn advanced_tab(ui: &mut egui::Ui, config: &mut AppConfig, defaults: &AppConfig) -> bool {
+    ui.heading("Network");
+    ui.separator();
+    ui.add_space(10.0);
+    let network = &mut config.network;
+    egui::Grid::new("network_settings").num_columns(2).spacing([8.0, 6.0]).show(ui, |ui| {
+        ui.label("Proxy:");
+        ui.add(egui::TextEdit::singleline(&mut network.proxy).hint_text("system default"))
+            .on_hover_text("http://host:port, socks5://host:port or socks5h://host:port; user:password@ if required");
+        ui.end_row();
+        ui.label("No proxy for:");
+        ui.add(egui::TextEdit::singleline(&mut network.no_proxy).hint_text("localhost, .corp.example"));
+        ui.end_row();
+        ui.label("CA certificate:");
+        let mut ca = network.ca_certificate.as_ref().map(|p| p.display().to_string()).unwrap_or_default();
+        if ui.add(egui::TextEdit::singleline(&mut ca).hint_text("path to a .pem file"))
+            .on_hover_text("Extra trusted root certificates, e.g. for a TLS-inspecting proxy")
+            .changed()
+        {
+            network.ca_certificate = (!ca.trim().is_empty()).then(|| std::path::PathBuf::from(ca.trim()));
+        }
+        ui.end_row();
+        ui.label("Timeouts:");
+        ui.horizontal(|ui| {
+            ui.add(egui::DragValue::new(&mut network.connect_timeout_secs).range(1..=600).suffix(" s connect"));
+            ui.add(egui::DragValue::new(&mut network.read_timeout_secs).range(1..=3600).suffix(" s without data"));
+            if ui.add_enabled(
+                (network.connect_timeout_secs, network.read_timeout_secs) != (defaults.network.connect_timeout_secs, defaults.network.read_timeout_secs),
+                egui::Button::new("↺").small(),
+            ).on_hover_text("Reset to default").clicked() {
+                network.connect_timeout_secs = defaults.network.connect_timeout_secs;
+                network.read_timeout_secs = defaults.network.read_timeout_secs;
+            }
+        });
+        ui.end_row();
+    });
+    if let Some(problem) = network.problem() {
+        ui.colored_label(Palette::current(ui.ctx()).warning, problem);
+    }
+    ui.label(egui::RichText::new("Used for model and runtime downloads and for sync.").small().weak());
+    ui.add_space(10.0);
+    render_huggingface_token(ui);
 
     ui.add_space(20.0);
 
-    ui.heading("Automation");
+    ui.heading("Model Catalog");
     ui.separator();
     ui.add_space(10.0);
-    ui.checkbox(&mut config.auto_load_last_model, "Auto-load last used model on startup");
-    ui.checkbox(&mut config.auto_select_latest_model, "If none, auto-select most recent model");
-    ui.checkbox(&mut config.auto_load_new_download, "Auto-load model immediately after download");
-    ui.checkbox(&mut config.auto_fix_onnx_runtime, "Attempt ONNX Runtime auto-fix on version mismatch");
-    ui.checkbox(&mut config.enable_ep_fallback, "Enable execution provider fallback attempts");
+    let catalog = &mut config.catalog;
+    egui::Grid::new("catalog_settings").num_columns(2).spacing([8.0, 6.0]).show(ui, |ui| {
+        ui.label("Catalog URL:");
+        ui.add(egui::TextEdit::singleline(&mut catalog.url).hint_text("bundled catalog"))
+            .on_hover_text("JSON list of models; its signature is read from the same URL plus .sig");
+        ui.end_row();
+        ui.label("Public key:");
+        ui.add(egui::TextEdit::singleline(&mut catalog.public_key).hint_text("ed25519, base64 or hex"))
+            .on_hover_text("The catalog publisher's signing key. Catalogs that don't verify against it are rejected.");
+        ui.end_row();
+        ui.label("Refresh every:");
+        ui.horizontal(|ui| {
+            ui.add(egui::DragValue::new(&mut catalog.refresh_hours).range(1..=720).suffix(" h"));
+            reset_button(ui, &mut catalog.refresh_hours, &defaults.catalog.refresh_hours);
+        });
+        ui.end_row();
+    });
+    if catalog.is_remote() {
+        if let Err(e) = crate::ai::catalog::parse_public_key(&catalog.public_key) {
+            ui.colored_label(Palette::current(ui.ctx()).warning, format!("{e:#}. Without a valid key the bundled catalog is used."));
+        }
+    }
+
+    ui.add_space(20.0);
+
+    ui.heading("Developer");
+    ui.separator();
+    ui.add_space(10.0);
+    ui.checkbox(&mut config.debug_inspector, "Message inspector")
+        .on_hover_text("Store the exact prompt, token ids, sampler settings and execution provider with each reply");
+    ui.label(egui::RichText::new("Adds an \"Inspect\" drawer under assistant messages. Traces are saved with the chat.").small().weak());
+    ui.checkbox(&mut config.error_analytics, "Count errors on this machine")
+        .on_hover_text("Keep a local tally of model load, execution provider and download errors");
+    ui.label(egui::RichText::new("Diagnostics then lists the most common issues with suggested fixes. The tally stays on this computer and is never sent anywhere.").small().weak());
 
     ui.add_space(20.0);
 
-    if ui.button("Save Settings").clicked() {
-        if let Err(e) = config.save() {
-            tracing::error!("Failed to save settings: {}", e);
+    ui.heading("Sync");
+    ui.separator();
+    ui.add_space(10.0);
+    ui.checkbox(&mut config.sync.enabled, "Enable end-to-end encrypted sync");
+    ui.add_enabled_ui(config.sync.enabled, |ui| {
+        use crate::sync::SyncTarget;
+        let sync = &mut config.sync;
+        ui.horizontal(|ui| {
+            ui.label("Target:");
+            ui.selectable_value(&mut sync.target, SyncTarget::WebDav, "WebDAV");
+            ui.selectable_value(&mut sync.target, SyncTarget::S3, "S3-compatible");
+        });
+        ui.horizontal(|ui| {
+            ui.label(if sync.target == SyncTarget::WebDav { "Folder URL:" } else { "Endpoint:" });
+            ui.text_edit_singleline(&mut sync.endpoint);
+        });
+        if sync.target == SyncTarget::S3 {
+            ui.horizontal(|ui| {
+                ui.label("Bucket:");
+                ui.text_edit_singleline(&mut sync.bucket);
+                ui.label("Region:");
+                ui.text_edit_singleline(&mut sync.region);
+            });
+        }
+        ui.horizontal(|ui| {
+            ui.label(if sync.target == SyncTarget::WebDav { "Username:" } else { "Access key:" });
+            ui.text_edit_singleline(&mut sync.username);
+        });
+        render_sync_secret(ui, sync);
+        ui.horizontal(|ui| {
+            ui.label("Encryption passphrase:");
+            ui.add(egui::TextEdit::singleline(&mut sync.passphrase).password(true));
+        });
+        ui.label(egui::RichText::new("The passphrase is never saved; enter it once per launch. Data is encrypted before upload.").small().weak());
+    });
+
+    ui.add_space(20.0);
+
+    render_profiles(ui, config)
+}
+
+/// A field's label, highlighted while it has an issue.
+fn field_label(ui: &mut egui::Ui, text: &str, issues: &[ConfigIssue], field: ConfigField) {
+    match issues.iter().find(|i| i.field == field) {
+        Some(issue) => {
+            ui.colored_label(issue_color(ui, issue), format!("⚠ {text}")).on_hover_text(&issue.message);
+        }
+        None => {
+            ui.label(text);
+        }
+    }
+}
+
+/// The field's issue under it, if it has one.
+fn field_issue(ui: &mut egui::Ui, issues: &[ConfigIssue], field: ConfigField) {
+    if let Some(issue) = issues.iter().find(|i| i.field == field) {
+        ui.label(egui::RichText::new(&issue.message).small().color(issue_color(ui, issue)));
+    }
+}
+
+fn issue_color(ui: &egui::Ui, issue: &ConfigIssue) -> egui::Color32 {
+    let palette = Palette::current(ui.ctx());
+    if issue.blocks_save() { palette.danger } else { palette.warning }
+}
+
+/// Transient state for the Hugging Face token field, kept in egui's temp storage.
+#[derive(Clone, Default)]
+struct TokenUiState {
+    input: String,
+    /// (is_error, message)
+    status: Option<(bool, String)>,
+}
+
+/// Editor for a word → bias table: boost (positive), discourage (negative) or ban words.
+/// Shared by Settings and the per-chat sampling popover.
+pub fn render_logit_bias(ui: &mut egui::Ui, id_salt: &str, bias: &mut std::collections::BTreeMap<String, f32>) {
+    let palette = Palette::current(ui.ctx());
+    let mut remove = None;
+    egui::Grid::new(id_salt).num_columns(3).show(ui, |ui| {
+        for (word, value) in bias.iter_mut() {
+            ui.monospace(word);
+            let banned = *value <= BAN_BIAS;
+            ui.add(egui::Slider::new(value, BAN_BIAS..=10.0).step_by(0.5).text(if banned { "banned" } else { "" }));
+            if ui.small_button("✕").on_hover_text("Remove").clicked() {
+                remove = Some(word.clone());
+            }
+            ui.end_row();
+        }
+    });
+    if let Some(word) = remove {
+        bias.remove(&word);
+    }
+
+    let id = ui.make_persistent_id((id_salt, "new_word"));
+    let mut new_word = ui.data_mut(|d| d.get_temp::<String>(id).unwrap_or_default());
+    ui.horizontal(|ui| {
+        ui.add(egui::TextEdit::singleline(&mut new_word).desired_width(120.0).hint_text("word or token"));
+        let word = new_word.trim().to_string();
+        if ui.add_enabled(!word.is_empty(), egui::Button::new("➕ Boost")).clicked() {
+            bias.insert(word.clone(), 2.0);
+            new_word.clear();
+        }
+        if ui.add_enabled(!word.is_empty(), egui::Button::new("🚫 Ban")).clicked() {
+            bias.insert(word, BAN_BIAS);
+            new_word.clear();
+        }
+    });
+    ui.data_mut(|d| d.insert_temp(id, new_word));
+    ui.label(
+        egui::RichText::new(format!("Matches whole tokens, with or without a leading space. {BAN_BIAS} bans the token."))
+            .small()
+            .color(palette.muted_text),
+    );
+}
+
+/// The token lives in the OS credential store, not in `AppConfig`, so it is saved here
+/// directly rather than with the rest of the settings.
+fn render_huggingface_token(ui: &mut egui::Ui) {
+    use crate::utils::credentials::{self, Credential};
+
+    let id = ui.make_persistent_id("settings_hf_token");
+    let mut state = ui.data_mut(|d| d.get_temp::<TokenUiState>(id).unwrap_or_default());
+    ui.horizontal(|ui| {
+        ui.label("Hugging Face token:");
+        let saved = credentials::cached(Credential::HuggingFaceToken);
+        if saved.is_none() {
+            // Read at startup on a background thread; the store can be slow to answer
+            ui.label(egui::RichText::new("checking the system credential store…").weak());
+            ui.ctx().request_repaint_after(std::time::Duration::from_millis(250));
+        } else if saved.flatten().is_some() {
+            ui.label("saved in the system credential store");
+            if ui.button("Clear").clicked() {
+                state.status = Some(match credentials::clear(Credential::HuggingFaceToken) {
+                    Ok(()) => (false, "Token removed".to_string()),
+                    Err(e) => (true, format!("{e:#}")),
+                });
+            }
+        } else {
+            ui.add(egui::TextEdit::singleline(&mut state.input).password(true).hint_text("hf_…").desired_width(220.0))
+                .on_hover_text("Access token for gated and private repositories, from huggingface.co/settings/tokens");
+            if ui.add_enabled(!state.input.trim().is_empty(), egui::Button::new("Save")).clicked() {
+                state.status = Some(match credentials::set(Credential::HuggingFaceToken, &state.input) {
+                    Ok(()) => (false, "Token saved".to_string()),
+                    Err(e) => (true, format!("{e:#}")),
+                });
+                state.input.clear();
+            }
+        }
+    });
+    if std::env::var_os("HF_TOKEN").is_some() {
+        ui.label(egui::RichText::new("HF_TOKEN is set in the environment and takes precedence.").small().weak());
+    }
+    if let Some((is_error, message)) = &state.status {
+        let palette = Palette::current(ui.ctx());
+        ui.colored_label(if *is_error { palette.warning } else { palette.success }, message);
+    }
+    ui.data_mut(|d| d.insert_temp(id, state));
+}
+
+/// Like the Hugging Face token, the sync password is saved to the credential store as soon as
+/// it's entered; `secret_saved` records that it is there.
+fn render_sync_secret(ui: &mut egui::Ui, sync: &mut crate::sync::SyncSettings) {
+    use crate::utils::credentials::{self, Credential};
+
+    let id = ui.make_persistent_id("settings_sync_secret");
+    let mut state = ui.data_mut(|d| d.get_temp::<TokenUiState>(id).unwrap_or_default());
+    ui.horizontal(|ui| {
+        ui.label(if sync.target == crate::sync::SyncTarget::WebDav { "Password:" } else { "Secret key:" });
+        if sync.secret_saved {
+            ui.label("saved in the system credential store");
+            if ui.button("Clear").clicked() {
+                state.status = Some(match credentials::clear(Credential::SyncSecret) {
+                    Ok(()) => {
+                        sync.secret_saved = false;
+                        (false, "Password removed".to_string())
+                    }
+                    Err(e) => (true, format!("{e:#}")),
+                });
+            }
+        } else {
+            ui.add(egui::TextEdit::singleline(&mut state.input).password(true).desired_width(220.0));
+            if ui.add_enabled(!state.input.trim().is_empty(), egui::Button::new("Save")).clicked() {
+                state.status = Some(match credentials::set(Credential::SyncSecret, &state.input) {
+                    Ok(()) => {
+                        sync.secret_saved = true;
+                        sync.secret.clear();
+                        (false, "Password saved".to_string())
+                    }
+                    Err(e) => (true, format!("{e:#}")),
+                });
+                state.input.clear();
+            }
+        }
+    });
+    if let Some((is_error, message)) = &state.status {
+        let palette = Palette::current(ui.ctx());
+        ui.colored_label(if *is_error { palette.warning } else { palette.success }, message);
+    }
+    ui.data_mut(|d| d.insert_temp(id, state));
+}
+
+fn render_personas(ui: &mut egui::Ui, library: &mut PersonaLibrary) {
+    ui.heading("Personas");
+    ui.separator();
+    ui.label(egui::RichText::new("A system prompt with its own sampling and model, picked with 🎭 when starting a chat.").small().weak());
+    ui.add_space(10.0);
+
+    let id = ui.make_persistent_id("settings_personas");
+    let mut state = ui.data_mut(|d| d.get_temp::<PersonasUiState>(id).unwrap_or_default());
+    let mut result: Option<anyhow::Result<String>> = None;
+
+    egui::ComboBox::from_id_salt("settings_persona_select")
+        .selected_text(state.editing.clone().unwrap_or_else(|| "New persona…".to_string()))
+        .show_ui(ui, |ui| {
+            if ui.selectable_label(state.editing.is_none(), "New persona…").clicked() {
+                state.editing = None;
+                state.draft = Persona::default();
+            }
+            for persona in &library.personas {
+                if ui.selectable_label(state.editing.as_deref() == Some(persona.name.as_str()), &persona.name).clicked() {
+                    state.editing = Some(persona.name.clone());
+                    state.draft = persona.clone();
+                }
+            }
+        });
+
+    let draft = &mut state.draft;
+    egui::Grid::new("persona_editor").num_columns(2).show(ui, |ui| {
+        ui.label("Name:");
+        ui.text_edit_singleline(&mut draft.name);
+        ui.end_row();
+        ui.label("System prompt:");
+        ui.add(egui::TextEdit::multiline(&mut draft.system_prompt).desired_rows(3));
+        ui.end_row();
+        ui.label("Temperature:");
+        optional_value(ui, &mut draft.temperature, 0.7, |ui, v| ui.add(egui::Slider::new(v, 0.0..=2.0).step_by(0.05)));
+        ui.end_row();
+        ui.label("Top-p:");
+        optional_value(ui, &mut draft.top_p, 0.9, |ui, v| ui.add(egui::Slider::new(v, 0.0..=1.0).step_by(0.05)));
+        ui.end_row();
+        ui.label("Max tokens:");
+        optional_value(ui, &mut draft.max_tokens, 512, |ui, v| ui.add(egui::DragValue::new(v).range(1..=32768)));
+        ui.end_row();
+        ui.label("Model:");
+        let mut model = draft.preferred_model.clone().unwrap_or_default();
+        if ui.add(egui::TextEdit::singleline(&mut model).hint_text("Any (keep the loaded model)")).changed() {
+            draft.preferred_model = Some(model).filter(|m| !m.trim().is_empty());
+        }
+        ui.end_row();
+    });
+    ui.label(egui::RichText::new(SAMPLING_SCOPE).small().weak());
+
+    ui.horizontal(|ui| {
+        if ui.add_enabled(!state.draft.name.trim().is_empty(), egui::Button::new("💾 Save persona")).clicked() {
+            let name = state.draft.name.trim().to_string();
+            result = Some(library.upsert(state.editing.as_deref(), state.draft.clone()).and_then(|_| {
+                library.save(&AppConfig::personas_path())?;
+                state.editing = Some(name.clone());
+                Ok(format!("Saved persona '{name}'"))
+            }));
+        }
+        if let Some(name) = state.editing.clone() {
+            if ui.button("🗑 Delete").clicked() {
+                library.remove(&name);
+                result = Some(library.save(&AppConfig::personas_path()).map(|_| format!("Deleted persona '{name}'")));
+                state.editing = None;
+                state.draft = Persona::default();
+            }
+        }
+    });
+
+    if let Some(result) = result {
+        state.status = Some(match result {
+            Ok(msg) => (false, msg),
+            Err(e) => (true, e.to_string()),
+        });
+    }
+    if let Some((is_error, msg)) = &state.status {
+        let palette = Palette::current(ui.ctx());
+        let color = if *is_error { palette.danger } else { palette.success };
+        ui.colored_label(color, msg);
+    }
+    ui.data_mut(|d| d.insert_temp(id, state));
+}
+
+/// A checkbox that turns a setting on with `default`, followed by its editor while on.
+fn optional_value<T: Copy>(ui: &mut egui::Ui, value: &mut Option<T>, default: T, edit: impl FnOnce(&mut egui::Ui, &mut T) -> egui::Response) {
+    ui.horizontal(|ui| {
+        let mut enabled = value.is_some();
+        if ui.checkbox(&mut enabled, "").on_hover_text("Off uses the chat's settings").changed() {
+            *value = enabled.then_some(default);
+        }
+        if let Some(v) = value {
+            edit(ui, v);
+        }
+    });
+}
+
+/// Returns true when a profile or settings file replaced the config.
+fn render_profiles(ui: &mut egui::Ui, config: &mut AppConfig) -> bool {
+    ui.heading("Profiles");
+    ui.separator();
+    ui.add_space(10.0);
+
+    let id = ui.make_persistent_id("settings_profiles");
+    let mut state = ui.data_mut(|d| d.get_temp::<ProfilesUiState>(id).unwrap_or_default());
+    let profiles = state.profiles.get_or_insert_with(profiles::list_profiles).clone();
+    let mut result: Option<anyhow::Result<String>> = None;
+    let mut changed_list = false;
+    let mut replaced = false;
+
+    if let Some(active) = &config.active_profile {
+        ui.label(format!("Active profile: {active}"));
+    }
+    ui.horizontal(|ui| {
+        egui::ComboBox::from_id_salt("settings_profile_select")
+            .selected_text(state.selected.clone().unwrap_or_else(|| "Select profile…".to_string()))
+            .show_ui(ui, |ui| {
+                for name in &profiles {
+                    ui.selectable_value(&mut state.selected, Some(name.clone()), name);
+                }
+            });
+        let selected = state.selected.clone();
+        if ui.add_enabled(selected.is_some(), egui::Button::new("Apply")).clicked() {
+            if let Some(name) = &selected {
+                result = Some(profiles::load_profile(name).and_then(|profile| {
+                    config.apply_profile(&profile.config)?;
+                    config.active_profile = Some(profile.name.clone());
+                    replaced = true;
+                    Ok(format!("Applied profile '{}'", profile.name))
+                }));
+            }
+        }
+        if ui.add_enabled(selected.is_some(), egui::Button::new("🗑 Delete")).clicked() {
+            if let Some(name) = &selected {
+                result = Some(profiles::delete_profile(name).map(|_| format!("Deleted profile '{name}'")));
+                if config.active_profile.as_deref() == Some(name.as_str()) {
+                    config.active_profile = None;
+                }
+                state.selected = None;
+                changed_list = true;
+            }
         }
+    });
+    ui.horizontal(|ui| {
+        ui.add(egui::TextEdit::singleline(&mut state.new_name).hint_text("e.g. NPU low-power"));
+        if ui.add_enabled(!state.new_name.trim().is_empty(), egui::Button::new("💾 Save current as profile")).clicked() {
+            let name = state.new_name.trim().to_string();
+            result = Some(profiles::save_profile(&name, config).map(|_| {
+                config.active_profile = Some(name.clone());
+                format!("Saved profile '{name}'")
+            }));
+            state.selected = Some(name);
+            state.new_name.clear();
+            changed_list = true;
+        }
+    });
+
+    ui.add_space(6.0);
+    ui.horizontal(|ui| {
+        ui.label("Settings file:");
+        ui.add(egui::TextEdit::singleline(&mut state.file_path).hint_text("/path/to/ria-settings.json"));
+    });
+    ui.horizontal(|ui| {
+        let has_path = !state.file_path.trim().is_empty();
+        let path = std::path::PathBuf::from(state.file_path.trim());
+        if ui.add_enabled(has_path, egui::Button::new("📤 Export settings")).clicked() {
+            result = Some(config.export_to(&path).map(|_| format!("Exported settings to {}", path.display())));
+        }
+        if ui.add_enabled(has_path, egui::Button::new("📥 Import settings")).clicked() {
+            result = Some(config.import_from(&path).map(|_| {
+                replaced = true;
+                format!("Imported settings from {}", path.display())
+            }));
+        }
+    });
+    ui.label(egui::RichText::new("Profiles and exported settings leave out what belongs to this machine: window layout, model folders, fonts, the ONNX Runtime choice and sync settings.").small().weak());
+
+    if let Some(result) = result {
+        state.status = Some(match result {
+            Ok(msg) => (false, msg),
+            Err(e) => (true, e.to_string()),
+        });
+    }
+    if let Some((is_error, msg)) = &state.status {
+        let palette = Palette::current(ui.ctx());
+        let color = if *is_error { palette.danger } else { palette.success };
+        ui.colored_label(color, msg);
+    }
+    if changed_list {
+        state.profiles = None;
     }
-}
\ No newline at end of file
+    ui.data_mut(|d| d.insert_temp(id, state));
+    replaced
+}
+
+fn render_appearance(ui: &mut egui::Ui, config: &mut AppConfig) {
+    let appearance = &mut config.appearance;
+
+    ui.horizontal(|ui| {
+        ui.label("Accent color:");
+        let mut custom = appearance.accent_color.is_some();
+        if ui.checkbox(&mut custom, "Custom").changed() {
+            appearance.accent_color = if custom {
+                let accent = Palette::current(ui.ctx()).accent;
+                Some([accent.r(), accent.g(), accent.b()])
+            } else {
+                None
+            };
+        }
+        if let Some(rgb) = appearance.accent_color.as_mut() {
+            ui.color_edit_button_srgb(rgb);
+        }
+    });
+
+    ui.horizontal(|ui| {
+        ui.label("Font size:");
+        ui.add(egui::Slider::new(&mut appearance.font_size, theme::FONT_SIZE_RANGE).step_by(0.5).suffix(" pt"));
+        if ui.small_button("Reset").clicked() {
+            appearance.font_size = theme::DEFAULT_FONT_SIZE;
+        }
+    });
+
+    ui.horizontal(|ui| {
+        ui.label("Message density:");
+        ui.selectable_value(&mut appearance.density, MessageDensity::Comfortable, "Comfortable");
+        ui.selectable_value(&mut appearance.density, MessageDensity::Compact, "Compact");
+    });
+
+    ui.checkbox(&mut appearance.touch, "Touch-friendly controls")
+        .on_hover_text("Larger buttons and spacing for tablets and touch screens");
+}
+
+fn render_fonts(ui: &mut egui::Ui, config: &mut AppConfig) {
+    let fonts = &mut config.fonts;
+    ui.label("Fonts:");
+    let mut remove = None;
+    for (i, path) in fonts.custom_fonts.iter().enumerate() {
+        ui.horizontal(|ui| {
+            ui.label(if i == 0 { "Main" } else { "Fallback" });
+            ui.code(path.display().to_string());
+            if !path.is_file() {
+                ui.colored_label(Palette::current(ui.ctx()).warning, "not found");
+            }
+            if ui.small_button("✖").clicked() {
+                remove = Some(i);
+            }
+        });
+    }
+    if let Some(i) = remove {
+        fonts.custom_fonts.remove(i);
+    }
+    ui.horizontal(|ui| {
+        let id = ui.make_persistent_id("new_font_path");
+        let mut new_font = ui.data_mut(|d| d.get_temp::<String>(id).unwrap_or_default());
+        ui.text_edit_singleline(&mut new_font).on_hover_text("Path to a .ttf, .otf or .ttc file");
+        let candidate = std::path::PathBuf::from(new_font.trim());
+        let addable = !new_font.trim().is_empty() && !fonts.custom_fonts.contains(&candidate);
+        if ui.add_enabled(addable, egui::Button::new("➕ Add font")).clicked() {
+            fonts.custom_fonts.push(candidate);
+            new_font.clear();
+        }
+        ui.data_mut(|d| d.insert_temp(id, new_font));
+    });
+    ui.checkbox(&mut fonts.cjk_fallback, "Use an installed font for Chinese, Japanese and Korean text");
+    ui.checkbox(&mut fonts.system_emoji, "Use an installed symbol font for missing emoji");
+}
+
+fn render_quick_ask(ui: &mut egui::Ui, quick_ask: &mut QuickAskSettings) {
+    ui.checkbox(&mut quick_ask.enabled, "Summon a quick-ask popup with a global hotkey");
+    ui.add_enabled_ui(quick_ask.enabled, |ui| {
+        ui.horizontal(|ui| {
+            ui.label("Hotkey:");
+            // Edited in a buffer so half-typed shortcuts are never registered
+            let id = ui.make_persistent_id("quick_ask_hotkey");
+            let mut hotkey = ui.data_mut(|d| d.get_temp::<String>(id)).unwrap_or_else(|| quick_ask.hotkey.clone());
+            ui.text_edit_singleline(&mut hotkey).on_hover_text("e.g. Ctrl+Shift+Space or CmdOrCtrl+Alt+K");
+            let parsed = parse_hotkey(&hotkey);
+            let changed = hotkey.trim() != quick_ask.hotkey;
+            if ui.add_enabled(changed && parsed.is_ok(), egui::Button::new("Apply")).clicked() {
+                quick_ask.hotkey = hotkey.trim().to_string();
+            }
+            if let Err(e) = parsed {
+                ui.colored_label(Palette::current(ui.ctx()).danger, e.to_string());
+            }
+            ui.data_mut(|d| d.insert_temp(id, hotkey));
+        });
+        ui.checkbox(&mut quick_ask.append_to_scratch, "Append answers to the \"Quick Ask\" chat");
+    });
+}
@@ -0,0 +1,127 @@
+//! Slash commands typed into the message input.
+//!
+//! A message starting with `/` is an instruction to the app rather than to the model: it is
+//! parsed when sent and run as an action instead of being added to the chat. Starting a
+//! message with `//` sends it with a single leading `/`.
+
+use crate::ui::export::ExportFormat;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SlashCommand {
+    New,
+    Model(String),
+    Temperature(f32),
+    /// Empty clears the chat's system prompt.
+    System(String),
+    Export(ExportFormat),
+    /// Summarize the chat; `replace` sends the summary instead of the messages it covers.
+    Summarize { replace: bool },
+    /// Add a text file's contents to the chat as a tool result.
+    File(String),
+}
+
+pub struct CommandSpec {
+    pub name: &'static str,
+    pub args: &'static str,
+    pub help: &'static str,
+}
+
+pub const COMMANDS: [CommandSpec; 7] = [
+    CommandSpec { name: "new", args: "", help: "Start a new chat" },
+    CommandSpec { name: "model", args: "<name>", help: "Load a model by (part of) its name" },
+    CommandSpec { name: "temp", args: "<0-2>", help: "Set the temperature for this chat" },
+    CommandSpec { name: "system", args: "<prompt>", help: "Set this chat's system prompt; empty clears it" },
+    CommandSpec { name: "export", args: "[md|html]", help: "Save this chat as a Markdown file or a shareable HTML page" },
+    CommandSpec { name: "summarize", args: "[replace]", help: "Summarize the chat so far; replace sends the summary instead of older messages" },
+    CommandSpec { name: "file", args: "<path>", help: "Add a text file to the chat for the model to read; long files are shortened in the prompt" },
+];
+
+/// The command in `input`, or `None` if it is an ordinary message.
+pub fn parse(input: &str) -> Option<Result<SlashCommand, String>> {
+    let input = input.trim();
+    let body = input.strip_prefix('/').filter(|rest| !rest.starts_with('/'))?;
+    let (name, args) = body.split_once(char::is_whitespace).map_or((body, ""), |(name, args)| (name, args.trim()));
+    let command = match name.to_lowercase().as_str() {
+        "new" => Ok(SlashCommand::New),
+        "model" if args.is_empty() => Err("Usage: /model <name>".to_string()),
+        "model" => Ok(SlashCommand::Model(args.to_string())),
+        "temp" | "temperature" => match args.parse::<f32>() {
+            Ok(t) if (0.0..=2.0).contains(&t) => Ok(SlashCommand::Temperature(t)),
+            _ => Err("Usage: /temp <0-2>, e.g. /temp 0.3".to_string()),
+        },
+        "system" => Ok(SlashCommand::System(args.to_string())),
+        "export" => match args.to_lowercase().as_str() {
+            "" | "md" | "markdown" => Ok(SlashCommand::Export(ExportFormat::Markdown)),
+            "html" => Ok(SlashCommand::Export(ExportFormat::Html)),
+            _ => Err("Usage: /export [md|html]".to_string()),
+        },
+        "summarize" | "summary" => match args.to_lowercase().as_str() {
+            "" => Ok(SlashCommand::Summarize { replace: false }),
+            "replace" => Ok(SlashCommand::Summarize { replace: true }),
+            _ => Err("Usage: /summarize [replace]".to_string()),
+        },
+        "file" if args.is_empty() => Err("Usage: /file <path>".to_string()),
+        "file" => Ok(SlashCommand::File(args.to_string())),
+        _ => Err(format!("Unknown command /{name}. Available: {}", COMMANDS.iter().map(|c| format!("/{}", c.name)).collect::<Vec<_>>().join(", "))),
+    };
+    Some(command)
+}
+
+/// What to send for an ordinary message: a leading `//` stands for a literal `/`.
+pub fn unescape(input: &str) -> &str {
+    match input.trim_start().strip_prefix("//") {
+        Some(_) => &input.trim_start()[1..],
+        None => input,
+    }
+}
+
+/// Commands for the palette shown while a command name is typed.
+pub fn matching(input: &str) -> Vec<&'static CommandSpec> {
+    let Some(body) = input.strip_prefix('/').filter(|rest| !rest.starts_with('/') && !rest.contains('\n')) else {
+        return Vec::new();
+    };
+    let (typed, has_args) = body.split_once(' ').map_or((body, false), |(name, _)| (name, true));
+    let typed = typed.to_lowercase();
+    COMMANDS
+        .iter()
+        .filter(|c| if has_args { c.name == typed } else { c.name.starts_with(&typed) })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_commands_and_plain_messages() {
+        assert_eq!(parse("hello"), None);
+        assert_eq!(parse("//etc/hosts is a file"), None);
+        assert_eq!(unescape("//etc/hosts is a file"), "/etc/hosts is a file");
+        assert_eq!(parse(" /new "), Some(Ok(SlashCommand::New)));
+        assert_eq!(parse("/model phi 3"), Some(Ok(SlashCommand::Model("phi 3".into()))));
+        assert_eq!(parse("/temp 0.3"), Some(Ok(SlashCommand::Temperature(0.3))));
+        assert_eq!(parse("/system Be terse.\nAlways."), Some(Ok(SlashCommand::System("Be terse.\nAlways.".into()))));
+        assert_eq!(parse("/system"), Some(Ok(SlashCommand::System(String::new()))));
+        assert_eq!(parse("/export"), Some(Ok(SlashCommand::Export(ExportFormat::Markdown))));
+        assert_eq!(parse("/export HTML"), Some(Ok(SlashCommand::Export(ExportFormat::Html))));
+        assert!(parse("/export pdf").unwrap().is_err());
+        assert_eq!(parse("/summarize"), Some(Ok(SlashCommand::Summarize { replace: false })));
+        assert_eq!(parse("/summarize Replace"), Some(Ok(SlashCommand::Summarize { replace: true })));
+        assert!(parse("/summarize everything").unwrap().is_err());
+        assert_eq!(parse("/file notes/todo list.md"), Some(Ok(SlashCommand::File("notes/todo list.md".into()))));
+        assert!(parse("/file").unwrap().is_err());
+        assert!(parse("/temp 5").unwrap().is_err());
+        assert!(parse("/model").unwrap().is_err());
+        assert!(parse("/frobnicate").unwrap().unwrap_err().contains("/export"));
+    }
+
+    #[test]
+    fn test_palette_narrows_as_the_name_is_typed() {
+        assert_eq!(matching("/").len(), COMMANDS.len());
+        assert_eq!(matching("/e").iter().map(|c| c.name).collect::<Vec<_>>(), ["export"]);
+        assert_eq!(matching("/s").iter().map(|c| c.name).collect::<Vec<_>>(), ["system", "summarize"]);
+        assert_eq!(matching("/temp 0.").iter().map(|c| c.name).collect::<Vec<_>>(), ["temp"]);
+        assert!(matching("hello").is_empty());
+        assert!(matching("//x").is_empty());
+    }
+}
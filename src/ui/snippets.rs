@@ -0,0 +1,201 @@
+//! Prompt snippets: reusable prompt text offered by the input area's "Quick Prompts" menu.
+//!
+//! A snippet may contain `{{variable}}` placeholders. Picking one that does opens a small form
+//! asking for each value, and the filled-in text is inserted once the form is confirmed.
+//! Snippets are saved with the config and edited in Settings.
+
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PromptSnippet {
+    pub name: String,
+    pub text: String,
+}
+
+impl PromptSnippet {
+    fn new(name: &str, text: &str) -> Self {
+        Self { name: name.to_string(), text: text.to_string() }
+    }
+
+    /// Placeholder names in order of first use.
+    pub fn variables(&self) -> Vec<String> {
+        let mut names: Vec<String> = Vec::new();
+        for part in parts(&self.text) {
+            if let Part::Variable(name) = part {
+                if !names.iter().any(|n| n == name) {
+                    names.push(name.to_string());
+                }
+            }
+        }
+        names
+    }
+
+    /// The text with each placeholder replaced by its value; placeholders without one are
+    /// left as they are.
+    pub fn fill(&self, values: &[(String, String)]) -> String {
+        parts(&self.text)
+            .into_iter()
+            .map(|part| match part {
+                Part::Text(text) => text.to_string(),
+                Part::Variable(name) => match values.iter().find(|(n, _)| n == name) {
+                    Some((_, value)) => value.clone(),
+                    None => format!("{{{{{name}}}}}"),
+                },
+            })
+            .collect()
+    }
+}
+
+/// The prompts the menu offered before it was editable.
+pub fn default_snippets() -> Vec<PromptSnippet> {
+    vec![
+        PromptSnippet::new("📝 Explain this concept", "Can you explain "),
+        PromptSnippet::new("🔍 Analyze this code", "Please analyze this {{language}} code:\n\n"),
+        PromptSnippet::new("🐛 Debug this issue", "Help me debug this problem: "),
+        PromptSnippet::new("💡 Brainstorm ideas", "I need ideas for "),
+        PromptSnippet::new("📚 Learn about", "Teach me about {{topic}}, assuming I know {{background}}."),
+    ]
+}
+
+enum Part<'a> {
+    Text(&'a str),
+    Variable(&'a str),
+}
+
+fn parts(mut text: &str) -> Vec<Part<'_>> {
+    let mut parts = Vec::new();
+    while let Some(start) = text.find("{{") {
+        let Some(len) = text[start + 2..].find("}}") else { break };
+        let name = text[start + 2..start + 2 + len].trim();
+        // `{{}}` and nested braces aren't placeholders
+        if name.is_empty() || name.contains(['{', '}']) {
+            parts.push(Part::Text(&text[..start + 2]));
+            text = &text[start + 2..];
+            continue;
+        }
+        parts.push(Part::Text(&text[..start]));
+        parts.push(Part::Variable(name));
+        text = &text[start + 2 + len + 2..];
+    }
+    parts.push(Part::Text(text));
+    parts
+}
+
+/// What the user did with a [`SnippetForm`].
+pub enum SnippetFormEvent {
+    Insert(String),
+    Cancel,
+}
+
+/// A picked snippet waiting for its variables.
+pub struct SnippetForm {
+    snippet: PromptSnippet,
+    values: Vec<(String, String)>,
+}
+
+impl SnippetForm {
+    /// `None` if the snippet has no variables and can be inserted as it is.
+    pub fn new(snippet: &PromptSnippet) -> Option<Self> {
+        let values: Vec<(String, String)> = snippet.variables().into_iter().map(|name| (name, String::new())).collect();
+        (!values.is_empty()).then(|| Self { snippet: snippet.clone(), values })
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) -> Option<SnippetFormEvent> {
+        let mut event = None;
+        let mut open = true;
+        let mut submitted = false;
+        egui::Window::new(&self.snippet.name)
+            .id(egui::Id::new("snippet_form"))
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                egui::Grid::new("snippet_form_values").num_columns(2).show(ui, |ui| {
+                    for (i, (name, value)) in self.values.iter_mut().enumerate() {
+                        ui.label(format!("{name}:"));
+                        let response = ui.text_edit_singleline(value);
+                        if i == 0 && ui.memory(|m| m.focused().is_none()) {
+                            response.request_focus();
+                        }
+                        submitted |= response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                        ui.end_row();
+                    }
+                });
+                ui.add_space(4.0);
+                ui.label(egui::RichText::new(self.snippet.fill(&self.values_so_far())).small().weak());
+                ui.horizontal(|ui| {
+                    if ui.button("Insert").clicked() || submitted {
+                        event = Some(SnippetFormEvent::Insert(self.snippet.fill(&self.values_so_far())));
+                    }
+                    if ui.button("Cancel").clicked() {
+                        event = Some(SnippetFormEvent::Cancel);
+                    }
+                });
+            });
+        if !open {
+            event = Some(SnippetFormEvent::Cancel);
+        }
+        event
+    }
+
+    /// Values entered so far; empty fields keep their placeholder in the preview.
+    fn values_so_far(&self) -> Vec<(String, String)> {
+        self.values.iter().filter(|(_, v)| !v.is_empty()).cloned().collect()
+    }
+}
+
+/// Settings editor for the snippet list.
+pub fn render_editor(ui: &mut egui::Ui, snippets: &mut Vec<PromptSnippet>) {
+    let mut remove = None;
+    let mut move_up = None;
+    for (i, snippet) in snippets.iter_mut().enumerate() {
+        ui.push_id(i, |ui| {
+            ui.horizontal(|ui| {
+                ui.add(egui::TextEdit::singleline(&mut snippet.name).desired_width(180.0).hint_text("Name"));
+                if ui.add_enabled(i > 0, egui::Button::new("⬆").small()).on_hover_text("Move up").clicked() {
+                    move_up = Some(i);
+                }
+                if ui.small_button("🗑").on_hover_text("Remove").clicked() {
+                    remove = Some(i);
+                }
+            });
+            ui.add(egui::TextEdit::multiline(&mut snippet.text).desired_rows(2).hint_text("Prompt text, e.g. Translate to {{language}}:"));
+        });
+        ui.add_space(4.0);
+    }
+    if let Some(i) = remove {
+        snippets.remove(i);
+    }
+    if let Some(i) = move_up {
+        snippets.swap(i, i - 1);
+    }
+    ui.horizontal(|ui| {
+        if ui.button("➕ Add snippet").clicked() {
+            snippets.push(PromptSnippet::new("New snippet", ""));
+        }
+        if ui.button("Restore defaults").clicked() {
+            *snippets = default_snippets();
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_variables_are_found_once_in_order() {
+        let snippet = PromptSnippet::new("t", "Port this {{ language }} to {{target}}, keeping {{language}} idioms. {{}} {x}");
+        assert_eq!(snippet.variables(), ["language", "target"]);
+        assert!(PromptSnippet::new("t", "No {{ placeholders").variables().is_empty());
+    }
+
+    #[test]
+    fn test_fill_replaces_known_values_only() {
+        let snippet = PromptSnippet::new("t", "Port this {{ language }} to {{target}}.");
+        let values = [("language".to_string(), "Python".to_string())];
+        assert_eq!(snippet.fill(&values), "Port this Python to {{target}}.");
+        assert!(SnippetForm::new(&PromptSnippet::new("t", "plain")).is_none());
+    }
+}
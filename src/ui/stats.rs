@@ -0,0 +1,124 @@
+//! "Stats" window: messages and tokens per day as bar charts, per-model throughput and how
+//! replies were rated.
+
+use crate::ai::feedback::FeedbackTotals;
+use crate::storage::stats::{DailyUsage, UsageReport, UsageStats};
+use crate::ui::theme::Palette;
+use eframe::egui;
+
+/// Days shown in the charts.
+const CHART_DAYS: u32 = 30;
+const CHART_HEIGHT: f32 = 90.0;
+
+/// Read what [`render`] shows; the result is kept until more usage is recorded.
+pub fn load(stats: &UsageStats) -> Result<UsageReport, String> {
+    stats.report(chrono::Local::now().date_naive(), CHART_DAYS).map_err(|e| e.to_string())
+}
+
+pub fn render(ui: &mut egui::Ui, report: &Result<UsageReport, String>) {
+    let UsageReport { daily, models, totals } = match report {
+        Ok(report) => report,
+        Err(e) => {
+            ui.colored_label(ui.visuals().error_fg_color, format!("Couldn't read usage statistics: {e}"));
+            return;
+        }
+    };
+    if totals.messages == 0 {
+        ui.label(egui::RichText::new("No usage recorded yet. Statistics appear once you start chatting.").weak());
+        return;
+    }
+
+    ui.horizontal(|ui| {
+        summary(ui, "Messages sent", totals.messages.to_string());
+        summary(ui, "Replies", totals.replies.to_string());
+        summary(ui, "Tokens generated", format!("~{}", totals.tokens));
+        let speed = totals.tokens_per_second().map_or("–".to_string(), |tps| format!("{tps:.1}"));
+        summary(ui, "Avg tokens/s", speed);
+    });
+
+    ui.add_space(8.0);
+    ui.label(egui::RichText::new(format!("Messages sent per day (last {CHART_DAYS} days)")).strong());
+    bar_chart(ui, daily, |d| d.messages);
+    ui.add_space(8.0);
+    ui.label(egui::RichText::new("Tokens generated per day").strong());
+    bar_chart(ui, daily, |d| d.tokens);
+
+    ui.add_space(8.0);
+    ui.label(egui::RichText::new("Per model").strong());
+    egui::Grid::new("stats_models").num_columns(4).striped(true).spacing([16.0, 4.0]).show(ui, |ui| {
+        for header in ["Model", "Replies", "Tokens", "Tokens/s"] {
+            ui.label(egui::RichText::new(header).small().weak());
+        }
+        ui.end_row();
+        for model in models {
+            ui.label(&model.model);
+            ui.label(model.replies.to_string());
+            ui.label(model.tokens.to_string());
+            ui.label(model.tokens_per_second().map_or("–".to_string(), |tps| format!("{tps:.1}")));
+            ui.end_row();
+        }
+    });
+    ui.label(egui::RichText::new("Token counts are estimated from the reply text.").small().weak());
+}
+
+/// Ratings given to replies. Returns true when the export was asked for.
+pub fn render_feedback(ui: &mut egui::Ui, totals: &FeedbackTotals) -> bool {
+    ui.label(egui::RichText::new("Feedback").strong());
+    ui.horizontal(|ui| {
+        summary(ui, "👍 Good", totals.good.to_string());
+        summary(ui, "👎 Poor", totals.poor.to_string());
+        let approval = totals.approval().map_or("–".to_string(), |share| format!("{:.0}%", share * 100.0));
+        summary(ui, "Approval", approval);
+        summary(ui, "Rated", format!("{} of {}", totals.rated(), totals.replies));
+    });
+    if totals.commented > 0 {
+        ui.label(egui::RichText::new(format!("{} ratings have a comment.", totals.commented)).small().weak());
+    }
+    ui.add_enabled(totals.rated() > 0, egui::Button::new("⬇ Export rated replies (JSONL)"))
+        .on_hover_text("Prompt, response, rating and comment per line, for fine-tuning datasets")
+        .clicked()
+}
+
+fn summary(ui: &mut egui::Ui, label: &str, value: String) {
+    ui.group(|ui| {
+        ui.vertical(|ui| {
+            ui.label(egui::RichText::new(label).small().weak());
+            ui.label(egui::RichText::new(value).size(18.0).strong());
+        });
+    });
+}
+
+/// One bar per day, scaled to the busiest day; hover a bar for its value.
+fn bar_chart(ui: &mut egui::Ui, days: &[DailyUsage], value: impl Fn(&DailyUsage) -> u64) {
+    let palette = Palette::current(ui.ctx());
+    let width = ui.available_width().max(120.0);
+    let (rect, response) = ui.allocate_exact_size(egui::vec2(width, CHART_HEIGHT), egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    painter.rect_stroke(rect, 2.0, ui.visuals().widgets.noninteractive.bg_stroke);
+
+    let max = days.iter().map(&value).max().unwrap_or(0).max(1) as f32;
+    let slot = rect.width() / days.len().max(1) as f32;
+    let mut hovered = None;
+    for (i, day) in days.iter().enumerate() {
+        let height = (rect.height() - 4.0) * value(day) as f32 / max;
+        let left = rect.left() + i as f32 * slot;
+        let bar = egui::Rect::from_min_max(
+            egui::pos2(left + slot * 0.15, rect.bottom() - height),
+            egui::pos2(left + slot * 0.85, rect.bottom()),
+        );
+        painter.rect_filled(bar, 1.0, palette.accent);
+        if response.hover_pos().is_some_and(|p| p.x >= left && p.x < left + slot) {
+            hovered = Some(day);
+        }
+    }
+    painter.text(
+        rect.left_top() + egui::vec2(4.0, 2.0),
+        egui::Align2::LEFT_TOP,
+        format!("max {}", max as u64),
+        egui::FontId::proportional(10.0),
+        ui.visuals().weak_text_color(),
+    );
+    if let Some(day) = hovered {
+        response.on_hover_text(format!("{}: {}", day.day.format("%a %b %-d"), value(day)));
+    }
+}
@@ -0,0 +1,125 @@
+//! How a reply appears while it streams.
+//!
+//! Providers hand text over in bursts (a batch of tokens, or a whole reply from a model
+//! without streaming), which makes the preview jump. The [`Pacer`] reveals the received text
+//! at a steady rate instead, and speeds up whenever it falls behind so the preview is never
+//! much more than a second late. Before the first token arrives, a typing indicator shows
+//! the model is working.
+
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::time::Instant;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StreamingSettings {
+    /// Characters revealed per second; 0 shows text as soon as it arrives.
+    pub chars_per_second: u32,
+    /// Animated dots while waiting for the first token.
+    pub typing_indicator: bool,
+}
+
+impl Default for StreamingSettings {
+    fn default() -> Self {
+        Self { chars_per_second: 120, typing_indicator: true }
+    }
+}
+
+/// Longest the display may trail the received text before the pace picks up.
+const MAX_LAG_SECS: f32 = 1.0;
+
+/// Tracks how much of a streamed reply is on screen.
+#[derive(Debug, Default)]
+pub struct Pacer {
+    received: usize,
+    shown: usize,
+    /// Fraction of a character owed from the last frame.
+    carry: f32,
+    last_tick: Option<Instant>,
+}
+
+impl Pacer {
+    pub fn received(&mut self, chunk: &str) {
+        self.received += chunk.chars().count();
+    }
+
+    /// Characters shown so far.
+    pub fn shown(&self) -> usize {
+        self.shown
+    }
+
+    pub fn caught_up(&self) -> bool {
+        self.shown >= self.received
+    }
+
+    /// Reveal the text due by `now` at `chars_per_second`; 0 reveals everything.
+    pub fn advance(&mut self, chars_per_second: u32, now: Instant) {
+        let elapsed = self.last_tick.map_or(0.0, |last| now.saturating_duration_since(last).as_secs_f32());
+        self.last_tick = Some(now);
+        if chars_per_second == 0 {
+            self.shown = self.received;
+            return;
+        }
+        let backlog = self.received.saturating_sub(self.shown) as f32;
+        let rate = (chars_per_second as f32).max(backlog / MAX_LAG_SECS);
+        let due = rate * elapsed + self.carry;
+        let step = due.floor();
+        self.carry = if self.received > self.shown { due - step } else { 0.0 };
+        self.shown = (self.shown + step as usize).min(self.received);
+    }
+}
+
+/// The first `chars` characters of `text`.
+pub fn visible(text: &str, chars: usize) -> &str {
+    match text.char_indices().nth(chars) {
+        Some((end, _)) => &text[..end],
+        None => text,
+    }
+}
+
+/// Three dots that rise in turn; still when animations are off.
+pub fn typing_indicator(ui: &mut egui::Ui, color: egui::Color32, animate: bool) {
+    let (rect, _) = ui.allocate_exact_size(egui::vec2(36.0, 16.0), egui::Sense::hover());
+    let time = ui.input(|i| i.time) as f32;
+    for i in 0..3 {
+        let phase = time * 6.0 - i as f32 * 0.9;
+        let lift = if animate { phase.sin().max(0.0) * 4.0 } else { 0.0 };
+        let center = egui::pos2(rect.left() + 6.0 + i as f32 * 12.0, rect.center().y + 2.0 - lift);
+        ui.painter().circle_filled(center, 3.5, color);
+    }
+    if animate {
+        ui.ctx().request_repaint();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_pacer_reveals_steadily_and_catches_up() {
+        let start = Instant::now();
+        let mut pacer = Pacer::default();
+        pacer.advance(100, start);
+        pacer.received(&"a".repeat(50));
+        pacer.advance(100, start + Duration::from_millis(100));
+        assert_eq!(pacer.shown(), 10);
+        pacer.advance(100, start + Duration::from_millis(150));
+        assert_eq!(pacer.shown(), 15);
+
+        // A large burst is shown within about a second
+        pacer.received(&"b".repeat(1000));
+        pacer.advance(100, start + Duration::from_millis(650));
+        assert!(pacer.shown() > 500);
+        pacer.advance(100, start + Duration::from_millis(1700));
+        assert!(pacer.caught_up());
+
+        let mut instant = Pacer::default();
+        instant.received("héllo");
+        instant.advance(0, start);
+        assert!(instant.caught_up());
+        assert_eq!(visible("héllo", 2), "hé");
+        assert_eq!(visible("héllo", 9), "héllo");
+    }
+}
@@ -0,0 +1,96 @@
+//! Colored tags on chats and the filter bar above the chat list.
+//!
+//! Tags are stored on the session itself, so they are saved and synced with the chat. A tag's
+//! color follows from its name, which keeps it the same everywhere without a separate list.
+
+use crate::ai::ChatSession;
+use eframe::egui;
+
+/// Offered in the tag menu before any chat has tags.
+pub const SUGGESTED: [&str; 3] = ["work", "research", "code"];
+
+/// Longest tag name, in characters.
+const MAX_TAG_CHARS: usize = 24;
+
+const COLORS: [egui::Color32; 8] = [
+    egui::Color32::from_rgb(66, 133, 244),
+    egui::Color32::from_rgb(52, 168, 83),
+    egui::Color32::from_rgb(171, 71, 188),
+    egui::Color32::from_rgb(239, 108, 0),
+    egui::Color32::from_rgb(0, 151, 167),
+    egui::Color32::from_rgb(229, 57, 53),
+    egui::Color32::from_rgb(124, 179, 66),
+    egui::Color32::from_rgb(255, 179, 0),
+];
+
+/// The tag as stored: trimmed, lowercase, inner whitespace collapsed to `-`; `None` if empty.
+pub fn normalize(name: &str) -> Option<String> {
+    let tag = name.split_whitespace().collect::<Vec<_>>().join("-").to_lowercase();
+    let tag: String = tag.chars().take(MAX_TAG_CHARS).collect();
+    (!tag.is_empty()).then_some(tag)
+}
+
+pub fn color(tag: &str) -> egui::Color32 {
+    let index = match SUGGESTED.iter().position(|s| *s == tag) {
+        Some(index) => index,
+        None => tag.bytes().fold(0usize, |hash, b| hash.wrapping_mul(31).wrapping_add(b as usize)),
+    };
+    COLORS[index % COLORS.len()]
+}
+
+/// Every tag used by `sessions` plus the suggested ones, sorted.
+pub fn known_tags(sessions: &[ChatSession]) -> Vec<String> {
+    let mut tags: Vec<String> = sessions.iter().flat_map(|s| s.tags.iter().cloned()).chain(SUGGESTED.map(String::from)).collect();
+    tags.sort();
+    tags.dedup();
+    tags
+}
+
+/// Whether `session` has every tag in `filter`.
+pub fn matches_filter(session: &ChatSession, filter: &[String]) -> bool {
+    filter.iter().all(|tag| session.tags.contains(tag))
+}
+
+/// A tag pill; filled when `active`.
+pub fn chip(ui: &mut egui::Ui, tag: &str, active: bool) -> egui::Response {
+    let color = color(tag);
+    let text = egui::RichText::new(tag).small().color(if active { egui::Color32::WHITE } else { color });
+    let button = egui::Button::new(text)
+        .fill(if active { color } else { egui::Color32::TRANSPARENT })
+        .stroke(egui::Stroke::new(1.0, color))
+        .rounding(8.0);
+    ui.add(button)
+}
+
+/// Small colored dots for a chat's tags in the list.
+pub fn dots(ui: &mut egui::Ui, tags: &[String]) {
+    for tag in tags {
+        let (rect, response) = ui.allocate_exact_size(egui::vec2(8.0, 8.0), egui::Sense::hover());
+        ui.painter().circle_filled(rect.center(), 3.5, color(tag));
+        response.on_hover_text(tag);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tags_normalize_and_filter() {
+        assert_eq!(normalize("  Side  Project "), Some("side-project".to_string()));
+        assert_eq!(normalize("   "), None);
+        assert_eq!(color("work"), COLORS[0]);
+        assert_eq!(color("side-project"), color("side-project"));
+
+        let session = ChatSession {
+            id: "s1".into(),
+            title: "Chat".into(),
+            tags: vec!["work".into(), "zeta".into()],
+            ..Default::default()
+        };
+        assert!(matches_filter(&session, &[]));
+        assert!(matches_filter(&session, &["work".into()]));
+        assert!(!matches_filter(&session, &["work".into(), "code".into()]));
+        assert_eq!(known_tags(&[session]), vec!["code", "research", "work", "zeta"]);
+    }
+}
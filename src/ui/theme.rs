@@ -0,0 +1,318 @@
+//! Light/dark palettes, appearance settings and the style builder that applies them.
+//!
+//! egui's own widgets follow `ctx.set_theme`; the chat bubbles, sidebar and model cards
+//! are painted with explicit colors, so they take their colors from here for the theme egui is
+//! currently rendering (which, for `Theme::System`, tracks the OS setting).
+//! `StyleBuilder` folds the user's `Appearance` (accent, font size, density) into both the
+//! egui styles and the palettes, so UI code never needs to hard-code those values.
+
+use crate::ui::app::Theme;
+use egui::{Color32, FontId, TextStyle};
+use serde::{Deserialize, Serialize};
+
+/// How tightly chat messages are packed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MessageDensity {
+    Compact,
+    #[default]
+    Comfortable,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Appearance {
+    /// Replaces the theme's accent color when set.
+    pub accent_color: Option<[u8; 3]>,
+    /// Body text size in points; other text styles scale with it.
+    pub font_size: f32,
+    pub density: MessageDensity,
+    /// Larger buttons and spacing for fingers on touch screens.
+    pub touch: bool,
+}
+
+impl Default for Appearance {
+    fn default() -> Self {
+        Self {
+            accent_color: None,
+            font_size: DEFAULT_FONT_SIZE,
+            density: MessageDensity::default(),
+            touch: cfg!(any(target_os = "android", target_os = "ios")),
+        }
+    }
+}
+
+/// egui's default body size.
+pub const DEFAULT_FONT_SIZE: f32 = 12.5;
+pub const FONT_SIZE_RANGE: std::ops::RangeInclusive<f32> = 10.0..=24.0;
+/// Smallest height of buttons and other controls in touch mode, about a fingertip.
+pub const TOUCH_TARGET: f32 = 44.0;
+
+/// Sizes for the custom-drawn chat elements, derived from `Appearance`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Metrics {
+    pub message_text: f32,
+    pub meta_text: f32,
+    pub bubble_margin: f32,
+    pub message_gap: f32,
+}
+
+impl Metrics {
+    /// Metrics installed by the last `StyleBuilder::install`, or the defaults.
+    pub fn current(ctx: &egui::Context) -> Self {
+        ctx.data(|d| d.get_temp(metrics_id()))
+            .unwrap_or_else(|| StyleBuilder::new(&Appearance::default()).metrics())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Palette {
+    pub accent: Color32,
+    pub status_bar_fill: Color32,
+    pub status_bar_stroke: Color32,
+    pub sidebar_fill: Color32,
+    pub sidebar_selected: Color32,
+    pub card_fill: Color32,
+    pub card_fill_selected: Color32,
+    pub card_stroke: Color32,
+    /// Small badges inside cards (model type, quantization).
+    pub chip_fill: Color32,
+    pub system_card_fill: Color32,
+    pub heading_text: Color32,
+    pub secondary_text: Color32,
+    pub muted_text: Color32,
+    pub user_bubble_fill: Color32,
+    pub user_bubble_stroke: Color32,
+    pub ai_bubble_fill: Color32,
+    pub ai_bubble_stroke: Color32,
+    pub bubble_text: Color32,
+    pub bubble_meta_text: Color32,
+    pub model_tag_text: Color32,
+    pub timing_text: Color32,
+    pub success: Color32,
+    pub warning: Color32,
+    pub danger: Color32,
+    pub shadow: Color32,
+}
+
+impl Palette {
+    pub const fn dark() -> Self {
+        Self {
+            accent: Color32::from_rgb(100, 200, 255),
+            status_bar_fill: Color32::from_rgb(25, 25, 35),
+            status_bar_stroke: Color32::from_rgb(50, 50, 60),
+            sidebar_fill: Color32::from_rgb(30, 30, 40),
+            sidebar_selected: Color32::from_rgb(60, 60, 80),
+            card_fill: Color32::from_rgb(40, 44, 52),
+            card_fill_selected: Color32::from_rgb(45, 55, 75),
+            card_stroke: Color32::from_rgb(60, 66, 74),
+            chip_fill: Color32::from_rgb(55, 60, 70),
+            system_card_fill: Color32::from_rgb(40, 50, 40),
+            heading_text: Color32::WHITE,
+            secondary_text: Color32::from_rgb(200, 200, 200),
+            muted_text: Color32::from_rgb(150, 150, 150),
+            user_bubble_fill: Color32::from_rgb(65, 105, 170),
+            user_bubble_stroke: Color32::from_rgb(85, 125, 190),
+            ai_bubble_fill: Color32::from_rgb(75, 85, 110),
+            ai_bubble_stroke: Color32::from_rgb(95, 105, 130),
+            bubble_text: Color32::WHITE,
+            bubble_meta_text: Color32::from_rgb(200, 210, 220),
+            model_tag_text: Color32::from_rgb(180, 220, 180),
+            timing_text: Color32::from_rgb(255, 220, 100),
+            success: Color32::from_rgb(76, 175, 80),
+            warning: Color32::from_rgb(255, 152, 0),
+            danger: Color32::from_rgb(244, 67, 54),
+            shadow: Color32::from_black_alpha(60),
+        }
+    }
+
+    pub const fn light() -> Self {
+        Self {
+            accent: Color32::from_rgb(0, 110, 200),
+            status_bar_fill: Color32::from_rgb(236, 238, 243),
+            status_bar_stroke: Color32::from_rgb(210, 214, 222),
+            sidebar_fill: Color32::from_rgb(242, 244, 248),
+            sidebar_selected: Color32::from_rgb(212, 224, 245),
+            card_fill: Color32::from_rgb(250, 251, 253),
+            card_fill_selected: Color32::from_rgb(228, 238, 252),
+            card_stroke: Color32::from_rgb(205, 210, 220),
+            chip_fill: Color32::from_rgb(230, 233, 240),
+            system_card_fill: Color32::from_rgb(232, 245, 232),
+            heading_text: Color32::from_rgb(25, 28, 35),
+            secondary_text: Color32::from_rgb(70, 75, 85),
+            muted_text: Color32::from_rgb(115, 120, 130),
+            user_bubble_fill: Color32::from_rgb(214, 228, 250),
+            user_bubble_stroke: Color32::from_rgb(170, 195, 235),
+            ai_bubble_fill: Color32::from_rgb(238, 240, 245),
+            ai_bubble_stroke: Color32::from_rgb(205, 210, 220),
+            bubble_text: Color32::from_rgb(25, 28, 35),
+            bubble_meta_text: Color32::from_rgb(90, 100, 115),
+            model_tag_text: Color32::from_rgb(40, 120, 50),
+            timing_text: Color32::from_rgb(170, 110, 0),
+            success: Color32::from_rgb(46, 125, 50),
+            warning: Color32::from_rgb(200, 100, 0),
+            danger: Color32::from_rgb(198, 40, 40),
+            shadow: Color32::from_black_alpha(25),
+        }
+    }
+
+    pub fn for_theme(theme: egui::Theme) -> Self {
+        match theme {
+            egui::Theme::Dark => Self::dark(),
+            egui::Theme::Light => Self::light(),
+        }
+    }
+
+    /// Palette for whatever egui is rendering this frame, including the user's accent.
+    pub fn current(ctx: &egui::Context) -> Self {
+        let theme = ctx.theme();
+        ctx.data(|d| d.get_temp(palette_id(theme)))
+            .unwrap_or_else(|| Self::for_theme(theme))
+    }
+}
+
+fn palette_id(theme: egui::Theme) -> egui::Id {
+    egui::Id::new(("ria_palette", theme))
+}
+
+fn metrics_id() -> egui::Id {
+    egui::Id::new("ria_metrics")
+}
+
+fn blend(a: Color32, b: Color32, t: f32) -> Color32 {
+    let mix = |x: u8, y: u8| (x as f32 + (y as f32 - x as f32) * t).round() as u8;
+    Color32::from_rgb(mix(a.r(), b.r()), mix(a.g(), b.g()), mix(a.b(), b.b()))
+}
+
+/// Builds egui styles, palettes and metrics from the appearance settings.
+pub struct StyleBuilder<'a> {
+    appearance: &'a Appearance,
+}
+
+impl<'a> StyleBuilder<'a> {
+    pub fn new(appearance: &'a Appearance) -> Self {
+        Self { appearance }
+    }
+
+    pub fn palette(&self, theme: egui::Theme) -> Palette {
+        let mut palette = Palette::for_theme(theme);
+        if let Some([r, g, b]) = self.appearance.accent_color {
+            palette.accent = Color32::from_rgb(r, g, b);
+        }
+        palette
+    }
+
+    pub fn metrics(&self) -> Metrics {
+        let scale = self.font_scale();
+        let (bubble_margin, message_gap) = match self.appearance.density {
+            MessageDensity::Compact => (8.0, 4.0),
+            MessageDensity::Comfortable => (16.0, 10.0),
+        };
+        Metrics { message_text: 15.0 * scale, meta_text: 11.0 * scale, bubble_margin, message_gap }
+    }
+
+    fn font_scale(&self) -> f32 {
+        self.appearance.font_size.clamp(*FONT_SIZE_RANGE.start(), *FONT_SIZE_RANGE.end()) / DEFAULT_FONT_SIZE
+    }
+
+    pub fn style(&self, theme: egui::Theme) -> egui::Style {
+        let mut style = theme.default_style();
+        let scale = self.font_scale();
+        for (text_style, font) in style.text_styles.iter_mut() {
+            let default_size = match text_style {
+                TextStyle::Small => 9.0,
+                TextStyle::Monospace => 12.0,
+                TextStyle::Heading => 18.0,
+                _ => DEFAULT_FONT_SIZE,
+            };
+            *font = FontId::new(default_size * scale, font.family.clone());
+        }
+
+        if self.appearance.density == MessageDensity::Compact {
+            style.spacing.item_spacing = egui::vec2(6.0, 2.0);
+            style.spacing.interact_size.y = 16.0;
+        }
+        if self.appearance.touch {
+            style.spacing.interact_size.y = TOUCH_TARGET;
+            style.spacing.button_padding = egui::vec2(12.0, 10.0);
+            style.spacing.item_spacing = egui::vec2(10.0, 8.0);
+            style.spacing.icon_width = 24.0;
+            style.spacing.scroll.bar_width = 14.0;
+        }
+
+        if self.appearance.accent_color.is_some() {
+            let palette = self.palette(theme);
+            let base = style.visuals.panel_fill;
+            style.visuals.hyperlink_color = palette.accent;
+            style.visuals.selection.bg_fill = blend(base, palette.accent, 0.55);
+            style.visuals.selection.stroke.color = palette.accent;
+        }
+        style
+    }
+
+    /// Apply the styles for both themes so a later OS theme switch picks them up too.
+    pub fn install(&self, ctx: &egui::Context) {
+        for theme in [egui::Theme::Dark, egui::Theme::Light] {
+            ctx.set_style_of(theme, self.style(theme));
+            let palette = self.palette(theme);
+            ctx.data_mut(|d| d.insert_temp(palette_id(theme), palette));
+        }
+        let metrics = self.metrics();
+        ctx.data_mut(|d| d.insert_temp(metrics_id(), metrics));
+    }
+}
+
+impl Theme {
+    pub fn preference(&self) -> egui::ThemePreference {
+        match self {
+            Theme::Dark => egui::ThemePreference::Dark,
+            Theme::Light => egui::ThemePreference::Light,
+            Theme::System => egui::ThemePreference::System,
+        }
+    }
+}
+
+/// Switch egui to `theme` and install the appearance settings. With `Theme::System`, egui
+/// follows the OS theme reported by the windowing backend and falls back to dark when it is
+/// unknown.
+pub fn apply(ctx: &egui::Context, theme: &Theme, appearance: &Appearance) {
+    ctx.set_theme(theme.preference());
+    StyleBuilder::new(appearance).install(ctx);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_palettes_keep_bubble_text_readable() {
+        for palette in [Palette::dark(), Palette::light()] {
+            let luma = |c: Color32| 0.299 * c.r() as f32 + 0.587 * c.g() as f32 + 0.114 * c.b() as f32;
+            for fill in [palette.user_bubble_fill, palette.ai_bubble_fill, palette.card_fill] {
+                assert!((luma(fill) - luma(palette.bubble_text)).abs() > 90.0);
+            }
+        }
+        assert_eq!(Palette::for_theme(egui::Theme::Light), Palette::light());
+    }
+
+    #[test]
+    fn test_style_builder_applies_appearance() {
+        let appearance = Appearance {
+            accent_color: Some([200, 30, 120]),
+            font_size: 25.0,
+            density: MessageDensity::Compact,
+            touch: false,
+        };
+        let builder = StyleBuilder::new(&appearance);
+        assert_eq!(builder.palette(egui::Theme::Light).accent, Color32::from_rgb(200, 30, 120));
+
+        // Font size is clamped to the supported range
+        let style = builder.style(egui::Theme::Dark);
+        assert_eq!(style.text_styles[&TextStyle::Body].size, 24.0);
+        assert_eq!(style.visuals.hyperlink_color, Color32::from_rgb(200, 30, 120));
+        assert!(builder.metrics().bubble_margin < StyleBuilder::new(&Appearance::default()).metrics().bubble_margin);
+
+        // Touch mode wins over compact spacing
+        let touch = StyleBuilder::new(&Appearance { touch: true, ..appearance }).style(egui::Theme::Dark);
+        assert_eq!(touch.spacing.interact_size.y, TOUCH_TARGET);
+    }
+}
@@ -0,0 +1,95 @@
+//! Translating a message with the loaded model.
+//!
+//! A translation is a one-off request built from [`prompt`], never added to the chat: it is
+//! streamed into a [`Translation`] kept by message id and shown under the original until it is
+//! closed. Translations aren't saved.
+
+use crate::ai::{ChatMessage, GenerationOverrides};
+use tokio::sync::mpsc;
+use tokio::sync::mpsc::error::TryRecvError;
+
+/// Offered in the Translate menu; the last one picked is remembered in the config.
+pub const LANGUAGES: [&str; 12] = [
+    "English", "Spanish", "French", "German", "Italian", "Portuguese", "Russian", "Ukrainian", "Chinese", "Japanese", "Korean", "Arabic",
+];
+
+pub fn default_language() -> String {
+    LANGUAGES[0].to_string()
+}
+
+/// The request for a translation of `text` into `language`: the text alone, with instructions
+/// that keep the model from answering it instead.
+pub fn prompt(text: &str, language: &str) -> Vec<ChatMessage> {
+    let instructions = format!(
+        "You are a translator. Translate the user's text into {language}. Keep its meaning, tone and \
+         Markdown formatting, leave code, URLs and names as they are, and reply with the translation only."
+    );
+    vec![
+        ChatMessage::system(instructions),
+        ChatMessage::user(text),
+    ]
+}
+
+/// Sampling for translations: faithful rather than creative.
+pub fn overrides() -> GenerationOverrides {
+    GenerationOverrides { temperature: Some(0.2), ..Default::default() }
+}
+
+pub struct Translation {
+    pub language: String,
+    pub text: String,
+    stream: Option<mpsc::Receiver<String>>,
+}
+
+impl Translation {
+    pub fn new(language: &str, stream: mpsc::Receiver<String>) -> Self {
+        Self { language: language.to_string(), text: String::new(), stream: Some(stream) }
+    }
+
+    pub fn is_streaming(&self) -> bool {
+        self.stream.is_some()
+    }
+
+    /// Take in whatever has been streamed since the last call.
+    pub fn poll(&mut self) {
+        let Some(rx) = self.stream.as_mut() else { return };
+        loop {
+            match rx.try_recv() {
+                Ok(chunk) => self.text.push_str(&chunk),
+                Err(TryRecvError::Empty) => return,
+                Err(TryRecvError::Disconnected) => {
+                    self.stream = None;
+                    if self.text.trim().is_empty() {
+                        self.text = "No translation was generated.".to_string();
+                    }
+                    return;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::MessageRole;
+
+    #[test]
+    fn test_prompt_and_streamed_translation() {
+        let messages = prompt("Hola, ¿qué tal?", "German");
+        assert!(matches!(messages[0].role, MessageRole::System) && messages[0].content.contains("into German"));
+        assert!(matches!(messages[1].role, MessageRole::User));
+        assert_eq!(messages[1].content, "Hola, ¿qué tal?");
+
+        let (tx, rx) = mpsc::channel(4);
+        let mut translation = Translation::new("German", rx);
+        tx.try_send("Hallo, ".to_string()).unwrap();
+        tx.try_send("wie geht's?".to_string()).unwrap();
+        translation.poll();
+        assert!(translation.is_streaming());
+        drop(tx);
+        translation.poll();
+        assert!(!translation.is_streaming());
+        assert_eq!(translation.text, "Hallo, wie geht's?");
+    }
+}
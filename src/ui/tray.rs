@@ -0,0 +1,225 @@
+//! System tray icon (`tray` feature).
+//!
+//! The icon has a menu with "New chat", "Show/Hide" and "Quit". Menu clicks are forwarded
+//! to the app as `TrayAction`s and wake the UI with a repaint. "Hiding" minimizes the window
+//! rather than making it invisible: winit stops delivering redraws to an invisible window on
+//! some platforms, and the app would never see the click that should bring it back. While
+//! the window is minimized, finished work is announced with a desktop notification.
+//!
+//! On Windows and macOS the icon lives on the main thread next to the winit event loop; on
+//! Linux it needs a GTK main loop, so it is created on a dedicated thread that runs until
+//! the `Tray` is dropped.
+
+use anyhow::{anyhow, Result};
+use tokio::sync::mpsc;
+use tray_icon::menu::{Menu, MenuEvent, MenuId, MenuItem, PredefinedMenuItem};
+use tray_icon::{Icon, MouseButton, MouseButtonState, TrayIcon, TrayIconBuilder, TrayIconEvent};
+
+const TOOLTIP: &str = "RIA AI Chat";
+
+/// AppUserModelID Windows shows toasts under when the app has no registered one of its own.
+#[cfg(target_os = "windows")]
+const POWERSHELL_APP_ID: &str = r"{1AC14E77-02E7-4E5D-B744-2EB1AE5198B7}\WindowsPowerShell\v1.0\powershell.exe";
+const ICON_SIZE: u32 = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrayAction {
+    NewChat,
+    ShowHide,
+    Quit,
+}
+
+struct TrayParts {
+    icon: TrayIcon,
+    new_chat: MenuId,
+    show_hide: MenuId,
+    quit: MenuId,
+}
+
+pub struct Tray {
+    actions: mpsc::UnboundedReceiver<TrayAction>,
+    /// Dropping it stops the GTK thread.
+    #[cfg(target_os = "linux")]
+    _alive: std::sync::mpsc::Sender<()>,
+    #[cfg(not(target_os = "linux"))]
+    _icon: TrayIcon,
+}
+
+/// Round RIA badge in the default accent color.
+fn icon_image() -> Result<Icon> {
+    let size = ICON_SIZE as f32;
+    let mut rgba = Vec::with_capacity((ICON_SIZE * ICON_SIZE * 4) as usize);
+    for y in 0..ICON_SIZE {
+        for x in 0..ICON_SIZE {
+            let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+            let d = ((px - size / 2.0).powi(2) + (py - size / 2.0).powi(2)).sqrt();
+            let pixel = if d < size * 0.3 {
+                [255, 255, 255, 255]
+            } else if d < size * 0.48 {
+                [100, 200, 255, 255]
+            } else {
+                [0, 0, 0, 0]
+            };
+            rgba.extend_from_slice(&pixel);
+        }
+    }
+    Icon::from_rgba(rgba, ICON_SIZE, ICON_SIZE).map_err(|e| anyhow!("Invalid tray icon: {e}"))
+}
+
+fn build_tray() -> Result<TrayParts> {
+    let new_chat = MenuItem::new("New chat", true, None);
+    let show_hide = MenuItem::new("Show/Hide", true, None);
+    let quit = MenuItem::new("Quit", true, None);
+    let menu = Menu::new();
+    menu.append_items(&[&new_chat, &show_hide, &PredefinedMenuItem::separator(), &quit])?;
+
+    let icon = TrayIconBuilder::new()
+        .with_menu(Box::new(menu))
+        .with_menu_on_left_click(false)
+        .with_tooltip(TOOLTIP)
+        .with_icon(icon_image()?)
+        .build()?;
+    Ok(TrayParts {
+        icon,
+        new_chat: new_chat.id().clone(),
+        show_hide: show_hide.id().clone(),
+        quit: quit.id().clone(),
+    })
+}
+
+/// Forward menu and icon clicks to `tx`, waking the UI for each.
+fn install_handlers(parts: &TrayParts, tx: mpsc::UnboundedSender<TrayAction>, ctx: egui::Context) {
+    let (new_chat, show_hide, quit) = (parts.new_chat.clone(), parts.show_hide.clone(), parts.quit.clone());
+    let menu_tx = tx.clone();
+    let menu_ctx = ctx.clone();
+    MenuEvent::set_event_handler(Some(move |event: MenuEvent| {
+        let action = if event.id == new_chat {
+            TrayAction::NewChat
+        } else if event.id == show_hide {
+            TrayAction::ShowHide
+        } else if event.id == quit {
+            TrayAction::Quit
+        } else {
+            return;
+        };
+        let _ = menu_tx.send(action);
+        menu_ctx.request_repaint();
+    }));
+    TrayIconEvent::set_event_handler(Some(move |event: TrayIconEvent| {
+        if let TrayIconEvent::Click { button: MouseButton::Left, button_state: MouseButtonState::Up, .. } = event {
+            let _ = tx.send(TrayAction::ShowHide);
+            ctx.request_repaint();
+        }
+    }));
+}
+
+impl Tray {
+    /// Create the tray icon. Must be called from the UI thread (it is re-homed to a GTK
+    /// thread on Linux).
+    pub fn new(ctx: &egui::Context) -> Result<Self> {
+        let (tx, actions) = mpsc::unbounded_channel();
+
+        #[cfg(target_os = "linux")]
+        {
+            let (alive, alive_rx) = std::sync::mpsc::channel::<()>();
+            let (ready_tx, ready_rx) = std::sync::mpsc::channel::<Result<()>>();
+            let ctx = ctx.clone();
+            std::thread::Builder::new().name("ria-tray".into()).spawn(move || {
+                if let Err(e) = gtk::init() {
+                    let _ = ready_tx.send(Err(anyhow!("GTK unavailable: {e}")));
+                    return;
+                }
+                let parts = match build_tray() {
+                    Ok(parts) => parts,
+                    Err(e) => {
+                        let _ = ready_tx.send(Err(e));
+                        return;
+                    }
+                };
+                install_handlers(&parts, tx, ctx);
+                let _ = ready_tx.send(Ok(()));
+                gtk::glib::timeout_add_local(std::time::Duration::from_millis(200), move || {
+                    // Owning the icon here keeps it in the tray for as long as the loop runs
+                    let _icon = &parts.icon;
+                    match alive_rx.try_recv() {
+                        Err(std::sync::mpsc::TryRecvError::Disconnected) => {
+                            gtk::main_quit();
+                            gtk::glib::ControlFlow::Break
+                        }
+                        _ => gtk::glib::ControlFlow::Continue,
+                    }
+                });
+                gtk::main();
+            })?;
+            ready_rx.recv().map_err(|_| anyhow!("Tray thread exited during startup"))??;
+            return Ok(Self { actions, _alive: alive });
+        }
+
+        #[cfg(not(target_os = "linux"))]
+        {
+            let parts = build_tray()?;
+            install_handlers(&parts, tx, ctx.clone());
+            return Ok(Self { actions, _icon: parts.icon });
+        }
+    }
+
+    pub fn try_recv(&mut self) -> Option<TrayAction> {
+        self.actions.try_recv().ok()
+    }
+}
+
+/// Show a desktop notification through the platform's notifier (`notify-send`, AppleScript
+/// or a PowerShell toast). The text is passed as arguments or environment variables, never
+/// spliced into a script. Best effort: failures are only logged.
+pub fn notify(summary: &str, body: &str) {
+    #[cfg(target_os = "linux")]
+    let command = {
+        let mut command = std::process::Command::new("notify-send");
+        command.args(["--app-name", TOOLTIP, summary, body]);
+        command
+    };
+    #[cfg(target_os = "macos")]
+    let command = {
+        let mut command = std::process::Command::new("osascript");
+        command
+            .args(["-e", r#"display notification (system attribute "RIA_NOTIFY_BODY") with title (system attribute "RIA_NOTIFY_SUMMARY")"#])
+            .env("RIA_NOTIFY_SUMMARY", summary)
+            .env("RIA_NOTIFY_BODY", body);
+        command
+    };
+    #[cfg(target_os = "windows")]
+    let command = {
+        const SCRIPT: &str = "[Windows.UI.Notifications.ToastNotificationManager, Windows.UI.Notifications, ContentType = WindowsRuntime] > $null; \
+            $xml = [Windows.UI.Notifications.ToastNotificationManager]::GetTemplateContent([Windows.UI.Notifications.ToastTemplateType]::ToastText02); \
+            $text = $xml.GetElementsByTagName('text'); \
+            $text[0].AppendChild($xml.CreateTextNode($env:RIA_NOTIFY_SUMMARY)) > $null; \
+            $text[1].AppendChild($xml.CreateTextNode($env:RIA_NOTIFY_BODY)) > $null; \
+            [Windows.UI.Notifications.ToastNotificationManager]::CreateToastNotifier($env:RIA_NOTIFY_APP).Show([Windows.UI.Notifications.ToastNotification]::new($xml))";
+        let mut command = std::process::Command::new("powershell");
+        command
+            .args(["-NoProfile", "-NonInteractive", "-WindowStyle", "Hidden", "-Command", SCRIPT])
+            .env("RIA_NOTIFY_SUMMARY", summary)
+            .env("RIA_NOTIFY_BODY", body)
+            .env("RIA_NOTIFY_APP", POWERSHELL_APP_ID);
+        command
+    };
+    #[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+    {
+        let _ = (summary, body);
+        return;
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+    {
+        let mut command = command;
+        // Waited for on a thread so the notifier doesn't linger as a zombie
+        let spawned = std::thread::Builder::new().name("ria-notify".into()).spawn(move || match command.status() {
+            Ok(status) if !status.success() => tracing::warn!("Desktop notification failed: {}", status),
+            Ok(_) => {}
+            Err(e) => tracing::warn!("Desktop notifications unavailable: {}", e),
+        });
+        if let Err(e) = spawned {
+            tracing::warn!("Failed to send a desktop notification: {}", e);
+        }
+    }
+}
@@ -0,0 +1,217 @@
+//! Crash handling: a panic hook that writes a crash report and whatever the user hadn't
+//! saved yet, so the next launch can offer to restore it.
+//!
+//! The hook can't reach the app, so the app keeps a small snapshot up to date (the open
+//! chat, the unsent draft and a reply still being streamed) through [`update_snapshot`].
+//! On a panic the snapshot is written to `recovery.json` in the data directory next to a
+//! `crashes/crash-<time>.txt` report with the backtrace. Panics caught with [`catch_unwind`]
+//! are reported as errors by their caller and leave nothing behind.
+
+use crate::ai::ChatSession;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::cell::Cell;
+use std::sync::{Mutex, OnceLock};
+
+const RECOVERY_FILE: &str = "recovery.json";
+
+/// State saved when the app crashed.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Recovery {
+    pub crashed_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// The chat that was open, as shown on screen.
+    pub session: Option<ChatSession>,
+    /// Text typed into the message box but not sent.
+    #[serde(default)]
+    pub draft: String,
+    /// A reply that was still streaming in.
+    #[serde(default)]
+    pub partial_reply: Option<String>,
+    pub crash_report: Option<PathBuf>,
+}
+
+impl Recovery {
+    pub fn is_empty(&self) -> bool {
+        self.session.is_none() && self.draft.trim().is_empty() && self.partial_reply.is_none()
+    }
+}
+
+/// Window size, position and maximized state, as stored in the config.
+type WindowGeometry = ((f32, f32), Option<(f32, f32)>, bool);
+
+#[derive(Default)]
+struct Snapshot {
+    recovery: Recovery,
+    /// Window geometry written into the config on a crash, as a normal exit does.
+    window: Option<WindowGeometry>,
+}
+
+static DATA_DIR: OnceLock<PathBuf> = OnceLock::new();
+static SNAPSHOT: Mutex<Option<Snapshot>> = Mutex::new(None);
+
+thread_local! {
+    /// Set while this thread runs code under [`catch_unwind`].
+    static CATCHING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// [`std::panic::catch_unwind`] for panics the caller turns into an error: the process
+/// carries on, so the hook writes no crash report or recovery state for them.
+pub fn catch_unwind<R>(f: impl FnOnce() -> R) -> std::thread::Result<R> {
+    let outer = CATCHING.with(|catching| catching.replace(true));
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(f));
+    CATCHING.with(|catching| catching.set(outer));
+    result
+}
+
+/// Install the panic hook. `data_dir` receives `recovery.json` and the `crashes` directory.
+pub fn install(data_dir: PathBuf) {
+    if DATA_DIR.set(data_dir).is_err() {
+        return; // already installed
+    }
+    if let Ok(mut snapshot) = SNAPSHOT.lock() {
+        *snapshot = Some(Snapshot::default());
+    }
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        handle_panic(info);
+        previous(info);
+    }));
+}
+
+/// Replace the parts of the snapshot that changed. Cheap to call every frame as long as the
+/// caller only passes what changed.
+pub fn update_snapshot(update: impl FnOnce(&mut Recovery)) {
+    if let Ok(mut snapshot) = SNAPSHOT.lock() {
+        if let Some(snapshot) = snapshot.as_mut() {
+            update(&mut snapshot.recovery);
+        }
+    }
+}
+
+pub fn update_window(size: (f32, f32), position: Option<(f32, f32)>, maximized: bool) {
+    if let Ok(mut snapshot) = SNAPSHOT.lock() {
+        if let Some(snapshot) = snapshot.as_mut() {
+            snapshot.window = Some((size, position, maximized));
+        }
+    }
+}
+
+fn handle_panic(info: &std::panic::PanicHookInfo<'_>) {
+    if CATCHING.with(Cell::get) {
+        return;
+    }
+    let Some(data_dir) = DATA_DIR.get() else { return };
+    let now = chrono::Utc::now();
+    let crashes = data_dir.join("crashes");
+    let report_path = crashes.join(format!("crash-{}.txt", now.format("%Y%m%d-%H%M%S")));
+    let written = std::fs::create_dir_all(&crashes).and_then(|_| std::fs::write(&report_path, crash_report(info, now)));
+    match written {
+        Ok(()) => eprintln!("RIA crashed; report written to {}", report_path.display()),
+        Err(e) => eprintln!("RIA crashed and the crash report couldn't be written: {e}"),
+    }
+
+    // `try_lock`: the panic may have happened while the snapshot was being updated
+    let Ok(guard) = SNAPSHOT.try_lock() else { return };
+    let Some(snapshot) = guard.as_ref() else { return };
+    let recovery = Recovery { crashed_at: Some(now), crash_report: Some(report_path), ..snapshot.recovery.clone() };
+    if let Err(e) = save_recovery(data_dir, &recovery) {
+        eprintln!("Failed to save unsent chat state: {e}");
+    }
+
+    if let Some((size, position, maximized)) = snapshot.window {
+        if let Ok(mut config) = crate::config::AppConfig::load() {
+            config.window_size = size;
+            config.window_position = position;
+            config.window_maximized = maximized;
+            let _ = config.save();
+        }
+    }
+}
+
+fn crash_report(info: &std::panic::PanicHookInfo<'_>, at: chrono::DateTime<chrono::Utc>) -> String {
+    let message = info
+        .payload()
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| info.payload().downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic payload".to_string());
+    let location = info.location().map_or_else(|| "unknown location".to_string(), |l| l.to_string());
+    let thread = std::thread::current().name().unwrap_or("unnamed").to_string();
+    format!(
+        "RIA AI Chat {} crashed at {}\nOS: {} {}\nThread: {thread}\nPanic: {message}\nLocation: {location}\n\nBacktrace:\n{}\n",
+        env!("CARGO_PKG_VERSION"),
+        at.to_rfc3339(),
+        std::env::consts::OS,
+        std::env::consts::ARCH,
+        std::backtrace::Backtrace::force_capture(),
+    )
+}
+
+fn save_recovery(data_dir: &Path, recovery: &Recovery) -> Result<()> {
+    std::fs::create_dir_all(data_dir)?;
+    let path = data_dir.join(RECOVERY_FILE);
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, serde_json::to_string(recovery)?)?;
+    std::fs::rename(&tmp, &path)?;
+    Ok(())
+}
+
+/// Remove state written by a panic that didn't end the process (one on a background
+/// thread), since the app is exiting normally and saved everything itself.
+pub fn discard_recovery() {
+    let Some(data_dir) = DATA_DIR.get() else { return };
+    let path = data_dir.join(RECOVERY_FILE);
+    if let Err(e) = std::fs::remove_file(&path) {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            tracing::warn!("Couldn't remove {}: {}", path.display(), e);
+        }
+    }
+}
+
+/// State left by a crash in the previous run, removing it so it is only offered once.
+pub fn take_recovery(data_dir: &Path) -> Option<Recovery> {
+    let path = data_dir.join(RECOVERY_FILE);
+    let content = std::fs::read_to_string(&path).ok()?;
+    if let Err(e) = std::fs::remove_file(&path) {
+        tracing::warn!("Couldn't remove {}: {}", path.display(), e);
+    }
+    match serde_json::from_str(&content) {
+        Ok(recovery) => Some(recovery),
+        Err(e) => {
+            tracing::warn!("Ignoring unreadable crash recovery file: {}", e);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recovery_is_taken_once() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(take_recovery(dir.path()).is_none());
+
+        let recovery = Recovery { draft: "half-written question".into(), partial_reply: Some("The answer".into()), ..Default::default() };
+        assert!(!recovery.is_empty());
+        save_recovery(dir.path(), &recovery).unwrap();
+        let restored = take_recovery(dir.path()).unwrap();
+        assert_eq!(restored.draft, recovery.draft);
+        assert_eq!(restored.partial_reply, recovery.partial_reply);
+        assert!(take_recovery(dir.path()).is_none());
+        assert!(Recovery::default().is_empty());
+    }
+
+    #[test]
+    fn test_catch_unwind_marks_the_thread_only_while_running() {
+        assert!(!CATCHING.with(Cell::get));
+        let result = catch_unwind(|| {
+            assert!(CATCHING.with(Cell::get));
+            panic!("caught");
+        });
+        assert!(result.is_err());
+        assert!(!CATCHING.with(Cell::get));
+    }
+}
@@ -0,0 +1,101 @@
+//! Secrets kept in the OS credential store (macOS Keychain, Windows Credential Manager, the
+//! Secret Service on Linux) rather than in the config file.
+//!
+//! The store is read once per secret and cached; reads happen on every download request and
+//! every frame the settings are open. Store calls run on a separate thread because the Secret
+//! Service backend drives its own async runtime, which can't start inside the app's.
+
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+const SERVICE: &str = "ria-ai-chat";
+
+/// A secret the app keeps in the credential store.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Credential {
+    HuggingFaceToken,
+    /// WebDAV password or S3 secret key used by sync.
+    SyncSecret,
+}
+
+impl Credential {
+    fn entry_name(self) -> &'static str {
+        match self {
+            Credential::HuggingFaceToken => "huggingface-token",
+            Credential::SyncSecret => "sync-secret",
+        }
+    }
+}
+
+/// Cached secrets; a credential is missing until the store has been read for it.
+static CACHE: Mutex<BTreeMap<Credential, Option<String>>> = Mutex::new(BTreeMap::new());
+
+fn off_runtime<T: Send>(f: impl FnOnce() -> T + Send) -> T {
+    std::thread::scope(|scope| scope.spawn(f).join().expect("credential store thread panicked"))
+}
+
+fn entry(credential: Credential) -> keyring::Result<keyring::Entry> {
+    keyring::Entry::new(SERVICE, credential.entry_name())
+}
+
+/// The stored secret, if any. The first read of each secret goes to the store and can block
+/// (the Secret Service may have to start or unlock); UI code uses [`cached`] instead.
+pub fn get(credential: Credential) -> Option<String> {
+    if let Some(secret) = cached(credential) {
+        return secret;
+    }
+    // The lock isn't held during the read, so `cached` never waits on the store
+    let secret = off_runtime(|| match entry(credential).and_then(|e| e.get_password()) {
+        Ok(secret) => Some(secret),
+        Err(keyring::Error::NoEntry) => None,
+        Err(e) => {
+            tracing::warn!("Couldn't read {} from the credential store: {}", credential.entry_name(), e);
+            None
+        }
+    });
+    let mut cache = CACHE.lock().ok()?;
+    cache.entry(credential).or_insert(secret).clone()
+}
+
+/// The secret if the store has already been read for it: `None` while that is still pending.
+pub fn cached(credential: Credential) -> Option<Option<String>> {
+    CACHE.lock().ok()?.get(&credential).cloned()
+}
+
+/// Read `credentials` from the store on a background thread so later lookups hit the cache.
+pub fn preload(credentials: &[Credential]) {
+    let credentials = credentials.to_vec();
+    std::thread::spawn(move || {
+        for credential in credentials {
+            get(credential);
+        }
+    });
+}
+
+pub fn set(credential: Credential, secret: &str) -> Result<()> {
+    let secret = secret.trim().to_string();
+    let stored = secret.clone();
+    off_runtime(move || entry(credential).and_then(|e| e.set_password(&stored)))
+        .context("Couldn't save the secret in the system credential store")?;
+    if let Ok(mut cache) = CACHE.lock() {
+        cache.insert(credential, Some(secret));
+    }
+    Ok(())
+}
+
+pub fn clear(credential: Credential) -> Result<()> {
+    match off_runtime(|| entry(credential).and_then(|e| e.delete_credential())) {
+        Ok(()) | Err(keyring::Error::NoEntry) => {}
+        Err(e) => return Err(e).context("Couldn't remove the secret from the system credential store"),
+    }
+    if let Ok(mut cache) = CACHE.lock() {
+        cache.insert(credential, None);
+    }
+    Ok(())
+}
+
+/// The stored Hugging Face token, if any.
+pub fn huggingface_token() -> Option<String> {
+    get(Credential::HuggingFaceToken)
+}
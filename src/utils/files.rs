@@ -39,18 +39,21 @@ pub fn safe_write<P: AsRef<Path>>(file_path: P, content: &str) -> Result<()> {
         backup_file(file_path)?;
     }
 
-    // Ensure parent directory exists
+    write_atomic(file_path, content)
+}
+
+/// Write `content` to `<file>.tmp` and move it over `file_path`, so readers (and a crash
+/// mid-write) see either the old file or the new one, never a partial one.
+pub fn write_atomic<P: AsRef<Path>>(file_path: P, content: &str) -> Result<()> {
+    let file_path = file_path.as_ref();
     if let Some(parent) = file_path.parent() {
         ensure_directory(parent)?;
     }
-
-    // Write to temporary file first
-    let temp_path = file_path.with_extension("tmp");
+    let mut temp_name = file_path.file_name().unwrap_or_default().to_os_string();
+    temp_name.push(".tmp");
+    let temp_path = file_path.with_file_name(temp_name);
     fs::write(&temp_path, content)?;
-
-    // Atomic move to final location
     fs::rename(&temp_path, file_path)?;
-    
     Ok(())
 }
 
@@ -168,6 +171,62 @@ pub fn compress_logs<P: AsRef<Path>>(log_dir: P) -> Result<()> {
     Ok(())
 }
 
+/// Model formats accepted by drag-and-drop import.
+pub const IMPORTABLE_MODEL_EXTENSIONS: &[&str] = &["onnx", "gguf"];
+
+pub fn is_importable_model<P: AsRef<Path>>(path: P) -> bool {
+    path.as_ref()
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| IMPORTABLE_MODEL_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+/// Bring a model file (plus any ONNX external-data sidecar next to it) into `dest_dir`.
+///
+/// Tries a hard link first so multi-GB models on the same volume import instantly, and
+/// falls back to copying through a temporary file. Returns the path of the imported model.
+pub fn import_model_file<P: AsRef<Path>, Q: AsRef<Path>>(source: P, dest_dir: Q) -> Result<PathBuf> {
+    let source = source.as_ref();
+    let dest_dir = dest_dir.as_ref();
+    let file_name = source
+        .file_name()
+        .ok_or_else(|| anyhow::anyhow!("Not a file: {:?}", source))?;
+    ensure_directory(dest_dir)?;
+
+    let dest = dest_dir.join(file_name);
+    if dest.exists() {
+        if fs::canonicalize(&dest).ok() == fs::canonicalize(source).ok() {
+            return Ok(dest); // Dropped a file that's already in the models directory
+        }
+        return Err(anyhow::anyhow!("{} already exists in {:?}", file_name.to_string_lossy(), dest_dir));
+    }
+
+    // External data is usually `model.onnx.data` or `model.onnx_data`
+    let name = file_name.to_string_lossy();
+    let sidecars = [format!("{name}.data"), format!("{name}_data")];
+    for sidecar in sidecars.iter().map(|s| source.with_file_name(s)).filter(|p| p.is_file()) {
+        link_or_copy(&sidecar, &dest_dir.join(sidecar.file_name().unwrap_or_default()))?;
+    }
+    link_or_copy(source, &dest)?;
+    Ok(dest)
+}
+
+fn link_or_copy(source: &Path, dest: &Path) -> Result<()> {
+    if fs::hard_link(source, dest).is_ok() {
+        return Ok(());
+    }
+    // Not `.part`: a download of the same file may be writing that right now
+    let name = dest.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    let temp_path = dest.with_file_name(format!(".{name}.linking-{}", std::process::id()));
+    if let Err(e) = fs::copy(source, &temp_path) {
+        let _ = fs::remove_file(&temp_path);
+        return Err(e.into());
+    }
+    fs::rename(&temp_path, dest)?;
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -218,4 +277,24 @@ mod tests {
         
         Ok(())
     }
+
+    #[test]
+    fn test_import_model_file_with_sidecar() -> Result<()> {
+        let src_dir = tempdir()?;
+        let models_dir = tempdir()?;
+        let model = src_dir.path().join("tiny.onnx");
+        fs::write(&model, "graph")?;
+        fs::write(src_dir.path().join("tiny.onnx.data"), "weights")?;
+
+        assert!(is_importable_model(&model));
+        assert!(!is_importable_model(src_dir.path().join("notes.txt")));
+
+        let imported = import_model_file(&model, models_dir.path())?;
+        assert_eq!(fs::read_to_string(&imported)?, "graph");
+        assert!(models_dir.path().join("tiny.onnx.data").exists());
+
+        // Importing the imported file again is a no-op
+        assert_eq!(import_model_file(&imported, models_dir.path())?, imported);
+        Ok(())
+    }
 }
\ No newline at end of file
@@ -0,0 +1,91 @@
+//! Keeps the last lines of the tracing output in memory for diagnostics reports.
+//!
+//! `LogTail` is a `MakeWriter` for `tracing_subscriber::fmt`: every formatted event goes to
+//! stdout as before and its text (without ANSI colors) is also added to a bounded buffer.
+
+use std::collections::VecDeque;
+use std::io::{self, Write};
+use std::sync::Mutex;
+
+const MAX_LINES: usize = 300;
+
+static LINES: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+/// The most recent log lines, oldest first.
+pub fn recent_lines() -> Vec<String> {
+    LINES.lock().map(|lines| lines.iter().cloned().collect()).unwrap_or_default()
+}
+
+fn push_line(line: &str) {
+    if let Ok(mut lines) = LINES.lock() {
+        lines.push_back(strip_ansi(line));
+        while lines.len() > MAX_LINES {
+            lines.pop_front();
+        }
+    }
+}
+
+/// Remove terminal color sequences (`ESC [ ... m`).
+fn strip_ansi(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\u{1b}' {
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct LogTail;
+
+impl<'a> tracing_subscriber::fmt::MakeWriter<'a> for LogTail {
+    type Writer = LogTailWriter;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        LogTailWriter { buffer: Vec::new() }
+    }
+}
+
+/// Writes one formatted event; the captured text is stored when the writer is dropped.
+pub struct LogTailWriter {
+    buffer: Vec<u8>,
+}
+
+impl Write for LogTailWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        io::stdout().write_all(buf)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        io::stdout().flush()
+    }
+}
+
+impl Drop for LogTailWriter {
+    fn drop(&mut self) {
+        let text = String::from_utf8_lossy(&self.buffer);
+        for line in text.lines().filter(|l| !l.trim().is_empty()) {
+            push_line(line);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_ansi() {
+        assert_eq!(strip_ansi("\u{1b}[32m INFO\u{1b}[0m ria: loaded"), " INFO ria: loaded");
+    }
+}
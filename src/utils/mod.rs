@@ -1,5 +1,10 @@
 pub mod system;
 pub mod files;
+pub mod diagnostics;
+pub mod log_tail;
+pub mod openvino;
+pub mod crash;
+pub mod credentials;
 
 use std::path::Path;
 
@@ -0,0 +1,192 @@
+//! OpenVINO device discovery.
+//!
+//! The OpenVINO execution provider accepts device strings such as `"CPU"`, `"GPU.1"` or
+//! `"AUTO:NPU,CPU"`. Rather than guessing from environment variables, the OpenVINO C API
+//! (`openvino_c`, shipped with both the OpenVINO runtime and the ONNX Runtime OpenVINO
+//! builds) is loaded and asked which devices it can actually use.
+
+use anyhow::{anyhow, Result};
+use std::ffi::{c_char, c_int, c_void, CStr, CString};
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+#[cfg(target_os = "windows")]
+const LIBRARY: &str = "openvino_c.dll";
+#[cfg(target_os = "macos")]
+const LIBRARY: &str = "libopenvino_c.dylib";
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+const LIBRARY: &str = "libopenvino_c.so";
+
+/// Preference order for the `AUTO` device: dedicated accelerators before the CPU.
+const AUTO_PRIORITY: [&str; 3] = ["NPU", "GPU", "CPU"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpenVinoDevice {
+    /// Device string for the execution provider, e.g. "GPU.0".
+    pub name: String,
+    /// Marketing name reported by the plugin, e.g. "Intel(R) AI Boost".
+    pub full_name: Option<String>,
+}
+
+impl OpenVinoDevice {
+    pub fn label(&self) -> String {
+        match &self.full_name {
+            Some(full_name) => format!("{} ({})", full_name, self.name),
+            None => self.name.clone(),
+        }
+    }
+}
+
+static DEVICES: OnceLock<Vec<OpenVinoDevice>> = OnceLock::new();
+
+/// Devices OpenVINO reports, queried once per run. Empty if OpenVINO isn't installed.
+///
+/// The first call loads `openvino_c` and creates an OpenVINO core, which can take seconds;
+/// UI code uses [`devices_if_ready`] after [`probe_in_background`] instead.
+pub fn devices() -> &'static [OpenVinoDevice] {
+    DEVICES.get_or_init(|| match query_devices() {
+        Ok(devices) => {
+            tracing::info!("OpenVINO devices: {:?}", devices.iter().map(|d| &d.name).collect::<Vec<_>>());
+            devices
+        }
+        Err(e) => {
+            tracing::debug!("OpenVINO device query unavailable: {}", e);
+            Vec::new()
+        }
+    })
+}
+
+/// The devices if the query has finished, without waiting for it.
+pub fn devices_if_ready() -> Option<&'static [OpenVinoDevice]> {
+    DEVICES.get().map(Vec::as_slice)
+}
+
+/// Start the device query on a background thread so that it is ready by the time the UI asks.
+pub fn probe_in_background() {
+    if DEVICES.get().is_none() {
+        std::thread::spawn(|| {
+            devices();
+        });
+    }
+}
+
+/// `AUTO:` device string over the discovered devices, accelerators first, e.g.
+/// `"AUTO:NPU,GPU.0,CPU"`. `None` if there are no devices.
+pub fn auto_device_string(devices: &[OpenVinoDevice]) -> Option<String> {
+    let mut ordered: Vec<&str> = Vec::new();
+    for kind in AUTO_PRIORITY {
+        ordered.extend(
+            devices
+                .iter()
+                .map(|d| d.name.as_str())
+                .filter(|name| name.split('.').next() == Some(kind)),
+        );
+    }
+    (!ordered.is_empty()).then(|| format!("AUTO:{}", ordered.join(",")))
+}
+
+/// Where `openvino_c` may live: the OpenVINO install named by `INTEL_OPENVINO_DIR`, next to
+/// the ONNX Runtime library, and finally the system library search path.
+fn library_candidates() -> Vec<PathBuf> {
+    let mut candidates = Vec::new();
+    if let Some(root) = std::env::var_os("INTEL_OPENVINO_DIR").map(PathBuf::from) {
+        let lib_dir = if cfg!(target_os = "windows") {
+            root.join("runtime").join("bin").join("intel64").join("Release")
+        } else if cfg!(target_os = "macos") {
+            root.join("runtime").join("lib").join(std::env::consts::ARCH).join("Release")
+        } else {
+            root.join("runtime").join("lib").join("intel64")
+        };
+        candidates.push(lib_dir.join(LIBRARY));
+    }
+    if let Some(dir) = crate::ai::runtime::ort_library_path().parent().filter(|d| !d.as_os_str().is_empty()) {
+        candidates.push(dir.join(LIBRARY));
+    }
+    candidates.push(PathBuf::from(LIBRARY));
+    candidates
+}
+
+#[repr(C)]
+struct AvailableDevices {
+    devices: *mut *mut c_char,
+    size: usize,
+}
+
+type CoreCreate = unsafe extern "C" fn(*mut *mut c_void) -> c_int;
+type CoreFree = unsafe extern "C" fn(*mut c_void);
+type GetAvailableDevices = unsafe extern "C" fn(*const c_void, *mut AvailableDevices) -> c_int;
+type AvailableDevicesFree = unsafe extern "C" fn(*mut AvailableDevices);
+type GetProperty = unsafe extern "C" fn(*const c_void, *const c_char, *const c_char, *mut *mut c_char) -> c_int;
+type Free = unsafe extern "C" fn(*const c_char);
+
+/// Ask the OpenVINO runtime for its devices through the C API.
+pub fn query_devices() -> Result<Vec<OpenVinoDevice>> {
+    // SAFETY: the symbols are the stable OpenVINO 2022.1+ C API; every pointer it hands out is
+    // released with the matching free function before the library is dropped.
+    unsafe {
+        let library = library_candidates()
+            .into_iter()
+            .find_map(|path| libloading::Library::new(&path).ok())
+            .ok_or_else(|| anyhow!("{LIBRARY} not found"))?;
+        let core_create: libloading::Symbol<CoreCreate> = library.get(b"ov_core_create")?;
+        let core_free: libloading::Symbol<CoreFree> = library.get(b"ov_core_free")?;
+        let get_devices: libloading::Symbol<GetAvailableDevices> = library.get(b"ov_core_get_available_devices")?;
+        let devices_free: libloading::Symbol<AvailableDevicesFree> = library.get(b"ov_available_devices_free")?;
+        let get_property: libloading::Symbol<GetProperty> = library.get(b"ov_core_get_property")?;
+        let free: libloading::Symbol<Free> = library.get(b"ov_free")?;
+
+        let mut core = std::ptr::null_mut();
+        let status = core_create(&mut core);
+        if status != 0 || core.is_null() {
+            return Err(anyhow!("ov_core_create failed with status {status}"));
+        }
+
+        let mut available = AvailableDevices { devices: std::ptr::null_mut(), size: 0 };
+        let status = get_devices(core, &mut available);
+        let mut devices = Vec::new();
+        if status == 0 && !available.devices.is_null() {
+            let full_name_key = CString::new("FULL_DEVICE_NAME")?;
+            for i in 0..available.size {
+                let name_ptr = *available.devices.add(i);
+                if name_ptr.is_null() {
+                    continue;
+                }
+                let mut value = std::ptr::null_mut();
+                let full_name = (get_property(core, name_ptr, full_name_key.as_ptr(), &mut value) == 0 && !value.is_null())
+                    .then(|| {
+                        let full_name = CStr::from_ptr(value).to_string_lossy().trim().to_string();
+                        free(value);
+                        full_name
+                    })
+                    .filter(|n| !n.is_empty());
+                devices.push(OpenVinoDevice { name: CStr::from_ptr(name_ptr).to_string_lossy().into_owned(), full_name });
+            }
+            devices_free(&mut available);
+        }
+        core_free(core);
+        if status != 0 {
+            return Err(anyhow!("ov_core_get_available_devices failed with status {status}"));
+        }
+        Ok(devices)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn device(name: &str) -> OpenVinoDevice {
+        OpenVinoDevice { name: name.to_string(), full_name: None }
+    }
+
+    #[test]
+    fn test_auto_device_string_puts_accelerators_first() {
+        let devices = [device("CPU"), device("GPU.0"), device("GPU.1"), device("NPU")];
+        assert_eq!(auto_device_string(&devices).as_deref(), Some("AUTO:NPU,GPU.0,GPU.1,CPU"));
+        assert_eq!(auto_device_string(&[device("CPU")]).as_deref(), Some("AUTO:CPU"));
+        assert_eq!(auto_device_string(&[]), None);
+
+        let npu = OpenVinoDevice { name: "NPU".into(), full_name: Some("Intel(R) AI Boost".into()) };
+        assert_eq!(npu.label(), "Intel(R) AI Boost (NPU)");
+    }
+}
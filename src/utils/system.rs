@@ -1,5 +1,17 @@
 use sysinfo::System;
 use std::collections::HashMap;
+use crate::ai::ExecutionProvider;
+
+/// A concrete device an execution provider can be pinned to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComputeDevice {
+    pub label: String,
+    pub execution_provider: ExecutionProvider,
+    /// Device index for CUDA / DirectML.
+    pub device_id: Option<i32>,
+    /// Device string for OpenVINO (e.g. "GPU", "NPU").
+    pub device_type: Option<String>,
+}
 
 pub struct SystemInfo {
     system: System,
@@ -160,21 +172,33 @@ impl SystemInfo {
 
     fn detect_qualcomm_npu(&self) -> bool {
         // Check for Qualcomm NPU (Snapdragon platforms)
-        std::path::Path::new("C:\\Windows\\System32\\QnnHtp.dll").exists() ||
-        std::path::Path::new("C:\\Windows\\System32\\QnnCpu.dll").exists()
+        Self::qnn_backend_path().is_some()
     }
 
-    fn detect_intel_npu(&self) -> bool {
-        // Heuristic checks for Intel NPU / OpenVINO presence
-        if cfg!(target_os = "windows") {
-            std::env::var("INTEL_OPENVINO_DIR").is_ok()
-                || std::path::Path::new("C:\\Program Files\\Intel\\openvino").exists()
-                || std::path::Path::new("C:\\Program Files (x86)\\Intel\\openvino").exists()
+    /// Locate the QNN HTP (NPU) backend library, falling back to the QNN CPU backend.
+    pub fn qnn_backend_path() -> Option<std::path::PathBuf> {
+        let candidates: &[&str] = if cfg!(target_os = "windows") {
+            &["C:\\Windows\\System32\\QnnHtp.dll", "C:\\Windows\\System32\\QnnCpu.dll"]
         } else {
-            std::env::var("INTEL_OPENVINO_DIR").is_ok()
-                || std::path::Path::new("/usr/lib/libopenvino.so").exists()
-                || std::path::Path::new("/opt/intel/openvino").exists()
+            &["/vendor/lib64/libQnnHtp.so", "/usr/lib/libQnnHtp.so", "/vendor/lib64/libQnnCpu.so", "/usr/lib/libQnnCpu.so"]
+        };
+        if let Ok(sdk) = std::env::var("QNN_SDK_ROOT") {
+            let lib = if cfg!(target_os = "windows") { "lib/aarch64-windows-msvc/QnnHtp.dll" } else { "lib/aarch64-android/libQnnHtp.so" };
+            let path = std::path::Path::new(&sdk).join(lib);
+            if path.exists() {
+                return Some(path);
+            }
         }
+        candidates.iter().map(std::path::PathBuf::from).find(|p| p.exists())
+    }
+
+    /// NNAPI is only meaningful on Android.
+    pub fn has_nnapi() -> bool {
+        cfg!(target_os = "android")
+    }
+
+    fn detect_intel_npu(&self) -> bool {
+        crate::utils::openvino::devices().iter().any(|d| d.name.starts_with("NPU"))
     }
 
     pub fn get_available_compute_devices(&self) -> Vec<String> {
@@ -187,14 +211,92 @@ impl SystemInfo {
             }
         }
         
-        // Add NPU if available
-        if self.has_npu() {
+        // Add NPU if available, without waiting for the OpenVINO device query (this runs every frame)
+        let intel_npu = crate::utils::openvino::devices_if_ready()
+            .is_some_and(|devices| devices.iter().any(|d| d.name.starts_with("NPU")));
+        if self.detect_qualcomm_npu() || intel_npu {
             devices.push("NPU".to_string());
         }
         
         devices
     }
 
+    /// Enumerate devices that can be targeted explicitly per execution provider (GPU 0/1, iGPU, NPU).
+    pub fn enumerate_compute_devices(&self) -> Vec<ComputeDevice> {
+        let mut devices = Vec::new();
+
+        // NVIDIA GPUs. nvidia-smi numbers them by PCI bus; CUDA only does with
+        // CUDA_DEVICE_ORDER=PCI_BUS_ID (set at startup unless the user chose another order),
+        // otherwise its ids wouldn't pick the GPU listed under them
+        if !crate::ai::cuda::numbers_devices_like_nvidia_smi() {
+            tracing::info!("CUDA_DEVICE_ORDER isn't PCI_BUS_ID; not offering individual CUDA GPUs");
+        } else if let Ok(output) = std::process::Command::new("nvidia-smi")
+            .args(["--query-gpu=index,name", "--format=csv,noheader"])
+            .output()
+        {
+            if output.status.success() {
+                let output_str = String::from_utf8_lossy(&output.stdout);
+                for line in output_str.lines() {
+                    if let Some((index, name)) = line.split_once(", ") {
+                        if let Ok(id) = index.trim().parse::<i32>() {
+                            devices.push(ComputeDevice {
+                                label: format!("GPU {}: {}", id, name.trim()),
+                                execution_provider: ExecutionProvider::Cuda,
+                                device_id: Some(id),
+                                device_type: None,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+
+        // DirectML adapters (enumeration order matches the DXGI adapter index)
+        for (id, name) in self.list_windows_adapters().into_iter().enumerate() {
+            devices.push(ComputeDevice {
+                label: format!("GPU {}: {}", id, name),
+                execution_provider: ExecutionProvider::DirectML,
+                device_id: Some(id as i32),
+                device_type: None,
+            });
+        }
+
+        // OpenVINO devices, as reported by the OpenVINO runtime itself once its query has finished
+        for device in crate::utils::openvino::devices_if_ready().unwrap_or_default() {
+            devices.push(ComputeDevice {
+                label: format!("{} (OpenVINO)", device.label()),
+                execution_provider: ExecutionProvider::OpenVINO,
+                device_id: None,
+                device_type: Some(device.name.clone()),
+            });
+        }
+
+        devices
+    }
+
+    #[cfg(target_os = "windows")]
+    fn list_windows_adapters(&self) -> Vec<String> {
+        let mut adapters = Vec::new();
+        if let Ok(output) = std::process::Command::new("wmic")
+            .args(&["path", "win32_VideoController", "get", "name"])
+            .output()
+        {
+            if output.status.success() {
+                let output_str = String::from_utf8_lossy(&output.stdout);
+                for line in output_str.lines() {
+                    let line = line.trim();
+                    if !line.is_empty() && line != "Name" {
+                        adapters.push(line.to_string());
+                    }
+                }
+            }
+        }
+        adapters
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    fn list_windows_adapters(&self) -> Vec<String> { Vec::new() }
+
     pub fn get_system_summary(&self) -> String {
         let cpu_info = self.get_cpu_info();
         let mem_info = self.get_memory_info();
@@ -0,0 +1,197 @@
+//! Local stand-in for a model host, for download tests.
+//!
+//! Serves the same synthetic model bytes at every path with an ETag, honours `Range: bytes=N-`
+//! requests (and `If-Range`), and applies queued [`Fault`]s to the next requests in order, so tests can interrupt,
+//! corrupt or refuse a download at a known point. Every request is recorded.
+#![allow(dead_code)]
+
+use bytes::Bytes;
+use futures_util::StreamExt;
+use http_body_util::{combinators::BoxBody, BodyExt, Full, StreamBody};
+use hyper::body::Frame;
+use hyper::header::{CONTENT_LENGTH, CONTENT_RANGE, ETAG, IF_RANGE, RANGE};
+use hyper::{Request, Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use sha2::{Digest, Sha256};
+use std::collections::VecDeque;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+/// Deterministic model bytes of the given length.
+pub fn synthetic_model(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i.wrapping_mul(31).wrapping_add(i / 251) % 256) as u8).collect()
+}
+
+/// What goes wrong with one request.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Fault {
+    /// Send this many bytes of the body, then drop the connection.
+    DisconnectAfter(usize),
+    /// Send the bytes with one of them flipped, so the digest doesn't match.
+    Corrupt,
+    /// Answer a Range request with the whole file and 200, as servers without resume do.
+    IgnoreRange,
+    /// Answer with this status and no body.
+    Status(u16),
+    /// Serve the file under a new ETag, as if it had been replaced.
+    Replaced,
+}
+
+/// A request the server received.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Served {
+    pub path: String,
+    /// Start of the requested range, for `Range: bytes=N-` requests.
+    pub range_start: Option<u64>,
+    /// The `If-Range` header sent with it.
+    pub if_range: Option<String>,
+}
+
+#[derive(Default)]
+struct State {
+    faults: VecDeque<Fault>,
+    served: Vec<Served>,
+    /// Times the file was replaced; part of its ETag.
+    version: u32,
+}
+
+pub struct FakeServer {
+    addr: SocketAddr,
+    payload: Arc<Vec<u8>>,
+    state: Arc<Mutex<State>>,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl FakeServer {
+    /// Listen on a free local port, serving `payload`.
+    pub async fn start(payload: Vec<u8>) -> Self {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.expect("bind fake server");
+        let addr = listener.local_addr().expect("fake server address");
+        let payload = Arc::new(payload);
+        let state = Arc::new(Mutex::new(State::default()));
+        let (task_payload, task_state) = (payload.clone(), state.clone());
+        let task = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let (payload, state) = (task_payload.clone(), task_state.clone());
+                tokio::spawn(async move {
+                    let service = hyper::service::service_fn(move |request| {
+                        let response = respond(&payload, &state, &request);
+                        async move { Ok::<_, Infallible>(response) }
+                    });
+                    // Injected disconnects end connections with an error; that's the point
+                    let _ = hyper::server::conn::http1::Builder::new().serve_connection(TokioIo::new(stream), service).await;
+                });
+            }
+        });
+        Self { addr, payload, state, task }
+    }
+
+    pub fn url(&self, path: &str) -> String {
+        format!("http://{}/{}", self.addr, path.trim_start_matches('/'))
+    }
+
+    pub fn payload(&self) -> &[u8] {
+        &self.payload
+    }
+
+    /// Hex SHA-256 of the payload, as a catalog would list it.
+    pub fn sha256(&self) -> String {
+        hex::encode(Sha256::digest(self.payload.as_slice()))
+    }
+
+    /// ETag the file is served with now.
+    pub fn etag(&self) -> String {
+        etag(&self.payload, self.state.lock().unwrap().version)
+    }
+
+    /// Apply `fault` to the next request that hasn't got one yet.
+    pub fn fault(&self, fault: Fault) {
+        self.state.lock().unwrap().faults.push_back(fault);
+    }
+
+    /// Requests received so far, oldest first.
+    pub fn served(&self) -> Vec<Served> {
+        self.state.lock().unwrap().served.clone()
+    }
+}
+
+impl Drop for FakeServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+type Body = BoxBody<Bytes, std::io::Error>;
+
+fn full(bytes: Vec<u8>) -> Body {
+    Full::new(Bytes::from(bytes)).map_err(|never| match never {}).boxed()
+}
+
+fn etag(payload: &[u8], version: u32) -> String {
+    format!("\"{}-{version}\"", &hex::encode(Sha256::digest(payload))[..16])
+}
+
+fn respond<B>(payload: &[u8], state: &Mutex<State>, request: &Request<B>) -> Response<Body> {
+    let range_start = request
+        .headers()
+        .get(RANGE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("bytes="))
+        .and_then(|v| v.strip_suffix('-'))
+        .and_then(|v| v.parse::<u64>().ok());
+    let if_range = request.headers().get(IF_RANGE).and_then(|v| v.to_str().ok()).map(str::to_string);
+    let (fault, etag) = {
+        let mut state = state.lock().unwrap();
+        state.served.push(Served { path: request.uri().path().to_string(), range_start, if_range: if_range.clone() });
+        let fault = state.faults.pop_front();
+        if fault == Some(Fault::Replaced) {
+            state.version += 1;
+        }
+        (fault, etag(payload, state.version))
+    };
+    let total = payload.len() as u64;
+    let response = Response::builder();
+
+    if let Some(Fault::Status(status)) = fault {
+        return response.status(status).body(full(Vec::new())).unwrap();
+    }
+    let response = response.header(ETAG, &etag);
+    // A range of another version of the file is no use: send all of this one
+    let range_start = range_start.filter(|_| fault != Some(Fault::IgnoreRange) && if_range.as_ref().is_none_or(|v| *v == etag));
+    let (response, start) = match range_start {
+        Some(start) if start >= total => {
+            return response
+                .status(StatusCode::RANGE_NOT_SATISFIABLE)
+                .header(CONTENT_RANGE, format!("bytes */{total}"))
+                .body(full(Vec::new()))
+                .unwrap();
+        }
+        Some(start) => (
+            response.status(StatusCode::PARTIAL_CONTENT).header(CONTENT_RANGE, format!("bytes {start}-{}/{total}", total - 1)),
+            start as usize,
+        ),
+        None => (response.status(StatusCode::OK), 0),
+    };
+    let mut bytes = payload[start..].to_vec();
+    let response = response.header(CONTENT_LENGTH, bytes.len());
+    match fault {
+        Some(Fault::DisconnectAfter(sent)) => {
+            bytes.truncate(sent);
+            let sent = futures_util::stream::iter([Ok(Frame::data(Bytes::from(bytes)))]);
+            // Give the bytes time to reach the client; hyper drops unflushed output on an error
+            let disconnect = futures_util::stream::once(async {
+                tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+                Err(std::io::Error::new(std::io::ErrorKind::ConnectionReset, "injected disconnect"))
+            });
+            response.body(BodyExt::boxed(StreamBody::new(sent.chain(disconnect)))).unwrap()
+        }
+        Some(Fault::Corrupt) => {
+            if let Some(byte) = bytes.last_mut() {
+                *byte ^= 0xff;
+            }
+            response.body(full(bytes)).unwrap()
+        }
+        _ => response.body(full(bytes)).unwrap(),
+    }
+}
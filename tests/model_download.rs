@@ -0,0 +1,113 @@
+//! ModelManager downloads against a local fake server: resuming after a dropped connection,
+//! restarting when the file changed meanwhile, digest checks, mirror fallback, retries and
+//! repairs. Runs offline.
+//! cargo test --test model_download
+use ria::ai::models::{DownloadRetry, ModelManager};
+mod fake_server;
+use fake_server::{synthetic_model, FakeServer, Fault, Served};
+
+const MODEL_SIZE: usize = 256 * 1024;
+
+fn served(path: &str, range_start: Option<u64>, if_range: Option<&str>) -> Served {
+    Served { path: path.into(), range_start, if_range: if_range.map(str::to_string) }
+}
+
+/// Download from `urls` into a fresh directory, returning the result and the retries reported.
+async fn download(urls: &[String], sha256: Option<&str>) -> (tempfile::TempDir, anyhow::Result<std::path::PathBuf>, Vec<DownloadRetry>) {
+    let dir = tempfile::tempdir().unwrap();
+    let mut manager = ModelManager::new(dir.path()).unwrap();
+    let mut retries = Vec::new();
+    let result = manager.download_model_from_mirrors(urls, "synthetic", sha256, |_, _, _| {}, |retry| retries.push(retry)).await;
+    (dir, result, retries)
+}
+
+#[tokio::test]
+async fn dropped_connection_resumes_from_the_part_file() {
+    let server = FakeServer::start(synthetic_model(MODEL_SIZE)).await;
+    server.fault(Fault::DisconnectAfter(100_000));
+    let (_dir, result, retries) = download(&[server.url("model.onnx")], Some(&server.sha256())).await;
+
+    let path = result.expect("the download resumes");
+    assert_eq!(std::fs::read(&path).unwrap(), server.payload());
+    let etag = server.etag();
+    assert_eq!(server.served(), [served("/model.onnx", None, None), served("/model.onnx", Some(100_000), Some(&etag))]);
+    assert_eq!(retries.iter().map(|r| (r.attempt, r.url_index)).collect::<Vec<_>>(), [(2, 0)]);
+    assert!(!path.with_extension("onnx.part").exists());
+}
+
+#[tokio::test]
+async fn a_file_replaced_meanwhile_is_downloaded_again_whole() {
+    let server = FakeServer::start(synthetic_model(MODEL_SIZE)).await;
+    let old_etag = server.etag();
+    server.fault(Fault::DisconnectAfter(100_000));
+    server.fault(Fault::Replaced);
+    let (_dir, result, _) = download(&[server.url("model.onnx")], Some(&server.sha256())).await;
+
+    assert_eq!(std::fs::read(result.expect("the download restarts")).unwrap(), server.payload());
+    assert_ne!(server.etag(), old_etag);
+    // The resume named the old version, so the server sent the new one from the start
+    assert_eq!(server.served()[1], served("/model.onnx", Some(100_000), Some(&old_etag)));
+    assert_eq!(server.served().len(), 2);
+}
+
+#[tokio::test]
+async fn wrong_digest_is_discarded_and_the_next_mirror_used() {
+    let server = FakeServer::start(synthetic_model(MODEL_SIZE)).await;
+    server.fault(Fault::Corrupt);
+    let urls = [server.url("primary.onnx"), server.url("mirror.onnx")];
+    let (_dir, result, retries) = download(&urls, Some(&server.sha256())).await;
+
+    assert_eq!(std::fs::read(result.expect("the mirror has good bytes")).unwrap(), server.payload());
+    // A checksum mismatch isn't retried on the same URL, and the bad bytes aren't resumed
+    assert_eq!(server.served(), [served("/primary.onnx", None, None), served("/mirror.onnx", None, None)]);
+    assert_eq!(retries.len(), 1);
+    assert_eq!((retries[0].attempt, retries[0].url_index), (1, 1));
+    assert!(retries[0].error.contains("SHA256 mismatch"), "{}", retries[0].error);
+
+    // Without another mirror the download fails
+    server.fault(Fault::Corrupt);
+    let (_dir, result, retries) = download(&[server.url("primary.onnx")], Some(&server.sha256())).await;
+    assert!(format!("{:#}", result.unwrap_err()).contains("SHA256 mismatch"));
+    assert!(retries.is_empty());
+}
+
+#[tokio::test]
+async fn server_errors_are_retried_and_missing_files_are_not() {
+    let server = FakeServer::start(synthetic_model(MODEL_SIZE)).await;
+    server.fault(Fault::DisconnectAfter(50_000));
+    server.fault(Fault::Status(503));
+    // After the outage the server forgets about ranges; the download starts over
+    server.fault(Fault::IgnoreRange);
+    let (_dir, result, retries) = download(&[server.url("model.onnx")], Some(&server.sha256())).await;
+
+    assert_eq!(std::fs::read(result.expect("the third attempt succeeds")).unwrap(), server.payload());
+    assert_eq!(
+        server.served().iter().map(|s| s.range_start).collect::<Vec<_>>(),
+        [None, Some(50_000), Some(50_000)]
+    );
+    assert_eq!(retries.iter().map(|r| r.attempt).collect::<Vec<_>>(), [2, 3]);
+
+    server.fault(Fault::Status(404));
+    let (_dir, result, retries) = download(&[server.url("gone.onnx")], None).await;
+    assert!(result.is_err());
+    assert!(retries.is_empty(), "a 404 won't change on retry");
+}
+
+#[tokio::test]
+async fn a_failed_repair_leaves_the_model_in_place() {
+    let server = FakeServer::start(synthetic_model(MODEL_SIZE)).await;
+    let (dir, result, _) = download(&[server.url("model.onnx")], Some(&server.sha256())).await;
+    let path = result.expect("the download succeeds");
+    let damaged = &server.payload()[..100_000];
+    std::fs::write(&path, damaged).unwrap();
+    let mut manager = ModelManager::new(dir.path()).unwrap();
+
+    server.fault(Fault::Corrupt);
+    let error = manager.repair_model(&path, None::<fn(u64, u64, f64)>).await.unwrap_err();
+    assert!(format!("{error:#}").contains("SHA256 mismatch"), "{error:#}");
+    assert_eq!(std::fs::read(&path).unwrap(), damaged);
+
+    manager.repair_model(&path, None::<fn(u64, u64, f64)>).await.expect("the repair succeeds");
+    assert_eq!(std::fs::read(&path).unwrap(), server.payload());
+    assert_eq!(server.served().last().unwrap().range_start, Some(100_000));
+}
@@ -1,7 +1,7 @@
 //! EP fallback simulation test.
 //! Forces an invalid preferred EP then ensures CPU fallback succeeds.
-use ria_ai_chat::ai::{InferenceConfig, ExecutionProvider};
-use ria_ai_chat::ai::providers::OnnxProvider;
+use ria::ai::{InferenceConfig, ExecutionProvider};
+use ria::ai::providers::OnnxProvider;
 mod common; use common::discover_test_model as test_model_path;
 
 #[test]
@@ -18,4 +18,16 @@ fn ep_fallback_to_cpu() {
     }
     assert!(provider.is_model_loaded(), "Provider did not end up loaded after fallback simulation");
     assert!(matches!(provider.loaded_execution_provider(), Some(ExecutionProvider::Cpu) | Some(ExecutionProvider::Cuda)), "Unexpected final EP");
-}
\ No newline at end of file
+}
+
+#[test]
+fn unsupported_npu_ep_falls_back_to_cpu() {
+    // NNAPI only exists on Android builds with `nnapi_ep`; elsewhere it must degrade to CPU.
+    if OnnxProvider::ep_supported(&ExecutionProvider::NNAPI) { eprintln!("SKIP: NNAPI is available on this platform"); return; }
+    let Some(model_path) = test_model_path() else { eprintln!("SKIP: no test model available for NNAPI fallback test"); return; };
+    let cfg = InferenceConfig { model_path, execution_provider: ExecutionProvider::NNAPI, prefer_npu: false, ..InferenceConfig::default() };
+    let mut provider = OnnxProvider::new(cfg).expect("create provider");
+    provider.load_model().expect("load with CPU fallback");
+    assert_eq!(provider.loaded_execution_provider(), Some(&ExecutionProvider::Cpu));
+    assert!(provider.last_ep_error_message().is_some(), "Fallback reason should be recorded");
+}
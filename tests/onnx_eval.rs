@@ -0,0 +1,89 @@
+//! Multi-turn prompt evaluation harness.
+//! Opt-in: RIA_EVAL=1 RIA_TEST_ONNX_MODEL=path cargo test --test onnx_eval -- --nocapture
+//! Replays fixed chat transcripts through OnnxProvider, each twice on a fresh provider, and checks
+//! that greedy decoding is deterministic (same reply tokens, same text), that prompt token counts
+//! grow turn by turn and stay inside the context window, and that every turn finishes within
+//! RIA_EVAL_MAX_TURN_MS (default 30000). Needs a model that is decoded token by token, i.e. one
+//! with its tokenizer.json alongside; other models are skipped, as their replies aren't generated.
+use std::time::{Duration, Instant};
+use ria::ai::{AIProvider, ChatMessage, ExecutionProvider, InferenceConfig};
+use ria::ai::providers::OnnxProvider;
+mod common;
+
+struct Transcript {
+    name: &'static str,
+    /// User turns, sent one after the other with the model's replies in between.
+    turns: &'static [&'static str],
+}
+
+const TRANSCRIPTS: [Transcript; 3] = [
+    Transcript { name: "greeting", turns: &["Hello!", "How are you today?", "Thanks, bye."] },
+    Transcript {
+        name: "follow-up",
+        turns: &["What is the capital of France?", "And roughly how many people live there?", "Name one famous museum in it."],
+    },
+    Transcript {
+        name: "code",
+        turns: &["Write a Rust function that adds two numbers.", "Now make it generic over numeric types.", "Add a unit test for it."],
+    },
+];
+
+struct Turn {
+    reply: String,
+    prompt_tokens: usize,
+    reply_tokens: u32,
+    elapsed: Duration,
+}
+
+fn eval_config(model_path: &str) -> InferenceConfig {
+    // Greedy decoding, so the same transcript must give the same replies
+    InferenceConfig { model_path: model_path.to_string(), execution_provider: ExecutionProvider::Cpu, temperature: 0.0, top_k: 1, max_tokens: 64, ..InferenceConfig::default() }
+}
+
+/// Play `transcript` on a freshly loaded provider, feeding each reply back into the history.
+fn replay(config: &InferenceConfig, transcript: &Transcript) -> Vec<Turn> {
+    let mut provider = OnnxProvider::new(config.clone()).expect("create provider");
+    provider.load_model().expect("load model");
+    let mut history = Vec::new();
+    transcript.turns.iter().map(|turn| {
+        history.push(ChatMessage::user(*turn));
+        let started = Instant::now();
+        let reply = provider.generate_response(&history).unwrap_or_else(|e| panic!("[{}] turn {turn:?} failed: {e:#}", transcript.name));
+        let elapsed = started.elapsed();
+        let trace = provider.last_trace().unwrap_or_else(|| panic!("[{}] turn {turn:?} left no trace", transcript.name));
+        let reply_tokens = trace.reply_tokens.unwrap_or_else(|| panic!("[{}] turn {turn:?} wasn't decoded", transcript.name));
+        history.push(ChatMessage::assistant(reply.as_str()));
+        Turn { reply, prompt_tokens: trace.token_ids.len(), reply_tokens, elapsed }
+    }).collect()
+}
+
+#[test]
+fn multi_turn_transcripts_are_stable() {
+    if std::env::var("RIA_EVAL").ok().as_deref() != Some("1") { eprintln!("SKIP: set RIA_EVAL=1 to run the evaluation harness"); return; }
+    let Some(model_path) = common::discover_test_model() else { eprintln!("SKIP: no test model available for evaluation"); return; };
+    let max_turn = Duration::from_millis(std::env::var("RIA_EVAL_MAX_TURN_MS").ok().and_then(|v| v.parse().ok()).unwrap_or(30_000));
+    let config = eval_config(&model_path);
+    let mut probe = OnnxProvider::new(config.clone()).expect("create provider");
+    probe.load_model().expect("load model");
+    if !probe.can_decode() { eprintln!("SKIP: {model_path} isn't decoded token by token (no tokenizer.json next to it?)"); return; }
+    drop(probe);
+
+    for transcript in &TRANSCRIPTS {
+        let first = replay(&config, transcript);
+        let second = replay(&config, transcript);
+        let mut previous_tokens = 0;
+        for (i, (a, b)) in first.iter().zip(&second).enumerate() {
+            let name = format!("[{} turn {}]", transcript.name, i + 1);
+            println!("EVAL {name} prompt {} tokens, {:.0} ms / {:.0} ms, reply {} tokens / {} chars",
+                a.prompt_tokens, a.elapsed.as_secs_f64() * 1000.0, b.elapsed.as_secs_f64() * 1000.0, a.reply_tokens, a.reply.len());
+            assert!(a.reply_tokens > 0 && a.reply_tokens <= config.max_tokens, "{name} decoded {} tokens", a.reply_tokens);
+            assert_eq!(a.reply_tokens, b.reply_tokens, "{name} decoding is not deterministic");
+            assert_eq!(a.reply, b.reply, "{name} decoded text is not deterministic");
+            assert_eq!(a.prompt_tokens, b.prompt_tokens, "{name} tokenization is not deterministic");
+            assert!(a.prompt_tokens > previous_tokens, "{name} prompt didn't grow: {} after {previous_tokens} tokens", a.prompt_tokens);
+            assert!(a.prompt_tokens as u64 <= config.token_limits.context_tokens, "{name} prompt of {} tokens overflows the context", a.prompt_tokens);
+            assert!(a.elapsed.max(b.elapsed) <= max_turn, "{name} took {:?}, over the {max_turn:?} bound", a.elapsed.max(b.elapsed));
+            previous_tokens = a.prompt_tokens;
+        }
+    }
+}
@@ -1,8 +1,8 @@
 //! Tests focused on actual inference path (run_onnx_inference)
 //! Requires RIA_TEST_ONNX_MODEL env var to point to a valid small ONNX model.
 
-use ria_ai_chat::ai::{InferenceConfig, ExecutionProvider, ChatMessage, MessageRole, AIProvider};
-use ria_ai_chat::ai::providers::OnnxProvider;
+use ria::ai::{InferenceConfig, ExecutionProvider, ChatMessage, AIProvider};
+use ria::ai::providers::OnnxProvider;
 mod common; use common::discover_test_model as test_model_path;
 
 #[test]
@@ -13,7 +13,7 @@ fn onnx_forward_probe_success_or_framework_response() {
     let mut provider = OnnxProvider::new(cfg).unwrap();
     provider.load_model().unwrap();
 
-    let user = ChatMessage { id: "u1".into(), content: "Test ONNX working?".into(), role: MessageRole::User, timestamp: chrono::Utc::now(), model_used: None, inference_time: None };
+    let user = ChatMessage::user("Test ONNX working?");
     let resp = provider.generate_response(&[user]).unwrap();
     // The response should mention tokens or success markers
     assert!(resp.contains("ONNX") || resp.contains("tokens") || resp.contains("forward pass"), "Unexpected response: {resp}");
@@ -27,7 +27,7 @@ fn onnx_multiple_sequential_calls() {
     provider.load_model().unwrap();
 
     for i in 0..3 {
-        let user = ChatMessage { id: format!("u{i}"), content: format!("hello iteration {i}"), role: MessageRole::User, timestamp: chrono::Utc::now(), model_used: None, inference_time: None };
+        let user = ChatMessage::user(format!("hello iteration {i}"));
         let resp = provider.generate_response(&[user]).unwrap();
         assert!(resp.len() > 10, "Short response at iteration {i}");
     }
@@ -9,9 +9,9 @@
 //! The test uses the sample model path from environment variable `RIA_TEST_ONNX_MODEL`.
 //! Provide a small ONNX file (e.g., a tiny distilled transformer) to exercise loading.
 
-use ria_ai_chat::ai::{InferenceConfig, ExecutionProvider};
-use ria_ai_chat::ai::AIProvider; // trait for generate_response
-use ria_ai_chat::ai::providers::OnnxProvider;
+use ria::ai::{InferenceConfig, ExecutionProvider};
+use ria::ai::AIProvider; // trait for generate_response
+use ria::ai::providers::OnnxProvider;
 mod common; use common::discover_test_model as test_model_path;
 
 #[test]
@@ -40,8 +40,8 @@ fn onnx_adaptive_probe_minimal_tokens() {
     provider.load_model().expect("load");
 
     // Build minimal fake messages to produce tokens
-    use ria_ai_chat::ai::{ChatMessage, MessageRole};
-    let msg = ChatMessage { id: "1".into(), content: "hello".into(), role: MessageRole::User, timestamp: chrono::Utc::now(), model_used: None, inference_time: None };
+    use ria::ai::ChatMessage;
+    let msg = ChatMessage::user("hello");
     let _ = provider.generate_response(&[msg]).expect("response generation");
     assert!(provider.last_probe_success(), "Adaptive probe did not report success");
 }
@@ -1,6 +1,6 @@
 //! Negative and edge-case tests for ONNX provider
-use ria_ai_chat::ai::{InferenceConfig, ExecutionProvider};
-use ria_ai_chat::ai::providers::{OnnxProvider, LoadError};
+use ria::ai::{InferenceConfig, ExecutionProvider};
+use ria::ai::providers::{OnnxProvider, LoadError};
 
 #[test]
 fn load_missing_file_yields_file_missing() {
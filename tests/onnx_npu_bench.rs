@@ -5,17 +5,17 @@ use std::time::Instant;
 mod common;
 
 #[cfg(feature = "openvino_ep")]
-fn bench_once(model: &str, ep: ria_ai_chat::ai::ExecutionProvider, iters: u32) -> (f64, f64, f64) {
-    use ria_ai_chat::ai::{InferenceConfig, ExecutionProvider};
-    use ria_ai_chat::ai::providers::OnnxProvider;
+fn bench_once(model: &str, ep: ria::ai::ExecutionProvider, iters: u32) -> (f64, f64, f64) {
+    use ria::ai::{InferenceConfig, ExecutionProvider};
+    use ria::ai::providers::OnnxProvider;
     let cfg = InferenceConfig { model_path: model.to_string(), execution_provider: ep, warmup_iterations: 2, profiling: true, ..InferenceConfig::default() };
     let mut provider = OnnxProvider::new(cfg).expect("create provider");
     let t0 = Instant::now();
     provider.load_model().expect("load");
     let load_ms = t0.elapsed().as_secs_f64() * 1000.0;
     // Build minimal chat message
-    use ria_ai_chat::ai::{ChatMessage, MessageRole, AIProvider};
-    let prompt = ChatMessage { id: "1".into(), content: "hello benchmark".into(), role: MessageRole::User, timestamp: chrono::Utc::now(), model_used: None, inference_time: None };
+    use ria::ai::{ChatMessage, AIProvider};
+    let prompt = ChatMessage::user("hello benchmark");
     let t1 = Instant::now();
     for _ in 0..iters { let _ = provider.generate_response(&[prompt.clone()]).expect("response"); }
     let total_infer_ms = t1.elapsed().as_secs_f64() * 1000.0;
@@ -29,7 +29,7 @@ fn npu_benchmark_compare_cpu_vs_openvino() {
     let model = match std::env::var("RIA_TEST_ONNX_MODEL").ok().or_else(common::discover_test_model) { Some(m) => m, None => { eprintln!("SKIP: no model found for benchmark"); return; } };
     if std::env::var("RIA_TEST_EXPECT_NPU").ok().as_deref() != Some("1") { eprintln!("SKIP: set RIA_TEST_EXPECT_NPU=1 for NPU benchmark"); return; }
     assert!(std::path::Path::new(&model).exists(), "Model file missing");
-    use ria_ai_chat::ai::ExecutionProvider;
+    use ria::ai::ExecutionProvider;
     let iters = 10; // coarse loops
     let (cpu_load, cpu_total, cpu_per) = bench_once(&model, ExecutionProvider::Cpu, iters);
     let (ov_load, ov_total, ov_per) = bench_once(&model, ExecutionProvider::OpenVINO, iters);
@@ -49,8 +49,8 @@ fn npu_profile_contains_device_hint() {
     if !expect { eprintln!("SKIP: set RIA_TEST_EXPECT_NPU=1 for profile test"); return; }
     let model = match std::env::var("RIA_TEST_ONNX_MODEL").ok().or_else(common::discover_test_model) { Some(m) => m, None => { eprintln!("SKIP: no model found for profile test"); return; } };
     assert!(std::path::Path::new(&model).exists());
-    use ria_ai_chat::ai::{InferenceConfig, ExecutionProvider};
-    use ria_ai_chat::ai::providers::OnnxProvider;
+    use ria::ai::{InferenceConfig, ExecutionProvider};
+    use ria::ai::providers::OnnxProvider;
     let cfg = InferenceConfig { model_path: model, execution_provider: ExecutionProvider::OpenVINO, profiling: true, warmup_iterations: 1, ..InferenceConfig::default() };
     let mut provider = OnnxProvider::new(cfg).unwrap();
     provider.load_model().unwrap();